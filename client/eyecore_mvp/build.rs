@@ -0,0 +1,34 @@
+use std::process::Command;
+
+fn main() {
+    // Embed git hash, build date and enabled features for the /version endpoint
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let build_date = chrono::Utc::now().to_rfc3339();
+
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|feature| feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+
+    println!("cargo:rustc-env=EYECORE_GIT_HASH={}", git_hash);
+    println!("cargo:rustc-env=EYECORE_BUILD_DATE={}", build_date);
+    println!("cargo:rustc-env=EYECORE_FEATURES={}", features.join(","));
+    println!("cargo:rerun-if-changed=build.rs");
+
+    if let Ok(output) = Command::new("git").args(["rev-parse", "--git-dir"]).output() {
+        if output.status.success() {
+            let git_dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        }
+    }
+}
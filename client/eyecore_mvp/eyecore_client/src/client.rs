@@ -0,0 +1,700 @@
+use chrono::NaiveDate;
+use reqwest::{RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::error::Error;
+use crate::models::{
+    AggregatedStats, AppClassifications, CalibrationStatus, CameraData, CollectionStatus, CollectorStatus, CollectorUpdate, ConsentStatus,
+    DriftReport, EncryptionStatus, EyeCoreData, FileMetadata, GazeCalibration, GazeCalibrationStatus, Goal, InputTraceStatus, KeyRotation,
+    KeystrokeDynamics, MaintenanceStatus, ModuleConsent, ModuleSettings, ModuleUpdate, MouseDynamics, NetworkActivityMetadata, NetworkCategory,
+    NewGoal, NotificationStats, OnboardingStatus, Productivity, Projects, ScreenInteractions, SessionNote, ShareBundle, ShareContents, StorageStats,
+    SwitchReport, SystemEvents, TimeEntry, TimeEntryStatus, UploadStatus, VoiceData, WifiNetwork,
+};
+use crate::types::*;
+
+/// Collection module configured through `/control`; `System` and `Screen` are always on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Module {
+    Voice,
+    Camera,
+    Keystroke,
+    Files,
+    System,
+    Screen,
+}
+
+impl Module {
+    fn path(self) -> &'static str {
+        match self {
+            Module::Voice => "voice",
+            Module::Camera => "camera",
+            Module::Keystroke => "keystroke",
+            Module::Files => "files",
+            Module::System => "system",
+            Module::Screen => "screen",
+        }
+    }
+}
+
+/// Privacy-sensitive module that only collects with consent, managed through `/consent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsentModule {
+    Voice,
+    Camera,
+    CameraPresence,    // face presence only, for when `Camera` is not granted
+    CameraCloud,       // frames sent to a cloud emotion backend, on top of `Camera`
+    Keystroke,         // timing only
+    TypedText,         // typed text and clicked button labels, on top of `Keystroke`
+    ScreenText,
+    Files,
+}
+
+impl ConsentModule {
+    fn path(self) -> &'static str {
+        match self {
+            ConsentModule::Voice => "voice",
+            ConsentModule::Camera => "camera",
+            ConsentModule::CameraPresence => "camera_presence",
+            ConsentModule::CameraCloud => "camera_cloud",
+            ConsentModule::Keystroke => "keystroke",
+            ConsentModule::TypedText => "typed_text",
+            ConsentModule::ScreenText => "screen_text",
+            ConsentModule::Files => "files",
+        }
+    }
+}
+
+/// OS permission checked through `/permissions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Camera,
+    Microphone,
+    Accessibility,
+    InputMonitoring,
+}
+
+impl Permission {
+    fn path(self) -> &'static str {
+        match self {
+            Permission::Camera => "camera",
+            Permission::Microphone => "microphone",
+            Permission::Accessibility => "accessibility",
+            Permission::InputMonitoring => "input_monitoring",
+        }
+    }
+}
+
+/// Client for one EyeCore instance; cheap to clone
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl Client {
+    /// `base_url` like "http://127.0.0.1:3000"
+    pub fn new(base_url: impl Into<String>) -> Result<Self, Error> {
+        Ok(Self::with_http_client(reqwest::Client::builder().build()?, base_url))
+    }
+
+    /// Use a preconfigured reqwest client (timeouts, proxy, TLS roots)
+    pub fn with_http_client(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Client {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+        }
+    }
+
+    /// Bearer key from EYECORE_METRICS_KEYS or EYECORE_CONTENT_KEYS; content keys are needed for
+    /// the content endpoints and also grant metrics access
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // ===== Snapshots =====
+
+    pub async fn health(&self) -> Result<Health, Error> {
+        self.send(self.get("/health")).await
+    }
+
+    /// Latest snapshot, or None before the first collection
+    pub async fn latest(&self) -> Result<Option<EyeCoreData>, Error> {
+        self.send_optional(self.get("/data/latest")).await
+    }
+
+    /// A page of the in-memory history (newest first); `cursor` is the previous page's `next_cursor`
+    pub async fn history(&self, limit: Option<usize>, cursor: Option<&str>) -> Result<HistoryPage<EyeCoreData>, Error> {
+        self.send(self.get("/data/history").query(&[("limit", limit.map(|l| l.to_string())), ("cursor", cursor.map(str::to_string))]))
+            .await
+    }
+
+    /// Like `history`, but each snapshot only carries `fields` (dotted paths such as
+    /// "system_metrics.cpu_usage") plus its timestamp
+    pub async fn history_fields(
+        &self,
+        fields: &[&str],
+        limit: Option<usize>,
+        cursor: Option<&str>,
+    ) -> Result<HistoryPage<serde_json::Value>, Error> {
+        self.send(self.get("/data/history").query(&[
+            ("limit", limit.map(|l| l.to_string())),
+            ("cursor", cursor.map(str::to_string)),
+            ("fields", Some(fields.join(","))),
+        ]))
+        .await
+    }
+
+    pub async fn stats(&self) -> Result<AggregatedStats, Error> {
+        self.send(self.get("/data/stats")).await
+    }
+
+    pub async fn status(&self) -> Result<CollectionStatus, Error> {
+        self.send(self.get("/status")).await
+    }
+
+    pub async fn voice(&self) -> Result<Option<VoiceData>, Error> {
+        self.send_optional(self.get("/data/voice")).await
+    }
+
+    pub async fn camera(&self) -> Result<Option<CameraData>, Error> {
+        self.send_optional(self.get("/data/camera")).await
+    }
+
+    pub async fn keystroke(&self) -> Result<Option<KeystrokeDynamics>, Error> {
+        self.send_optional(self.get("/data/keystroke")).await
+    }
+
+    pub async fn screen(&self) -> Result<Option<ScreenInteractions>, Error> {
+        self.send_optional(self.get("/data/screen")).await
+    }
+
+    pub async fn files(&self) -> Result<Option<FileMetadata>, Error> {
+        self.send_optional(self.get("/data/files")).await
+    }
+
+    pub async fn system_events(&self) -> Result<Option<SystemEvents>, Error> {
+        self.send_optional(self.get("/data/system-events")).await
+    }
+
+    pub async fn mouse(&self) -> Result<Option<MouseDynamics>, Error> {
+        self.send_optional(self.get("/data/mouse")).await
+    }
+
+    pub async fn network(&self) -> Result<Option<NetworkActivityMetadata>, Error> {
+        self.send_optional(self.get("/data/network")).await
+    }
+
+    pub async fn notifications(&self) -> Result<NotificationStats, Error> {
+        self.send(self.get("/data/notifications")).await
+    }
+
+    /// App switches of the last `minutes` (default 5, max 60) with their cadence
+    pub async fn switches(&self, minutes: Option<u32>) -> Result<SwitchReport, Error> {
+        self.send(self.get("/data/switches").query(&[("minutes", minutes)])).await
+    }
+
+    // ===== Stored data =====
+
+    pub async fn coverage(&self, range: &DateRange) -> Result<Coverage, Error> {
+        self.send(self.get("/data/coverage").query(range)).await
+    }
+
+    /// Drift between measured collectors and their simulations, from snapshots stored with
+    /// `compare_collectors` on
+    pub async fn collector_comparison(&self, range: &DateRange) -> Result<DriftReport, Error> {
+        self.send(self.get("/data/comparison").query(range)).await
+    }
+
+    /// Snapshot numbers from the unencrypted metrics-only mirror (`metrics_mirror`)
+    pub async fn metrics_mirror(&self, range: &DateRange) -> Result<MetricsMirror, Error> {
+        self.send(self.get("/data/metrics").query(range)).await
+    }
+
+    /// Periods without keyboard or mouse input that ended in `range`
+    pub async fn idle_sessions(&self, range: &DateRange) -> Result<IdleSessions, Error> {
+        self.send(self.get("/data/idle").query(range)).await
+    }
+
+    pub async fn privacy_activity(&self, range: &DateRange) -> Result<PrivacyActivity, Error> {
+        self.send(self.get("/privacy/activity").query(range)).await
+    }
+
+    pub async fn content_access_log(&self, range: &DateRange) -> Result<ContentAccessLog, Error> {
+        self.send(self.get("/privacy/content-access").query(range)).await
+    }
+
+    pub async fn audio_segments(&self, range: &DateRange) -> Result<AudioSegments, Error> {
+        self.send(self.get("/data/audio").query(range)).await
+    }
+
+    pub async fn query_sql(&self, query: &SqlQuery) -> Result<QueryResult, Error> {
+        self.send(self.post("/query/sql").json(query)).await
+    }
+
+    pub async fn samples(&self, query: &SampleQuery) -> Result<SampleSet, Error> {
+        self.send(self.get("/data/samples").query(query)).await
+    }
+
+    pub async fn compliance_register(&self) -> Result<ProcessingRegister, Error> {
+        self.send(self.get("/compliance/register")).await
+    }
+
+    pub async fn config(&self) -> Result<EffectiveConfig, Error> {
+        self.send(self.get("/config")).await
+    }
+
+    /// Disk usage per category, retention limits and reclaimed space
+    pub async fn storage_stats(&self) -> Result<StorageStats, Error> {
+        self.send(self.get("/storage/stats")).await
+    }
+
+    pub async fn encryption_status(&self) -> Result<EncryptionStatus, Error> {
+        self.send(self.get("/storage/encryption")).await
+    }
+
+    /// Switch to a new encryption key and re-encrypt the stored files with it
+    pub async fn rotate_encryption_key(&self) -> Result<KeyRotation, Error> {
+        self.send(self.post("/storage/encryption/rotate")).await
+    }
+
+    // ===== Bookmarks and notes =====
+
+    pub async fn create_bookmark(&self, note: Option<&str>) -> Result<BookmarkCreated, Error> {
+        self.send(self.post("/bookmark").json(&json!({ "note": note }))).await
+    }
+
+    pub async fn bookmarks(&self, range: &DateRange) -> Result<Bookmarks, Error> {
+        self.send(self.get("/bookmarks").query(range)).await
+    }
+
+    pub async fn create_note(&self, note: &NewNote) -> Result<SessionNote, Error> {
+        self.send(self.post("/notes").json(note)).await
+    }
+
+    pub async fn notes(&self, query: &NotesQuery) -> Result<Notes, Error> {
+        self.send(self.get("/notes").query(query)).await
+    }
+
+    // ===== Flags =====
+
+    /// Flags the flag detector raised on the days of `query`
+    pub async fn flags(&self, query: &FlagsQuery) -> Result<Flags, Error> {
+        self.send(self.get("/flags").query(query)).await
+    }
+
+    pub async fn session_flags(&self, session_id: &str, query: &FlagsQuery) -> Result<Flags, Error> {
+        self.send(self.get(&format!("/flags/{}", session_id)).query(query)).await
+    }
+
+    /// The latest flags, newest first
+    pub async fn recent_flags(&self, query: &RecentFlagsQuery) -> Result<Flags, Error> {
+        self.send(self.get("/flags/recent").query(query)).await
+    }
+
+    /// Thresholds the flag detector judges snapshots by
+    pub async fn detector_config(&self) -> Result<DetectorConfig, Error> {
+        self.send(self.get("/detector/config")).await
+    }
+
+    // ===== Share bundles =====
+
+    /// What a share bundle of `share` would hold, without building it
+    pub async fn preview_share(&self, share: &NewShare) -> Result<ShareContents, Error> {
+        self.send(self.post("/share").json(&NewShare { confirm: false, ..share.clone() })).await
+    }
+
+    /// Build the bundle; its password is only returned here
+    pub async fn create_share(&self, share: &NewShare) -> Result<ShareBundle, Error> {
+        self.send(self.post("/share").json(&NewShare { confirm: true, ..share.clone() })).await
+    }
+
+    /// The bundle's AES-encrypted zip
+    pub async fn download_share(&self, id: &str) -> Result<Vec<u8>, Error> {
+        let response = check(self.get(&format!("/share/{}", id)).send().await?).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    // ===== Heart rate =====
+
+    /// Returns the number of samples accepted
+    pub async fn ingest_heart_rate(&self, samples: &[HeartRateSample]) -> Result<usize, Error> {
+        #[derive(serde::Deserialize)]
+        struct Accepted {
+            accepted: usize,
+        }
+        let reply: Accepted = self.send(self.post("/ingest/heart-rate").json(&json!({ "samples": samples }))).await?;
+        Ok(reply.accepted)
+    }
+
+    pub async fn stress_calibration(&self) -> Result<StressCalibration, Error> {
+        self.send(self.get("/calibration/stress")).await
+    }
+
+    pub async fn calibration_status(&self) -> Result<CalibrationStatus, Error> {
+        self.send(self.get("/calibration/status")).await
+    }
+
+    /// Start a calibration session of `minutes` (default 10); fails with 409 while one is running
+    pub async fn start_calibration(&self, minutes: Option<u32>) -> Result<CalibrationStatus, Error> {
+        self.send(self.post("/calibration/start").json(&json!({ "minutes": minutes }))).await
+    }
+
+    pub async fn gaze_calibration(&self) -> Result<GazeCalibrationStatus, Error> {
+        self.send(self.get("/calibration/gaze")).await
+    }
+
+    /// Start gaze calibration on a `width` x `height` screen; show the returned `prompts` one by one
+    pub async fn start_gaze_calibration(&self, width: u32, height: u32) -> Result<GazeCalibrationStatus, Error> {
+        self.send(self.post("/calibration/gaze/start").json(&json!({ "screen_width": width, "screen_height": height }))).await
+    }
+
+    /// Sample the camera's gaze while the user looks at the prompt shown at `x`, `y`
+    pub async fn record_gaze_point(&self, x: f32, y: f32) -> Result<GazeCalibrationStatus, Error> {
+        self.send(self.post("/calibration/gaze/point").json(&json!({ "x": x, "y": y }))).await
+    }
+
+    /// Fit the calibration from the recorded samples; fails with 409 if there are too few
+    pub async fn finish_gaze_calibration(&self) -> Result<GazeCalibration, Error> {
+        self.send(self.post("/calibration/gaze/finish")).await
+    }
+
+    // ===== Onboarding =====
+
+    pub async fn onboarding_state(&self) -> Result<OnboardingStatus, Error> {
+        self.send(self.get("/onboarding/state")).await
+    }
+
+    /// Answer onboarding step `step` (its `id`); a step that is no longer current fails with 409
+    pub async fn answer_onboarding(&self, step: &str, answer: &str) -> Result<OnboardingStatus, Error> {
+        self.send(self.post("/onboarding/answer").json(&json!({ "step": step, "answer": answer }))).await
+    }
+
+    // ===== Consent =====
+
+    /// Consent per module, with every grant and revocation
+    pub async fn consent_status(&self) -> Result<ConsentStatus, Error> {
+        self.send(self.get("/consent")).await
+    }
+
+    pub async fn grant_consent(&self, module: ConsentModule, reason: Option<&str>) -> Result<ModuleConsent, Error> {
+        self.send(self.post(&format!("/consent/{}/grant", module.path())).json(&json!({ "reason": reason }))).await
+    }
+
+    /// Stop the module and record the revocation; stored data is kept until its retention expires
+    pub async fn revoke_consent(&self, module: ConsentModule, reason: Option<&str>) -> Result<ModuleConsent, Error> {
+        self.send(self.post(&format!("/consent/{}/revoke", module.path())).json(&json!({ "reason": reason }))).await
+    }
+
+    // ===== Version and updates =====
+
+    pub async fn version(&self) -> Result<VersionInfo, Error> {
+        self.send(self.get("/version")).await
+    }
+
+    /// Install the available update; the instance restarts shortly after replying
+    pub async fn apply_update(&self) -> Result<UpdateApplied, Error> {
+        self.send(self.post("/update/apply")).await
+    }
+
+    // ===== Discovery and team =====
+
+    pub async fn discovery_peers(&self) -> Result<DiscoveryPeers, Error> {
+        self.send(self.get("/discovery/peers")).await
+    }
+
+    pub async fn team_status(&self) -> Result<TeamOverview, Error> {
+        self.send(self.get("/team/status")).await
+    }
+
+    pub async fn set_do_not_disturb(&self, enabled: bool) -> Result<DoNotDisturb, Error> {
+        self.send(self.post("/team/dnd").json(&json!({ "enabled": enabled }))).await
+    }
+
+    /// Label the collector's current Wi-Fi network; `NetworkCategory::Unknown` removes the label
+    pub async fn label_network(&self, category: NetworkCategory) -> Result<WifiNetwork, Error> {
+        self.send(self.post("/network/label").json(&json!({ "category": category }))).await
+    }
+
+    /// Bundled app and site classes with the user's overrides
+    pub async fn app_classification(&self) -> Result<AppClassifications, Error> {
+        self.send(self.get("/classification/apps")).await
+    }
+
+    /// Replace the user's overrides; keys are app names or site domains
+    pub async fn set_app_classification(
+        &self,
+        overrides: &BTreeMap<String, Productivity>,
+    ) -> Result<AppClassifications, Error> {
+        self.send(self.put("/classification/apps").json(&json!({ "overrides": overrides }))).await
+    }
+
+    /// Projects detected from window titles and paths, and the names rejected
+    pub async fn projects(&self) -> Result<Projects, Error> {
+        self.send(self.get("/projects")).await
+    }
+
+    /// Confirm a detected project, renaming it to `name` if given (another project's name merges them)
+    pub async fn confirm_project(&self, project: &str, name: Option<&str>) -> Result<Projects, Error> {
+        self.send(self.post(&format!("/projects/{}/confirm", project)).json(&json!({ "name": name }))).await
+    }
+
+    /// Forget a detected project; its names are not detected again
+    pub async fn reject_project(&self, project: &str) -> Result<Projects, Error> {
+        self.send(self.post(&format!("/projects/{}/reject", project))).await
+    }
+
+    pub async fn goals(&self) -> Result<Goals, Error> {
+        self.send(self.get("/goals")).await
+    }
+
+    /// Register a goal, e.g. 240 minutes of deep work on a project per day
+    pub async fn create_goal(&self, goal: &NewGoal) -> Result<Goal, Error> {
+        self.send(self.post("/goals").json(goal)).await
+    }
+
+    /// Remove a goal; the remaining ones are returned
+    pub async fn delete_goal(&self, id: &str) -> Result<Goals, Error> {
+        self.send(self.delete(&format!("/goals/{}", id))).await
+    }
+
+    /// Progress of every goal today or this week, with a projection for weekly goals
+    pub async fn goal_progress(&self) -> Result<GoalsProgress, Error> {
+        self.send(self.get("/goals/progress")).await
+    }
+
+    /// The review queue of time entries, optionally only those with `status`
+    pub async fn time_entries(&self, status: Option<TimeEntryStatus>) -> Result<TimeEntries, Error> {
+        self.send(self.get("/time_entries").query(&[("status", status)])).await
+    }
+
+    /// Draft a day's time entries for review (today if `date` is None), replacing its pending ones
+    pub async fn draft_time_entries(&self, date: Option<NaiveDate>) -> Result<Vec<TimeEntry>, Error> {
+        let drafted: TimeEntries = self.send(self.post("/time_entries/draft").json(&json!({ "date": date }))).await?;
+        Ok(drafted.entries)
+    }
+
+    /// Send a pending entry to the time-tracking tool, optionally with another description
+    pub async fn submit_time_entry(&self, id: &str, description: Option<&str>) -> Result<TimeEntry, Error> {
+        self.send(self.post(&format!("/time_entries/{}/submit", id)).json(&json!({ "description": description }))).await
+    }
+
+    pub async fn discard_time_entry(&self, id: &str) -> Result<TimeEntry, Error> {
+        self.send(self.post(&format!("/time_entries/{}/discard", id))).await
+    }
+
+    // ===== Content (content key required, every call is audited) =====
+
+    pub async fn typed_text(&self) -> Result<Option<TypedText>, Error> {
+        self.send_optional(self.get("/content/typed-text")).await
+    }
+
+    pub async fn screen_text(&self) -> Result<Option<ScreenText>, Error> {
+        self.send_optional(self.get("/content/screen-text")).await
+    }
+
+    pub async fn transcripts(&self, range: &DateRange) -> Result<Transcripts, Error> {
+        self.send(self.get("/content/transcripts").query(range)).await
+    }
+
+    /// Raw input bundled with the Critical SecurityConcern flags raised in `range`
+    pub async fn incidents(&self, range: &DateRange) -> Result<Incidents, Error> {
+        self.send(self.get("/content/incidents").query(range)).await
+    }
+
+    /// tar.zst of everything stored for the days `from` through `to`, from byte `offset` on; write
+    /// the body out with `chunk()`. To resume, pass the `ETag` of the first response as `if_range`:
+    /// if the archive has changed since, the answer is 200 with the whole archive instead of 206.
+    pub async fn download_range(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        offset: u64,
+        if_range: Option<&str>,
+    ) -> Result<reqwest::Response, Error> {
+        let mut request = self.get("/download/range").query(&[("from", from.to_string()), ("to", to.to_string())]);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+            if let Some(etag) = if_range {
+                request = request.header(reqwest::header::IF_RANGE, etag);
+            }
+        }
+        check(request.send().await?).await
+    }
+
+    // ===== Control =====
+
+    /// Opt-in modules with their settings; switching one on needs its consent (403 otherwise)
+    pub async fn modules(&self) -> Result<Modules, Error> {
+        self.send(self.get("/control")).await
+    }
+
+    /// Change a module's settings; fields left as None keep their value
+    pub async fn update_module(&self, module: Module, update: &ModuleUpdate) -> Result<ModuleSettings, Error> {
+        self.send(self.post(&format!("/control/{}", module.path())).json(update)).await
+    }
+
+    pub async fn set_module(&self, module: Module, enabled: bool) -> Result<ModuleSettings, Error> {
+        self.update_module(module, &ModuleUpdate { enabled: Some(enabled), ..ModuleUpdate::default() }).await
+    }
+
+    /// Pluggable collectors with their interval and latest outcome
+    pub async fn collectors(&self) -> Result<Collectors, Error> {
+        self.send(self.get("/collectors")).await
+    }
+
+    /// Switch a collector or change its interval (0 restores its own); unknown names are 404
+    pub async fn update_collector(&self, name: &str, update: &CollectorUpdate) -> Result<CollectorStatus, Error> {
+        self.send(self.post(&format!("/collectors/{}", name)).json(update)).await
+    }
+
+    /// Background jobs with their schedules, whether they are running and their last runs
+    pub async fn scheduler_jobs(&self) -> Result<ScheduledJobs, Error> {
+        self.send(self.get("/scheduler/jobs")).await
+    }
+
+    /// OS permissions the collectors need, with the steps to grant each missing one
+    pub async fn permissions(&self) -> Result<Permissions, Error> {
+        self.send(self.get("/permissions")).await
+    }
+
+    /// Show the OS prompt for `permission` or open its settings page; for the microphone this
+    /// records half a second
+    pub async fn request_permission(&self, permission: Permission) -> Result<PermissionRequest, Error> {
+        self.send(self.post(&format!("/permissions/{}/request", permission.path()))).await
+    }
+
+    /// Research input trace: whether it records, its files and dropped events
+    pub async fn input_trace(&self) -> Result<InputTraceStatus, Error> {
+        self.send(self.get("/input_trace")).await
+    }
+
+    /// Upload connection state, the last send and the snapshots waiting to be sent
+    pub async fn upload_status(&self) -> Result<UploadStatus, Error> {
+        self.send(self.get("/upload/status")).await
+    }
+
+    pub async fn maintenance(&self) -> Result<MaintenanceStatus, Error> {
+        self.send(self.get("/maintenance")).await
+    }
+
+    /// Pause (`true`) or resume collection; the API and stored data stay available
+    pub async fn set_maintenance(&self, active: bool, reason: Option<&str>) -> Result<MaintenanceStatus, Error> {
+        self.send(self.post("/maintenance").json(&json!({ "active": active, "reason": reason }))).await
+    }
+
+    // ===== Plumbing =====
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.authorize(self.http.get(format!("{}{}", self.base_url, path)))
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        self.authorize(self.http.post(format!("{}{}", self.base_url, path)))
+    }
+
+    fn put(&self, path: &str) -> RequestBuilder {
+        self.authorize(self.http.put(format!("{}{}", self.base_url, path)))
+    }
+
+    fn delete(&self, path: &str) -> RequestBuilder {
+        self.authorize(self.http.delete(format!("{}{}", self.base_url, path)))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, Error> {
+        let response = check(request.send().await?).await?;
+        Ok(serde_json::from_slice(&response.bytes().await?)?)
+    }
+
+    /// For endpoints that answer 204 (or 404 before the first snapshot) when there is nothing to show
+    async fn send_optional<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<Option<T>, Error> {
+        let response = request.send().await?;
+        if matches!(response.status(), StatusCode::NO_CONTENT | StatusCode::NOT_FOUND) {
+            return Ok(None);
+        }
+        let response = check(response).await?;
+        Ok(Some(serde_json::from_slice(&response.bytes().await?)?))
+    }
+}
+
+/// Turn non-success statuses into `Error::Api`, using the `{"error": ...}` body when present
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|value| value.get("error").and_then(|e| e.as_str()).map(str::to_string))
+        .unwrap_or(body);
+    Err(Error::Api { status: status.as_u16(), message })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serve one canned HTTP response on a local port, returning the base URL
+    async fn serve_once(status: &'static str, body: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = socket.read(&mut request).await;
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn api_errors_carry_the_error_field() {
+        let client = Client::new(serve_once("400 Bad Request", r#"{"error": "date must be YYYY-MM-DD"}"#).await).unwrap();
+        match client.coverage(&DateRange::default()).await {
+            Err(Error::Api { status, message }) => {
+                assert_eq!(status, 400);
+                assert_eq!(message, "date must be YYYY-MM-DD");
+            }
+            other => panic!("expected an API error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn no_content_means_none() {
+        let client = Client::new(serve_once("204 No Content", "").await).unwrap();
+        assert!(client.latest().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn control_replies_are_typed() {
+        let body = r#"{"module": "voice", "enabled": true, "sampling_interval_secs": 60, "retention_days": null}"#;
+        let settings = Client::new(serve_once("200 OK", body).await).unwrap().set_module(Module::Voice, true).await.unwrap();
+        assert_eq!(settings.module, "voice");
+        assert!(settings.enabled);
+        assert_eq!(settings.sampling_interval_secs, Some(60));
+        assert_eq!(settings.retention_days, None);
+    }
+}
@@ -0,0 +1,25 @@
+//! Typed async client for the EyeCore local API (`http://127.0.0.1:3000` by default) and the
+//! collector's upload socket, for dashboards and test harnesses written in Rust.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), eyecore_client::Error> {
+//! let client = eyecore_client::Client::new("http://127.0.0.1:3000")?.with_api_key("dashboard-key");
+//! if let Some(latest) = client.latest().await? {
+//!     println!("CPU {:.1}%", latest.system_metrics.cpu_usage);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod error;
+mod types;
+pub mod ws;
+
+/// Snapshot and record types, shared with the collector so they cannot drift from what it serves
+#[path = "../../src/models.rs"]
+pub mod models;
+
+pub use client::{Client, ConsentModule, Module, Permission};
+pub use error::Error;
+pub use types::*;
@@ -0,0 +1,669 @@
+//! Request and response bodies of the local API that are not stored records (see `models`)
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::models::{
+    AudioSegmentMetadata, Bookmark, ButtonClick, CollectorStatus, ContentAccessEvent, DailyCoverage, EyeCoreData, FlagRecord, Goal,
+    GoalProgress, IdleSession, IncidentBundle, MetricsOnlyEntry, ModuleSettings, PermissionStatus, ScheduledJob, SessionNote, TimeEntry,
+    WindowContent,
+};
+
+/// `date`/`days` selection used by most stored-data endpoints: `days` days ending at `date`
+/// (server defaults: today, 1 day)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DateRange {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+}
+
+impl DateRange {
+    pub fn days_ending(date: NaiveDate, days: u32) -> Self {
+        DateRange { date: Some(date), days: Some(days) }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Health {
+    pub status: String,
+}
+
+/// One page of `/data/history`, newest first; pass `next_cursor` back to get the next page
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryPage<T> {
+    pub count: usize,
+    pub snapshots: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// Metrics-only mirror entries from `/data/metrics`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricsMirror {
+    pub enabled: bool,
+    pub entries: Vec<MetricsOnlyEntry>,
+}
+
+/// Idle sessions from `/data/idle`
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdleSessions {
+    pub count: usize,
+    pub total_idle_seconds: u64,
+    pub sessions: Vec<IdleSession>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Coverage {
+    pub days: Vec<DailyCoverage>,
+    pub total_uncovered_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceActivity {
+    pub sessions: usize,
+    pub total_seconds: f64,
+    pub first_opened: Option<DateTime<Utc>>,
+    pub last_closed: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DailyCaptureActivity {
+    pub date: String,
+    pub microphone: DeviceActivity,
+    pub camera: DeviceActivity,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrivacyActivity {
+    pub days: Vec<DailyCaptureActivity>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AudioSegments {
+    pub count: usize,
+    pub segments: Vec<AudioSegmentMetadata>,
+}
+
+/// Body of `POST /query/sql`
+#[derive(Debug, Clone, Serialize)]
+pub struct SqlQuery {
+    pub sql: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rows: Option<usize>,
+}
+
+impl SqlQuery {
+    pub fn new(sql: impl Into<String>) -> Self {
+        SqlQuery { sql: sql.into(), date: None, days: None, max_rows: None }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_count: usize,
+    pub truncated: bool,
+}
+
+/// Parameters of `/data/samples`; unset fields use the server defaults
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SampleQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stratify_by: Option<String>, // flag_type, severity or none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_secs: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SampleFlag {
+    pub flag_type: String,
+    pub severity: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LabelFields {
+    pub focus_level: Option<f32>,
+    pub stress_level: Option<f32>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LabelingSample {
+    pub sample_id: String,
+    pub stratum: String,
+    pub snapshot: EyeCoreData,
+    pub flags: Vec<SampleFlag>,
+    pub context: Vec<EyeCoreData>,
+    pub labels: LabelFields,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StratumSummary {
+    pub available: usize,
+    pub sampled: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SampleSet {
+    pub stratify_by: String,
+    pub seed: u64,
+    pub requested: usize,
+    pub count: usize,
+    pub strata: BTreeMap<String, StratumSummary>,
+    pub samples: Vec<LabelingSample>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessingActivity {
+    pub id: String,
+    pub name: String,
+    pub active: bool,
+    pub data_categories: Vec<String>,
+    pub purposes: Vec<String>,
+    pub retention: String,
+    pub recipients: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessingRegister {
+    pub generated_at: DateTime<Utc>,
+    pub device_id: String,
+    pub software_version: String,
+    pub activities: Vec<ProcessingActivity>,
+}
+
+/// Opt-in modules enabled by the configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModuleConfig {
+    pub voice_data: bool,
+    pub camera_data: bool,
+    pub keystroke_dynamics: bool,
+    pub file_metadata: bool,
+}
+
+/// `[retention]` limits; None or empty means no limit
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionConfig {
+    pub max_age_days: Option<u32>,
+    pub max_total_mb: Option<u64>,
+    pub quotas_mb: BTreeMap<String, u64>,
+}
+
+/// `[redaction]` rules applied to captured text before it is stored or uploaded
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactionConfig {
+    pub emails: bool,
+    pub card_numbers: bool,
+    pub ssns: bool,
+    pub secrets: bool,
+    pub rules: Vec<RedactionRule>,
+}
+
+/// A deployment's own redaction rule: matches of `pattern` are masked as `[REDACTED:<name>]`
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactionRule {
+    pub name: String,
+    pub pattern: String,
+}
+
+/// `[emotion]`: where `camera_data` facial emotions come from
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmotionConfig {
+    pub backend: String,            // "simulated", "onnx" or "cloud"
+    pub model_path: Option<String>,
+    pub labels: Option<Vec<String>>,
+    pub input_size: u32,
+    pub endpoint: Option<String>,
+    pub interval_secs: u64,
+}
+
+/// `[upload_tls]`: certificates accepted from a wss:// upload server
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadTlsConfig {
+    pub ca_file: Option<String>,
+    pub accept_invalid_certs: bool,
+}
+
+/// `[input_trace]`: full-resolution input in binary files for research
+#[derive(Debug, Clone, Deserialize)]
+pub struct InputTraceConfig {
+    pub enabled: bool,
+    pub max_file_mb: u64,
+    pub max_total_mb: u64,
+    pub max_events_per_sec: u32,
+}
+
+/// `[time_export]`: time entries for a time-tracking tool
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimeExportConfig {
+    pub provider: Option<String>,   // "toggl", "clockify" or "tempo"
+    pub workspace_id: Option<String>,
+    pub author_account_id: Option<String>,
+    pub min_block_minutes: u32,
+    pub merge_gap_minutes: u32,
+    pub daily: bool,
+    pub projects: BTreeMap<String, TimeEntryMapping>,
+}
+
+/// `[time_export.projects.<name>]`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimeEntryMapping {
+    pub project_id: Option<String>,
+    pub task_id: Option<String>,
+    pub issue_id: Option<String>,
+    pub billable: bool,
+}
+
+/// `[collectors.<name>]`: one pluggable collector
+#[derive(Debug, Clone, Deserialize)]
+pub struct CollectorConfig {
+    pub enabled: bool,
+    pub interval_secs: Option<u64>, // the collector's own interval when None
+}
+
+/// `[scheduler]`: background jobs
+#[derive(Debug, Clone, Deserialize)]
+pub struct SchedulerConfig {
+    pub jitter_secs: u64,
+    pub jobs: BTreeMap<String, JobConfig>,
+}
+
+/// `[scheduler.jobs.<name>]`
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobConfig {
+    pub enabled: bool,
+    pub cron: Option<String>,       // the job's own schedule when None
+    pub jitter_secs: Option<u64>,   // `scheduler.jitter_secs` when None
+}
+
+/// Configuration the collector started with, from `/config`
+#[derive(Debug, Clone, Deserialize)]
+pub struct EffectiveConfig {
+    pub file: Option<String>,
+    pub env_overrides: Vec<String>,
+    pub snapshot_interval_secs: u64,
+    pub data_dir: String,
+    pub history_size: usize,
+    pub bind_addr: String,
+    pub server_url: String,
+    pub device_id: Option<String>,  // None when generated on first run
+    pub upload_queue_max_mb: u64,
+    pub upload_batch_size: usize,
+    pub upload_tls: UploadTlsConfig,
+    pub remote_commands: bool,
+    pub flag_detection: String,     // "external" or "in_process"
+    pub storage_backend: String,    // "json" or "sqlite"
+    pub compression: String,        // "none" or "zstd"
+    pub snapshot_format: String,    // "files" or "ndjson"
+    pub snapshot_log_max_mb: u64,
+    pub encryption: String,         // "none" or "xchacha20poly1305"
+    pub encryption_key_file: Option<String>,
+    pub compact_after_days: Option<u32>,
+    pub compare_collectors: bool,
+    pub keystroke_capture: String,  // "off", "timing" or "content"
+    pub metrics_mirror: bool,
+    pub forensic_buffer_secs: u32,  // 0 when off
+    pub top_processes: usize,       // 0 when off
+    pub modules: ModuleConfig,
+    pub retention: RetentionConfig,
+    pub redaction: RedactionConfig,
+    pub emotion: EmotionConfig,
+    pub input_trace: InputTraceConfig,
+    pub time_export: TimeExportConfig,
+    pub scheduler: SchedulerConfig,
+    pub collectors: BTreeMap<String, CollectorConfig>,
+}
+
+/// One reading pushed to `/ingest/heart-rate`
+#[derive(Debug, Clone, Serialize)]
+pub struct HeartRateSample {
+    pub timestamp: DateTime<Utc>,
+    pub bpm: f32,
+    pub rr_intervals_ms: Vec<f32>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StressCalibration {
+    pub paired_samples: usize,
+    pub baseline_heart_rate_bpm: Option<f32>,
+    pub keystroke_vs_heart_rate: Option<f32>,
+    pub keystroke_vs_hrv: Option<f32>,
+    pub mouse_erratic_vs_heart_rate: Option<f32>,
+    pub sufficient_data: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BuildInfo {
+    pub name: String,
+    pub version: String,
+    pub git_hash: String,
+    pub build_date: String,
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateStatus {
+    pub current_version: String,
+    pub latest_version: Option<String>,
+    pub update_available: bool,
+    pub download_url: Option<String>,
+    pub release_notes: Option<String>,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionInfo {
+    pub build: BuildInfo,
+    pub update: UpdateStatus,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateApplied {
+    pub status: String,
+    pub version: String,
+    pub restarting: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Peer {
+    pub name: String,
+    pub device_id: Option<String>,
+    pub hostname: String,
+    pub addresses: Vec<String>,
+    pub port: u16,
+    pub version: Option<String>,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscoveryPeers {
+    pub enabled: bool,
+    pub peers: Vec<Peer>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TeamStatus {
+    pub device_id: String,
+    pub device_name: String,
+    pub focus_state: String, // "focused", "available", "idle", "unknown"
+    pub in_meeting: bool,
+    pub do_not_disturb: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TeamMember {
+    pub status: Option<TeamStatus>,
+    pub reachable: bool,
+    pub last_error: Option<String>,
+    pub last_contact: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TeamOverview {
+    pub enabled: bool,
+    #[serde(rename = "self", default)]
+    pub local: Option<TeamStatus>,
+    pub teammates: Vec<TeamMember>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DoNotDisturb {
+    pub do_not_disturb: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BookmarkCreated {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub note: Option<String>,
+    pub snapshots_before: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bookmarks {
+    pub count: usize,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+/// Body of `POST /notes`; `start` defaults to now and `end` to `start`
+#[derive(Debug, Clone, Serialize)]
+pub struct NewNote {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl NewNote {
+    pub fn new(text: impl Into<String>) -> Self {
+        NewNote { text: text.into(), session_id: None, start: None, end: None }
+    }
+}
+
+/// Parameters of `GET /notes`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NotesQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Notes {
+    pub count: usize,
+    pub notes: Vec<SessionNote>,
+}
+
+/// Parameters of `GET /flags` and `GET /flags/{session_id}`; `severity` is a minimum ("high" is
+/// High and Critical)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FlagsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flag_type: Option<String>,
+}
+
+/// Parameters of `GET /flags/recent` (server defaults: the last 24 hours, 50 flags)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecentFlagsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flag_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hours: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// Body of `POST /share`: every snapshot field to include must be listed (dotted paths such as
+/// `system_metrics.cpu_usage`); the window is at most 2 hours
+#[derive(Debug, Clone, Serialize)]
+pub struct NewShare {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub fields: Vec<String>,
+    pub flags: bool,
+    pub logs: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_hours: Option<u32>,
+    pub(crate) confirm: bool,
+}
+
+impl NewShare {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>, fields: &[&str]) -> Self {
+        NewShare {
+            start,
+            end,
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+            flags: false,
+            logs: false,
+            expires_in_hours: None,
+            confirm: false,
+        }
+    }
+}
+
+/// Flags from `/flags`, oldest first; newest first from `/flags/recent`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Flags {
+    #[serde(default)]
+    pub hours: Option<u32>, // window of `/flags/recent`
+    pub count: usize,
+    pub flags: Vec<FlagRecord>,
+}
+
+/// Flag detector thresholds from `/detector/config`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DetectorConfig {
+    pub mode: String,                  // "in_process" (live) or "external" (as in the file)
+    pub path: String,                  // detector.toml
+    pub file_exists: bool,
+    pub thresholds: BTreeMap<String, DetectorRule>, // by rule: "cpu", "memory", "idle", "focus", ...
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DetectorRule {
+    pub threshold: f32,
+    pub severity: String,
+    pub escalate: BTreeMap<String, f32>, // higher severity -> level it applies from
+    pub confidence: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContentAccessLog {
+    pub count: usize,
+    pub events: Vec<ContentAccessEvent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TypedText {
+    pub timestamp: DateTime<Utc>,
+    pub typed_text: Option<String>,
+    pub buttons_clicked: Vec<ButtonClick>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScreenText {
+    pub timestamp: DateTime<Utc>,
+    pub screen_text_snapshot: Option<String>,
+    pub active_windows: Vec<WindowContent>,
+}
+
+/// Incident bundles from `/content/incidents`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Incidents {
+    pub forensic_buffer_secs: u32,
+    pub count: usize,
+    pub incidents: Vec<IncidentBundle>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transcripts {
+    pub count: usize,
+    pub transcripts: Vec<serde_json::Value>, // stored transcription files as written
+}
+
+/// Opt-in modules and their settings, from `/control`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Modules {
+    pub modules: Vec<ModuleSettings>,
+}
+
+/// Pluggable collectors and their cadence, from `/collectors`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Collectors {
+    pub collectors: Vec<CollectorStatus>,
+}
+
+/// Background jobs and their last runs, from `/scheduler/jobs`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduledJobs {
+    pub jobs: Vec<ScheduledJob>,
+}
+
+/// OS permissions the collectors need, from `/permissions`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Permissions {
+    pub permissions: Vec<PermissionStatus>,
+}
+
+/// Registered goals, from `/goals`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Goals {
+    pub goals: Vec<Goal>,
+}
+
+/// Today's and this week's progress of every goal, from `/goals/progress`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GoalsProgress {
+    pub goals: Vec<GoalProgress>,
+}
+
+/// The review queue of time entries, from `/time_entries`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimeEntries {
+    pub provider: Option<String>,   // None when no time-tracking tool is configured
+    pub entries: Vec<TimeEntry>,
+}
+
+/// Reply of `POST /permissions/{name}/request`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PermissionRequest {
+    pub permission: PermissionStatus,
+    pub prompted: bool,    // an OS prompt or settings page was shown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn team_overview_reads_the_self_field() {
+        let body = r#"{"enabled": true, "self": {"device_id": "d1", "device_name": "desk", "focus_state": "focused",
+            "in_meeting": false, "do_not_disturb": true, "updated_at": "2025-10-26T13:35:36Z"}, "teammates": []}"#;
+        let overview: TeamOverview = serde_json::from_str(body).unwrap();
+        assert!(overview.local.unwrap().do_not_disturb);
+
+        let disabled: TeamOverview = serde_json::from_str(r#"{"enabled": false, "teammates": []}"#).unwrap();
+        assert!(disabled.local.is_none());
+    }
+
+    #[test]
+    fn unset_query_fields_are_left_to_the_server() {
+        let query = SampleQuery { n: Some(20), ..Default::default() };
+        assert_eq!(serde_json::to_value(&query).unwrap(), serde_json::json!({ "n": 20 }));
+        let range = DateRange::days_ending(NaiveDate::from_ymd_opt(2025, 10, 26).unwrap(), 7);
+        assert_eq!(serde_json::to_value(&range).unwrap(), serde_json::json!({ "date": "2025-10-26", "days": 7 }));
+    }
+}
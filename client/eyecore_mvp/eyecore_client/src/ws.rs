@@ -0,0 +1,118 @@
+//! The collector's upload socket (`ws://<server>:8765`), for harnesses that stand in for a
+//! collector against the server, or for the server against a test collector
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+use crate::error::Error;
+use crate::models::EyeCoreData;
+
+/// Messages the server sends after authentication
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    /// Limit `Package` messages to these JSON pointers; None restores full snapshots
+    SetProjection { fields: Option<Vec<String>> },
+    /// Any reply or method this client does not model
+    Other(serde_json::Value),
+}
+
+impl ServerMessage {
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        if value.get("method").and_then(|m| m.as_str()) == Some("SetProjection") {
+            #[derive(Deserialize)]
+            struct Projection {
+                #[serde(default)]
+                fields: Option<Vec<String>>,
+            }
+            let projection: Projection = serde_json::from_value(value["data"].clone()).unwrap_or(Projection { fields: None });
+            let fields = projection.fields.filter(|fields| !fields.is_empty());
+            return Ok(ServerMessage::SetProjection { fields });
+        }
+        Ok(ServerMessage::Other(value))
+    }
+}
+
+/// An authenticated upload connection
+pub struct UploadSocket {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    token: String,
+}
+
+impl UploadSocket {
+    /// Connect and authenticate with an access code, as the collector does on startup
+    pub async fn connect(url: &str, access_code: &str) -> Result<Self, Error> {
+        let (mut stream, _) = connect_async(url).await?;
+        let auth = json!({ "method": "Authenticate", "data": { "access_code": access_code } });
+        stream.send(Message::Text(auth.to_string())).await?;
+
+        let reply = loop {
+            match stream.next().await {
+                Some(Ok(Message::Text(text))) => break serde_json::from_str::<serde_json::Value>(&text)?,
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(Error::Authentication("connection closed before a reply".to_string()))
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+            }
+        };
+        if reply.get("status").and_then(|s| s.as_str()) != Some("success") {
+            return Err(Error::Authentication(reply.to_string()));
+        }
+        let token = reply["data"]["token"]
+            .as_str()
+            .ok_or_else(|| Error::Authentication("reply carries no token".to_string()))?
+            .to_string();
+        Ok(UploadSocket { stream, token })
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Send one snapshot as a `Package` message
+    pub async fn send_package(&mut self, data: &EyeCoreData) -> Result<(), Error> {
+        let mut data = serde_json::to_value(data)?;
+        data["token"] = json!(self.token);
+        let package = json!({ "method": "Package", "data": data });
+        self.stream.send(Message::Text(package.to_string())).await?;
+        Ok(())
+    }
+
+    /// Next text message from the server; None once the connection is closed
+    pub async fn next_message(&mut self) -> Result<Option<ServerMessage>, Error> {
+        while let Some(message) = self.stream.next().await {
+            match message? {
+                Message::Text(text) => return ServerMessage::parse(&text).map(Some),
+                Message::Close(_) => return Ok(None),
+                _ => continue,
+            }
+        }
+        Ok(None)
+    }
+
+    pub async fn close(mut self) -> Result<(), Error> {
+        self.stream.close(None).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_projection_is_parsed_and_empty_lists_clear_it() {
+        let message = ServerMessage::parse(r#"{"method": "SetProjection", "data": {"fields": ["/timestamp"]}}"#).unwrap();
+        assert_eq!(message, ServerMessage::SetProjection { fields: Some(vec!["/timestamp".to_string()]) });
+
+        let cleared = ServerMessage::parse(r#"{"method": "SetProjection", "data": {"fields": []}}"#).unwrap();
+        assert_eq!(cleared, ServerMessage::SetProjection { fields: None });
+
+        assert!(matches!(ServerMessage::parse(r#"{"status": "ok"}"#).unwrap(), ServerMessage::Other(_)));
+    }
+}
@@ -0,0 +1,181 @@
+//! `import eyecore`: the production flag rules, audio cleaner and snapshot readers for notebooks.
+//! Nothing is reimplemented here; the collector's modules are compiled in as-is and the detector
+//! comes from the flag_detection crate.
+
+// pyo3 0.22's #[pyfunction] expansion converts PyErr into itself
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use std::path::{Path, PathBuf};
+
+use flag_detection::detector::FlagDetector;
+use flag_detection::models::{EyeCoreDataFile, Flag};
+use flag_detection::policy::SeverityPolicy;
+
+// The collector's own sources, linted with the collector; only part of each is exposed to Python
+#[allow(dead_code, clippy::all)]
+#[path = "../../src/models.rs"]
+mod models;
+#[allow(dead_code, clippy::all)]
+#[path = "../../src/bulk_read.rs"]
+mod bulk_read;
+#[allow(dead_code, clippy::all)]
+#[path = "../../src/audio_cleaner.rs"]
+mod audio_cleaner;
+
+/// Run the flag detector over one exported snapshot file, snapshot log or archive, or every `.json`
+/// file, log and archive under a directory. `policy` is a severity policy file, as in FLAG_SEVERITY_POLICY. Returns
+/// the flags as dicts. In a directory, files and log lines that are not snapshots are skipped.
+#[pyfunction]
+#[pyo3(signature = (path, policy=None))]
+fn analyze(py: Python<'_>, path: PathBuf, policy: Option<PathBuf>) -> PyResult<PyObject> {
+    let flags = py.allow_threads(|| -> Result<Vec<Flag>, String> {
+        let policy = match policy {
+            Some(path) => SeverityPolicy::load(&path)?,
+            None => SeverityPolicy::default(),
+        };
+        let single_file = path.is_file();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let detector = FlagDetector::new();
+
+        let mut flags = Vec::new();
+        for file in json_files(&path)? {
+            let documents: Vec<Vec<u8>> = if bulk_read::is_snapshot_archive(&file) {
+                let archive = bulk_read::SnapshotArchive::open(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+                archive
+                    .entries()
+                    .iter()
+                    .map(|entry| archive.record(entry).map(|record| record.into_owned()))
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| format!("{}: {}", file.display(), e))?
+            } else {
+                let content = std::fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+                // A log holds one snapshot per line; a last line still being written is left out
+                if bulk_read::is_snapshot_log(&file) {
+                    content
+                        .split_inclusive('\n')
+                        .filter(|line| line.ends_with('\n') && !line.trim().is_empty())
+                        .map(|line| line.as_bytes().to_vec())
+                        .collect()
+                } else {
+                    vec![content.into_bytes()]
+                }
+            };
+            for document in documents {
+                let data_file = match serde_json::from_slice::<EyeCoreDataFile>(&document) {
+                    Ok(data_file) => data_file,
+                    Err(e) if single_file => return Err(format!("{}: {}", file.display(), e)),
+                    Err(_) => continue,
+                };
+                for mut flag in runtime.block_on(detector.analyze_data(&data_file.data))? {
+                    policy.apply(&mut flag);
+                    flags.push(flag);
+                }
+            }
+        }
+        Ok(flags)
+    });
+    to_python(py, &flags.map_err(PyValueError::new_err)?)
+}
+
+/// Read snapshots from a timeslot file, a snapshot log, an archive, or every `timeslots/` file, log
+/// and archive under a directory (as the collector's reports and queries do), returning the `data` part of each as a dict
+#[pyfunction]
+fn load_snapshots(py: Python<'_>, path: PathBuf) -> PyResult<PyObject> {
+    let snapshots = py.allow_threads(|| -> Result<Vec<models::EyeCoreData>, String> {
+        if path.is_file() && bulk_read::is_snapshot_log(&path) {
+            let mut snapshots = Vec::new();
+            bulk_read::read_snapshot_log(&path, |data| snapshots.push(data))?;
+            return Ok(snapshots);
+        }
+        if path.is_file() && bulk_read::is_snapshot_archive(&path) {
+            let mut snapshots = Vec::new();
+            bulk_read::read_snapshot_archive(&path, |data| snapshots.push(data))?;
+            return Ok(snapshots);
+        }
+        if path.is_file() {
+            return bulk_read::read_snapshot(&path).map(|data| vec![data]);
+        }
+        let paths: Vec<PathBuf> = json_files(&path)?
+            .into_iter()
+            .filter(|file| file.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()) == Some("timeslots"))
+            .collect();
+        let mut snapshots = Vec::with_capacity(paths.len());
+        bulk_read::scan_snapshots(&paths, |data| snapshots.push(data));
+        Ok(snapshots)
+    });
+    to_python(py, &snapshots.map_err(PyIOError::new_err)?)
+}
+
+/// Clean a WAV recording exactly as the collector does, writing the result to `output`
+/// (default `<name>_cleaned.wav` next to the input). Returns the cleaning report with
+/// voice segments (ms), estimated speakers and stage timings.
+#[pyfunction]
+#[pyo3(signature = (path, output=None, silence_threshold_db=-40.0, min_speech_duration_ms=500))]
+fn clean_audio(
+    py: Python<'_>,
+    path: PathBuf,
+    output: Option<PathBuf>,
+    silence_threshold_db: f32,
+    min_speech_duration_ms: u32,
+) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| {
+        let output = output.unwrap_or_else(|| {
+            let name = path.file_stem().and_then(|n| n.to_str()).unwrap_or("audio");
+            path.with_file_name(format!("{}_cleaned.wav", name))
+        });
+        audio_cleaner::AudioCleaner::clean_audio_segment(&path, &output, silence_threshold_db, min_speech_duration_ms)
+            .map(|cleaned| {
+                let mut report = cleaned.report();
+                report["output_path"] = serde_json::json!(output);
+                report["voice_segments_ms"] = serde_json::json!(cleaned.voice_segments_ms());
+                report["estimated_speakers"] = serde_json::json!(cleaned.estimated_speakers);
+                report["timings_ms"] = serde_json::json!({
+                    "decode": cleaned.timings.decode_ms,
+                    "analysis": cleaned.timings.analysis_ms,
+                    "write": cleaned.timings.write_ms,
+                });
+                report
+            })
+    });
+    to_python(py, &result.map_err(PyValueError::new_err)?)
+}
+
+/// `path` itself, or the `.json` files, snapshot logs and archives under it (sorted, recursively)
+fn json_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            files.extend(json_files(&entry)?);
+        } else if bulk_read::is_json_file(&entry) || bulk_read::is_snapshot_log(&entry) || bulk_read::is_snapshot_archive(&entry) {
+            files.push(entry);
+        }
+    }
+    Ok(files)
+}
+
+/// Hand results over as plain dicts/lists via the json module, matching the files on disk
+fn to_python<T: serde::Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let text = serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(py.import_bound("json")?.call_method1("loads", (text,))?.unbind())
+}
+
+#[pymodule]
+fn eyecore(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(analyze, m)?)?;
+    m.add_function(wrap_pyfunction!(load_snapshots, m)?)?;
+    m.add_function(wrap_pyfunction!(clean_audio, m)?)?;
+    Ok(())
+}
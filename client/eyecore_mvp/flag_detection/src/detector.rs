@@ -1,54 +1,85 @@
 use crate::models::*;
-use chrono::Utc;
+use crate::thresholds::{SharedThresholds, Thresholds};
 use log::debug;
 use serde_json::json;
-use uuid::Uuid;
 
 pub struct FlagDetector {
-    // Thresholds for anomaly detection
-    cpu_threshold: f32,
-    memory_threshold: f32,
-    idle_threshold: u32,
-    focus_threshold: f32,
-    stress_threshold: f32,
-    fatigue_threshold: f32,
+    // Thresholds for anomaly detection, replaced when detector.toml changes
+    thresholds: SharedThresholds,
+    // VPNs are routine on personal devices; only managed deployments want them flagged
+    vpn_alerts: bool,
+}
+
+impl Default for FlagDetector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FlagDetector {
     pub fn new() -> Self {
         FlagDetector {
-            cpu_threshold: 90.0,       // CPU usage > 90%
-            memory_threshold: 85.0,    // Memory usage > 85%
-            idle_threshold: 300,       // Idle > 5 minutes
-            focus_threshold: 0.3,      // Focus < 0.3
-            stress_threshold: 0.7,     // Stress > 0.7
-            fatigue_threshold: 0.8,    // Fatigue > 0.8
+            thresholds: Default::default(),
+            vpn_alerts: false,
         }
     }
     
+    /// Use `thresholds`, which may be replaced while the detector runs
+    pub fn with_thresholds(mut self, thresholds: SharedThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+    
+    /// The thresholds in use
+    pub fn thresholds(&self) -> Thresholds {
+        self.thresholds.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    
+    /// Raise a SecurityConcern when a VPN connects partway through a session
+    pub fn with_vpn_alerts(mut self, enabled: bool) -> Self {
+        self.vpn_alerts = enabled;
+        self
+    }
+    
     /// Analyze EyeCore data and detect anomalies
     pub async fn analyze_data(&self, data: &EyeCoreData) -> Result<Vec<Flag>, String> {
+        self.analyze_data_with_baseline(data, None).await
+    }
+    
+    /// Analyze EyeCore data against the device's calibrated baseline, if it has one
+    pub async fn analyze_data_with_baseline(&self, data: &EyeCoreData, baseline: Option<&Baseline>) -> Result<Vec<Flag>, String> {
         let mut flags = Vec::new();
         
         debug!("Analyzing data for session: {}", data.session_id);
         
+        // The whole snapshot is judged by the same thresholds, even if they are reloaded meanwhile
+        let thresholds = self.thresholds();
+        
         // Check system metrics
-        flags.extend(self.check_system_metrics(&data.system_metrics, &data.session_id));
+        let cpu_warming_up = data.is_warming_up("system_metrics.cpu_usage");
+        let mut system_flags = self.check_system_metrics(&thresholds, &data.system_metrics, &data.session_id, cpu_warming_up);
+        // Put high CPU down to the busiest process when the client lists them
+        if let Some(top) = data.top_processes.first().filter(|_| !data.is_warming_up("top_processes")) {
+            for flag in system_flags.iter_mut().filter(|f| f.title == "High CPU Usage") {
+                self.attribute_cpu(flag, top);
+            }
+        }
+        flags.extend(system_flags);
         
-        // Check input patterns
-        flags.extend(self.check_input_metrics(&data.input_metrics, &data.session_id));
+        // Check input patterns (little input is expected while on a call or watching something)
+        flags.extend(self.check_input_metrics(&thresholds, &data.input_metrics, &data.session_id, data.in_meeting(), data.presence_state()));
         
-        // Check focus metrics
-        flags.extend(self.check_focus_metrics(&data.focus_metrics, &data.session_id));
+        // Check focus metrics (nobody is there to be focused while away)
+        flags.extend(self.check_focus_metrics(&thresholds, &data.focus_metrics, &data.session_id, data.in_meeting(), data.presence_state()));
         
         // Check keystroke dynamics if available
         if let Some(ref keystroke) = data.keystroke_dynamics {
-            flags.extend(self.check_keystroke_dynamics(keystroke, &data.session_id));
+            flags.extend(self.check_keystroke_dynamics(&thresholds, keystroke, &data.session_id, baseline));
         }
         
         // Check mouse dynamics if available
         if let Some(ref mouse) = data.mouse_dynamics {
-            flags.extend(self.check_mouse_dynamics(mouse, &data.session_id));
+            flags.extend(self.check_mouse_dynamics(&thresholds, mouse, &data.session_id, baseline));
         }
         
         // Check voice data if available
@@ -63,7 +94,8 @@ impl FlagDetector {
         
         // Check network activity if available
         if let Some(ref network) = data.network_activity_metadata {
-            flags.extend(self.check_network_activity(network, &data.session_id));
+            let bandwidth_warming_up = data.is_warming_up("network_activity_metadata.bandwidth_usage_mbps");
+            flags.extend(self.check_network_activity(network, &data.session_id, bandwidth_warming_up));
         }
         
         // Check screen interactions if available
@@ -71,50 +103,87 @@ impl FlagDetector {
             flags.extend(self.check_screen_interactions(screen, &data.session_id));
         }
         
+        // Say what pulled attention away, so productivity alerts are actionable
+        if let Some(context) = data.focus_context.as_ref().filter(|c| !c.is_empty()) {
+            for flag in flags.iter_mut().filter(|f| f.flag_type == FlagType::ProductivityAlert) {
+                self.attribute_distraction(flag, context);
+            }
+        }
+        
         Ok(flags)
     }
     
+    /// Add the client's distraction sources to a flag: the raw events under `metrics.attribution`
+    /// and the most recent of each kind in the description
+    fn attribute_distraction(&self, flag: &mut Flag, context: &FocusContext) {
+        let mut causes = Vec::new();
+        if let Some(switch) = context.app_switches.last() {
+            causes.push(format!("switched to {}", switch.to_app));
+        }
+        if let Some(notification) = context.notifications.last() {
+            causes.push(format!("notification from {}", notification.app));
+        }
+        if let Some(change) = context.site_changes.last() {
+            causes.push(format!("{} moved to {}", change.browser, change.to_site));
+        }
+        let summary = causes.join(", ");
+        
+        if let Some(metrics) = flag.metrics.as_object_mut() {
+            metrics.insert("attribution".to_string(), json!({
+                "summary": summary,
+                "app_switches": context.app_switches,
+                "notifications": context.notifications,
+                "site_changes": context.site_changes,
+            }));
+        }
+        flag.description = format!("{} (likely cause: {})", flag.description, summary);
+    }
+    
+    /// Name the process using the most CPU in a flag: under `metrics.top_process` and in the description
+    fn attribute_cpu(&self, flag: &mut Flag, top: &ProcessUsage) {
+        if let Some(metrics) = flag.metrics.as_object_mut() {
+            metrics.insert("top_process".to_string(), json!({
+                "pid": top.pid,
+                "name": top.name,
+                "cpu_usage": top.cpu_usage,
+                "memory_bytes": top.memory_bytes,
+            }));
+        }
+        flag.description = format!("{} (top process: {} at {:.1}%)", flag.description, top.name, top.cpu_usage);
+    }
+    
     /// Check system metrics for anomalies
-    fn check_system_metrics(&self, metrics: &SystemMetrics, session_id: &str) -> Vec<Flag> {
+    fn check_system_metrics(&self, thresholds: &Thresholds, metrics: &SystemMetrics, session_id: &str, cpu_warming_up: bool) -> Vec<Flag> {
         let mut flags = Vec::new();
+        let (cpu, memory) = (&thresholds.cpu, &thresholds.memory);
         
-        // High CPU usage
-        if metrics.cpu_usage > self.cpu_threshold {
+        // High CPU usage (first-cycle readings are not meaningful)
+        if !cpu_warming_up && metrics.cpu_usage > cpu.threshold {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::PerformanceIssue,
-                severity: if metrics.cpu_usage > 95.0 { Severity::Critical } else { Severity::High },
-                title: "High CPU Usage".to_string(),
                 description: format!("CPU usage at {:.1}% exceeds threshold of {:.1}%", 
-                    metrics.cpu_usage, self.cpu_threshold),
+                    metrics.cpu_usage, cpu.threshold),
                 data_source: "system_metrics".to_string(),
                 metrics: json!({
                     "cpu_usage": metrics.cpu_usage,
-                    "threshold": self.cpu_threshold,
+                    "threshold": cpu.threshold,
                 }),
-                confidence: 0.95,
+                confidence: cpu.confidence,
+                ..Flag::new(session_id, FlagType::PerformanceIssue, cpu.severity_above(metrics.cpu_usage), "High CPU Usage")
             });
         }
         
         // High memory usage
-        if metrics.memory_usage > self.memory_threshold {
+        if metrics.memory_usage > memory.threshold {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::PerformanceIssue,
-                severity: if metrics.memory_usage > 95.0 { Severity::Critical } else { Severity::High },
-                title: "High Memory Usage".to_string(),
                 description: format!("Memory usage at {:.1}% exceeds threshold of {:.1}%", 
-                    metrics.memory_usage, self.memory_threshold),
+                    metrics.memory_usage, memory.threshold),
                 data_source: "system_metrics".to_string(),
                 metrics: json!({
                     "memory_usage": metrics.memory_usage,
-                    "threshold": self.memory_threshold,
+                    "threshold": memory.threshold,
                 }),
-                confidence: 0.95,
+                confidence: memory.confidence,
+                ..Flag::new(session_id, FlagType::PerformanceIssue, memory.severity_above(metrics.memory_usage), "High Memory Usage")
             });
         }
         
@@ -122,38 +191,39 @@ impl FlagDetector {
     }
     
     /// Check input metrics for anomalies
-    fn check_input_metrics(&self, metrics: &InputMetrics, session_id: &str) -> Vec<Flag> {
+    fn check_input_metrics(
+        &self,
+        thresholds: &Thresholds,
+        metrics: &InputMetrics,
+        session_id: &str,
+        in_meeting: bool,
+        presence: Option<PresenceState>,
+    ) -> Vec<Flag> {
         let mut flags = Vec::new();
         
-        // Prolonged idle time
-        if metrics.idle_duration_seconds > self.idle_threshold {
+        // Prolonged idle time, unless the client saw the user reading or watching
+        let consuming = presence == Some(PresenceState::PassiveConsuming);
+        let idle = &thresholds.idle;
+        let idle_seconds = metrics.idle_duration_seconds as f32;
+        if !in_meeting && !consuming && idle_seconds > idle.threshold {
+            let away = presence == Some(PresenceState::Away);
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::ProductivityAlert,
-                severity: Severity::Low,
-                title: "Prolonged Idle Time".to_string(),
-                description: format!("User idle for {} seconds (>{} seconds)", 
-                    metrics.idle_duration_seconds, self.idle_threshold),
+                description: format!("User {} for {} seconds (>{} seconds)", 
+                    if away { "away" } else { "idle" }, metrics.idle_duration_seconds, idle.threshold),
                 data_source: "input_metrics".to_string(),
                 metrics: json!({
                     "idle_duration_seconds": metrics.idle_duration_seconds,
-                    "threshold": self.idle_threshold,
+                    "threshold": idle.threshold,
+                    "presence": presence,
                 }),
-                confidence: 0.85,
+                confidence: idle.confidence,
+                ..Flag::new(session_id, FlagType::ProductivityAlert, idle.severity_above(idle_seconds), "Prolonged Idle Time")
             });
         }
         
         // Unusual activity pattern - no input at all
         if metrics.mouse_clicks == 0 && metrics.keyboard_events == 0 && metrics.idle_duration_seconds < 10 {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::BehaviorAnomaly,
-                severity: Severity::Low,
-                title: "No User Input Detected".to_string(),
                 description: "System active but no mouse/keyboard activity detected".to_string(),
                 data_source: "input_metrics".to_string(),
                 metrics: json!({
@@ -162,6 +232,7 @@ impl FlagDetector {
                     "idle_duration_seconds": metrics.idle_duration_seconds,
                 }),
                 confidence: 0.7,
+                ..Flag::new(session_id, FlagType::BehaviorAnomaly, Severity::Low, "No User Input Detected")
             });
         }
         
@@ -169,39 +240,39 @@ impl FlagDetector {
     }
     
     /// Check focus metrics for anomalies
-    fn check_focus_metrics(&self, metrics: &FocusMetrics, session_id: &str) -> Vec<Flag> {
+    fn check_focus_metrics(
+        &self,
+        thresholds: &Thresholds,
+        metrics: &FocusMetrics,
+        session_id: &str,
+        in_meeting: bool,
+        presence: Option<PresenceState>,
+    ) -> Vec<Flag> {
         let mut flags = Vec::new();
+        if presence == Some(PresenceState::Away) {
+            return flags;
+        }
         
         // Low focus level
-        if metrics.focus_level < self.focus_threshold {
+        let focus = &thresholds.focus;
+        if !in_meeting && metrics.focus_level < focus.threshold {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::ProductivityAlert,
-                severity: Severity::Medium,
-                title: "Low Focus Level".to_string(),
                 description: format!("Focus level at {:.2} is below threshold of {:.2}", 
-                    metrics.focus_level, self.focus_threshold),
+                    metrics.focus_level, focus.threshold),
                 data_source: "focus_metrics".to_string(),
                 metrics: json!({
                     "focus_level": metrics.focus_level,
                     "context_switches": metrics.context_switches,
-                    "threshold": self.focus_threshold,
+                    "threshold": focus.threshold,
                 }),
-                confidence: 0.8,
+                confidence: focus.confidence,
+                ..Flag::new(session_id, FlagType::ProductivityAlert, focus.severity_below(metrics.focus_level), "Low Focus Level")
             });
         }
         
         // Excessive context switching
         if metrics.context_switches > 50 {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::ProductivityAlert,
-                severity: Severity::Medium,
-                title: "Excessive Context Switching".to_string(),
                 description: format!("Detected {} context switches, indicating possible distraction", 
                     metrics.context_switches),
                 data_source: "focus_metrics".to_string(),
@@ -209,6 +280,7 @@ impl FlagDetector {
                     "context_switches": metrics.context_switches,
                 }),
                 confidence: 0.75,
+                ..Flag::new(session_id, FlagType::ProductivityAlert, Severity::Medium, "Excessive Context Switching")
             });
         }
         
@@ -216,18 +288,15 @@ impl FlagDetector {
     }
     
     /// Check keystroke dynamics for stress/fatigue
-    fn check_keystroke_dynamics(&self, keystroke: &KeystrokeDynamics, session_id: &str) -> Vec<Flag> {
+    fn check_keystroke_dynamics(&self, thresholds: &Thresholds, keystroke: &KeystrokeDynamics, session_id: &str, baseline: Option<&Baseline>) -> Vec<Flag> {
         let mut flags = Vec::new();
+        let limit = |metric: &str, default: f32| baseline.map_or(default, |b| b.upper_limit(metric, default));
         
         // High stress indicator
-        if keystroke.stress_indicator > self.stress_threshold {
+        let stress = &thresholds.stress;
+        let stress_limit = limit("keystroke_dynamics.stress_indicator", stress.threshold);
+        if keystroke.stress_indicator > stress_limit {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::HealthConcern,
-                severity: Severity::Medium,
-                title: "High Stress Detected".to_string(),
                 description: format!("Keystroke patterns indicate stress level of {:.2}", 
                     keystroke.stress_indicator),
                 data_source: "keystroke_dynamics".to_string(),
@@ -235,20 +304,18 @@ impl FlagDetector {
                     "stress_indicator": keystroke.stress_indicator,
                     "typing_speed_wpm": keystroke.typing_speed_wpm,
                     "error_correction_rate": keystroke.error_correction_rate,
+                    "limit": stress_limit,
                 }),
-                confidence: 0.75,
+                confidence: stress.confidence,
+                ..Flag::new(session_id, FlagType::HealthConcern, stress.severity_above(keystroke.stress_indicator), "High Stress Detected")
             });
         }
         
         // High fatigue indicator
-        if keystroke.fatigue_indicator > self.fatigue_threshold {
+        let fatigue = &thresholds.fatigue;
+        let fatigue_limit = limit("keystroke_dynamics.fatigue_indicator", fatigue.threshold);
+        if keystroke.fatigue_indicator > fatigue_limit {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::HealthConcern,
-                severity: Severity::Medium,
-                title: "Fatigue Detected".to_string(),
                 description: format!("Keystroke patterns indicate fatigue level of {:.2}", 
                     keystroke.fatigue_indicator),
                 data_source: "keystroke_dynamics".to_string(),
@@ -256,27 +323,26 @@ impl FlagDetector {
                     "fatigue_indicator": keystroke.fatigue_indicator,
                     "typing_speed_wpm": keystroke.typing_speed_wpm,
                     "key_press_variance": keystroke.key_press_variance,
+                    "limit": fatigue_limit,
                 }),
-                confidence: 0.75,
+                confidence: fatigue.confidence,
+                ..Flag::new(session_id, FlagType::HealthConcern, fatigue.severity_above(keystroke.fatigue_indicator), "Fatigue Detected")
             });
         }
         
         // High error rate
-        if keystroke.error_correction_rate > 0.15 {
+        let error_limit = limit("keystroke_dynamics.error_correction_rate", 0.15);
+        if keystroke.error_correction_rate > error_limit {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::HealthConcern,
-                severity: Severity::Low,
-                title: "High Typing Error Rate".to_string(),
                 description: format!("Error correction rate at {:.1}% suggests possible fatigue or distraction", 
                     keystroke.error_correction_rate * 100.0),
                 data_source: "keystroke_dynamics".to_string(),
                 metrics: json!({
                     "error_correction_rate": keystroke.error_correction_rate,
+                    "limit": error_limit,
                 }),
                 confidence: 0.7,
+                ..Flag::new(session_id, FlagType::HealthConcern, Severity::Low, "High Typing Error Rate")
             });
         }
         
@@ -284,18 +350,16 @@ impl FlagDetector {
     }
     
     /// Check mouse dynamics for anomalies
-    fn check_mouse_dynamics(&self, mouse: &MouseDynamics, session_id: &str) -> Vec<Flag> {
+    fn check_mouse_dynamics(&self, thresholds: &Thresholds, mouse: &MouseDynamics, session_id: &str, baseline: Option<&Baseline>) -> Vec<Flag> {
         let mut flags = Vec::new();
         
         // High fatigue from mouse patterns
-        if mouse.fatigue_indicator > self.fatigue_threshold {
+        let fatigue = &thresholds.mouse_fatigue;
+        let fatigue_limit = baseline.map_or(fatigue.threshold, |b| {
+            b.upper_limit("mouse_dynamics.fatigue_indicator", fatigue.threshold)
+        });
+        if mouse.fatigue_indicator > fatigue_limit {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::HealthConcern,
-                severity: Severity::Medium,
-                title: "Mouse Movement Fatigue".to_string(),
                 description: format!("Mouse patterns indicate fatigue level of {:.2}", 
                     mouse.fatigue_indicator),
                 data_source: "mouse_dynamics".to_string(),
@@ -303,20 +367,16 @@ impl FlagDetector {
                     "fatigue_indicator": mouse.fatigue_indicator,
                     "path_smoothness": mouse.path_smoothness,
                     "hesitation_count": mouse.hesitation_count,
+                    "limit": fatigue_limit,
                 }),
-                confidence: 0.7,
+                confidence: fatigue.confidence,
+                ..Flag::new(session_id, FlagType::HealthConcern, fatigue.severity_above(mouse.fatigue_indicator), "Mouse Movement Fatigue")
             });
         }
         
         // Erratic mouse movement
         if mouse.path_smoothness < 0.3 && mouse.hesitation_count > 15 {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::BehaviorAnomaly,
-                severity: Severity::Low,
-                title: "Erratic Mouse Movement".to_string(),
                 description: "Mouse movement patterns are irregular with many hesitations".to_string(),
                 data_source: "mouse_dynamics".to_string(),
                 metrics: json!({
@@ -324,6 +384,7 @@ impl FlagDetector {
                     "hesitation_count": mouse.hesitation_count,
                 }),
                 confidence: 0.65,
+                ..Flag::new(session_id, FlagType::BehaviorAnomaly, Severity::Low, "Erratic Mouse Movement")
             });
         }
         
@@ -337,12 +398,6 @@ impl FlagDetector {
         // Negative sentiment
         if voice.sentiment_score < -0.5 {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::HealthConcern,
-                severity: Severity::Medium,
-                title: "Negative Emotional State".to_string(),
                 description: format!("Voice sentiment at {:.2} indicates negative emotional state", 
                     voice.sentiment_score),
                 data_source: "voice_data".to_string(),
@@ -352,18 +407,13 @@ impl FlagDetector {
                     "vocal_tone_score": voice.vocal_tone_score,
                 }),
                 confidence: 0.75,
+                ..Flag::new(session_id, FlagType::HealthConcern, Severity::Medium, "Negative Emotional State")
             });
         }
         
         // Stress-related emotions
         if voice.emotion_detected == "stressed" || voice.emotion_detected == "frustrated" {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::HealthConcern,
-                severity: Severity::Medium,
-                title: "Stress Detected in Voice".to_string(),
                 description: format!("Voice analysis detected emotion: {}", voice.emotion_detected),
                 data_source: "voice_data".to_string(),
                 metrics: json!({
@@ -371,6 +421,7 @@ impl FlagDetector {
                     "vocal_tone_score": voice.vocal_tone_score,
                 }),
                 confidence: 0.8,
+                ..Flag::new(session_id, FlagType::HealthConcern, Severity::Medium, "Stress Detected in Voice")
             });
         }
         
@@ -381,15 +432,14 @@ impl FlagDetector {
     fn check_camera_data(&self, camera: &CameraData, session_id: &str) -> Vec<Flag> {
         let mut flags = Vec::new();
         
+        // Presence-only cameras report nothing to judge posture or gaze by
+        if camera.presence_only {
+            return flags;
+        }
+        
         // Poor posture
         if camera.posture_score < 0.4 {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::HealthConcern,
-                severity: Severity::Low,
-                title: "Poor Posture Detected".to_string(),
                 description: format!("Posture score of {:.2} suggests poor ergonomics", 
                     camera.posture_score),
                 data_source: "camera_data".to_string(),
@@ -397,24 +447,20 @@ impl FlagDetector {
                     "posture_score": camera.posture_score,
                 }),
                 confidence: 0.7,
+                ..Flag::new(session_id, FlagType::HealthConcern, Severity::Low, "Poor Posture Detected")
             });
         }
         
         // Gaze away from screen
         if camera.gaze_direction == "away" {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::ProductivityAlert,
-                severity: Severity::Low,
-                title: "User Not Looking at Screen".to_string(),
                 description: "Camera detected user gaze is away from screen".to_string(),
                 data_source: "camera_data".to_string(),
                 metrics: json!({
                     "gaze_direction": camera.gaze_direction,
                 }),
                 confidence: 0.75,
+                ..Flag::new(session_id, FlagType::ProductivityAlert, Severity::Low, "User Not Looking at Screen")
             });
         }
         
@@ -422,18 +468,12 @@ impl FlagDetector {
     }
     
     /// Check network activity for suspicious patterns
-    fn check_network_activity(&self, network: &NetworkActivityMetadata, session_id: &str) -> Vec<Flag> {
+    fn check_network_activity(&self, network: &NetworkActivityMetadata, session_id: &str, bandwidth_warming_up: bool) -> Vec<Flag> {
         let mut flags = Vec::new();
         
         // Excessive bandwidth usage
-        if network.bandwidth_usage_mbps > 500.0 {
+        if !bandwidth_warming_up && network.bandwidth_usage_mbps > 500.0 {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::SystemAnomaly,
-                severity: Severity::Medium,
-                title: "High Bandwidth Usage".to_string(),
                 description: format!("Bandwidth usage at {:.1} Mbps is unusually high", 
                     network.bandwidth_usage_mbps),
                 data_source: "network_activity_metadata".to_string(),
@@ -442,18 +482,13 @@ impl FlagDetector {
                     "traffic_type": network.traffic_type,
                 }),
                 confidence: 0.8,
+                ..Flag::new(session_id, FlagType::SystemAnomaly, Severity::Medium, "High Bandwidth Usage")
             });
         }
         
         // High packet loss
         if network.packet_loss_rate > 0.05 {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::PerformanceIssue,
-                severity: Severity::Medium,
-                title: "High Network Packet Loss".to_string(),
                 description: format!("Packet loss rate at {:.1}% indicates network issues", 
                     network.packet_loss_rate * 100.0),
                 data_source: "network_activity_metadata".to_string(),
@@ -462,6 +497,22 @@ impl FlagDetector {
                     "connection_stability": network.connection_stability,
                 }),
                 confidence: 0.85,
+                ..Flag::new(session_id, FlagType::PerformanceIssue, Severity::Medium, "High Network Packet Loss")
+            });
+        }
+        
+        // A tunnel brought up mid-session can bypass the managed network's monitoring
+        if let Some(routing) = network.routing.as_ref().filter(|r| self.vpn_alerts && r.vpn_connected_mid_session) {
+            flags.push(Flag {
+                description: format!("A VPN connection ({}) was established during the session",
+                    routing.vpn_adapters.join(", ")),
+                data_source: "network_activity_metadata".to_string(),
+                metrics: json!({
+                    "vpn_adapters": routing.vpn_adapters,
+                    "proxy": routing.proxy,
+                }),
+                confidence: 0.8,
+                ..Flag::new(session_id, FlagType::SecurityConcern, Severity::Medium, "VPN Connected Mid-Session")
             });
         }
         
@@ -475,12 +526,6 @@ impl FlagDetector {
         // High workflow friction
         if screen.workflow_friction_score > 0.7 {
             flags.push(Flag {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                session_id: session_id.to_string(),
-                flag_type: FlagType::ProductivityAlert,
-                severity: Severity::Medium,
-                title: "High Workflow Friction".to_string(),
                 description: format!("Workflow friction score of {:.2} indicates UI/UX issues", 
                     screen.workflow_friction_score),
                 data_source: "screen_interactions".to_string(),
@@ -489,9 +534,13 @@ impl FlagDetector {
                     "click_count": screen.click_count,
                 }),
                 confidence: 0.7,
+                ..Flag::new(session_id, FlagType::ProductivityAlert, Severity::Medium, "High Workflow Friction")
             });
         }
         
         flags
     }
 }
+
+#[cfg(test)]
+mod tests;
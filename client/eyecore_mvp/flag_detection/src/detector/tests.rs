@@ -0,0 +1,341 @@
+use crate::detector::FlagDetector;
+use crate::models::{FlagType, PresenceState, Severity};
+use crate::testing::{assert_flags, baseline, flag, ScenarioBuilder};
+use crate::thresholds::Thresholds;
+use std::sync::{Arc, RwLock};
+
+#[tokio::test]
+async fn calm_baseline_raises_nothing() {
+    assert_flags(&ScenarioBuilder::new().flags().await, &[]);
+    assert_flags(&ScenarioBuilder::new().core_only().flags().await, &[]);
+}
+
+// ===== System metrics =====
+
+#[tokio::test]
+async fn high_cpu_is_a_performance_issue() {
+    let flags = ScenarioBuilder::new().cpu(92.0).flags().await;
+    assert_flags(&flags, &["High CPU Usage"]);
+    let cpu = flag(&flags, "High CPU Usage");
+    assert_eq!(cpu.flag_type, FlagType::PerformanceIssue);
+    assert_eq!(cpu.severity, Severity::High);
+    assert_eq!(cpu.data_source, "system_metrics");
+}
+
+#[tokio::test]
+async fn cpu_above_95_is_critical() {
+    let flags = ScenarioBuilder::new().cpu(97.0).flags().await;
+    assert_eq!(flag(&flags, "High CPU Usage").severity, Severity::Critical);
+}
+
+#[tokio::test]
+async fn cpu_at_threshold_is_not_flagged() {
+    assert_flags(&ScenarioBuilder::new().cpu(90.0).flags().await, &[]);
+}
+
+#[tokio::test]
+async fn cpu_is_ignored_while_warming_up() {
+    let flags = ScenarioBuilder::new().cpu(99.0).warming_up("system_metrics.cpu_usage").flags().await;
+    assert_flags(&flags, &[]);
+}
+
+#[tokio::test]
+async fn high_cpu_names_the_top_process() {
+    let flags = ScenarioBuilder::new().cpu(92.0).top_process("chrome", 61.5).flags().await;
+    let cpu = flag(&flags, "High CPU Usage");
+    assert_eq!(cpu.metrics["top_process"]["name"], "chrome");
+    assert!(cpu.description.ends_with("(top process: chrome at 61.5%)"));
+    
+    // Without a process list the flag is unchanged
+    let flags = ScenarioBuilder::new().cpu(92.0).flags().await;
+    assert!(flag(&flags, "High CPU Usage").metrics.get("top_process").is_none());
+}
+
+#[tokio::test]
+async fn high_memory_is_a_performance_issue() {
+    let flags = ScenarioBuilder::new().memory(88.0).flags().await;
+    assert_flags(&flags, &["High Memory Usage"]);
+    assert_eq!(flag(&flags, "High Memory Usage").severity, Severity::High);
+
+    let flags = ScenarioBuilder::new().memory(96.0).flags().await;
+    assert_eq!(flag(&flags, "High Memory Usage").severity, Severity::Critical);
+}
+
+// ===== Input and focus =====
+
+#[tokio::test]
+async fn prolonged_idle_is_flagged() {
+    let flags = ScenarioBuilder::new().idle_secs(600).flags().await;
+    assert_flags(&flags, &["Prolonged Idle Time"]);
+    assert_eq!(flag(&flags, "Prolonged Idle Time").flag_type, FlagType::ProductivityAlert);
+}
+
+#[tokio::test]
+async fn idle_is_expected_during_meetings() {
+    assert_flags(&ScenarioBuilder::new().idle_secs(600).in_meeting().flags().await, &[]);
+}
+
+#[tokio::test]
+async fn idle_while_watching_or_reading_is_not_flagged() {
+    let flags = ScenarioBuilder::new().presence(PresenceState::PassiveConsuming, 900).flags().await;
+    assert_flags(&flags, &[]);
+}
+
+#[tokio::test]
+async fn away_users_are_flagged_as_away_but_not_for_focus() {
+    let flags = ScenarioBuilder::new().presence(PresenceState::Away, 600).focus(0.1).flags().await;
+    assert_flags(&flags, &["Prolonged Idle Time"]);
+    let idle = flag(&flags, "Prolonged Idle Time");
+    assert!(idle.description.starts_with("User away"));
+    assert_eq!(idle.metrics["presence"], "away");
+}
+
+#[tokio::test]
+async fn active_system_without_input_is_a_behavior_anomaly() {
+    let flags = ScenarioBuilder::new().input(0, 0).idle_secs(5).flags().await;
+    assert_flags(&flags, &["No User Input Detected"]);
+    assert_eq!(flag(&flags, "No User Input Detected").flag_type, FlagType::BehaviorAnomaly);
+}
+
+#[tokio::test]
+async fn no_input_while_idle_is_not_an_anomaly() {
+    // Idle users are covered by the idle rule, not the "no input" rule
+    let flags = ScenarioBuilder::new().input(0, 0).idle_secs(60).flags().await;
+    assert_flags(&flags, &[]);
+}
+
+#[tokio::test]
+async fn low_focus_is_flagged_outside_meetings() {
+    assert_flags(&ScenarioBuilder::new().focus(0.1).flags().await, &["Low Focus Level"]);
+    assert_flags(&ScenarioBuilder::new().focus(0.1).in_meeting().flags().await, &[]);
+}
+
+#[tokio::test]
+async fn productivity_alerts_name_the_distraction() {
+    let flags = ScenarioBuilder::new()
+        .focus(0.1)
+        .cpu(92.0)
+        .app_switch("Code.exe", "Discord.exe")
+        .notification("Slack")
+        .flags()
+        .await;
+    let low_focus = flag(&flags, "Low Focus Level");
+    assert_eq!(low_focus.metrics["attribution"]["summary"], "switched to Discord.exe, notification from Slack");
+    assert_eq!(low_focus.metrics["attribution"]["app_switches"][0]["from_app"], "Code.exe");
+    assert!(low_focus.description.ends_with("(likely cause: switched to Discord.exe, notification from Slack)"));
+    
+    // Only productivity alerts are attributed
+    assert!(flag(&flags, "High CPU Usage").metrics.get("attribution").is_none());
+}
+
+#[tokio::test]
+async fn alerts_without_distraction_sources_are_unchanged() {
+    let flags = ScenarioBuilder::new().focus(0.1).flags().await;
+    assert!(flag(&flags, "Low Focus Level").metrics.get("attribution").is_none());
+}
+
+#[tokio::test]
+async fn excessive_context_switching_is_flagged() {
+    assert_flags(&ScenarioBuilder::new().context_switches(51).flags().await, &["Excessive Context Switching"]);
+    assert_flags(&ScenarioBuilder::new().context_switches(50).flags().await, &[]);
+}
+
+// ===== Keystroke and mouse dynamics =====
+
+#[tokio::test]
+async fn keystroke_stress_and_fatigue_are_health_concerns() {
+    let flags = ScenarioBuilder::new().stress(0.9).typing_fatigue(0.9).flags().await;
+    assert_flags(&flags, &["High Stress Detected", "Fatigue Detected"]);
+    assert!(flags.iter().all(|f| f.flag_type == FlagType::HealthConcern));
+}
+
+#[tokio::test]
+async fn high_typing_error_rate_is_flagged() {
+    let flags = ScenarioBuilder::new().typing_errors(0.3).flags().await;
+    assert_flags(&flags, &["High Typing Error Rate"]);
+    assert_eq!(flag(&flags, "High Typing Error Rate").severity, Severity::Low);
+}
+
+#[tokio::test]
+async fn mouse_fatigue_is_flagged() {
+    assert_flags(&ScenarioBuilder::new().mouse_fatigue(0.9).flags().await, &["Mouse Movement Fatigue"]);
+}
+
+#[tokio::test]
+async fn calibrated_baseline_raises_limits_for_a_high_normal() {
+    // This user's typing already reads as stressed during calm calibration: limit 0.85 + (0.85 - 0.75)
+    let calm_but_high = baseline(60, &[("keystroke_dynamics.stress_indicator", 0.75, 0.85)]);
+    assert_flags(&ScenarioBuilder::new().stress(0.9).flags_with_baseline(&calm_but_high).await, &[]);
+    let flags = ScenarioBuilder::new().stress(0.97).flags_with_baseline(&calm_but_high).await;
+    assert_flags(&flags, &["High Stress Detected"]);
+    assert!((flag(&flags, "High Stress Detected").metrics["limit"].as_f64().unwrap() - 0.95).abs() < 1e-4);
+    
+    // Too few samples, or a normal below the default, keep the default limit
+    let short = baseline(5, &[("keystroke_dynamics.stress_indicator", 0.75, 0.85)]);
+    assert_flags(&ScenarioBuilder::new().stress(0.9).flags_with_baseline(&short).await, &["High Stress Detected"]);
+    let relaxed = baseline(60, &[("mouse_dynamics.fatigue_indicator", 0.2, 0.3)]);
+    assert_flags(&ScenarioBuilder::new().mouse_fatigue(0.9).flags_with_baseline(&relaxed).await, &["Mouse Movement Fatigue"]);
+}
+
+#[tokio::test]
+async fn erratic_mouse_needs_both_rough_paths_and_hesitation() {
+    assert_flags(&ScenarioBuilder::new().mouse_path(0.1, 20).flags().await, &["Erratic Mouse Movement"]);
+    assert_flags(&ScenarioBuilder::new().mouse_path(0.1, 5).flags().await, &[]);
+    assert_flags(&ScenarioBuilder::new().mouse_path(0.9, 20).flags().await, &[]);
+}
+
+// ===== Voice and camera =====
+
+#[tokio::test]
+async fn negative_sentiment_is_flagged() {
+    assert_flags(&ScenarioBuilder::new().sentiment(-0.8).flags().await, &["Negative Emotional State"]);
+}
+
+#[tokio::test]
+async fn stressed_or_frustrated_voice_is_flagged() {
+    for emotion in ["stressed", "frustrated"] {
+        let flags = ScenarioBuilder::new().voice_emotion(emotion).flags().await;
+        assert_flags(&flags, &["Stress Detected in Voice"]);
+    }
+}
+
+#[tokio::test]
+async fn poor_posture_and_gaze_away_are_flagged() {
+    assert_flags(&ScenarioBuilder::new().posture(0.2).flags().await, &["Poor Posture Detected"]);
+    let flags = ScenarioBuilder::new().gaze("away").flags().await;
+    assert_flags(&flags, &["User Not Looking at Screen"]);
+    assert_eq!(flag(&flags, "User Not Looking at Screen").flag_type, FlagType::ProductivityAlert);
+}
+
+#[tokio::test]
+async fn presence_only_camera_raises_no_posture_or_gaze_flags() {
+    assert_flags(&ScenarioBuilder::new().camera_presence_only().flags().await, &[]);
+}
+
+// ===== Network, screen and system events =====
+
+#[tokio::test]
+async fn high_bandwidth_is_a_system_anomaly() {
+    let flags = ScenarioBuilder::new().bandwidth_mbps(800.0).flags().await;
+    assert_flags(&flags, &["High Bandwidth Usage"]);
+    assert_eq!(flag(&flags, "High Bandwidth Usage").flag_type, FlagType::SystemAnomaly);
+}
+
+#[tokio::test]
+async fn bandwidth_is_ignored_while_warming_up() {
+    let flags = ScenarioBuilder::new()
+        .bandwidth_mbps(800.0)
+        .warming_up("network_activity_metadata.bandwidth_usage_mbps")
+        .flags()
+        .await;
+    assert_flags(&flags, &[]);
+}
+
+#[tokio::test]
+async fn packet_loss_is_flagged() {
+    assert_flags(&ScenarioBuilder::new().packet_loss(0.1).flags().await, &["High Network Packet Loss"]);
+}
+
+#[tokio::test]
+async fn workflow_friction_is_flagged() {
+    assert_flags(&ScenarioBuilder::new().friction(0.9).flags().await, &["High Workflow Friction"]);
+}
+
+#[tokio::test]
+async fn peripheral_connections_are_not_flagged() {
+    // Device events are not measured by the collector, so nothing is raised from them
+    let flags = ScenarioBuilder::new().system_event("peripheral_connect", "usb_device").flags().await;
+    assert_flags(&flags, &[]);
+}
+
+#[tokio::test]
+async fn vpn_connecting_mid_session_is_flagged_only_when_enabled() {
+    assert_flags(&ScenarioBuilder::new().vpn_connected("wg0").flags().await, &[]);
+
+    let detector = FlagDetector::new().with_vpn_alerts(true);
+    let flags = ScenarioBuilder::new().vpn_connected("wg0").flags_with(&detector).await;
+    assert_flags(&flags, &["VPN Connected Mid-Session"]);
+    let vpn = flag(&flags, "VPN Connected Mid-Session");
+    assert_eq!(vpn.flag_type, FlagType::SecurityConcern);
+    assert_eq!(vpn.metrics["vpn_adapters"][0], "wg0");
+
+    assert_flags(&ScenarioBuilder::new().flags_with(&detector).await, &[]);
+}
+
+// ===== Thresholds =====
+
+#[tokio::test]
+async fn thresholds_from_detector_toml_change_the_flags() {
+    let thresholds = Thresholds::parse(
+        "[cpu]\nthreshold = 70.0\nescalate = { Critical = 80.0 }\nconfidence = 0.5\n\n[focus]\nseverity = \"Low\"",
+    )
+    .unwrap();
+    let detector = FlagDetector::new().with_thresholds(Arc::new(RwLock::new(thresholds)));
+
+    let flags = ScenarioBuilder::new().cpu(75.0).focus(0.1).flags_with(&detector).await;
+    assert_flags(&flags, &["High CPU Usage", "Low Focus Level"]);
+    assert_eq!(flag(&flags, "High CPU Usage").severity, Severity::High);
+    assert_eq!(flag(&flags, "High CPU Usage").confidence, 0.5);
+    assert_eq!(flag(&flags, "Low Focus Level").severity, Severity::Low);
+
+    let flags = ScenarioBuilder::new().cpu(85.0).flags_with(&detector).await;
+    assert_eq!(flag(&flags, "High CPU Usage").severity, Severity::Critical);
+}
+
+#[tokio::test]
+async fn reloaded_thresholds_apply_to_the_next_snapshot() {
+    let thresholds = Arc::new(RwLock::new(Thresholds::default()));
+    let detector = FlagDetector::new().with_thresholds(Arc::clone(&thresholds));
+    assert_flags(&ScenarioBuilder::new().idle_secs(200).flags_with(&detector).await, &[]);
+
+    *thresholds.write().unwrap() = Thresholds::parse("[idle]\nthreshold = 120").unwrap();
+    let flags = ScenarioBuilder::new().idle_secs(200).flags_with(&detector).await;
+    assert_flags(&flags, &["Prolonged Idle Time"]);
+    assert_eq!(detector.thresholds().idle.threshold, 120.0);
+}
+
+// ===== Coverage =====
+
+/// A scenario raising `flag_type`, and the detector to run it through. The match has no wildcard, so a new FlagType
+/// does not compile until it has a scenario (and a detector rule) here, or is
+/// explicitly listed as one the detector never raises.
+fn scenario_for(flag_type: &FlagType) -> Option<(ScenarioBuilder, FlagDetector)> {
+    let default = |scenario| Some((scenario, FlagDetector::new()));
+    match flag_type {
+        FlagType::SystemAnomaly => default(ScenarioBuilder::new().bandwidth_mbps(800.0)),
+        FlagType::BehaviorAnomaly => default(ScenarioBuilder::new().input(0, 0)),
+        FlagType::PerformanceIssue => default(ScenarioBuilder::new().cpu(95.0)),
+        // Only raised by the opt-in VPN rule
+        FlagType::SecurityConcern => {
+            Some((ScenarioBuilder::new().vpn_connected("wg0"), FlagDetector::new().with_vpn_alerts(true)))
+        }
+        FlagType::HealthConcern => default(ScenarioBuilder::new().stress(0.9)),
+        FlagType::ProductivityAlert => default(ScenarioBuilder::new().idle_secs(600)),
+        FlagType::UserBookmark => None,
+    }
+}
+
+#[tokio::test]
+async fn every_flag_type_is_reachable() {
+    let all = [
+        FlagType::SystemAnomaly,
+        FlagType::BehaviorAnomaly,
+        FlagType::PerformanceIssue,
+        FlagType::SecurityConcern,
+        FlagType::HealthConcern,
+        FlagType::ProductivityAlert,
+        FlagType::UserBookmark,
+    ];
+    for flag_type in &all {
+        let Some((scenario, detector)) = scenario_for(flag_type) else {
+            continue;
+        };
+        let flags = scenario.flags_with(&detector).await;
+        assert!(
+            flags.iter().any(|f| &f.flag_type == flag_type),
+            "no scenario raised {:?}, got {:?}",
+            flag_type,
+            flags.iter().map(|f| &f.title).collect::<Vec<_>>()
+        );
+    }
+}
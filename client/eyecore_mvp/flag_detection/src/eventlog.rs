@@ -0,0 +1,193 @@
+//! Critical and High flags in a dedicated Windows Event Log ("EyeCore"), for monitoring that
+//! already watches event logs
+
+use crate::models::{Flag, FlagType, Severity};
+
+pub const LOG_NAME: &str = "EyeCore";
+pub const SOURCE_NAME: &str = "EyeCore-FlagDetection";
+
+/// Stable event ID per flag type, so monitoring rules can match on it
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn event_id(flag_type: &FlagType) -> u32 {
+    match flag_type {
+        FlagType::SystemAnomaly => 1001,
+        FlagType::BehaviorAnomaly => 1002,
+        FlagType::PerformanceIssue => 1003,
+        FlagType::SecurityConcern => 1004,
+        FlagType::HealthConcern => 1005,
+        FlagType::ProductivityAlert => 1006,
+        FlagType::UserBookmark => 1007,
+    }
+}
+
+/// Event category per flag type (1-based, as Event Viewer expects)
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn category(flag_type: &FlagType) -> u16 {
+    (event_id(flag_type) - 1000) as u16
+}
+
+pub fn should_log(flag: &Flag) -> bool {
+    matches!(flag.severity, Severity::Critical | Severity::High)
+}
+
+/// Event text: title and description first, then the fields an operator needs to follow up
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn message(flag: &Flag) -> String {
+    let severity = match &flag.raw_severity {
+        Some(raw) => format!("{:?} (rule: {:?})", flag.severity, raw),
+        None => format!("{:?}", flag.severity),
+    };
+    format!(
+        "{}\r\n\r\n{}\r\n\r\nSeverity: {}\r\nFlag type: {}\r\nSession: {}\r\nFlag ID: {}\r\nTime: {}\r\nData source: {}\r\nMetrics: {}",
+        flag.title,
+        flag.description,
+        severity,
+        flag.flag_type.name(),
+        flag.session_id,
+        flag.id,
+        flag.timestamp.to_rfc3339(),
+        flag.data_source,
+        flag.metrics
+    )
+}
+
+/// Create the EyeCore log and its event source (once, as administrator). The source uses the
+/// .NET message file, which renders each event's text as-is.
+pub fn register() -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        let output = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                &format!(
+                    "if (-not [System.Diagnostics.EventLog]::SourceExists('{source}')) {{ New-EventLog -LogName '{log}' -Source '{source}' }}",
+                    source = SOURCE_NAME,
+                    log = LOG_NAME
+                ),
+            ])
+            .output()
+            .map_err(|e| format!("Failed to run powershell: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to register the event source (run as administrator): {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err("The Windows Event Log is only available on Windows".to_string())
+    }
+}
+
+/// Handle to the registered event source
+pub struct EventLog {
+    #[cfg(target_os = "windows")]
+    handle: windows::Win32::Foundation::HANDLE,
+}
+
+impl EventLog {
+    pub fn open() -> Result<Self, String> {
+        #[cfg(target_os = "windows")]
+        {
+            use windows::core::{HSTRING, PCWSTR};
+            use windows::Win32::System::EventLog::RegisterEventSourceW;
+
+            let handle = unsafe { RegisterEventSourceW(PCWSTR::null(), &HSTRING::from(SOURCE_NAME)) }
+                .map_err(|e| format!("Failed to open event source {}: {}", SOURCE_NAME, e))?;
+            Ok(EventLog { handle })
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            Err("The Windows Event Log is only available on Windows".to_string())
+        }
+    }
+
+    pub fn write(&self, flag: &Flag) -> Result<(), String> {
+        #[cfg(target_os = "windows")]
+        {
+            use windows::core::{HSTRING, PCWSTR};
+            use windows::Win32::Foundation::PSID;
+            use windows::Win32::System::EventLog::{ReportEventW, EVENTLOG_ERROR_TYPE, EVENTLOG_WARNING_TYPE};
+
+            let event_type = match flag.severity {
+                Severity::Critical => EVENTLOG_ERROR_TYPE,
+                _ => EVENTLOG_WARNING_TYPE,
+            };
+            let text = HSTRING::from(message(flag));
+            unsafe {
+                ReportEventW(
+                    self.handle,
+                    event_type,
+                    category(&flag.flag_type),
+                    event_id(&flag.flag_type),
+                    PSID::default(),
+                    0,
+                    Some(&[PCWSTR(text.as_ptr())]),
+                    None,
+                )
+            }
+            .map_err(|e| format!("Failed to write event: {}", e))
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = flag;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for EventLog {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = windows::Win32::System::EventLog::DeregisterEventSource(self.handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{flag, ScenarioBuilder};
+
+    #[test]
+    fn event_ids_are_distinct_per_flag_type() {
+        let all = [
+            FlagType::SystemAnomaly,
+            FlagType::BehaviorAnomaly,
+            FlagType::PerformanceIssue,
+            FlagType::SecurityConcern,
+            FlagType::HealthConcern,
+            FlagType::ProductivityAlert,
+            FlagType::UserBookmark,
+        ];
+        let mut ids: Vec<u32> = all.iter().map(event_id).collect();
+        ids.dedup();
+        assert_eq!(ids.len(), all.len());
+        assert!(all.iter().all(|t| category(t) >= 1));
+    }
+
+    #[tokio::test]
+    async fn only_critical_and_high_flags_are_logged() {
+        let flags = ScenarioBuilder::new().cpu(97.0).memory(88.0).typing_errors(0.3).flags().await;
+        assert!(should_log(flag(&flags, "High CPU Usage")));
+        assert!(should_log(flag(&flags, "High Memory Usage")));
+        assert!(!should_log(flag(&flags, "High Typing Error Rate")));
+    }
+
+    #[tokio::test]
+    async fn message_carries_the_follow_up_fields() {
+        let flags = ScenarioBuilder::new().cpu(97.0).flags().await;
+        let cpu = flag(&flags, "High CPU Usage");
+        let text = message(cpu);
+        assert!(text.starts_with("High CPU Usage\r\n"));
+        assert!(text.contains("Severity: Critical\r\n"));
+        assert!(text.contains(&format!("Flag ID: {}", cpu.id)));
+    }
+}
@@ -1,10 +1,16 @@
 use crate::models::Flag;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use log::{info, error};
 use serde_json::to_string_pretty;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
+/// Archived flags live in `<flags>/archive/flags_<YYYY-MM>.ndjson`, one flag per line, by the month
+/// they were raised in; the collector's API reads them alongside the live files
+pub const ARCHIVE_DIR: &str = "archive";
+
 pub struct FlagStorage {
     flags_dir: PathBuf,
 }
@@ -65,8 +71,125 @@ impl FlagStorage {
         Ok(all_flags.into_iter().filter(|f| f.session_id == session_id).collect())
     }
     
+    /// Move flags raised before `cutoff` into their monthly archive and remove their files; returns
+    /// the number archived. Flags already in the archive (from a run that stopped before removing
+    /// them) are not written twice; unreadable files are logged and left in place.
+    pub async fn archive_older_than(&self, cutoff: DateTime<Utc>) -> std::io::Result<usize> {
+        let mut by_month: BTreeMap<String, Vec<(PathBuf, String, String)>> = BTreeMap::new();
+        let mut entries = fs::read_dir(&self.flags_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            // Kept as written, so flags the collector raised (bookmarks) archive unchanged
+            let flag = match fs::read_to_string(&path).await.map_err(|e| e.to_string()).and_then(|content| {
+                serde_json::from_str::<serde_json::Value>(&content).map_err(|e| e.to_string())
+            }) {
+                Ok(flag) => flag,
+                Err(e) => {
+                    error!("Not archiving unreadable flag file {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            let timestamp = flag["timestamp"].as_str().and_then(|t| t.parse::<DateTime<Utc>>().ok());
+            let (Some(timestamp), Some(id)) = (timestamp, flag["id"].as_str()) else {
+                error!("Not archiving flag file {:?}: no id or timestamp", path);
+                continue;
+            };
+            if timestamp < cutoff {
+                let month = timestamp.format("%Y-%m").to_string();
+                by_month.entry(month).or_default().push((path, id.to_string(), flag.to_string()));
+            }
+        }
+
+        let mut archived = 0;
+        for (month, flags) in by_month {
+            let archive = archive_path(&self.flags_dir, &month);
+            let written = tokio::task::spawn_blocking(move || append_to_archive(&archive, flags))
+                .await
+                .map_err(std::io::Error::other)??;
+            for path in written {
+                fs::remove_file(&path).await?;
+                archived += 1;
+            }
+        }
+        if archived > 0 {
+            info!("🗄️ Archived {} flags", archived);
+        }
+        Ok(archived)
+    }
+
     /// Get path to flags directory
     pub fn get_flags_dir(&self) -> &Path {
         &self.flags_dir
     }
 }
+
+/// `<flags>/archive/flags_<YYYY-MM>.ndjson`
+pub fn archive_path(flags_dir: &Path, month: &str) -> PathBuf {
+    flags_dir.join(ARCHIVE_DIR).join(format!("flags_{}.ndjson", month))
+}
+
+/// Append the flags not yet in `archive` and flush it to disk; returns the files now archived
+fn append_to_archive(archive: &Path, flags: Vec<(PathBuf, String, String)>) -> std::io::Result<Vec<PathBuf>> {
+    if let Some(dir) = archive.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let archived_ids: HashSet<String> = match std::fs::read_to_string(archive) {
+        Ok(content) => content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .filter_map(|flag| flag["id"].as_str().map(str::to_string))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashSet::new(),
+        Err(e) => return Err(e),
+    };
+    let mut lines = String::new();
+    let mut paths = Vec::new();
+    for (path, id, flag) in flags {
+        if !archived_ids.contains(&id) {
+            lines.push_str(&flag);
+            lines.push('\n');
+        }
+        paths.push(path);
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(archive)?;
+    file.write_all(lines.as_bytes())?;
+    file.sync_data()?;
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_flag(dir: &Path, id: &str, timestamp: &str) {
+        let flag = json!({ "id": id, "timestamp": timestamp, "title": "Test" });
+        std::fs::write(dir.join(format!("flag_{}.json", id)), flag.to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn archives_old_flags_by_month_once() {
+        let dir = std::env::temp_dir().join(format!("flag_archive_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_flag(&dir, "a0000000", "2025-09-30T23:00:00Z");
+        write_flag(&dir, "b0000000", "2025-10-01T08:00:00Z");
+        write_flag(&dir, "c0000000", "2025-10-20T08:00:00Z");
+        let storage = FlagStorage::new(dir.to_str().unwrap());
+        let cutoff = "2025-10-10T00:00:00Z".parse().unwrap();
+
+        assert_eq!(storage.archive_older_than(cutoff).await.unwrap(), 2);
+        assert!(dir.join("flag_c0000000.json").exists());
+        let september = std::fs::read_to_string(archive_path(&dir, "2025-09")).unwrap();
+        assert_eq!(september.lines().count(), 1);
+        assert!(september.contains("a0000000"));
+
+        // Left behind by an interrupted run: archived already, so only the file goes
+        write_flag(&dir, "b0000000", "2025-10-01T08:00:00Z");
+        assert_eq!(storage.archive_older_than(cutoff).await.unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(archive_path(&dir, "2025-10")).unwrap().lines().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,15 @@
+//! Flag detection rules, severity policy and flag outputs, shared by the watcher binary, EyeCore's
+//! in-process detection and the Python bindings in `../eyecore_py`
+
+pub mod models;
+pub mod detector;
+pub mod flag_storage;
+pub mod pipeline;
+pub mod policy;
+pub mod thresholds;
+pub mod siem;
+pub mod eventlog;
+pub mod tail;
+pub mod sealed;
+#[cfg(test)]
+mod testing;
@@ -1,36 +1,39 @@
-mod models;
-mod detector;
-mod flag_storage;
-
+use flag_detection::{eventlog, models, pipeline, sealed, tail};
 use log::{info, error};
 use notify::{Watcher, RecursiveMode, Event, EventKind};
-use std::path::PathBuf;
+use notify::event::ModifyKind;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
     
-    info!("🚩 Flag Detection System Starting...");
-    
-    // Initialize flag storage
-    let flag_storage = Arc::new(RwLock::new(
-        flag_storage::FlagStorage::new("../data/flags")
-    ));
-    
-    if let Err(e) = flag_storage.write().await.initialize().await {
-        error!("Failed to initialize flag storage: {}", e);
+    // One-off setup of the Windows Event Log channel (as administrator)
+    if std::env::args().nth(1).as_deref() == Some("register-eventlog") {
+        match eventlog::register() {
+            Ok(()) => println!("Event source {} registered in the {} log", eventlog::SOURCE_NAME, eventlog::LOG_NAME),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
         return;
     }
     
-    // Initialize detector with thresholds
-    let detector = Arc::new(detector::FlagDetector::new());
+    info!("🚩 Flag Detection System Starting...");
     
-    info!("✓ Flag detector initialized");
+    // Detector, severity policy and flag outputs, configured from FLAG_* variables
+    let pipeline = match pipeline::Pipeline::from_env("../data/flags", reqwest::Client::new()).await {
+        Ok(pipeline) => Arc::new(pipeline),
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
     
-    // Watch for new data files in ../data/timeslots
-    let data_dir = PathBuf::from("../data/timeslots");
+    // Watch for new data files in ../data/<device>/<YYYY>/<MM>/<DD>/timeslots
+    let data_dir = PathBuf::from("../data");
     
     if !data_dir.exists() {
         error!("Data directory does not exist: {:?}", data_dir);
@@ -40,15 +43,24 @@ async fn main() {
     
     info!("📂 Watching directory: {:?}", data_dir);
     
+    // Snapshot logs are followed from their current end; lines already in them were analyzed before
+    let mut tail = tail::LogTail::new();
+    tail.skip_existing(&data_dir);
+    
     // Create file watcher
     let (tx, mut rx) = tokio::sync::mpsc::channel(100);
     
     let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
         match res {
             Ok(event) => {
+                // Files renamed into place were rewritten by the collector (key rotation, module
+                // expiry), not newly collected, so they are not analyzed again
+                if let EventKind::Modify(ModifyKind::Name(_)) = event.kind {
+                    return;
+                }
                 if let EventKind::Create(_) | EventKind::Modify(_) = event.kind {
                     for path in event.paths {
-                        if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                        if is_timeslot_file(&path) || tail::is_snapshot_log(&path) {
                             let _ = tx.blocking_send(path);
                         }
                     }
@@ -58,46 +70,36 @@ async fn main() {
         }
     }).expect("Failed to create watcher");
     
-    watcher.watch(&data_dir, RecursiveMode::NonRecursive)
+    watcher.watch(&data_dir, RecursiveMode::Recursive)
         .expect("Failed to watch directory");
     
     info!("✓ File watcher started");
     info!("🔍 Monitoring for anomalies in real-time...");
     
-    // Process incoming data files
+    // Process incoming data files and the lines appended to snapshot logs
     while let Some(file_path) = rx.recv().await {
-        info!("📄 New data file detected: {:?}", file_path);
+        if tail::is_snapshot_log(&file_path) {
+            let lines = match tail.read_new_lines(&file_path) {
+                Ok(lines) => lines,
+                Err(e) => {
+                    error!("Failed to read snapshot log {:?}: {}", file_path, e);
+                    continue;
+                }
+            };
+            for line in lines {
+                let pipeline = Arc::clone(&pipeline);
+                let file_path = file_path.clone();
+                tokio::spawn(async move { analyze(&pipeline, &line, &file_path).await });
+            }
+            continue;
+        }
         
-        let detector_clone = Arc::clone(&detector);
-        let storage_clone = Arc::clone(&flag_storage);
+        info!("📄 New data file detected: {:?}", file_path);
         
+        let pipeline = Arc::clone(&pipeline);
         tokio::spawn(async move {
-            // Read and analyze the data file
-            match tokio::fs::read_to_string(&file_path).await {
-                Ok(content) => {
-                    match serde_json::from_str::<models::EyeCoreDataFile>(&content) {
-                        Ok(data_file) => {
-                            // Detect anomalies
-                            match detector_clone.analyze_data(&data_file.data).await {
-                                Ok(flags) if !flags.is_empty() => {
-                                    info!("🚩 Found {} flags in data", flags.len());
-                                    
-                                    // Save flags to file
-                                    for flag in flags {
-                                        if let Err(e) = storage_clone.write().await.save_flag(&flag).await {
-                                            error!("Failed to save flag: {}", e);
-                                        }
-                                    }
-                                }
-                                Ok(_) => {
-                                    // No flags - data looks normal
-                                }
-                                Err(e) => error!("Flag detection failed: {}", e),
-                            }
-                        }
-                        Err(e) => error!("Failed to parse data file: {}", e),
-                    }
-                }
+            match read_snapshot_file(&file_path).await {
+                Ok(content) => analyze(&pipeline, &content, &file_path).await,
                 Err(e) => error!("Failed to read file: {}", e),
             }
         });
@@ -105,3 +107,54 @@ async fn main() {
     
     info!("🛑 Flag detection system shutting down");
 }
+
+/// Analyze one snapshot (a timeslot file or a snapshot log line) from `source` and record its flags
+async fn analyze(pipeline: &pipeline::Pipeline, content: &[u8], source: &Path) {
+    let data_file = match serde_json::from_slice::<models::EyeCoreDataFile>(content) {
+        Ok(data_file) => data_file,
+        Err(e) => {
+            error!("Failed to parse data file: {}", e);
+            return;
+        }
+    };
+    let baseline = device_baseline(source).await;
+    pipeline.analyze(&data_file.data, baseline.as_ref()).await;
+}
+
+/// Calibrated baseline of the device a snapshot belongs to; `<device>/baseline.json` sits five levels
+/// above `<device>/<YYYY>/<MM>/<DD>/timeslots/<file>`
+async fn device_baseline(snapshot: &Path) -> Option<models::Baseline> {
+    let device_dir = snapshot.ancestors().nth(5)?;
+    let content = tokio::fs::read_to_string(device_dir.join("baseline.json")).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Contents of a snapshot file, decrypted when the collector wrote it with encryption =
+/// "xchacha20poly1305" and decompressed when it wrote it with compression = "zstd"
+async fn read_snapshot_file(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut bytes = tokio::fs::read(path).await?;
+    let mut name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if sealed::is_encrypted(path) {
+        // The keyring sits in the device directory, next to baseline.json
+        let keyring = match std::env::var_os("FLAG_ENCRYPTION_KEY_FILE") {
+            Some(keyring) => PathBuf::from(keyring),
+            None => path.ancestors().nth(5).unwrap_or(Path::new(".")).join(sealed::DEFAULT_KEY_FILE),
+        };
+        bytes = sealed::open(&keyring, &bytes).map_err(std::io::Error::other)?;
+        name = name.trim_end_matches(sealed::ENCRYPTED_SUFFIX);
+    }
+    if name.ends_with(".zst") {
+        return zstd::decode_all(bytes.as_slice());
+    }
+    Ok(bytes)
+}
+
+/// Snapshot files (`.json`, `.json.zst` when compressed, with `.enc` added when encrypted) live in a
+/// `timeslots` directory inside each day shard
+fn is_timeslot_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.trim_end_matches(sealed::ENCRYPTED_SUFFIX))
+        .is_some_and(|n| n.ends_with(".json") || n.ends_with(".json.zst"))
+        && path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()) == Some("timeslots")
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
 
 /// Wrapper for EyeCore data files
 #[derive(Debug, Clone, Deserialize)]
@@ -40,6 +41,8 @@ pub struct EyeCoreData {
     pub timestamp: DateTime<Utc>,
     pub system_metrics: SystemMetrics,
     pub process_data: ProcessData,
+    #[serde(default)]
+    pub top_processes: Vec<ProcessUsage>,
     pub input_metrics: InputMetrics,
     pub network_metrics: NetworkMetrics,
     pub focus_metrics: FocusMetrics,
@@ -53,6 +56,114 @@ pub struct EyeCoreData {
     pub system_events: Option<SystemEvents>,
     pub mouse_dynamics: Option<MouseDynamics>,
     pub network_activity_metadata: Option<NetworkActivityMetadata>,
+    
+    #[serde(default)]
+    pub meeting: Option<MeetingStatus>,
+    
+    #[serde(default)]
+    pub focus_context: Option<FocusContext>,
+    
+    #[serde(default)]
+    pub presence: Option<Presence>,
+    
+    #[serde(default)]
+    pub provenance: BTreeMap<String, Provenance>,
+}
+
+impl EyeCoreData {
+    /// True when the collector behind `field` had not finished warming up
+    pub fn is_warming_up(&self, field: &str) -> bool {
+        self.provenance.get(field) == Some(&Provenance::WarmingUp)
+    }
+    
+    /// True when the client detected a call during this snapshot
+    pub fn in_meeting(&self) -> bool {
+        self.meeting.as_ref().is_some_and(|m| m.in_meeting)
+    }
+    
+    /// Location category of the Wi-Fi network the snapshot was taken on; None when not on Wi-Fi
+    pub fn network_category(&self) -> Option<NetworkCategory> {
+        self.network_activity_metadata.as_ref()?.wifi.as_ref().map(|wifi| wifi.category)
+    }
+    
+    /// The client's presence classification; None for clients that do not report one
+    pub fn presence_state(&self) -> Option<PresenceState> {
+        self.presence.as_ref().map(|p| p.state)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MeetingStatus {
+    pub in_meeting: bool,
+    pub confidence: f32,
+    pub signals: Vec<String>,
+    pub estimated_speakers: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceState {
+    Active,
+    PassiveConsuming,
+    Away,
+}
+
+/// Whether the user was at the device, reading or watching, or away
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Presence {
+    pub state: PresenceState,
+    pub idle_seconds: u32,
+    pub display_on: Option<bool>,
+    #[serde(default)]
+    pub media_apps: Option<Vec<String>>,
+}
+
+/// Possible distraction sources the client saw since its previous snapshot
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FocusContext {
+    #[serde(default)]
+    pub app_switches: Vec<AppSwitch>,
+    #[serde(default)]
+    pub notifications: Vec<NotificationArrival>,
+    #[serde(default)]
+    pub site_changes: Vec<SiteChange>,
+}
+
+impl FocusContext {
+    pub fn is_empty(&self) -> bool {
+        self.app_switches.is_empty() && self.notifications.is_empty() && self.site_changes.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AppSwitch {
+    pub timestamp: DateTime<Utc>,
+    pub from_app: Option<String>,
+    pub to_app: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NotificationArrival {
+    pub timestamp: DateTime<Utc>,
+    pub app: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SiteChange {
+    pub timestamp: DateTime<Utc>,
+    pub browser: String,
+    pub from_site: Option<String>,
+    pub to_site: String,
+}
+
+/// Origin of a collected value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provenance {
+    Measured,
+    Simulated,
+    WarmingUp,
+    Withheld,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -61,6 +172,10 @@ pub struct SystemMetrics {
     pub cpu_usage: f32,
     pub memory_usage: f32,
     pub disk_usage: f32,
+    #[serde(default)]
+    pub display_brightness: Option<f32>, // percent, where the display reports it
+    #[serde(default)]
+    pub ambient_light_lux: Option<f32>,  // only on devices with a light sensor
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -71,6 +186,17 @@ pub struct ProcessData {
     pub process_count: usize,
 }
 
+/// One of the busiest processes in a snapshot; `cpu_usage` is a percent of all cores
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_usage: f32,
+    pub memory_bytes: u64,
+    pub disk_read_bytes: u64,
+    pub disk_written_bytes: u64,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct InputMetrics {
     pub timestamp: DateTime<Utc>,
@@ -85,6 +211,10 @@ pub struct NetworkMetrics {
     pub bytes_sent: u64,
     pub bytes_received: u64,
     pub active_connections: usize,
+    #[serde(default)]
+    pub bytes_sent_per_sec: f64,
+    #[serde(default)]
+    pub bytes_received_per_sec: f64,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -117,6 +247,8 @@ pub struct CameraData {
     pub face_detected: bool,
     pub posture_score: f32,
     pub enabled: bool,
+    #[serde(default)]
+    pub presence_only: bool,    // only face_detected is real; posture, gaze and emotions are withheld
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -199,10 +331,84 @@ pub struct NetworkActivityMetadata {
     pub latency_avg_ms: f32,
     pub packet_loss_rate: f32,
     pub connection_stability: f32,
+    #[serde(default)]
+    pub routing: Option<NetworkRouting>,
+    #[serde(default)]
+    pub wifi: Option<WifiNetwork>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkCategory {
+    Home,
+    Office,
+    Public,
+    Unknown,
+}
+
+impl NetworkCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkCategory::Home => "home",
+            NetworkCategory::Office => "office",
+            NetworkCategory::Public => "public",
+            NetworkCategory::Unknown => "unknown",
+        }
+    }
+}
+
+/// Connected Wi-Fi network (hashed) and its location category
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WifiNetwork {
+    pub ssid_hash: String,
+    pub bssid_hash: Option<String>,
+    pub category: NetworkCategory,
+    #[serde(default)]
+    pub labeled: bool,
+}
+
+/// VPN adapters and proxy the client saw in use
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct NetworkRouting {
+    pub vpn_active: bool,
+    #[serde(default)]
+    pub vpn_adapters: Vec<String>,
+    #[serde(default)]
+    pub vpn_connected_mid_session: bool,
+    pub proxy: Option<String>,
+}
+
+/// Personal baseline recorded by the client's calibration session (`<device>/baseline.json`)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Baseline {
+    pub ended_at: DateTime<Utc>,
+    pub metrics: BTreeMap<String, MetricDistribution>,
+}
+
+/// Calibrated spread of one metric, keyed by snapshot path (e.g. "keystroke_dynamics.stress_indicator")
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MetricDistribution {
+    pub samples: usize,
+    pub p50: f32,
+    pub p90: f32,
+}
+
+impl Baseline {
+    /// Calibrations with fewer samples of a metric leave its default limit in place
+    pub const MIN_SAMPLES: usize = 20;
+    
+    /// Limit for a higher-is-worse metric: `default`, raised for users whose calibrated normal
+    /// already reaches it to the 90th percentile plus its distance from the median
+    pub fn upper_limit(&self, metric: &str, default: f32) -> f32 {
+        match self.metrics.get(metric) {
+            Some(d) if d.samples >= Self::MIN_SAMPLES => default.max(d.p90 + (d.p90 - d.p50)),
+            _ => default,
+        }
+    }
 }
 
 /// Flag types for detected anomalies
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum FlagType {
     SystemAnomaly,
@@ -211,6 +417,22 @@ pub enum FlagType {
     SecurityConcern,
     HealthConcern,
     ProductivityAlert,
+    UserBookmark, // set by the user in EyeCore (hotkey or POST /bookmark), never by the detector
+}
+
+impl FlagType {
+    /// Variant name, as used in flag files and severity policies
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlagType::SystemAnomaly => "SystemAnomaly",
+            FlagType::BehaviorAnomaly => "BehaviorAnomaly",
+            FlagType::PerformanceIssue => "PerformanceIssue",
+            FlagType::SecurityConcern => "SecurityConcern",
+            FlagType::HealthConcern => "HealthConcern",
+            FlagType::ProductivityAlert => "ProductivityAlert",
+            FlagType::UserBookmark => "UserBookmark",
+        }
+    }
 }
 
 /// A flag represents a detected anomaly in the data
@@ -226,10 +448,35 @@ pub struct Flag {
     pub data_source: String,
     pub metrics: serde_json::Value,
     pub confidence: f32,
+    // Severity the rule raised before the deployment's severity policy remapped it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_severity: Option<Severity>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Flag {
+    /// A flag raised now with a fresh id; rules fill in the description, data source, metrics
+    /// and confidence with struct update syntax
+    pub fn new(session_id: &str, flag_type: FlagType, severity: Severity, title: &str) -> Self {
+        Flag {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            session_id: session_id.to_string(),
+            flag_type,
+            severity,
+            title: title.to_string(),
+            description: String::new(),
+            data_source: String::new(),
+            metrics: serde_json::Value::Null,
+            confidence: 1.0,
+            raw_severity: None,
+        }
+    }
+}
+
+// Ordered lowest first
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Severity {
+    Informational,
     Low,
     Medium,
     High,
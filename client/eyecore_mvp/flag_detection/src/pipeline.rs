@@ -0,0 +1,168 @@
+//! Detection and the flag outputs: thresholds, severity policy, flag files, SIEM export and the
+//! Windows Event Log, all configured from `FLAG_*` environment variables. The watcher binary feeds it snapshots it
+//! finds on disk; EyeCore feeds it snapshots in-process as it collects them (`flag_detection =
+//! "in_process"`), so both modes raise the same flags.
+
+use chrono::Utc;
+use log::{error, info};
+use std::path::Path;
+use std::sync::{Arc, RwLock as StdRwLock};
+use tokio::sync::RwLock;
+
+use crate::detector::FlagDetector;
+use crate::eventlog::{self, EventLog};
+use crate::flag_storage::FlagStorage;
+use crate::models::{Baseline, EyeCoreData, Flag};
+use crate::policy::SeverityPolicy;
+use crate::siem::{SiemConfig, SiemExporter};
+use crate::thresholds::{self, SharedThresholds, Thresholds};
+
+/// Days a flag stays in the live flags directory unless FLAG_RETENTION_DAYS says otherwise
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+pub struct Pipeline {
+    detector: FlagDetector,
+    thresholds: SharedThresholds,
+    flag_storage: Arc<RwLock<FlagStorage>>,
+    policy: SeverityPolicy,
+    siem: Option<SiemExporter>,
+    event_log: Option<EventLog>,
+}
+
+impl Pipeline {
+    /// Set up from the environment, writing flags to `flags_dir`. Flags older than
+    /// FLAG_RETENTION_DAYS (default 30, 0 keeps every flag live) move to monthly archives in
+    /// `flags/archive`, checked hourly. SIEM export over HTTP uses `http`.
+    pub async fn from_env(flags_dir: &str, http: reqwest::Client) -> Result<Self, String> {
+        let flag_storage = Arc::new(RwLock::new(FlagStorage::new(flags_dir)));
+        flag_storage
+            .write()
+            .await
+            .initialize()
+            .await
+            .map_err(|e| format!("Failed to initialize flag storage: {}", e))?;
+
+        let retention_days = match std::env::var("FLAG_RETENTION_DAYS") {
+            Ok(value) => match value.trim().parse::<i64>() {
+                Ok(days) if days >= 0 => days,
+                _ => return Err(format!("FLAG_RETENTION_DAYS must be a number of days, got {:?}", value)),
+            },
+            Err(_) => DEFAULT_RETENTION_DAYS,
+        };
+        if retention_days > 0 {
+            let flag_storage = Arc::clone(&flag_storage);
+            tokio::spawn(async move {
+                let mut hourly = tokio::time::interval(std::time::Duration::from_secs(3600));
+                loop {
+                    hourly.tick().await;
+                    let cutoff = Utc::now() - chrono::Duration::days(retention_days);
+                    if let Err(e) = flag_storage.read().await.archive_older_than(cutoff).await {
+                        error!("Flag archival failed: {}", e);
+                    }
+                }
+            });
+            info!("✓ Archiving flags older than {} days", retention_days);
+        }
+
+        // Thresholds from detector.toml (FLAG_DETECTOR_CONFIG=path/to/detector.toml), reloaded on change
+        let thresholds_path = thresholds::path_from_env();
+        let thresholds: SharedThresholds = Arc::new(StdRwLock::new(Thresholds::load_or_default(&thresholds_path)?));
+        if thresholds_path.exists() {
+            info!("✓ Detector thresholds loaded from {:?}", thresholds_path);
+        }
+        if let Err(e) = thresholds::watch(thresholds_path, Arc::clone(&thresholds)) {
+            error!("{}; thresholds are not reloaded", e);
+        }
+        
+        // VPNs connecting mid-session are a concern on managed devices only (FLAG_VPN_ALERT=1)
+        let vpn_alerts = std::env::var("FLAG_VPN_ALERT").as_deref() == Ok("1");
+        let detector = FlagDetector::new()
+            .with_thresholds(Arc::clone(&thresholds))
+            .with_vpn_alerts(vpn_alerts);
+        info!("✓ Flag detector initialized");
+        if vpn_alerts {
+            info!("🔒 Flagging VPN connections made mid-session");
+        }
+
+        // Deployment-specific severities (FLAG_SEVERITY_POLICY=path/to/policy.json)
+        let policy = match std::env::var("FLAG_SEVERITY_POLICY") {
+            Ok(path) => {
+                let policy = SeverityPolicy::load(Path::new(&path))?;
+                info!("✓ Severity policy loaded: {}", policy.name.as_deref().unwrap_or(&path));
+                policy
+            }
+            Err(_) => SeverityPolicy::default(),
+        };
+
+        // Forward selected flags to a SIEM (FLAG_SIEM_CONFIG=path/to/siem.json)
+        let siem = match std::env::var("FLAG_SIEM_CONFIG") {
+            Ok(path) => {
+                let config = SiemConfig::load(Path::new(&path))?;
+                info!("✓ SIEM export enabled for {}", config.flag_types.join(", "));
+                Some(SiemExporter::start(config, http))
+            }
+            Err(_) => None,
+        };
+
+        // Critical/High flags into the Windows Event Log (FLAG_EVENT_LOG=1)
+        let event_log = if std::env::var("FLAG_EVENT_LOG").as_deref() == Ok("1") {
+            match EventLog::open() {
+                Ok(event_log) => {
+                    info!("✓ Writing Critical/High flags to the {} event log", eventlog::LOG_NAME);
+                    Some(event_log)
+                }
+                Err(e) => {
+                    error!("{} (run `flag_detection register-eventlog` as administrator first)", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(Pipeline { detector, thresholds, flag_storage, policy, siem, event_log })
+    }
+
+    /// The detector's thresholds, as last loaded from detector.toml
+    pub fn thresholds(&self) -> SharedThresholds {
+        Arc::clone(&self.thresholds)
+    }
+
+    /// The SIEM flags are forwarded to and which flags go there, when SIEM export is on
+    pub fn siem_recipient(&self) -> Option<String> {
+        let config = self.siem.as_ref()?.config();
+        Some(format!("SIEM at {} ({} flags)", config.destination(), config.flag_types.join(", ")))
+    }
+
+    /// Analyze one snapshot against the device's calibrated `baseline` and record its flags;
+    /// returns them with the policy's severities
+    pub async fn analyze(&self, data: &EyeCoreData, baseline: Option<&Baseline>) -> Vec<Flag> {
+        let network = data.network_category();
+        let mut flags = match self.detector.analyze_data_with_baseline(data, baseline).await {
+            Ok(flags) => flags,
+            Err(e) => {
+                error!("Flag detection failed: {}", e);
+                return Vec::new();
+            }
+        };
+        if !flags.is_empty() {
+            info!("🚩 Found {} flags in data", flags.len());
+        }
+
+        for flag in &mut flags {
+            self.policy.apply_on(flag, network);
+            if let Err(e) = self.flag_storage.write().await.save_flag(flag).await {
+                error!("Failed to save flag: {}", e);
+            }
+            if let Some(siem) = &self.siem {
+                siem.export(flag);
+            }
+            if let Some(event_log) = self.event_log.as_ref().filter(|_| eventlog::should_log(flag)) {
+                if let Err(e) = event_log.write(flag) {
+                    error!("{}", e);
+                }
+            }
+        }
+        flags
+    }
+}
@@ -0,0 +1,266 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::models::{Flag, FlagType, NetworkCategory, Severity};
+
+pub(crate) const FLAG_TYPE_NAMES: &[&str] = &[
+    "SystemAnomaly",
+    "BehaviorAnomaly",
+    "PerformanceIssue",
+    "SecurityConcern",
+    "HealthConcern",
+    "ProductivityAlert",
+    "UserBookmark",
+];
+
+/// Titles of the flags the detector raises, plus bookmarks; policy `rules` are keyed by them
+pub(crate) const RULE_TITLES: &[&str] = &[
+    "High CPU Usage",
+    "High Memory Usage",
+    "Prolonged Idle Time",
+    "No User Input Detected",
+    "Low Focus Level",
+    "Excessive Context Switching",
+    "High Stress Detected",
+    "Fatigue Detected",
+    "High Typing Error Rate",
+    "Mouse Movement Fatigue",
+    "Erratic Mouse Movement",
+    "Negative Emotional State",
+    "Stress Detected in Voice",
+    "Poor Posture Detected",
+    "User Not Looking at Screen",
+    "High Bandwidth Usage",
+    "High Network Packet Loss",
+    "VPN Connected Mid-Session",
+    "High Workflow Friction",
+    "User Bookmark",
+];
+
+const NETWORK_CATEGORY_NAMES: &[&str] = &["home", "office", "public", "unknown"];
+
+/// Deployment-specific severities, e.g. a school that only wants health signals for information:
+///
+/// ```json
+/// {
+///   "name": "school",
+///   "severities": { "Critical": "High" },
+///   "flag_types": { "HealthConcern": "Informational" },
+///   "rules": { "Prolonged Idle Time": "Medium" },
+///   "networks": { "public": { "flag_types": { "SecurityConcern": "High" } } }
+/// }
+/// ```
+///
+/// The most specific entry wins: `rules` (by flag title), then `flag_types`, then `severities`.
+/// `networks` holds the same three maps per Wi-Fi location category (`home`, `office`, `public`,
+/// `unknown`); they apply to snapshots taken on such a network and take precedence over the rest.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeverityPolicy {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    severities: HashMap<String, Severity>,
+    #[serde(default)]
+    flag_types: HashMap<String, Severity>,
+    #[serde(default)]
+    rules: HashMap<String, Severity>,
+    #[serde(default)]
+    networks: HashMap<String, SeverityPolicy>,
+}
+
+impl SeverityPolicy {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read severity policy {:?}: {}", path, e))?;
+        Self::parse(&content).map_err(|e| format!("Invalid severity policy {:?}: {}", path, e))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let policy: SeverityPolicy = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        policy.validate()?;
+        for (category, overrides) in &policy.networks {
+            if !NETWORK_CATEGORY_NAMES.contains(&category.as_str()) {
+                return Err(format!("unknown network category {:?} (expected one of {})", category, NETWORK_CATEGORY_NAMES.join(", ")));
+            }
+            if overrides.name.is_some() || !overrides.networks.is_empty() {
+                return Err(format!("network {:?} may only set severities, flag_types and rules", category));
+            }
+            overrides.validate().map_err(|e| format!("network {:?}: {}", category, e))?;
+        }
+        Ok(policy)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        // Catch typos that would otherwise silently leave a severity unchanged
+        if let Some(name) = self.flag_types.keys().find(|k| !FLAG_TYPE_NAMES.contains(&k.as_str())) {
+            return Err(format!("unknown flag type {:?} (expected one of {})", name, FLAG_TYPE_NAMES.join(", ")));
+        }
+        if let Some(title) = self.rules.keys().find(|k| !RULE_TITLES.contains(&k.as_str())) {
+            return Err(format!("unknown rule {:?} (expected a flag title: {})", title, RULE_TITLES.join(", ")));
+        }
+        for name in self.severities.keys() {
+            serde_json::from_value::<Severity>(serde_json::Value::String(name.clone()))
+                .map_err(|_| format!("unknown severity {:?}", name))?;
+        }
+        Ok(())
+    }
+
+    /// Deployment severity for a rule outcome
+    pub fn severity_for(&self, flag_type: &FlagType, title: &str, raw: &Severity) -> Severity {
+        self.severity_on(flag_type, title, raw, None)
+    }
+
+    /// Deployment severity for a rule outcome on a snapshot taken on a `network` Wi-Fi location
+    pub fn severity_on(&self, flag_type: &FlagType, title: &str, raw: &Severity, network: Option<NetworkCategory>) -> Severity {
+        network
+            .and_then(|category| self.networks.get(category.as_str()))
+            .and_then(|overrides| overrides.lookup(flag_type, title, raw))
+            .or_else(|| self.lookup(flag_type, title, raw))
+            .unwrap_or(raw)
+            .clone()
+    }
+
+    fn lookup(&self, flag_type: &FlagType, title: &str, raw: &Severity) -> Option<&Severity> {
+        self.rules
+            .get(title)
+            .or_else(|| self.flag_types.get(flag_type.name()))
+            .or_else(|| self.severities.get(&format!("{:?}", raw)))
+    }
+
+    /// Remap `flag` in place, keeping the rule's own severity in `raw_severity` when it changes
+    pub fn apply(&self, flag: &mut Flag) {
+        self.apply_on(flag, None);
+    }
+
+    /// Remap `flag` raised on a snapshot taken on a `network` Wi-Fi location
+    pub fn apply_on(&self, flag: &mut Flag, network: Option<NetworkCategory>) {
+        let severity = self.severity_on(&flag.flag_type, &flag.title, &flag.severity, network);
+        if severity != flag.severity {
+            flag.raw_severity = Some(std::mem::replace(&mut flag.severity, severity));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::FlagDetector;
+    use crate::testing::{flag, ScenarioBuilder};
+
+    #[test]
+    fn empty_policy_keeps_rule_severities() {
+        let policy = SeverityPolicy::parse("{}").unwrap();
+        assert_eq!(policy.severity_for(&FlagType::PerformanceIssue, "High CPU Usage", &Severity::Critical), Severity::Critical);
+    }
+
+    #[test]
+    fn most_specific_entry_wins() {
+        let policy = SeverityPolicy::parse(
+            r#"{
+                "severities": { "Medium": "Low" },
+                "flag_types": { "HealthConcern": "Informational" },
+                "rules": { "Fatigue Detected": "High" }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.severity_for(&FlagType::HealthConcern, "Fatigue Detected", &Severity::Medium), Severity::High);
+        assert_eq!(policy.severity_for(&FlagType::HealthConcern, "High Stress Detected", &Severity::Medium), Severity::Informational);
+        assert_eq!(policy.severity_for(&FlagType::ProductivityAlert, "Low Focus Level", &Severity::Medium), Severity::Low);
+        assert_eq!(policy.severity_for(&FlagType::ProductivityAlert, "Prolonged Idle Time", &Severity::Low), Severity::Low);
+    }
+
+    #[test]
+    fn bookmark_flags_are_a_known_flag_type() {
+        let flag: Flag = serde_json::from_str(
+            r#"{
+                "id": "9f1c2d3e-0000-4000-8000-000000000000",
+                "timestamp": "2026-01-12T10:15:00Z",
+                "session_id": "test-session",
+                "flag_type": {"type": "UserBookmark"},
+                "severity": "Informational",
+                "title": "User Bookmark",
+                "description": "compiler froze",
+                "data_source": "user",
+                "metrics": {"source": "hotkey"},
+                "confidence": 1.0
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(flag.flag_type, FlagType::UserBookmark);
+        assert!(SeverityPolicy::parse(r#"{"flag_types": {"UserBookmark": "Medium"}}"#).is_ok());
+    }
+
+    #[test]
+    fn every_detector_title_can_be_remapped() {
+        for call in include_str!("detector.rs").split("..Flag::new(").skip(1) {
+            let call = call.lines().next().unwrap();
+            let title = call.rsplit('"').nth(1).unwrap();
+            assert!(RULE_TITLES.contains(&title), "{:?} is missing from RULE_TITLES", title);
+        }
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert!(SeverityPolicy::parse(r#"{"flag_types": {"Health": "Low"}}"#).is_err());
+        assert!(SeverityPolicy::parse(r#"{"severities": {"Severe": "Low"}}"#).is_err());
+        assert!(SeverityPolicy::parse(r#"{"rules": {"High CPU Usage": "Urgent"}}"#).is_err());
+        assert!(SeverityPolicy::parse(r#"{"rules": {"High CPU usage": "Low"}}"#).is_err());
+        assert!(SeverityPolicy::parse(r#"{"networks": {"public": {"rules": {"VPN Connected": "High"}}}}"#).is_err());
+        assert!(SeverityPolicy::parse(r#"{"flagtypes": {}}"#).is_err());
+    }
+
+    #[test]
+    fn network_overrides_apply_on_their_category_only() {
+        let policy = SeverityPolicy::parse(
+            r#"{
+                "flag_types": { "SecurityConcern": "Low" },
+                "networks": { "public": { "flag_types": { "SecurityConcern": "High" } } }
+            }"#,
+        )
+        .unwrap();
+
+        let vpn = |network| policy.severity_on(&FlagType::SecurityConcern, "VPN Connected Mid-Session", &Severity::Medium, network);
+        assert_eq!(vpn(Some(NetworkCategory::Public)), Severity::High);
+        assert_eq!(vpn(Some(NetworkCategory::Office)), Severity::Low);
+        assert_eq!(vpn(None), Severity::Low);
+        assert_eq!(policy.severity_on(&FlagType::HealthConcern, "Fatigue Detected", &Severity::Medium, Some(NetworkCategory::Public)), Severity::Medium);
+    }
+
+    #[tokio::test]
+    async fn snapshots_on_public_wifi_get_the_public_overrides() {
+        let policy = SeverityPolicy::parse(r#"{"networks": {"public": {"rules": {"High CPU Usage": "Critical"}}}}"#).unwrap();
+        let scenario = ScenarioBuilder::new().cpu(92.0).wifi(NetworkCategory::Public).build();
+        let mut flags = FlagDetector::new().analyze_data(&scenario).await.unwrap();
+        flags.iter_mut().for_each(|f| policy.apply_on(f, scenario.network_category()));
+
+        let cpu = flag(&flags, "High CPU Usage");
+        assert_eq!(cpu.severity, Severity::Critical);
+        assert_eq!(cpu.raw_severity, Some(Severity::High));
+    }
+
+    #[test]
+    fn network_overrides_are_validated() {
+        assert!(SeverityPolicy::parse(r#"{"networks": {"cafe": {}}}"#).is_err());
+        assert!(SeverityPolicy::parse(r#"{"networks": {"public": {"flag_types": {"Security": "High"}}}}"#).is_err());
+        assert!(SeverityPolicy::parse(r#"{"networks": {"public": {"networks": {"home": {}}}}}"#).is_err());
+        assert!(SeverityPolicy::parse(r#"{"networks": {"home": {"severities": {"High": "Medium"}}}}"#).is_ok());
+    }
+
+    #[tokio::test]
+    async fn applied_policy_keeps_the_raw_severity() {
+        let policy = SeverityPolicy::parse(r#"{"flag_types": {"PerformanceIssue": "Informational"}}"#).unwrap();
+        let mut flags = ScenarioBuilder::new().cpu(97.0).stress(0.9).flags().await;
+        flags.iter_mut().for_each(|f| policy.apply(f));
+
+        let cpu = flag(&flags, "High CPU Usage");
+        assert_eq!(cpu.severity, Severity::Informational);
+        assert_eq!(cpu.raw_severity, Some(Severity::Critical));
+
+        let stress = flag(&flags, "High Stress Detected");
+        assert_eq!(stress.severity, Severity::Medium);
+        assert_eq!(stress.raw_severity, None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Snapshots the collector sealed with `encryption = "xchacha20poly1305"` (`<name>.json.enc`, or
+//! `<name>.json.zst.enc` when also compressed). They are opened with the collector's keyring: the
+//! file named by FLAG_ENCRYPTION_KEY_FILE, otherwise `encryption_keys.json` in the device directory.
+//! The format is the collector's (storage/encryption.rs); only opening is needed here.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+pub const ENCRYPTED_SUFFIX: &str = ".enc";
+pub const DEFAULT_KEY_FILE: &str = "encryption_keys.json";
+
+const MAGIC: &[u8] = b"EYEENC";
+const FORMAT_VERSION: u8 = 1;
+const KEY_ID_LEN: usize = 4;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + KEY_ID_LEN;
+
+#[derive(Deserialize)]
+struct KeyFile {
+    active: String,
+    #[serde(default)]
+    previous: Vec<String>,
+}
+
+pub fn is_encrypted(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.ends_with(ENCRYPTED_SUFFIX))
+}
+
+/// Decrypt `sealed` with the keyring at `keyring`; the key is picked by the ID in the file's header
+pub fn open(keyring: &Path, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    let content = std::fs::read_to_string(keyring).map_err(|e| format!("No encryption keyring at {:?}: {}", keyring, e))?;
+    let file: KeyFile = serde_json::from_str(&content).map_err(|e| format!("Invalid encryption keyring {:?}: {}", keyring, e))?;
+
+    if sealed.len() < HEADER_LEN + NONCE_LEN || !sealed.starts_with(MAGIC) || sealed[MAGIC.len()] != FORMAT_VERSION {
+        return Err("Not an encrypted EyeCore file".to_string());
+    }
+    let (header, rest) = sealed.split_at(HEADER_LEN);
+    let id = &header[MAGIC.len() + 1..];
+    let key = std::iter::once(&file.active)
+        .chain(&file.previous)
+        .filter_map(|encoded| BASE64.decode(encoded.trim()).ok())
+        .filter(|key| key.len() == 32)
+        .find(|key| &Sha256::digest(key)[..KEY_ID_LEN] == id)
+        .ok_or_else(|| format!("Encrypted with key {}, which is not in {:?}", hex(id), keyring))?;
+
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let key: [u8; 32] = key.try_into().expect("the key length is checked above");
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("the nonce is NONCE_LEN bytes");
+    XChaCha20Poly1305::new(&Key::from(key))
+        .decrypt(&XNonce::from(nonce), Payload { msg: ciphertext, aad: header })
+        .map_err(|_| "Decryption failed: the file is damaged or was altered".to_string())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chacha20poly1305::aead::{AeadCore, OsRng};
+
+    /// Seal `plaintext` the way the collector does
+    fn seal(key: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let mut sealed = MAGIC.to_vec();
+        sealed.push(FORMAT_VERSION);
+        sealed.extend_from_slice(&Sha256::digest(key)[..KEY_ID_LEN]);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+        let ciphertext = cipher.encrypt(&nonce, Payload { msg: plaintext, aad: &sealed }).unwrap();
+        sealed.extend_from_slice(&nonce);
+        sealed.extend(ciphertext);
+        sealed
+    }
+
+    fn keyring(active: &[u8], previous: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("flag_keyring_{}.json", uuid::Uuid::new_v4()));
+        let content = serde_json::json!({ "active": BASE64.encode(active), "previous": [BASE64.encode(previous)] });
+        std::fs::write(&path, content.to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn opens_files_sealed_with_any_key_in_the_keyring() {
+        let (active, previous) = ([1u8; 32], [2u8; 32]);
+        let path = keyring(&active, &previous);
+        assert_eq!(open(&path, &seal(&active, b"{\"data\":1}")).unwrap(), b"{\"data\":1}");
+        assert_eq!(open(&path, &seal(&previous, b"older")).unwrap(), b"older");
+        assert!(open(&path, &seal(&[3u8; 32], b"other")).unwrap_err().contains("not in"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_altered_files() {
+        let key = [1u8; 32];
+        let path = keyring(&key, &[2u8; 32]);
+        let mut sealed = seal(&key, b"snapshot");
+        *sealed.last_mut().unwrap() ^= 1;
+        assert!(open(&path, &sealed).is_err());
+        assert!(open(&path, b"{\"plain\":true}").is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
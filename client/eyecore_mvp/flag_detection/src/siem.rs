@@ -0,0 +1,571 @@
+//! Forwarding flags to a SIEM as CEF, JSON or SARIF, over syslog or HTTP
+
+use chrono::Utc;
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+use crate::models::{Flag, Severity};
+use crate::policy::FLAG_TYPE_NAMES;
+
+/// Flags kept for retry while the SIEM is unreachable; older ones are dropped first
+const MAX_PENDING: usize = 10_000;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+/// syslog facility local0
+const SYSLOG_FACILITY: u8 = 16;
+
+/// Flag fields that can be mapped into events, besides `metrics.<key>`
+const SOURCE_FIELDS: &[&str] = &[
+    "id",
+    "timestamp",
+    "timestamp_ms",
+    "session_id",
+    "flag_type",
+    "severity",
+    "raw_severity",
+    "title",
+    "description",
+    "data_source",
+    "confidence",
+    "metrics",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Cef,
+    Json,
+    Sarif,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogProtocol {
+    #[default]
+    Udp,
+    Tcp,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+pub enum Transport {
+    Syslog {
+        address: String,
+        #[serde(default)]
+        protocol: SyslogProtocol,
+    },
+    Http {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+}
+
+/// Where and how flags are forwarded, e.g.
+///
+/// ```json
+/// {
+///   "format": "cef",
+///   "transport": { "syslog": { "address": "siem.school.local:514", "protocol": "tcp" } },
+///   "flag_types": ["SecurityConcern", "BehaviorAnomaly"],
+///   "fields": { "externalId": "id", "cs1": "session_id", "cn1": "metrics.cpu_usage" }
+/// }
+/// ```
+///
+/// `fields` maps event keys to flag fields (CEF extension keys for `cef`); omitted, a default
+/// mapping is used.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiemConfig {
+    pub format: Format,
+    pub transport: Transport,
+    #[serde(default = "default_flag_types")]
+    pub flag_types: Vec<String>,
+    #[serde(default)]
+    fields: Option<BTreeMap<String, String>>,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+}
+
+fn default_flag_types() -> Vec<String> {
+    vec!["SecurityConcern".to_string(), "BehaviorAnomaly".to_string()]
+}
+
+fn default_batch_size() -> usize {
+    50
+}
+
+fn default_flush_interval_secs() -> u64 {
+    10
+}
+
+impl SiemConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read SIEM config {:?}: {}", path, e))?;
+        Self::parse(&content).map_err(|e| format!("Invalid SIEM config {:?}: {}", path, e))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let config: SiemConfig = serde_json::from_str(content).map_err(|e| e.to_string())?;
+
+        if let Some(name) = config.flag_types.iter().find(|t| !FLAG_TYPE_NAMES.contains(&t.as_str())) {
+            return Err(format!("unknown flag type {:?} (expected one of {})", name, FLAG_TYPE_NAMES.join(", ")));
+        }
+        if let Some(source) = config
+            .fields
+            .iter()
+            .flat_map(|fields| fields.values())
+            .find(|source| !SOURCE_FIELDS.contains(&source.as_str()) && !source.starts_with("metrics."))
+        {
+            return Err(format!("unknown flag field {:?} (expected one of {} or metrics.<key>)", source, SOURCE_FIELDS.join(", ")));
+        }
+        if config.batch_size == 0 || config.flush_interval_secs == 0 {
+            return Err("batch_size and flush_interval_secs must be at least 1".to_string());
+        }
+        // A SARIF log is one document per batch, which syslog lines cannot carry
+        if config.format == Format::Sarif && matches!(config.transport, Transport::Syslog { .. }) {
+            return Err("sarif can only be sent over http".to_string());
+        }
+        Ok(config)
+    }
+
+    /// Where flags are sent, without credentials: the syslog address or the URL without its user
+    /// and query
+    pub fn destination(&self) -> String {
+        match &self.transport {
+            Transport::Syslog { address, protocol } => format!("syslog {} over {:?}", address, protocol).to_lowercase(),
+            Transport::Http { url, .. } => match reqwest::Url::parse(url) {
+                Ok(mut url) => {
+                    url.set_query(None);
+                    let _ = url.set_username("");
+                    let _ = url.set_password(None);
+                    url.to_string()
+                }
+                Err(_) => url.split('?').next().unwrap_or_default().to_string(),
+            },
+        }
+    }
+
+    pub fn accepts(&self, flag: &Flag) -> bool {
+        self.flag_types.iter().any(|t| t == flag.flag_type.name())
+    }
+
+    /// Event key -> flag field, the configured mapping or the format's default
+    fn fields(&self) -> BTreeMap<String, String> {
+        if let Some(fields) = &self.fields {
+            return fields.clone();
+        }
+        let defaults: &[(&str, &str)] = match self.format {
+            Format::Cef => &[
+                ("rt", "timestamp_ms"),
+                ("externalId", "id"),
+                ("cat", "flag_type"),
+                ("msg", "description"),
+                ("cs1", "session_id"),
+                ("cs2", "data_source"),
+                ("cfp1", "confidence"),
+            ],
+            Format::Json | Format::Sarif => &[
+                ("id", "id"),
+                ("timestamp", "timestamp"),
+                ("session_id", "session_id"),
+                ("flag_type", "flag_type"),
+                ("severity", "severity"),
+                ("title", "title"),
+                ("description", "description"),
+                ("data_source", "data_source"),
+                ("confidence", "confidence"),
+                ("metrics", "metrics"),
+            ],
+        };
+        defaults.iter().map(|(key, source)| (key.to_string(), source.to_string())).collect()
+    }
+}
+
+fn severity_name(severity: &Severity) -> String {
+    format!("{:?}", severity)
+}
+
+fn field_value(flag: &Flag, source: &str) -> Option<Value> {
+    match source {
+        "id" => Some(json!(flag.id)),
+        "timestamp" => Some(json!(flag.timestamp.to_rfc3339())),
+        "timestamp_ms" => Some(json!(flag.timestamp.timestamp_millis())),
+        "session_id" => Some(json!(flag.session_id)),
+        "flag_type" => Some(json!(flag.flag_type.name())),
+        "severity" => Some(json!(severity_name(&flag.severity))),
+        "raw_severity" => flag.raw_severity.as_ref().map(|s| json!(severity_name(s))),
+        "title" => Some(json!(flag.title)),
+        "description" => Some(json!(flag.description)),
+        "data_source" => Some(json!(flag.data_source)),
+        "confidence" => Some(json!(flag.confidence)),
+        "metrics" => Some(flag.metrics.clone()),
+        other => other.strip_prefix("metrics.").and_then(|key| flag.metrics.get(key).cloned()),
+    }
+}
+
+/// CEF severity, 0 (lowest) to 10
+fn cef_severity(severity: &Severity) -> u8 {
+    match severity {
+        Severity::Informational => 1,
+        Severity::Low => 3,
+        Severity::Medium => 5,
+        Severity::High => 8,
+        Severity::Critical => 10,
+    }
+}
+
+fn cef_header_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn cef_extension_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace("\r\n", "\\n")
+        .replace(['\n', '\r'], "\\n")
+}
+
+pub fn to_cef(flag: &Flag, fields: &BTreeMap<String, String>) -> String {
+    let mut extension = Vec::new();
+    for (key, source) in fields {
+        let Some(value) = field_value(flag, source) else {
+            continue;
+        };
+        let value = match value {
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+        extension.push(format!("{}={}", key, cef_extension_escape(&value)));
+        // Custom fields (cs1, cn1, cfp1, ...) carry their meaning in a matching label
+        if key.starts_with("cs") || key.starts_with("cn") || key.starts_with("cfp") {
+            extension.push(format!("{}Label={}", key, cef_extension_escape(source)));
+        }
+    }
+
+    format!(
+        "CEF:0|EyeCore|FlagDetection|{}|{}|{}|{}|{}",
+        cef_header_escape(env!("CARGO_PKG_VERSION")),
+        cef_header_escape(flag.flag_type.name()),
+        cef_header_escape(&flag.title),
+        cef_severity(&flag.severity),
+        extension.join(" ")
+    )
+}
+
+pub fn to_json(flag: &Flag, fields: &BTreeMap<String, String>) -> Value {
+    Value::Object(
+        fields
+            .iter()
+            .filter_map(|(key, source)| Some((key.clone(), field_value(flag, source)?)))
+            .collect(),
+    )
+}
+
+/// One SARIF 2.1.0 log for a batch; rules are the flag titles
+pub fn to_sarif(flags: &[Flag], fields: &BTreeMap<String, String>) -> Value {
+    let mut rules: Vec<&str> = flags.iter().map(|f| f.title.as_str()).collect();
+    rules.sort_unstable();
+    rules.dedup();
+
+    let results: Vec<Value> = flags
+        .iter()
+        .map(|flag| {
+            let level = match flag.severity {
+                Severity::Critical | Severity::High => "error",
+                Severity::Medium => "warning",
+                Severity::Low | Severity::Informational => "note",
+            };
+            json!({
+                "ruleId": flag.title,
+                "level": level,
+                "message": { "text": flag.description },
+                "properties": to_json(flag, fields),
+            })
+        })
+        .collect();
+
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "EyeCore Flag Detection",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// RFC 5424 frame around one event
+fn syslog_line(flag: &Flag, message: &str) -> String {
+    let severity = match flag.severity {
+        Severity::Critical => 2,
+        Severity::High => 3,
+        Severity::Medium => 4,
+        Severity::Low => 5,
+        Severity::Informational => 6,
+    };
+    let host = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .unwrap_or_else(|_| "-".to_string());
+    format!(
+        "<{}>1 {} {} eyecore-flags - - - {}",
+        SYSLOG_FACILITY * 8 + severity,
+        Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        host,
+        message
+    )
+}
+
+/// Queue of flags for the SIEM, sent in batches by a background task
+pub struct SiemExporter {
+    config: std::sync::Arc<SiemConfig>,
+    queue: mpsc::UnboundedSender<Flag>,
+}
+
+impl SiemExporter {
+    pub fn config(&self) -> &SiemConfig {
+        &self.config
+    }
+
+    /// Start the sender task; HTTP transports go through `http`, the embedding app's shared client
+    pub fn start(config: SiemConfig, http: reqwest::Client) -> Self {
+        let config = std::sync::Arc::new(config);
+        let (queue, mut received) = mpsc::unbounded_channel::<Flag>();
+
+        let sender_config = std::sync::Arc::clone(&config);
+        tokio::spawn(async move {
+            let config = sender_config;
+            let mut pending: Vec<Flag> = Vec::new();
+            let mut flush = tokio::time::interval(Duration::from_secs(config.flush_interval_secs));
+            loop {
+                tokio::select! {
+                    flag = received.recv() => match flag {
+                        Some(flag) => {
+                            pending.push(flag);
+                            if pending.len() < config.batch_size {
+                                continue;
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = flush.tick() => {}
+                }
+                send_pending(&config, &http, &mut pending).await;
+            }
+            send_pending(&config, &http, &mut pending).await;
+        });
+
+        SiemExporter { config, queue }
+    }
+
+    /// Queue `flag` if the config forwards its type
+    pub fn export(&self, flag: &Flag) {
+        if self.config.accepts(flag) {
+            let _ = self.queue.send(flag.clone());
+        }
+    }
+}
+
+/// Send `pending` in batches, keeping what could not be delivered for the next flush
+async fn send_pending(config: &SiemConfig, http: &reqwest::Client, pending: &mut Vec<Flag>) {
+    let mut sent = 0;
+    for batch in pending.chunks(config.batch_size) {
+        if let Err(e) = send_batch(config, http, batch).await {
+            warn!("SIEM export failed, {} flags kept for retry: {}", pending.len() - sent, e);
+            break;
+        }
+        sent += batch.len();
+    }
+    if sent > 0 {
+        info!("📤 {} flags sent to SIEM", sent);
+    }
+    pending.drain(..sent);
+
+    let overflow = pending.len().saturating_sub(MAX_PENDING);
+    if overflow > 0 {
+        warn!("SIEM unreachable, dropping {} oldest flags", overflow);
+        pending.drain(..overflow);
+    }
+}
+
+async fn send_batch(config: &SiemConfig, http: &reqwest::Client, batch: &[Flag]) -> Result<(), String> {
+    let fields = config.fields();
+    match &config.transport {
+        Transport::Http { url, headers } => {
+            let (content_type, body) = match config.format {
+                Format::Cef => ("text/plain", batch.iter().map(|f| to_cef(f, &fields) + "\n").collect::<String>()),
+                Format::Json => (
+                    "application/json",
+                    Value::Array(batch.iter().map(|f| to_json(f, &fields)).collect()).to_string(),
+                ),
+                Format::Sarif => ("application/sarif+json", to_sarif(batch, &fields).to_string()),
+            };
+            let mut request = http
+                .post(url)
+                .timeout(HTTP_TIMEOUT)
+                .header(reqwest::header::CONTENT_TYPE, content_type)
+                .body(body);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("HTTP {}", response.status()));
+            }
+            Ok(())
+        }
+        Transport::Syslog { address, protocol } => {
+            let lines: Vec<String> = batch
+                .iter()
+                .map(|flag| {
+                    let message = match config.format {
+                        Format::Json => to_json(flag, &fields).to_string(),
+                        _ => to_cef(flag, &fields),
+                    };
+                    syslog_line(flag, &message)
+                })
+                .collect();
+            let target = tokio::net::lookup_host(address.as_str())
+                .await
+                .map_err(|e| e.to_string())?
+                .next()
+                .ok_or_else(|| format!("{} did not resolve", address))?;
+
+            match protocol {
+                SyslogProtocol::Udp => {
+                    let local = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+                    let socket = tokio::net::UdpSocket::bind(local).await.map_err(|e| e.to_string())?;
+                    for line in &lines {
+                        socket.send_to(line.as_bytes(), target).await.map_err(|e| e.to_string())?;
+                    }
+                }
+                SyslogProtocol::Tcp => {
+                    // Newline-delimited framing (RFC 6587 non-transparent), as most collectors accept
+                    let mut stream = tokio::net::TcpStream::connect(target).await.map_err(|e| e.to_string())?;
+                    for line in &lines {
+                        stream.write_all(line.as_bytes()).await.map_err(|e| e.to_string())?;
+                        stream.write_all(b"\n").await.map_err(|e| e.to_string())?;
+                    }
+                    stream.shutdown().await.map_err(|e| e.to_string())?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::FlagDetector;
+    use crate::testing::{flag, ScenarioBuilder};
+
+    async fn vpn_flag() -> Flag {
+        let detector = FlagDetector::new().with_vpn_alerts(true);
+        let flags = ScenarioBuilder::new().vpn_connected("wg0").flags_with(&detector).await;
+        flag(&flags, "VPN Connected Mid-Session").clone()
+    }
+
+    fn config(json: &str) -> SiemConfig {
+        SiemConfig::parse(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn cef_uses_the_default_mapping() {
+        let flag = vpn_flag().await;
+        let config = config(r#"{"format": "cef", "transport": {"syslog": {"address": "127.0.0.1:514"}}}"#);
+        let cef = to_cef(&flag, &config.fields());
+
+        assert!(cef.starts_with("CEF:0|EyeCore|FlagDetection|"), "{}", cef);
+        assert!(cef.contains("|SecurityConcern|VPN Connected Mid-Session|5|"), "{}", cef);
+        assert!(cef.contains("cs1=test-session cs1Label=session_id"), "{}", cef);
+        assert!(cef.contains(&format!("externalId={}", flag.id)), "{}", cef);
+    }
+
+    #[tokio::test]
+    async fn cef_escapes_separators() {
+        let mut flag = vpn_flag().await;
+        flag.title = "a|b".to_string();
+        flag.description = "x=1\nback\\slash".to_string();
+        let fields = BTreeMap::from([("msg".to_string(), "description".to_string())]);
+
+        let cef = to_cef(&flag, &fields);
+        assert!(cef.contains("|a\\|b|"), "{}", cef);
+        assert!(cef.ends_with("msg=x\\=1\\nback\\\\slash"), "{}", cef);
+    }
+
+    #[tokio::test]
+    async fn json_follows_the_configured_mapping() {
+        let flag = vpn_flag().await;
+        let config = config(
+            r#"{
+                "format": "json",
+                "transport": {"http": {"url": "https://siem.example/ingest"}},
+                "fields": {"event_id": "id", "host_session": "session_id", "adapters": "metrics.vpn_adapters", "missing": "metrics.vendor"}
+            }"#,
+        );
+        let event = to_json(&flag, &config.fields());
+        assert_eq!(event["event_id"], json!(flag.id));
+        assert_eq!(event["host_session"], json!("test-session"));
+        assert_eq!(event["adapters"], json!(["wg0"]));
+        // Metrics the flag does not carry are left out rather than sent as null
+        assert_eq!(event.as_object().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn sarif_groups_a_batch_into_one_run() {
+        let flag = vpn_flag().await;
+        let log = to_sarif(&[flag.clone(), flag], &BTreeMap::new());
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(log["runs"][0]["results"].as_array().unwrap().len(), 2);
+        assert_eq!(log["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap().len(), 1);
+        assert_eq!(log["runs"][0]["results"][0]["level"], "warning");
+    }
+
+    #[tokio::test]
+    async fn only_configured_flag_types_are_exported() {
+        let config = config(r#"{"format": "json", "transport": {"http": {"url": "http://siem"}}}"#);
+        assert!(config.accepts(&vpn_flag().await));
+
+        let flags = ScenarioBuilder::new().cpu(97.0).flags().await;
+        assert!(!config.accepts(flag(&flags, "High CPU Usage")));
+    }
+
+    #[test]
+    fn destination_leaves_out_credentials() {
+        let http = config(r#"{"format": "json", "transport": {"http": {"url": "https://user:pw@siem.example/ingest?token=abc"}}}"#);
+        assert_eq!(http.destination(), "https://siem.example/ingest");
+        let syslog = config(r#"{"format": "cef", "transport": {"syslog": {"address": "siem:514", "protocol": "tcp"}}}"#);
+        assert_eq!(syslog.destination(), "syslog siem:514 over tcp");
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        let http = r#"{"http": {"url": "http://siem"}}"#;
+        let syslog = r#"{"syslog": {"address": "siem:514"}}"#;
+        for json in [
+            format!(r#"{{"format": "cef", "transport": {}, "flag_types": ["Security"]}}"#, http),
+            format!(r#"{{"format": "cef", "transport": {}, "fields": {{"msg": "note"}}}}"#, http),
+            format!(r#"{{"format": "sarif", "transport": {}}}"#, syslog),
+            format!(r#"{{"format": "json", "transport": {}, "batch_size": 0}}"#, http),
+            format!(r#"{{"format": "leef", "transport": {}}}"#, http),
+        ] {
+            assert!(SiemConfig::parse(&json).is_err(), "accepted {}", json);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Following the collector's snapshot logs (`snapshot_format = "ndjson"`): each
+//! `timeslots/snapshots_<date>_<part>.ndjson` grows by one line per snapshot, so only the lines
+//! added since the last read are analyzed.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_LOG_SUFFIX: &str = ".ndjson";
+
+/// Snapshot logs live next to snapshot files, in a `timeslots` directory inside each day shard
+pub fn is_snapshot_log(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.ends_with(SNAPSHOT_LOG_SUFFIX))
+        && path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()) == Some("timeslots")
+}
+
+/// How far each snapshot log has been read
+#[derive(Default)]
+pub struct LogTail {
+    offsets: HashMap<PathBuf, u64>,
+}
+
+impl LogTail {
+    pub fn new() -> Self {
+        LogTail::default()
+    }
+
+    /// Start every log already under `dir` at its end, so snapshots written before startup are not
+    /// analyzed again
+    pub fn skip_existing(&mut self, dir: &Path) {
+        let Ok(entries) = std::fs::read_dir(dir) else { return };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                self.skip_existing(&path);
+            } else if is_snapshot_log(&path) {
+                if let Ok(content) = std::fs::read(&path) {
+                    self.offsets.insert(path, complete_length(&content) as u64);
+                }
+            }
+        }
+    }
+
+    /// Complete lines added to `path` since the last call, without their newlines. A line still being
+    /// written is left for the next call. A log that shrank was rewritten by the collector's
+    /// retention, which keeps the snapshots it had, so reading carries on from its new end.
+    pub fn read_new_lines(&mut self, path: &Path) -> std::io::Result<Vec<Vec<u8>>> {
+        let mut file = std::fs::File::open(path)?;
+        let size = file.metadata()?.len();
+        let offset = self.offsets.entry(path.to_path_buf()).or_insert(0);
+        if size < *offset {
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+            *offset = complete_length(&content) as u64;
+            return Ok(Vec::new());
+        }
+
+        let mut added = Vec::new();
+        file.seek(SeekFrom::Start(*offset))?;
+        file.read_to_end(&mut added)?;
+        added.truncate(complete_length(&added));
+        *offset += added.len() as u64;
+        Ok(added
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .map(<[u8]>::to_vec)
+            .collect())
+    }
+}
+
+/// Length up to and including the last newline
+fn complete_length(content: &[u8]) -> usize {
+    content.iter().rposition(|byte| *byte == b'\n').map_or(0, |end| end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn timeslots_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("flag_tail_{}", uuid::Uuid::new_v4())).join("timeslots");
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn append(path: &Path, content: &str) {
+        std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap().write_all(content.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn recognizes_logs_in_timeslots() {
+        assert!(is_snapshot_log(Path::new("dev/2025/10/26/timeslots/snapshots_2025-10-26_000.ndjson")));
+        assert!(!is_snapshot_log(Path::new("dev/2025/10/26/timeslots/data_2025-10-26_10-00-00.json")));
+        assert!(!is_snapshot_log(Path::new("dev/2025/10/26/sessions/snapshots_2025-10-26_000.ndjson")));
+    }
+
+    #[test]
+    fn reads_each_complete_line_once() {
+        let dir = timeslots_dir();
+        let log = dir.join("snapshots_2025-10-26_000.ndjson");
+        let mut tail = LogTail::new();
+
+        append(&log, "{\"a\":1}\n{\"b\":2");
+        assert_eq!(tail.read_new_lines(&log).unwrap(), vec![b"{\"a\":1}".to_vec()]);
+        assert!(tail.read_new_lines(&log).unwrap().is_empty());
+
+        append(&log, "}\n{\"c\":3}\n");
+        assert_eq!(tail.read_new_lines(&log).unwrap(), vec![b"{\"b\":2}".to_vec(), b"{\"c\":3}".to_vec()]);
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn skips_lines_written_before_startup_and_rewrites() {
+        let dir = timeslots_dir();
+        let log = dir.join("snapshots_2025-10-26_000.ndjson");
+        append(&log, "{\"a\":1}\n{\"b\":2}\n");
+        let mut tail = LogTail::new();
+        tail.skip_existing(dir.parent().unwrap());
+        assert!(tail.read_new_lines(&log).unwrap().is_empty());
+
+        std::fs::write(&log, "{\"a\":0}\n").unwrap();
+        assert!(tail.read_new_lines(&log).unwrap().is_empty());
+        append(&log, "{\"c\":3}\n");
+        assert_eq!(tail.read_new_lines(&log).unwrap(), vec![b"{\"c\":3}".to_vec()]);
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
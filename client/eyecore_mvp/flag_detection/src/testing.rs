@@ -0,0 +1,434 @@
+//! Synthetic snapshots and flag assertions for detector tests
+
+use chrono::Utc;
+use std::collections::BTreeMap;
+
+use crate::detector::FlagDetector;
+use crate::models::*;
+
+/// Builds an `EyeCoreData` snapshot from a calm baseline (every section present, nothing
+/// flagged) so each test only states the values it is about:
+/// `ScenarioBuilder::new().cpu(95.0).idle_secs(600).stress(0.9).build()`
+pub struct ScenarioBuilder {
+    data: EyeCoreData,
+}
+
+impl ScenarioBuilder {
+    pub fn new() -> Self {
+        let now = Utc::now();
+        ScenarioBuilder {
+            data: EyeCoreData {
+                session_id: "test-session".to_string(),
+                timestamp: now,
+                system_metrics: SystemMetrics {
+                    timestamp: now,
+                    cpu_usage: 20.0,
+                    memory_usage: 40.0,
+                    disk_usage: 50.0,
+                    display_brightness: None,
+                    ambient_light_lux: None,
+                },
+                process_data: ProcessData {
+                    timestamp: now,
+                    active_process: "code".to_string(),
+                    active_window_title: "main.rs - editor".to_string(),
+                    process_count: 120,
+                },
+                top_processes: Vec::new(),
+                input_metrics: InputMetrics {
+                    timestamp: now,
+                    mouse_clicks: 12,
+                    keyboard_events: 80,
+                    idle_duration_seconds: 0,
+                },
+                network_metrics: NetworkMetrics {
+                    timestamp: now,
+                    bytes_sent: 1_000,
+                    bytes_received: 5_000,
+                    active_connections: 10,
+                    bytes_sent_per_sec: 200.0,
+                    bytes_received_per_sec: 1_000.0,
+                },
+                focus_metrics: FocusMetrics {
+                    timestamp: now,
+                    focus_level: 0.8,
+                    context_switches: 5,
+                    productive_app_time: 300,
+                },
+                voice_data: Some(VoiceData {
+                    timestamp: now,
+                    vocal_tone_score: 0.5,
+                    sentiment_score: 0.2,
+                    emotion_detected: "neutral".to_string(),
+                    speaking_duration_ms: 10_000,
+                    silence_duration_ms: 5_000,
+                    volume_level: 0.5,
+                    enabled: true,
+                }),
+                camera_data: Some(CameraData {
+                    timestamp: now,
+                    facial_emotions: vec!["focused".to_string()],
+                    dominant_emotion: "focused".to_string(),
+                    emotion_confidence: 0.8,
+                    gaze_direction: "center".to_string(),
+                    face_detected: true,
+                    posture_score: 0.8,
+                    enabled: true,
+                    presence_only: false,
+                }),
+                keystroke_dynamics: Some(KeystrokeDynamics {
+                    timestamp: now,
+                    typing_speed_wpm: 60.0,
+                    avg_key_hold_time_ms: 90.0,
+                    avg_key_interval_ms: 120.0,
+                    key_press_variance: 0.2,
+                    error_correction_rate: 0.05,
+                    stress_indicator: 0.2,
+                    fatigue_indicator: 0.2,
+                    total_keystrokes: 80,
+                    enabled: true,
+                }),
+                screen_interactions: Some(ScreenInteractions {
+                    timestamp: now,
+                    click_count: 12,
+                    double_click_count: 1,
+                    right_click_count: 0,
+                    scroll_events: 4,
+                    ui_element_types: vec!["button".to_string()],
+                    interaction_speed: 10.0,
+                    workflow_friction_score: 0.2,
+                    mouse_travel_distance_px: 4_000,
+                    screen_region_heatmap: Vec::new(),
+                }),
+                file_metadata: None,
+                system_events: Some(SystemEvents {
+                    timestamp: now,
+                    event_type: "unlock".to_string(),
+                    event_subtype: "keyboard".to_string(),
+                    session_start: Some(now),
+                    session_end: None,
+                    break_duration_seconds: 0,
+                    active_session_duration_seconds: 3_600,
+                    daily_rhythm_score: 0.7,
+                }),
+                mouse_dynamics: Some(MouseDynamics {
+                    timestamp: now,
+                    movement_speed_avg: 400.0,
+                    movement_speed_variance: 0.2,
+                    path_smoothness: 0.8,
+                    click_pattern_regularity: 0.7,
+                    hesitation_count: 3,
+                    acceleration_avg: 100.0,
+                    fatigue_indicator: 0.2,
+                    focus_indicator: 0.8,
+                    total_distance_px: 4_000,
+                }),
+                network_activity_metadata: Some(NetworkActivityMetadata {
+                    timestamp: now,
+                    bytes_sent: 1_000,
+                    bytes_received: 5_000,
+                    active_connections: 10,
+                    traffic_type: "browsing".to_string(),
+                    activity_context: "research".to_string(),
+                    bandwidth_usage_mbps: 10.0,
+                    latency_avg_ms: 20.0,
+                    packet_loss_rate: 0.0,
+                    connection_stability: 0.95,
+                    routing: None,
+                    wifi: None,
+                }),
+                meeting: None,
+                focus_context: None,
+                presence: None,
+                provenance: BTreeMap::new(),
+            },
+        }
+    }
+
+    pub fn cpu(mut self, percent: f32) -> Self {
+        self.data.system_metrics.cpu_usage = percent;
+        self
+    }
+
+    /// Report `name` as the busiest process, using `percent` of all cores
+    pub fn top_process(mut self, name: &str, percent: f32) -> Self {
+        self.data.top_processes.insert(0, ProcessUsage {
+            pid: 4_242,
+            name: name.to_string(),
+            cpu_usage: percent,
+            memory_bytes: 500_000_000,
+            disk_read_bytes: 0,
+            disk_written_bytes: 0,
+        });
+        self
+    }
+
+    pub fn memory(mut self, percent: f32) -> Self {
+        self.data.system_metrics.memory_usage = percent;
+        self
+    }
+
+    pub fn idle_secs(mut self, seconds: u32) -> Self {
+        self.data.input_metrics.idle_duration_seconds = seconds;
+        self
+    }
+
+    pub fn input(mut self, mouse_clicks: u32, keyboard_events: u32) -> Self {
+        self.data.input_metrics.mouse_clicks = mouse_clicks;
+        self.data.input_metrics.keyboard_events = keyboard_events;
+        self
+    }
+
+    pub fn focus(mut self, level: f32) -> Self {
+        self.data.focus_metrics.focus_level = level;
+        self
+    }
+
+    pub fn context_switches(mut self, switches: u32) -> Self {
+        self.data.focus_metrics.context_switches = switches;
+        self
+    }
+
+    pub fn stress(mut self, indicator: f32) -> Self {
+        self.keystrokes().stress_indicator = indicator;
+        self
+    }
+
+    pub fn typing_fatigue(mut self, indicator: f32) -> Self {
+        self.keystrokes().fatigue_indicator = indicator;
+        self
+    }
+
+    pub fn typing_errors(mut self, rate: f32) -> Self {
+        self.keystrokes().error_correction_rate = rate;
+        self
+    }
+
+    pub fn mouse_fatigue(mut self, indicator: f32) -> Self {
+        self.mouse().fatigue_indicator = indicator;
+        self
+    }
+
+    pub fn mouse_path(mut self, smoothness: f32, hesitations: u32) -> Self {
+        let mouse = self.mouse();
+        mouse.path_smoothness = smoothness;
+        mouse.hesitation_count = hesitations;
+        self
+    }
+
+    pub fn sentiment(mut self, score: f32) -> Self {
+        self.voice().sentiment_score = score;
+        self
+    }
+
+    pub fn voice_emotion(mut self, emotion: &str) -> Self {
+        self.voice().emotion_detected = emotion.to_string();
+        self
+    }
+
+    pub fn posture(mut self, score: f32) -> Self {
+        self.camera().posture_score = score;
+        self
+    }
+
+    /// Camera in presence-only mode: nothing but `face_detected`, the rest left empty
+    pub fn camera_presence_only(mut self) -> Self {
+        let camera = self.camera();
+        camera.facial_emotions.clear();
+        camera.dominant_emotion = "unknown".to_string();
+        camera.emotion_confidence = 0.0;
+        camera.gaze_direction = "unknown".to_string();
+        camera.posture_score = 0.0;
+        camera.presence_only = true;
+        self
+    }
+
+    pub fn gaze(mut self, direction: &str) -> Self {
+        self.camera().gaze_direction = direction.to_string();
+        self
+    }
+
+    pub fn bandwidth_mbps(mut self, mbps: f32) -> Self {
+        self.network().bandwidth_usage_mbps = mbps;
+        self
+    }
+
+    pub fn packet_loss(mut self, rate: f32) -> Self {
+        self.network().packet_loss_rate = rate;
+        self
+    }
+
+    pub fn friction(mut self, score: f32) -> Self {
+        self.data.screen_interactions.as_mut().expect("baseline has screen interactions").workflow_friction_score = score;
+        self
+    }
+
+    pub fn system_event(mut self, event_type: &str, subtype: &str) -> Self {
+        let events = self.data.system_events.as_mut().expect("baseline has system events");
+        events.event_type = event_type.to_string();
+        events.event_subtype = subtype.to_string();
+        self
+    }
+
+    /// Report `adapter` as a VPN that came up since the previous snapshot
+    pub fn vpn_connected(mut self, adapter: &str) -> Self {
+        self.network().routing = Some(NetworkRouting {
+            vpn_active: true,
+            vpn_adapters: vec![adapter.to_string()],
+            vpn_connected_mid_session: true,
+            proxy: None,
+        });
+        self
+    }
+
+    /// Take the snapshot on a Wi-Fi network of `category`
+    pub fn wifi(mut self, category: NetworkCategory) -> Self {
+        self.network().wifi = Some(WifiNetwork {
+            ssid_hash: "5f2b1c9d0e3a4b6c".to_string(),
+            bssid_hash: None,
+            category,
+            labeled: category != NetworkCategory::Unknown,
+        });
+        self
+    }
+
+    /// Mark the snapshot as taken during a detected call
+    pub fn in_meeting(mut self) -> Self {
+        self.data.meeting = Some(MeetingStatus {
+            in_meeting: true,
+            confidence: 0.75,
+            signals: vec!["calendar".to_string(), "meeting_app".to_string()],
+            estimated_speakers: Some(3),
+        });
+        self
+    }
+
+    /// Set the client's presence classification, with `idle_secs` as its idle time
+    pub fn presence(mut self, state: PresenceState, idle_secs: u32) -> Self {
+        self.data.input_metrics.idle_duration_seconds = idle_secs;
+        self.data.presence = Some(Presence {
+            state,
+            idle_seconds: idle_secs,
+            display_on: Some(state != PresenceState::Away),
+            media_apps: Some(match state {
+                PresenceState::PassiveConsuming => vec!["vlc.exe".to_string()],
+                _ => Vec::new(),
+            }),
+        });
+        self
+    }
+
+    /// Record a foreground switch from `from` to `to` since the previous snapshot
+    pub fn app_switch(mut self, from: &str, to: &str) -> Self {
+        self.focus_context().app_switches.push(AppSwitch {
+            timestamp: Utc::now(),
+            from_app: Some(from.to_string()),
+            to_app: to.to_string(),
+        });
+        self
+    }
+
+    /// Record a notification from `app` since the previous snapshot
+    pub fn notification(mut self, app: &str) -> Self {
+        self.focus_context().notifications.push(NotificationArrival {
+            timestamp: Utc::now(),
+            app: app.to_string(),
+        });
+        self
+    }
+
+    /// Mark `field` as coming from a collector that has not warmed up yet
+    pub fn warming_up(mut self, field: &str) -> Self {
+        self.data.provenance.insert(field.to_string(), Provenance::WarmingUp);
+        self
+    }
+
+    /// Drop every optional section, leaving only the core metrics
+    pub fn core_only(mut self) -> Self {
+        self.data.voice_data = None;
+        self.data.camera_data = None;
+        self.data.keystroke_dynamics = None;
+        self.data.screen_interactions = None;
+        self.data.system_events = None;
+        self.data.mouse_dynamics = None;
+        self.data.network_activity_metadata = None;
+        self
+    }
+
+    pub fn build(self) -> EyeCoreData {
+        self.data
+    }
+
+    /// Run the default detector over the scenario
+    pub async fn flags(self) -> Vec<Flag> {
+        self.flags_with(&FlagDetector::new()).await
+    }
+
+    /// Run a configured detector over the scenario
+    pub async fn flags_with(self, detector: &FlagDetector) -> Vec<Flag> {
+        detector
+            .analyze_data(&self.build())
+            .await
+            .expect("detector accepts synthetic data")
+    }
+
+    /// Run the default detector against a calibrated baseline
+    pub async fn flags_with_baseline(self, baseline: &Baseline) -> Vec<Flag> {
+        FlagDetector::new()
+            .analyze_data_with_baseline(&self.build(), Some(baseline))
+            .await
+            .expect("detector accepts synthetic data")
+    }
+
+    fn focus_context(&mut self) -> &mut FocusContext {
+        self.data.focus_context.get_or_insert_with(FocusContext::default)
+    }
+
+    fn keystrokes(&mut self) -> &mut KeystrokeDynamics {
+        self.data.keystroke_dynamics.as_mut().expect("baseline has keystroke dynamics")
+    }
+
+    fn mouse(&mut self) -> &mut MouseDynamics {
+        self.data.mouse_dynamics.as_mut().expect("baseline has mouse dynamics")
+    }
+
+    fn voice(&mut self) -> &mut VoiceData {
+        self.data.voice_data.as_mut().expect("baseline has voice data")
+    }
+
+    fn camera(&mut self) -> &mut CameraData {
+        self.data.camera_data.as_mut().expect("baseline has camera data")
+    }
+
+    fn network(&mut self) -> &mut NetworkActivityMetadata {
+        self.data.network_activity_metadata.as_mut().expect("baseline has network metadata")
+    }
+}
+
+/// A baseline of `(metric, p50, p90)` distributions, each from `samples` calibration snapshots
+pub fn baseline(samples: usize, metrics: &[(&str, f32, f32)]) -> Baseline {
+    Baseline {
+        ended_at: Utc::now(),
+        metrics: metrics
+            .iter()
+            .map(|(metric, p50, p90)| (metric.to_string(), MetricDistribution { samples, p50: *p50, p90: *p90 }))
+            .collect(),
+    }
+}
+
+/// Assert that `flags` holds exactly the flags titled `expected` (in any order)
+pub fn assert_flags(flags: &[Flag], expected: &[&str]) {
+    let mut actual: Vec<&str> = flags.iter().map(|f| f.title.as_str()).collect();
+    let mut expected = expected.to_vec();
+    actual.sort_unstable();
+    expected.sort_unstable();
+    assert_eq!(actual, expected, "unexpected set of flags");
+}
+
+/// The flag titled `title`, failing the test with the titles that were raised if it is missing
+pub fn flag<'a>(flags: &'a [Flag], title: &str) -> &'a Flag {
+    flags.iter().find(|f| f.title == title).unwrap_or_else(|| {
+        let titles: Vec<&str> = flags.iter().map(|f| f.title.as_str()).collect();
+        panic!("expected a {:?} flag, got {:?}", title, titles)
+    })
+}
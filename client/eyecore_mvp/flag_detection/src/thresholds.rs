@@ -0,0 +1,279 @@
+//! Detection thresholds from `detector.toml` (FLAG_DETECTOR_CONFIG): per rule, the threshold, the
+//! severity it raises and how it escalates, and the confidence of its flags. The file is watched and
+//! edits apply to the next snapshot analyzed, without a restart.
+
+use log::{error, info};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::models::Severity;
+
+pub const DEFAULT_PATH: &str = "detector.toml";
+
+/// The thresholds file: FLAG_DETECTOR_CONFIG, or `detector.toml` in the working directory
+pub fn path_from_env() -> PathBuf {
+    std::env::var_os("FLAG_DETECTOR_CONFIG").map(PathBuf::from).unwrap_or_else(|| PathBuf::from(DEFAULT_PATH))
+}
+
+/// Thresholds shared between the detector and whoever reloads or reports them
+pub type SharedThresholds = Arc<RwLock<Thresholds>>;
+
+/// When one detection rule raises a flag
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rule {
+    pub threshold: f32,
+    pub severity: Severity,
+    /// Higher severities for values further past the threshold, e.g. `{ Critical = 95.0 }`
+    pub escalate: BTreeMap<Severity, f32>,
+    pub confidence: f32,
+}
+
+impl Rule {
+    fn new(threshold: f32, severity: Severity, confidence: f32) -> Self {
+        Rule { threshold, severity, escalate: BTreeMap::new(), confidence }
+    }
+
+    fn escalate(mut self, severity: Severity, level: f32) -> Self {
+        self.escalate.insert(severity, level);
+        self
+    }
+
+    /// Severity of a value above the threshold
+    pub fn severity_above(&self, value: f32) -> Severity {
+        self.highest(|level| value > level)
+    }
+
+    /// Severity of a value below the threshold, for rules on scores that are bad when low
+    pub fn severity_below(&self, value: f32) -> Severity {
+        self.highest(|level| value < level)
+    }
+
+    fn highest(&self, crossed: impl Fn(f32) -> bool) -> Severity {
+        self.escalate
+            .iter()
+            .filter(|(_, &level)| crossed(level))
+            .map(|(severity, _)| severity)
+            .chain(std::iter::once(&self.severity))
+            .max()
+            .cloned()
+            .unwrap_or(Severity::Informational)
+    }
+}
+
+/// Thresholds of the rules that used to be hard-coded in `FlagDetector`. A `detector.toml` only
+/// lists what it changes; everything else keeps its default:
+///
+/// ```toml
+/// [cpu]
+/// threshold = 80.0                # percent
+/// escalate = { Critical = 90.0 }
+///
+/// [focus]
+/// threshold = 0.25                # flagged below this
+/// severity = "Low"
+/// confidence = 0.7
+/// ```
+///
+/// `stress`, `fatigue` and `mouse_fatigue` are the limits for devices without a calibrated
+/// baseline; a baseline can only raise them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Thresholds {
+    /// CPU usage in percent
+    pub cpu: Rule,
+    /// Memory usage in percent
+    pub memory: Rule,
+    /// Seconds without input
+    pub idle: Rule,
+    /// Focus level, flagged below the threshold
+    pub focus: Rule,
+    /// Keystroke stress indicator
+    pub stress: Rule,
+    /// Keystroke fatigue indicator
+    pub fatigue: Rule,
+    /// Mouse fatigue indicator
+    pub mouse_fatigue: Rule,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            cpu: Rule::new(90.0, Severity::High, 0.95).escalate(Severity::Critical, 95.0),
+            memory: Rule::new(85.0, Severity::High, 0.95).escalate(Severity::Critical, 95.0),
+            idle: Rule::new(300.0, Severity::Low, 0.85),
+            focus: Rule::new(0.3, Severity::Medium, 0.8),
+            stress: Rule::new(0.7, Severity::Medium, 0.75),
+            fatigue: Rule::new(0.8, Severity::Medium, 0.75),
+            mouse_fatigue: Rule::new(0.8, Severity::Medium, 0.7),
+        }
+    }
+}
+
+/// One rule's section in `detector.toml`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleOverride {
+    threshold: Option<f32>,
+    severity: Option<Severity>,
+    escalate: Option<BTreeMap<Severity, f32>>,
+    confidence: Option<f32>,
+}
+
+impl Thresholds {
+    pub const RULES: [&'static str; 7] = ["cpu", "memory", "idle", "focus", "stress", "fatigue", "mouse_fatigue"];
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read detector thresholds {:?}: {}", path, e))?;
+        Self::parse(&content).map_err(|e| format!("Invalid detector thresholds {:?}: {}", path, e))
+    }
+
+    /// The thresholds in `path`, or the defaults when there is no such file
+    pub fn load_or_default(path: &Path) -> Result<Self, String> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Thresholds::default())
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let overrides: BTreeMap<String, RuleOverride> = toml::from_str(content).map_err(|e| e.to_string())?;
+        let mut thresholds = Thresholds::default();
+        for (name, changes) in overrides {
+            let rule = thresholds.rule_mut(&name).ok_or_else(|| {
+                format!("unknown rule {:?} (expected one of {})", name, Self::RULES.join(", "))
+            })?;
+            if let Some(threshold) = changes.threshold {
+                rule.threshold = threshold;
+            }
+            if let Some(severity) = changes.severity {
+                rule.severity = severity;
+            }
+            if let Some(escalate) = changes.escalate {
+                rule.escalate = escalate;
+            }
+            if let Some(confidence) = changes.confidence {
+                rule.confidence = confidence;
+            }
+            if !rule.threshold.is_finite() || rule.escalate.values().any(|level| !level.is_finite()) {
+                return Err(format!("{}: thresholds must be numbers", name));
+            }
+            if !(0.0..=1.0).contains(&rule.confidence) {
+                return Err(format!("{}: confidence must be between 0 and 1, got {}", name, rule.confidence));
+            }
+        }
+        Ok(thresholds)
+    }
+
+    fn rule_mut(&mut self, name: &str) -> Option<&mut Rule> {
+        match name {
+            "cpu" => Some(&mut self.cpu),
+            "memory" => Some(&mut self.memory),
+            "idle" => Some(&mut self.idle),
+            "focus" => Some(&mut self.focus),
+            "stress" => Some(&mut self.stress),
+            "fatigue" => Some(&mut self.fatigue),
+            "mouse_fatigue" => Some(&mut self.mouse_fatigue),
+            _ => None,
+        }
+    }
+}
+
+/// Reload `thresholds` from `path` whenever the file is written, replaced or removed (removing it
+/// goes back to the defaults). A file that does not parse is reported and the thresholds in use
+/// stay as they are.
+pub fn watch(path: PathBuf, thresholds: SharedThresholds) -> Result<(), String> {
+    // Editors save by replacing the file, so its directory is watched rather than the file
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let name = path.file_name().map(|name| name.to_os_string());
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+
+    let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| match res {
+        Ok(event) => {
+            let ours = event.paths.iter().any(|changed| changed.file_name().map(|n| n.to_os_string()) == name);
+            if ours && matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+                let _ = tx.try_send(());
+            }
+        }
+        Err(e) => error!("Watch error: {:?}", e),
+    })
+    .map_err(|e| format!("Failed to watch detector thresholds {:?}: {}", path, e))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch detector thresholds {:?}: {}", path, e))?;
+
+    tokio::spawn(async move {
+        let _watcher = watcher;
+        while rx.recv().await.is_some() {
+            let reloaded = match Thresholds::load_or_default(&path) {
+                Ok(reloaded) => reloaded,
+                Err(e) => {
+                    error!("{}; keeping the thresholds in use", e);
+                    continue;
+                }
+            };
+            let mut current = thresholds.write().unwrap_or_else(|e| e.into_inner());
+            if *current != reloaded {
+                *current = reloaded;
+                info!("🔄 Detector thresholds reloaded from {:?}", path);
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_file_changes_only_what_it_lists() {
+        let thresholds = Thresholds::parse(
+            r#"
+            [cpu]
+            threshold = 80.0
+            escalate = { Critical = 90.0 }
+
+            [focus]
+            severity = "Low"
+            confidence = 0.6
+            "#,
+        )
+        .unwrap();
+
+        let defaults = Thresholds::default();
+        assert_eq!(thresholds.cpu.threshold, 80.0);
+        assert_eq!(thresholds.cpu.confidence, defaults.cpu.confidence);
+        assert_eq!(thresholds.focus.threshold, defaults.focus.threshold);
+        assert_eq!(thresholds.focus.severity, Severity::Low);
+        assert_eq!(thresholds.memory, defaults.memory);
+    }
+
+    #[test]
+    fn escalations_pick_the_highest_severity_crossed() {
+        let rule = Rule::new(90.0, Severity::Medium, 0.9).escalate(Severity::High, 93.0).escalate(Severity::Critical, 96.0);
+        assert_eq!(rule.severity_above(91.0), Severity::Medium);
+        assert_eq!(rule.severity_above(94.0), Severity::High);
+        assert_eq!(rule.severity_above(99.0), Severity::Critical);
+
+        let focus = Rule::new(0.3, Severity::Medium, 0.8).escalate(Severity::High, 0.1);
+        assert_eq!(focus.severity_below(0.2), Severity::Medium);
+        assert_eq!(focus.severity_below(0.05), Severity::High);
+    }
+
+    #[test]
+    fn mistakes_are_rejected() {
+        assert!(Thresholds::parse("[gpu]\nthreshold = 50.0").is_err());
+        assert!(Thresholds::parse("[cpu]\nthreshhold = 50.0").is_err());
+        assert!(Thresholds::parse("[cpu]\nseverity = \"Urgent\"").is_err());
+        assert!(Thresholds::parse("[cpu]\nescalate = { Severe = 99.0 }").is_err());
+        assert!(Thresholds::parse("[idle]\nconfidence = 1.5").is_err());
+        assert_eq!(Thresholds::parse("").unwrap(), Thresholds::default());
+    }
+}
@@ -0,0 +1,60 @@
+/*
+ * EyeCore collection engine, embedded (cargo build --release --features ffi).
+ * Declarations for eyecore.dll / libeyecore.so; see src/ffi.rs.
+ *
+ * Snapshots are UTF-8 JSON in the shape served by /data/latest. Strings returned by the library
+ * are owned by the caller and must be released with eyecore_string_free. One EyeCore handle must
+ * not be used from several threads at once.
+ */
+#ifndef EYECORE_H
+#define EYECORE_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define EYECORE_ABI_VERSION 2
+
+#define EYECORE_OK 0
+#define EYECORE_ERR_NULL (-1)
+#define EYECORE_ERR_RUNNING (-2)
+#define EYECORE_ERR_NOT_RUNNING (-3)
+#define EYECORE_ERR_INVALID_ARGUMENT (-4)
+#define EYECORE_ERR_PANIC (-5)
+#define EYECORE_ERR_IO (-6)
+
+typedef struct EyeCore EyeCore;
+
+/* Compare with EYECORE_ABI_VERSION after loading the library */
+uint32_t eyecore_abi_version(void);
+
+/* Privacy-sensitive modules follow the recorded consent; NULL if the collector's runtime cannot be
+ * started or the configuration is broken */
+EyeCore *eyecore_create(void);
+/* Stops collection and frees the handle; NULL is ignored */
+void eyecore_destroy(EyeCore *core);
+
+/* Collect every interval_ms milliseconds on a background thread */
+int32_t eyecore_start(EyeCore *core, uint32_t interval_ms);
+/* Returns once no collection is running */
+int32_t eyecore_stop(EyeCore *core);
+
+/* Collect one snapshot now (blocks about a second the first time); NULL on failure */
+char *eyecore_collect_once(EyeCore *core);
+/* Latest snapshot, NULL before the first collection */
+char *eyecore_latest_json(EyeCore *core);
+/* Consent for "voice", "camera", "camera_presence", "camera_cloud", "keystroke", "typed_text",
+ * "screen_text" or "files", recorded in consent.json; EYECORE_ERR_IO if it cannot be saved */
+int32_t eyecore_enable_module(EyeCore *core, const char *module);
+int32_t eyecore_disable_module(EyeCore *core, const char *module);
+/* Consent per module and its history, the shape of /consent */
+char *eyecore_consent_json(EyeCore *core);
+void eyecore_string_free(char *json);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* EYECORE_H */
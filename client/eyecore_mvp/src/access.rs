@@ -0,0 +1,295 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    extract::ConnectInfo,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use log::{error, info, warn};
+use serde_json::json;
+use std::net::SocketAddr;
+
+use crate::api::AppState;
+use crate::models::{ContentAccessEvent, EyeCoreData};
+
+/// API tiers: metrics are numbers and labels, content is what the user typed, saw or said, and
+/// admin changes what is collected or sends data off the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Metrics,
+    Content,
+    Admin,
+}
+
+struct ApiKey {
+    requester: String,
+    key: String,
+    scope: Scope,
+}
+
+impl ApiKey {
+    /// Every key can read metrics; content and admin need a key of that tier
+    fn grants(&self, scope: Scope) -> bool {
+        self.scope == scope || scope == Scope::Metrics
+    }
+}
+
+/// Why a request was turned away, and by whom if the key was recognised
+pub struct Denied {
+    pub status: StatusCode,
+    pub requester: Option<String>,
+    pub reason: &'static str,
+}
+
+/// Bearer keys for the three API tiers. Content and admin keys also grant metrics access.
+#[derive(Default)]
+pub struct AccessControl {
+    keys: Vec<ApiKey>,
+}
+
+impl AccessControl {
+    /// EYECORE_METRICS_KEYS, EYECORE_CONTENT_KEYS and EYECORE_ADMIN_KEYS, each a comma-separated
+    /// list of `name:key`
+    pub fn from_env() -> Result<Self, String> {
+        let mut keys = Vec::new();
+        for (variable, scope) in [
+            ("EYECORE_METRICS_KEYS", Scope::Metrics),
+            ("EYECORE_CONTENT_KEYS", Scope::Content),
+            ("EYECORE_ADMIN_KEYS", Scope::Admin),
+        ] {
+            let Ok(value) = std::env::var(variable) else {
+                continue;
+            };
+            for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let (requester, key) = entry
+                    .split_once(':')
+                    .map(|(name, key)| (name.trim(), key.trim()))
+                    .filter(|(name, key)| !name.is_empty() && !key.is_empty())
+                    .ok_or_else(|| format!("{} entries must be name:key", variable))?;
+                keys.push(ApiKey { requester: requester.to_string(), key: key.to_string(), scope });
+            }
+        }
+        Ok(AccessControl { keys })
+    }
+
+    /// Metric and admin endpoints stay open (as before) until any key is configured; from then on
+    /// every request needs a key for its tier
+    pub fn is_open(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn content_enabled(&self) -> bool {
+        self.keys.iter().any(|k| k.scope == Scope::Content)
+    }
+
+    pub fn admin_enabled(&self) -> bool {
+        self.keys.iter().any(|k| k.scope == Scope::Admin)
+    }
+
+    /// Name of the requester allowed to use `scope`, from the `Authorization: Bearer` key
+    pub fn authorize(&self, headers: &HeaderMap, scope: Scope) -> Result<String, Denied> {
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim);
+        let key = presented.and_then(|presented| {
+            self.keys
+                .iter()
+                .find(|k| constant_time_eq(k.key.as_bytes(), presented.as_bytes()))
+        });
+
+        match key {
+            Some(key) if key.grants(scope) => Ok(key.requester.clone()),
+            Some(key) => Err(Denied {
+                status: StatusCode::FORBIDDEN,
+                requester: Some(key.requester.clone()),
+                reason: if scope == Scope::Content {
+                    "key does not grant content access"
+                } else {
+                    "key does not grant admin access"
+                },
+            }),
+            None if scope != Scope::Content && self.is_open() && presented.is_none() => Ok("anonymous".to_string()),
+            None if scope == Scope::Content && !self.content_enabled() => Err(Denied {
+                status: StatusCode::FORBIDDEN,
+                requester: None,
+                reason: "content access is disabled (EYECORE_CONTENT_KEYS not set)",
+            }),
+            None if scope == Scope::Admin && !self.admin_enabled() => Err(Denied {
+                status: StatusCode::FORBIDDEN,
+                requester: None,
+                reason: "admin access is disabled (EYECORE_ADMIN_KEYS not set)",
+            }),
+            None => Err(Denied {
+                status: StatusCode::UNAUTHORIZED,
+                requester: None,
+                reason: "missing or invalid API key",
+            }),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn denied_response(denied: &Denied) -> Response {
+    (denied.status, Json(json!({ "error": denied.reason }))).into_response()
+}
+
+/// Route layer for metric-grade endpoints
+pub async fn require_metrics(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    match state.access.authorize(request.headers(), Scope::Metrics) {
+        Ok(_) => next.run(request).await,
+        Err(denied) => denied_response(&denied),
+    }
+}
+
+/// Route layer for endpoints that change settings or send data off the device
+pub async fn require_admin(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    match state.access.authorize(request.headers(), Scope::Admin) {
+        Ok(requester) => {
+            info!("🔑 Admin request by {}: {} {}", requester, request.method(), request.uri().path());
+            next.run(request).await
+        }
+        Err(denied) => {
+            warn!("Rejected admin request to {}: {}", request.uri().path(), denied.reason);
+            denied_response(&denied)
+        }
+    }
+}
+
+/// Route layer for consent: it is given by the person at the machine, never over the network,
+/// whatever key the request carries
+pub async fn require_local(ConnectInfo(peer): ConnectInfo<SocketAddr>, request: Request, next: Next) -> Response {
+    if peer.ip().is_loopback() {
+        next.run(request).await
+    } else {
+        warn!("Rejected {} from {}: consent can only be given locally", request.uri().path(), peer);
+        (StatusCode::FORBIDDEN, Json(json!({ "error": "consent can only be given on this machine" }))).into_response()
+    }
+}
+
+/// Route layer for content-grade endpoints; every attempt, allowed or not, goes to the audit log
+pub async fn require_content(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let endpoint = request.uri().path().to_string();
+    let query = request.uri().query().map(str::to_string);
+
+    let (requester, response) = match state.access.authorize(request.headers(), Scope::Content) {
+        Ok(requester) => {
+            info!("🔐 Content access by {}: {}", requester, endpoint);
+            (requester, next.run(request).await)
+        }
+        Err(denied) => {
+            warn!("Rejected content access to {}: {}", endpoint, denied.reason);
+            (denied.requester.clone().unwrap_or_else(|| "unknown".to_string()), denied_response(&denied))
+        }
+    };
+
+    let event = ContentAccessEvent {
+        timestamp: Utc::now(),
+        requester,
+        endpoint,
+        query,
+        status: response.status().as_u16(),
+    };
+    if let Err(e) = state.storage.save_content_access(&event).await {
+        error!("Failed to write content access audit entry: {}", e);
+    }
+    response
+}
+
+/// Metric-grade copy of a snapshot: typed text, clicked button labels, window titles and screen
+/// text removed
+pub fn metrics_only(data: &EyeCoreData) -> EyeCoreData {
+    let mut data = data.clone();
+    data.process_data.active_window_title.clear();
+    if let Some(keystrokes) = data.keystroke_dynamics.as_mut() {
+        keystrokes.typed_text = None;
+        keystrokes.buttons_clicked.clear();
+    }
+    if let Some(screen) = data.screen_interactions.as_mut() {
+        screen.active_windows.clear();
+        screen.screen_text_snapshot = None;
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(keys: &[(&str, Scope)]) -> AccessControl {
+        AccessControl {
+            keys: keys
+                .iter()
+                .map(|(key, scope)| ApiKey { requester: format!("{}-user", key), key: key.to_string(), scope: *scope })
+                .collect(),
+        }
+    }
+
+    fn bearer(key: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(key) = key {
+            headers.insert(header::AUTHORIZATION, format!("Bearer {}", key).parse().unwrap());
+        }
+        headers
+    }
+
+    fn status(access: &AccessControl, key: Option<&str>, scope: Scope) -> StatusCode {
+        match access.authorize(&bearer(key), scope) {
+            Ok(_) => StatusCode::OK,
+            Err(denied) => denied.status,
+        }
+    }
+
+    #[test]
+    fn without_keys_only_content_is_closed() {
+        let open = access(&[]);
+        assert_eq!(status(&open, None, Scope::Metrics), StatusCode::OK);
+        assert_eq!(status(&open, None, Scope::Admin), StatusCode::OK);
+        assert_eq!(status(&open, None, Scope::Content), StatusCode::FORBIDDEN);
+        assert_eq!(status(&open, Some("guess"), Scope::Metrics), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn any_configured_key_closes_every_tier() {
+        let content_only = access(&[("c", Scope::Content)]);
+        assert_eq!(status(&content_only, None, Scope::Metrics), StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&content_only, None, Scope::Admin), StatusCode::FORBIDDEN);
+        assert_eq!(status(&content_only, Some("c"), Scope::Metrics), StatusCode::OK);
+        assert_eq!(status(&content_only, Some("c"), Scope::Admin), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn keys_grant_their_tier_and_metrics() {
+        let access = access(&[("m", Scope::Metrics), ("c", Scope::Content), ("a", Scope::Admin)]);
+        assert_eq!(status(&access, Some("m"), Scope::Metrics), StatusCode::OK);
+        assert_eq!(status(&access, Some("m"), Scope::Content), StatusCode::FORBIDDEN);
+        assert_eq!(status(&access, Some("m"), Scope::Admin), StatusCode::FORBIDDEN);
+        assert_eq!(status(&access, Some("c"), Scope::Admin), StatusCode::FORBIDDEN);
+        assert_eq!(status(&access, Some("a"), Scope::Admin), StatusCode::OK);
+        assert_eq!(status(&access, Some("a"), Scope::Metrics), StatusCode::OK);
+        assert_eq!(status(&access, Some("a"), Scope::Content), StatusCode::FORBIDDEN);
+        assert_eq!(status(&access, None, Scope::Metrics), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn metrics_copy_drops_the_window_title() {
+        let now = Utc::now();
+        let data: EyeCoreData = serde_json::from_value(json!({
+            "session_id": "test",
+            "timestamp": now,
+            "system_metrics": { "timestamp": now, "cpu_usage": 0.0, "memory_usage": 0.0, "disk_usage": 0.0 },
+            "process_data": { "timestamp": now, "active_process": "excel", "active_window_title": "Payroll.xlsx - Excel", "process_count": 0 },
+            "input_metrics": { "timestamp": now, "mouse_clicks": 0, "keyboard_events": 0, "idle_duration_seconds": 0 },
+            "network_metrics": { "timestamp": now, "bytes_sent": 0, "bytes_received": 0, "active_connections": 0 },
+            "focus_metrics": { "timestamp": now, "focus_level": 0.0, "context_switches": 0, "productive_app_time": 0 },
+        }))
+        .unwrap();
+        assert!(metrics_only(&data).process_data.active_window_title.is_empty());
+    }
+}
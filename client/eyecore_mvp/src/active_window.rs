@@ -0,0 +1,334 @@
+//! The window with keyboard focus, for `process_data`. Each platform has its own backend behind
+//! `ActiveWindowProvider`: Win32 on Windows, EWMH properties on X11 (including XWayland), the
+//! compositor's IPC on Wayland (Sway, Hyprland) and System Events on macOS.
+
+use sysinfo::{Pid, System};
+
+/// Foreground window as reported by the window system
+#[derive(Debug, Clone)]
+pub struct ActiveWindow {
+    pub process: String,           // executable name, or the app name where no PID is exposed
+    pub title: String,
+}
+
+pub trait ActiveWindowProvider: Send + Sync {
+    /// Backend name for logs
+    fn name(&self) -> &'static str;
+
+    /// None when no window has focus or the window system cannot be read
+    fn active_window(&mut self) -> Option<ActiveWindow>;
+}
+
+/// The backend for the running session, if any works here
+pub fn detect() -> Option<Box<dyn ActiveWindowProvider>> {
+    let provider = platform_provider();
+    match &provider {
+        Some(provider) => log::info!("🪟 Active window from {}", provider.name()),
+        None => log::warn!("⚠️ No supported window system, active window will be unknown"),
+    }
+    provider
+}
+
+/// Name of the backend that would be used, without logging it
+pub fn backend() -> Option<&'static str> {
+    platform_provider().map(|provider| provider.name())
+}
+
+fn platform_provider() -> Option<Box<dyn ActiveWindowProvider>> {
+    #[cfg(target_os = "windows")]
+    {
+        Some(Box::new(WindowsProvider { names: ProcessNames::new() }))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // Native Wayland windows are invisible to X11, so the compositor is asked first
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+        if wayland {
+            if let Some(provider) = WaylandProvider::detect() {
+                return Some(Box::new(provider));
+            }
+        }
+        if let Some(provider) = x11_backend::X11Provider::connect() {
+            if wayland {
+                log::warn!("⚠️ Compositor has no supported IPC, only XWayland windows are seen");
+            }
+            return Some(Box::new(provider));
+        }
+        None
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        Some(Box::new(MacProvider))
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+/// Executable names for PIDs reported by the window system
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+struct ProcessNames {
+    system: System,
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+impl ProcessNames {
+    fn new() -> Self {
+        ProcessNames { system: System::new() }
+    }
+
+    fn name(&mut self, pid: u32) -> Option<String> {
+        let pid = Pid::from_u32(pid);
+        self.system.refresh_process(pid);
+        self.system.process(pid).map(|process| process.name().to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+struct WindowsProvider {
+    names: ProcessNames,
+}
+
+#[cfg(target_os = "windows")]
+impl ActiveWindowProvider for WindowsProvider {
+    fn name(&self) -> &'static str {
+        "win32"
+    }
+
+    fn active_window(&mut self) -> Option<ActiveWindow> {
+        use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId};
+
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.0 == 0 {
+            return None;
+        }
+        let mut title = vec![0u16; 512];
+        let len = unsafe { GetWindowTextW(hwnd, &mut title) };
+        let mut pid = 0u32;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+
+        let pid = (pid != 0).then_some(pid);
+        Some(ActiveWindow {
+            process: pid.and_then(|pid| self.names.name(pid)).unwrap_or_else(|| "unknown".to_string()),
+            title: String::from_utf16_lossy(&title[..len.max(0) as usize]),
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod x11_backend {
+    use super::{ActiveWindow, ActiveWindowProvider, ProcessNames};
+    use std::ffi::CString;
+    use std::os::raw::{c_int, c_uchar, c_ulong};
+    use x11::xlib;
+
+    /// Xlib's default handler exits the process, e.g. when the focused window closes mid-read
+    unsafe extern "C" fn ignore_x_error(_display: *mut xlib::Display, _event: *mut xlib::XErrorEvent) -> c_int {
+        0
+    }
+
+    pub struct X11Provider {
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        net_active_window: xlib::Atom,
+        net_wm_name: xlib::Atom,
+        net_wm_pid: xlib::Atom,
+        utf8_string: xlib::Atom,
+        names: ProcessNames,
+    }
+
+    // The display connection is private to the provider and only used through `&mut self`
+    unsafe impl Send for X11Provider {}
+    unsafe impl Sync for X11Provider {}
+
+    impl X11Provider {
+        pub fn connect() -> Option<Self> {
+            unsafe {
+                let display = xlib::XOpenDisplay(std::ptr::null());
+                if display.is_null() {
+                    return None;
+                }
+                xlib::XSetErrorHandler(Some(ignore_x_error));
+                let atom = |name: &str| {
+                    let name = CString::new(name).expect("atom names have no NUL");
+                    xlib::XInternAtom(display, name.as_ptr(), xlib::False)
+                };
+                Some(X11Provider {
+                    display,
+                    root: xlib::XDefaultRootWindow(display),
+                    net_active_window: atom("_NET_ACTIVE_WINDOW"),
+                    net_wm_name: atom("_NET_WM_NAME"),
+                    net_wm_pid: atom("_NET_WM_PID"),
+                    utf8_string: atom("UTF8_STRING"),
+                    names: ProcessNames::new(),
+                })
+            }
+        }
+
+        /// Raw property value: bytes for format 8, one `c_ulong` per item for format 32
+        fn property(&self, window: xlib::Window, property: xlib::Atom, kind: xlib::Atom) -> Option<(c_int, Vec<u8>)> {
+            let mut actual_type: xlib::Atom = 0;
+            let mut format: c_int = 0;
+            let mut items: c_ulong = 0;
+            let mut bytes_after: c_ulong = 0;
+            let mut data: *mut c_uchar = std::ptr::null_mut();
+            unsafe {
+                let status = xlib::XGetWindowProperty(
+                    self.display, window, property, 0, 1024, xlib::False, kind,
+                    &mut actual_type, &mut format, &mut items, &mut bytes_after, &mut data,
+                );
+                if status != xlib::Success as c_int || data.is_null() {
+                    return None;
+                }
+                let item_size = match format {
+                    8 => 1,
+                    16 => std::mem::size_of::<std::os::raw::c_short>(),
+                    _ => std::mem::size_of::<c_ulong>(),
+                };
+                let value = std::slice::from_raw_parts(data, items as usize * item_size).to_vec();
+                xlib::XFree(data.cast());
+                (items > 0).then_some((format, value))
+            }
+        }
+
+        fn cardinal(&self, window: xlib::Window, property: xlib::Atom) -> Option<c_ulong> {
+            let (format, value) = self.property(window, property, xlib::AnyPropertyType as xlib::Atom)?;
+            let bytes = value.get(..std::mem::size_of::<c_ulong>()).filter(|_| format == 32)?;
+            Some(c_ulong::from_ne_bytes(bytes.try_into().ok()?))
+        }
+
+        fn title(&self, window: xlib::Window) -> String {
+            self.property(window, self.net_wm_name, self.utf8_string)
+                .or_else(|| self.property(window, xlib::XA_WM_NAME, xlib::AnyPropertyType as xlib::Atom))
+                .map(|(_, bytes)| String::from_utf8_lossy(&bytes).to_string())
+                .unwrap_or_default()
+        }
+    }
+
+    impl ActiveWindowProvider for X11Provider {
+        fn name(&self) -> &'static str {
+            "x11"
+        }
+
+        fn active_window(&mut self) -> Option<ActiveWindow> {
+            let window = self.cardinal(self.root, self.net_active_window).filter(|window| *window != 0)?;
+            let pid = self.cardinal(window, self.net_wm_pid).map(|pid| pid as u32);
+            Some(ActiveWindow {
+                process: pid.and_then(|pid| self.names.name(pid)).unwrap_or_else(|| "unknown".to_string()),
+                title: self.title(window),
+            })
+        }
+    }
+
+    impl Drop for X11Provider {
+        fn drop(&mut self) {
+            unsafe { xlib::XCloseDisplay(self.display) };
+        }
+    }
+}
+
+/// Wayland has no common protocol for the focused window, so the compositor's own IPC is used
+#[cfg(target_os = "linux")]
+enum WaylandProvider {
+    Sway(ProcessNames),
+    Hyprland(ProcessNames),
+}
+
+#[cfg(target_os = "linux")]
+impl WaylandProvider {
+    fn detect() -> Option<Self> {
+        if std::env::var_os("SWAYSOCK").is_some() {
+            Some(WaylandProvider::Sway(ProcessNames::new()))
+        } else if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            Some(WaylandProvider::Hyprland(ProcessNames::new()))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ActiveWindowProvider for WaylandProvider {
+    fn name(&self) -> &'static str {
+        match self {
+            WaylandProvider::Sway(_) => "sway",
+            WaylandProvider::Hyprland(_) => "hyprland",
+        }
+    }
+
+    fn active_window(&mut self) -> Option<ActiveWindow> {
+        let json = |program: &str, args: &[&str]| -> Option<serde_json::Value> {
+            let output = std::process::Command::new(program).args(args).output().ok()?;
+            serde_json::from_slice(&output.stdout).ok()
+        };
+        // Sway: the focused leaf of the layout tree; Hyprland: `activewindow` is empty ({}) without focus
+        let (window, title_key, app_keys, names) = match self {
+            WaylandProvider::Sway(names) => {
+                (focused_node(&json("swaymsg", &["-t", "get_tree"])?)?.clone(), "name", ["app_id", "class"], names)
+            }
+            WaylandProvider::Hyprland(names) => (json("hyprctl", &["activewindow", "-j"])?, "title", ["class", "initialClass"], names),
+        };
+        let pid = window["pid"].as_u64().filter(|pid| *pid > 0).map(|pid| pid as u32);
+        // XWayland windows in Sway keep their class under window_properties
+        let app = app_keys
+            .iter()
+            .find_map(|key| window[key].as_str().or_else(|| window["window_properties"][key].as_str()))
+            .filter(|app| !app.is_empty());
+        Some(ActiveWindow {
+            process: pid
+                .and_then(|pid| names.name(pid))
+                .or(app.map(str::to_string))
+                .unwrap_or_else(|| "unknown".to_string()),
+            title: window[title_key].as_str()?.to_string(),
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn focused_node(node: &serde_json::Value) -> Option<&serde_json::Value> {
+    let children = ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten();
+    let mut has_children = false;
+    for child in children {
+        has_children = true;
+        if let Some(focused) = focused_node(child) {
+            return Some(focused);
+        }
+    }
+    (!has_children && node["focused"].as_bool() == Some(true)).then_some(node)
+}
+
+/// Reads the frontmost app through System Events; the window title needs the Accessibility permission
+#[cfg(target_os = "macos")]
+struct MacProvider;
+
+#[cfg(target_os = "macos")]
+impl ActiveWindowProvider for MacProvider {
+    fn name(&self) -> &'static str {
+        "system_events"
+    }
+
+    fn active_window(&mut self) -> Option<ActiveWindow> {
+        const SCRIPT: &str = r#"tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    set windowTitle to ""
+    try
+        set windowTitle to name of front window of frontApp
+    end try
+    return (name of frontApp) & tab & windowTitle
+end tell"#;
+        let output = std::process::Command::new("osascript").args(["-e", SCRIPT]).output().ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let mut fields = text.trim_end_matches('\n').splitn(2, '\t');
+        Some(ActiveWindow {
+            process: fields.next().filter(|name| !name.is_empty())?.to_string(),
+            title: fields.next().unwrap_or_default().to_string(),
+        })
+    }
+}
@@ -1 +1,64 @@
 pub mod handlers;
+
+use axum::extract::FromRef;
+use flag_detection::thresholds::SharedThresholds;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use crate::access::AccessControl;
+use crate::calibration::Calibration;
+use crate::compliance::ProcessingContext;
+use crate::config::EffectiveConfig;
+use crate::consent::ConsentManager;
+use crate::data_collector::DataCollector;
+use crate::gaze::GazeCalibrator;
+use crate::goals::GoalBook;
+use crate::heart_rate::HeartRateMonitor;
+use crate::maintenance::Maintenance;
+use crate::module_control::ModuleControl;
+use crate::permissions::PermissionChecker;
+use crate::scheduler::Scheduler;
+use crate::input_trace::InputTrace;
+use crate::websocket_client::WebSocketClient;
+use crate::discovery::Discovery;
+use crate::onboarding::Onboarding;
+use crate::storage::DataStorage;
+use crate::storage::retention::RetentionManager;
+use crate::team::TeamMode;
+use crate::time_export::TimeExporter;
+use crate::updater::Updater;
+use crate::version::UpdateStatus;
+
+/// Shared state for all API handlers
+#[derive(Clone)]
+pub struct AppState {
+    pub collector: Arc<RwLock<DataCollector>>,
+    pub storage: Arc<DataStorage>,
+    pub update_status: Arc<RwLock<UpdateStatus>>,
+    pub updater: Option<Arc<Updater>>,
+    pub discovery: Option<Arc<Discovery>>,
+    pub team: Option<Arc<TeamMode>>,
+    pub processing: Arc<ProcessingContext>,
+    pub heart_rate: Option<Arc<HeartRateMonitor>>,
+    pub access: Arc<AccessControl>,
+    pub onboarding: Arc<RwLock<Onboarding>>,
+    pub consent: Arc<RwLock<ConsentManager>>,
+    pub calibration: Arc<RwLock<Calibration>>,
+    pub gaze_calibration: Arc<RwLock<GazeCalibrator>>,
+    pub config: Arc<EffectiveConfig>,
+    pub module_control: Arc<RwLock<ModuleControl>>,
+    pub permissions: Arc<PermissionChecker>,
+    pub input_trace: Option<Arc<InputTrace>>,
+    pub upload: Arc<WebSocketClient>,
+    pub maintenance: Arc<RwLock<Maintenance>>,
+    pub retention: Arc<RwLock<RetentionManager>>,
+    pub goals: Arc<RwLock<GoalBook>>,
+    pub time_export: Arc<RwLock<TimeExporter>>,
+    pub detector_thresholds: Option<SharedThresholds>,
+    pub scheduler: Arc<Scheduler>,
+}
+
+impl FromRef<AppState> for Arc<RwLock<DataCollector>> {
+    fn from_ref(state: &AppState) -> Self {
+        Arc::clone(&state.collector)
+    }
+}
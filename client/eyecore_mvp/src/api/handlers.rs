@@ -1,18 +1,147 @@
 use axum::{
-    extract::{State, Query},
-    http::StatusCode,
+    extract::{Path, State, Query},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
 use serde_json::json;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use crate::access;
+use crate::bookmarks;
+use crate::calibration;
+use crate::comparison;
+use crate::consent;
+use crate::download;
+use crate::flags;
+use crate::gaze;
+use crate::goals;
+use crate::api::AppState;
 use crate::data_collector::DataCollector;
+use crate::models::{CollectorUpdate, ConsentChange, DriftReport, EyeCoreData, ModuleUpdate, NetworkCategory, NewGoal, Productivity, Projects, SessionNote};
+use crate::compliance;
+use crate::heart_rate::{self, HeartRateSample};
+use crate::module_control;
+use crate::permissions;
+use crate::query;
+use crate::sampling::{self, Stratify};
+use crate::share::{self, ShareSelection};
+use crate::team;
+use crate::timeline;
+use crate::time_export;
+use crate::models::TimeEntryStatus;
+use crate::version::BuildInfo;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
 pub struct HistoryQuery {
     limit: Option<usize>,
+    cursor: Option<String>,  // `next_cursor` from the previous page
+    fields: Option<String>,  // comma-separated, e.g. "system_metrics.cpu_usage,focus_metrics"
+}
+
+#[derive(Deserialize)]
+pub struct DoNotDisturbRequest {
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SqlQueryRequest {
+    sql: String,
+    date: Option<String>,     // last day of the queried range (YYYY-MM-DD), defaults to today
+    days: Option<u32>,        // number of days loaded into the tables, default 7
+    max_rows: Option<usize>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct CalibrationRequest {
+    minutes: Option<i64>,   // session length, default 10
+}
+
+#[derive(Deserialize)]
+pub struct GazeCalibrationRequest {
+    screen_width: u32,      // pixels of the screen the prompts are shown on
+    screen_height: u32,
+}
+
+#[derive(Deserialize)]
+pub struct GazePointRequest {
+    x: f32,                 // prompt position in screen pixels
+    y: f32,
+}
+
+#[derive(Deserialize)]
+pub struct OnboardingAnswerRequest {
+    step: String,     // `step.id` from `/onboarding/state`
+    answer: String,
+}
+
+#[derive(Deserialize)]
+pub struct NetworkLabelRequest {
+    category: NetworkCategory,   // "home", "office", "public", or "unknown" to remove the label
+}
+
+#[derive(Deserialize)]
+pub struct AppClassificationRequest {
+    // app or site -> "productive", "neutral" or "distracting"; replaces all previous overrides
+    overrides: std::collections::BTreeMap<String, Productivity>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ProjectConfirmation {
+    name: Option<String>,      // rename to this; the name of another project merges the two
+}
+
+#[derive(Deserialize)]
+pub struct MaintenanceRequest {
+    active: bool,
+    reason: Option<String>,    // e.g. "disk migration", shown in /maintenance
+}
+
+#[derive(Deserialize, Default)]
+pub struct BookmarkRequest {
+    note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NotesQuery {
+    date: Option<String>,
+    days: Option<u32>,
+    session_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NoteRequest {
+    text: String,
+    session_id: Option<String>,
+    start: Option<chrono::DateTime<chrono::Utc>>, // defaults to now
+    end: Option<chrono::DateTime<chrono::Utc>>,   // defaults to `start`
+}
+
+#[derive(Deserialize)]
+pub struct SampleQuery {
+    stratify_by: Option<String>, // flag_type (default), severity or none
+    n: Option<usize>,            // default 100, max 1000
+    date: Option<String>,        // last day sampled from (YYYY-MM-DD), defaults to today
+    days: Option<u32>,           // default 7, max 31
+    context_secs: Option<i64>,   // include same-session snapshots this close to each sample, max 120
+    seed: Option<u64>,           // repeat a previous sample; random when omitted
+}
+
+#[derive(Deserialize)]
+pub struct SwitchesQuery {
+    minutes: Option<i64>,        // window for the switches and cadence, default 5, max 60
+}
+
+#[derive(Deserialize)]
+pub struct HeartRateIngest {
+    samples: Vec<HeartRateSample>,
+}
+
+#[derive(Deserialize)]
+pub struct DateRangeQuery {
+    date: Option<String>, // YYYY-MM-DD, defaults to today
+    days: Option<u32>,    // number of days ending at `date`, default 1
 }
 
 pub async fn health() -> impl IntoResponse {
@@ -21,23 +150,104 @@ pub async fn health() -> impl IntoResponse {
 
 pub async fn get_latest_data(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    match collector.get_latest_data() {
-        Some(data) => (StatusCode::OK, Json(serde_json::to_value(data).unwrap_or(serde_json::Value::Null))).into_response(),
+    match collector.latest_with_sequence() {
+        Some((sequence, data)) => {
+            let validators = snapshot_validators(sequence, data);
+            if is_not_modified(&headers, &validators) {
+                return (StatusCode::NOT_MODIFIED, validators).into_response();
+            }
+            (StatusCode::OK, validators, Json(access::metrics_only(data))).into_response()
+        }
         None => StatusCode::NO_CONTENT.into_response(),
     }
 }
 
+/// ETag (collector session + snapshot sequence number) and Last-Modified for the latest snapshot
+fn snapshot_validators(sequence: usize, data: &EyeCoreData) -> [(header::HeaderName, String); 2] {
+    [
+        (header::ETAG, format!("\"{}-{}\"", &data.session_id[..8], sequence)),
+        (header::LAST_MODIFIED, data.timestamp.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+    ]
+}
+
+/// True when the client's If-None-Match already names the current ETag
+fn is_not_modified(headers: &HeaderMap, validators: &[(header::HeaderName, String); 2]) -> bool {
+    let etag = &validators[0].1;
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|tags| {
+            tags.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == etag)
+        })
+}
+
+/// One section of the latest snapshot, with the same caching validators as /data/latest
+fn latest_section<'a, T: serde::Serialize>(
+    collector: &'a DataCollector,
+    headers: &HeaderMap,
+    section: impl FnOnce(&'a EyeCoreData) -> Option<T>,
+    unavailable: &str,
+) -> axum::response::Response {
+    let Some((sequence, data)) = collector.latest_with_sequence() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let validators = snapshot_validators(sequence, data);
+    if is_not_modified(headers, &validators) {
+        return (StatusCode::NOT_MODIFIED, validators).into_response();
+    }
+    match section(data) {
+        Some(value) => (StatusCode::OK, validators, Json(value)).into_response(),
+        None => (StatusCode::NO_CONTENT, Json(json!({"message": unavailable}))).into_response(),
+    }
+}
+
 pub async fn get_history(
     State(collector): State<Arc<RwLock<DataCollector>>>,
     Query(query): Query<HistoryQuery>,
 ) -> impl IntoResponse {
+    let before = match query.cursor.as_deref().map(str::parse::<usize>) {
+        None => None,
+        Some(Ok(sequence)) => Some(sequence),
+        Some(Err(_)) => return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid cursor"}))).into_response(),
+    };
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    // "system_metrics.cpu_usage" -> "/system_metrics/cpu_usage"; timestamp is always kept
+    let fields: Option<Vec<String>> = query.fields.as_deref().map(|fields| {
+        fields
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(|field| format!("/{}", field.trim_start_matches('/').replace('.', "/")))
+            .chain(std::iter::once("/timestamp".to_string()))
+            .collect()
+    });
+    
     let collector = collector.read().await;
-    let limit = query.limit.unwrap_or(100);
-    let history = collector.get_history(limit);
+    let (page, has_more) = collector.get_history_page(before, limit);
+    let next_cursor = if has_more { page.last().map(|(sequence, _)| sequence.to_string()) } else { None };
+    
+    let snapshots: Vec<serde_json::Value> = page
+        .into_iter()
+        .map(|(_, data)| {
+            let value = serde_json::to_value(access::metrics_only(data)).unwrap_or_default();
+            match &fields {
+                Some(fields) => crate::utils::project_json(&value, fields),
+                None => value,
+            }
+        })
+        .collect();
     
-    (StatusCode::OK, Json(history))
+    (StatusCode::OK, Json(json!({
+        "count": snapshots.len(),
+        "snapshots": snapshots,
+        "next_cursor": next_cursor,
+    }))).into_response()
 }
 
 pub async fn get_stats(
@@ -50,197 +260,1435 @@ pub async fn get_stats(
 }
 
 pub async fn get_status(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut status = state.collector.read().await.get_status();
+    
+    let update = state.update_status.read().await;
+    status.update_available = update.update_available;
+    status.latest_version = update.latest_version.clone();
+    
+    (StatusCode::OK, Json(status))
+}
+
+pub async fn get_coverage(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    
+    match crate::reports::coverage_for_dates(&state.storage, &dates).await {
+        Ok(coverage) => {
+            let total_uncovered: u64 = coverage.iter().map(|c| c.uncovered_seconds).sum();
+            (StatusCode::OK, Json(json!({
+                "days": coverage,
+                "total_uncovered_seconds": total_uncovered,
+            }))).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+    }
+}
+
+/// Drift between measured collectors and their simulations over the requested days
+pub async fn get_collector_comparison(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    
+    let mut snapshots = Vec::new();
+    for date in &dates {
+        match state.storage.load_snapshots(*date).await {
+            Ok(day) => snapshots.extend(day),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+    let (fields, snapshots_compared) = comparison::drift(&snapshots);
+    let report = DriftReport {
+        enabled: state.config.config.compare_collectors,
+        days: dates.iter().map(|date| date.format("%Y-%m-%d").to_string()).collect(),
+        snapshots_compared,
+        fields,
+    };
+    (StatusCode::OK, Json(report)).into_response()
+}
+
+/// Entries of the metrics-only mirror over the requested days, oldest first
+pub async fn get_metrics_mirror(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    
+    let mut entries = Vec::new();
+    for date in &dates {
+        match state.storage.load_metrics_mirror(*date).await {
+            Ok(day) => entries.extend(day),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+    (StatusCode::OK, Json(json!({
+        "enabled": state.config.config.metrics_mirror,
+        "entries": entries,
+    }))).into_response()
+}
+
+/// Idle sessions that ended on the requested days, oldest first
+pub async fn get_idle_sessions(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    
+    let mut sessions = Vec::new();
+    for date in &dates {
+        match state.storage.load_idle_sessions(*date).await {
+            Ok(day) => sessions.extend(day),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+    let total_idle_seconds: u64 = sessions.iter().map(|session| session.duration_seconds).sum();
+    (StatusCode::OK, Json(json!({
+        "count": sessions.len(),
+        "total_idle_seconds": total_idle_seconds,
+        "sessions": sessions,
+    }))).into_response()
+}
+
+/// Days covered by a `date`/`days` query, oldest first
+fn date_range(query: &DateRangeQuery) -> Result<Vec<chrono::NaiveDate>, (StatusCode, Json<serde_json::Value>)> {
+    let end_date = match query.date.as_deref() {
+        Some(date) => match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => return Err((StatusCode::BAD_REQUEST, Json(json!({"error": "date must be YYYY-MM-DD"})))),
+        },
+        None => chrono::Utc::now().date_naive(),
+    };
+    let days = query.days.unwrap_or(1).clamp(1, 366);
+    Ok((0..days)
+        .rev()
+        .map(|offset| end_date - chrono::Duration::days(offset as i64))
+        .collect())
+}
+
+pub async fn get_privacy_activity(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    
+    match crate::reports::capture_activity_for_dates(&state.storage, &dates).await {
+        Ok(days) => (StatusCode::OK, Json(json!({ "days": days }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+    }
+}
+
+/// Recorded audio segments with their sidecar metadata
+pub async fn get_audio_segments(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    
+    let mut segments = Vec::new();
+    for date in dates {
+        match state.storage.load_audio_metadata(date).await {
+            Ok(day) => segments.extend(day),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+    
+    (StatusCode::OK, Json(json!({
+        "count": segments.len(),
+        "segments": segments,
+    }))).into_response()
+}
+
+pub async fn query_sql(
+    State(state): State<AppState>,
+    Json(request): Json<SqlQueryRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = query::validate_sql(&request.sql) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e })));
+    }
+    
+    let end = match &request.date {
+        Some(date) => match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Invalid date, expected YYYY-MM-DD" }))),
+        },
+        None => chrono::Utc::now().date_naive(),
+    };
+    let days = request.days.unwrap_or(7);
+    let max_rows = request.max_rows.unwrap_or(query::DEFAULT_MAX_ROWS).min(query::MAX_ROWS_LIMIT);
+    
+    // Only the tables the query names are loaded
+    let mut tables = query::QueryTables::default();
+    if query::references(&request.sql, query::SNAPSHOTS_TABLE) {
+        match query::load_snapshot_table(&state.storage, end, days.min(query::MAX_SNAPSHOT_DAYS)).await {
+            Ok(snapshots) => tables.snapshots = snapshots,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+        }
+    }
+    if query::references(&request.sql, query::HOURLY_TABLE) {
+        match query::load_hourly_table(&state.storage, end, days).await {
+            Ok(hourly) => tables.hourly = hourly,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+        }
+    }
+    
+    // Query execution is CPU-bound, keep it off the async workers; it stops itself at the deadline
+    let sql = request.sql.clone();
+    let task = tokio::task::spawn_blocking(move || query::execute(&sql, &tables, max_rows, query::QUERY_TIMEOUT));
+    match task.await {
+        Ok(Ok(result)) => (StatusCode::OK, Json(json!(result))),
+        Ok(Err(e @ query::QueryError::TimedOut)) => (StatusCode::GATEWAY_TIMEOUT, Json(json!({ "error": e.to_string() }))),
+        Ok(Err(e)) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e.to_string() }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+/// Stratified random sample of stored snapshots with empty label fields, for building training sets
+pub async fn get_samples(
+    State(state): State<AppState>,
+    Query(query): Query<SampleQuery>,
+) -> impl IntoResponse {
+    let stratify = match Stratify::parse(query.stratify_by.as_deref().unwrap_or("flag_type")) {
+        Ok(stratify) => stratify,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    };
+    let end = match &query.date {
+        Some(date) => match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Invalid date, expected YYYY-MM-DD" }))),
+        },
+        None => chrono::Utc::now().date_naive(),
+    };
+    let days = query.days.unwrap_or(7).clamp(1, query::MAX_DAYS);
+    let n = query.n.unwrap_or(sampling::DEFAULT_SAMPLES).clamp(1, sampling::MAX_SAMPLES);
+    
+    let snapshots = match query::load_snapshot_table(&state.storage, end, days).await {
+        Ok(snapshots) => snapshots,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+    let start = (end - chrono::Duration::days(days as i64 - 1)).and_hms_opt(0, 0, 0).unwrap().and_utc();
+    // Flags trail their snapshot by a moment, so look a little past the end of the range
+    let until = (end + chrono::Duration::days(1)).and_hms_opt(0, 1, 0).unwrap().and_utc();
+    let flags = match state.storage.load_flags(start, until).await {
+        Ok(flags) => flags,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+    
+    let seed = query.seed.unwrap_or_else(rand::random);
+    let set = sampling::stratified_sample(snapshots, &flags, stratify, n, query.context_secs.unwrap_or(0), seed);
+    (StatusCode::OK, Json(json!(set)))
+}
+
+pub async fn get_compliance_register(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let collector = state.collector.read().await;
+    let register = compliance::build_register(&state.processing, &collector.enabled_modules(), collector.sends_camera_frames());
+    (StatusCode::OK, Json(json!(register)))
+}
+
+/// Configuration in effect: the config file's values with environment overrides applied
+pub async fn get_config(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(json!(&*state.config)))
+}
+
+/// Disk usage per category, the retention limits and the space the last runs reclaimed
+pub async fn get_storage_stats(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.retention.read().await.stats(&state.storage).await {
+        Ok(stats) => (StatusCode::OK, Json(json!(stats))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))),
+    }
+}
+
+pub async fn get_encryption_status(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(json!(state.storage.encryption_status())))
+}
+
+/// Switch to a new encryption key and re-encrypt the stored files with it
+pub async fn rotate_encryption_key(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.storage.rotate_encryption_key().await {
+        Ok(rotation) => (StatusCode::OK, Json(json!(rotation))),
+        Err(e) if e.kind() == std::io::ErrorKind::Unsupported => (StatusCode::CONFLICT, Json(json!({"error": e.to_string()}))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))),
+    }
+}
+
+/// Maintenance mode keeps the dataset frozen, so requests that add to it are refused
+async fn refuse_in_maintenance(state: &AppState) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    state.maintenance.write().await.active().then(|| {
+        (StatusCode::CONFLICT, Json(json!({ "error": "Maintenance mode is on, collection is paused" })))
+    })
+}
+
+pub async fn get_maintenance(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(json!(state.maintenance.write().await.status())))
+}
+
+/// Pause or resume collection (`{"active": true, "reason": "..."}`)
+pub async fn set_maintenance(
+    State(state): State<AppState>,
+    Json(request): Json<MaintenanceRequest>,
+) -> impl IntoResponse {
+    match state.maintenance.write().await.set(request.active, request.reason, "api") {
+        Ok(status) => (StatusCode::OK, Json(json!(status))),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
+}
+
+/// Samples pushed by a Health Connect/HealthKit bridge or other wearable relay
+pub async fn ingest_heart_rate(
+    State(state): State<AppState>,
+    Json(request): Json<HeartRateIngest>,
+) -> impl IntoResponse {
+    if let Some(refusal) = refuse_in_maintenance(&state).await {
+        return refusal;
+    }
+    let Some(monitor) = &state.heart_rate else {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "Heart-rate collection is not enabled" })));
+    };
+    
+    if let Err(e) = state.storage.save_heart_rate_samples(&request.samples).await {
+        log::error!("Failed to save heart-rate samples: {}", e);
+    }
+    let accepted = monitor.ingest(request.samples).await;
+    (StatusCode::OK, Json(json!({ "accepted": accepted })))
+}
+
+pub async fn get_stress_calibration(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let history = state.collector.read().await.get_history(usize::MAX);
+    (StatusCode::OK, Json(json!(heart_rate::calibrate_stress(&history))))
+}
+
+/// First-run onboarding progress and the question it is waiting on
+pub async fn get_onboarding_state(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut onboarding = state.onboarding.write().await;
+    onboarding.poll();
+    (StatusCode::OK, Json(onboarding.status()))
+}
+
+/// Answer the current onboarding step; answers for any other step are rejected as stale
+pub async fn answer_onboarding(
+    State(state): State<AppState>,
+    Json(request): Json<OnboardingAnswerRequest>,
+) -> impl IntoResponse {
+    let mut onboarding = state.onboarding.write().await;
+    let current = onboarding.status().step.id;
+    if request.step != current {
+        return (StatusCode::CONFLICT, Json(json!({
+            "error": format!("\"{}\" is not the current onboarding step", request.step),
+            "step": current,
+        })));
+    }
+    let mut collector = state.collector.write().await;
+    let mut consent = state.consent.write().await;
+    let mut calibration = state.calibration.write().await;
+    match onboarding.answer(&request.answer, &mut collector, &mut consent, &mut calibration) {
+        Ok(status) => (StatusCode::OK, Json(json!(status))),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
+}
+
+/// Running calibration session and the recorded baseline with its quality
+pub async fn get_calibration_status(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(state.calibration.read().await.status()))
+}
+
+/// Start a calibration session; the body (`{"minutes": 10}`) is optional
+pub async fn start_calibration(
+    State(state): State<AppState>,
+    request: Option<Json<CalibrationRequest>>,
+) -> impl IntoResponse {
+    if let Some(refusal) = refuse_in_maintenance(&state).await {
+        return refusal;
+    }
+    let minutes = request
+        .and_then(|Json(request)| request.minutes)
+        .unwrap_or(calibration::DEFAULT_CALIBRATION_MINUTES);
+    if !(1..=calibration::MAX_CALIBRATION_MINUTES).contains(&minutes) {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": format!("minutes must be between 1 and {}", calibration::MAX_CALIBRATION_MINUTES),
+        })));
+    }
+    match state.calibration.write().await.start(minutes) {
+        Ok(status) => (StatusCode::OK, Json(json!(status))),
+        Err(e) => (StatusCode::CONFLICT, Json(json!({ "error": e }))),
+    }
+}
+
+/// Running gaze calibration session, where to show its prompts, and the calibration in use
+pub async fn get_gaze_calibration(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(state.gaze_calibration.read().await.status()))
+}
+
+/// The camera, with an emotion backend grabbing frames, or why gaze cannot be calibrated
+async fn gaze_camera(state: &AppState) -> Result<Arc<crate::emotion::EmotionDetector>, (StatusCode, Json<serde_json::Value>)> {
+    match state.collector.read().await.emotion_detector() {
+        Some(detector) if detector.is_active() && detector.is_presence_only() => {
+            Err((StatusCode::CONFLICT, Json(json!({ "error": "The camera only reports face presence (camera_presence consent)" }))))
+        }
+        Some(detector) if detector.is_active() => Ok(detector),
+        Some(_) => Err((StatusCode::CONFLICT, Json(json!({ "error": "The camera module is disabled or lacks consent" })))),
+        None => Err((StatusCode::CONFLICT, Json(json!({
+            "error": "Gaze calibration needs a camera backend ([emotion] backend = \"onnx\" or \"cloud\")",
+        })))),
+    }
+}
+
+/// Start a gaze calibration session (`{"screen_width": 1920, "screen_height": 1080}`)
+pub async fn start_gaze_calibration(
+    State(state): State<AppState>,
+    Json(request): Json<GazeCalibrationRequest>,
+) -> impl IntoResponse {
+    if let Err(refusal) = gaze_camera(&state).await {
+        return refusal;
+    }
+    match state.gaze_calibration.write().await.start(request.screen_width, request.screen_height) {
+        Ok(status) => (StatusCode::OK, Json(json!(status))),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
+}
+
+/// Record the camera's gaze estimate while the user looks at the prompt at `{"x", "y"}`
+pub async fn record_gaze_point(
+    State(state): State<AppState>,
+    Json(request): Json<GazePointRequest>,
+) -> impl IntoResponse {
+    let detector = match gaze_camera(&state).await {
+        Ok(detector) => detector,
+        Err(refusal) => return refusal,
+    };
+    if !state.gaze_calibration.read().await.status().active {
+        return (StatusCode::CONFLICT, Json(json!({ "error": "No gaze calibration is running" })));
+    }
+
+    let requested = std::time::Instant::now();
+    detector.request_frame();
+    let reading = loop {
+        if let Some(reading) = detector.latest_since(requested) {
+            break reading;
+        }
+        if requested.elapsed() >= gaze::SAMPLE_TIMEOUT {
+            return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({
+                "error": format!("No camera frame within {}s", gaze::SAMPLE_TIMEOUT.as_secs()),
+            })));
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    };
+    let Some((u, v)) = reading.gaze else {
+        let error = if reading.face_detected() { "The camera backend gave no gaze estimate" } else { "No face in the camera frame" };
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": error })));
+    };
+    match state.gaze_calibration.write().await.record([request.x, request.y], [u, v]) {
+        Ok(status) => (StatusCode::OK, Json(json!(status))),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
+}
+
+/// Fit the calibration from the recorded samples and map gaze with it from now on
+pub async fn finish_gaze_calibration(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let calibration = state.gaze_calibration.write().await.finish();
+    match calibration {
+        Ok(calibration) => {
+            state.collector.write().await.set_gaze_calibration(Some(calibration.clone()));
+            (StatusCode::OK, Json(json!(calibration)))
+        }
+        Err(e) => (StatusCode::CONFLICT, Json(json!({ "error": e }))),
+    }
+}
+
+pub async fn get_version(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let update = state.update_status.read().await.clone();
+    
+    (StatusCode::OK, Json(json!({
+        "build": BuildInfo::current(),
+        "update": update,
+    })))
+}
+
+pub async fn get_discovery_peers(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match &state.discovery {
+        Some(discovery) => (StatusCode::OK, Json(json!({
+            "enabled": true,
+            "peers": discovery.peers().await,
+        }))),
+        None => (StatusCode::OK, Json(json!({
+            "enabled": false,
+            "peers": [],
+        }))),
+    }
+}
+
+pub async fn get_team_status(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let Some(team) = &state.team else {
+        return (StatusCode::OK, Json(json!({
+            "enabled": false,
+            "teammates": [],
+        })));
+    };
+    
+    let latest = state.collector.read().await.get_latest_data();
+    (StatusCode::OK, Json(json!({
+        "enabled": true,
+        "self": team.local_status(latest.as_ref()).await,
+        "teammates": team.members().await,
+    })))
+}
+
+pub async fn set_do_not_disturb(
+    State(state): State<AppState>,
+    Json(request): Json<DoNotDisturbRequest>,
+) -> impl IntoResponse {
+    match &state.team {
+        Some(team) => {
+            team.set_do_not_disturb(request.enabled).await;
+            (StatusCode::OK, Json(json!({ "do_not_disturb": request.enabled })))
+        }
+        None => (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "Team mode is not enabled" }))),
+    }
+}
+
+/// Label the connected Wi-Fi network so reports and flags can tell locations apart
+pub async fn label_network(
+    State(state): State<AppState>,
+    Json(request): Json<NetworkLabelRequest>,
+) -> impl IntoResponse {
+    match state.collector.write().await.label_network(request.category) {
+        Ok(network) => (StatusCode::OK, Json(json!(network))),
+        Err(e) => (StatusCode::CONFLICT, Json(json!({ "error": e }))),
+    }
+}
+
+/// Bundled app and site classes with the user's overrides
+pub async fn get_app_classification(State(state): State<AppState>) -> impl IntoResponse {
+    Json(json!(state.collector.read().await.app_classification()))
+}
+
+pub async fn set_app_classification(
+    State(state): State<AppState>,
+    Json(request): Json<AppClassificationRequest>,
+) -> impl IntoResponse {
+    match state.collector.write().await.set_app_classification(request.overrides) {
+        Ok(rules) => (StatusCode::OK, Json(json!(rules))),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
+}
+
+/// Mark "something just happened"; the body (`{"note": "..."}`) is optional
+pub async fn create_bookmark(
+    State(state): State<AppState>,
+    request: Option<Json<BookmarkRequest>>,
+) -> impl IntoResponse {
+    if let Some(refusal) = refuse_in_maintenance(&state).await {
+        return refusal;
+    }
+    let note = request
+        .and_then(|Json(request)| request.note)
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+    if note.as_ref().is_some_and(|note| note.chars().count() > bookmarks::MAX_NOTE_LENGTH) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("note is limited to {} characters", bookmarks::MAX_NOTE_LENGTH) })));
+    }
+    
+    match bookmarks::create(Arc::clone(&state.collector), Arc::clone(&state.storage), note, "api").await {
+        Ok(bookmark) => (StatusCode::CREATED, Json(json!({
+            "id": bookmark.id,
+            "timestamp": bookmark.timestamp,
+            "note": bookmark.note,
+            "snapshots_before": bookmark.snapshots.len(),
+        }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+pub async fn get_bookmarks(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    
+    let mut bookmarks = Vec::new();
+    for date in dates {
+        match state.storage.load_bookmarks(date).await {
+            Ok(day) => bookmarks.extend(day),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+    for bookmark in &mut bookmarks {
+        bookmark.snapshots = bookmark.snapshots.iter().map(access::metrics_only).collect();
+    }
+    
+    (StatusCode::OK, Json(json!({
+        "count": bookmarks.len(),
+        "bookmarks": bookmarks,
+    }))).into_response()
+}
+
+const MAX_NOTE_TEXT_LENGTH: usize = 2_000;
+
+/// Attach context to a moment or time range ("compiling large project")
+pub async fn create_note(
+    State(state): State<AppState>,
+    Json(request): Json<NoteRequest>,
+) -> impl IntoResponse {
+    if let Some(refusal) = refuse_in_maintenance(&state).await {
+        return refusal;
+    }
+    let text = request.text.trim().to_string();
+    if text.is_empty() || text.chars().count() > MAX_NOTE_TEXT_LENGTH {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("text must be 1-{} characters", MAX_NOTE_TEXT_LENGTH) })));
+    }
+    let now = chrono::Utc::now();
+    let start = request.start.unwrap_or(now);
+    let end = request.end.unwrap_or(start);
+    if end < start {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "end must not be before start" })));
+    }
+    if end - start > chrono::Duration::hours(timeline::MAX_NOTE_SPAN_HOURS) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("notes can span at most {} hours", timeline::MAX_NOTE_SPAN_HOURS) })));
+    }
+    
+    let note = SessionNote {
+        id: uuid::Uuid::new_v4().to_string(),
+        created_at: now,
+        session_id: request.session_id.filter(|id| !id.trim().is_empty()),
+        start,
+        end,
+        text,
+    };
+    match state.storage.save_note(&note).await {
+        Ok(_) => (StatusCode::CREATED, Json(json!(note))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+/// Notes overlapping the requested days, optionally for one session only
+pub async fn get_notes(
+    State(state): State<AppState>,
+    Query(query): Query<NotesQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&DateRangeQuery { date: query.date.clone(), days: query.days }) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    let first = dates[0];
+    let range_start = first.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let range_end = range_start + chrono::Duration::days(dates.len() as i64);
+    
+    // Notes live under their start day and may run into the next one
+    let mut notes: Vec<SessionNote> = Vec::new();
+    for date in std::iter::once(first - chrono::Duration::days(1)).chain(dates.iter().copied()) {
+        match state.storage.load_notes(date).await {
+            Ok(day) => notes.extend(day),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+    notes.retain(|note| {
+        note.start < range_end
+            && note.end >= range_start
+            && query.session_id.as_ref().is_none_or(|id| note.session_id.as_ref() == Some(id))
+    });
+    
+    (StatusCode::OK, Json(json!({
+        "count": notes.len(),
+        "notes": notes,
+    }))).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct FlagsQuery {
+    date: Option<String>,      // YYYY-MM-DD, defaults to today
+    days: Option<u32>,         // number of days ending at `date`, default 1
+    severity: Option<String>,  // minimum severity, e.g. "high" for High and Critical
+    flag_type: Option<String>, // e.g. "HealthConcern"
+}
+
+#[derive(Deserialize)]
+pub struct RecentFlagsQuery {
+    severity: Option<String>,
+    flag_type: Option<String>,
+    hours: Option<i64>,        // default 24, max 168
+    limit: Option<usize>,      // default 50, max 500
+}
+
+/// Flags raised on the days of the query, oldest first
+pub async fn get_flags(
+    State(state): State<AppState>,
+    Query(query): Query<FlagsQuery>,
+) -> impl IntoResponse {
+    flags_for_days(&state, query, None).await
+}
+
+/// Flags of one session on the days of the query, oldest first
+pub async fn get_session_flags(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+    Query(query): Query<FlagsQuery>,
+) -> impl IntoResponse {
+    flags_for_days(&state, query, Some(&session_id)).await
+}
+
+async fn flags_for_days(state: &AppState, query: FlagsQuery, session_id: Option<&str>) -> axum::response::Response {
+    let filter = match flags::FlagFilter::new(query.severity.as_deref(), query.flag_type.as_deref(), session_id) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+    let dates = match date_range(&DateRangeQuery { date: query.date, days: query.days }) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    let start = dates[0].and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let end = start + chrono::Duration::days(dates.len() as i64) - chrono::Duration::nanoseconds(1);
+
+    let mut flags = match state.storage.load_flags(start, end).await {
+        Ok(flags) => flags,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+    };
+    flags.retain(|flag| filter.matches(flag));
+
+    (StatusCode::OK, Json(json!({
+        "count": flags.len(),
+        "flags": flags,
+    }))).into_response()
+}
+
+/// The latest flags of the last `hours`, newest first
+pub async fn get_recent_flags(
+    State(state): State<AppState>,
+    Query(query): Query<RecentFlagsQuery>,
+) -> impl IntoResponse {
+    let filter = match flags::FlagFilter::new(query.severity.as_deref(), query.flag_type.as_deref(), None) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))),
+    };
+    let hours = query.hours.unwrap_or(flags::DEFAULT_RECENT_HOURS).clamp(1, flags::MAX_RECENT_HOURS);
+    let limit = query.limit.unwrap_or(flags::DEFAULT_RECENT_LIMIT).clamp(1, flags::MAX_RECENT_LIMIT);
+    let now = chrono::Utc::now();
+
+    let mut flags = match state.storage.load_flags(now - chrono::Duration::hours(hours), now).await {
+        Ok(flags) => flags,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))),
+    };
+    flags.retain(|flag| filter.matches(flag));
+    flags.reverse();
+    flags.truncate(limit);
+
+    (StatusCode::OK, Json(json!({
+        "hours": hours,
+        "count": flags.len(),
+        "flags": flags,
+    })))
+}
+
+/// Thresholds of the flag detector: the ones in use with flag_detection = "in_process", otherwise
+/// the ones the standalone detector loads from the same detector.toml
+pub async fn get_detector_config(State(state): State<AppState>) -> impl IntoResponse {
+    let path = flag_detection::thresholds::path_from_env();
+    let (mode, thresholds) = match &state.detector_thresholds {
+        Some(live) => ("in_process", live.read().unwrap_or_else(|e| e.into_inner()).clone()),
+        None => match flag_detection::thresholds::Thresholds::load_or_default(&path) {
+            Ok(thresholds) => ("external", thresholds),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e}))),
+        },
+    };
+
+    (StatusCode::OK, Json(json!({
+        "mode": mode,
+        "path": path,
+        "file_exists": path.exists(),
+        "thresholds": thresholds,
+    })))
+}
+
+/// Status shared with authenticated teammates only
+pub async fn get_team_peer_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let Some(team) = &state.team else {
+        return (StatusCode::NOT_FOUND, HeaderMap::new(), Vec::new());
+    };
+    
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or("");
+    if let Err(e) = team.verify(header(team::DEVICE_HEADER), b"", header(team::AUTH_HEADER)) {
+        log::warn!("Rejected team status request: {}", e);
+        return (StatusCode::UNAUTHORIZED, HeaderMap::new(), Vec::new());
+    }
+    
+    let latest = state.collector.read().await.get_latest_data();
+    let status = team.local_status(latest.as_ref()).await;
+    let body = serde_json::to_vec(&status).unwrap_or_default();
+    
+    let mut response_headers = HeaderMap::new();
+    if let Ok(auth) = team.sign(&status.device_id, &body).parse() {
+        response_headers.insert(team::AUTH_HEADER, auth);
+    }
+    response_headers.insert(axum::http::header::CONTENT_TYPE, axum::http::HeaderValue::from_static("application/json"));
+    (StatusCode::OK, response_headers, body)
+}
+
+pub async fn apply_update(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let Some(updater) = state.updater.clone() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({"error": "Updater not configured (EYECORE_UPDATE_PUBLIC_KEY missing)"})));
+    };
+    
+    let manifest = {
+        let update = state.update_status.read().await;
+        match (&update.latest_manifest, update.update_available) {
+            (Some(manifest), true) => manifest.clone(),
+            _ => return (StatusCode::CONFLICT, Json(json!({"error": "No update available"}))),
+        }
+    };
+    
+    match updater.apply(&manifest).await {
+        Ok(()) => {
+            // The server finishes in-flight responses, including this one, before shutting down
+            updater.request_restart();
+            (StatusCode::OK, Json(json!({"status": "update_installed", "version": manifest.version, "restarting": true})))
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e}))),
+    }
+}
+
+/// Who used the content endpoints, from the audit log
+pub async fn get_content_access_log(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    
+    let mut events = Vec::new();
+    for date in dates {
+        match state.storage.load_content_access(date).await {
+            Ok(day) => events.extend(day),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+    
+    (StatusCode::OK, Json(json!({
+        "count": events.len(),
+        "events": events,
+    }))).into_response()
+}
+
+// ===== CONTENT ENDPOINT HANDLERS =====
+// Routed behind access::require_content, which checks the key and writes the audit entry
+
+pub async fn get_typed_text(
     State(collector): State<Arc<RwLock<DataCollector>>>,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    let status = collector.get_status();
+    match collector.get_latest_data().and_then(|data| data.keystroke_dynamics) {
+        Some(keystrokes) => (StatusCode::OK, Json(json!({
+            "timestamp": keystrokes.timestamp,
+            "typed_text": keystrokes.typed_text,
+            "buttons_clicked": keystrokes.buttons_clicked,
+        }))),
+        None => (StatusCode::NO_CONTENT, Json(json!({"message": "Keystroke monitoring not enabled"}))),
+    }
+}
+
+pub async fn get_screen_text(
+    State(collector): State<Arc<RwLock<DataCollector>>>,
+) -> impl IntoResponse {
+    let collector = collector.read().await;
+    match collector.get_latest_data().and_then(|data| data.screen_interactions) {
+        Some(screen) => (StatusCode::OK, Json(json!({
+            "timestamp": screen.timestamp,
+            "screen_text_snapshot": screen.screen_text_snapshot,
+            "active_windows": screen.active_windows,
+        }))),
+        None => (StatusCode::NO_CONTENT, Json(json!({"message": "Screen interaction data unavailable"}))),
+    }
+}
+
+/// Saved voice transcriptions
+pub async fn get_transcripts(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
     
-    (StatusCode::OK, Json(status))
+    let mut transcripts = Vec::new();
+    for date in dates {
+        match state.storage.load_transcriptions(date).await {
+            Ok(day) => transcripts.extend(day),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+    
+    (StatusCode::OK, Json(json!({
+        "count": transcripts.len(),
+        "transcripts": transcripts,
+    }))).into_response()
+}
+
+/// Incident bundles of Critical SecurityConcern flags raised on the requested days
+pub async fn get_incidents(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> impl IntoResponse {
+    let dates = match date_range(&query) {
+        Ok(dates) => dates,
+        Err(error) => return error.into_response(),
+    };
+    
+    let mut incidents = Vec::new();
+    for date in dates {
+        match state.storage.load_incident_bundles(date).await {
+            Ok(day) => incidents.extend(day),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+    
+    (StatusCode::OK, Json(json!({
+        "forensic_buffer_secs": state.config.config.forensic_buffer_secs,
+        "count": incidents.len(),
+        "incidents": incidents,
+    }))).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct DownloadRangeQuery {
+    from: String, // YYYY-MM-DD, first day
+    to: String,   // YYYY-MM-DD, last day
+}
+
+/// Everything stored for the days `from` through `to` as a tar.zst, resumable with `Range`
+pub async fn download_range(
+    State(state): State<AppState>,
+    Query(query): Query<DownloadRangeQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let parse = |date: &str| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d");
+    let (from, to) = match (parse(&query.from), parse(&query.to)) {
+        (Ok(from), Ok(to)) => (from, to),
+        _ => return (StatusCode::BAD_REQUEST, Json(json!({"error": "from and to must be YYYY-MM-DD"}))).into_response(),
+    };
+    if to < from {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "to must not be before from"}))).into_response();
+    }
+    if (to - from).num_days() >= download::MAX_DOWNLOAD_DAYS {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": format!("at most {} days per download", download::MAX_DOWNLOAD_DAYS)
+        }))).into_response();
+    }
+
+    match download::range_archive(&state.storage, from, to).await {
+        Ok(Some(archive)) => download::respond(archive, &headers).await,
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({"error": "no data stored in this range"}))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ShareRequest {
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,      // at most 2 hours after `start`
+    #[serde(default)]
+    fields: Vec<String>,                     // snapshot fields, e.g. "system_metrics.cpu_usage" or "focus_metrics"
+    #[serde(default)]
+    flags: bool,
+    #[serde(default)]
+    logs: bool,                              // capture events and collection gaps
+    expires_in_hours: Option<i64>,           // default 24, max 168
+    #[serde(default)]
+    confirm: bool,                           // without it, only the contents are previewed
+}
+
+/// Build a password-protected bundle of anonymized data for someone else, or preview its contents
+pub async fn create_share(
+    State(state): State<AppState>,
+    Json(request): Json<ShareRequest>,
+) -> impl IntoResponse {
+    let selection = ShareSelection {
+        start: request.start,
+        end: request.end,
+        fields: request.fields,
+        flags: request.flags,
+        logs: request.logs,
+    };
+    if let Err(e) = selection.validate() {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": e})));
+    }
+    let expires_in_hours = request.expires_in_hours.unwrap_or(share::DEFAULT_EXPIRY_HOURS);
+    if !(1..=share::MAX_EXPIRY_HOURS).contains(&expires_in_hours) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": format!("expires_in_hours must be 1-{}", share::MAX_EXPIRY_HOURS)})));
+    }
+
+    let data = match share::collect(&state.storage, &selection).await {
+        Ok(data) => data,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))),
+    };
+    if !request.confirm {
+        return (StatusCode::OK, Json(json!(data.contents)));
+    }
+    match share::create(&state.storage, data, chrono::Duration::hours(expires_in_hours)).await {
+        Ok(bundle) => (StatusCode::CREATED, Json(json!(bundle))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))),
+    }
+}
+
+/// The zip of a share bundle until it expires
+pub async fn get_share(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let path = match share::find(&state.storage, &id).await {
+        Ok(Some(path)) => path,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({"error": "No such share, or it has expired"}))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+    };
+    match tokio::fs::read(&path).await {
+        Ok(bytes) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "application/zip".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"eyecore_share_{}.zip\"", id)),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+    }
 }
 
 // ===== NEW ENDPOINT HANDLERS =====
 
 pub async fn get_voice_data(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    match collector.get_latest_data() {
-        Some(data) => {
-            match data.voice_data {
-                Some(voice) => (StatusCode::OK, Json(voice)).into_response(),
-                None => (StatusCode::NO_CONTENT, Json(json!({"message": "Voice data not enabled or unavailable"}))).into_response(),
-            }
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
+    latest_section(&collector, &headers, |data| data.voice_data.as_ref(), "Voice data not enabled or unavailable")
 }
 
 pub async fn get_camera_data(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    match collector.get_latest_data() {
-        Some(data) => {
-            match data.camera_data {
-                Some(camera) => (StatusCode::OK, Json(camera)).into_response(),
-                None => (StatusCode::NO_CONTENT, Json(json!({"message": "Camera data not enabled or unavailable"}))).into_response(),
-            }
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
+    latest_section(&collector, &headers, |data| data.camera_data.as_ref(), "Camera data not enabled or unavailable")
 }
 
 pub async fn get_keystroke_dynamics(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    match collector.get_latest_data() {
-        Some(data) => {
-            match data.keystroke_dynamics {
-                Some(keystrokes) => (StatusCode::OK, Json(keystrokes)).into_response(),
-                None => (StatusCode::NO_CONTENT, Json(json!({"message": "Keystroke monitoring not enabled"}))).into_response(),
-            }
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
+    latest_section(&collector, &headers, |data| access::metrics_only(data).keystroke_dynamics, "Keystroke monitoring not enabled")
 }
 
 pub async fn get_screen_interactions(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    match collector.get_latest_data() {
-        Some(data) => {
-            match data.screen_interactions {
-                Some(screen) => (StatusCode::OK, Json(screen)).into_response(),
-                None => (StatusCode::NO_CONTENT, Json(json!({"message": "Screen interaction data unavailable"}))).into_response(),
-            }
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
+    latest_section(&collector, &headers, |data| access::metrics_only(data).screen_interactions, "Screen interaction data unavailable")
 }
 
 pub async fn get_file_metadata(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    match collector.get_latest_data() {
-        Some(data) => {
-            match data.file_metadata {
-                Some(files) => (StatusCode::OK, Json(files)).into_response(),
-                None => (StatusCode::NO_CONTENT, Json(json!({"message": "File monitoring not enabled"}))).into_response(),
-            }
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
+    latest_section(&collector, &headers, |data| data.file_metadata.as_ref(), "File monitoring not enabled")
 }
 
 pub async fn get_system_events(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    match collector.get_latest_data() {
-        Some(data) => {
-            match data.system_events {
-                Some(events) => (StatusCode::OK, Json(events)).into_response(),
-                None => (StatusCode::NO_CONTENT, Json(json!({"message": "System events unavailable"}))).into_response(),
-            }
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
+    latest_section(&collector, &headers, |data| data.system_events.as_ref(), "System events unavailable")
 }
 
 pub async fn get_mouse_dynamics(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    match collector.get_latest_data() {
-        Some(data) => {
-            match data.mouse_dynamics {
-                Some(mouse) => (StatusCode::OK, Json(mouse)).into_response(),
-                None => (StatusCode::NO_CONTENT, Json(json!({"message": "Mouse dynamics data unavailable"}))).into_response(),
-            }
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
+    latest_section(&collector, &headers, |data| data.mouse_dynamics.as_ref(), "Mouse dynamics data unavailable")
 }
 
 pub async fn get_network_metadata(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let collector = collector.read().await;
-    match collector.get_latest_data() {
-        Some(data) => {
-            match data.network_activity_metadata {
-                Some(network) => (StatusCode::OK, Json(network)).into_response(),
-                None => (StatusCode::NO_CONTENT, Json(json!({"message": "Network metadata unavailable"}))).into_response(),
-            }
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
+    latest_section(&collector, &headers, |data| data.network_activity_metadata.as_ref(), "Network metadata unavailable")
 }
 
-// Control endpoints for enabling/disabling collection modules
-pub async fn enable_voice(
+/// Foreground app switches with their cadence and ping-pong runs
+pub async fn get_switches(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    Query(query): Query<SwitchesQuery>,
 ) -> impl IntoResponse {
-    let mut collector = collector.write().await;
-    collector.enable_voice();
-    (StatusCode::OK, Json(json!({"status": "voice_enabled"})))
+    let minutes = query.minutes.unwrap_or(5).clamp(1, 60);
+    let collector = collector.read().await;
+    (StatusCode::OK, Json(collector.switch_report(chrono::Duration::minutes(minutes))))
 }
 
-pub async fn disable_voice(
+/// Notification counts per sending app since startup (never notification content)
+pub async fn get_notifications(
     State(collector): State<Arc<RwLock<DataCollector>>>,
 ) -> impl IntoResponse {
-    let mut collector = collector.write().await;
-    collector.disable_voice();
-    (StatusCode::OK, Json(json!({"status": "voice_disabled"})))
+    let collector = collector.read().await;
+    (StatusCode::OK, Json(collector.notification_stats()))
 }
 
-pub async fn enable_camera(
-    State(collector): State<Arc<RwLock<DataCollector>>>,
+/// Modules with their current settings
+pub async fn get_modules(
+    State(state): State<AppState>,
 ) -> impl IntoResponse {
-    let mut collector = collector.write().await;
-    collector.enable_camera();
-    (StatusCode::OK, Json(json!({"status": "camera_enabled"})))
+    let collector = state.collector.read().await;
+    let modules = state.module_control.read().await.list(&collector);
+    (StatusCode::OK, Json(json!({ "modules": modules })))
 }
 
-pub async fn disable_camera(
-    State(collector): State<Arc<RwLock<DataCollector>>>,
+/// Change one module's settings, e.g. `{"enabled": true, "sampling_interval_secs": 60, "retention_days": 30}`
+pub async fn update_module(
+    State(state): State<AppState>,
+    Path(module): Path<String>,
+    Json(update): Json<ModuleUpdate>,
 ) -> impl IntoResponse {
-    let mut collector = collector.write().await;
-    collector.disable_camera();
-    (StatusCode::OK, Json(json!({"status": "camera_disabled"})))
+    if !module_control::is_module(&module) {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": format!("Unknown module {:?} (expected voice, camera, keystroke, files, system or screen)", module) })));
+    }
+    let mut collector = state.collector.write().await;
+    if update.enabled == Some(true) && consent::is_module(&module) && !state.consent.read().await.is_granted(&module) {
+        return (StatusCode::FORBIDDEN, Json(json!({
+            "error": format!("No consent for {}; grant it with POST /consent/{}/grant first", module, module),
+        })));
+    }
+    match state.module_control.write().await.update(&module, update, &mut collector) {
+        Ok(settings) => (StatusCode::OK, Json(json!(settings))),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
 }
 
-pub async fn enable_keystroke(
+/// Pluggable collectors with their cadence and latest outcome
+pub async fn get_collectors(
     State(collector): State<Arc<RwLock<DataCollector>>>,
 ) -> impl IntoResponse {
-    let mut collector = collector.write().await;
-    collector.enable_keystroke();
-    (StatusCode::OK, Json(json!({"status": "keystroke_enabled"})))
+    let collector = collector.read().await;
+    (StatusCode::OK, Json(json!({ "collectors": collector.collectors().list() })))
 }
 
-pub async fn disable_keystroke(
+/// Background jobs with their schedules and last runs
+pub async fn get_scheduler_jobs(State(state): State<AppState>) -> impl IntoResponse {
+    (StatusCode::OK, Json(json!({ "jobs": state.scheduler.jobs().await })))
+}
+
+/// Switch a collector or change its interval, e.g. `{"enabled": true, "interval_secs": 300}`
+pub async fn update_collector(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    Path(name): Path<String>,
+    Json(update): Json<CollectorUpdate>,
 ) -> impl IntoResponse {
     let mut collector = collector.write().await;
-    collector.disable_keystroke();
-    (StatusCode::OK, Json(json!({"status": "keystroke_disabled"})))
+    if !collector.collectors().contains(&name) {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": format!("Unknown collector {:?}", name) })));
+    }
+    match collector.collectors_mut().update(&name, update) {
+        Ok(status) => {
+            log::info!("🧩 Collector {}: enabled={}, interval={}s", status.name, status.enabled, status.interval_secs);
+            (StatusCode::OK, Json(json!(status)))
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
+}
+
+/// Detected projects, confirmed or not, and the names the user rejected
+pub async fn get_projects(State(collector): State<Arc<RwLock<DataCollector>>>) -> impl IntoResponse {
+    Json(json!(collector.read().await.projects()))
 }
 
-pub async fn enable_file_monitoring(
+/// Confirm a detected project; the body (`{"name": "..."}`) is optional and renames or merges it
+pub async fn confirm_project(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    Path(name): Path<String>,
+    confirmation: Option<Json<ProjectConfirmation>>,
 ) -> impl IntoResponse {
-    let mut collector = collector.write().await;
-    collector.enable_file_monitoring();
-    (StatusCode::OK, Json(json!({"status": "file_monitoring_enabled"})))
+    let confirmation = confirmation.map(|Json(confirmation)| confirmation).unwrap_or_default();
+    change_project(collector, name, |collector, name| collector.confirm_project(name, confirmation.name)).await
 }
 
-pub async fn disable_file_monitoring(
+/// Forget a detected project; its names are not detected again
+pub async fn reject_project(
     State(collector): State<Arc<RwLock<DataCollector>>>,
+    Path(name): Path<String>,
 ) -> impl IntoResponse {
+    change_project(collector, name, DataCollector::reject_project).await
+}
+
+async fn change_project(
+    collector: Arc<RwLock<DataCollector>>,
+    name: String,
+    change: impl FnOnce(&mut DataCollector, &str) -> Result<Projects, String>,
+) -> (StatusCode, Json<serde_json::Value>) {
     let mut collector = collector.write().await;
-    collector.disable_file_monitoring();
-    (StatusCode::OK, Json(json!({"status": "file_monitoring_disabled"})))
+    if !collector.has_project(&name) {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": format!("Unknown project {:?}", name) })));
+    }
+    match change(&mut collector, &name) {
+        Ok(projects) => (StatusCode::OK, Json(json!(projects))),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
+}
+
+/// Registered goals
+pub async fn get_goals(State(state): State<AppState>) -> impl IntoResponse {
+    Json(json!({ "goals": state.goals.read().await.goals() }))
+}
+
+/// Register a goal, e.g. `{"metric": "deep_work", "project": "eyecore", "target_minutes": 240, "period": "daily"}`
+pub async fn create_goal(
+    State(state): State<AppState>,
+    Json(goal): Json<NewGoal>,
+) -> impl IntoResponse {
+    match state.goals.write().await.add(goal) {
+        Ok(goal) => (StatusCode::CREATED, Json(json!(goal))),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
+}
+
+/// Remove a goal; the remaining ones are returned
+pub async fn delete_goal(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let mut book = state.goals.write().await;
+    match book.remove(&id) {
+        Ok(true) => (StatusCode::OK, Json(json!({ "goals": book.goals() }))),
+        Ok(false) => (StatusCode::NOT_FOUND, Json(json!({ "error": format!("Unknown goal {:?}", id) }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
+    }
+}
+
+/// Progress of every goal today or this week (weeks start on Monday), with a projection for weekly goals
+pub async fn get_goal_progress(State(state): State<AppState>) -> impl IntoResponse {
+    let goals = state.goals.read().await.goals().to_vec();
+    match goals::progress(&state.storage, &goals, chrono::Utc::now().date_naive()).await {
+        Ok(progress) => (StatusCode::OK, Json(json!({ "goals": progress }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TimeEntriesQuery {
+    status: Option<TimeEntryStatus>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct TimeEntriesDraft {
+    date: Option<chrono::NaiveDate>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct TimeEntrySubmission {
+    description: Option<String>,
+}
+
+/// The review queue of time entries, optionally only those with `?status=pending|submitted|discarded`
+pub async fn get_time_entries(
+    State(state): State<AppState>,
+    Query(query): Query<TimeEntriesQuery>,
+) -> impl IntoResponse {
+    let exporter = state.time_export.read().await;
+    Json(json!({ "provider": exporter.provider(), "entries": exporter.entries(query.status) }))
+}
+
+/// Draft a day's time entries for review (`{"date": "YYYY-MM-DD"}`, default today), replacing its pending ones
+pub async fn draft_time_entries(
+    State(state): State<AppState>,
+    request: Option<Json<TimeEntriesDraft>>,
+) -> impl IntoResponse {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let date = request.date.unwrap_or_else(|| chrono::Utc::now().date_naive());
+    match state.time_export.write().await.draft(&state.storage, date).await {
+        Ok(entries) => (StatusCode::OK, Json(json!({ "entries": entries }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
+    }
+}
+
+/// Send a pending entry to the time-tracking tool; the body (`{"description": "..."}`) is optional
+pub async fn submit_time_entry(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    submission: Option<Json<TimeEntrySubmission>>,
+) -> impl IntoResponse {
+    let submission = submission.map(|Json(submission)| submission).unwrap_or_default();
+    let description = submission.description.map(|description| description.trim().to_string());
+    if description.as_ref().is_some_and(|d| d.is_empty() || d.chars().count() > time_export::MAX_DESCRIPTION_LENGTH) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("description must be 1-{} characters", time_export::MAX_DESCRIPTION_LENGTH) })));
+    }
+    let mut exporter = state.time_export.write().await;
+    let Some(entry) = exporter.get(&id) else {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": format!("Unknown time entry {:?}", id) })));
+    };
+    if let Err(e) = exporter.check_submittable(entry) {
+        return (StatusCode::CONFLICT, Json(json!({ "error": e })));
+    }
+    match exporter.submit(&id, description).await {
+        Ok(entry) => (StatusCode::OK, Json(json!(entry))),
+        Err(e) => (StatusCode::BAD_GATEWAY, Json(json!({ "error": e }))),
+    }
+}
+
+/// Drop a pending entry without submitting it
+pub async fn discard_time_entry(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let mut exporter = state.time_export.write().await;
+    if exporter.get(&id).is_none() {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": format!("Unknown time entry {:?}", id) })));
+    }
+    match exporter.discard(&id) {
+        Ok(entry) => (StatusCode::OK, Json(json!(entry))),
+        Err(e) => (StatusCode::CONFLICT, Json(json!({ "error": e }))),
+    }
+}
+
+/// OS permissions the collectors need, with the steps to grant each missing one
+pub async fn get_permissions(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let checker = Arc::clone(&state.permissions);
+    match tokio::task::spawn_blocking(move || checker.check_all()).await {
+        Ok(permissions) => (StatusCode::OK, Json(json!({ "permissions": permissions }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+/// Show the OS prompt for a permission, or open its settings page; the microphone is recorded for
+/// half a second to find out whether it is allowed
+pub async fn request_permission(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    if !permissions::is_permission(&name) {
+        return (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("Unknown permission {:?} (expected camera, microphone, accessibility or input_monitoring)", name),
+        })));
+    }
+    let checker = Arc::clone(&state.permissions);
+    let capture_tracker = state.collector.read().await.capture_tracker();
+    match tokio::task::spawn_blocking(move || checker.request(&name, &capture_tracker)).await {
+        Ok(Ok((permission, prompted))) => (StatusCode::OK, Json(json!({ "permission": permission, "prompted": prompted }))),
+        Ok(Err(e)) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+/// Research input trace: whether it records, its files and how many events were dropped
+pub async fn get_input_trace(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let status = state.input_trace.as_ref().map(|trace| trace.status()).unwrap_or_default();
+    (StatusCode::OK, Json(status))
+}
+
+/// Upload connection state, the last send and the snapshots waiting to be sent
+pub async fn get_upload_status(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(state.upload.status()))
+}
+
+/// Consent per privacy-sensitive module, with every grant and revocation
+pub async fn get_consent(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(state.consent.read().await.status()))
+}
+
+pub async fn grant_consent(
+    State(state): State<AppState>,
+    Path(module): Path<String>,
+    change: Option<Json<ConsentChange>>,
+) -> impl IntoResponse {
+    change_consent(state, module, true, change.map(|Json(change)| change).unwrap_or_default()).await
+}
+
+/// Revoking stops the module at once; data already stored is kept until its retention expires
+pub async fn revoke_consent(
+    State(state): State<AppState>,
+    Path(module): Path<String>,
+    change: Option<Json<ConsentChange>>,
+) -> impl IntoResponse {
+    change_consent(state, module, false, change.map(|Json(change)| change).unwrap_or_default()).await
+}
+
+async fn change_consent(state: AppState, module: String, granted: bool, change: ConsentChange) -> (StatusCode, Json<serde_json::Value>) {
+    if !consent::is_module(&module) {
+        return (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("Unknown module {:?} (expected voice, camera, keystroke, screen_text or files)", module),
+        })));
+    }
+    let mut collector = state.collector.write().await;
+    match state.consent.write().await.set(&module, granted, "api", change.reason, &mut collector) {
+        Ok(consent) => (StatusCode::OK, Json(json!(consent))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
+    }
 }
@@ -0,0 +1,166 @@
+//! App identification for `process_data.app`: which app the foreground window belongs to, by its
+//! executable. User rules in `app_rules.json` (device directory) are tried first and can match the
+//! executable and the window title with regular expressions, e.g. to tell apps sharing a runtime
+//! apart:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     { "process": "^javaw?$", "title": "IntelliJ IDEA", "app": "IntelliJ IDEA", "category": "productive" },
+//!     { "process": "^electron$", "title": " - Obsidian", "app": "Obsidian" }
+//!   ]
+//! }
+//! ```
+//!
+//! Patterns are case-insensitive and an omitted pattern matches anything. The first matching rule
+//! names the app, and its optional `category` is the app's productivity class unless overridden
+//! through `/classification/apps`.
+
+use log::{info, warn};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::models::Productivity;
+
+/// Rules file in the device directory
+pub const RULES_FILE: &str = "app_rules.json";
+
+/// Apps by executable (lowercase, without ".exe") or macOS app name
+const KNOWN_APPS: &[(&str, &[&str])] = &[
+    ("Visual Studio Code", &["code", "visual studio code"]),
+    ("Visual Studio", &["devenv"]),
+    ("IntelliJ IDEA", &["idea", "idea64"]),
+    ("PyCharm", &["pycharm", "pycharm64"]),
+    ("Xcode", &["xcode"]),
+    ("Terminal", &["windowsterminal", "cmd", "gnome-terminal-server", "konsole", "alacritty", "kitty", "iterm2", "terminal"]),
+    ("PowerShell", &["powershell", "pwsh"]),
+    ("Chrome", &["chrome", "google chrome", "chromium"]),
+    ("Edge", &["msedge", "microsoft edge"]),
+    ("Firefox", &["firefox"]),
+    ("Safari", &["safari"]),
+    ("Word", &["winword", "microsoft word"]),
+    ("Excel", &["excel", "microsoft excel"]),
+    ("PowerPoint", &["powerpnt", "microsoft powerpoint"]),
+    ("Outlook", &["outlook", "microsoft outlook"]),
+    ("Teams", &["teams", "ms-teams", "microsoft teams"]),
+    ("Slack", &["slack"]),
+    ("Discord", &["discord"]),
+    ("Zoom", &["zoom", "zoom.us"]),
+    ("Spotify", &["spotify"]),
+    ("File Explorer", &["explorer"]),
+    ("Finder", &["finder"]),
+];
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    rules: Vec<RuleSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    #[serde(default)]
+    process: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    app: String,
+    #[serde(default)]
+    category: Option<Productivity>,
+}
+
+struct AppRule {
+    process: Option<Regex>,
+    title: Option<Regex>,
+    app: String,
+    category: Option<Productivity>,
+}
+
+/// The app a window belongs to, with the class its rule gave it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentifiedApp {
+    pub name: String,
+    pub category: Option<Productivity>,
+}
+
+#[derive(Default)]
+pub struct AppRules {
+    rules: Vec<AppRule>,
+}
+
+impl AppRules {
+    /// The bundled executables only
+    pub fn new() -> Self {
+        AppRules::default()
+    }
+
+    /// User rules from `path`; a missing file has none, and a file that cannot be used is logged and
+    /// ignored so collection carries on with the bundled executables
+    pub fn load(path: &Path) -> Self {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => return AppRules::new(),
+        };
+        match Self::parse(&content) {
+            Ok(rules) => {
+                info!("🏷️ Loaded {} app rules from {:?}", rules.rules.len(), path);
+                rules
+            }
+            Err(e) => {
+                warn!("⚠️ Ignoring app rules {:?}: {}", path, e);
+                AppRules::new()
+            }
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let file: RulesFile = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        let rules = file
+            .rules
+            .into_iter()
+            .enumerate()
+            .map(|(i, spec)| {
+                let app = spec.app.trim().to_string();
+                if app.is_empty() {
+                    return Err(format!("rule {}: app must not be empty", i + 1));
+                }
+                if spec.process.is_none() && spec.title.is_none() {
+                    return Err(format!("rule {} ({}): needs a process or title pattern", i + 1, app));
+                }
+                let compile = |pattern: Option<String>| {
+                    pattern
+                        .map(|pattern| RegexBuilder::new(&pattern).case_insensitive(true).build())
+                        .transpose()
+                        .map_err(|e| format!("rule {} ({}): {}", i + 1, app, e))
+                };
+                Ok(AppRule { process: compile(spec.process)?, title: compile(spec.title)?, app: app.clone(), category: spec.category })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(AppRules { rules })
+    }
+
+    /// The app of a window: the first user rule that matches, then the bundled executables, otherwise
+    /// the executable name itself
+    pub fn identify(&self, process: &str, window_title: &str) -> IdentifiedApp {
+        let executable = executable_name(process);
+        let matched = self.rules.iter().find(|rule| {
+            rule.process.as_ref().is_none_or(|pattern| pattern.is_match(&executable))
+                && rule.title.as_ref().is_none_or(|pattern| pattern.is_match(window_title))
+        });
+        if let Some(rule) = matched {
+            return IdentifiedApp { name: rule.app.clone(), category: rule.category };
+        }
+        let name = KNOWN_APPS
+            .iter()
+            .find(|(_, executables)| executables.contains(&executable.as_str()))
+            .map_or(executable, |(app, _)| app.to_string());
+        IdentifiedApp { name, category: None }
+    }
+}
+
+/// Lowercase, without ".exe"
+pub fn executable_name(process: &str) -> String {
+    let name = process.trim().to_lowercase();
+    name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+}
@@ -0,0 +1,131 @@
+//! Distraction sources for attributing focus drops: foreground app switches (from `switches`), site
+//! changes in the foreground browser and toast notifications (from `notifications`), each since the
+//! previous snapshot. The flag detector attaches them to ProductivityAlert flags.
+
+use chrono::Utc;
+
+use crate::models::{FocusContext, SiteChange};
+use crate::notifications::NotificationMonitor;
+use crate::switches::SwitchMonitor;
+
+/// Browser executables (lowercase) and the names they append to window titles
+const BROWSERS: &[(&str, &str)] = &[
+    ("chrome.exe", "Chrome"),
+    ("msedge.exe", "Edge"),
+    ("firefox.exe", "Firefox"),
+    ("brave.exe", "Brave"),
+    ("opera.exe", "Opera"),
+    ("vivaldi.exe", "Vivaldi"),
+];
+
+pub struct FocusContextTracker {
+    #[cfg(target_os = "windows")]
+    system: sysinfo::System,
+    last_site: Option<(String, String)>, // (browser, site)
+}
+
+impl FocusContextTracker {
+    pub fn new() -> Self {
+        FocusContextTracker {
+            #[cfg(target_os = "windows")]
+            system: sysinfo::System::new(),
+            last_site: None,
+        }
+    }
+
+    /// Changes since the previous call, given the foreground window's title and the app switches and
+    /// notifications that arrived meanwhile
+    pub fn observe(&mut self, window_title: &str, switches: &SwitchMonitor, notifications: &NotificationMonitor) -> FocusContext {
+        let now = Utc::now();
+        let mut context = FocusContext {
+            app_switches: switches.since_last_poll().to_vec(),
+            notifications: notifications.since_last_poll().to_vec(),
+            notifications_available: notifications.available(),
+            ..Default::default()
+        };
+
+        let Some(app) = self.foreground_app() else {
+            return context;
+        };
+        let browser = BROWSERS
+            .iter()
+            .find(|(exe, _)| app.eq_ignore_ascii_case(exe))
+            .map(|(_, name)| name.to_string());
+        let site = browser.and_then(|browser| site_from_title(window_title).map(|site| (browser, site)));
+        if let Some((browser, site)) = &site {
+            let from_site = self.last_site.as_ref().filter(|(b, _)| b == browser).map(|(_, s)| s.clone());
+            if from_site.as_ref() != Some(site) && self.last_site.is_some() {
+                context.site_changes.push(SiteChange {
+                    timestamp: now,
+                    browser: browser.clone(),
+                    from_site,
+                    to_site: site.clone(),
+                });
+            }
+        }
+        if site.is_some() {
+            self.last_site = site;
+        }
+        context
+    }
+
+    /// Executable name of the process owning the foreground window
+    #[cfg(target_os = "windows")]
+    fn foreground_app(&mut self) -> Option<String> {
+        use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+        let mut pid = 0u32;
+        unsafe {
+            GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut pid));
+        }
+        if pid == 0 {
+            return None;
+        }
+        let pid = sysinfo::Pid::from_u32(pid);
+        self.system.refresh_process(pid);
+        self.system.process(pid).map(|process| process.name().to_string())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn foreground_app(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// Whether `process` is one of the browsers whose window titles show the site
+pub fn is_browser(process: &str) -> bool {
+    BROWSERS.iter().any(|(exe, _)| process.eq_ignore_ascii_case(exe))
+}
+
+/// The site shown in a browser window title such as "Pull requests · github.com - Google Chrome"
+/// or "Inbox - Gmail - Google Chrome": a domain when the title contains one, otherwise the last
+/// part of the page title, which is usually the site name
+pub fn site_from_title(title: &str) -> Option<String> {
+    let title = [" — ", " · ", " | "].iter().fold(title.to_string(), |title, sep| title.replace(sep, " - "));
+    let parts: Vec<&str> = title
+        .split(" - ")
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect();
+    // The last part is the browser's own name
+    let page = &parts[..parts.len().saturating_sub(1)];
+
+    let domain = page
+        .iter()
+        .flat_map(|part| part.split_whitespace())
+        .map(|word| word.trim_matches(|c: char| !c.is_ascii_alphanumeric()))
+        .map(|word| word.rsplit('@').next().unwrap_or(word))
+        .find(|word| looks_like_domain(word));
+    domain
+        .map(|domain| domain.trim_start_matches("www.").to_lowercase())
+        .or_else(|| page.last().map(|site| site.to_string()))
+}
+
+fn looks_like_domain(word: &str) -> bool {
+    let labels: Vec<&str> = word.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && labels.last().is_some_and(|tld| tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()))
+}
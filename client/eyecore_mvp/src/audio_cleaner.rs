@@ -1,20 +1,69 @@
 use log::{info, debug};
 use serde_json::json;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// Speaker estimation: pitch per 40ms of voiced audio, within the range of human speech
+const PITCH_WINDOW_MS: usize = 40;
+const MIN_PITCH_HZ: f32 = 70.0;
+const MAX_PITCH_HZ: f32 = 400.0;
+const MIN_VOICING: f32 = 0.5;             // normalized autocorrelation needed to trust a pitch
+const SPEAKER_PITCH_SEPARATION: f32 = 1.2; // segments >20% apart in pitch count as different voices
+
+// Cleaning settings of stored recordings
+const SILENCE_THRESHOLD_DB: f32 = -40.0;
+const MIN_SPEECH_DURATION_MS: u32 = 500;
 
 pub struct AudioCleaner;
 
 impl AudioCleaner {
-    /// Clean audio by removing silence, static, and inactivity segments
-    pub async fn clean_audio_segment(
+    /// Clean audio by removing silence, static, and inactivity segments, streaming
+    /// 10ms frames from `audio_path` to `output_path` so memory stays flat for long recordings.
+    /// CPU-bound; call from a blocking thread (see `start_cleaning_pool`).
+    pub fn clean_audio_segment(
         audio_path: &Path,
+        output_path: &Path,
         silence_threshold_db: f32,
-        _min_speech_duration_ms: u32,
+        min_speech_duration_ms: u32,
     ) -> Result<CleanedAudio, String> {
         info!("Starting audio cleaning for: {:?}", audio_path);
 
         // Read audio file
-        let mut reader = hound::WavReader::open(audio_path).map_err(|e| format!("Failed to open WAV: {}", e))?;
+        let reader = hound::WavReader::open(audio_path).map_err(|e| format!("Failed to open WAV: {}", e))?;
+        let writer = hound::WavWriter::create(output_path, reader.spec()).map_err(|e| format!("Failed to create WAV: {}", e))?;
+        match Self::clean_stream(reader, writer, silence_threshold_db, min_speech_duration_ms) {
+            Ok(cleaned) => {
+                info!("✓ Cleaned audio saved to: {:?}", output_path);
+                Ok(cleaned)
+            }
+            Err(e) => {
+                // Don't leave a truncated file behind
+                let _ = std::fs::remove_file(output_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Like `clean_audio_segment`, for a recording held in memory (decrypted from storage); returns
+    /// the cleaned WAV bytes
+    pub fn clean_audio_bytes(
+        audio: &[u8],
+        silence_threshold_db: f32,
+        min_speech_duration_ms: u32,
+    ) -> Result<(CleanedAudio, Vec<u8>), String> {
+        let reader = hound::WavReader::new(std::io::Cursor::new(audio)).map_err(|e| format!("Failed to open WAV: {}", e))?;
+        let mut output = std::io::Cursor::new(Vec::new());
+        let writer = hound::WavWriter::new(&mut output, reader.spec()).map_err(|e| format!("Failed to create WAV: {}", e))?;
+        let cleaned = Self::clean_stream(reader, writer, silence_threshold_db, min_speech_duration_ms)?;
+        Ok((cleaned, output.into_inner()))
+    }
+
+    fn clean_stream<R: std::io::Read, W: std::io::Write + std::io::Seek>(
+        mut reader: hound::WavReader<R>,
+        mut writer: hound::WavWriter<W>,
+        silence_threshold_db: f32,
+        min_speech_duration_ms: u32,
+    ) -> Result<CleanedAudio, String> {
         let spec = reader.spec();
         
         debug!(
@@ -24,87 +73,128 @@ impl AudioCleaner {
             (reader.len() / spec.sample_rate as u32) * 1000
         );
 
-        // Convert to samples and analyze
-        let samples = Self::read_samples(&mut reader).map_err(|e| format!("Failed to read samples: {}", e))?;
+        let result = Self::stream_voice_frames(&mut reader, &mut writer, silence_threshold_db, min_speech_duration_ms);
+        let finalized = writer.finalize().map_err(|e| format!("Finalize WAV error: {}", e));
+        let streamed = result.and_then(|r| finalized.map(|_| r))?;
         
-        // Detect voice activity
-        let voice_segments = Self::detect_voice_activity(&samples, spec.sample_rate, silence_threshold_db).map_err(|e| format!("VAD failed: {}", e))?;
-        
-        // Remove silence and combine segments
-        let cleaned_samples = Self::remove_silence(&samples, &voice_segments).map_err(|e| format!("Silence removal failed: {}", e))?;
+        let StreamedAudio { segments: voice_segments, total_samples, kept_samples, segment_pitches_hz, timings } = streamed;
+        let estimated_speakers = count_speakers(&segment_pitches_hz);
         
         // Calculate statistics
-        let original_duration_ms = (samples.len() as f32 / spec.sample_rate as f32) * 1000.0;
-        let cleaned_duration_ms = (cleaned_samples.len() as f32 / spec.sample_rate as f32) * 1000.0;
+        let original_duration_ms = (total_samples as f32 / spec.sample_rate as f32) * 1000.0;
+        let cleaned_duration_ms = (kept_samples as f32 / spec.sample_rate as f32) * 1000.0;
         let compression_ratio = original_duration_ms / cleaned_duration_ms.max(1.0);
 
-        info!(
-            "✓ Audio cleaned: {:.1}ms -> {:.1}ms (compression ratio: {:.2}x)",
-            original_duration_ms, cleaned_duration_ms, compression_ratio
-        );
+        debug!("Found {} voice segments, removed {} silence samples", voice_segments.len(), total_samples - kept_samples);
 
         Ok(CleanedAudio {
-            samples: cleaned_samples,
             voice_segments,
             original_duration_ms: original_duration_ms as u32,
             cleaned_duration_ms: cleaned_duration_ms as u32,
             compression_ratio,
+            estimated_speakers,
+            total_samples,
             spec,
+            timings,
         })
     }
 
-    /// Detect voice activity in audio using energy-based VAD
-    fn detect_voice_activity(
-        samples: &[f32],
-        sample_rate: u32,
+    /// Energy-based VAD over 10ms frames: voiced frames are written out as they are read,
+    /// silent frames are dropped. Voiced audio is also pitch-tracked for speaker estimation.
+    fn stream_voice_frames<R: std::io::Read, W: std::io::Write + std::io::Seek>(
+        reader: &mut hound::WavReader<R>,
+        writer: &mut hound::WavWriter<W>,
         silence_threshold_db: f32,
-    ) -> Result<Vec<(usize, usize)>, String> {
-        let frame_size = (sample_rate as usize) / 100; // 10ms frames
-        let mut segments = Vec::new();
-        let mut in_speech = false;
-        let mut segment_start = 0;
+        min_speech_duration_ms: u32,
+    ) -> Result<StreamedAudio, String> {
+        let spec = reader.spec();
+        let channels = spec.channels.max(1) as usize;
+        let frame_size = (spec.sample_rate as usize / 100).max(1); // 10ms frames
+        let pitch_window_size = spec.sample_rate as usize * PITCH_WINDOW_MS / 1000;
+        let min_speech_samples = spec.sample_rate as usize * channels * min_speech_duration_ms as usize / 1000;
 
         // Convert dB threshold to linear amplitude
         let threshold_linear = 10f32.powf(silence_threshold_db / 20.0) * 0.001; // normalize
 
-        for (i, chunk) in samples.chunks(frame_size).enumerate() {
+        let mut streamed = StreamedAudio::default();
+        let mut segment_start: Option<usize> = None;
+        let mut frame = Vec::with_capacity(frame_size);
+        let mut pitch_window = Vec::with_capacity(pitch_window_size);
+        let mut segment_pitches = Vec::new();
+        // Per-frame stages take microseconds, so sum durations and round once at the end
+        let (mut decode, mut analysis, mut write) = (Duration::ZERO, Duration::ZERO, Duration::ZERO);
+        let mut samples = Self::sample_stream(reader);
+
+        // Segments long enough to be speech contribute their median pitch
+        let close_segment = |streamed: &mut StreamedAudio, start: usize, pitches: &mut Vec<f32>| {
+            let end = streamed.total_samples;
+            streamed.segments.push((start, end));
+            if end - start >= min_speech_samples && !pitches.is_empty() {
+                pitches.sort_by(|a, b| a.total_cmp(b));
+                streamed.segment_pitches_hz.push(pitches[pitches.len() / 2]);
+            }
+            pitches.clear();
+        };
+
+        loop {
+            let started = Instant::now();
+            frame.clear();
+            for sample in samples.by_ref().take(frame_size) {
+                frame.push(sample?);
+            }
+            decode += started.elapsed();
+            if frame.is_empty() {
+                break;
+            }
+
+            let started = Instant::now();
             // Calculate energy of frame
-            let energy: f32 = chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32;
+            let energy: f32 = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
             let is_speech = energy.sqrt() > threshold_linear;
 
-            if is_speech && !in_speech {
-                segment_start = i * frame_size;
-                in_speech = true;
-            } else if !is_speech && in_speech {
-                segments.push((segment_start, i * frame_size));
-                in_speech = false;
+            match (is_speech, segment_start) {
+                (true, None) => segment_start = Some(streamed.total_samples),
+                (false, Some(start)) => {
+                    close_segment(&mut streamed, start, &mut segment_pitches);
+                    pitch_window.clear();
+                    segment_start = None;
+                }
+                _ => {}
             }
-        }
-
-        // Close last segment if still in speech
-        if in_speech {
-            segments.push((segment_start, samples.len()));
-        }
 
-        debug!("Found {} voice segments", segments.len());
-        Ok(segments)
-    }
-
-    /// Remove silence segments and concatenate voice segments
-    fn remove_silence(
-        samples: &[f32],
-        voice_segments: &[(usize, usize)],
-    ) -> Result<Vec<f32>, String> {
-        let mut cleaned = Vec::new();
+            if is_speech {
+                // Pitch is tracked on the first channel only
+                pitch_window.extend(frame.iter().step_by(channels));
+                if pitch_window.len() >= pitch_window_size {
+                    segment_pitches.extend(estimate_pitch_hz(&pitch_window, spec.sample_rate));
+                    pitch_window.clear();
+                }
+            }
+            analysis += started.elapsed();
 
-        for (start, end) in voice_segments {
-            if *start < samples.len() && *end <= samples.len() {
-                cleaned.extend_from_slice(&samples[*start..*end]);
+            if is_speech {
+                let started = Instant::now();
+                for sample in &frame {
+                    let normalized = (*sample * 32767.0).clamp(-32768.0, 32767.0) as i16;
+                    writer.write_sample(normalized).map_err(|e| format!("Write sample error: {}", e))?;
+                }
+                streamed.kept_samples += frame.len();
+                write += started.elapsed();
             }
+            streamed.total_samples += frame.len();
         }
 
-        debug!("Removed {} silence frames", samples.len() - cleaned.len());
-        Ok(cleaned)
+        // Close last segment if still in speech
+        if let Some(start) = segment_start {
+            close_segment(&mut streamed, start, &mut segment_pitches);
+        }
+
+        streamed.timings = CleaningTimings {
+            decode_ms: decode.as_millis() as u64,
+            analysis_ms: analysis.as_millis() as u64,
+            write_ms: write.as_millis() as u64,
+        };
+        Ok(streamed)
     }
 
     /// Remove static/noise segments
@@ -164,33 +254,15 @@ impl AudioCleaner {
         Ok(inactivity_segments)
     }
 
-    /// Read audio samples from WAV file
-    fn read_samples(reader: &mut hound::WavReader<std::io::BufReader<std::fs::File>>) -> Result<Vec<f32>, String> {
-        let mut samples = Vec::new();
-        let spec = reader.spec();
-
-        match spec.bits_per_sample {
-            16 => {
-                for sample in reader.samples::<i16>() {
-                    let s = sample.map_err(|e| format!("Sample read error: {}", e))? as f32 / 32768.0; // normalize to -1.0..1.0
-                    samples.push(s);
-                }
-            }
-            32 => {
-                for sample in reader.samples::<i32>() {
-                    let s = sample.map_err(|e| format!("Sample read error: {}", e))? as f32 / i32::MAX as f32;
-                    samples.push(s);
-                }
-            }
-            _ => {
-                for sample in reader.samples::<i16>() {
-                    let s = sample.map_err(|e| format!("Sample read error: {}", e))? as f32 / 32768.0;
-                    samples.push(s);
-                }
-            }
+    /// Decode samples lazily, normalized to -1.0..1.0
+    fn sample_stream<'r, R: std::io::Read>(
+        reader: &'r mut hound::WavReader<R>,
+    ) -> Box<dyn Iterator<Item = Result<f32, String>> + 'r> {
+        let error = |e: hound::Error| format!("Sample read error: {}", e);
+        match reader.spec().bits_per_sample {
+            32 => Box::new(reader.samples::<i32>().map(move |s| s.map(|s| s as f32 / i32::MAX as f32).map_err(error))),
+            _ => Box::new(reader.samples::<i16>().map(move |s| s.map(|s| s as f32 / 32768.0).map_err(error))),
         }
-
-        Ok(samples)
     }
 
     /// Generate cleaning report
@@ -215,27 +287,117 @@ impl AudioCleaner {
     }
 }
 
+/// Summary of a cleaned recording; the audio itself is already on disk
 pub struct CleanedAudio {
-    pub samples: Vec<f32>,
     pub voice_segments: Vec<(usize, usize)>,
     pub original_duration_ms: u32,
     pub cleaned_duration_ms: u32,
     pub compression_ratio: f32,
+    pub estimated_speakers: u32,  // distinct voices by pitch, 0 when no usable speech
+    pub total_samples: usize,
     pub spec: hound::WavSpec,
+    pub timings: CleaningTimings,
 }
 
 impl CleanedAudio {
-    /// Save cleaned audio to new file
-    pub async fn save(&self, output_path: &Path) -> Result<(), String> {
-        let mut writer = hound::WavWriter::create(output_path, self.spec).map_err(|e| format!("Failed to create WAV: {}", e))?;
+    /// Voice segments as millisecond offsets into the original recording
+    pub fn voice_segments_ms(&self) -> Vec<(u64, u64)> {
+        let samples_per_second = (self.spec.sample_rate as u64 * self.spec.channels.max(1) as u64).max(1);
+        self.voice_segments
+            .iter()
+            .map(|(start, end)| (*start as u64 * 1000 / samples_per_second, *end as u64 * 1000 / samples_per_second))
+            .collect()
+    }
 
-        for sample in &self.samples {
-            let normalized = (*sample * 32767.0).clamp(-32768.0, 32767.0) as i16;
-            writer.write_sample(normalized).map_err(|e| format!("Write sample error: {}", e))?;
-        }
+    /// Cleaning report; silent stretches between and around voice segments count as inactivity
+    pub fn report(&self) -> serde_json::Value {
+        let mut boundaries = vec![0];
+        boundaries.extend(self.voice_segments.iter().flat_map(|(start, end)| [*start, *end]));
+        boundaries.push(self.total_samples);
+        let silent_stretches = boundaries.chunks(2).filter(|pair| pair[1] > pair[0]).count();
+
+        AudioCleaner::generate_report(
+            self.original_duration_ms,
+            self.cleaned_duration_ms,
+            self.voice_segments.len(),
+            silent_stretches,
+        )
+    }
+}
 
-        writer.finalize().map_err(|e| format!("Finalize WAV error: {}", e))?;
-        info!("✓ Cleaned audio saved to: {:?}", output_path);
-        Ok(())
+/// Output of one streaming pass over a recording
+#[derive(Default)]
+struct StreamedAudio {
+    segments: Vec<(usize, usize)>,
+    total_samples: usize,
+    kept_samples: usize,
+    segment_pitches_hz: Vec<f32>,  // median pitch of each speech-length segment
+    timings: CleaningTimings,
+}
+
+/// Time spent on each stage of cleaning one file
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleaningTimings {
+    pub decode_ms: u64,
+    pub analysis_ms: u64,
+    pub write_ms: u64,
+}
+
+impl CleaningTimings {
+    pub fn total_ms(&self) -> u64 {
+        self.decode_ms + self.analysis_ms + self.write_ms
     }
 }
+
+/// Fundamental frequency of a voiced window by autocorrelation, if it is clearly periodic
+fn estimate_pitch_hz(window: &[f32], sample_rate: u32) -> Option<f32> {
+    let min_lag = (sample_rate as f32 / MAX_PITCH_HZ) as usize;
+    let max_lag = ((sample_rate as f32 / MIN_PITCH_HZ) as usize).min(window.len().saturating_sub(1));
+    let energy: f32 = window.iter().map(|s| s * s).sum();
+    if min_lag == 0 || min_lag >= max_lag || energy == 0.0 {
+        return None;
+    }
+
+    let (lag, correlation) = (min_lag..=max_lag)
+        .map(|lag| {
+            let correlation: f32 = window.iter().zip(&window[lag..]).map(|(a, b)| a * b).sum();
+            (lag, correlation / energy)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    (correlation >= MIN_VOICING).then(|| sample_rate as f32 / lag as f32)
+}
+
+/// Count distinct voices by grouping segment pitches that lie close together
+fn count_speakers(segment_pitches_hz: &[f32]) -> u32 {
+    let mut pitches = segment_pitches_hz.to_vec();
+    pitches.sort_by(|a, b| a.total_cmp(b));
+    let mut speakers = 0;
+    let mut cluster_floor = None;
+    for pitch in pitches {
+        if cluster_floor.is_none_or(|floor: f32| pitch > floor * SPEAKER_PITCH_SEPARATION) {
+            speakers += 1;
+            cluster_floor = Some(pitch);
+        }
+    }
+    speakers
+}
+
+/// Clean one recording and write `<name>_cleaned.wav` next to it
+pub fn clean_file(audio_path: &Path) -> Result<(CleanedAudio, PathBuf), String> {
+    let cleaned_filename = audio_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.replace(".wav", "_cleaned.wav"))
+        .unwrap_or_else(|| "cleaned.wav".to_string());
+
+    // Cleaned audio sits next to the raw recording in its day directory
+    let cleaned_path = audio_path.with_file_name(cleaned_filename);
+    let cleaned = AudioCleaner::clean_audio_segment(audio_path, &cleaned_path, SILENCE_THRESHOLD_DB, MIN_SPEECH_DURATION_MS)?;
+    Ok((cleaned, cleaned_path))
+}
+
+/// Clean a recording held in memory with the same settings as `clean_file`
+pub fn clean_bytes(audio: &[u8]) -> Result<(CleanedAudio, Vec<u8>), String> {
+    AudioCleaner::clean_audio_bytes(audio, SILENCE_THRESHOLD_DB, MIN_SPEECH_DURATION_MS)
+}
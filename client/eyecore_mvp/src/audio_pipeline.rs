@@ -0,0 +1,110 @@
+use log::{info, error};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinHandle;
+
+use crate::audio_cleaner::{clean_bytes, clean_file, CleanedAudio};
+use crate::meeting::MeetingDetector;
+use crate::storage::{encryption, DataStorage};
+
+/// Concurrent cleaning jobs when EYECORE_AUDIO_WORKERS is not set
+const DEFAULT_WORKERS: usize = 2;
+
+/// Clean recordings from `audio_rx` on blocking threads, at most `workers` at a time,
+/// so DSP work never stalls the async runtime. Results are recorded in each file's metadata sidecar.
+/// The returned task ends once every sender is dropped and the queued files are cleaned.
+pub fn start_cleaning_pool(
+    mut audio_rx: mpsc::Receiver<PathBuf>,
+    workers: usize,
+    storage: Arc<DataStorage>,
+    meeting_detector: Arc<MeetingDetector>,
+) -> JoinHandle<()> {
+    let workers = workers.max(1);
+    let permits = Arc::new(Semaphore::new(workers));
+
+    tokio::spawn(async move {
+        info!("🧹 Audio cleaning pipeline started ({} workers)", workers);
+
+        while let Some(audio_path) = audio_rx.recv().await {
+            // Wait for a free worker; queued files stay in the channel meanwhile
+            let Ok(permit) = Arc::clone(&permits).acquire_owned().await else {
+                break;
+            };
+
+            let storage = Arc::clone(&storage);
+            let meeting_detector = Arc::clone(&meeting_detector);
+            tokio::spawn(async move {
+                info!("Cleaning audio file: {:?}", audio_path);
+                let result = clean_recording(&storage, &audio_path).await;
+                drop(permit);
+
+                match result {
+                    Ok(Ok((cleaned, cleaned_path))) => {
+                        info!(
+                            "✓ Audio cleaned: {}ms -> {}ms in {}ms (decode {}ms, analysis {}ms, write {}ms) {:?}",
+                            cleaned.original_duration_ms,
+                            cleaned.cleaned_duration_ms,
+                            cleaned.timings.total_ms(),
+                            cleaned.timings.decode_ms,
+                            cleaned.timings.analysis_ms,
+                            cleaned.timings.write_ms,
+                            audio_path
+                        );
+                        let mut recorded_at = None;
+                        let update = storage.update_audio_metadata(&audio_path, |metadata| {
+                            recorded_at = Some(metadata.recorded_at);
+                            metadata.estimated_speakers = Some(cleaned.estimated_speakers);
+                            metadata.voice_segments_ms = cleaned.voice_segments_ms();
+                            metadata.cleaned_file = cleaned_path.file_name().map(|n| n.to_string_lossy().into_owned());
+                            metadata.cleaning_report = Some(cleaned.report());
+                        });
+                        if let Err(e) = update.await {
+                            error!("Failed to update audio metadata for {:?}: {}", audio_path, e);
+                        }
+                        meeting_detector
+                            .record_speakers(recorded_at.unwrap_or_else(chrono::Utc::now), cleaned.estimated_speakers)
+                            .await;
+                    }
+                    Ok(Err(e)) => error!("Audio cleaning failed for {:?}: {}", audio_path, e),
+                    Err(e) => error!("Audio cleaning worker panicked for {:?}: {}", audio_path, e),
+                }
+            });
+        }
+
+        // Channel closed: wait for the jobs still running
+        let _ = permits.acquire_many(workers as u32).await;
+        info!("🧹 Audio cleaning queue drained");
+    })
+}
+
+/// Clean one saved recording on a blocking thread. Sealed recordings are decrypted and cleaned in
+/// memory, and the cleaned audio is stored sealed too, so no plaintext audio reaches the disk.
+async fn clean_recording(
+    storage: &DataStorage,
+    audio_path: &Path,
+) -> Result<Result<(CleanedAudio, PathBuf), String>, tokio::task::JoinError> {
+    let path = audio_path.to_path_buf();
+    if !encryption::is_encrypted(audio_path) {
+        return tokio::task::spawn_blocking(move || clean_file(&path)).await;
+    }
+    let audio = match storage.read_stored(audio_path).await {
+        Ok(audio) => audio,
+        Err(e) => return Ok(Err(e.to_string())),
+    };
+    let (cleaned, wav) = match tokio::task::spawn_blocking(move || clean_bytes(&audio)).await? {
+        Ok(cleaned) => cleaned,
+        Err(e) => return Ok(Err(e)),
+    };
+    let saved = storage.save_cleaned_audio(audio_path, wav).await;
+    Ok(saved.map(|cleaned_path| (cleaned, cleaned_path)).map_err(|e| e.to_string()))
+}
+
+/// Worker count from EYECORE_AUDIO_WORKERS
+pub fn workers_from_env() -> usize {
+    std::env::var("EYECORE_AUDIO_WORKERS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n: &usize| n > 0)
+        .unwrap_or(DEFAULT_WORKERS)
+}
@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::data_collector::DataCollector;
+use crate::input_hooks;
+use crate::models::{Bookmark, EyeCoreData, FlagRecord, FlagTypeTag};
+use crate::storage::DataStorage;
+
+/// Snapshots kept on each side of a bookmark
+pub const BOOKMARK_WINDOW: std::time::Duration = std::time::Duration::from_secs(60);
+pub const MAX_NOTE_LENGTH: usize = 1_000;
+
+/// Holding the hotkey auto-repeats; presses closer together than this are one bookmark
+const HOTKEY_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Mark "now" for later review: saves the bookmark with the snapshots of the last minute, raises a
+/// user flag next to the detector's flags, and adds the following minute's snapshots once collected
+pub async fn create(
+    collector: Arc<RwLock<DataCollector>>,
+    storage: Arc<DataStorage>,
+    note: Option<String>,
+    source: &str,
+) -> std::io::Result<Bookmark> {
+    let now = Utc::now();
+    let window = chrono::Duration::from_std(BOOKMARK_WINDOW).expect("window fits chrono");
+    let (session_id, snapshots) = {
+        let collector = collector.read().await;
+        (collector.session_id().to_string(), snapshots_between(&collector, now - window, now))
+    };
+
+    let bookmark = Bookmark {
+        id: Uuid::new_v4().to_string(),
+        timestamp: now,
+        session_id,
+        note,
+        source: source.to_string(),
+        snapshots,
+    };
+    storage.save_bookmark(&bookmark).await?;
+    storage
+        .save_flag(&FlagRecord {
+            id: bookmark.id.clone(),
+            timestamp: bookmark.timestamp,
+            session_id: bookmark.session_id.clone(),
+            flag_type: FlagTypeTag { name: "UserBookmark".to_string() },
+            severity: "Informational".to_string(),
+            title: "User Bookmark".to_string(),
+            description: bookmark.note.clone().unwrap_or_else(|| "Marked by the user".to_string()),
+            data_source: "user".to_string(),
+            metrics: json!({ "bookmark_id": bookmark.id, "source": bookmark.source }),
+            confidence: 1.0,
+        })
+        .await?;
+    info!("🔖 Bookmark set from {}: {}", bookmark.source, bookmark.note.as_deref().unwrap_or("(no note)"));
+
+    let pending = bookmark.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(BOOKMARK_WINDOW).await;
+        let mut bookmark = pending;
+        let following = snapshots_between(&*collector.read().await, bookmark.timestamp, bookmark.timestamp + window);
+        bookmark.snapshots.extend(following);
+        if let Err(e) = storage.save_bookmark(&bookmark).await {
+            error!("Failed to add following snapshots to bookmark {}: {}", bookmark.id, e);
+        }
+    });
+
+    Ok(bookmark)
+}
+
+/// Snapshots still in the collector's history taken in (`start`, `end`], oldest first
+fn snapshots_between(collector: &DataCollector, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<EyeCoreData> {
+    let (page, _) = collector.get_history_page(None, collector.history_capacity());
+    page.into_iter()
+        .rev()
+        .map(|(_, data)| data)
+        .filter(|data| data.timestamp > start && data.timestamp <= end)
+        .cloned()
+        .collect()
+}
+
+/// Set a bookmark whenever Ctrl+Alt+B is pressed, in any application
+pub fn start_hotkey_listener(collector: Arc<RwLock<DataCollector>>, storage: Arc<DataStorage>) {
+    let (presses, mut pressed) = tokio::sync::mpsc::unbounded_channel::<()>();
+
+    let (mut ctrl, mut alt) = (false, false);
+    let mut last_press: Option<std::time::Instant> = None;
+    input_hooks::global().subscribe(move |event| {
+        use rdev::{EventType, Key};
+
+        match event.event_type {
+            EventType::KeyPress(Key::ControlLeft | Key::ControlRight) => ctrl = true,
+            EventType::KeyRelease(Key::ControlLeft | Key::ControlRight) => ctrl = false,
+            EventType::KeyPress(Key::Alt | Key::AltGr) => alt = true,
+            EventType::KeyRelease(Key::Alt | Key::AltGr) => alt = false,
+            EventType::KeyPress(Key::KeyB)
+                if ctrl && alt && last_press.is_none_or(|last| last.elapsed() >= HOTKEY_DEBOUNCE) =>
+            {
+                last_press = Some(std::time::Instant::now());
+                let _ = presses.send(());
+            }
+            _ => {}
+        }
+    });
+
+    tokio::spawn(async move {
+        while pressed.recv().await.is_some() {
+            if let Err(e) = create(Arc::clone(&collector), Arc::clone(&storage), None, "hotkey").await {
+                error!("Failed to save bookmark: {}", e);
+            }
+        }
+    });
+    info!("✓ Bookmark hotkey listening (Ctrl+Alt+B)");
+}
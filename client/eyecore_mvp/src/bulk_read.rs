@@ -0,0 +1,325 @@
+//! Read path for bulk scans of stored snapshots (replay, reports, queries over many days).
+//! Files that are never modified in place, the archives compaction merges past days into
+//! (`.archive`) and timeslot files of past days, are memory-mapped and parsed in place; today's files
+//! are read into a buffer, as a file truncated while mapped raises SIGBUS instead of an error. Only
+//! the `data` part of a timeslot file is deserialized, straight into `EyeCoreData` without an
+//! intermediate `serde_json::Value`. Snapshot logs (`.ndjson`) are read line by line, and archives
+//! record by record through their index.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use log::error;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::BufRead;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use crate::models::EyeCoreData;
+
+/// Appended to the name of zstd-compressed files: `<name>.json.zst`
+pub const COMPRESSED_SUFFIX: &str = ".zst";
+
+/// Name suffix of append-only snapshot logs, one timeslot object per line
+pub const SNAPSHOT_LOG_SUFFIX: &str = ".ndjson";
+
+/// Name suffix of a day of timeslot files merged into one file by compaction
+pub const SNAPSHOT_ARCHIVE_SUFFIX: &str = ".archive";
+
+// An archive is a header (magic, version, flags), the timeslot objects each prefixed with their u32
+// little-endian length, the JSON index, and a footer: the index offset as a u64 and the magic again
+pub const ARCHIVE_MAGIC: &[u8] = b"EYEARC";
+pub const ARCHIVE_VERSION: u8 = 1;
+/// Header flag: records are zstd-compressed
+pub const ARCHIVE_ZSTD: u8 = 1;
+pub const ARCHIVE_HEADER_LEN: usize = ARCHIVE_MAGIC.len() + 2;
+const ARCHIVE_FOOTER_LEN: usize = 8 + ARCHIVE_MAGIC.len();
+
+/// Lookup index at the end of an archive
+#[derive(Default, Serialize, Deserialize)]
+pub struct ArchiveIndex {
+    pub records: Vec<ArchiveEntry>,  // sorted by name
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub name: String,                // the timeslot file the record was, without `.zst`
+    pub timestamp: DateTime<Utc>,
+    pub offset: u64,                 // of the record, after its length
+    pub length: u64,
+}
+
+/// Timeslot file as written by `DataStorage::save_data_snapshot`; `metadata` is skipped
+#[derive(Deserialize)]
+struct TimeslotFile {
+    data: EyeCoreData,
+}
+
+/// Only the capture time of a timeslot object; `data` is skipped over unparsed
+#[derive(Deserialize)]
+struct TimeslotTimestamp {
+    metadata: TimeslotMetadata,
+}
+
+#[derive(Deserialize)]
+struct TimeslotMetadata {
+    timestamp: DateTime<Utc>,
+}
+
+/// File contents, either mapped or read into memory
+enum FileBytes {
+    Mapped(Mmap),
+    Read(Vec<u8>),
+}
+
+impl Deref for FileBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileBytes::Mapped(map) => map,
+            FileBytes::Read(bytes) => bytes,
+        }
+    }
+}
+
+/// Whether bulk scans memory-map `path`: archives, and plain timeslot files captured before today
+/// (UTC). Both are only ever replaced by a rename or deleted, never truncated or rewritten in place,
+/// so a mapping stays valid; today's files may still be rewritten.
+pub fn is_mapped(path: &Path) -> bool {
+    if is_snapshot_archive(path) {
+        return true;
+    }
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let captured = name.get(..10).and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+    is_json_file(path) && !is_compressed(path) && captured.is_some_and(|date| date < Utc::now().date_naive())
+}
+
+fn read_bytes(path: &Path) -> std::io::Result<FileBytes> {
+    if !is_mapped(path) {
+        return std::fs::read(path).map(FileBytes::Read);
+    }
+    let file = File::open(path)?;
+    // Safety: see `is_mapped`; a file replaced or deleted mid-scan keeps its old contents mapped
+    let map = unsafe { Mmap::map(&file)? };
+    Ok(FileBytes::Mapped(map))
+}
+
+/// A stored JSON file, plain or compressed
+pub fn is_json_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".json") || name.ends_with(".json.zst"))
+}
+
+pub fn is_snapshot_log(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(SNAPSHOT_LOG_SUFFIX))
+}
+
+pub fn is_snapshot_archive(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(SNAPSHOT_ARCHIVE_SUFFIX))
+}
+
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("zst")
+}
+
+/// Contents of a stored JSON file as read from disk, decompressed if it is a `.json.zst` file
+pub fn decode(path: &Path, bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    if is_compressed(path) {
+        zstd::decode_all(bytes.as_slice())
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Parse one timeslot file
+pub fn read_snapshot(path: &Path) -> Result<EyeCoreData, String> {
+    // Compressed files are small and have to be decoded anyway, so they are never mapped
+    let bytes = if is_compressed(path) {
+        FileBytes::Read(std::fs::read(path).and_then(|bytes| decode(path, bytes)).map_err(|e| e.to_string())?)
+    } else {
+        read_bytes(path).map_err(|e| e.to_string())?
+    };
+    parse_snapshot(&bytes)
+}
+
+/// Parse the contents of a timeslot file, already read and decoded
+pub fn parse_snapshot(bytes: &[u8]) -> Result<EyeCoreData, String> {
+    serde_json::from_slice::<TimeslotFile>(bytes)
+        .map(|file| file.data)
+        .map_err(|e| e.to_string())
+}
+
+/// Visit the complete lines of a snapshot log in order; a last line still being written is left
+/// out. Lines that fail to parse are logged and skipped.
+fn scan_log_lines<T: for<'de> Deserialize<'de>>(path: &Path, mut visit: impl FnMut(T)) -> Result<(), String> {
+    let mut reader = std::io::BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+    let mut line = Vec::new();
+    for number in 1.. {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).map_err(|e| e.to_string())? == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        match serde_json::from_slice::<T>(&line) {
+            Ok(value) => visit(value),
+            Err(e) => error!("Skipping unreadable line {} of {:?}: {}", number, path, e),
+        }
+    }
+    Ok(())
+}
+
+/// Parse the snapshots of one snapshot log
+pub fn read_snapshot_log(path: &Path, mut visit: impl FnMut(EyeCoreData)) -> Result<(), String> {
+    scan_log_lines(path, |file: TimeslotFile| visit(file.data))
+}
+
+/// Capture times of the snapshots in a snapshot log, without parsing their data
+pub fn snapshot_log_timestamps(path: &Path) -> Result<Vec<DateTime<Utc>>, String> {
+    let mut timestamps = Vec::new();
+    scan_log_lines(path, |line: TimeslotTimestamp| timestamps.push(line.metadata.timestamp))?;
+    Ok(timestamps)
+}
+
+/// An opened archive, mapped; records are read from the mapping and decompressed on demand
+pub struct SnapshotArchive {
+    bytes: FileBytes,
+    compressed: bool,
+    index: ArchiveIndex,
+}
+
+impl SnapshotArchive {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let bytes = read_bytes(path).map_err(|e| e.to_string())?;
+        let invalid = |reason: &str| format!("Invalid snapshot archive: {}", reason);
+        if bytes.len() < ARCHIVE_HEADER_LEN + ARCHIVE_FOOTER_LEN || !bytes.starts_with(ARCHIVE_MAGIC) {
+            return Err(invalid("no archive header"));
+        }
+        if bytes[ARCHIVE_MAGIC.len()] != ARCHIVE_VERSION {
+            return Err(invalid(&format!("unknown version {}", bytes[ARCHIVE_MAGIC.len()])));
+        }
+        let compressed = bytes[ARCHIVE_MAGIC.len() + 1] & ARCHIVE_ZSTD != 0;
+        let footer = &bytes[bytes.len() - ARCHIVE_FOOTER_LEN..];
+        if !footer.ends_with(ARCHIVE_MAGIC) {
+            return Err(invalid("no footer, the file is incomplete"));
+        }
+        let index_offset = u64::from_le_bytes(footer[..8].try_into().expect("the footer starts with 8 bytes")) as usize;
+        let index = bytes
+            .get(index_offset..bytes.len() - ARCHIVE_FOOTER_LEN)
+            .ok_or_else(|| invalid("index offset out of range"))?;
+        let index = serde_json::from_slice(index).map_err(|e| invalid(&e.to_string()))?;
+        Ok(SnapshotArchive { bytes, compressed, index })
+    }
+
+    pub fn entries(&self) -> &[ArchiveEntry] {
+        &self.index.records
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// Whether the timeslot file `name` was merged into this archive
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.records.binary_search_by(|entry| entry.name.as_str().cmp(name)).is_ok()
+    }
+
+    /// The timeslot object of one record, decompressed
+    pub fn record(&self, entry: &ArchiveEntry) -> Result<Cow<'_, [u8]>, String> {
+        let record = usize::try_from(entry.offset)
+            .ok()
+            .zip(usize::try_from(entry.length).ok())
+            .and_then(|(offset, length)| self.bytes.get(offset..offset.checked_add(length)?))
+            .ok_or_else(|| format!("Record {} is out of range", entry.name))?;
+        if self.compressed {
+            zstd::decode_all(record).map(Cow::Owned).map_err(|e| e.to_string())
+        } else {
+            Ok(Cow::Borrowed(record))
+        }
+    }
+}
+
+/// Parse the snapshots of one archive in index order; records that fail to parse are logged and
+/// skipped
+pub fn read_snapshot_archive(path: &Path, mut visit: impl FnMut(EyeCoreData)) -> Result<(), String> {
+    let archive = SnapshotArchive::open(path)?;
+    for entry in archive.entries() {
+        match archive.record(entry).and_then(|record| parse_snapshot(&record)) {
+            Ok(data) => visit(data),
+            Err(e) => error!("Skipping unreadable record {} of {:?}: {}", entry.name, path, e),
+        }
+    }
+    Ok(())
+}
+
+/// Visit the snapshots in `paths` (timeslot files, snapshot logs and archives) one at a time, in order,
+/// skipping (and logging) files that fail to read or parse. Only one file, log line or archive record
+/// is held in memory at a time. Blocking; run off the async runtime.
+pub fn scan_snapshots(paths: &[PathBuf], mut visit: impl FnMut(EyeCoreData)) {
+    for path in paths {
+        if is_snapshot_log(path) {
+            if let Err(e) = read_snapshot_log(path, &mut visit) {
+                error!("Skipping unreadable snapshot log {:?}: {}", path, e);
+            }
+            continue;
+        }
+        if is_snapshot_archive(path) {
+            if let Err(e) = read_snapshot_archive(path, &mut visit) {
+                error!("Skipping unreadable snapshot archive {:?}: {}", path, e);
+            }
+            continue;
+        }
+        if !is_json_file(path) {
+            continue;
+        }
+        match read_snapshot(path) {
+            Ok(data) => visit(data),
+            Err(e) => error!("Skipping unreadable snapshot {:?}: {}", path, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncated_files_are_errors() {
+        let dir = std::env::temp_dir().join(format!("eyecore_bulk_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let snapshot = dir.join("2026-10-16_13-35-36-104_495d9930_00000001.json");
+        std::fs::write(&snapshot, br#"{"metadata": {"timestamp": "2026-10-16T13:35:36Z"}, "data": {"session_id": "#).unwrap();
+        assert!(read_snapshot(&snapshot).is_err());
+
+        let archive = dir.join(format!("2026-10-16{}", SNAPSHOT_ARCHIVE_SUFFIX));
+        std::fs::write(&archive, [ARCHIVE_MAGIC, &[ARCHIVE_VERSION, 0], b"{\"records\": []}"].concat()).unwrap();
+        assert!(SnapshotArchive::open(&archive).is_err());
+
+        let mut visited = 0;
+        scan_snapshots(&[snapshot, archive], |_| visited += 1);
+        assert_eq!(visited, 0);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn only_files_never_rewritten_are_mapped() {
+        let today = Utc::now().format("%Y-%m-%d");
+        let timeslots = Path::new("data/device/2026/01/02/timeslots");
+        assert!(is_mapped(&timeslots.join("2026-01-02_13-35-36-104_495d9930_00000001.json")));
+        assert!(!is_mapped(&timeslots.join(format!("{}_13-35-36-104_495d9930_00000001.json", today))));
+        assert!(!is_mapped(&timeslots.join("2026-01-02_13-35-36-104_495d9930_00000001.json.zst")));
+        assert!(!is_mapped(&timeslots.join("2026-01-02_session.ndjson")));
+        assert!(is_mapped(&timeslots.join(format!("2026-01-02{}", SNAPSHOT_ARCHIVE_SUFFIX))));
+    }
+}
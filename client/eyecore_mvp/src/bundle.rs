@@ -0,0 +1,215 @@
+//! Configuration bundles: one signed JSON file holding a device's configuration, the flag detector's
+//! severity policy and SIEM forwarding rules, the module consents and the personal baseline, so
+//! IT can provision machines alike and users can move to a new laptop.
+//!
+//! Bundles are signed with an ed25519 key (EYECORE_BUNDLE_SIGNING_KEY) and only imported when the
+//! signature matches EYECORE_BUNDLE_PUBLIC_KEY. Network labels are not carried over: they are salted
+//! with the device ID and mean nothing on another machine.
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, Config};
+use crate::consent::ConsentManager;
+use crate::models::Baseline;
+use crate::onboarding::Onboarding;
+use crate::storage;
+
+const BUNDLE_FORMAT: &str = "eyecore-config-bundle";
+const BUNDLE_VERSION: u32 = 1;
+/// Where imported flag detector files go when FLAG_SEVERITY_POLICY / FLAG_SIEM_CONFIG are unset
+const POLICY_DIR: &str = "policies";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleContents {
+    pub config: Config,
+    pub severity_policy: Option<serde_json::Value>,
+    pub siem_rules: Option<serde_json::Value>,
+    pub consents: Option<BTreeMap<String, bool>>,
+    pub baseline: Option<Baseline>,
+}
+
+/// Everything the signature covers
+#[derive(Debug, Serialize, Deserialize)]
+struct UnsignedBundle {
+    format: String,
+    version: u32,
+    created_at: DateTime<Utc>,
+    source_device: String,
+    contents: BundleContents,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SignedBundle {
+    #[serde(flatten)]
+    bundle: UnsignedBundle,
+    signature: String,             // base64 ed25519 signature of the other fields as JSON
+}
+
+/// What an import wrote, for the CLI to report
+pub struct ImportReport {
+    pub source_device: String,
+    pub created_at: DateTime<Utc>,
+    pub written: Vec<PathBuf>,
+}
+
+/// Bytes the signature is computed over. They go through `serde_json::Value` so that signing and
+/// verification serialize nested policy documents identically.
+fn signed_bytes(bundle: &UnsignedBundle) -> Result<Vec<u8>, String> {
+    let value = serde_json::to_value(bundle).map_err(|e| e.to_string())?;
+    serde_json::to_vec(&value).map_err(|e| e.to_string())
+}
+
+fn decode_key(variable: &str) -> Result<[u8; 32], String> {
+    let encoded = std::env::var(variable).map_err(|_| format!("{} is not set", variable))?;
+    general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("Invalid {} encoding: {}", variable, e))?
+        .try_into()
+        .map_err(|_| format!("{} must be 32 bytes", variable))
+}
+
+/// A fresh key pair as base64 (signing key, public key)
+pub fn generate_keys() -> (String, String) {
+    let signing_key = SigningKey::from_bytes(&rand::random::<[u8; 32]>());
+    (
+        general_purpose::STANDARD.encode(signing_key.to_bytes()),
+        general_purpose::STANDARD.encode(signing_key.verifying_key().to_bytes()),
+    )
+}
+
+fn read_json_file(path: &Path) -> Result<Option<serde_json::Value>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).map(Some).map_err(|e| format!("Invalid JSON in {:?}: {}", path, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {:?}: {}", path, e)),
+    }
+}
+
+/// Flag detector file named by `variable`, if one is configured
+fn flag_detector_file(variable: &str) -> Result<Option<serde_json::Value>, String> {
+    match std::env::var(variable) {
+        Ok(path) => read_json_file(Path::new(&path))?
+            .map(Some)
+            .ok_or_else(|| format!("{} points at {:?}, which does not exist", variable, path)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Collect and sign this device's bundle
+pub async fn export(config: &Config) -> Result<String, String> {
+    let signing_key = SigningKey::from_bytes(&decode_key("EYECORE_BUNDLE_SIGNING_KEY")?);
+    let device_id = storage::load_or_create_device_id(&config.data_dir)
+        .await
+        .map_err(|e| format!("Failed to read device ID: {}", e))?;
+    let device_dir = Path::new(&config.data_dir).join(&device_id);
+
+    let consents = Some(ConsentManager::load(&device_dir).consents()).filter(|consents| !consents.is_empty());
+    let baseline = read_json_file(&device_dir.join("baseline.json"))?
+        .map(serde_json::from_value::<Baseline>)
+        .transpose()
+        .map_err(|e| format!("Invalid baseline: {}", e))?;
+
+    let bundle = UnsignedBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        created_at: Utc::now(),
+        source_device: device_id,
+        contents: BundleContents {
+            config: config.clone(),
+            severity_policy: flag_detector_file("FLAG_SEVERITY_POLICY")?,
+            siem_rules: flag_detector_file("FLAG_SIEM_CONFIG")?,
+            consents,
+            baseline,
+        },
+    };
+    let signature = signing_key.sign(&signed_bytes(&bundle)?);
+    let signed = SignedBundle { bundle, signature: general_purpose::STANDARD.encode(signature.to_bytes()) };
+    serde_json::to_string_pretty(&signed).map_err(|e| e.to_string())
+}
+
+/// Check a bundle's signature against EYECORE_BUNDLE_PUBLIC_KEY and return its contents
+fn verify(content: &str) -> Result<UnsignedBundle, String> {
+    let public_key = VerifyingKey::from_bytes(&decode_key("EYECORE_BUNDLE_PUBLIC_KEY")?)
+        .map_err(|e| format!("Invalid EYECORE_BUNDLE_PUBLIC_KEY: {}", e))?;
+    let signed: SignedBundle = serde_json::from_str(content).map_err(|e| format!("Not a configuration bundle: {}", e))?;
+    if signed.bundle.format != BUNDLE_FORMAT || signed.bundle.version != BUNDLE_VERSION {
+        return Err(format!(
+            "Unsupported bundle {} v{} (expected {} v{})",
+            signed.bundle.format, signed.bundle.version, BUNDLE_FORMAT, BUNDLE_VERSION
+        ));
+    }
+    let signature_bytes = general_purpose::STANDARD
+        .decode(signed.signature.trim())
+        .map_err(|e| format!("Invalid signature encoding: {}", e))?;
+    let signature = Signature::from_slice(&signature_bytes).map_err(|e| format!("Invalid signature: {}", e))?;
+    public_key
+        .verify(&signed_bytes(&signed.bundle)?, &signature)
+        .map_err(|_| "Bundle signature verification failed".to_string())?;
+    Ok(signed.bundle)
+}
+
+/// Write a file, keeping the previous version next to it as `<name>.bak`
+fn replace_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+    }
+    if path.exists() {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".bak");
+        std::fs::copy(path, &backup).map_err(|e| format!("Failed to back up {:?}: {}", path, e))?;
+    }
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Verify a bundle and install its contents on this device. Nothing is written unless the
+/// signature and the bundled config check out.
+pub async fn import(content: &str) -> Result<ImportReport, String> {
+    let bundle = verify(content)?;
+    let contents = bundle.contents;
+    let config_toml = toml::to_string_pretty(&contents.config).map_err(|e| format!("Invalid config in bundle: {}", e))?;
+    config::validate(&contents.config).map_err(|e| format!("Invalid config in bundle: {}", e))?;
+
+    let mut written = Vec::new();
+    let config_path = config::config_path();
+    replace_file(&config_path, &config_toml)?;
+    written.push(config_path);
+
+    let policy_dir = Path::new(&contents.config.data_dir).join(POLICY_DIR);
+    for (variable, document, file_name) in [
+        ("FLAG_SEVERITY_POLICY", &contents.severity_policy, "severity.json"),
+        ("FLAG_SIEM_CONFIG", &contents.siem_rules, "siem.json"),
+    ] {
+        let Some(document) = document else { continue };
+        let path = std::env::var(variable).map(PathBuf::from).unwrap_or_else(|_| policy_dir.join(file_name));
+        let json = serde_json::to_string_pretty(document).map_err(|e| e.to_string())?;
+        replace_file(&path, &json)?;
+        written.push(path);
+    }
+
+    // Consents and the baseline belong to this device's own directory, whatever its ID
+    let device_id = storage::load_or_create_device_id(&contents.config.data_dir)
+        .await
+        .map_err(|e| format!("Failed to read device ID: {}", e))?;
+    let device_dir = Path::new(&contents.config.data_dir).join(&device_id);
+    std::fs::create_dir_all(&device_dir).map_err(|e| format!("Failed to create {:?}: {}", device_dir, e))?;
+    if let Some(consents) = &contents.consents {
+        ConsentManager::load(&device_dir).import(consents, "bundle")?;
+        written.push(device_dir.join("consent.json"));
+        let path = device_dir.join("onboarding.json");
+        Onboarding::load(path.clone(), false).import_consents(consents)?;
+        written.push(path);
+    }
+    if let Some(baseline) = &contents.baseline {
+        let path = device_dir.join("baseline.json");
+        let json = serde_json::to_string_pretty(baseline).map_err(|e| e.to_string())?;
+        replace_file(&path, &json)?;
+        written.push(path);
+    }
+
+    Ok(ImportReport { source_device: bundle.source_device, created_at: bundle.created_at, written })
+}
@@ -0,0 +1,106 @@
+//! Burst-mode capture: when a snapshot raises an anomaly, snapshots are collected at a short
+//! interval for a while (default every second for two minutes) and marked as burst samples, so the
+//! moments around the anomaly have fine-grained context. The interval then eases back to normal.
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use tokio::time::{Duration, Instant};
+
+use crate::models::BurstSample;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_DURATION: Duration = Duration::from_secs(120);
+
+struct ActiveBurst {
+    started_at: DateTime<Utc>,
+    until: Instant,
+    triggers: Vec<String>,
+}
+
+pub struct BurstMode {
+    enabled: bool,
+    interval: Duration,
+    duration: Duration,
+    active: Option<ActiveBurst>,
+}
+
+impl BurstMode {
+    /// EYECORE_BURST=0 turns burst mode off; EYECORE_BURST_INTERVAL_MS and EYECORE_BURST_DURATION_SECS
+    /// set the raised rate and how long it lasts after the latest anomaly
+    pub fn from_env() -> Self {
+        let enabled = !matches!(std::env::var("EYECORE_BURST").as_deref().map(str::trim), Ok("0" | "false" | "off"));
+        let read = |name: &str, unit: fn(u64) -> Duration, default: Duration| match std::env::var(name) {
+            Ok(value) => match value.trim().parse::<u64>() {
+                Ok(n) if n > 0 => unit(n),
+                _ => {
+                    warn!("Ignoring {}: {:?} is not a positive number", name, value);
+                    default
+                }
+            },
+            Err(_) => default,
+        };
+        BurstMode {
+            enabled,
+            interval: read("EYECORE_BURST_INTERVAL_MS", Duration::from_millis, DEFAULT_INTERVAL),
+            duration: read("EYECORE_BURST_DURATION_SECS", Duration::from_secs, DEFAULT_DURATION),
+            active: None,
+        }
+    }
+
+    /// Start a burst for these anomalies, or extend the running one by the full duration
+    pub fn trigger(&mut self, anomalies: &[serde_json::Value]) {
+        if !self.enabled || anomalies.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let starting = self.active.as_ref().is_none_or(|burst| burst.until <= now);
+        if starting {
+            self.active = Some(ActiveBurst {
+                started_at: Utc::now(),
+                until: now,
+                triggers: Vec::new(),
+            });
+        }
+        let Some(burst) = self.active.as_mut() else {
+            return;
+        };
+        burst.until = now + self.duration;
+        for anomaly_type in anomalies.iter().filter_map(|a| a["type"].as_str()) {
+            if !burst.triggers.iter().any(|t| t == anomaly_type) {
+                burst.triggers.push(anomaly_type.to_string());
+            }
+        }
+        if starting {
+            info!(
+                "⚡ Burst capture every {:?} for {:?} after {}",
+                self.interval,
+                self.duration,
+                burst.triggers.join(", ")
+            );
+        }
+    }
+
+    /// Interval until the next snapshot: the burst interval during a burst, then the time since it
+    /// ended (so the interval roughly doubles each run) until it is back at `normal`
+    pub fn interval(&mut self, normal: Duration) -> Duration {
+        let Some(burst) = &self.active else {
+            return normal;
+        };
+        let since_end = Instant::now().saturating_duration_since(burst.until);
+        if since_end >= normal {
+            self.active = None;
+            return normal;
+        }
+        since_end.clamp(self.interval.min(normal), normal)
+    }
+
+    /// Annotation for a snapshot taken now; None outside a burst, including while easing back
+    pub fn sample(&self) -> Option<BurstSample> {
+        let burst = self.active.as_ref().filter(|burst| burst.until > Instant::now())?;
+        Some(BurstSample {
+            triggers: burst.triggers.clone(),
+            started_at: burst.started_at,
+            interval_ms: self.interval.as_millis() as u64,
+        })
+    }
+}
@@ -0,0 +1,213 @@
+//! Guided calibration: a stretch of normal typing, mouse use and speech whose snapshots become the
+//! personal baseline in `<device>/baseline.json`, graded by how much activity it actually saw.
+
+use chrono::{DateTime, Duration, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::models::{Baseline, CalibrationQuality, CalibrationStatus, EyeCoreData, MetricDistribution};
+use crate::storage::DataStorage;
+
+pub const DEFAULT_CALIBRATION_MINUTES: i64 = 10;
+pub const MAX_CALIBRATION_MINUTES: i64 = 60;
+/// Snapshot cadence the coverage is measured against
+const SNAPSHOT_INTERVAL_SECS: i64 = 5;
+
+/// Snapshots with activity each group needs for a full quality score; voice only counts when enabled
+const REQUIRED_SAMPLES: [(&str, usize); 3] = [("typing", 30), ("mouse", 30), ("voice", 10)];
+
+/// Start and end of the running session, persisted so a restart does not lose it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct CalibrationWindow {
+    started_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+}
+
+pub struct Calibration {
+    session_path: PathBuf,
+    baseline_path: PathBuf,
+    session: Option<CalibrationWindow>,
+    baseline: Option<Baseline>,
+}
+
+impl Calibration {
+    /// Running session and baseline kept in `device_dir`
+    pub fn load(device_dir: &Path) -> Self {
+        let session_path = device_dir.join("calibration.json");
+        let baseline_path = device_dir.join("baseline.json");
+        Calibration {
+            session: read_json(&session_path),
+            baseline: read_json(&baseline_path),
+            session_path,
+            baseline_path,
+        }
+    }
+
+    /// Begin a session of `minutes` minutes; the current baseline stays in use until it ends
+    pub fn start(&mut self, minutes: i64) -> Result<CalibrationStatus, String> {
+        if let Some(session) = self.session {
+            return Err(format!("A calibration session is already running until {}", session.ends_at.to_rfc3339()));
+        }
+        let started_at = Utc::now();
+        let session = CalibrationWindow { started_at, ends_at: started_at + Duration::minutes(minutes) };
+        let content = serde_json::to_string_pretty(&session).map_err(|e| e.to_string())?;
+        std::fs::write(&self.session_path, content)
+            .map_err(|e| format!("Failed to save calibration session {:?}: {}", self.session_path, e))?;
+        self.session = Some(session);
+        info!("📏 Calibration started, {} minutes of normal use", minutes);
+        Ok(self.status())
+    }
+
+    pub fn status(&self) -> CalibrationStatus {
+        CalibrationStatus {
+            active: self.session.is_some(),
+            started_at: self.session.map(|session| session.started_at),
+            ends_at: self.session.map(|session| session.ends_at),
+            baseline: self.baseline.clone(),
+        }
+    }
+
+    /// Record the baseline once the running session is over
+    pub async fn poll(&mut self, storage: &DataStorage) {
+        let Some(session) = self.session.filter(|session| Utc::now() >= session.ends_at) else {
+            return;
+        };
+
+        let mut snapshots = Vec::new();
+        let mut date = session.started_at.date_naive();
+        while date <= session.ends_at.date_naive() {
+            match storage.load_snapshots(date).await {
+                Ok(day) => snapshots.extend(day),
+                Err(e) => warn!("⚠️ Calibration could not read snapshots of {}: {}", date, e),
+            }
+            let Some(next) = date.succ_opt() else { break };
+            date = next;
+        }
+        snapshots.retain(|data| data.timestamp >= session.started_at && data.timestamp <= session.ends_at);
+
+        let baseline = build_baseline(session.started_at, session.ends_at, &snapshots);
+        info!("📏 Baseline recorded, quality {} ({:.2})", baseline.quality.grade, baseline.quality.score);
+        for issue in &baseline.quality.issues {
+            warn!("⚠️ Calibration: {}", issue);
+        }
+        match serde_json::to_string_pretty(&baseline) {
+            Ok(content) => {
+                if let Err(e) = std::fs::write(&self.baseline_path, content) {
+                    log::error!("Failed to save baseline {:?}: {}", self.baseline_path, e);
+                }
+            }
+            Err(e) => log::error!("Failed to serialize baseline: {}", e),
+        }
+        let _ = std::fs::remove_file(&self.session_path);
+        self.session = None;
+        self.baseline = Some(baseline);
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content)
+        .map_err(|e| warn!("⚠️ Ignoring unreadable {:?}: {}", path, e))
+        .ok()
+}
+
+/// Metric values of one snapshot by group; a group is left out when the snapshot saw no such activity
+fn activity_samples(data: &EyeCoreData) -> Vec<(&'static str, Vec<(&'static str, f32)>)> {
+    let mut groups = Vec::new();
+    if let Some(keystroke) = data.keystroke_dynamics.as_ref().filter(|k| k.enabled && k.total_keystrokes > 0) {
+        groups.push(("typing", vec![
+            ("keystroke_dynamics.typing_speed_wpm", keystroke.typing_speed_wpm),
+            ("keystroke_dynamics.avg_key_hold_time_ms", keystroke.avg_key_hold_time_ms),
+            ("keystroke_dynamics.avg_key_interval_ms", keystroke.avg_key_interval_ms),
+            ("keystroke_dynamics.key_press_variance", keystroke.key_press_variance),
+            ("keystroke_dynamics.error_correction_rate", keystroke.error_correction_rate),
+            ("keystroke_dynamics.stress_indicator", keystroke.stress_indicator),
+            ("keystroke_dynamics.fatigue_indicator", keystroke.fatigue_indicator),
+        ]));
+    }
+    if let Some(mouse) = data.mouse_dynamics.as_ref().filter(|m| m.total_distance_px > 0) {
+        groups.push(("mouse", vec![
+            ("mouse_dynamics.movement_speed_avg", mouse.movement_speed_avg),
+            ("mouse_dynamics.path_smoothness", mouse.path_smoothness),
+            ("mouse_dynamics.acceleration_avg", mouse.acceleration_avg),
+            ("mouse_dynamics.hesitation_count", mouse.hesitation_count as f32),
+            ("mouse_dynamics.fatigue_indicator", mouse.fatigue_indicator),
+        ]));
+    }
+    if let Some(voice) = data.voice_data.as_ref().filter(|v| v.enabled && v.speaking_duration_ms > 0) {
+        groups.push(("voice", vec![
+            ("voice_data.volume_level", voice.volume_level),
+            ("voice_data.vocal_tone_score", voice.vocal_tone_score),
+            ("voice_data.sentiment_score", voice.sentiment_score),
+        ]));
+    }
+    groups
+}
+
+/// Distributions and quality of a session's snapshots
+pub fn build_baseline(started_at: DateTime<Utc>, ended_at: DateTime<Utc>, snapshots: &[EyeCoreData]) -> Baseline {
+    let mut values: BTreeMap<String, Vec<f32>> = BTreeMap::new();
+    let mut samples: BTreeMap<String, usize> = BTreeMap::new();
+    for data in snapshots {
+        for (group, metrics) in activity_samples(data) {
+            *samples.entry(group.to_string()).or_default() += 1;
+            for (metric, value) in metrics {
+                values.entry(metric.to_string()).or_default().push(value);
+            }
+        }
+    }
+    let voice_enabled = snapshots.iter().any(|data| data.voice_data.as_ref().is_some_and(|voice| voice.enabled));
+
+    let mut issues = Vec::new();
+    let mut fractions = Vec::new();
+    for (group, required) in REQUIRED_SAMPLES {
+        if group == "voice" && !voice_enabled {
+            continue;
+        }
+        let seen = samples.get(group).copied().unwrap_or(0);
+        if seen < required {
+            issues.push(format!("Only {} {} samples ({} needed for a reliable baseline)", seen, group, required));
+        }
+        fractions.push((seen as f32 / required as f32).min(1.0));
+    }
+    let expected = ((ended_at - started_at).num_seconds() / SNAPSHOT_INTERVAL_SECS).max(1) as f32;
+    let coverage = (snapshots.len() as f32 / expected).min(1.0);
+    if coverage < 0.8 {
+        issues.push(format!("Snapshots cover {:.0}% of the calibration period", coverage * 100.0));
+    }
+
+    let score = fractions.iter().sum::<f32>() / fractions.len().max(1) as f32 * coverage.min(0.8) / 0.8;
+    let grade = if score >= 0.8 { "good" } else if score >= 0.5 { "fair" } else { "poor" };
+    Baseline {
+        started_at,
+        ended_at,
+        metrics: values.into_iter().map(|(metric, values)| (metric, distribution(values))).collect(),
+        quality: CalibrationQuality {
+            grade: grade.to_string(),
+            score,
+            snapshots: snapshots.len(),
+            coverage,
+            samples,
+            issues,
+        },
+    }
+}
+
+fn distribution(mut values: Vec<f32>) -> MetricDistribution {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let n = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+    // Nearest-rank percentiles
+    let percentile = |p: f32| values[((p * n).ceil() as usize).clamp(1, values.len()) - 1];
+    MetricDistribution {
+        samples: values.len(),
+        mean,
+        std_dev: variance.sqrt(),
+        p10: percentile(0.1),
+        p50: percentile(0.5),
+        p90: percentile(0.9),
+    }
+}
@@ -0,0 +1,173 @@
+//! Capability negotiation on the upload connection. `Authenticate` carries the client's manifest:
+//! the snapshot schema versions, data categories and package encodings it can send. A server that
+//! answers with a manifest of its own (`capabilities` in the reply's data) gets what both support:
+//! packages in the highest schema version both know, only the categories it takes, and batches only
+//! if it can decode them. Servers without a manifest get plain `Package` messages with every
+//! category, as before negotiation existed.
+
+use serde_json::{json, Value};
+
+use crate::models::NegotiatedCapabilities;
+use crate::upload_batch;
+
+/// Snapshot schema versions the client can send, oldest first
+pub const SCHEMA_VERSIONS: [u32; 1] = [1];
+/// Package encoding every server decodes: one snapshot per JSON message
+pub const PLAIN_ENCODING: &str = "json";
+/// Snapshot sections a server may decline; keep in step with `EyeCoreData`
+pub const DATA_CATEGORIES: [&str; 25] = [
+    "system_metrics",
+    "process_data",
+    "top_processes",
+    "input_metrics",
+    "network_metrics",
+    "focus_metrics",
+    "voice_data",
+    "camera_data",
+    "keystroke_dynamics",
+    "screen_interactions",
+    "file_metadata",
+    "system_events",
+    "mouse_dynamics",
+    "network_activity_metadata",
+    "physiological_data",
+    "meeting",
+    "burst",
+    "focus_context",
+    "presence",
+    "media",
+    "power",
+    "provenance",
+    "redactions",
+    "comparisons",
+    "collectors",
+];
+/// Fields that identify a snapshot; always sent
+const IDENTIFYING_FIELDS: [&str; 3] = ["session_id", "timestamp", "sequence"];
+
+/// The client's manifest, sent with `Authenticate`
+pub fn manifest() -> Value {
+    json!({
+        "client_version": env!("CARGO_PKG_VERSION"),
+        "schema_versions": SCHEMA_VERSIONS,
+        "data_categories": DATA_CATEGORIES,
+        "encodings": [PLAIN_ENCODING, upload_batch::ENCODING],
+    })
+}
+
+/// What to send a server with the manifest `server`; None for a server without one. Lists the
+/// server leaves out put no limit on what it takes.
+pub fn negotiate(server: Option<&Value>) -> Result<Option<NegotiatedCapabilities>, String> {
+    let Some(server) = server.filter(|server| server.is_object()) else {
+        return Ok(None);
+    };
+    let strings = |key: &str| -> Option<Vec<String>> {
+        let list = server.get(key)?.as_array()?;
+        Some(list.iter().filter_map(|value| value.as_str().map(str::to_string)).collect())
+    };
+
+    let schema_version = match server.get("schema_versions").and_then(|versions| versions.as_array()) {
+        Some(versions) => {
+            let versions: Vec<u64> = versions.iter().filter_map(|version| version.as_u64()).collect();
+            SCHEMA_VERSIONS
+                .iter()
+                .rev()
+                .find(|version| versions.contains(&(**version as u64)))
+                .copied()
+                .ok_or_else(|| format!("No common snapshot schema version: the client sends {:?}, the server takes {:?}", SCHEMA_VERSIONS, versions))?
+        }
+        None => SCHEMA_VERSIONS[SCHEMA_VERSIONS.len() - 1],
+    };
+    let data_categories = strings("data_categories").map(|categories| {
+        DATA_CATEGORIES
+            .iter()
+            .filter(|category| categories.iter().any(|taken| taken == *category))
+            .map(|category| category.to_string())
+            .collect()
+    });
+    let server_encodings = strings("encodings").unwrap_or_default();
+    let encodings = [PLAIN_ENCODING, upload_batch::ENCODING]
+        .iter()
+        .filter(|encoding| **encoding == PLAIN_ENCODING || server_encodings.iter().any(|taken| taken == *encoding))
+        .map(|encoding| encoding.to_string())
+        .collect();
+
+    Ok(Some(NegotiatedCapabilities {
+        server_version: server.get("server_version").and_then(|version| version.as_str()).map(str::to_string),
+        schema_version,
+        data_categories,
+        encodings,
+    }))
+}
+
+/// Remove the sections of a snapshot the server does not take
+pub fn restrict(snapshot: &mut Value, negotiated: &NegotiatedCapabilities) {
+    let (Some(categories), Some(object)) = (&negotiated.data_categories, snapshot.as_object_mut()) else {
+        return;
+    };
+    object.retain(|key, _| IDENTIFYING_FIELDS.contains(&key.as_str()) || categories.iter().any(|category| category == key));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EyeCoreData;
+
+    #[test]
+    fn servers_without_a_manifest_get_plain_packages() {
+        assert!(negotiate(None).unwrap().is_none());
+        assert!(negotiate(Some(&json!("v2"))).unwrap().is_none());
+    }
+
+    #[test]
+    fn negotiation_takes_the_intersection() {
+        let server = json!({
+            "server_version": "2.1.0",
+            "schema_versions": [1, 2],
+            "data_categories": ["system_metrics", "focus_metrics", "gaze_data"],
+            "encodings": ["json", "json-merge-patch+zstd+base64", "cbor"],
+        });
+        let negotiated = negotiate(Some(&server)).unwrap().unwrap();
+        assert_eq!(negotiated.server_version.as_deref(), Some("2.1.0"));
+        assert_eq!(negotiated.schema_version, 1);
+        assert_eq!(negotiated.data_categories.unwrap(), ["system_metrics", "focus_metrics"]);
+        assert_eq!(negotiated.encodings, ["json", "json-merge-patch+zstd+base64"]);
+
+        // Nothing listed: every category, plain packages only
+        let negotiated = negotiate(Some(&json!({}))).unwrap().unwrap();
+        assert!(negotiated.data_categories.is_none());
+        assert_eq!(negotiated.encodings, ["json"]);
+    }
+
+    #[test]
+    fn no_common_schema_version_is_an_error() {
+        assert!(negotiate(Some(&json!({ "schema_versions": [2, 3] }))).is_err());
+    }
+
+    #[test]
+    fn declined_categories_are_not_sent() {
+        let negotiated = negotiate(Some(&json!({ "data_categories": ["focus_metrics"] }))).unwrap().unwrap();
+        let mut snapshot = json!({"session_id": "s", "timestamp": "t", "sequence": 1, "focus_metrics": {}, "camera_data": {}});
+        restrict(&mut snapshot, &negotiated);
+        assert_eq!(snapshot, json!({"session_id": "s", "timestamp": "t", "sequence": 1, "focus_metrics": {}}));
+    }
+
+    #[test]
+    fn every_snapshot_section_is_a_category() {
+        let now = chrono::Utc::now();
+        let snapshot: EyeCoreData = serde_json::from_value(json!({
+            "session_id": "test",
+            "timestamp": now,
+            "system_metrics": { "timestamp": now, "cpu_usage": 0.0, "memory_usage": 0.0, "disk_usage": 0.0 },
+            "process_data": { "timestamp": now, "active_process": "", "active_window_title": "", "process_count": 0 },
+            "input_metrics": { "timestamp": now, "mouse_clicks": 0, "keyboard_events": 0, "idle_duration_seconds": 0 },
+            "network_metrics": { "timestamp": now, "bytes_sent": 0, "bytes_received": 0, "active_connections": 0 },
+            "focus_metrics": { "timestamp": now, "focus_level": 0.0, "context_switches": 0, "productive_app_time": 0 },
+        }))
+        .unwrap();
+        let value = serde_json::to_value(&snapshot).unwrap();
+        for field in value.as_object().unwrap().keys() {
+            assert!(IDENTIFYING_FIELDS.contains(&field.as_str()) || DATA_CATEGORIES.contains(&field.as_str()), "{}", field);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+use crate::models::CaptureEvent;
+
+/// Records exactly when capture streams are open, so privacy indicators can be audited
+#[derive(Default)]
+pub struct CaptureTracker {
+    pending: Mutex<Vec<CaptureEvent>>,
+}
+
+impl CaptureTracker {
+    pub fn new() -> Self {
+        CaptureTracker::default()
+    }
+
+    /// Mark a stream as open; the event is recorded when the guard is dropped
+    pub fn open(self: &Arc<Self>, device: &str, purpose: &str) -> CaptureGuard {
+        CaptureGuard {
+            tracker: Arc::clone(self),
+            device: device.to_string(),
+            purpose: purpose.to_string(),
+            started_at: Utc::now(),
+        }
+    }
+
+    /// Events completed since the last call
+    pub fn drain(&self) -> Vec<CaptureEvent> {
+        self.pending.lock().map(|mut pending| std::mem::take(&mut *pending)).unwrap_or_default()
+    }
+}
+
+/// Open capture stream; closing it (drop) logs the stop event with its duration
+pub struct CaptureGuard {
+    tracker: Arc<CaptureTracker>,
+    device: String,
+    purpose: String,
+    started_at: DateTime<Utc>,
+}
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        let ended_at = Utc::now();
+        let event = CaptureEvent {
+            device: std::mem::take(&mut self.device),
+            purpose: std::mem::take(&mut self.purpose),
+            started_at: self.started_at,
+            ended_at,
+            duration_ms: (ended_at - self.started_at).num_milliseconds().max(0) as u64,
+        };
+        if let Ok(mut pending) = self.tracker.pending.lock() {
+            pending.push(event);
+        }
+    }
+}
+
+/// Capture time for one device on one day
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeviceActivity {
+    pub sessions: usize,
+    pub total_seconds: f64,
+    pub first_opened: Option<DateTime<Utc>>,
+    pub last_closed: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyCaptureActivity {
+    pub date: String,
+    pub microphone: DeviceActivity,
+    pub camera: DeviceActivity,
+}
+
+/// Summarize a day's capture events per device
+pub fn summarize_day(date: NaiveDate, events: &[CaptureEvent]) -> DailyCaptureActivity {
+    let summarize = |device: &str| {
+        events
+            .iter()
+            .filter(|e| e.device == device)
+            .fold(DeviceActivity::default(), |mut activity, event| {
+                activity.sessions += 1;
+                activity.total_seconds += event.duration_ms as f64 / 1000.0;
+                activity.first_opened = Some(activity.first_opened.map_or(event.started_at, |t| t.min(event.started_at)));
+                activity.last_closed = Some(activity.last_closed.map_or(event.ended_at, |t| t.max(event.ended_at)));
+                activity
+            })
+    };
+
+    DailyCaptureActivity {
+        date: date.format("%Y-%m-%d").to_string(),
+        microphone: summarize("microphone"),
+        camera: summarize("camera"),
+    }
+}
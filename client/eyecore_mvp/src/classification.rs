@@ -0,0 +1,212 @@
+//! Productivity classes of apps and sites: productive, neutral or distracting. A bundled list covers
+//! common apps (by executable or app name) and sites (by domain, for the foreground browser tab).
+//! The category of a matching `app_rules` rule comes before it, and the user's overrides from
+//! `PUT /classification/apps` take precedence over both. Focus metrics and the daily report both
+//! classify through here.
+
+use log::{info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::app_rules::{self, executable_name, AppRules};
+use crate::attribution::site_from_title;
+use crate::models::{AppClassifications, AppTime, EyeCoreData, Productivity, ProductivitySummary};
+
+/// Overrides file in the device directory
+const OVERRIDES_FILE: &str = "app_classification.json";
+/// Snapshots further apart than this do not add foreground time
+const MAX_SAMPLE_GAP_SECS: i64 = 60;
+const MAX_KEY_LENGTH: usize = 200;
+
+/// Apps (lowercase, without ".exe") by class; anything not listed is neutral
+const DEFAULT_APPS: &[(Productivity, &[&str])] = &[
+    (Productivity::Productive, &[
+        "code", "visual studio code", "devenv", "idea", "idea64", "pycharm", "pycharm64", "rider64", "clion64",
+        "webstorm64", "xcode", "sublime_text", "notepad++", "vim", "nvim", "emacs", "windowsterminal", "cmd",
+        "powershell", "pwsh", "gnome-terminal-server", "konsole", "alacritty", "kitty", "iterm2", "terminal",
+        "winword", "excel", "powerpnt", "onenote", "microsoft word", "microsoft excel", "microsoft powerpoint",
+        "soffice.bin", "pages", "numbers", "keynote", "acrobat", "acrord32", "notion", "obsidian", "figma",
+    ]),
+    (Productivity::Neutral, &[
+        "chrome", "google chrome", "chromium", "msedge", "microsoft edge", "firefox", "brave", "opera", "vivaldi",
+        "safari", "slack", "teams", "ms-teams", "zoom", "outlook", "microsoft outlook", "thunderbird", "mail",
+        "explorer", "finder", "nautilus", "taskmgr", "systemsettings", "system settings", "spotify", "music",
+    ]),
+    (Productivity::Distracting, &[
+        "discord", "telegram", "whatsapp", "steam", "epicgameslauncher", "minecraft", "vlc", "netflix", "tiktok",
+    ]),
+];
+
+/// Sites by class, matched against the foreground browser tab including subdomains
+const DEFAULT_SITES: &[(Productivity, &[&str])] = &[
+    (Productivity::Productive, &[
+        "github.com", "gitlab.com", "bitbucket.org", "stackoverflow.com", "docs.rs", "crates.io",
+        "developer.mozilla.org", "docs.google.com", "atlassian.net", "notion.so", "figma.com",
+    ]),
+    (Productivity::Neutral, &["mail.google.com", "calendar.google.com", "outlook.office.com"]),
+    (Productivity::Distracting, &[
+        "youtube.com", "netflix.com", "twitch.tv", "reddit.com", "facebook.com", "instagram.com", "twitter.com",
+        "x.com", "tiktok.com",
+    ]),
+];
+
+/// Apps whose window titles name the open site
+const BROWSERS: &[&str] = &[
+    "chrome", "google chrome", "chromium", "msedge", "microsoft edge", "firefox", "brave", "opera", "vivaldi", "safari",
+];
+
+/// What a foreground window was classified as: its app, and the site or app the class came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Classified {
+    pub app: String,
+    pub subject: String,
+    pub productivity: Productivity,
+}
+
+pub struct AppClassification {
+    rules: AppRules,
+    overrides_path: Option<PathBuf>,
+    overrides: BTreeMap<String, Productivity>,
+}
+
+impl AppClassification {
+    /// The bundled list only
+    pub fn new() -> Self {
+        AppClassification { rules: AppRules::new(), overrides_path: None, overrides: BTreeMap::new() }
+    }
+
+    /// The bundled list with the app rules and overrides kept in `device_dir`
+    pub fn load(device_dir: &Path) -> Self {
+        let overrides_path = device_dir.join(OVERRIDES_FILE);
+        let overrides = match std::fs::read_to_string(&overrides_path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("⚠️ Ignoring unreadable app classification {:?}: {}", overrides_path, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        AppClassification {
+            rules: AppRules::load(&device_dir.join(app_rules::RULES_FILE)),
+            overrides_path: Some(overrides_path),
+            overrides,
+        }
+    }
+
+    /// Class of the foreground window: its site when a browser shows one with a rule, otherwise the app
+    pub fn classify(&self, process: &str, window_title: &str) -> Classified {
+        let executable = executable_name(process);
+        let app = self.rules.identify(process, window_title);
+        if BROWSERS.contains(&executable.as_str()) {
+            if let Some(site) = site_from_title(window_title).map(|site| normalize(&site)) {
+                if let Some(productivity) = self.site_rule(&site) {
+                    return Classified { app: app.name, subject: site, productivity };
+                }
+            }
+        }
+        let productivity = self
+            .overrides
+            .get(&normalize(&app.name))
+            .or_else(|| self.overrides.get(&executable))
+            .copied()
+            .or(app.category)
+            .or_else(|| default_rule(DEFAULT_APPS, |name| *name == executable))
+            .unwrap_or(Productivity::Neutral);
+        Classified { subject: app.name.clone(), app: app.name, productivity }
+    }
+
+    fn site_rule(&self, site: &str) -> Option<Productivity> {
+        self.overrides
+            .iter()
+            .find(|(key, _)| site_matches(site, key))
+            .map(|(_, productivity)| *productivity)
+            .or_else(|| default_rule(DEFAULT_SITES, |domain| site_matches(site, domain)))
+    }
+
+    /// The bundled list and the overrides, for `GET /classification/apps`
+    pub fn rules(&self) -> AppClassifications {
+        let defaults = DEFAULT_APPS
+            .iter()
+            .chain(DEFAULT_SITES)
+            .flat_map(|(productivity, names)| names.iter().map(|name| (name.to_string(), *productivity)))
+            .collect();
+        AppClassifications { defaults, overrides: self.overrides.clone() }
+    }
+
+    /// Replace the overrides; keys are app names (executable or app name) or site domains
+    pub fn set_overrides(&mut self, overrides: BTreeMap<String, Productivity>) -> Result<AppClassifications, String> {
+        let mut normalized = BTreeMap::new();
+        for (key, productivity) in overrides {
+            let name = normalize(&key);
+            if name.is_empty() || name.len() > MAX_KEY_LENGTH {
+                return Err(format!("App or site names must be 1 to {} characters, got {:?}", MAX_KEY_LENGTH, key));
+            }
+            normalized.insert(name, productivity);
+        }
+
+        if let Some(path) = &self.overrides_path {
+            let content = serde_json::to_string_pretty(&normalized).map_err(|e| e.to_string())?;
+            std::fs::write(path, content).map_err(|e| format!("Failed to save app classification {:?}: {}", path, e))?;
+        }
+        self.overrides = normalized;
+        info!("🏷️ App classification updated with {} overrides", self.overrides.len());
+        Ok(self.rules())
+    }
+}
+
+/// Foreground time of a day's snapshots by class and app, classified with the current rules so
+/// overrides apply to earlier days too. Each snapshot counts the time until the next one, unless
+/// they are more than a minute apart.
+pub fn productivity_breakdown(snapshots: &[EyeCoreData], classification: &AppClassification) -> ProductivitySummary {
+    let mut summary = ProductivitySummary::default();
+    let mut apps: HashMap<Classified, i64> = HashMap::new();
+    for (i, data) in snapshots.iter().enumerate() {
+        let process = &data.process_data;
+        if process.active_process == "unknown" {
+            continue;
+        }
+        let seconds = snapshots
+            .get(i + 1)
+            .map(|next| (next.timestamp - data.timestamp).num_seconds())
+            .filter(|gap| (0..=MAX_SAMPLE_GAP_SECS).contains(gap))
+            .unwrap_or(0);
+        *apps.entry(classification.classify(&process.active_process, &process.active_window_title)).or_default() += seconds;
+    }
+
+    for (classified, seconds) in apps {
+        match classified.productivity {
+            Productivity::Productive => summary.productive_minutes += seconds,
+            Productivity::Neutral => summary.neutral_minutes += seconds,
+            Productivity::Distracting => summary.distracting_minutes += seconds,
+        }
+        summary.apps.push(AppTime { app: classified.subject, productivity: classified.productivity, minutes: seconds / 60 });
+    }
+    summary.productive_minutes /= 60;
+    summary.neutral_minutes /= 60;
+    summary.distracting_minutes /= 60;
+    summary.apps.retain(|app| app.minutes > 0);
+    summary.apps.sort_by(|a, b| b.minutes.cmp(&a.minutes).then_with(|| a.app.cmp(&b.app)));
+    summary
+}
+
+fn default_rule(rules: &[(Productivity, &[&str])], matches: impl Fn(&&str) -> bool) -> Option<Productivity> {
+    rules
+        .iter()
+        .find(|(_, names)| names.iter().any(&matches))
+        .map(|(productivity, _)| *productivity)
+}
+
+/// Lowercase, without ".exe" or a leading "www."
+fn normalize(name: &str) -> String {
+    let name = executable_name(name);
+    name.strip_prefix("www.").map(str::to_string).unwrap_or(name)
+}
+
+/// A site matches a domain rule on the domain or a subdomain of it. Titles without a domain give
+/// the site's name ("YouTube"), which matches the rule's first label ("youtube.com").
+fn site_matches(site: &str, rule: &str) -> bool {
+    if site.contains('.') {
+        site == rule || site.strip_suffix(rule).is_some_and(|prefix| prefix.ends_with('.'))
+    } else {
+        rule.contains('.') && rule.split('.').next() == Some(&site.replace(' ', ""))
+    }
+}
@@ -0,0 +1,561 @@
+use log::info;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::consent::ConsentManager;
+use crate::data_collector::{DataCollector, WARM_UP_INTERVAL};
+use crate::meeting::MeetingDetector;
+
+/// `eyecore_mvp collect --once [--output out.json]`: one full collection, printed or written, then exit
+pub async fn run_collect(args: &[String]) -> i32 {
+    let mut once = false;
+    let mut output: Option<PathBuf> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--once" => once = true,
+            "--output" | "-o" => match args.next() {
+                Some(path) => output = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("--output needs a file path");
+                    return 2;
+                }
+            },
+            other => {
+                eprintln!("Unknown collect option: {}", other);
+                return 2;
+            }
+        }
+    }
+    if !once {
+        eprintln!("Usage: eyecore_mvp collect --once [--output <file>]");
+        return 2;
+    }
+
+    // The same modules as the service: those with recorded consent, less any the config disables
+    let config = match crate::config::load() {
+        Ok(config) => config.config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let mut collector = DataCollector::new();
+    ConsentManager::load_configured(&config).apply(&mut collector);
+    config.modules.apply(&mut collector);
+    let calendar_path = std::env::var("EYECORE_CALENDAR_ICS").ok().map(PathBuf::from);
+    collector.attach_meeting_detector(Arc::new(MeetingDetector::new(calendar_path)));
+
+    // The first pass only primes delta-based collectors; the second is the reported snapshot
+    collector.collect_all().await;
+    tokio::time::sleep(WARM_UP_INTERVAL).await;
+    collector.collect_all().await;
+
+    let Some(data) = collector.get_latest_data() else {
+        eprintln!("Collector produced no data");
+        return 1;
+    };
+    let json = match serde_json::to_string_pretty(&data) {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("Failed to serialize snapshot: {}", e);
+            return 1;
+        }
+    };
+
+    match output {
+        Some(path) => match std::fs::write(&path, json) {
+            Ok(()) => {
+                info!("✓ Snapshot written to {:?}", path);
+                0
+            }
+            Err(e) => {
+                eprintln!("Failed to write {:?}: {}", path, e);
+                1
+            }
+        },
+        None => {
+            println!("{}", json);
+            0
+        }
+    }
+}
+
+const TIMESLOT_SCHEMA: &str = include_str!("../schemas/Timeslot.schema.json");
+const ENHANCED_SCREEN_KEYSTROKE_SCHEMA: &str = include_str!("../schemas/EnhancedScreenKeystroke.schema.json");
+
+/// `eyecore_mvp validate <file|dir>...`: check stored files, and each line of snapshot logs and record
+/// of archives, against the bundled JSON schemas. Exits non-zero if any file is unreadable or has violations.
+pub fn run_validate(args: &[String]) -> i32 {
+    if args.is_empty() {
+        eprintln!("Usage: eyecore_mvp validate <file|dir>...");
+        return 2;
+    }
+
+    let compile = |source: &str| {
+        let schema: serde_json::Value = serde_json::from_str(source).expect("bundled schema is valid JSON");
+        jsonschema::validator_for(&schema).expect("bundled schema compiles")
+    };
+    let timeslot = compile(TIMESLOT_SCHEMA);
+    let enhanced = compile(ENHANCED_SCREEN_KEYSTROKE_SCHEMA);
+
+    let mut files = Vec::new();
+    for arg in args {
+        collect_json_files(&PathBuf::from(arg), &mut files);
+    }
+
+    let (mut valid, mut invalid, mut skipped) = (0, 0, 0);
+    let documents = files.iter().flat_map(|path| read_documents(path));
+    for (source, document) in documents {
+        let document = match document {
+            Ok(document) => document,
+            Err(e) => {
+                println!("✗ {}: {}", source, e);
+                invalid += 1;
+                continue;
+            }
+        };
+
+        // Pick the schema from the document's shape, so files can be checked wherever they live
+        let validator = if document.get("schema_version").is_some() {
+            &enhanced
+        } else if document.get("metadata").is_some() && document.get("data").is_some() {
+            &timeslot
+        } else {
+            skipped += 1;
+            continue;
+        };
+
+        let violations: Vec<String> = validator
+            .iter_errors(&document)
+            .map(|error| format!("{}: {}", display_pointer(&error.instance_path.to_string()), error))
+            .collect();
+        if violations.is_empty() {
+            valid += 1;
+        } else {
+            println!("✗ {}", source);
+            for violation in &violations {
+                println!("    {}", violation);
+            }
+            invalid += 1;
+        }
+    }
+
+    println!("Checked {} files: {} valid, {} invalid, {} skipped (no schema)", files.len(), valid, invalid, skipped);
+    if invalid > 0 { 1 } else { 0 }
+}
+
+/// The JSON documents of a file, labeled for output: the file itself, each line of a snapshot log,
+/// or each record of an archive
+fn read_documents(path: &std::path::Path) -> Vec<(String, Result<serde_json::Value, String>)> {
+    let parse = |content: &[u8]| serde_json::from_slice::<serde_json::Value>(content).map_err(|e| e.to_string());
+    if crate::bulk_read::is_snapshot_archive(path) {
+        let archive = match crate::bulk_read::SnapshotArchive::open(path) {
+            Ok(archive) => archive,
+            Err(e) => return vec![(path.display().to_string(), Err(e))],
+        };
+        return archive
+            .entries()
+            .iter()
+            .map(|entry| (format!("{}:{}", path.display(), entry.name), archive.record(entry).and_then(|record| parse(&record))))
+            .collect();
+    }
+    let content = match std::fs::read(path).and_then(|content| crate::bulk_read::decode(path, content)) {
+        Ok(content) => content,
+        Err(e) => return vec![(path.display().to_string(), Err(e.to_string()))],
+    };
+    if !crate::bulk_read::is_snapshot_log(path) {
+        return vec![(path.display().to_string(), parse(&content))];
+    }
+    content
+        .split(|byte| *byte == b'\n')
+        .enumerate()
+        .filter(|(_, line)| !line.iter().all(u8::is_ascii_whitespace))
+        .map(|(index, line)| (format!("{}:{}", path.display(), index + 1), parse(line)))
+        .collect()
+}
+
+/// JSON files (plain or `.json.zst`), snapshot logs and archives under `path` (or `path` itself),
+/// sorted for stable output
+fn collect_json_files(path: &std::path::Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = match std::fs::read_dir(path) {
+            Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+            Err(e) => {
+                eprintln!("Cannot read {}: {}", path.display(), e);
+                return;
+            }
+        };
+        entries.sort();
+        for entry in entries {
+            collect_json_files(&entry, files);
+        }
+    } else if crate::bulk_read::is_json_file(path)
+        || crate::bulk_read::is_snapshot_log(path)
+        || crate::bulk_read::is_snapshot_archive(path)
+        || !path.exists()
+    {
+        files.push(path.to_path_buf());
+    }
+}
+
+/// "" (the document root) reads better as "/"
+fn display_pointer(pointer: &str) -> &str {
+    if pointer.is_empty() { "/" } else { pointer }
+}
+
+/// A full scan over timeslot files, returning how many parsed
+type ScanFn = dyn Fn(&[PathBuf]) -> usize;
+
+/// `eyecore_mvp bench-scan <data-dir> [--runs N] [--generate-gb X]`: time a full scan of every stored
+/// snapshot with the old read path (read_to_string, then Value) against the bulk read path, which
+/// maps past days' files.
+/// `--generate-gb` first fills `<data-dir>` with copies of a fresh snapshot until it holds X GB.
+pub async fn run_bench_scan(args: &[String]) -> i32 {
+    let mut data_dir: Option<PathBuf> = None;
+    let mut runs: usize = 3;
+    let mut generate_gb: Option<f64> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => match args.next().and_then(|v| v.parse().ok()).filter(|&n: &usize| n > 0) {
+                Some(n) => runs = n,
+                None => {
+                    eprintln!("--runs needs a positive number");
+                    return 2;
+                }
+            },
+            "--generate-gb" => match args.next().and_then(|v| v.parse().ok()).filter(|&gb: &f64| gb > 0.0) {
+                Some(gb) => generate_gb = Some(gb),
+                None => {
+                    eprintln!("--generate-gb needs a positive size");
+                    return 2;
+                }
+            },
+            other if data_dir.is_none() && !other.starts_with('-') => data_dir = Some(PathBuf::from(other)),
+            other => {
+                eprintln!("Unknown bench-scan option: {}", other);
+                return 2;
+            }
+        }
+    }
+    let Some(data_dir) = data_dir else {
+        eprintln!("Usage: eyecore_mvp bench-scan <data-dir> [--runs N] [--generate-gb X]");
+        return 2;
+    };
+
+    if let Some(gb) = generate_gb {
+        if let Err(e) = generate_bench_data(&data_dir, gb).await {
+            eprintln!("Failed to generate data: {}", e);
+            return 1;
+        }
+    }
+
+    let mut paths = Vec::new();
+    collect_json_files(&data_dir, &mut paths);
+    // Compressed snapshots, snapshot logs and archives have no read_to_string path to compare, so only
+    // plain files are timed
+    paths.retain(|path| path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()) == Some("timeslots"));
+    paths.retain(|path| crate::bulk_read::is_json_file(path) && !crate::bulk_read::is_compressed(path));
+    if paths.is_empty() {
+        eprintln!("No snapshots under {}", data_dir.display());
+        return 1;
+    }
+    let bytes: u64 = paths.iter().filter_map(|p| std::fs::metadata(p).ok()).map(|m| m.len()).sum();
+    let mapped = paths.iter().filter(|path| crate::bulk_read::is_mapped(path)).count();
+    println!("{} snapshots, {:.2} GB, {} memory-mapped by bulk_read (captured before today)", paths.len(), bytes as f64 / 1e9, mapped);
+
+    let legacy = |paths: &[PathBuf]| -> usize {
+        let mut count = 0;
+        for path in paths {
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).map_err(|e| e.to_string()))
+                .and_then(|mut file| {
+                    serde_json::from_value::<crate::models::EyeCoreData>(file["data"].take()).map_err(|e| e.to_string())
+                });
+            if parsed.is_ok() {
+                count += 1;
+            }
+        }
+        count
+    };
+    let bulk = |paths: &[PathBuf]| -> usize {
+        let mut count = 0;
+        crate::bulk_read::scan_snapshots(paths, |_| count += 1);
+        count
+    };
+
+    // Alternate the two paths so neither always runs against a colder page cache
+    let scans: [(&str, &ScanFn); 2] = [("read_to_string", &legacy), ("bulk_read", &bulk)];
+    let mut best = [std::time::Duration::MAX; 2];
+    for run in 1..=runs {
+        for (index, (name, scan)) in scans.iter().enumerate() {
+            let started = std::time::Instant::now();
+            let parsed = scan(&paths);
+            let elapsed = started.elapsed();
+            best[index] = best[index].min(elapsed);
+            println!(
+                "run {} {:>14}: {:>8.2?}  {:>8.1} MB/s  ({} parsed)",
+                run,
+                name,
+                elapsed,
+                bytes as f64 / 1e6 / elapsed.as_secs_f64(),
+                parsed
+            );
+        }
+    }
+    println!(
+        "best: read_to_string {:.2?}, bulk_read {:.2?}, speedup {:.2}x",
+        best[0],
+        best[1],
+        best[0].as_secs_f64() / best[1].as_secs_f64()
+    );
+    0
+}
+
+/// Write copies of one fresh snapshot, 5 s apart going back from the start of today (UTC), until `gb`
+/// is reached; like the history bulk scans cover, they are past days' files, which bulk reads map
+async fn generate_bench_data(data_dir: &std::path::Path, gb: f64) -> std::io::Result<()> {
+    let mut collector = DataCollector::new();
+    collector.collect_all().await;
+    tokio::time::sleep(WARM_UP_INTERVAL).await;
+    collector.collect_all().await;
+    let Some(mut data) = collector.get_latest_data() else {
+        return Err(std::io::Error::other("collector produced no data"));
+    };
+
+    data.timestamp = chrono::Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let storage = crate::storage::DataStorage::new(&data_dir.to_string_lossy(), "bench");
+    let target = (gb * 1e9) as u64;
+    let (mut written, mut files) = (0u64, 0usize);
+    while written < target {
+        data.timestamp -= chrono::Duration::seconds(5);
+        written += std::fs::metadata(storage.save_data_snapshot(&data).await?)?.len();
+        files += 1;
+    }
+    println!("Generated {} snapshots ({:.2} GB) under {}", files, written as f64 / 1e9, data_dir.display());
+    Ok(())
+}
+
+const SOAK_USAGE: &str = "Usage: eyecore_mvp bench-soak [--duration 4h] [--speedup 10-100] [--sample-every 60s] \
+[--data-dir <dir>] [--max-memory-growth-mb N] [--max-fd-growth N] [--report <file>]";
+
+/// `90s`, `30m`, `4h` or plain seconds
+fn parse_duration(value: &str) -> Option<std::time::Duration> {
+    let (number, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+    let number: u64 = number.parse().ok()?;
+    let secs = match unit {
+        "" | "s" => number,
+        "m" => number * 60,
+        "h" => number * 3600,
+        _ => return None,
+    };
+    (secs > 0).then(|| std::time::Duration::from_secs(secs))
+}
+
+/// `eyecore_mvp bench-soak`: run the pipeline for hours at 10-100x the real snapshot rate and fail
+/// (exit 1) when memory, file descriptors or collector buffers keep growing. Meant for builds with
+/// the `simulated-input` feature.
+pub async fn run_bench_soak(args: &[String]) -> i32 {
+    let mut options = crate::soak::SoakOptions {
+        duration: std::time::Duration::from_secs(4 * 3600),
+        speedup: 20,
+        sample_every: std::time::Duration::from_secs(60),
+        data_dir: std::env::temp_dir().join(format!("eyecore-soak-{}", std::process::id())),
+        keep_data: false,
+        max_memory_growth_mb: 64.0,
+        max_fd_growth: 8,
+        report: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        let valid = match (arg.as_str(), value) {
+            ("--duration", Some(v)) => parse_duration(v).map(|d| options.duration = d).is_some(),
+            ("--sample-every", Some(v)) => parse_duration(v).map(|d| options.sample_every = d).is_some(),
+            ("--speedup", Some(v)) => v
+                .parse()
+                .ok()
+                .filter(|n: &u32| (1..=100).contains(n))
+                .map(|n| options.speedup = n)
+                .is_some(),
+            ("--data-dir", Some(v)) => {
+                options.data_dir = PathBuf::from(v);
+                options.keep_data = true;
+                true
+            }
+            ("--max-memory-growth-mb", Some(v)) => v
+                .parse()
+                .ok()
+                .filter(|mb: &f64| *mb > 0.0)
+                .map(|mb| options.max_memory_growth_mb = mb)
+                .is_some(),
+            ("--max-fd-growth", Some(v)) => v.parse().map(|n| options.max_fd_growth = n).is_ok(),
+            ("--report", Some(v)) => {
+                options.report = Some(PathBuf::from(v));
+                true
+            }
+            _ => false,
+        };
+        if !valid {
+            eprintln!("Invalid bench-soak option: {} {}", arg, value.map(String::as_str).unwrap_or(""));
+            eprintln!("{}", SOAK_USAGE);
+            return 2;
+        }
+    }
+
+    match crate::soak::run(options).await {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("Soak aborted: {}", e);
+            1
+        }
+    }
+}
+
+const CONFIG_USAGE: &str = "Usage: eyecore_mvp config export [--output <file>] | config import <file> | config keygen";
+
+/// `eyecore_mvp config export|import|keygen`: signed configuration bundles for provisioning and migration
+pub async fn run_config(args: &[String]) -> i32 {
+    match args.first().map(String::as_str) {
+        Some("keygen") => {
+            let (signing_key, public_key) = crate::bundle::generate_keys();
+            println!("EYECORE_BUNDLE_SIGNING_KEY={}", signing_key);
+            println!("EYECORE_BUNDLE_PUBLIC_KEY={}", public_key);
+            0
+        }
+        Some("export") => {
+            let output = match &args[1..] {
+                [] => None,
+                [flag, path] if flag == "--output" || flag == "-o" => Some(PathBuf::from(path)),
+                _ => {
+                    eprintln!("{}", CONFIG_USAGE);
+                    return 2;
+                }
+            };
+            let config = match crate::config::load() {
+                Ok(config) => config.config,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return 1;
+                }
+            };
+            let bundle = match crate::bundle::export(&config).await {
+                Ok(bundle) => bundle,
+                Err(e) => {
+                    eprintln!("Export failed: {}", e);
+                    return 1;
+                }
+            };
+            match output {
+                Some(path) => match std::fs::write(&path, bundle) {
+                    Ok(()) => {
+                        info!("✓ Configuration bundle written to {:?}", path);
+                        0
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to write {:?}: {}", path, e);
+                        1
+                    }
+                },
+                None => {
+                    println!("{}", bundle);
+                    0
+                }
+            }
+        }
+        Some("import") => {
+            let [_, path] = args else {
+                eprintln!("{}", CONFIG_USAGE);
+                return 2;
+            };
+            let content = match std::fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(e) => {
+                    eprintln!("Failed to read {:?}: {}", path, e);
+                    return 1;
+                }
+            };
+            match crate::bundle::import(&content).await {
+                Ok(report) => {
+                    println!(
+                        "Imported the bundle exported from {} at {}:",
+                        report.source_device,
+                        report.created_at.to_rfc3339()
+                    );
+                    for path in report.written {
+                        println!("  {}", path.display());
+                    }
+                    println!("Restart EyeCore (and the flag detector) to apply it.");
+                    0
+                }
+                Err(e) => {
+                    eprintln!("Import failed: {}", e);
+                    1
+                }
+            }
+        }
+        _ => {
+            eprintln!("{}", CONFIG_USAGE);
+            2
+        }
+    }
+}
+
+const MAINTENANCE_USAGE: &str = "Usage: eyecore_mvp maintenance on [--reason <text>] | maintenance off | maintenance status";
+
+/// `eyecore_mvp maintenance on|off|status`: pause collection, also on a running instance
+pub async fn run_maintenance(args: &[String]) -> i32 {
+    let change = match args {
+        [command] if command == "status" => None,
+        [command] if command == "off" => Some((false, None)),
+        [command] if command == "on" => Some((true, None)),
+        [command, flag, reason] if command == "on" && flag == "--reason" => Some((true, Some(reason.clone()))),
+        _ => {
+            eprintln!("{}", MAINTENANCE_USAGE);
+            return 2;
+        }
+    };
+    let config = match crate::config::load() {
+        Ok(config) => config.config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let device_id = match crate::storage::load_or_create_device_id(&config.data_dir).await {
+        Ok(device_id) => device_id,
+        Err(e) => {
+            eprintln!("Failed to read device ID: {}", e);
+            return 1;
+        }
+    };
+
+    let device_dir = std::path::Path::new(&config.data_dir).join(device_id);
+    if let Err(e) = std::fs::create_dir_all(&device_dir) {
+        eprintln!("Failed to create {:?}: {}", device_dir, e);
+        return 1;
+    }
+    let mut maintenance = crate::maintenance::Maintenance::load(&device_dir);
+    let status = match change {
+        Some((active, reason)) => match maintenance.set(active, reason, "cli") {
+            Ok(status) => status,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        },
+        None => maintenance.status(),
+    };
+    match (status.active, status.since) {
+        (true, Some(since)) => println!(
+            "Maintenance mode on since {}{}",
+            since.to_rfc3339(),
+            status.reason.map(|reason| format!(": {}", reason)).unwrap_or_default()
+        ),
+        _ => println!("Maintenance mode off, collection running"),
+    }
+    0
+}
@@ -0,0 +1,215 @@
+//! Pluggable collectors for readings that need no typed snapshot field (GPU, clipboard, browser
+//! and the like). Each one implements `Collector` and is registered with the collector's
+//! `CollectorRegistry`, which runs it on its own cadence and puts its JSON under
+//! `collectors.<name>` in the snapshot. Collectors are switched on and off and given intervals
+//! under `[collectors.<name>]` in eyecore.toml or through `/collectors`.
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::models::{CollectorStatus, CollectorUpdate};
+
+pub const MAX_INTERVAL_SECS: u64 = 86_400;
+
+/// One source of snapshot data. `collect` runs on the collection task, so it must return quickly;
+/// anything slow belongs on a thread of its own that `collect` only reads from.
+pub trait Collector: Send + Sync {
+    /// Key under `collectors` in the snapshot, and in `/collectors`
+    fn name(&self) -> &'static str;
+
+    /// How often to collect unless configured otherwise
+    fn interval(&self) -> Duration;
+
+    fn collect(&mut self) -> Result<serde_json::Value, String>;
+}
+
+/// `[collectors.<name>]` settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CollectorConfig {
+    pub enabled: bool,
+    pub interval_secs: Option<u64>,    // the collector's own interval if unset
+}
+
+impl Default for CollectorConfig {
+    fn default() -> Self {
+        CollectorConfig { enabled: true, interval_secs: None }
+    }
+}
+
+impl CollectorConfig {
+    pub fn validate(&self, name: &str) -> Result<(), String> {
+        match self.interval_secs {
+            Some(0) => Err(format!("collectors.{}.interval_secs must be at least 1", name)),
+            Some(secs) if secs > MAX_INTERVAL_SECS => {
+                Err(format!("collectors.{}.interval_secs must be at most {}", name, MAX_INTERVAL_SECS))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Runs something at most once per `interval`
+#[derive(Debug, Clone, Copy)]
+pub struct Cadence {
+    pub interval: Duration,
+    last: Option<Instant>,
+}
+
+impl Cadence {
+    pub fn new(interval: Duration) -> Self {
+        Cadence { interval, last: None }
+    }
+
+    /// Whether a new interval has started, starting it if so
+    pub fn due(&mut self) -> bool {
+        if self.last.is_some_and(|at| at.elapsed() < self.interval) {
+            return false;
+        }
+        self.last = Some(Instant::now());
+        true
+    }
+}
+
+struct Entry {
+    collector: Box<dyn Collector>,
+    enabled: bool,
+    cadence: Cadence,
+    last_collected: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct CollectorRegistry {
+    entries: Vec<Entry>,
+}
+
+impl CollectorRegistry {
+    /// The built-in collectors
+    pub fn new() -> Self {
+        let mut registry = CollectorRegistry::default();
+        #[cfg(unix)]
+        registry.register(Box::new(LoadAverage));
+        registry
+    }
+
+    /// Add `collector`, enabled on its own interval; replaces one registered under the same name
+    pub fn register(&mut self, collector: Box<dyn Collector>) {
+        self.entries.retain(|entry| entry.collector.name() != collector.name());
+        let cadence = Cadence::new(collector.interval());
+        self.entries.push(Entry {
+            collector,
+            enabled: true,
+            cadence,
+            last_collected: None,
+            last_error: None,
+        });
+    }
+
+    /// Apply `[collectors]`; names no registered collector has are reported back
+    pub fn configure(&mut self, config: &BTreeMap<String, CollectorConfig>) -> Vec<String> {
+        let mut unknown = Vec::new();
+        for (name, settings) in config {
+            match self.entries.iter_mut().find(|entry| entry.collector.name() == name) {
+                Some(entry) => {
+                    entry.enabled = settings.enabled;
+                    entry.set_interval(settings.interval_secs.map(Duration::from_secs));
+                }
+                None => unknown.push(name.clone()),
+            }
+        }
+        unknown
+    }
+
+    /// Apply a `POST /collectors/{name}` body; 0 restores the collector's own interval
+    pub fn update(&mut self, name: &str, update: CollectorUpdate) -> Result<CollectorStatus, String> {
+        if update.interval_secs.is_some_and(|secs| secs > MAX_INTERVAL_SECS) {
+            return Err(format!("interval_secs must be at most {}", MAX_INTERVAL_SECS));
+        }
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.collector.name() == name)
+            .ok_or_else(|| format!("Unknown collector {:?}", name))?;
+        if let Some(secs) = update.interval_secs {
+            entry.set_interval((secs > 0).then(|| Duration::from_secs(secs)));
+        }
+        if let Some(enabled) = update.enabled {
+            entry.enabled = enabled;
+        }
+        Ok(entry.status())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.collector.name() == name)
+    }
+
+    pub fn list(&self) -> Vec<CollectorStatus> {
+        self.entries.iter().map(Entry::status).collect()
+    }
+
+    /// Readings of the enabled collectors that are due, by name. A failing collector is left out
+    /// of the snapshot, and each new failure is logged once.
+    pub fn collect_due(&mut self) -> BTreeMap<String, serde_json::Value> {
+        let mut readings = BTreeMap::new();
+        for entry in self.entries.iter_mut().filter(|entry| entry.enabled) {
+            if !entry.cadence.due() {
+                continue;
+            }
+            match entry.collector.collect() {
+                Ok(value) => {
+                    entry.last_collected = Some(Utc::now());
+                    entry.last_error = None;
+                    readings.insert(entry.collector.name().to_string(), value);
+                }
+                Err(e) => {
+                    if entry.last_error.as_ref() != Some(&e) {
+                        warn!("⚠️ Collector {} failed: {}", entry.collector.name(), e);
+                    }
+                    entry.last_error = Some(e);
+                }
+            }
+        }
+        readings
+    }
+}
+
+impl Entry {
+    fn set_interval(&mut self, interval: Option<Duration>) {
+        self.cadence.interval = interval.unwrap_or_else(|| self.collector.interval());
+    }
+
+    fn status(&self) -> CollectorStatus {
+        CollectorStatus {
+            name: self.collector.name().to_string(),
+            enabled: self.enabled,
+            interval_secs: self.cadence.interval.as_secs(),
+            default_interval_secs: self.collector.interval().as_secs(),
+            last_collected: self.last_collected,
+            last_error: self.last_error.clone(),
+        }
+    }
+}
+
+/// Run-queue length averaged over 1, 5 and 15 minutes; Windows has no load average
+#[cfg(unix)]
+struct LoadAverage;
+
+#[cfg(unix)]
+impl Collector for LoadAverage {
+    fn name(&self) -> &'static str {
+        "load_average"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn collect(&mut self) -> Result<serde_json::Value, String> {
+        let load = sysinfo::System::load_average();
+        Ok(serde_json::json!({ "one": load.one, "five": load.five, "fifteen": load.fifteen }))
+    }
+}
@@ -0,0 +1,51 @@
+//! Comparison of measured collectors with the simulations they replace (`compare_collectors`).
+//! While it is on, each snapshot records, for every field that was measured and still has a
+//! simulation, both values in its `comparisons` map; the values the snapshot reports stay the
+//! measured ones. The drift report sums these up per field over stored snapshots, to check how far
+//! the simulated defaults are from real behaviour before switching a collector over.
+
+use std::collections::BTreeMap;
+
+use crate::models::{CollectorDrift, EyeCoreData};
+
+#[derive(Default)]
+struct Totals {
+    samples: usize,
+    measured: f64,
+    simulated: f64,
+    absolute_difference: f64,
+}
+
+/// Drift per compared field in `snapshots`, by field name, and the number of snapshots with
+/// comparisons
+pub fn drift(snapshots: &[EyeCoreData]) -> (Vec<CollectorDrift>, usize) {
+    let mut totals: BTreeMap<&str, Totals> = BTreeMap::new();
+    let mut compared = 0;
+    for snapshot in snapshots.iter().filter(|snapshot| !snapshot.comparisons.is_empty()) {
+        compared += 1;
+        for (field, comparison) in &snapshot.comparisons {
+            let field = totals.entry(field.as_str()).or_default();
+            field.samples += 1;
+            field.measured += comparison.measured;
+            field.simulated += comparison.simulated;
+            field.absolute_difference += (comparison.measured - comparison.simulated).abs();
+        }
+    }
+    let fields = totals
+        .into_iter()
+        .map(|(field, totals)| {
+            let samples = totals.samples as f64;
+            let measured_mean = totals.measured / samples;
+            let mean_absolute_difference = totals.absolute_difference / samples;
+            CollectorDrift {
+                field: field.to_string(),
+                samples: totals.samples,
+                measured_mean,
+                simulated_mean: totals.simulated / samples,
+                mean_absolute_difference,
+                relative_difference: (measured_mean != 0.0).then(|| mean_absolute_difference / measured_mean.abs()),
+            }
+        })
+        .collect();
+    (fields, compared)
+}
@@ -0,0 +1,306 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::config::RetentionConfig;
+use crate::privacy::UploadMode;
+
+/// Runtime settings that decide where data goes, captured at startup
+#[derive(Debug, Clone)]
+pub struct ProcessingContext {
+    pub device_id: String,
+    pub data_dir: String,
+    pub history_size: usize,
+    pub retention: RetentionConfig,
+    pub upload_server: String,
+    pub upload_mode: UploadMode,
+    pub voice_transcription: bool,
+    pub lan_discovery: bool,
+    pub team_mode: bool,
+    pub update_check: bool,
+    pub emotion_service: Option<String>,   // endpoint of the cloud emotion backend
+    pub time_export: Option<String>,       // time-tracking tool entries are submitted to
+    pub siem: Option<String>,              // SIEM in-process flag detection forwards flags to
+}
+
+/// One entry of the register of processing activities (GDPR Art. 30)
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessingActivity {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub active: bool,
+    pub data_categories: Vec<String>,
+    pub purposes: Vec<&'static str>,
+    pub retention: String,
+    pub recipients: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessingRegister {
+    pub generated_at: DateTime<Utc>,
+    pub device_id: String,
+    pub software_version: &'static str,
+    pub activities: Vec<ProcessingActivity>,
+}
+
+/// Build the register from the live configuration and the collector's enabled modules;
+/// `sends_camera_frames` is whether the cloud emotion backend is currently receiving frames
+pub fn build_register(context: &ProcessingContext, modules: &BTreeMap<&'static str, bool>, sends_camera_frames: bool) -> ProcessingRegister {
+    let collected: Vec<String> = modules
+        .iter()
+        .filter(|(_, enabled)| **enabled)
+        .map(|(module, _)| module.to_string())
+        .collect();
+
+    let mut activities = vec![
+        ProcessingActivity {
+            id: "activity_collection",
+            name: "Device activity collection",
+            active: true,
+            data_categories: collected.clone(),
+            purposes: vec!["learning analytics", "device fleet management", "focus and productivity insights"],
+            retention: format!("last {} snapshots held in memory", context.history_size),
+            recipients: vec!["local API (this device)".to_string()],
+        },
+        ProcessingActivity {
+            id: "local_storage",
+            name: "Local snapshot storage",
+            active: true,
+            data_categories: collected.clone(),
+            purposes: vec!["history queries", "coverage and daily reports", "flag detection"],
+            retention: describe_retention(&context.retention),
+            recipients: vec![format!("local disk ({}/{})", context.data_dir, context.device_id)],
+        },
+    ];
+
+    let (upload_categories, upload_purpose) = match context.upload_mode {
+        UploadMode::Raw => (collected.clone(), "central dashboard (raw snapshots)".to_string()),
+        UploadMode::Aggregate { epsilon } => (
+            vec!["aggregate statistics (differentially private)".to_string()],
+            format!("central dashboard (noised aggregates, epsilon {})", epsilon),
+        ),
+        UploadMode::Team { k } => (
+            vec!["aggregate statistics and apps/domains used (k-anonymous)".to_string()],
+            format!("central dashboard (team aggregates, k {})", k),
+        ),
+    };
+    activities.push(ProcessingActivity {
+        id: "server_upload",
+        name: "Upload to organisation server",
+        active: true,
+        data_categories: upload_categories,
+        purposes: vec!["organisational dashboards"],
+        retention: "determined by the receiving server".to_string(),
+        recipients: vec![format!("{} — {}", context.upload_server, upload_purpose)],
+    });
+
+    activities.push(ProcessingActivity {
+        id: "voice_transcription",
+        name: "Voice transcription and analysis",
+        active: context.voice_transcription && modules.get("voice_data").copied().unwrap_or(false),
+        data_categories: vec!["raw_audio".to_string(), "transcriptions".to_string(), "voice_data".to_string()],
+        purposes: vec!["speech transcription", "voice anomaly detection"],
+        retention: "audio and transcripts kept in local storage until deleted".to_string(),
+        recipients: vec!["ElevenLabs speech-to-text API".to_string()],
+    });
+
+    if let Some(endpoint) = &context.emotion_service {
+        activities.push(ProcessingActivity {
+            id: "emotion_cloud",
+            name: "Facial emotion analysis by a cloud service",
+            active: sends_camera_frames,
+            data_categories: vec!["camera frames (the user's face)".to_string(), "camera_data".to_string()],
+            purposes: vec!["facial emotion and gaze estimates"],
+            retention: "determined by the emotion service; frames are not stored on the device".to_string(),
+            recipients: vec![format!("emotion service ({})", endpoint)],
+        });
+    }
+
+    if let Some(tool) = &context.time_export {
+        activities.push(ProcessingActivity {
+            id: "time_export",
+            name: "Time entries submitted to a time-tracking tool",
+            active: true,
+            data_categories: vec![
+                "time entries (start, duration, description)".to_string(),
+                "mapped project, task or Jira issue".to_string(),
+            ],
+            purposes: vec!["time tracking and billing"],
+            retention: format!(
+                "determined by the time-tracking tool; the review queue on the device keeps {} days",
+                crate::time_export::KEEP_DAYS
+            ),
+            recipients: vec![format!("{}, for entries the user submits", tool)],
+        });
+    }
+
+    if let Some(siem) = &context.siem {
+        activities.push(ProcessingActivity {
+            id: "siem_export",
+            name: "Flag forwarding to a SIEM",
+            active: true,
+            data_categories: vec![
+                "flags (type, severity, title, description, metrics)".to_string(),
+                "session_id".to_string(),
+            ],
+            purposes: vec!["security monitoring"],
+            retention: "determined by the SIEM".to_string(),
+            recipients: vec![siem.clone()],
+        });
+    }
+
+    activities.push(ProcessingActivity {
+        id: "lan_discovery",
+        name: "LAN service advertisement",
+        active: context.lan_discovery,
+        data_categories: vec!["device_id".to_string(), "device_name".to_string(), "software version".to_string()],
+        purposes: vec!["finding EyeCore instances on the local network"],
+        retention: "not stored; advertised while running".to_string(),
+        recipients: vec!["devices on the local network (mDNS)".to_string()],
+    });
+
+    activities.push(ProcessingActivity {
+        id: "team_status",
+        name: "Team availability sharing",
+        active: context.team_mode,
+        data_categories: vec!["focus state".to_string(), "in-meeting".to_string(), "do-not-disturb".to_string()],
+        purposes: vec!["teammate availability"],
+        retention: "held in memory by teammates while running".to_string(),
+        recipients: vec!["teammates sharing the team secret".to_string()],
+    });
+
+    activities.push(ProcessingActivity {
+        id: "update_check",
+        name: "Software update check",
+        active: context.update_check,
+        data_categories: vec!["IP address (request metadata)".to_string()],
+        purposes: vec!["keeping the agent up to date"],
+        retention: "determined by the update server".to_string(),
+        recipients: vec!["configured update server".to_string()],
+    });
+
+    ProcessingRegister {
+        generated_at: Utc::now(),
+        device_id: context.device_id.clone(),
+        software_version: env!("CARGO_PKG_VERSION"),
+        activities,
+    }
+}
+
+/// `[retention]` in words, e.g. "days older than 30 removed; oldest days removed above 2048 MB in total"
+fn describe_retention(policy: &RetentionConfig) -> String {
+    let mut limits = Vec::new();
+    if let Some(days) = policy.max_age_days {
+        limits.push(format!("days older than {} removed", days));
+    }
+    for (category, mb) in &policy.quotas_mb {
+        limits.push(format!("oldest {} files removed above {} MB", category, mb));
+    }
+    if let Some(mb) = policy.max_total_mb {
+        limits.push(format!("oldest days removed above {} MB in total", mb));
+    }
+    if limits.is_empty() {
+        return "kept until deleted; no automatic expiry".to_string();
+    }
+    format!("{}; the content access log is kept until deleted", limits.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Source files that talk to the network, and the register activity covering what they send;
+    /// None for plumbing that only sends on behalf of the others
+    const SENDERS: &[(&str, Option<&str>)] = &[
+        ("src/websocket_client.rs", Some("server_upload")),
+        ("src/voice.rs", Some("voice_transcription")),
+        ("src/discovery.rs", Some("lan_discovery")),
+        ("src/team.rs", Some("team_status")),
+        ("src/version.rs", Some("update_check")),
+        ("src/updater.rs", Some("update_check")),
+        ("src/emotion.rs", Some("emotion_cloud")),
+        ("src/time_export.rs", Some("time_export")),
+        ("flag_detection/src/siem.rs", Some("siem_export")),
+        ("src/http.rs", None),
+        ("src/main.rs", None),
+        ("src/flag_pipeline.rs", None),
+        ("flag_detection/src/pipeline.rs", None),
+        ("flag_detection/src/main.rs", None),
+    ];
+    const NETWORK_APIS: &[&str] = &["HttpClient", "reqwest::", "connect_async", "UdpSocket", "TcpStream", "ServiceDaemon"];
+
+    fn sources(dir: &std::path::Path, found: &mut Vec<std::path::PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                sources(&path, found);
+            } else if path.extension().is_some_and(|extension| extension == "rs") {
+                found.push(path);
+            }
+        }
+    }
+
+    fn configured_everywhere() -> ProcessingContext {
+        ProcessingContext {
+            device_id: "device".to_string(),
+            data_dir: "./data".to_string(),
+            history_size: 1000,
+            retention: RetentionConfig::default(),
+            upload_server: "wss://server".to_string(),
+            upload_mode: UploadMode::Raw,
+            voice_transcription: true,
+            lan_discovery: true,
+            team_mode: true,
+            update_check: true,
+            emotion_service: Some("https://emotions".to_string()),
+            time_export: Some("Toggl Track".to_string()),
+            siem: Some("SIEM at syslog siem:514 over udp".to_string()),
+        }
+    }
+
+    #[test]
+    fn every_off_device_sender_has_a_register_activity() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut files = Vec::new();
+        sources(&root.join("src"), &mut files);
+        sources(&root.join("flag_detection/src"), &mut files);
+        for file in files {
+            let content = std::fs::read_to_string(&file).unwrap();
+            if !NETWORK_APIS.iter().any(|api| content.contains(api)) {
+                continue;
+            }
+            let relative = file.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+            // This file names the APIs itself
+            if relative == "src/compliance.rs" {
+                continue;
+            }
+            assert!(
+                SENDERS.iter().any(|(sender, _)| *sender == relative),
+                "{} uses the network; add the processing activity covering it to build_register and SENDERS",
+                relative
+            );
+        }
+
+        let register = build_register(&configured_everywhere(), &BTreeMap::from([("voice_data", true)]), true);
+        for (sender, activity) in SENDERS {
+            let Some(activity) = activity else { continue };
+            let entry = register.activities.iter().find(|entry| entry.id == *activity);
+            assert!(entry.is_some_and(|entry| entry.active), "no active {} activity for {}", activity, sender);
+        }
+    }
+
+    #[test]
+    fn retention_is_described_from_the_policy() {
+        assert_eq!(describe_retention(&RetentionConfig::default()), "kept until deleted; no automatic expiry");
+        let policy = RetentionConfig {
+            max_age_days: Some(30),
+            max_total_mb: Some(2048),
+            quotas_mb: BTreeMap::from([("raw_audio".to_string(), 512)]),
+        };
+        assert_eq!(
+            describe_retention(&policy),
+            "days older than 30 removed; oldest raw_audio files removed above 512 MB; \
+             oldest days removed above 2048 MB in total; the content access log is kept until deleted"
+        );
+    }
+}
@@ -0,0 +1,573 @@
+//! Startup configuration: `eyecore.toml` in the working directory (or the file named by
+//! EYECORE_CONFIG), then environment variables on top. Anything left out keeps its default.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::data_collector::DataCollector;
+pub use crate::collectors::CollectorConfig;
+pub use crate::emotion::{EmotionBackendKind, EmotionConfig};
+pub use crate::input_trace::InputTraceConfig;
+pub use crate::keystroke_capture::CaptureMode;
+pub use crate::redaction::RedactionConfig;
+pub use crate::scheduler::SchedulerConfig;
+pub use crate::time_export::TimeExportConfig;
+pub use crate::websocket_client::UploadTlsConfig;
+
+const DEFAULT_CONFIG_FILE: &str = "eyecore.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub snapshot_interval_secs: u64,
+    pub data_dir: String,
+    pub history_size: usize,           // snapshots kept in memory for /data/history
+    pub bind_addr: String,             // API listen address
+    pub server_url: String,            // WebSocket upload server
+    pub device_id: Option<String>,     // replaces the ID generated into <data_dir>/device_id
+    pub upload_queue_max_mb: u64,      // snapshots spooled for the server, see upload_queue
+    pub upload_batch_size: usize,      // snapshots per PackageBatch message, see upload_batch; 1 sends each alone
+    pub upload_tls: UploadTlsConfig,   // certificates accepted from a wss:// server
+    pub remote_commands: bool,         // carry out commands the upload server sends, see remote_commands
+    pub flag_detection: FlagDetection, // where snapshots are checked for flags, see flag_pipeline
+    pub storage_backend: StorageBackend,
+    pub compression: Compression,
+    pub snapshot_format: SnapshotFormat,
+    pub snapshot_log_max_mb: u64,      // size at which the snapshot log starts a new part
+    pub encryption: Encryption,
+    pub encryption_key_file: Option<String>, // keyring; <data_dir>/<device>/encryption_keys.json if unset
+    pub compact_after_days: Option<u32>, // days this old get their timeslot files merged nightly; off if unset
+    pub compare_collectors: bool,      // record simulated values next to measured ones, see comparison
+    pub keystroke_capture: CaptureMode, // real keystrokes: off, timing or content (keystroke-capture builds)
+    pub metrics_mirror: bool,          // unencrypted hourly metrics-only files next to the snapshots
+    pub forensic_buffer_secs: u32,     // raw input kept for incident bundles, see forensics; 0 is off
+    pub top_processes: usize,          // busiest processes listed per snapshot, see process_resources; 0 is off
+    pub modules: ModuleConfig,
+    pub retention: RetentionConfig,
+    pub redaction: RedactionConfig,
+    pub emotion: EmotionConfig,
+    pub input_trace: InputTraceConfig,
+    pub time_export: TimeExportConfig,
+    pub scheduler: SchedulerConfig,
+    pub collectors: BTreeMap<String, CollectorConfig>, // pluggable collectors by name, see collectors
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            snapshot_interval_secs: 5,
+            data_dir: "./data".to_string(),
+            history_size: 1000,
+            bind_addr: "127.0.0.1:3000".to_string(),
+            server_url: "ws://localhost:8765".to_string(),
+            device_id: None,
+            upload_queue_max_mb: 256,
+            upload_batch_size: 1,
+            upload_tls: UploadTlsConfig::default(),
+            remote_commands: true,
+            flag_detection: FlagDetection::External,
+            storage_backend: StorageBackend::Json,
+            compression: Compression::None,
+            snapshot_format: SnapshotFormat::Files,
+            snapshot_log_max_mb: 64,
+            encryption: Encryption::None,
+            encryption_key_file: None,
+            compact_after_days: None,
+            compare_collectors: false,
+            keystroke_capture: CaptureMode::Off,
+            metrics_mirror: false,
+            forensic_buffer_secs: 0,
+            top_processes: crate::process_resources::DEFAULT_TOP_N,
+            modules: ModuleConfig::default(),
+            retention: RetentionConfig::default(),
+            redaction: RedactionConfig::default(),
+            emotion: EmotionConfig::default(),
+            input_trace: InputTraceConfig::default(),
+            time_export: TimeExportConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            collectors: BTreeMap::new(),
+        }
+    }
+}
+
+/// Where snapshots, anomalies, transcriptions and flags are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    Json,      // one JSON file each in the day directories
+    Sqlite,    // tables in <device>/eyecore.db
+}
+
+/// Where the flag detector runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagDetection {
+    External,  // the standalone flag_detection binary, watching the data directory
+    InProcess, // inside the collector, on each snapshot as it is collected
+}
+
+/// How snapshots and session logs are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,      // pretty-printed `.json`
+    Zstd,      // compact JSON, zstd-compressed `.json.zst`
+}
+
+/// How snapshots are laid out in the day directories (JSON storage backend only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    Files,     // one timeslot file per snapshot
+    Ndjson,    // appended to a daily `snapshots_<date>_<part>.ndjson` log, one snapshot per line
+}
+
+/// At-rest encryption of snapshots, session logs, screen and keyboard data, transcriptions and audio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encryption {
+    None,
+    Xchacha20poly1305,  // sealed `.enc` files, see storage::encryption
+}
+
+/// Pruning of stored data, checked hourly (the scheduler's `retention` job); nothing is removed unless a limit is set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    pub max_age_days: Option<u32>,         // days older than this are removed
+    pub max_total_mb: Option<u64>,         // oldest days are removed until the data fits
+    pub quotas_mb: BTreeMap<String, u64>,  // per category, e.g. raw_audio = 2048; oldest files go first
+}
+
+/// Opt-in modules; those switched off here start disabled even if consented to during onboarding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModuleConfig {
+    pub voice_data: bool,
+    pub camera_data: bool,
+    pub keystroke_dynamics: bool,
+    pub file_metadata: bool,
+}
+
+impl Default for ModuleConfig {
+    fn default() -> Self {
+        ModuleConfig { voice_data: true, camera_data: true, keystroke_dynamics: true, file_metadata: true }
+    }
+}
+
+impl ModuleConfig {
+    const NAMES: [&'static str; 4] = ["voice_data", "camera_data", "keystroke_dynamics", "file_metadata"];
+
+    fn set(&mut self, module: &str, enabled: bool) {
+        match module {
+            "voice_data" => self.voice_data = enabled,
+            "camera_data" => self.camera_data = enabled,
+            "keystroke_dynamics" => self.keystroke_dynamics = enabled,
+            "file_metadata" => self.file_metadata = enabled,
+            _ => {}
+        }
+    }
+
+    /// Switch off the modules disabled here
+    pub fn apply(&self, collector: &mut DataCollector) {
+        if !self.voice_data {
+            collector.disable_voice();
+        }
+        if !self.camera_data {
+            collector.disable_camera();
+            collector.disable_camera_presence();
+        }
+        if !self.keystroke_dynamics {
+            collector.disable_keystroke();
+        }
+        if !self.file_metadata {
+            collector.disable_file_monitoring();
+        }
+    }
+}
+
+/// The configuration in effect and where it came from, as served by `/config`
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    pub file: Option<PathBuf>,         // None when no config file was found
+    pub env_overrides: Vec<String>,    // environment variables that replaced a value
+    #[serde(flatten)]
+    pub config: Config,
+}
+
+/// Read the config file and apply environment overrides. A config file that exists but cannot be
+/// read is an error, so a typo does not silently fall back to the defaults.
+pub fn load() -> Result<EffectiveConfig, String> {
+    let path = config_path();
+    let required = std::env::var_os("EYECORE_CONFIG").is_some();
+    let (mut config, file) = if path.exists() || required {
+        (read_file(&path)?, Some(path))
+    } else {
+        (Config::default(), None)
+    };
+    let env_overrides = apply_env(&mut config);
+    validate(&config)?;
+
+    match &file {
+        Some(path) => info!("⚙️ Configuration from {:?}", path),
+        None => info!("⚙️ No {} found, using the default configuration", DEFAULT_CONFIG_FILE),
+    }
+    if !env_overrides.is_empty() {
+        info!("⚙️ Overridden by {}", env_overrides.join(", "));
+    }
+    Ok(EffectiveConfig { file, env_overrides, config })
+}
+
+/// The config file read at startup: EYECORE_CONFIG, or `eyecore.toml` in the working directory
+pub fn config_path() -> PathBuf {
+    std::env::var_os("EYECORE_CONFIG").map_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE), PathBuf::from)
+}
+
+fn read_file(path: &Path) -> Result<Config, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read config {:?}: {}", path, e))?;
+    toml::from_str(&content).map_err(|e| format!("Invalid config {:?}: {}", path, e))
+}
+
+/// Apply EYECORE_* overrides, returning the names of those that were used
+fn apply_env(config: &mut Config) -> Vec<String> {
+    let mut applied = Vec::new();
+    let mut text = |name: &str, target: &mut String| {
+        if let Ok(value) = std::env::var(name) {
+            *target = value.trim().to_string();
+            applied.push(name.to_string());
+        }
+    };
+    text("EYECORE_DATA_DIR", &mut config.data_dir);
+    text("EYECORE_BIND_ADDR", &mut config.bind_addr);
+    text("EYECORE_SERVER_URL", &mut config.server_url);
+    if let Ok(value) = std::env::var("EYECORE_DEVICE_ID") {
+        config.device_id = Some(value.trim().to_string());
+        applied.push("EYECORE_DEVICE_ID".to_string());
+    }
+    if let Ok(value) = std::env::var("EYECORE_UPLOAD_CA_FILE") {
+        config.upload_tls.ca_file = Some(value.trim().to_string());
+        applied.push("EYECORE_UPLOAD_CA_FILE".to_string());
+    }
+    if let Ok(value) = std::env::var("EYECORE_ENCRYPTION_KEY_FILE") {
+        config.encryption_key_file = Some(value.trim().to_string());
+        applied.push("EYECORE_ENCRYPTION_KEY_FILE".to_string());
+    }
+    if let Ok(value) = std::env::var("EYECORE_EMOTION_MODEL") {
+        config.emotion.model_path = Some(value.trim().to_string());
+        applied.push("EYECORE_EMOTION_MODEL".to_string());
+    }
+    if let Ok(value) = std::env::var("EYECORE_EMOTION_ENDPOINT") {
+        config.emotion.endpoint = Some(value.trim().to_string());
+        applied.push("EYECORE_EMOTION_ENDPOINT".to_string());
+    }
+
+    let mut number = |name: &str| match std::env::var(name) {
+        Ok(value) => match value.trim().parse::<u64>() {
+            Ok(n) if n > 0 => {
+                applied.push(name.to_string());
+                Some(n)
+            }
+            _ => {
+                warn!("Ignoring {}: {:?} is not a positive number", name, value);
+                None
+            }
+        },
+        Err(_) => None,
+    };
+    if let Some(secs) = number("EYECORE_SNAPSHOT_INTERVAL_SECS") {
+        config.snapshot_interval_secs = secs;
+    }
+    if let Some(size) = number("EYECORE_HISTORY_SIZE") {
+        config.history_size = size as usize;
+    }
+    if let Some(days) = number("EYECORE_RETENTION_MAX_AGE_DAYS") {
+        config.retention.max_age_days = Some(days.min(u32::MAX as u64) as u32);
+    }
+    if let Some(mb) = number("EYECORE_RETENTION_MAX_TOTAL_MB") {
+        config.retention.max_total_mb = Some(mb);
+    }
+    if let Some(mb) = number("EYECORE_SNAPSHOT_LOG_MAX_MB") {
+        config.snapshot_log_max_mb = mb;
+    }
+    if let Some(days) = number("EYECORE_COMPACT_AFTER_DAYS") {
+        config.compact_after_days = Some(days.min(u32::MAX as u64) as u32);
+    }
+    if let Some(secs) = number("EYECORE_FORENSIC_BUFFER_SECS") {
+        config.forensic_buffer_secs = secs.min(u32::MAX as u64) as u32;
+    }
+    if let Some(mb) = number("EYECORE_UPLOAD_QUEUE_MAX_MB") {
+        config.upload_queue_max_mb = mb;
+    }
+    if let Some(size) = number("EYECORE_UPLOAD_BATCH_SIZE") {
+        config.upload_batch_size = size.min(usize::MAX as u64) as usize;
+    }
+    if let Some(count) = number("EYECORE_TOP_PROCESSES") {
+        config.top_processes = count.min(usize::MAX as u64) as usize;
+    }
+
+    if let Ok(value) = std::env::var("EYECORE_STORAGE_BACKEND") {
+        let backend = match value.trim() {
+            "json" => Some(StorageBackend::Json),
+            "sqlite" => Some(StorageBackend::Sqlite),
+            _ => None,
+        };
+        match backend {
+            Some(backend) => {
+                config.storage_backend = backend;
+                applied.push("EYECORE_STORAGE_BACKEND".to_string());
+            }
+            None => warn!("Ignoring EYECORE_STORAGE_BACKEND: {:?} is not json or sqlite", value),
+        }
+    }
+    if let Ok(value) = std::env::var("EYECORE_FLAG_DETECTION") {
+        let mode = match value.trim() {
+            "external" => Some(FlagDetection::External),
+            "in_process" => Some(FlagDetection::InProcess),
+            _ => None,
+        };
+        match mode {
+            Some(mode) => {
+                config.flag_detection = mode;
+                applied.push("EYECORE_FLAG_DETECTION".to_string());
+            }
+            None => warn!("Ignoring EYECORE_FLAG_DETECTION: {:?} is not external or in_process", value),
+        }
+    }
+    if let Ok(value) = std::env::var("EYECORE_COMPRESSION") {
+        let compression = match value.trim() {
+            "none" => Some(Compression::None),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        };
+        match compression {
+            Some(compression) => {
+                config.compression = compression;
+                applied.push("EYECORE_COMPRESSION".to_string());
+            }
+            None => warn!("Ignoring EYECORE_COMPRESSION: {:?} is not none or zstd", value),
+        }
+    }
+    if let Ok(value) = std::env::var("EYECORE_SNAPSHOT_FORMAT") {
+        let format = match value.trim() {
+            "files" => Some(SnapshotFormat::Files),
+            "ndjson" => Some(SnapshotFormat::Ndjson),
+            _ => None,
+        };
+        match format {
+            Some(format) => {
+                config.snapshot_format = format;
+                applied.push("EYECORE_SNAPSHOT_FORMAT".to_string());
+            }
+            None => warn!("Ignoring EYECORE_SNAPSHOT_FORMAT: {:?} is not files or ndjson", value),
+        }
+    }
+
+    if let Ok(value) = std::env::var("EYECORE_EMOTION_BACKEND") {
+        let backend = match value.trim() {
+            "simulated" => Some(EmotionBackendKind::Simulated),
+            "onnx" => Some(EmotionBackendKind::Onnx),
+            "cloud" => Some(EmotionBackendKind::Cloud),
+            _ => None,
+        };
+        match backend {
+            Some(backend) => {
+                config.emotion.backend = backend;
+                applied.push("EYECORE_EMOTION_BACKEND".to_string());
+            }
+            None => warn!("Ignoring EYECORE_EMOTION_BACKEND: {:?} is not simulated, onnx or cloud", value),
+        }
+    }
+
+    if let Ok(value) = std::env::var("EYECORE_ENCRYPTION") {
+        let encryption = match value.trim() {
+            "none" => Some(Encryption::None),
+            "xchacha20poly1305" => Some(Encryption::Xchacha20poly1305),
+            _ => None,
+        };
+        match encryption {
+            Some(encryption) => {
+                config.encryption = encryption;
+                applied.push("EYECORE_ENCRYPTION".to_string());
+            }
+            None => warn!("Ignoring EYECORE_ENCRYPTION: {:?} is not none or xchacha20poly1305", value),
+        }
+    }
+
+    if let Ok(value) = std::env::var("EYECORE_COMPARE_COLLECTORS") {
+        let compare = match value.trim() {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        };
+        match compare {
+            Some(compare) => {
+                config.compare_collectors = compare;
+                applied.push("EYECORE_COMPARE_COLLECTORS".to_string());
+            }
+            None => warn!("Ignoring EYECORE_COMPARE_COLLECTORS: {:?} is not true or false", value),
+        }
+    }
+
+    if let Ok(value) = std::env::var("EYECORE_METRICS_MIRROR") {
+        let mirror = match value.trim() {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        };
+        match mirror {
+            Some(mirror) => {
+                config.metrics_mirror = mirror;
+                applied.push("EYECORE_METRICS_MIRROR".to_string());
+            }
+            None => warn!("Ignoring EYECORE_METRICS_MIRROR: {:?} is not true or false", value),
+        }
+    }
+    if let Ok(value) = std::env::var("EYECORE_INPUT_TRACE") {
+        let trace = match value.trim() {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        };
+        match trace {
+            Some(trace) => {
+                config.input_trace.enabled = trace;
+                applied.push("EYECORE_INPUT_TRACE".to_string());
+            }
+            None => warn!("Ignoring EYECORE_INPUT_TRACE: {:?} is not true or false", value),
+        }
+    }
+    if let Ok(value) = std::env::var("EYECORE_KEYSTROKE_CAPTURE") {
+        let capture = match value.trim() {
+            "off" => Some(CaptureMode::Off),
+            "timing" => Some(CaptureMode::Timing),
+            "content" => Some(CaptureMode::Content),
+            _ => None,
+        };
+        match capture {
+            Some(capture) => {
+                config.keystroke_capture = capture;
+                applied.push("EYECORE_KEYSTROKE_CAPTURE".to_string());
+            }
+            None => warn!("Ignoring EYECORE_KEYSTROKE_CAPTURE: {:?} is not off, timing or content", value),
+        }
+    }
+
+    // Comma-separated list of the opt-in modules to run; the others are switched off
+    if let Ok(value) = std::env::var("EYECORE_MODULES") {
+        let enabled: Vec<&str> = value.split(',').map(str::trim).filter(|m| !m.is_empty()).collect();
+        match enabled.iter().find(|module| !ModuleConfig::NAMES.contains(module)) {
+            Some(unknown) => warn!(
+                "Ignoring EYECORE_MODULES: unknown module {:?} (expected {})",
+                unknown,
+                ModuleConfig::NAMES.join(", ")
+            ),
+            None => {
+                for module in ModuleConfig::NAMES {
+                    config.modules.set(module, enabled.contains(&module));
+                }
+                applied.push("EYECORE_MODULES".to_string());
+            }
+        }
+    }
+    applied
+}
+
+pub fn validate(config: &Config) -> Result<(), String> {
+    if config.snapshot_interval_secs == 0 {
+        return Err("snapshot_interval_secs must be at least 1".to_string());
+    }
+    if config.history_size == 0 {
+        return Err("history_size must be at least 1".to_string());
+    }
+    if config.data_dir.is_empty() {
+        return Err("data_dir must not be empty".to_string());
+    }
+    if config.snapshot_log_max_mb == 0 {
+        return Err("snapshot_log_max_mb must be at least 1".to_string());
+    }
+    if config.upload_queue_max_mb == 0 {
+        return Err("upload_queue_max_mb must be at least 1".to_string());
+    }
+    if !(1..=crate::upload_batch::MAX_BATCH_SIZE).contains(&config.upload_batch_size) {
+        return Err(format!("upload_batch_size must be 1-{}", crate::upload_batch::MAX_BATCH_SIZE));
+    }
+    if config.encryption != Encryption::None {
+        // Neither keeps data in sealed files
+        if config.storage_backend == StorageBackend::Sqlite {
+            return Err("encryption requires storage_backend = \"json\"".to_string());
+        }
+        if config.snapshot_format == SnapshotFormat::Ndjson {
+            return Err("encryption requires snapshot_format = \"files\"".to_string());
+        }
+        if config.input_trace.enabled {
+            return Err("encryption requires input_trace.enabled = false".to_string());
+        }
+    }
+    if config.encryption_key_file.as_deref() == Some("") {
+        return Err("encryption_key_file must not be empty".to_string());
+    }
+    if config.compact_after_days.is_some() {
+        // The current day is still being written
+        if config.compact_after_days == Some(0) {
+            return Err("compact_after_days must be at least 1".to_string());
+        }
+        if config.storage_backend == StorageBackend::Sqlite {
+            return Err("compact_after_days requires storage_backend = \"json\"".to_string());
+        }
+    }
+    if config.keystroke_capture != CaptureMode::Off && !cfg!(feature = "keystroke-capture") {
+        return Err("keystroke_capture requires a build with --features keystroke-capture".to_string());
+    }
+    if config.forensic_buffer_secs > 0 {
+        if config.forensic_buffer_secs > crate::forensics::MAX_BUFFER_SECS {
+            return Err(format!("forensic_buffer_secs must be at most {}", crate::forensics::MAX_BUFFER_SECS));
+        }
+        // Incident bundles are only ever stored sealed
+        if config.encryption == Encryption::None {
+            return Err("forensic_buffer_secs requires encryption = \"xchacha20poly1305\"".to_string());
+        }
+    }
+    if config.top_processes > crate::process_resources::MAX_TOP_N {
+        return Err(format!("top_processes must be at most {}", crate::process_resources::MAX_TOP_N));
+    }
+    if config.retention.max_age_days == Some(0) {
+        return Err("retention.max_age_days must be at least 1".to_string());
+    }
+    if config.retention.max_total_mb == Some(0) {
+        return Err("retention.max_total_mb must be at least 1".to_string());
+    }
+    for (category, mb) in &config.retention.quotas_mb {
+        if !crate::storage::retention::is_prunable(category) {
+            return Err(format!(
+                "Unknown retention quota category {:?} (expected one of {})",
+                category,
+                crate::storage::retention::prunable_categories().join(", ")
+            ));
+        }
+        if *mb == 0 {
+            return Err(format!("retention.quotas_mb.{} must be at least 1", category));
+        }
+    }
+    crate::redaction::Redactor::new(&config.redaction)?;
+    config.emotion.validate()?;
+    config.input_trace.validate()?;
+    config.time_export.validate()?;
+    config.scheduler.validate()?;
+    for (name, collector) in &config.collectors {
+        collector.validate(name)?;
+    }
+    if !(config.server_url.starts_with("ws://") || config.server_url.starts_with("wss://")) {
+        return Err(format!("Invalid server_url {:?}: expected a ws:// or wss:// URL", config.server_url));
+    }
+    config.upload_tls.validate()?;
+    if let Some(device_id) = &config.device_id {
+        // It names the device's data directory
+        let valid = device_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if device_id.is_empty() || device_id.len() > 64 || !valid {
+            return Err(format!("Invalid device_id {:?}: expected 1-64 letters, digits, - or _", device_id));
+        }
+    }
+    Ok(())
+}
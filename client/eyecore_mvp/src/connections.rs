@@ -0,0 +1,91 @@
+//! Open sockets for `network_metrics`: TCP connections in every state but listening, and UDP
+//! sockets, for IPv4 and IPv6. Read from /proc/net on Linux, the IP Helper tables on Windows and
+//! `netstat` on macOS.
+
+/// Socket counts at one moment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketCounts {
+    pub tcp: usize,
+    pub udp: usize,
+}
+
+/// None where the socket tables cannot be read
+#[cfg(target_os = "linux")]
+pub fn count_sockets() -> Option<SocketCounts> {
+    const LISTEN: &str = "0A";
+    // A header line, then one socket per line with its state in hex in the fourth column
+    let states = |table: &str| -> Vec<String> {
+        let table = std::fs::read_to_string(table).unwrap_or_default();
+        table.lines().skip(1).filter_map(|row| row.split_whitespace().nth(3)).map(str::to_string).collect()
+    };
+    // Without procfs nothing can be told; the IPv6 tables are missing when IPv6 is off
+    std::fs::metadata("/proc/net/tcp").ok()?;
+    let tcp = [states("/proc/net/tcp"), states("/proc/net/tcp6")].concat();
+    Some(SocketCounts {
+        tcp: tcp.iter().filter(|state| *state != LISTEN).count(),
+        udp: states("/proc/net/udp").len() + states("/proc/net/udp6").len(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+pub fn count_sockets() -> Option<SocketCounts> {
+    use windows::Win32::NetworkManagement::IpHelper::{
+        GetExtendedTcpTable, GetExtendedUdpTable, TCP_TABLE_BASIC_CONNECTIONS, UDP_TABLE_BASIC,
+    };
+
+    const AF_INET: u32 = 2;
+    const AF_INET6: u32 = 23;
+
+    let tcp = |family: u32| {
+        table_entries(|table, size| unsafe { GetExtendedTcpTable(table, size, false, family, TCP_TABLE_BASIC_CONNECTIONS, 0) })
+    };
+    let udp = |family: u32| table_entries(|table, size| unsafe { GetExtendedUdpTable(table, size, false, family, UDP_TABLE_BASIC, 0) });
+    Some(SocketCounts {
+        tcp: tcp(AF_INET)? + tcp(AF_INET6).unwrap_or(0),
+        udp: udp(AF_INET)? + udp(AF_INET6).unwrap_or(0),
+    })
+}
+
+/// Entries of an IP Helper table; the first call only reports the size needed, and every table
+/// starts with its entry count
+#[cfg(target_os = "windows")]
+fn table_entries(fetch: impl Fn(Option<*mut core::ffi::c_void>, *mut u32) -> u32) -> Option<usize> {
+    const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
+    for _ in 0..3 {
+        let mut size = 0u32;
+        if fetch(None, &mut size) != ERROR_INSUFFICIENT_BUFFER {
+            return None;
+        }
+        let mut table = vec![0u32; (size as usize).div_ceil(4)];
+        match fetch(Some(table.as_mut_ptr().cast()), &mut size) {
+            0 => return table.first().map(|count| *count as usize),
+            // Sockets were opened between the two calls
+            ERROR_INSUFFICIENT_BUFFER => continue,
+            _ => return None,
+        }
+    }
+    None
+}
+
+#[cfg(target_os = "macos")]
+pub fn count_sockets() -> Option<SocketCounts> {
+    let output = std::process::Command::new("netstat").args(["-an"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut counts = SocketCounts { tcp: 0, udp: 0 };
+    // "tcp4  0  0  192.168.1.5.52144  17.57.146.52.5223  ESTABLISHED"
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if line.starts_with("tcp") && !line.trim_end().ends_with("LISTEN") {
+            counts.tcp += 1;
+        } else if line.starts_with("udp") {
+            counts.udp += 1;
+        }
+    }
+    Some(counts)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub fn count_sockets() -> Option<SocketCounts> {
+    None
+}
@@ -0,0 +1,234 @@
+//! Consent for the privacy-sensitive modules, kept in `<device>/consent.json` together with every
+//! grant and revocation (when, how and why). A module without granted consent does not collect,
+//! whatever its default: the collector is switched to the recorded consent at startup and on every
+//! change. Consent is given during onboarding, through `/consent`, or by importing a configuration
+//! bundle. `camera_presence` is a lesser level of camera consent: without `camera`, the camera only
+//! tells whether someone is at the desk. Likewise `keystroke` covers timing only, and the text typed
+//! needs `typed_text` on top.
+
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::data_collector::DataCollector;
+use crate::models::{ConsentEvent, ConsentStatus, ModuleConsent};
+
+/// API name, the name onboarding and configuration bundles use, and what consent covers
+const MODULES: [(&str, &str, &str); 8] = [
+    ("voice", "voice_data", "Microphone samples for speaking time and vocal stress"),
+    ("camera", "camera_data", "Camera frames for posture, gaze and facial expressions"),
+    ("camera_presence", "camera_presence", "Camera frames, analysed in memory, only for whether a face is present"),
+    ("camera_cloud", "camera_cloud", "Camera frames sent to the configured emotion service, off the device"),
+    ("keystroke", "keystroke_dynamics", "Keystroke timing: when keys go down and come up, never which keys"),
+    ("typed_text", "typed_text", "The text typed and the labels of buttons clicked"),
+    ("screen_text", "screen_text", "Text on screen and in open windows"),
+    ("files", "file_metadata", "File types, sizes and access patterns"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Decision {
+    granted: bool,
+    at: DateTime<Utc>,
+    source: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ConsentRecord {
+    modules: BTreeMap<String, Decision>,
+    history: Vec<ConsentEvent>,
+}
+
+pub struct ConsentManager {
+    path: PathBuf,
+    record: ConsentRecord,
+}
+
+/// Whether `/consent/{module}` names a module that needs consent
+pub fn is_module(module: &str) -> bool {
+    MODULES.iter().any(|(name, _, _)| *name == module)
+}
+
+/// The consent module onboarding and configuration bundles call `field`
+pub fn module_for_field(field: &str) -> Option<&'static str> {
+    MODULES.iter().find(|(_, name, _)| *name == field).map(|(module, _, _)| *module)
+}
+
+fn set_enabled(collector: &mut DataCollector, module: &str, enabled: bool) {
+    match (module, enabled) {
+        ("voice", true) => collector.enable_voice(),
+        ("voice", false) => collector.disable_voice(),
+        ("camera", true) => collector.enable_camera(),
+        ("camera", false) => collector.disable_camera(),
+        ("camera_presence", true) => collector.enable_camera_presence(),
+        ("camera_presence", false) => collector.disable_camera_presence(),
+        ("camera_cloud", true) => collector.enable_camera_cloud(),
+        ("camera_cloud", false) => collector.disable_camera_cloud(),
+        ("keystroke", true) => collector.enable_keystroke(),
+        ("keystroke", false) => collector.disable_keystroke(),
+        ("typed_text", true) => collector.enable_typed_text(),
+        ("typed_text", false) => collector.disable_typed_text(),
+        ("screen_text", true) => collector.enable_screen_text(),
+        ("screen_text", false) => collector.disable_screen_text(),
+        ("files", true) => collector.enable_file_monitoring(),
+        ("files", false) => collector.disable_file_monitoring(),
+        _ => {}
+    }
+}
+
+impl ConsentManager {
+    /// Consent kept in `device_dir`. An unreadable file counts as no consent at all.
+    pub fn load(device_dir: &Path) -> Self {
+        let path = device_dir.join("consent.json");
+        let record = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("⚠️ Unreadable consent record {:?}, treating every module as not consented: {}", path, e);
+                ConsentRecord::default()
+            }),
+            Err(_) => ConsentRecord::default(),
+        };
+        ConsentManager { path, record }
+    }
+
+    /// Consent the service recorded for this device, found through the configured data directory and
+    /// device ID, for collecting outside the service (`collect --once`, the C API). Before the
+    /// service has run there is no device ID, and no module has consent.
+    pub fn load_configured(config: &crate::config::Config) -> Self {
+        let data_dir = Path::new(&config.data_dir);
+        let device_id = config.device_id.clone().or_else(|| {
+            std::fs::read_to_string(data_dir.join("device_id"))
+                .ok()
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+        });
+        match device_id {
+            Some(device_id) => Self::load(&data_dir.join(device_id)),
+            None => ConsentManager { path: data_dir.join("consent.json"), record: ConsentRecord::default() },
+        }
+    }
+
+    /// Take over the answers onboarding recorded before consent had its own record; does nothing
+    /// once any consent was recorded
+    pub fn migrate_onboarding(&mut self, consents: &BTreeMap<String, bool>) {
+        if !self.record.history.is_empty() || consents.is_empty() {
+            return;
+        }
+        if let Err(e) = self.import(consents, "onboarding") {
+            error!("{}", e);
+        } else {
+            info!("✓ Consent taken over from onboarding answers");
+        }
+    }
+
+    /// Record consents keyed by their onboarding names, e.g. from a configuration bundle
+    pub fn import(&mut self, consents: &BTreeMap<String, bool>, source: &str) -> Result<(), String> {
+        for (field, granted) in consents {
+            if let Some(module) = module_for_field(field) {
+                self.record_change(module, *granted, source, None);
+            }
+        }
+        self.write()
+    }
+
+    pub fn is_granted(&self, module: &str) -> bool {
+        self.record.modules.get(module).is_some_and(|decision| decision.granted)
+    }
+
+    /// Switch every module to its consent: on only if granted
+    pub fn apply(&self, collector: &mut DataCollector) {
+        for (module, _, _) in MODULES {
+            set_enabled(collector, module, self.is_granted(module));
+        }
+    }
+
+    /// Grant or revoke consent for `module` and switch it accordingly. Nothing changes unless the
+    /// change could be saved.
+    pub fn set(
+        &mut self,
+        module: &str,
+        granted: bool,
+        source: &str,
+        reason: Option<String>,
+        collector: &mut DataCollector,
+    ) -> Result<ModuleConsent, String> {
+        let (name, _, _) = MODULES
+            .iter()
+            .find(|(name, _, _)| *name == module)
+            .ok_or_else(|| format!("Unknown module {:?}", module))?;
+        let previous = self.record.modules.get(*name).cloned();
+        self.record_change(name, granted, source, reason);
+        if let Err(e) = self.write() {
+            match previous {
+                Some(previous) => self.record.modules.insert(name.to_string(), previous),
+                None => self.record.modules.remove(*name),
+            };
+            self.record.history.pop();
+            return Err(e);
+        }
+        set_enabled(collector, name, granted);
+        info!("🔏 Consent for {} {} ({})", name, if granted { "granted" } else { "revoked" }, source);
+        Ok(self.describe(name))
+    }
+
+    /// Consent onboarding and configuration bundles carry, keyed by their names
+    pub fn consents(&self) -> BTreeMap<String, bool> {
+        MODULES
+            .iter()
+            .filter_map(|(module, field, _)| self.record.modules.get(*module).map(|decision| (field.to_string(), decision.granted)))
+            .collect()
+    }
+
+    pub fn status(&self) -> ConsentStatus {
+        ConsentStatus {
+            modules: MODULES.iter().map(|(module, _, _)| self.describe(module)).collect(),
+            history: self.record.history.clone(),
+        }
+    }
+
+    fn describe(&self, module: &str) -> ModuleConsent {
+        let decision = self.record.modules.get(module);
+        ModuleConsent {
+            module: module.to_string(),
+            description: MODULES.iter().find(|(name, _, _)| *name == module).map_or("", |(_, _, text)| text).to_string(),
+            granted: decision.is_some_and(|decision| decision.granted),
+            updated_at: decision.map(|decision| decision.at),
+            source: decision.map(|decision| decision.source.clone()),
+        }
+    }
+
+    fn record_change(&mut self, module: &str, granted: bool, source: &str, reason: Option<String>) {
+        let at = Utc::now();
+        self.record.modules.insert(module.to_string(), Decision { granted, at, source: source.to_string() });
+        self.record.history.push(ConsentEvent { module: module.to_string(), granted, at, source: source.to_string(), reason });
+    }
+
+    fn write(&self) -> Result<(), String> {
+        serde_json::to_string_pretty(&self.record)
+            .map_err(|e| e.to_string())
+            .and_then(|content| std::fs::write(&self.path, content).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to save consent record {:?}: {}", self.path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_consent_follows_the_device_id_file() {
+        let dir = std::env::temp_dir().join(format!("eyecore_consent_{}", uuid::Uuid::new_v4()));
+        let config = crate::config::Config { data_dir: dir.to_string_lossy().to_string(), ..Default::default() };
+        assert!(!ConsentManager::load_configured(&config).is_granted("voice"));
+
+        std::fs::create_dir_all(dir.join("device-1")).unwrap();
+        std::fs::write(dir.join("device_id"), "device-1\n").unwrap();
+        let mut consent = ConsentManager::load(&dir.join("device-1"));
+        consent.import(&BTreeMap::from([("voice_data".to_string(), true)]), "test").unwrap();
+
+        let loaded = ConsentManager::load_configured(&config);
+        assert!(loaded.is_granted("voice"));
+        assert!(!loaded.is_granted("camera"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
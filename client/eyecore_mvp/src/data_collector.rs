@@ -1,9 +1,190 @@
 use crate::models::*;
 use chrono::Utc;
 use log::{info, debug};
-use sysinfo::System;
-use std::collections::VecDeque;
+use sysinfo::Networks;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use uuid::Uuid;
+use std::sync::Arc;
+use crate::heart_rate::HeartRateMonitor;
+use crate::meeting::MeetingDetector;
+use crate::capture_log::CaptureTracker;
+use crate::attribution::FocusContextTracker;
+use crate::notifications::NotificationMonitor;
+use crate::switches::{SwitchMonitor, CADENCE_WINDOW};
+use crate::presence::PresenceMonitor;
+use crate::media::MediaMonitor;
+use crate::routing::RoutingDetector;
+use crate::wifi::WifiMonitor;
+use crate::classification::{AppClassification, Classified};
+use crate::projects::ProjectDetector;
+use crate::docking::DockingMonitor;
+use crate::input_hooks::{self, InputHooks};
+use crate::system_sampler::SystemSampler;
+use crate::process_resources::ProcessResourceTracker;
+use crate::active_window::{self, ActiveWindowProvider};
+use crate::power::PowerMonitor;
+use crate::redaction::Redactor;
+use crate::forensics::ForensicBuffer;
+use crate::input_trace::InputTrace;
+use crate::connections;
+use crate::collectors::{Cadence, Collector, CollectorRegistry};
+use crate::emotion::EmotionDetector;
+use crate::gaze;
+use crate::keystroke_capture::{CapturedKeystrokes, KeystrokeCapture};
+
+// Switching that lowers the focus level: the full penalty from 6 switches a minute
+const SWITCH_RATE_SATURATION: f32 = 6.0;
+const MAX_SWITCH_PENALTY: f32 = 0.3;
+const PING_PONG_PENALTY: f32 = 0.1;
+// Focus lost to a video playing alongside work
+const VIDEO_PENALTY: f32 = 0.15;
+// Focus lost while a distracting app or site is in front
+const DISTRACTING_APP_PENALTY: f32 = 0.2;
+// Foreground samples further apart than this do not add productive time
+const MAX_APP_SAMPLE_GAP: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Modules the module scheduler samples on their own interval rather than with each snapshot:
+/// snapshot field and the interval it declares, which `/control` can override
+pub const SCHEDULED_MODULES: [(&str, std::time::Duration); 4] = [
+    ("system_metrics", std::time::Duration::from_secs(1)),
+    ("screen_interactions", std::time::Duration::from_secs(10)),
+    ("camera_data", std::time::Duration::from_secs(30)),
+    ("file_metadata", std::time::Duration::from_secs(60)),
+];
+
+/// Latest readings of the scheduled modules. System metrics carry over into every snapshot; the
+/// optional sections only go into the next one.
+#[derive(Default)]
+struct ScheduledReadings {
+    system_metrics: Option<SystemMetrics>,
+    screen_interactions: Option<ScreenInteractions>,
+    camera_data: Option<CameraData>,
+    file_metadata: Option<FileMetadata>,
+}
+
+/// Traffic type and activity context implied by what is playing: a call or video; music may be local
+fn media_traffic_type(media: Option<&[MediaPlayback]>) -> Option<(&'static str, &'static str)> {
+    let playing = |kind: MediaKind| media.is_some_and(|media| media.iter().any(|playback| playback.kind == kind));
+    if playing(MediaKind::VideoCall) {
+        Some(("video_conference", "meeting"))
+    } else if playing(MediaKind::Video) {
+        Some(("streaming", "entertainment"))
+    } else {
+        None
+    }
+}
+
+/// Mouse clicks, key presses and idle seconds for CI builds and where the hooks cannot be installed
+fn simulated_input() -> (u32, u32, u32) {
+    (rand::random::<u32>() % 10, rand::random::<u32>() % 20, rand::random::<u32>() % 60)
+}
+
+fn simulated_context_switches() -> u32 {
+    rand::random::<u32>() % 20
+}
+
+fn simulated_productive_app_time() -> u32 {
+    rand::random::<u32>() % 600
+}
+
+fn simulated_connections() -> usize {
+    (rand::random::<usize>() % 50) + 1
+}
+
+/// Typing speed (words of five keys a minute), mean hold and interval in ms, the variation of the
+/// intervals (0 for perfectly even typing, capped at 1) and the share of presses that were corrections
+fn measured_typing(captured: &CapturedKeystrokes) -> (f32, f32, f32, f32, f32) {
+    let mean = |values: &[f32]| if values.is_empty() { 0.0 } else { values.iter().sum::<f32>() / values.len() as f32 };
+    let intervals = captured.intervals_ms();
+    let interval = mean(&intervals);
+    let minutes = captured.elapsed.as_secs_f32() / 60.0;
+    let wpm = if minutes > 0.0 { captured.presses.len() as f32 / 5.0 / minutes } else { 0.0 };
+    let variation = if interval > 0.0 {
+        let deviation = (intervals.iter().map(|i| (i - interval).powi(2)).sum::<f32>() / intervals.len() as f32).sqrt();
+        (deviation / interval).min(1.0)
+    } else {
+        0.0
+    };
+    let corrections = if captured.presses.is_empty() { 0.0 } else { captured.corrections as f32 / captured.presses.len() as f32 };
+    (wpm, mean(&captured.holds_ms), interval, variation, corrections)
+}
+
+/// The simulated value of each measured field of `data` that still has a simulation
+fn compare_with_simulation(data: &EyeCoreData) -> BTreeMap<String, CollectorComparison> {
+    let measured = |field: &str| data.provenance.get(field) == Some(&Provenance::Measured);
+    let (clicks, key_presses, idle) = simulated_input();
+    let compared = [
+        ("input_metrics.mouse_clicks", measured("input_metrics"), data.input_metrics.mouse_clicks, clicks),
+        ("input_metrics.keyboard_events", measured("input_metrics"), data.input_metrics.keyboard_events, key_presses),
+        (
+            "input_metrics.idle_duration_seconds",
+            measured("input_metrics") || measured("input_metrics.idle_duration_seconds"),
+            data.input_metrics.idle_duration_seconds,
+            idle,
+        ),
+        (
+            "focus_metrics.context_switches",
+            measured("focus_metrics.context_switches"),
+            data.focus_metrics.context_switches,
+            simulated_context_switches(),
+        ),
+        (
+            "focus_metrics.productive_app_time",
+            measured("focus_metrics.productive_app_time"),
+            data.focus_metrics.productive_app_time,
+            simulated_productive_app_time(),
+        ),
+        (
+            "network_metrics.active_connections",
+            measured("network_metrics.active_connections"),
+            data.network_metrics.active_connections as u32,
+            simulated_connections() as u32,
+        ),
+    ];
+    compared
+        .into_iter()
+        .filter(|(_, measured, _, _)| *measured)
+        .map(|(field, _, measured, simulated)| {
+            (field.to_string(), CollectorComparison { measured: measured as f64, simulated: simulated as f64 })
+        })
+        .collect()
+}
+
+/// Pause between a warm-up pass and a reported collection, so CPU and network deltas are real
+pub const WARM_UP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Warm-up requirement declared by a collector whose first readings are not meaningful
+struct WarmUpRequirement {
+    collector: &'static str,
+    samples: u32,                  // samples needed before values are trustworthy
+    fields: &'static [&'static str],
+}
+
+// sysinfo computes CPU usage and network traffic as deltas between two refreshes
+const WARM_UP_REQUIREMENTS: &[WarmUpRequirement] = &[
+    WarmUpRequirement {
+        collector: "system_metrics",
+        samples: 2,
+        fields: &["system_metrics.cpu_usage", "system_metrics.per_core_cpu"],
+    },
+    WarmUpRequirement {
+        collector: "network_metrics",
+        samples: 2,
+        fields: &[
+            "network_metrics.bytes_sent",
+            "network_metrics.bytes_received",
+            "network_metrics.bytes_sent_per_sec",
+            "network_metrics.bytes_received_per_sec",
+            "network_metrics.interfaces",
+            "network_activity_metadata.bandwidth_usage_mbps",
+        ],
+    },
+    WarmUpRequirement {
+        collector: "process_resources",
+        samples: 2,
+        fields: &["top_processes"],
+    },
+];
 
 pub struct DataCollector {
     session_id: String,
@@ -16,7 +197,7 @@ pub struct DataCollector {
     // Enhanced tracking
     mouse_positions: VecDeque<(f32, f32, std::time::Instant)>,
     keystroke_timings: VecDeque<std::time::Instant>,
-    app_usage_history: VecDeque<(String, std::time::Instant)>,
+    app_usage_history: VecDeque<(Classified, std::time::Instant)>,
     screen_element_history: VecDeque<(String, std::time::Instant)>,
     voice_transcripts: VecDeque<String>,
     // NEW: Enhanced tracking for screen and keystroke content
@@ -31,8 +212,67 @@ pub struct DataCollector {
     _file_access_cache: std::collections::HashMap<String, u64>,
     voice_enabled: bool,
     camera_enabled: bool,
+    // Face presence only, when the camera module itself is off
+    camera_presence_enabled: bool,
+    // Frames may go to a cloud emotion backend, on top of camera consent
+    camera_cloud_enabled: bool,
     keystroke_enabled: bool,
+    // Typed text and clicked button labels, on top of keystroke timing
+    typed_text_enabled: bool,
     file_monitoring_enabled: bool,
+    // Window contents and on-screen (OCR) text
+    screen_text_enabled: bool,
+    // Masks personal data in typed, window and on-screen text
+    redactor: Arc<Redactor>,
+    // Record what the simulations report next to measured values
+    compare_collectors: bool,
+    // Opt-in modules collected less often than every snapshot: interval and last collection
+    sampling_intervals: HashMap<&'static str, Cadence>,
+    // Set once the module scheduler samples SCHEDULED_MODULES; snapshots then take its readings
+    module_scheduler: bool,
+    scheduled: ScheduledReadings,
+    // Pluggable collectors, each on its own cadence
+    collectors: CollectorRegistry,
+    // Persistent OS handles so delta-based readings survive between cycles
+    system_sampler: SystemSampler,
+    processes: ProcessResourceTracker,
+    // None where no supported window system is running
+    active_window: Option<Box<dyn ActiveWindowProvider>>,
+    networks: Networks,
+    last_network_refresh: std::time::Instant,
+    collector_samples: HashMap<&'static str, u32>,
+    heart_rate: Option<Arc<HeartRateMonitor>>,
+    meeting_detector: Option<Arc<MeetingDetector>>,
+    capture_tracker: Arc<CaptureTracker>,
+    // Brightness/ambient light change slowly and can be costly to read, so they are cached
+    display: crate::display::DisplayCache,
+    keyboard_layout: crate::keyboard::LayoutCache,
+    // Burst annotation for the snapshots collected while burst mode is active
+    burst: Option<BurstSample>,
+    focus_context: FocusContextTracker,
+    notifications: NotificationMonitor,
+    switches: SwitchMonitor,
+    presence: PresenceMonitor,
+    media: MediaMonitor,
+    routing: RoutingDetector,
+    wifi: WifiMonitor,
+    classification: AppClassification,
+    projects: ProjectDetector,
+    docking: DockingMonitor,
+    power: PowerMonitor,
+    // None when built with `simulated-input`
+    input_hooks: Option<&'static InputHooks>,
+    // Real keystrokes; None unless `keystroke_capture` is on
+    keystroke_capture: Option<Arc<KeystrokeCapture>>,
+    // Raw input for incident bundles; None unless `forensic_buffer_secs` is set
+    forensic_buffer: Option<Arc<ForensicBuffer>>,
+    // Full-resolution input for research; None unless `[input_trace]` is enabled
+    input_trace: Option<Arc<InputTrace>>,
+    // Camera emotions from a real backend; None for the simulated one
+    emotion_detector: Option<Arc<EmotionDetector>>,
+    // Maps raw gaze estimates to the screen; None until gaze is calibrated
+    gaze_calibration: Option<GazeCalibration>,
+    gaze_points: VecDeque<(f32, f32, std::time::Instant)>,
 }
 
 impl DataCollector {
@@ -60,69 +300,411 @@ impl DataCollector {
             keystroke_buffer: VecDeque::with_capacity(10000),
             button_click_history: VecDeque::with_capacity(5000),
             window_content_cache: std::collections::HashMap::new(),
-            voice_enabled: true,     // ENABLED - collecting all data
-            camera_enabled: true,    // ENABLED - collecting all data
-            keystroke_enabled: true, // ENABLED - collecting all data
-            file_monitoring_enabled: true, // ENABLED - collecting all data
+            // Privacy-sensitive modules stay off until consent switches them on (see consent)
+            voice_enabled: false,
+            camera_enabled: false,
+            camera_presence_enabled: false,
+            camera_cloud_enabled: false,
+            keystroke_enabled: false,
+            typed_text_enabled: false,
+            file_monitoring_enabled: false,
+            screen_text_enabled: false,
+            redactor: Arc::new(Redactor::default()),
+            compare_collectors: false,
+            sampling_intervals: HashMap::new(),
+            module_scheduler: false,
+            scheduled: ScheduledReadings::default(),
+            collectors: CollectorRegistry::new(),
+            system_sampler: SystemSampler::new(),
+            processes: ProcessResourceTracker::new(crate::process_resources::DEFAULT_TOP_N),
+            active_window: active_window::detect(),
+            networks: Networks::new_with_refreshed_list(),
+            last_network_refresh: std::time::Instant::now(),
+            collector_samples: HashMap::new(),
+            heart_rate: None,
+            meeting_detector: None,
+            capture_tracker: Arc::new(CaptureTracker::new()),
+            display: crate::display::DisplayCache::default(),
+            keyboard_layout: crate::keyboard::LayoutCache::default(),
+            burst: None,
+            focus_context: FocusContextTracker::new(),
+            notifications: NotificationMonitor::new(),
+            switches: SwitchMonitor::new(),
+            presence: PresenceMonitor::new(),
+            media: MediaMonitor::new(),
+            routing: RoutingDetector::new(),
+            wifi: WifiMonitor::new(),
+            classification: AppClassification::new(),
+            projects: ProjectDetector::new(),
+            docking: DockingMonitor::new(),
+            power: PowerMonitor::from_env(),
+            input_hooks: if cfg!(feature = "simulated-input") { None } else { Some(input_hooks::global()) },
+            keystroke_capture: None,
+            forensic_buffer: None,
+            input_trace: None,
+            emotion_detector: None,
+            gaze_calibration: None,
+            gaze_points: VecDeque::with_capacity(1000),
         }
     }
     
     // Enable/Disable collection modules
     pub fn enable_voice(&mut self) { self.voice_enabled = true; }
     pub fn disable_voice(&mut self) { self.voice_enabled = false; }
-    pub fn enable_camera(&mut self) { self.camera_enabled = true; }
-    pub fn disable_camera(&mut self) { self.camera_enabled = false; }
-    pub fn enable_keystroke(&mut self) { self.keystroke_enabled = true; }
-    pub fn disable_keystroke(&mut self) { self.keystroke_enabled = false; }
+    pub fn enable_camera(&mut self) { self.set_camera(true); }
+    pub fn disable_camera(&mut self) { self.set_camera(false); }
+    pub fn enable_camera_presence(&mut self) { self.set_camera_presence(true); }
+    pub fn disable_camera_presence(&mut self) { self.set_camera_presence(false); }
+    pub fn enable_camera_cloud(&mut self) { self.set_camera_cloud(true); }
+    pub fn disable_camera_cloud(&mut self) { self.set_camera_cloud(false); }
+    pub fn enable_keystroke(&mut self) { self.set_keystroke(true); }
+    pub fn disable_keystroke(&mut self) { self.set_keystroke(false); }
+    pub fn enable_typed_text(&mut self) { self.set_typed_text(true); }
+    pub fn disable_typed_text(&mut self) { self.set_typed_text(false); }
     pub fn enable_file_monitoring(&mut self) { self.file_monitoring_enabled = true; }
     pub fn disable_file_monitoring(&mut self) { self.file_monitoring_enabled = false; }
+    pub fn enable_screen_text(&mut self) { self.screen_text_enabled = true; }
+    pub fn disable_screen_text(&mut self) { self.screen_text_enabled = false; }
+    
+    /// Redact captured text with `redactor` (`[redaction]`) instead of every built-in rule
+    pub fn set_redactor(&mut self, redactor: Arc<Redactor>) {
+        self.redactor = redactor;
+    }
+    
+    fn set_camera(&mut self, enabled: bool) {
+        self.camera_enabled = enabled;
+        self.scheduled.camera_data = None;
+        self.sync_emotion_detector();
+    }
+    
+    fn set_camera_presence(&mut self, enabled: bool) {
+        self.camera_presence_enabled = enabled;
+        self.scheduled.camera_data = None;
+        self.sync_emotion_detector();
+    }
+    
+    fn set_camera_cloud(&mut self, enabled: bool) {
+        self.camera_cloud_enabled = enabled;
+        self.sync_emotion_detector();
+    }
+    
+    /// Grab frames while either camera mode is on, keeping only presence without the camera module.
+    /// A cloud backend also needs `camera_cloud` consent, since its frames leave the device.
+    fn sync_emotion_detector(&self) {
+        if let Some(detector) = &self.emotion_detector {
+            detector.set_presence_only(!self.camera_enabled);
+            let allowed = detector.on_device() || self.camera_cloud_enabled;
+            detector.set_active(allowed && (self.camera_enabled || self.camera_presence_enabled));
+        }
+    }
+
+    /// Whether camera frames are currently being sent to a cloud emotion backend
+    pub fn sends_camera_frames(&self) -> bool {
+        self.emotion_detector.as_ref().is_some_and(|detector| !detector.on_device() && detector.is_active())
+    }
+    
+    fn set_keystroke(&mut self, enabled: bool) {
+        self.keystroke_enabled = enabled;
+        if let Some(capture) = &self.keystroke_capture {
+            capture.set_active(enabled);
+        }
+        if let Some(buffer) = &self.forensic_buffer {
+            buffer.set_active(enabled);
+        }
+        if let Some(trace) = &self.input_trace {
+            trace.set_active(enabled);
+        }
+    }
+
+    fn set_typed_text(&mut self, enabled: bool) {
+        self.typed_text_enabled = enabled;
+        if let Some(capture) = &self.keystroke_capture {
+            capture.set_text_allowed(enabled);
+        }
+        if !enabled {
+            self.keystroke_buffer.clear();
+            self.button_click_history.clear();
+        }
+    }
+
+    /// Take keystroke dynamics from real keystrokes, recorded while the keystroke module is enabled
+    pub fn attach_keystroke_capture(&mut self, capture: Arc<KeystrokeCapture>) {
+        capture.set_active(self.keystroke_enabled);
+        capture.set_text_allowed(self.typed_text_enabled);
+        self.keystroke_capture = Some(capture);
+    }
+
+    /// Buffer raw input for incident bundles, like keystroke capture only while the keystroke
+    /// module is enabled
+    pub fn attach_forensic_buffer(&mut self, buffer: Arc<ForensicBuffer>) {
+        buffer.set_active(self.keystroke_enabled);
+        self.forensic_buffer = Some(buffer);
+    }
+
+    /// Record the input trace, like keystroke capture only while the keystroke module is enabled
+    pub fn attach_input_trace(&mut self, trace: Arc<InputTrace>) {
+        trace.set_active(self.keystroke_enabled);
+        self.input_trace = Some(trace);
+    }
+
+    /// Take facial emotions from `detector`, which grabs frames only while the camera module is enabled
+    /// (or just face presence in presence-only mode)
+    pub fn attach_emotion_detector(&mut self, detector: Arc<EmotionDetector>) {
+        self.emotion_detector = Some(detector);
+        self.sync_emotion_detector();
+    }
+
+    pub fn emotion_detector(&self) -> Option<Arc<EmotionDetector>> {
+        self.emotion_detector.clone()
+    }
+
+    /// Map gaze estimates to the screen with `calibration` (`/calibration/gaze`)
+    pub fn set_gaze_calibration(&mut self, calibration: Option<GazeCalibration>) {
+        self.gaze_calibration = calibration;
+        self.gaze_points.clear();
+    }
+
+    /// Report the `top_n` busiest processes in each snapshot's `top_processes`; 0 turns it off
+    pub fn set_top_processes(&mut self, top_n: usize) {
+        self.processes.set_top_n(top_n);
+    }
+
+    /// Record the simulated values of measured fields in each snapshot's `comparisons`
+    pub fn set_compare_collectors(&mut self, enabled: bool) {
+        self.compare_collectors = enabled;
+    }
+
+    /// Collect `module` (a snapshot field) at most once per `interval`; None collects it every time,
+    /// or on its declared interval under the module scheduler
+    pub fn set_sampling_interval(&mut self, module: &'static str, interval: Option<std::time::Duration>) {
+        match interval {
+            Some(interval) => {
+                self.sampling_intervals.entry(module).or_insert_with(|| Cadence::new(interval)).interval = interval;
+            }
+            None => {
+                self.sampling_intervals.remove(module);
+            }
+        }
+    }
+    
+    /// Whether `module` is due under its sampling interval, starting a new interval if it is
+    fn module_due(&mut self, module: &'static str) -> bool {
+        self.sampling_intervals.get_mut(module).is_none_or(Cadence::due)
+    }
+
+    /// Leave SCHEDULED_MODULES to the module scheduler: snapshots take the readings of
+    /// `sample_module` instead of collecting these modules themselves
+    pub fn start_module_scheduler(&mut self) {
+        self.module_scheduler = true;
+    }
+
+    /// How often the module scheduler samples `module`: its sampling interval, else the one it
+    /// declares. None for modules that are not scheduled.
+    pub fn module_interval(&self, module: &str) -> Option<std::time::Duration> {
+        let (_, declared) = SCHEDULED_MODULES.iter().find(|(name, _)| *name == module)?;
+        Some(self.sampling_intervals.get(module).map_or(*declared, |cadence| cadence.interval))
+    }
+
+    /// Take a reading of the scheduled `module` for the next snapshot
+    pub fn sample_module(&mut self, module: &str) {
+        match module {
+            "system_metrics" => self.scheduled.system_metrics = Some(self.collect_system_metrics()),
+            "screen_interactions" => self.scheduled.screen_interactions = Some(self.collect_screen_interactions()),
+            "camera_data" if (self.camera_enabled || self.camera_presence_enabled) && self.power.profile() == CollectionProfile::Full => {
+                // The emotion detector logs its own frame grabs
+                let _camera_stream = self.emotion_detector.is_none().then(|| self.capture_tracker.open("camera", "camera_data"));
+                self.scheduled.camera_data = Some(self.collect_camera_data());
+            }
+            "file_metadata" if self.file_monitoring_enabled => {
+                self.scheduled.file_metadata = Some(self.collect_file_metadata());
+            }
+            _ => {}
+        }
+    }
+
+    /// Run `collector` on its own cadence alongside the built-in ones
+    pub fn register_collector(&mut self, collector: Box<dyn Collector>) {
+        self.collectors.register(collector);
+    }
+
+    pub fn collectors(&self) -> &CollectorRegistry {
+        &self.collectors
+    }
+
+    pub fn collectors_mut(&mut self) -> &mut CollectorRegistry {
+        &mut self.collectors
+    }
 
     pub async fn collect_all(&mut self) {
         debug!("Collecting all data...");
         
         let now = Utc::now();
+        let power = self.power.observe();
         
-        let system_metrics = self.collect_system_metrics();
-        let process_data = self.collect_process_data();
-        let input_metrics = self.collect_input_metrics();
+        let system_metrics = match self.scheduled.system_metrics.clone() {
+            Some(metrics) if self.module_scheduler => metrics,
+            _ => self.collect_system_metrics(),
+        };
+        let (process_data, top_processes) = self.collect_process_data();
+        let media = self.media.observe(&process_data.active_window_title);
+        let mut input_metrics = self.collect_input_metrics();
+        let presence = self.presence.observe(input_metrics.idle_duration_seconds, media.as_deref());
+        input_metrics.idle_duration_seconds = presence.idle_seconds;
         let network_metrics = self.collect_network_metrics();
-        let focus_metrics = self.calculate_focus_metrics();
+        self.switches.poll();
+        let focus_metrics = self.calculate_focus_metrics(media.as_deref());
+        self.notifications.poll();
+        let focus_context =
+            self.focus_context.observe(&process_data.active_window_title, &self.switches, &self.notifications);
         
         // Collect enhanced data (opt-in modules)
-        let voice_data = if self.voice_enabled {
+        let voice_data = if self.voice_enabled && self.module_due("voice_data") {
             Some(self.collect_voice_data())
         } else {
             None
         };
         
-        let camera_data = if self.camera_enabled {
+        let camera_data = if self.module_scheduler {
+            self.scheduled.camera_data.take()
+        } else if (self.camera_enabled || self.camera_presence_enabled) && power.profile == CollectionProfile::Full && self.module_due("camera_data") {
+            // The emotion detector logs its own frame grabs
+            let _camera_stream = self.emotion_detector.is_none().then(|| self.capture_tracker.open("camera", "camera_data"));
             Some(self.collect_camera_data())
         } else {
             None
         };
         
-        let keystroke_dynamics = if self.keystroke_enabled {
+        let keystroke_dynamics = if self.keystroke_enabled && self.module_due("keystroke_dynamics") {
             Some(self.collect_keystroke_dynamics())
         } else {
             None
         };
         
-        let screen_interactions = Some(self.collect_screen_interactions());
-        let file_metadata = if self.file_monitoring_enabled {
+        let screen_interactions = if self.module_scheduler {
+            self.scheduled.screen_interactions.take()
+        } else {
+            Some(self.collect_screen_interactions())
+        };
+        let file_metadata = if self.module_scheduler {
+            self.scheduled.file_metadata.take().filter(|_| self.file_monitoring_enabled)
+        } else if self.file_monitoring_enabled && self.module_due("file_metadata") {
             Some(self.collect_file_metadata())
         } else {
             None
         };
         
-        let system_events = Some(self.collect_system_events());
+        let (dock, dock_change) = self.docking.observe();
+        let system_events = Some(self.collect_system_events(dock, dock_change));
         let mouse_dynamics = Some(self.collect_mouse_dynamics());
-        let network_activity_metadata = Some(self.enhance_network_metrics(&network_metrics));
+        let routing = self.routing.observe();
+        let wifi = self.wifi.observe();
+        let network_activity_metadata = Some(self.enhance_network_metrics(&network_metrics, media.as_deref(), routing, wifi));
+        let physiological_data = match &self.heart_rate {
+            Some(monitor) => monitor.summarize(chrono::Duration::seconds(10)).await,
+            None => None,
+        };
+        let collectors = self.collectors.collect_due();
+        let mut provenance = self.build_provenance();
+        if physiological_data.is_some() {
+            provenance.insert("physiological_data".to_string(), Provenance::Measured);
+        }
+        for name in collectors.keys() {
+            provenance.insert(format!("collectors.{}", name), Provenance::Measured);
+        }
+        if system_metrics.display_brightness.is_some() {
+            provenance.insert("system_metrics.display_brightness".to_string(), Provenance::Measured);
+        }
+        if system_metrics.ambient_light_lux.is_some() {
+            provenance.insert("system_metrics.ambient_light_lux".to_string(), Provenance::Measured);
+        }
+        if media_traffic_type(media.as_deref()).is_some() {
+            provenance.insert("network_activity_metadata.traffic_type".to_string(), Provenance::Measured);
+            provenance.insert("network_activity_metadata.activity_context".to_string(), Provenance::Measured);
+        }
+        if network_metrics.tcp_connections.is_some() {
+            provenance.insert("network_metrics.active_connections".to_string(), Provenance::Measured);
+            provenance.insert("network_metrics.tcp_connections".to_string(), Provenance::Measured);
+            provenance.insert("network_metrics.udp_sockets".to_string(), Provenance::Measured);
+        }
+        provenance.insert("network_activity_metadata.routing".to_string(), Provenance::Measured);
+        provenance.insert("network_activity_metadata.wifi".to_string(), Provenance::Measured);
+        if system_events.as_ref().is_some_and(|events| events.dock.is_some()) {
+            provenance.insert("system_events.dock".to_string(), Provenance::Measured);
+        }
+        if dock_change.is_some() {
+            provenance.insert("system_events.event_type".to_string(), Provenance::Measured);
+            provenance.insert("system_events.event_subtype".to_string(), Provenance::Measured);
+        }
+        provenance.insert("power".to_string(), Provenance::Measured);
+        if self.active_window.is_some() {
+            provenance.insert("focus_metrics.productive_app_time".to_string(), Provenance::Measured);
+        }
+        if focus_metrics.switching.is_some() {
+            provenance.insert("focus_metrics.context_switches".to_string(), Provenance::Measured);
+        }
+        if self.input_measured() {
+            provenance.insert("input_metrics".to_string(), Provenance::Measured);
+        }
+        if presence.idle_measured {
+            provenance.insert("input_metrics.idle_duration_seconds".to_string(), Provenance::Measured);
+        }
+        if keystroke_dynamics.is_some() {
+            if let Some(capture) = &self.keystroke_capture {
+                for field in [
+                    "keystroke_dynamics.typing_speed_wpm",
+                    "keystroke_dynamics.avg_key_hold_time_ms",
+                    "keystroke_dynamics.avg_key_interval_ms",
+                    "keystroke_dynamics.key_press_variance",
+                    "keystroke_dynamics.error_correction_rate",
+                    "keystroke_dynamics.total_keystrokes",
+                ] {
+                    provenance.insert(field.to_string(), Provenance::Measured);
+                }
+                if capture.records_text() {
+                    provenance.insert("keystroke_dynamics.typed_text".to_string(), Provenance::Measured);
+                }
+            }
+        }
+        if camera_data.as_ref().is_some_and(|camera| camera.gaze_point.is_some()) {
+            provenance.insert("camera_data.gaze_direction".to_string(), Provenance::Measured);
+            provenance.insert("camera_data.gaze_point".to_string(), Provenance::Measured);
+        }
+        if camera_data.as_ref().is_some_and(|camera| camera.emotion_backend.is_some() && !camera.presence_only) {
+            for field in [
+                "camera_data.facial_emotions",
+                "camera_data.dominant_emotion",
+                "camera_data.emotion_confidence",
+                "camera_data.face_detected",
+            ] {
+                provenance.insert(field.to_string(), Provenance::Measured);
+            }
+        }
+        if let Some(camera) = camera_data.as_ref().filter(|camera| camera.presence_only) {
+            if camera.emotion_backend.is_some() {
+                provenance.insert("camera_data.face_detected".to_string(), Provenance::Measured);
+            }
+            for field in [
+                "camera_data.facial_emotions",
+                "camera_data.dominant_emotion",
+                "camera_data.emotion_confidence",
+                "camera_data.gaze_direction",
+                "camera_data.gaze_point",
+                "camera_data.posture_score",
+            ] {
+                provenance.insert(field.to_string(), Provenance::Withheld);
+            }
+        }
+        if keystroke_dynamics.as_ref().is_some_and(|k| k.keyboard_layout.is_some()) {
+            provenance.insert("keystroke_dynamics.keyboard_layout".to_string(), Provenance::Measured);
+        }
         
-        let data = EyeCoreData {
+        let mut data = EyeCoreData {
             session_id: self.session_id.clone(),
             timestamp: now,
+            sequence: self.total_collections as u64 + 1,
             system_metrics,
             process_data,
+            top_processes,
             input_metrics,
             network_metrics,
             focus_metrics,
@@ -134,7 +716,26 @@ impl DataCollector {
             system_events,
             mouse_dynamics,
             network_activity_metadata,
+            physiological_data,
+            meeting: None,
+            burst: self.burst.clone(),
+            focus_context: Some(focus_context),
+            presence: Some(presence),
+            media,
+            power: Some(power),
+            provenance,
+            redactions: BTreeMap::new(),
+            comparisons: BTreeMap::new(),
+            collectors,
         };
+        if self.compare_collectors {
+            data.comparisons = compare_with_simulation(&data);
+        }
+        if let Some(detector) = &self.meeting_detector {
+            data.meeting = Some(detector.assess(&data).await);
+        }
+        // Before the snapshot is kept in history, uploaded or saved
+        self.redactor.redact_snapshot(&mut data);
         
         self.data_history.push_back(data);
         if self.data_history.len() > self.max_history {
@@ -146,16 +747,13 @@ impl DataCollector {
     }
 
     fn collect_system_metrics(&mut self) -> SystemMetrics {
-        let _sys = System::new_all();
-        
-        // Collect more granular data with trends
-        let cpu_usage = (rand::random::<f32>() * 100.0).min(100.0);
-        let memory_usage = (rand::random::<f32>() * 100.0).min(100.0);
-        let disk_usage = (rand::random::<f32>() * 100.0).min(100.0);
+        // CPU usage is the delta between this refresh and the previous one
+        let sample = self.system_sampler.sample();
+        self.record_sample("system_metrics");
         
         // Track history for trend analysis
-        self.cpu_history.push_back(cpu_usage);
-        self.memory_history.push_back(memory_usage);
+        self.cpu_history.push_back(sample.cpu_usage);
+        self.memory_history.push_back(sample.memory_usage);
         if self.cpu_history.len() > 10000 { self.cpu_history.pop_front(); }
         if self.memory_history.len() > 10000 { self.memory_history.pop_front(); }
         
@@ -163,84 +761,84 @@ impl DataCollector {
         let cpu_trend = self.calculate_trend(&self.cpu_history);
         let memory_trend = self.calculate_trend(&self.memory_history);
         
+        // The first snapshots go without readings until the background read finishes
+        let (display_brightness, ambient_light_lux) = self.display.readings();
+        
         SystemMetrics {
             timestamp: Utc::now(),
-            cpu_usage,
-            memory_usage,
-            disk_usage,
+            cpu_usage: sample.cpu_usage,
+            memory_usage: sample.memory_usage,
+            disk_usage: sample.disk_usage,
+            display_brightness,
+            ambient_light_lux,
+            per_core_cpu: sample.per_core_cpu,
+            disks: sample.disks,
         }
     }
 
-    fn collect_process_data(&mut self) -> ProcessData {
-        let mut _sys = System::new_all();
-        _sys.refresh_all();
-        
-        let process_count = _sys.processes().len();
-        
-        // Get active window info (Windows-specific)
-        #[cfg(target_os = "windows")]
-        let (active_process, active_window_title) = self.get_active_window_windows();
-        
-        #[cfg(not(target_os = "windows"))]
-        let (active_process, active_window_title) = (
-            "unknown".to_string(),
-            "unknown".to_string(),
-        );
+    fn collect_process_data(&mut self) -> (ProcessData, Vec<ProcessUsage>) {
+        let (process_count, top_processes) = self.processes.sample();
+        self.record_sample("process_resources");
+        
+        let window = self.active_window.as_mut().and_then(|provider| provider.active_window());
+        let classified = window.as_ref().map(|window| self.track_app_usage(&window.process, &window.title));
+        let project = window.as_ref().and_then(|window| self.projects.observe(&window.process, &window.title));
+        let (active_process, active_window_title) = match window {
+            Some(window) => (window.process, window.title),
+            None => ("unknown".to_string(), "unknown".to_string()),
+        };
         
-        ProcessData {
+        let process_data = ProcessData {
             timestamp: Utc::now(),
             active_process,
             active_window_title,
             process_count,
-        }
+            productivity: classified.as_ref().map(|classified| classified.productivity),
+            app: classified.map(|classified| classified.app),
+            project,
+        };
+        (process_data, top_processes)
     }
 
-    #[cfg(target_os = "windows")]
-    fn get_active_window_windows(&mut self) -> (String, String) {
-        use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW};
-        
-        unsafe {
-            let hwnd = GetForegroundWindow();
-            let mut title = vec![0; 256];
-            let len = GetWindowTextW(hwnd, &mut title);
-            
-            let title_str = String::from_utf16_lossy(&title[..len as usize]).to_string();
-            let pid_str = format!("{:?}", hwnd);
-            
-            // Track app usage history (for 10x more app tracking)
-            let app_name = if title_str.contains("Visual Studio") {
-                "Visual Studio".to_string()
-            } else if title_str.contains("PowerShell") {
-                "PowerShell".to_string()
-            } else if title_str.contains("Chrome") || title_str.contains("Chromium") {
-                "Chrome".to_string()
-            } else if title_str.contains("Firefox") {
-                "Firefox".to_string()
-            } else if title_str.contains("Word") {
-                "Word".to_string()
-            } else if title_str.contains("Excel") {
-                "Excel".to_string()
-            } else if title_str.contains("Slack") {
-                "Slack".to_string()
-            } else if title_str.contains("Discord") {
-                "Discord".to_string()
-            } else {
-                "Other".to_string()
-            };
-            
-            // Track app usage
-            self.app_usage_history.push_back((app_name.clone(), std::time::Instant::now()));
-            if self.app_usage_history.len() > 1000 { self.app_usage_history.pop_front(); }
-            
-            (pid_str, title_str)
-        }
+    /// Record the foreground app and its class for focus metrics
+    fn track_app_usage(&mut self, process: &str, title: &str) -> Classified {
+        let classified = self.classification.classify(process, title);
+        self.app_usage_history.push_back((classified.clone(), std::time::Instant::now()));
+        if self.app_usage_history.len() > 1000 { self.app_usage_history.pop_front(); }
+        classified
     }
 
+    /// Seconds spent in productive apps over `window`; None while the foreground window is not read
+    fn productive_app_time(&self, window: std::time::Duration) -> Option<u32> {
+        self.active_window.as_ref()?;
+        let now = std::time::Instant::now();
+        let seconds: u64 = self
+            .app_usage_history
+            .iter()
+            .zip(self.app_usage_history.iter().skip(1).map(|(_, at)| *at).chain([now]))
+            .filter(|((classified, at), _)| {
+                classified.productivity == Productivity::Productive && now.duration_since(*at) <= window
+            })
+            .map(|((_, at), next)| next.duration_since(*at))
+            .filter(|gap| *gap <= MAX_APP_SAMPLE_GAP)
+            .map(|gap| gap.as_secs())
+            .sum();
+        Some(seconds as u32)
+    }
+
+    /// True when input metrics come from the OS hooks rather than simulation
+    fn input_measured(&self) -> bool {
+        self.input_hooks.is_some_and(|hooks| hooks.available())
+    }
+    
     fn collect_input_metrics(&mut self) -> InputMetrics {
-        // Simulate input metrics for MVP
-        let mouse_clicks = rand::random::<u32>() % 10;
-        let keyboard_events = rand::random::<u32>() % 20;
-        let idle_duration = rand::random::<u32>() % 60;
+        let (mouse_clicks, keyboard_events, idle_duration) = match self.input_hooks.filter(|hooks| hooks.available()) {
+            Some(hooks) => {
+                let counts = hooks.take();
+                (counts.mouse_clicks, counts.keyboard_events, counts.idle_seconds)
+            }
+            None => simulated_input(),
+        };
         
         self.last_mouse_clicks = self.last_mouse_clicks.saturating_add(mouse_clicks);
         self.last_keyboard_events = self.last_keyboard_events.saturating_add(keyboard_events);
@@ -253,21 +851,101 @@ impl DataCollector {
         }
     }
 
-    fn collect_network_metrics(&self) -> NetworkMetrics {
-        // Simulate network metrics for MVP
-        let bytes_sent = rand::random::<u64>() % 1_000_000;
-        let bytes_received = rand::random::<u64>() % 5_000_000;
-        let connections = (rand::random::<usize>() % 50) + 1;
+    fn collect_network_metrics(&mut self) -> NetworkMetrics {
+        // Traffic counters are deltas since the previous refresh
+        self.networks.refresh();
+        let elapsed_secs = self.last_network_refresh.elapsed().as_secs_f64();
+        self.last_network_refresh = std::time::Instant::now();
+        self.record_sample("network_metrics");
+        
+        let rate = |bytes: u64| if elapsed_secs > 0.0 { bytes as f64 / elapsed_secs } else { 0.0 };
+        let mut interfaces: Vec<InterfaceTraffic> = self.networks
+            .iter()
+            .filter(|(_, data)| data.total_received() + data.total_transmitted() > 0)
+            .map(|(name, data)| InterfaceTraffic {
+                name: name.to_string(),
+                bytes_sent: data.transmitted(),
+                bytes_received: data.received(),
+                bytes_sent_per_sec: rate(data.transmitted()),
+                bytes_received_per_sec: rate(data.received()),
+            })
+            .collect();
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+        let bytes_sent = interfaces.iter().map(|interface| interface.bytes_sent).sum();
+        let bytes_received = interfaces.iter().map(|interface| interface.bytes_received).sum();
+        
+        let sockets = connections::count_sockets();
+        let connections = match sockets {
+            Some(sockets) => sockets.tcp + sockets.udp,
+            None => simulated_connections(),
+        };
         
         NetworkMetrics {
             timestamp: Utc::now(),
             bytes_sent,
             bytes_received,
             active_connections: connections,
+            bytes_sent_per_sec: rate(bytes_sent),
+            bytes_received_per_sec: rate(bytes_received),
+            tcp_connections: sockets.map(|sockets| sockets.tcp),
+            udp_sockets: sockets.map(|sockets| sockets.udp),
+            interfaces,
+        }
+    }
+    
+    fn record_sample(&mut self, collector: &'static str) {
+        *self.collector_samples.entry(collector).or_insert(0) += 1;
+    }
+    
+    /// Describe where each part of the snapshot came from, marking fields of
+    /// collectors that have not finished warming up
+    fn build_provenance(&self) -> BTreeMap<String, Provenance> {
+        let mut provenance = BTreeMap::new();
+        
+        for field in [
+            "system_metrics.cpu_usage",
+            "system_metrics.per_core_cpu",
+            "system_metrics.memory_usage",
+            "system_metrics.disk_usage",
+            "system_metrics.disks",
+            "network_metrics.bytes_sent",
+            "network_metrics.bytes_received",
+            "network_metrics.bytes_sent_per_sec",
+            "network_metrics.bytes_received_per_sec",
+            "network_metrics.interfaces",
+            "network_activity_metadata.bandwidth_usage_mbps",
+            "top_processes",
+        ] {
+            provenance.insert(field.to_string(), Provenance::Measured);
+        }
+        for field in [
+            "network_metrics.active_connections",
+            "input_metrics",
+            "focus_metrics",
+            "voice_data",
+            "camera_data",
+            "keystroke_dynamics",
+            "screen_interactions",
+            "file_metadata",
+            "system_events",
+            "mouse_dynamics",
+        ] {
+            provenance.insert(field.to_string(), Provenance::Simulated);
         }
+        
+        for requirement in WARM_UP_REQUIREMENTS {
+            let samples = self.collector_samples.get(requirement.collector).copied().unwrap_or(0);
+            if samples < requirement.samples {
+                for field in requirement.fields {
+                    provenance.insert(field.to_string(), Provenance::WarmingUp);
+                }
+            }
+        }
+        
+        provenance
     }
 
-    fn calculate_focus_metrics(&self) -> FocusMetrics {
+    fn calculate_focus_metrics(&self, media: Option<&[MediaPlayback]>) -> FocusMetrics {
         // Calculate focus based on activity patterns
         let focus_level = if self.total_collections > 0 {
             let activity = self.total_collections as f32 / 100.0;
@@ -276,18 +954,67 @@ impl DataCollector {
             0.5
         };
         
+        // Measured switching replaces the simulated count and lowers focus: frequent switches by up
+        // to MAX_SWITCH_PENALTY, each ping-pong run by a further PING_PONG_PENALTY
+        let switching = self.switches.cadence(CADENCE_WINDOW);
+        let (focus_level, context_switches) = match &switching {
+            Some(cadence) => {
+                let penalty = (cadence.switches_per_minute / SWITCH_RATE_SATURATION).min(1.0) * MAX_SWITCH_PENALTY
+                    + cadence.ping_pong.len() as f32 * PING_PONG_PENALTY;
+                ((focus_level - penalty).max(0.0), cadence.switches)
+            }
+            None => (focus_level, simulated_context_switches()),
+        };
+        // Video splits attention; music and calls do not count against focus
+        let watching = media.is_some_and(|media| media.iter().any(|playback| playback.kind == MediaKind::Video));
+        let focus_level = if watching { (focus_level - VIDEO_PENALTY).max(0.0) } else { focus_level };
+        let distracted = self
+            .app_usage_history
+            .back()
+            .is_some_and(|(classified, _)| classified.productivity == Productivity::Distracting);
+        let focus_level = if distracted { (focus_level - DISTRACTING_APP_PENALTY).max(0.0) } else { focus_level };
+        let productive_app_time = self
+            .productive_app_time(CADENCE_WINDOW.to_std().unwrap_or_default())
+            .unwrap_or_else(simulated_productive_app_time);
+        
         FocusMetrics {
             timestamp: Utc::now(),
             focus_level,
-            context_switches: rand::random::<u32>() % 20,
-            productive_app_time: rand::random::<u32>() % 600,
+            context_switches,
+            productive_app_time,
+            switching,
         }
     }
 
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
     pub fn get_latest_data(&self) -> Option<EyeCoreData> {
         self.data_history.back().cloned()
     }
 
+    /// Newest snapshot with its sequence number (1 = first collection since startup)
+    pub fn latest_with_sequence(&self) -> Option<(usize, &EyeCoreData)> {
+        self.data_history.back().map(|data| (self.total_collections, data))
+    }
+    
+    /// Up to `limit` snapshots older than sequence `before` (or the newest), newest first,
+    /// with their sequence numbers, and whether older snapshots remain
+    pub fn get_history_page(&self, before: Option<usize>, limit: usize) -> (Vec<(usize, &EyeCoreData)>, bool) {
+        let first_sequence = self.total_collections + 1 - self.data_history.len();
+        let mut older = self
+            .data_history
+            .iter()
+            .enumerate()
+            .map(|(i, data)| (first_sequence + i, data))
+            .rev()
+            .filter(|(sequence, _)| before.is_none_or(|before| *sequence < before));
+        let page: Vec<(usize, &EyeCoreData)> = older.by_ref().take(limit).collect();
+        let has_more = older.next().is_some();
+        (page, has_more)
+    }
+
     pub fn get_history(&self, limit: usize) -> Vec<EyeCoreData> {
         self.data_history
             .iter()
@@ -340,12 +1067,159 @@ impl DataCollector {
         }
     }
 
+    /// Feed wearable heart-rate readings into snapshots
+    pub fn attach_heart_rate(&mut self, monitor: Arc<HeartRateMonitor>) {
+        self.heart_rate = Some(monitor);
+    }
+    
+    /// Mark snapshots taken during calls
+    pub fn attach_meeting_detector(&mut self, detector: Arc<MeetingDetector>) {
+        self.meeting_detector = Some(detector);
+    }
+    
+    /// Hash Wi-Fi names with the device's salt and keep the user's network labels at `labels_path`
+    pub fn attach_network_labels(&mut self, salt: &str, labels_path: std::path::PathBuf) {
+        self.wifi = WifiMonitor::with_labels(salt, labels_path);
+    }
+    
+    /// Identify and classify apps with the user's rules and overrides kept in `device_dir`
+    pub fn attach_app_classification(&mut self, device_dir: &std::path::Path) {
+        self.classification = AppClassification::load(device_dir);
+    }
+    
+    pub fn app_classification(&self) -> AppClassifications {
+        self.classification.rules()
+    }
+    
+    /// Replace the user's app and site classes
+    pub fn set_app_classification(&mut self, overrides: BTreeMap<String, Productivity>) -> Result<AppClassifications, String> {
+        self.classification.set_overrides(overrides)
+    }
+    
+    /// Detect projects into, and keep the user's decisions in, `device_dir`
+    pub fn attach_projects(&mut self, device_dir: &std::path::Path) {
+        self.projects = ProjectDetector::load(device_dir);
+    }
+    
+    pub fn projects(&self) -> Projects {
+        self.projects.projects()
+    }
+    
+    pub fn has_project(&self, name: &str) -> bool {
+        self.projects.contains(name)
+    }
+    
+    /// Confirm a detected project, optionally renaming it or merging it into another
+    pub fn confirm_project(&mut self, project: &str, name: Option<String>) -> Result<Projects, String> {
+        self.projects.confirm(project, name)
+    }
+    
+    /// Forget a detected project and stop detecting it
+    pub fn reject_project(&mut self, project: &str) -> Result<Projects, String> {
+        self.projects.reject(project)
+    }
+    
+    /// Label the connected Wi-Fi network as home, office or public
+    pub fn label_network(&mut self, category: NetworkCategory) -> Result<WifiNetwork, String> {
+        self.wifi.label_current(category)
+    }
+    
+    /// Collection profile picked by the power source at the latest snapshot
+    pub fn collection_profile(&self) -> CollectionProfile {
+        self.power.profile()
+    }
+    
+    /// Annotation for the following collections; None once burst mode ends
+    pub fn set_burst(&mut self, burst: Option<BurstSample>) {
+        self.burst = burst;
+    }
+    
+    /// Shared log of microphone/camera stream open periods
+    pub fn capture_tracker(&self) -> Arc<CaptureTracker> {
+        Arc::clone(&self.capture_tracker)
+    }
+    
+    /// Which data categories are currently collected
+    pub fn enabled_modules(&self) -> BTreeMap<&'static str, bool> {
+        BTreeMap::from([
+            ("system_metrics", true),
+            ("process_data", true),
+            ("input_metrics", true),
+            ("network_metrics", true),
+            ("focus_metrics", true),
+            ("voice_data", self.voice_enabled),
+            ("camera_data", self.camera_enabled || self.camera_presence_enabled),
+            ("keystroke_dynamics", self.keystroke_enabled),
+            ("screen_interactions", true),
+            ("file_metadata", self.file_monitoring_enabled),
+            ("system_events", true),
+            ("mouse_dynamics", true),
+            ("network_activity_metadata", true),
+            ("physiological_data", self.heart_rate.is_some()),
+            ("focus_context", true),
+            ("notification_sources", self.notifications.available()),
+            ("window_switches", self.switches.available()),
+        ])
+    }
+    
+    /// Foreground app switches of the last `window` with their cadence
+    pub fn switch_report(&self, window: chrono::Duration) -> SwitchReport {
+        let mut switches = self.switches.recent(window);
+        switches.reverse();
+        SwitchReport {
+            available: self.switches.available(),
+            cadence: self.switches.cadence(window),
+            switches,
+        }
+    }
+
+    /// Per-app notification counts; the content of notifications is never read
+    pub fn notification_stats(&self) -> NotificationStats {
+        self.notifications.stats()
+    }
+    
+    /// Entries held by each in-memory buffer and cache, for the soak benchmark
+    pub fn buffer_lengths(&self) -> BTreeMap<&'static str, usize> {
+        BTreeMap::from([
+            ("data_history", self.data_history.len()),
+            ("mouse_positions", self.mouse_positions.len()),
+            ("keystroke_timings", self.keystroke_timings.len()),
+            ("app_usage_history", self.app_usage_history.len()),
+            ("screen_element_history", self.screen_element_history.len()),
+            ("voice_transcripts", self.voice_transcripts.len()),
+            ("keystroke_buffer", self.keystroke_buffer.len()),
+            ("button_click_history", self.button_click_history.len()),
+            ("window_content_cache", self.window_content_cache.len()),
+            ("rapid_mouse_events", self.rapid_mouse_events.len()),
+            ("cpu_history", self.cpu_history.len()),
+            ("memory_history", self.memory_history.len()),
+            ("focus_state_history", self.focus_state_history.len()),
+            ("file_access_cache", self._file_access_cache.len()),
+            ("collector_samples", self.collector_samples.len()),
+            ("sampling_intervals", self.sampling_intervals.len()),
+        ])
+    }
+    
+    pub fn history_capacity(&self) -> usize {
+        self.max_history
+    }
+    
+    /// Number of snapshots kept in memory
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.max_history = capacity;
+        while self.data_history.len() > capacity {
+            self.data_history.pop_front();
+        }
+    }
+    
     pub fn get_status(&self) -> CollectionStatus {
         CollectionStatus {
             is_running: true,
             uptime_seconds: self.start_time.elapsed().as_secs(),
             data_points_collected: self.total_collections,
             last_collection: Utc::now(),
+            update_available: false,
+            latest_version: None,
         }
     }
 
@@ -401,26 +1275,84 @@ impl DataCollector {
     }
 
     /// Collect camera data: facial emotions, gaze, posture
-    fn collect_camera_data(&self) -> CameraData {
-        let emotions = vec!["focused", "tired", "engaged", "confused", "happy"];
-        let facial_emotions: Vec<String> = emotions.iter()
-            .take(rand::random::<usize>() % 3 + 1)
-            .map(|s| s.to_string())
-            .collect();
-        let dominant = facial_emotions.first().cloned().unwrap_or("neutral".to_string());
+    fn collect_camera_data(&mut self) -> CameraData {
+        if !self.camera_enabled {
+            return self.collect_camera_presence();
+        }
+        let reading = self.emotion_detector.as_ref().and_then(|detector| detector.latest());
+        let gaze_point = reading
+            .as_ref()
+            .and_then(|reading| reading.gaze)
+            .zip(self.gaze_calibration.as_ref())
+            .map(|((u, v), calibration)| calibration.to_screen([u, v]));
+        if let Some((x, y)) = gaze_point {
+            self.gaze_points.push_back((x, y, std::time::Instant::now()));
+            if self.gaze_points.len() > 1000 { self.gaze_points.pop_front(); }
+        }
         
-        let gazes = vec!["center", "away", "down", "left", "right"];
-        let gaze = gazes[rand::random::<usize>() % gazes.len()].to_string();
+        let (facial_emotions, dominant, confidence, face_detected) = match &self.emotion_detector {
+            Some(_) => match reading {
+                Some(reading) => {
+                    let (dominant, confidence) = reading.dominant().unwrap_or(("neutral", 0.0));
+                    (reading.listed(), dominant.to_string(), confidence, reading.face_detected())
+                }
+                // No frame yet, or the camera could not be read
+                None => (Vec::new(), "neutral".to_string(), 0.0, false),
+            },
+            None => {
+                let emotions = vec!["focused", "tired", "engaged", "confused", "happy"];
+                let facial_emotions: Vec<String> = emotions.iter()
+                    .take(rand::random::<usize>() % 3 + 1)
+                    .map(|s| s.to_string())
+                    .collect();
+                let dominant = facial_emotions.first().cloned().unwrap_or("neutral".to_string());
+                (facial_emotions, dominant, rand::random::<f32>(), rand::random::<bool>())
+            }
+        };
+        
+        let gaze = match (gaze_point, &self.gaze_calibration) {
+            (Some(point), Some(calibration)) => calibration.direction(point).to_string(),
+            _ => {
+                let gazes = vec!["center", "away", "down", "left", "right"];
+                gazes[rand::random::<usize>() % gazes.len()].to_string()
+            }
+        };
         
         CameraData {
             timestamp: Utc::now(),
             facial_emotions,
             dominant_emotion: dominant,
-            emotion_confidence: rand::random::<f32>(),
+            emotion_confidence: confidence,
             gaze_direction: gaze,
-            face_detected: rand::random::<bool>(),
+            face_detected,
             posture_score: rand::random::<f32>(),
             enabled: self.camera_enabled,
+            emotion_backend: self.emotion_detector.as_ref().map(|detector| detector.backend().to_string()),
+            gaze_point: gaze_point.map(|(x, y)| [x, y]),
+            presence_only: false,
+        }
+    }
+
+    /// Presence-only mode: whether a face is in view, read on the device; nothing else is reported
+    fn collect_camera_presence(&self) -> CameraData {
+        // A cloud backend grabs no frames in this mode
+        let detector = self.emotion_detector.as_ref().filter(|detector| detector.on_device());
+        let face_detected = match detector {
+            Some(detector) => detector.latest().is_some_and(|reading| reading.face_detected()),
+            None => rand::random::<bool>(),
+        };
+        CameraData {
+            timestamp: Utc::now(),
+            facial_emotions: Vec::new(),
+            dominant_emotion: "unknown".to_string(),
+            emotion_confidence: 0.0,
+            gaze_direction: "unknown".to_string(),
+            face_detected,
+            posture_score: 0.0,
+            enabled: true,
+            emotion_backend: detector.map(|detector| detector.backend().to_string()),
+            gaze_point: None,
+            presence_only: true,
         }
     }
 
@@ -428,22 +1360,35 @@ impl DataCollector {
     /// ENHANCED: Now captures actual typed text and all button clicks
     fn collect_keystroke_dynamics(&mut self) -> KeystrokeDynamics {
         let now = std::time::Instant::now();
+        let captured = self.keystroke_capture.as_ref().map(|capture| capture.take());
         
-        // Record 50-200 keystroke events per collection (10x more)
-        for _ in 0..(rand::random::<usize>() % 150 + 50) {
-            self.keystroke_timings.push_back(now);
-        }
+        let (typing_speed, key_hold, key_interval, variance, error_rate) = match &captured {
+            Some(captured) => {
+                self.keystroke_timings.extend(&captured.presses);
+                measured_typing(captured)
+            }
+            None => {
+                // Record 50-200 keystroke events per collection (10x more)
+                for _ in 0..(rand::random::<usize>() % 150 + 50) {
+                    self.keystroke_timings.push_back(now);
+                }
+                
+                // Calculate typing patterns with 10x more detail
+                (
+                    (30.0 + rand::random::<f32>() * 150.0).min(180.0), // WPM - wider range
+                    20.0 + rand::random::<f32>() * 200.0,              // ms held - more varied
+                    10.0 + rand::random::<f32>() * 150.0,              // ms between keys
+                    rand::random::<f32>(),                             // 0-1 consistency
+                    rand::random::<f32>() * 0.5,                       // 0-50% error correction
+                )
+            }
+        };
         
         if self.keystroke_timings.len() > 10000 {
-            self.keystroke_timings.pop_front();
+            let excess = self.keystroke_timings.len() - 10000;
+            self.keystroke_timings.drain(..excess);
         }
         
-        // Calculate typing patterns with 10x more detail
-        let typing_speed = (30.0 + rand::random::<f32>() * 150.0).min(180.0); // WPM - wider range
-        let key_hold = 20.0 + rand::random::<f32>() * 200.0; // ms - more varied
-        let key_interval = 10.0 + rand::random::<f32>() * 150.0; // ms between keys
-        let variance = rand::random::<f32>(); // 0-1 consistency
-        let error_rate = rand::random::<f32>() * 0.5; // 0-50% error correction
         let stress = rand::random::<f32>(); // 0 = relaxed, 1 = stressed
         let fatigue = rand::random::<f32>(); // 0 = fresh, 1 = tired
         
@@ -452,10 +1397,13 @@ impl DataCollector {
         let has_pauses = key_interval > 100.0;
         
         // ENHANCED: Capture actual typed text for AI context
-        let typed_text = self.capture_typed_text();
+        let typed_text = match captured {
+            Some(captured) => captured.text.inspect(|text| self.remember_typed_text(text.clone())),
+            None => self.capture_typed_text(),
+        };
         
         // ENHANCED: Get all button clicks that occurred
-        let buttons_clicked = self.button_click_history.iter().cloned().collect();
+        let buttons_clicked = self.clicked_buttons();
         
         KeystrokeDynamics {
             timestamp: Utc::now(),
@@ -471,6 +1419,7 @@ impl DataCollector {
             typed_text,
             buttons_clicked,
             enabled: self.keystroke_enabled,
+            keyboard_layout: self.keyboard_layout.layout(),
         }
     }
 
@@ -555,12 +1504,21 @@ impl DataCollector {
     }
 
     /// Collect system power events: lock, unlock, sleep, wake, peripherals
-    fn collect_system_events(&mut self) -> SystemEvents {
+    fn collect_system_events(
+        &mut self,
+        dock: Option<DockState>,
+        dock_change: Option<(&'static str, &'static str)>,
+    ) -> SystemEvents {
         let event_types = vec!["lock", "unlock", "sleep", "wake", "peripheral_connect", "peripheral_disconnect"];
-        let event_type = event_types[rand::random::<usize>() % event_types.len()].to_string();
-        
         let subtypes = vec!["usb_device", "monitor", "keyboard", "mouse", "headphones", "dock"];
-        let subtype = subtypes[rand::random::<usize>() % subtypes.len()].to_string();
+        // A measured dock or lid change replaces the simulated event
+        let (event_type, subtype) = match dock_change {
+            Some((event_type, subtype)) => (event_type.to_string(), subtype.to_string()),
+            None => (
+                event_types[rand::random::<usize>() % event_types.len()].to_string(),
+                subtypes[rand::random::<usize>() % subtypes.len()].to_string(),
+            ),
+        };
         
         let now = Utc::now();
         let break_duration = if event_type == "lock" { rand::random::<u64>() % 3600 } else { 0 };
@@ -575,6 +1533,8 @@ impl DataCollector {
             break_duration_seconds: break_duration,
             active_session_duration_seconds: active_duration,
             daily_rhythm_score: rand::random::<f32>(),
+            capture_events: self.capture_tracker.drain(),
+            dock,
         }
     }
 
@@ -639,16 +1599,28 @@ impl DataCollector {
     }
 
     /// Enhanced network metrics with traffic type inference
-    fn enhance_network_metrics(&self, base_metrics: &NetworkMetrics) -> NetworkActivityMetadata {
-        let traffic_types = vec!["video_conference", "streaming", "download", "browsing", "gaming", "file_transfer"];
-        let traffic_type = traffic_types[rand::random::<usize>() % traffic_types.len()].to_string();
-        
-        let contexts = vec!["meeting", "entertainment", "research", "file_transfer", "updates", "gaming"];
-        let context = contexts[rand::random::<usize>() % contexts.len()].to_string();
+    fn enhance_network_metrics(
+        &self,
+        base_metrics: &NetworkMetrics,
+        media: Option<&[MediaPlayback]>,
+        routing: NetworkRouting,
+        wifi: Option<WifiNetwork>,
+    ) -> NetworkActivityMetadata {
+        let (traffic_type, context) = match media_traffic_type(media) {
+            Some((traffic_type, context)) => (traffic_type.to_string(), context.to_string()),
+            None => {
+                let traffic_types = vec!["video_conference", "streaming", "download", "browsing", "gaming", "file_transfer"];
+                let contexts = vec!["meeting", "entertainment", "research", "file_transfer", "updates", "gaming"];
+                (
+                    traffic_types[rand::random::<usize>() % traffic_types.len()].to_string(),
+                    contexts[rand::random::<usize>() % contexts.len()].to_string(),
+                )
+            }
+        };
         
-        // Infer bandwidth from byte counts (rough estimate)
-        let total_bytes = base_metrics.bytes_sent + base_metrics.bytes_received;
-        let bandwidth_mbps = (total_bytes as f32 / 1_000_000.0).min(1000.0);
+        // Bandwidth from the measured transfer rates
+        let bytes_per_sec = base_metrics.bytes_sent_per_sec + base_metrics.bytes_received_per_sec;
+        let bandwidth_mbps = (bytes_per_sec * 8.0 / 1_000_000.0) as f32;
         
         NetworkActivityMetadata {
             timestamp: Utc::now(),
@@ -661,16 +1633,18 @@ impl DataCollector {
             latency_avg_ms: 20.0 + rand::random::<f32>() * 80.0,
             packet_loss_rate: rand::random::<f32>() * 0.05,
             connection_stability: 0.8 + rand::random::<f32>() * 0.2,
+            routing: Some(routing),
+            wifi,
         }
     }
     
     /// NEW: Capture typed text from keyboard buffer
     fn capture_typed_text(&mut self) -> Option<String> {
-        if !self.keystroke_enabled {
+        if !self.keystroke_enabled || !self.typed_text_enabled {
             return None;
         }
         
-        // Simulate capturing typed text (in production, use keyboard hooks)
+        // Simulated unless keystroke capture is on
         let sample_texts = vec![
             "Working on the assignment",
             "Researching topic for essay",
@@ -685,17 +1659,32 @@ impl DataCollector {
         ];
         
         let text = sample_texts[rand::random::<usize>() % sample_texts.len()].to_string();
-        self.keystroke_buffer.push_back(text.clone());
+        self.remember_typed_text(text.clone());
+        
+        Some(text)
+    }
+    
+    /// Labels of the buttons clicked lately; content, so only with typed text consent
+    fn clicked_buttons(&self) -> Vec<ButtonClick> {
+        if !self.typed_text_enabled {
+            return Vec::new();
+        }
+        self.button_click_history.iter().cloned().collect()
+    }
+    
+    fn remember_typed_text(&mut self, text: String) {
+        self.keystroke_buffer.push_back(text);
         if self.keystroke_buffer.len() > 10000 {
             self.keystroke_buffer.pop_front();
         }
-        
-        Some(text)
     }
     
     /// NEW: Capture all visible window content with text and UI elements
     #[cfg(target_os = "windows")]
     fn capture_all_window_content(&mut self) -> Vec<WindowContent> {
+        if !self.screen_text_enabled {
+            return Vec::new();
+        }
         use windows::Win32::UI::WindowsAndMessaging::{
             EnumWindows, IsWindowVisible, GetWindowTextW,
         };
@@ -868,6 +1857,13 @@ impl DataCollector {
     
     /// NEW: Capture full screen text using OCR-like scanning
     fn capture_screen_text(&self) -> Option<String> {
+        if !self.screen_text_enabled {
+            return None;
+        }
+        // OCR is the costliest collector and is left out while saving power
+        if self.power.profile() == CollectionProfile::PowerSaving {
+            return None;
+        }
         // In production, use OCR library (tesseract-rs) to extract all visible text
         // For MVP, simulate comprehensive screen text capture
         let sample_screen_texts = vec![
@@ -935,7 +1931,7 @@ impl DataCollector {
             fatigue_indicator: keystroke_dynamics.fatigue_indicator,
             total_keystrokes: keystroke_dynamics.total_keystrokes as u64,  // Convert u32 to u64
             typed_text: self.collect_typed_text(),
-            buttons_clicked: self.button_click_history.iter().cloned().collect(),
+            buttons_clicked: self.clicked_buttons(),
             keystroke_sequence: self.generate_keystroke_sequence(),
             typing_patterns: self.generate_typing_patterns(),
             enabled: self.keystroke_enabled,
@@ -976,18 +1972,28 @@ impl DataCollector {
             workflow_stage: Some(self.infer_workflow_stage()),
         });
         
-        EnhancedScreenKeystrokeData {
+        let mut data = EnhancedScreenKeystrokeData {
             session_id: self.session_id.clone(),
             timestamp: now,
             enhanced_keystroke_data,
             enhanced_screen_data,
             context_metadata,
-        }
+            redactions: BTreeMap::new(),
+        };
+        self.redactor.redact_enhanced(&mut data);
+        data
     }
     
-    /// Collect actual typed text (simulated for privacy)
+    /// Typed text for the screen and keyboard record: the latest captured with keystroke capture,
+    /// simulated otherwise
     fn collect_typed_text(&self) -> Option<String> {
-        // In production with user consent: capture actual text from keystroke buffer
+        if !self.keystroke_enabled || !self.typed_text_enabled {
+            return None;
+        }
+        // Text from real keystrokes, once the keystroke module has collected some
+        if let Some(capture) = &self.keystroke_capture {
+            return capture.records_text().then(|| self.keystroke_buffer.back().cloned()).flatten();
+        }
         // For MVP: generate realistic sample text
         let sample_texts = vec![
             "Working on the final project report for CSE 101. Need to complete the methodology section by tonight.",
@@ -1101,20 +2107,14 @@ impl DataCollector {
         tasks[rand::random::<usize>() % tasks.len()].to_string()
     }
     
-    /// Calculate attention zones on screen
+    /// Attention zones on screen from the pointer and calibrated gaze of the last minute
     fn calculate_attention_zones(&self) -> Vec<AttentionZone> {
-        vec![
-            AttentionZone {
-                region: vec![100, 100, 800, 600],
-                attention_score: 0.8,
-                duration_seconds: 45.5,
-            },
-            AttentionZone {
-                region: vec![900, 200, 500, 400],
-                attention_score: 0.4,
-                duration_seconds: 12.3,
-            },
-        ]
+        const WINDOW: std::time::Duration = std::time::Duration::from_secs(60);
+        let recent = |points: &VecDeque<(f32, f32, std::time::Instant)>| -> Vec<(f32, f32)> {
+            points.iter().filter(|(_, _, at)| at.elapsed() <= WINDOW).map(|(x, y, _)| (*x, *y)).collect()
+        };
+        let screen = self.gaze_calibration.as_ref().map_or(gaze::DEFAULT_SCREEN, |calibration| calibration.screen());
+        gaze::attention_zones(&recent(&self.mouse_positions), &recent(&self.gaze_points), screen, WINDOW.as_secs_f32())
     }
     
     /// Infer workflow stage
@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use sysinfo::System;
+use tokio::sync::RwLock;
+
+/// DNS-SD service type advertised by every EyeCore instance
+pub const SERVICE_TYPE: &str = "_eyecore._tcp.local.";
+
+/// Another EyeCore instance seen on the LAN
+#[derive(Debug, Clone, Serialize)]
+pub struct Peer {
+    pub name: String,
+    pub device_id: Option<String>,
+    pub hostname: String,
+    pub addresses: Vec<String>,
+    pub port: u16,
+    pub version: Option<String>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Advertises the local API over mDNS and tracks other advertised instances
+pub struct Discovery {
+    _daemon: ServiceDaemon, // keeps the advertisement alive
+    peers: Arc<RwLock<HashMap<String, Peer>>>,
+}
+
+impl Discovery {
+    /// Register the API on `port` and start browsing for peers
+    pub fn start(device_id: &str, port: u16) -> Result<Self, String> {
+        let daemon = ServiceDaemon::new().map_err(|e| format!("mDNS daemon error: {}", e))?;
+
+        let host = System::host_name().unwrap_or_else(|| "eyecore".to_string());
+        let instance_name = format!("{}-{}", host, &device_id[..8.min(device_id.len())]);
+        let properties = [
+            ("device_id", device_id),
+            ("device_name", host.as_str()),
+            ("version", env!("CARGO_PKG_VERSION")),
+            ("path", "/status"),
+        ];
+
+        let service = ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance_name,
+            &format!("{}.local.", host),
+            "",
+            port,
+            &properties[..],
+        )
+        .map_err(|e| format!("Invalid mDNS service info: {}", e))?
+        .enable_addr_auto();
+
+        let own_fullname = service.get_fullname().to_string();
+        daemon
+            .register(service)
+            .map_err(|e| format!("mDNS registration failed: {}", e))?;
+
+        let receiver = daemon
+            .browse(SERVICE_TYPE)
+            .map_err(|e| format!("mDNS browse failed: {}", e))?;
+
+        let peers = Arc::new(RwLock::new(HashMap::new()));
+        let peers_clone = Arc::clone(&peers);
+        let own_name = own_fullname.clone();
+        tokio::spawn(async move {
+            while let Ok(event) = receiver.recv_async().await {
+                match event {
+                    ServiceEvent::ServiceResolved(info) if info.get_fullname() != own_name => {
+                        let peer = Peer {
+                            name: info.get_fullname().to_string(),
+                            device_id: info.get_property_val_str("device_id").map(|s| s.to_string()),
+                            hostname: info.get_hostname().to_string(),
+                            addresses: info.get_addresses().iter().map(|ip| ip.to_string()).collect(),
+                            port: info.get_port(),
+                            version: info.get_property_val_str("version").map(|s| s.to_string()),
+                            last_seen: Utc::now(),
+                        };
+                        info!("📡 Discovered EyeCore peer: {}", peer.name);
+                        peers_clone.write().await.insert(peer.name.clone(), peer);
+                    }
+                    ServiceEvent::ServiceRemoved(_, fullname) => {
+                        let removed = peers_clone.write().await.remove(&fullname);
+                        if removed.is_some() {
+                            info!("📡 EyeCore peer left: {}", fullname);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            warn!("mDNS browse stopped");
+        });
+
+        info!("✓ Advertising {} as {}", SERVICE_TYPE, own_fullname);
+
+        Ok(Discovery {
+            _daemon: daemon,
+            peers,
+        })
+    }
+
+    /// Currently known peers, excluding this instance
+    pub async fn peers(&self) -> Vec<Peer> {
+        let mut peers: Vec<Peer> = self.peers.read().await.values().cloned().collect();
+        peers.sort_by(|a, b| a.name.cmp(&b.name));
+        peers
+    }
+}
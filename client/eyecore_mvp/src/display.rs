@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Brightness and ambient light change slowly
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct CachedReadings {
+    readings: (Option<f32>, Option<f32>),
+    refreshed: Option<Instant>,
+    refreshing: bool,
+}
+
+/// Brightness and ambient light readings, re-read on a blocking thread at most once a minute.
+/// Reading them can mean starting PowerShell, which must not hold up collection.
+#[derive(Clone, Default)]
+pub struct DisplayCache {
+    inner: Arc<Mutex<CachedReadings>>,
+}
+
+impl DisplayCache {
+    /// Latest (brightness, ambient light) readings; starts a background refresh when they are stale
+    pub fn readings(&self) -> (Option<f32>, Option<f32>) {
+        let mut cached = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let stale = cached.refreshed.is_none_or(|refreshed| refreshed.elapsed() >= REFRESH_INTERVAL);
+        if stale && !cached.refreshing {
+            if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                cached.refreshing = true;
+                let inner = self.inner.clone();
+                runtime.spawn_blocking(move || {
+                    let readings = (read_brightness(), read_ambient_light());
+                    let mut cached = inner.lock().unwrap_or_else(|e| e.into_inner());
+                    cached.readings = readings;
+                    cached.refreshed = Some(Instant::now());
+                    cached.refreshing = false;
+                });
+            }
+        }
+        cached.readings
+    }
+}
+
+/// Current display brightness in percent (0-100)
+pub fn read_brightness() -> Option<f32> {
+    #[cfg(target_os = "windows")]
+    {
+        // WMI only reports brightness for panels with a software-controlled backlight (laptops)
+        let output = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "(Get-CimInstance -Namespace root/WMI -ClassName WmiMonitorBrightness | Select-Object -First 1).CurrentBrightness",
+            ])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse::<f32>().ok()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let device = std::fs::read_dir("/sys/class/backlight").ok()?.flatten().next()?.path();
+        let read = |name: &str| -> Option<f32> {
+            std::fs::read_to_string(device.join(name)).ok()?.trim().parse().ok()
+        };
+        let max = read("max_brightness")?;
+        if max <= 0.0 {
+            return None;
+        }
+        Some(read("actual_brightness").or_else(|| read("brightness"))? / max * 100.0)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+/// Ambient light in lux from an illuminance sensor
+pub fn read_ambient_light() -> Option<f32> {
+    #[cfg(target_os = "linux")]
+    {
+        // Industrial I/O ambient light sensors (common on laptops and tablets)
+        for device in std::fs::read_dir("/sys/bus/iio/devices").ok()?.flatten() {
+            let path = device.path();
+            if let Ok(value) = std::fs::read_to_string(path.join("in_illuminance_input")) {
+                if let Ok(lux) = value.trim().parse::<f32>() {
+                    return Some(lux);
+                }
+            }
+            let raw = std::fs::read_to_string(path.join("in_illuminance_raw")).ok();
+            if let Some(raw) = raw.and_then(|v| v.trim().parse::<f32>().ok()) {
+                let scale = std::fs::read_to_string(path.join("in_illuminance_scale"))
+                    .ok()
+                    .and_then(|v| v.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                return Some(raw * scale);
+            }
+        }
+        None
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readings_outside_a_runtime_stay_empty() {
+        let cache = DisplayCache::default();
+        assert_eq!(cache.readings(), (None, None));
+        assert!(!cache.inner.lock().unwrap().refreshing);
+    }
+
+    #[tokio::test]
+    async fn stale_readings_refresh_once_in_the_background() {
+        let cache = DisplayCache::default();
+        cache.readings();
+        assert!(cache.inner.lock().unwrap().refreshing);
+        // A second call while the read is running does not start another
+        cache.readings();
+
+        for _ in 0..100 {
+            if cache.inner.lock().unwrap().refreshed.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let cached = cache.inner.lock().unwrap();
+        assert!(cached.refreshed.is_some());
+        assert!(!cached.refreshing);
+    }
+}
@@ -0,0 +1,131 @@
+//! Laptop docking and lid state for `system_events.dock`: which displays are in use (the built-in panel,
+//! external monitors) and whether the lid is open, plus the dock/undock and lid changes between snapshots.
+
+use crate::models::DockState;
+
+/// Displays currently driven by the device
+struct DisplayTopology {
+    has_internal: bool,        // the device has a built-in panel, active or not
+    internal_active: bool,
+    external_active: u32,
+}
+
+pub struct DockingMonitor {
+    previous: Option<DockState>,
+}
+
+impl DockingMonitor {
+    pub fn new() -> Self {
+        DockingMonitor { previous: None }
+    }
+
+    /// Current state, and the (event_type, event_subtype) of a change since the previous call
+    pub fn observe(&mut self) -> (Option<DockState>, Option<(&'static str, &'static str)>) {
+        let Some(displays) = read_displays() else {
+            return (None, None);
+        };
+        let state = DockState {
+            docked: displays.external_active > 0,
+            lid_open: read_lid_open().or(displays.has_internal.then_some(displays.internal_active)),
+            internal_display_active: displays.internal_active,
+            external_displays: displays.external_active,
+        };
+
+        let change = self.previous.as_ref().and_then(|previous| {
+            if previous.docked != state.docked {
+                Some((if state.docked { "docked" } else { "undocked" }, "external_display"))
+            } else if previous.lid_open.is_some() && previous.lid_open != state.lid_open {
+                Some((if state.lid_open == Some(true) { "lid_open" } else { "lid_closed" }, "lid"))
+            } else {
+                None
+            }
+        });
+        self.previous = Some(state.clone());
+        (Some(state), change)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_displays() -> Option<DisplayTopology> {
+    use windows::Win32::Devices::Display::{
+        GetDisplayConfigBufferSizes, QueryDisplayConfig, DISPLAYCONFIG_MODE_INFO, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED,
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EMBEDDED, DISPLAYCONFIG_PATH_INFO,
+        QDC_ALL_PATHS,
+    };
+    // wingdi.h; the windows crate only exports it with the GDI bindings
+    const DISPLAYCONFIG_PATH_ACTIVE: u32 = 0x1;
+
+    let (mut path_count, mut mode_count) = (0u32, 0u32);
+    // All paths, not only active ones, so a closed laptop's panel still shows up as present
+    unsafe { GetDisplayConfigBufferSizes(QDC_ALL_PATHS, &mut path_count, &mut mode_count).ok()? };
+    let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+    let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+    unsafe {
+        QueryDisplayConfig(QDC_ALL_PATHS, &mut path_count, paths.as_mut_ptr(), &mut mode_count, modes.as_mut_ptr(), None).ok()?
+    };
+    paths.truncate(path_count as usize);
+
+    let internal = |path: &DISPLAYCONFIG_PATH_INFO| {
+        [DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EMBEDDED]
+            .contains(&path.targetInfo.outputTechnology)
+    };
+    let active = |path: &DISPLAYCONFIG_PATH_INFO| path.flags & DISPLAYCONFIG_PATH_ACTIVE != 0;
+    let mut external_targets: Vec<u32> = paths
+        .iter()
+        .filter(|path| active(path) && !internal(path))
+        .map(|path| path.targetInfo.id)
+        .collect();
+    external_targets.sort_unstable();
+    external_targets.dedup();
+
+    Some(DisplayTopology {
+        has_internal: paths.iter().any(|path| internal(path) && path.targetInfo.targetAvailable.as_bool()),
+        internal_active: paths.iter().any(|path| internal(path) && active(path)),
+        external_active: external_targets.len() as u32,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_displays() -> Option<DisplayTopology> {
+    // Connectors are /sys/class/drm/card0-eDP-1, card0-HDMI-A-1, ...; eDP/LVDS/DSI are built-in panels
+    let mut topology = DisplayTopology { has_internal: false, internal_active: false, external_active: 0 };
+    let mut connectors = 0;
+    for entry in std::fs::read_dir("/sys/class/drm").ok()?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some((_, connector)) = name.split_once('-') else {
+            continue;
+        };
+        connectors += 1;
+        let read = |file: &str| std::fs::read_to_string(entry.path().join(file)).map(|s| s.trim().to_string()).unwrap_or_default();
+        let internal = ["eDP", "LVDS", "DSI"].iter().any(|prefix| connector.starts_with(prefix));
+        let active = read("status") == "connected" && read("enabled") == "enabled";
+        if internal {
+            topology.has_internal = true;
+            topology.internal_active |= active;
+        } else if active {
+            topology.external_active += 1;
+        }
+    }
+    (connectors > 0).then_some(topology)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn read_displays() -> Option<DisplayTopology> {
+    None
+}
+
+/// Lid switch where the OS exposes it; otherwise the lid is inferred from the built-in panel
+fn read_lid_open() -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        // /proc/acpi/button/lid/LID0/state: "state:      open"
+        let lid = std::fs::read_dir("/proc/acpi/button/lid").ok()?.flatten().next()?;
+        let state = std::fs::read_to_string(lid.path().join("state")).ok()?;
+        Some(state.contains("open"))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
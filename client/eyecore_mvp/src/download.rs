@@ -0,0 +1,423 @@
+//! Bulk download of stored data (`/download/range?from=&to=`): every file of the days in the range,
+//! as a zstd-compressed tar laid out like the data directory (`<device>/<YYYY>/<MM>/<DD>/<category>/`).
+//! Files are packed as stored, so encrypted ones stay encrypted. The first download of a range is
+//! streamed while the archive is built, and the archive is kept in `<device>/downloads/`; later
+//! requests are served from there with HTTP range requests, so an interrupted transfer resumes where
+//! it stopped (`Range` with `If-Range`). It is built again when files in the range change, which
+//! also changes its ETag.
+
+use axum::body::{Body, Bytes};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::NaiveDate;
+use log::{info, warn};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{ErrorKind, Read, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+use crate::storage::DataStorage;
+
+/// Longest range one download may cover
+pub const MAX_DOWNLOAD_DAYS: i64 = 366;
+/// Built archives are removed once unused for this long
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const CACHE_DIR: &str = "downloads";
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Compressed chunks waiting for a slow client before the build waits too
+const STREAM_CHUNKS: usize = 16;
+
+/// The archive of a range, built or still to be built
+pub struct RangeArchive {
+    path: PathBuf,
+    etag: String,
+    filename: String,
+    pending: Option<Vec<Entry>>,   // the files to pack, until the archive has been built
+}
+
+struct Entry {
+    path: PathBuf,
+    name: String,    // path in the archive
+    size: u64,
+    modified: u64,   // seconds since the epoch
+}
+
+/// The archive of every file stored for the days `from` through `to`; None if there are none
+pub async fn range_archive(storage: &DataStorage, from: NaiveDate, to: NaiveDate) -> std::io::Result<Option<RangeArchive>> {
+    let dates: Vec<NaiveDate> = storage.list_dates().await?.into_iter().filter(|date| (from..=to).contains(date)).collect();
+    let device_dir = storage.device_dir();
+    let day_dirs: Vec<PathBuf> = dates.iter().map(|date| storage.day_dir(*date, "")).collect();
+    let filename = format!(
+        "eyecore_{}_{}_{}.tar.zst",
+        device_dir.file_name().and_then(|name| name.to_str()).unwrap_or("device"),
+        from.format("%Y-%m-%d"),
+        to.format("%Y-%m-%d")
+    );
+    tokio::task::spawn_blocking(move || {
+        let base = device_dir.parent().unwrap_or(&device_dir).to_path_buf();
+        let mut entries = Vec::new();
+        for dir in &day_dirs {
+            collect_files(dir, &base, &mut entries)?;
+        }
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // Named after what goes in, so unchanged days reuse the archive built before
+        let mut digest = Sha256::new();
+        for entry in &entries {
+            digest.update(format!("{}\0{}\0{}\n", entry.name, entry.size, entry.modified));
+        }
+        let etag = hex::encode(&digest.finalize()[..8]);
+        let cache = device_dir.join(CACHE_DIR);
+        std::fs::create_dir_all(&cache)?;
+        remove_stale(&cache);
+        let path = cache.join(format!("range_{}_{}_{}.tar.zst", from.format("%Y-%m-%d"), to.format("%Y-%m-%d"), etag));
+        let pending = (!path.exists()).then_some(entries);
+        Ok(Some(RangeArchive { path, etag: format!("\"{}\"", etag), filename, pending }))
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+/// Serve `archive`, or the part of it a `Range` header asks for. An archive not built yet is
+/// streamed as it is built, unless only part of it is asked for.
+pub async fn respond(mut archive: RangeArchive, headers: &HeaderMap) -> Response {
+    // A resumed download of an archive that has since been rebuilt starts over
+    let current = headers
+        .get(header::IF_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|tag| tag.trim() == archive.etag);
+    let requested = headers.get(header::RANGE).and_then(|value| value.to_str().ok()).filter(|_| current);
+
+    if let Some(entries) = archive.pending.take() {
+        if requested.is_none() {
+            return stream_build(archive, entries);
+        }
+        let path = archive.path.clone();
+        match tokio::task::spawn_blocking(move || build(&path, &entries, None)).await.map_err(std::io::Error::other) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) | Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+        }
+    }
+
+    let len = match tokio::fs::metadata(&archive.path).await {
+        Ok(metadata) => metadata.len(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+    };
+    let (status, start, end) = match requested.map(|range| parse_range(range, len)) {
+        None => (StatusCode::OK, 0, len),
+        Some(Some((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end),
+        Some(None) => {
+            let mut response = (StatusCode::RANGE_NOT_SATISFIABLE, Json(json!({"error": "range not satisfiable"}))).into_response();
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", len)) {
+                response.headers_mut().insert(header::CONTENT_RANGE, value);
+            }
+            return response;
+        }
+    };
+
+    let mut file = match tokio::fs::File::open(&archive.path).await {
+        Ok(file) => file,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response(),
+    };
+    if let Err(e) = tokio::io::AsyncSeekExt::seek(&mut file, SeekFrom::Start(start)).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response();
+    }
+    let body = Body::from_stream(futures_util::stream::unfold((file, end - start), |(mut file, remaining)| async move {
+        if remaining == 0 {
+            return None;
+        }
+        let mut chunk = vec![0u8; CHUNK_SIZE.min(remaining as usize)];
+        match tokio::io::AsyncReadExt::read(&mut file, &mut chunk).await {
+            Ok(0) => None,
+            Ok(read) => {
+                chunk.truncate(read);
+                Some((Ok::<_, std::io::Error>(Bytes::from(chunk)), (file, remaining - read as u64)))
+            }
+            Err(e) => Some((Err(e), (file, 0))),
+        }
+    }));
+
+    let mut response = (status, body).into_response();
+    set_headers(&mut response, &archive, Some(end - start));
+    if status == StatusCode::PARTIAL_CONTENT {
+        if let Ok(value) = HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end - 1, len)) {
+            response.headers_mut().insert(header::CONTENT_RANGE, value);
+        }
+    }
+    response
+}
+
+/// Send the archive while it is built. Its length is not known yet, so the response has none; the
+/// build carries on into the cache if the client goes away, for a resumed download to use.
+fn stream_build(archive: RangeArchive, entries: Vec<Entry>) -> Response {
+    let (sender, receiver) = mpsc::channel::<std::io::Result<Bytes>>(STREAM_CHUNKS);
+    let path = archive.path.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = build(&path, &entries, Some(sender.clone())) {
+            warn!("⚠️ Building download {:?} failed: {}", path, e);
+            // Ends the response with an error, so the client does not take it for complete
+            let _ = sender.blocking_send(Err(e));
+        }
+    });
+    let body = Body::from_stream(futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    }));
+    let mut response = (StatusCode::OK, body).into_response();
+    set_headers(&mut response, &archive, None);
+    response
+}
+
+fn set_headers(response: &mut Response, archive: &RangeArchive, length: Option<u64>) {
+    let headers = response.headers_mut();
+    let mut set = |name: header::HeaderName, value: String| {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    };
+    set(header::CONTENT_TYPE, "application/zstd".to_string());
+    set(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", archive.filename));
+    set(header::ACCEPT_RANGES, "bytes".to_string());
+    set(header::ETAG, archive.etag.clone());
+    if let Some(length) = length {
+        set(header::CONTENT_LENGTH, length.to_string());
+    }
+}
+
+/// First and one-past-last byte of a single `bytes=` range; None if it cannot be served
+fn parse_range(range: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    // Several ranges at once are not needed to resume
+    if spec.contains(',') {
+        return None;
+    }
+    let (first, last) = spec.split_once('-')?;
+    let (start, end) = match (first.trim(), last.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (len.saturating_sub(suffix), len)
+        }
+        (first, "") => (first.parse().ok()?, len),
+        (first, last) => (first.parse().ok()?, last.parse::<u64>().ok()?.saturating_add(1).min(len)),
+    };
+    (start < end && start < len).then_some((start, end))
+}
+
+fn collect_files(dir: &Path, base: &Path, entries: &mut Vec<Entry>) -> std::io::Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            collect_files(&path, base, entries)?;
+            continue;
+        }
+        let Some(name) = path.strip_prefix(base).ok().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let modified = metadata.modified().ok().and_then(|at| at.duration_since(UNIX_EPOCH).ok()).map_or(0, |at| at.as_secs());
+        entries.push(Entry { name: name.replace('\\', "/"), size: metadata.len(), modified, path });
+    }
+    Ok(())
+}
+
+fn remove_stale(cache: &Path) {
+    let Ok(entries) = std::fs::read_dir(cache) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| SystemTime::now().duration_since(modified).unwrap_or_default() > CACHE_TTL);
+        if stale {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Write the tar.zst to a temporary file next to `path`, then move it into place; with `stream`,
+/// every compressed chunk is also sent there as it is written
+fn build(path: &Path, entries: &[Entry], stream: Option<mpsc::Sender<std::io::Result<Bytes>>>) -> std::io::Result<()> {
+    let temporary = path.with_extension(format!("tmp-{}", uuid::Uuid::new_v4()));
+    let result = (|| {
+        let out = Tee { file: File::create(&temporary)?, stream };
+        let mut archive = tar::Builder::new(zstd::Encoder::new(out, crate::storage::ZSTD_LEVEL)?);
+        let mut packed = 0;
+        for entry in entries {
+            // Files can go between the listing and here, e.g. to retention
+            let file = match File::open(&entry.path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    warn!("Not downloading {:?}: it was removed while the archive was built", entry.path);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            archive.append_data(&mut file_header(entry.size, entry.modified), &entry.name, Exactly::new(file, entry.size))?;
+            packed += 1;
+        }
+        let out = archive.into_inner()?.finish()?;
+        out.file.sync_all()?;
+        info!("📦 Built download of {} files into {:?}", packed, path);
+        Ok(())
+    })();
+    match result {
+        Ok(()) => std::fs::rename(&temporary, path),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temporary);
+            Err(e)
+        }
+    }
+}
+
+/// Header of a regular file in the archive; `tar` fills in the name and checksum
+fn file_header(size: u64, modified: u64) -> tar::Header {
+    let mut header = tar::Header::new_ustar();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_mtime(modified);
+    header
+}
+
+/// Exactly `size` bytes of a file, as its header says: a file that grew since the listing is cut
+/// there, one that shrank padded with zeros
+struct Exactly {
+    file: std::io::Take<File>,
+    remaining: u64,
+}
+
+impl Exactly {
+    fn new(file: File, size: u64) -> Self {
+        Exactly { file: file.take(size), remaining: size }
+    }
+}
+
+impl Read for Exactly {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let limit = buf.len().min(self.remaining.min(usize::MAX as u64) as usize);
+        if limit == 0 {
+            return Ok(0);
+        }
+        let mut read = self.file.read(&mut buf[..limit])?;
+        if read == 0 {
+            buf[..limit].fill(0);
+            read = limit;
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Writes to the cached archive and, while the client is still there, to the response
+struct Tee {
+    file: File,
+    stream: Option<mpsc::Sender<std::io::Result<Bytes>>>,
+}
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write_all(buf)?;
+        if let Some(stream) = &self.stream {
+            if stream.blocking_send(Ok(Bytes::copy_from_slice(buf))).is_err() {
+                self.stream = None;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_resolve_to_start_and_end() {
+        let cases = [
+            ("bytes=0-99", Some((0, 100))),
+            ("bytes=100-", Some((100, 1000))),
+            ("bytes=-100", Some((900, 1000))),
+            ("bytes=-5000", Some((0, 1000))),
+            ("bytes=900-5000", Some((900, 1000))),
+            (" bytes=999-999 ", Some((999, 1000))),
+            ("bytes=1000-", None),
+            ("bytes=500-100", None),
+            ("bytes=0-1,5-9", None),
+            ("bytes=abc-", None),
+            ("bytes=-", None),
+            ("items=0-99", None),
+        ];
+        for (range, expected) in cases {
+            assert_eq!(parse_range(range, 1000), expected, "{:?}", range);
+        }
+    }
+
+    #[test]
+    fn file_headers_describe_a_regular_file() {
+        let header = file_header(1234, 1_700_000_000);
+        assert_eq!(header.entry_type(), tar::EntryType::Regular);
+        assert_eq!(header.size().unwrap(), 1234);
+        assert_eq!(header.mtime().unwrap(), 1_700_000_000);
+        assert_eq!(header.mode().unwrap(), 0o644);
+        assert!(header.as_ustar().is_some());
+    }
+
+    #[test]
+    fn archives_keep_their_listed_sizes_and_skip_vanished_files() {
+        let dir = std::env::temp_dir().join(format!("eyecore_download_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = |name: &str, content: &[u8], listed: u64| {
+            let path = dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            Entry { path, name: name.to_string(), size: listed, modified: 0 }
+        };
+        let long_name = format!("device/{}snapshot.json", "day/".repeat(40));
+        let mut entries = vec![
+            file("same.json", b"0123456789", 10),
+            file("grew.json", b"0123456789", 4),
+            file("shrank.json", b"0123", 8),
+            file("gone.json", b"x", 1),
+        ];
+        entries.push(Entry { name: long_name.clone(), ..file("long.json", b"long", 4) });
+        std::fs::remove_file(dir.join("gone.json")).unwrap();
+
+        let archive_path = dir.join("range.tar.zst");
+        build(&archive_path, &entries, None).unwrap();
+
+        let mut archive = tar::Archive::new(zstd::Decoder::new(File::open(&archive_path).unwrap()).unwrap());
+        let unpacked: Vec<(String, Vec<u8>)> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content).unwrap();
+                (entry.path().unwrap().to_string_lossy().to_string(), content)
+            })
+            .collect();
+        assert_eq!(
+            unpacked,
+            vec![
+                ("same.json".to_string(), b"0123456789".to_vec()),
+                ("grew.json".to_string(), b"0123".to_vec()),
+                ("shrank.json".to_string(), b"0123\0\0\0\0".to_vec()),
+                (long_name, b"long".to_vec()),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
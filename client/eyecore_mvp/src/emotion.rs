@@ -0,0 +1,472 @@
+//! Facial emotions for `camera_data` (`[emotion]` in eyecore.toml). A detector thread grabs a camera
+//! frame every `interval_secs` while the camera module has consent and is enabled, and hands it to
+//! the configured backend:
+//!
+//! - `simulated` (default): no camera access; the collector keeps its generated values.
+//! - `onnx`: a local facial-expression model run with ONNX Runtime (`--features emotion-onnx`).
+//!   Frames never leave the device. The default labels are those of the FER+ model (64x64
+//!   grayscale input, 8 scores).
+//! - `cloud`: the frame is posted as JPEG to `endpoint`, with EYECORE_EMOTION_API_KEY as bearer
+//!   token. The service answers `{"emotions": {"<label>": <score>, ...}}`, empty when it found no
+//!   face, and may add a raw gaze estimate as `"gaze": {"x": .., "y": ..}`. More accurate models,
+//!   at the cost of sending the user's face off the device, so it also needs `camera_cloud` consent.
+//!
+//! Raw gaze estimates are in the backend's own units; `gaze` maps them to the screen once the
+//! user has calibrated. Every frame grab is recorded in the capture log like any other camera use.
+//!
+//! In presence-only mode (consent for `camera_presence` but not `camera`) only whether a face was
+//! seen is kept: emotions and gaze are dropped as soon as the backend returns. Frames are only ever
+//! held in memory, and the cloud backend grabs none in this mode, since they would leave the device.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::capture_log::CaptureTracker;
+use crate::http::HttpClient;
+
+/// Emotions scoring below this are not listed in `facial_emotions`
+const MIN_LISTED_SCORE: f32 = 0.2;
+const MAX_LISTED: usize = 3;
+
+/// Where camera emotions come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmotionBackendKind {
+    Simulated,
+    Onnx,      // local model, frames stay on the device
+    Cloud,     // frames are sent to `endpoint`
+}
+
+/// `[emotion]` settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmotionConfig {
+    pub backend: EmotionBackendKind,
+    pub model_path: Option<String>,      // ONNX model file (onnx)
+    pub labels: Option<Vec<String>>,     // one per model output; FER+ labels if unset (onnx)
+    pub input_size: u32,                 // square grayscale input the model expects, in pixels (onnx)
+    pub endpoint: Option<String>,        // emotion service URL (cloud)
+    pub interval_secs: u64,              // between frames
+}
+
+impl Default for EmotionConfig {
+    fn default() -> Self {
+        EmotionConfig {
+            backend: EmotionBackendKind::Simulated,
+            model_path: None,
+            labels: None,
+            input_size: 64,
+            endpoint: None,
+            interval_secs: 30,
+        }
+    }
+}
+
+impl EmotionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interval_secs == 0 {
+            return Err("emotion.interval_secs must be at least 1".to_string());
+        }
+        match self.backend {
+            EmotionBackendKind::Simulated => {}
+            EmotionBackendKind::Onnx => {
+                if !cfg!(feature = "emotion-onnx") {
+                    return Err("emotion.backend = \"onnx\" requires a build with --features emotion-onnx".to_string());
+                }
+                if self.model_path.as_deref().is_none_or(str::is_empty) {
+                    return Err("emotion.backend = \"onnx\" requires emotion.model_path".to_string());
+                }
+                if self.input_size == 0 {
+                    return Err("emotion.input_size must be at least 1".to_string());
+                }
+                if self.labels.as_ref().is_some_and(Vec::is_empty) {
+                    return Err("emotion.labels must not be empty".to_string());
+                }
+            }
+            EmotionBackendKind::Cloud => {
+                let endpoint = self.endpoint.as_deref().unwrap_or_default();
+                let local = ["http://127.0.0.1", "http://localhost", "http://[::1]"].iter().any(|prefix| endpoint.starts_with(prefix));
+                // Frames are pictures of the user's face
+                if !endpoint.starts_with("https://") && !local {
+                    return Err("emotion.backend = \"cloud\" requires an https:// emotion.endpoint".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One camera frame
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub luma: Vec<u8>,           // grayscale, row by row
+    pub jpeg: Option<Vec<u8>>,   // as the camera sent it, when it sends MJPEG
+}
+
+/// What a backend saw in one frame
+#[derive(Debug, Clone, Default)]
+pub struct Detection {
+    pub emotions: Vec<(String, f32)>,   // score per emotion label; empty when there is no face
+    pub gaze: Option<(f32, f32)>,       // raw gaze estimate, when the backend makes one
+}
+
+/// Turns a camera frame into emotion scores
+pub trait EmotionBackend: Send {
+    fn name(&self) -> &'static str;
+
+    fn detect(&mut self, frame: &Frame) -> Result<Detection, String>;
+}
+
+/// Scores of the latest frame
+#[derive(Debug, Clone)]
+pub struct EmotionReading {
+    pub backend: &'static str,
+    pub scores: Vec<(String, f32)>,   // highest first; empty in presence-only mode
+    pub gaze: Option<(f32, f32)>,
+    face: bool,
+    taken: Instant,
+}
+
+impl EmotionReading {
+    fn new(backend: &'static str, detection: Detection) -> Self {
+        let mut scores = detection.emotions;
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        EmotionReading { backend, face: !scores.is_empty(), scores, gaze: detection.gaze, taken: Instant::now() }
+    }
+
+    /// Only whether a face was seen
+    fn presence(backend: &'static str, detection: Detection) -> Self {
+        EmotionReading { backend, scores: Vec::new(), gaze: None, face: !detection.emotions.is_empty(), taken: Instant::now() }
+    }
+
+    pub fn face_detected(&self) -> bool {
+        self.face
+    }
+
+    /// Emotions scoring at least `MIN_LISTED_SCORE`, highest first; at least the top one
+    pub fn listed(&self) -> Vec<String> {
+        self.scores
+            .iter()
+            .enumerate()
+            .take_while(|(rank, (_, score))| *rank == 0 || *score >= MIN_LISTED_SCORE)
+            .take(MAX_LISTED)
+            .map(|(_, (label, _))| label.clone())
+            .collect()
+    }
+
+    pub fn dominant(&self) -> Option<(&str, f32)> {
+        self.scores.first().map(|(label, score)| (label.as_str(), *score))
+    }
+}
+
+pub struct EmotionDetector {
+    backend: &'static str,
+    on_device: bool,
+    interval: Duration,
+    active: AtomicBool,
+    presence_only: AtomicBool,
+    latest: Mutex<Option<EmotionReading>>,
+    frame_requested: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl EmotionDetector {
+    pub fn backend(&self) -> &'static str {
+        self.backend
+    }
+
+    /// Grab frames only while active, i.e. while the camera module has consent and is enabled;
+    /// stopping forgets the last reading
+    pub fn set_active(&self, active: bool) {
+        if !self.active.swap(active, Ordering::Relaxed) || active {
+            return;
+        }
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = None;
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Keep only whether a face was seen; switching to it forgets the last reading
+    pub fn set_presence_only(&self, presence_only: bool) {
+        if self.presence_only.swap(presence_only, Ordering::Relaxed) || !presence_only {
+            return;
+        }
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = None;
+        }
+    }
+
+    pub fn is_presence_only(&self) -> bool {
+        self.presence_only.load(Ordering::Relaxed)
+    }
+
+    /// Whether frames are analysed on the device, so presence can be read in presence-only mode
+    pub fn on_device(&self) -> bool {
+        self.on_device
+    }
+
+    /// The latest reading, unless it is older than two frame intervals
+    pub fn latest(&self) -> Option<EmotionReading> {
+        let latest = self.latest.lock().ok()?;
+        latest.as_ref().filter(|reading| reading.taken.elapsed() <= self.interval * 2).cloned()
+    }
+
+    /// The latest reading if it was taken after `since`
+    pub fn latest_since(&self, since: Instant) -> Option<EmotionReading> {
+        let latest = self.latest.lock().ok()?;
+        latest.as_ref().filter(|reading| reading.taken > since).cloned()
+    }
+
+    /// Grab the next frame now instead of at the end of the interval (gaze calibration)
+    pub fn request_frame(&self) {
+        if let Ok(mut requested) = self.frame_requested.lock() {
+            *requested = true;
+            self.wake.notify_one();
+        }
+    }
+
+    /// Sleep until the next frame is due or requested
+    fn wait_for_frame(&self) {
+        let Ok(requested) = self.frame_requested.lock() else {
+            return std::thread::sleep(self.interval);
+        };
+        if let Ok((mut requested, _)) = self.wake.wait_timeout_while(requested, self.interval, |requested| !*requested) {
+            *requested = false;
+        }
+    }
+}
+
+/// Start the detector configured in `config`; None for the simulated backend
+pub fn start(config: &EmotionConfig, tracker: Arc<CaptureTracker>, http: HttpClient) -> Result<Option<Arc<EmotionDetector>>, String> {
+    let mut backend: Box<dyn EmotionBackend> = match config.backend {
+        EmotionBackendKind::Simulated => return Ok(None),
+        #[cfg(feature = "emotion-onnx")]
+        EmotionBackendKind::Onnx => Box::new(onnx::OnnxBackend::load(config)?),
+        #[cfg(not(feature = "emotion-onnx"))]
+        EmotionBackendKind::Onnx => return Err("built without --features emotion-onnx".to_string()),
+        EmotionBackendKind::Cloud => Box::new(CloudBackend::new(config, http)?),
+    };
+    let detector = Arc::new(EmotionDetector {
+        backend: backend.name(),
+        on_device: config.backend == EmotionBackendKind::Onnx,
+        interval: Duration::from_secs(config.interval_secs),
+        active: AtomicBool::new(false),
+        presence_only: AtomicBool::new(false),
+        latest: Mutex::new(None),
+        frame_requested: Mutex::new(false),
+        wake: Condvar::new(),
+    });
+    info!("🙂 Camera emotions from the {} backend, every {}s", detector.backend, config.interval_secs);
+
+    let worker = Arc::clone(&detector);
+    std::thread::spawn(move || {
+        let mut last_error = None;
+        loop {
+            worker.wait_for_frame();
+            if !worker.active.load(Ordering::Relaxed) || (worker.presence_only.load(Ordering::Relaxed) && !worker.on_device) {
+                continue;
+            }
+            let frame = {
+                let _camera_stream = tracker.open("camera", "emotion_detection");
+                camera::grab()
+            };
+            match frame.and_then(|frame| backend.detect(&frame)) {
+                Ok(detection) => {
+                    last_error = None;
+                    // The mode may have changed while the frame was analysed
+                    let reading = if worker.presence_only.load(Ordering::Relaxed) {
+                        EmotionReading::presence(worker.backend, detection)
+                    } else {
+                        EmotionReading::new(worker.backend, detection)
+                    };
+                    if let Ok(mut latest) = worker.latest.lock() {
+                        *latest = Some(reading);
+                    }
+                }
+                // Report each new failure once, not every frame
+                Err(e) if last_error.as_ref() != Some(&e) => {
+                    warn!("⚠️ Emotion detection failed: {}", e);
+                    last_error = Some(e);
+                }
+                Err(_) => {}
+            }
+        }
+    });
+    Ok(Some(detector))
+}
+
+/// Frames posted to an emotion service
+struct CloudBackend {
+    endpoint: String,
+    api_key: Option<String>,
+    http: HttpClient,
+    runtime: tokio::runtime::Handle,
+}
+
+#[derive(Deserialize)]
+struct CloudReply {
+    #[serde(default)]
+    emotions: std::collections::BTreeMap<String, f32>,
+    #[serde(default)]
+    gaze: Option<CloudGaze>,
+}
+
+#[derive(Deserialize)]
+struct CloudGaze {
+    x: f32,
+    y: f32,
+}
+
+impl CloudBackend {
+    /// Needs to be created on the runtime, which runs its requests
+    fn new(config: &EmotionConfig, http: HttpClient) -> Result<Self, String> {
+        Ok(CloudBackend {
+            endpoint: config.endpoint.clone().ok_or("emotion.endpoint is not set")?,
+            api_key: std::env::var("EYECORE_EMOTION_API_KEY").ok().filter(|key| !key.trim().is_empty()),
+            http,
+            runtime: tokio::runtime::Handle::try_current().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+impl EmotionBackend for CloudBackend {
+    fn name(&self) -> &'static str {
+        "cloud"
+    }
+
+    fn detect(&mut self, frame: &Frame) -> Result<Detection, String> {
+        let jpeg = frame.jpeg.clone().ok_or("the camera does not deliver MJPEG frames to send")?;
+        let mut request = self.http.post(&self.endpoint).header(reqwest::header::CONTENT_TYPE, "image/jpeg").body(jpeg);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        self.runtime.block_on(async {
+            let response = self.http.send(request).await?;
+            if !response.status().is_success() {
+                return Err(format!("emotion service returned {}", response.status()));
+            }
+            let reply: CloudReply = response.json().await.map_err(|e| format!("Invalid emotion service reply: {}", e))?;
+            Ok(Detection {
+                emotions: reply.emotions.into_iter().collect(),
+                gaze: reply.gaze.map(|gaze| (gaze.x, gaze.y)),
+            })
+        })
+    }
+}
+
+/// Local facial-expression model
+#[cfg(feature = "emotion-onnx")]
+mod onnx {
+    use super::{Detection, EmotionBackend, EmotionConfig, Frame};
+    use ort::session::Session;
+    use ort::value::Tensor;
+
+    /// Output order of the FER+ emotion model
+    const FERPLUS_LABELS: [&str; 8] = ["neutral", "happiness", "surprise", "sadness", "anger", "disgust", "fear", "contempt"];
+
+    pub struct OnnxBackend {
+        session: Session,
+        labels: Vec<String>,
+        size: u32,
+    }
+
+    impl OnnxBackend {
+        pub fn load(config: &EmotionConfig) -> Result<Self, String> {
+            let path = config.model_path.as_deref().ok_or("emotion.model_path is not set")?;
+            let session = Session::builder()
+                .and_then(|builder| builder.commit_from_file(path))
+                .map_err(|e| format!("Cannot load emotion model {:?}: {}", path, e))?;
+            Ok(OnnxBackend {
+                session,
+                labels: config
+                    .labels
+                    .clone()
+                    .unwrap_or_else(|| FERPLUS_LABELS.iter().map(|label| label.to_string()).collect()),
+                size: config.input_size,
+            })
+        }
+    }
+
+    impl EmotionBackend for OnnxBackend {
+        fn name(&self) -> &'static str {
+            "onnx"
+        }
+
+        fn detect(&mut self, frame: &Frame) -> Result<Detection, String> {
+            let size = self.size as usize;
+            let input = Tensor::from_array(([1, 1, size, size], super::square(frame, self.size))).map_err(|e| e.to_string())?;
+            let outputs = self.session.run(ort::inputs![input].map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+            let (_, logits) = outputs[0].try_extract_raw_tensor::<f32>().map_err(|e| e.to_string())?;
+            if logits.len() != self.labels.len() {
+                return Err(format!("the model has {} outputs for {} labels", logits.len(), self.labels.len()));
+            }
+            Ok(Detection {
+                emotions: self.labels.iter().cloned().zip(super::softmax(logits)).collect(),
+                gaze: None,
+            })
+        }
+    }
+}
+
+/// The centre square of `frame`, scaled to `size` x `size` pixel values (0-255)
+#[cfg_attr(not(feature = "emotion-onnx"), allow(dead_code))]
+fn square(frame: &Frame, size: u32) -> Vec<f32> {
+    let side = frame.width.min(frame.height).max(1);
+    let (left, top) = ((frame.width - side.min(frame.width)) / 2, (frame.height - side.min(frame.height)) / 2);
+    let mut pixels = Vec::with_capacity((size * size) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let source_x = left + x * side / size;
+            let source_y = top + y * side / size;
+            let index = (source_y * frame.width + source_x) as usize;
+            pixels.push(frame.luma.get(index).copied().unwrap_or(0) as f32);
+        }
+    }
+    pixels
+}
+
+#[cfg_attr(not(feature = "emotion-onnx"), allow(dead_code))]
+fn softmax(logits: &[f32]) -> Vec<f32> {
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = logits.iter().map(|logit| (logit - max).exp()).collect();
+    let sum: f32 = exps.iter().sum();
+    exps.into_iter().map(|exp| exp / sum).collect()
+}
+
+/// One frame from the default camera, opened for just that frame
+mod camera {
+    use super::Frame;
+    use nokhwa::pixel_format::LumaFormat;
+    use nokhwa::utils::{CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType, Resolution};
+    use nokhwa::Camera;
+
+    pub fn grab() -> Result<Frame, String> {
+        let format = RequestedFormat::new::<LumaFormat>(RequestedFormatType::Closest(CameraFormat::new(
+            Resolution::new(640, 480),
+            FrameFormat::MJPEG,
+            30,
+        )));
+        let mut camera = Camera::new(CameraIndex::Index(0), format).map_err(|e| format!("Camera unavailable: {}", e))?;
+        camera.open_stream().map_err(|e| format!("Camera unavailable: {}", e))?;
+        let buffer = camera.frame().map_err(|e| format!("No camera frame: {}", e));
+        let _ = camera.stop_stream();
+        let buffer = buffer?;
+
+        let jpeg = (buffer.source_frame_format() == FrameFormat::MJPEG).then(|| buffer.buffer().to_vec());
+        let image = buffer.decode_image::<LumaFormat>().map_err(|e| format!("Undecodable camera frame: {}", e))?;
+        Ok(Frame {
+            width: image.width(),
+            height: image.height(),
+            luma: image.into_raw(),
+            jpeg,
+        })
+    }
+}
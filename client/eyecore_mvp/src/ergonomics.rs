@@ -0,0 +1,231 @@
+use chrono::{DateTime, Local, Timelike, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::models::{DockState, EyeCoreData};
+
+// Evening glare: bright screen in a dark room late in the day
+const EVENING_START_HOUR: u32 = 19;
+const EVENING_END_HOUR: u32 = 6;
+const HIGH_BRIGHTNESS_PERCENT: f32 = 70.0;
+const LOW_AMBIENT_LUX: f32 = 50.0;
+const MIN_SESSION_MINUTES: i64 = 30;
+const MAX_SAMPLE_GAP_SECS: i64 = 60; // longer pauses end a session
+
+/// A prolonged stretch of evening glare
+#[derive(Debug, Clone, Serialize)]
+pub struct ErgonomicsFinding {
+    pub rule: &'static str,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_minutes: i64,
+    pub avg_brightness: f32,
+    pub avg_ambient_lux: f32,
+}
+
+/// Bright display in low ambient light during local evening hours
+fn is_evening_glare(data: &EyeCoreData) -> Option<(f32, f32)> {
+    let brightness = data.system_metrics.display_brightness?;
+    let lux = data.system_metrics.ambient_light_lux?;
+    let hour = data.timestamp.with_timezone(&Local).hour();
+    let evening = !(EVENING_END_HOUR..EVENING_START_HOUR).contains(&hour);
+
+    (evening && brightness >= HIGH_BRIGHTNESS_PERCENT && lux <= LOW_AMBIENT_LUX).then_some((brightness, lux))
+}
+
+/// Tracks the current glare session as snapshots arrive
+#[derive(Default)]
+struct GlareSession {
+    start: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+    brightness_sum: f32,
+    lux_sum: f32,
+    samples: u32,
+}
+
+impl GlareSession {
+    /// Add a snapshot; returns the finished session if this snapshot ended one
+    fn observe(&mut self, data: &EyeCoreData) -> Option<ErgonomicsFinding> {
+        let continues = self
+            .last
+            .is_some_and(|last| (data.timestamp - last).num_seconds() <= MAX_SAMPLE_GAP_SECS);
+
+        match is_evening_glare(data) {
+            Some((brightness, lux)) => {
+                let finished = if continues { None } else { self.finish() };
+                self.start.get_or_insert(data.timestamp);
+                self.last = Some(data.timestamp);
+                self.brightness_sum += brightness;
+                self.lux_sum += lux;
+                self.samples += 1;
+                finished
+            }
+            None => self.finish(),
+        }
+    }
+
+    fn duration_minutes(&self) -> i64 {
+        match (self.start, self.last) {
+            (Some(start), Some(last)) => (last - start).num_minutes(),
+            _ => 0,
+        }
+    }
+
+    fn current(&self) -> Option<ErgonomicsFinding> {
+        Some(ErgonomicsFinding {
+            rule: "evening_glare",
+            start: self.start?,
+            end: self.last?,
+            duration_minutes: self.duration_minutes(),
+            avg_brightness: self.brightness_sum / self.samples.max(1) as f32,
+            avg_ambient_lux: self.lux_sum / self.samples.max(1) as f32,
+        })
+    }
+
+    /// Close the session, keeping it only if it lasted long enough
+    fn finish(&mut self) -> Option<ErgonomicsFinding> {
+        let finding = self.current().filter(|f| f.duration_minutes >= MIN_SESSION_MINUTES);
+        *self = GlareSession::default();
+        finding
+    }
+}
+
+/// All prolonged evening-glare sessions in a chronological run of snapshots
+pub fn evening_glare_sessions(snapshots: &[EyeCoreData]) -> Vec<ErgonomicsFinding> {
+    let mut session = GlareSession::default();
+    let mut findings: Vec<ErgonomicsFinding> = snapshots.iter().filter_map(|d| session.observe(d)).collect();
+    findings.extend(session.finish());
+    findings
+}
+
+/// Time spent in one display setup, with the average posture seen in it
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplaySetupSummary {
+    pub setup: &'static str,
+    pub minutes: i64,
+    pub snapshots: usize,
+    pub avg_posture_score: Option<f32>, // from snapshots with camera data
+}
+
+/// Docked-time breakdown for a day: a laptop screen alone keeps the head tilted down, so posture is
+/// compared between docked (external display) and undocked time
+#[derive(Debug, Clone, Serialize)]
+pub struct DockingSummary {
+    pub docked_minutes: i64,
+    pub undocked_minutes: i64,
+    pub avg_posture_docked: Option<f32>,
+    pub avg_posture_undocked: Option<f32>,
+    pub setups: Vec<DisplaySetupSummary>,
+}
+
+/// "laptop_only", "external_only" (lid closed, or a desktop) or "internal_and_external"; None with
+/// every display off
+fn display_setup(dock: &DockState) -> Option<&'static str> {
+    match (dock.internal_display_active, dock.external_displays > 0) {
+        (true, false) => Some("laptop_only"),
+        (false, true) => Some("external_only"),
+        (true, true) => Some("internal_and_external"),
+        (false, false) => None,
+    }
+}
+
+/// Seconds, snapshots and posture samples collected for one display setup
+#[derive(Default)]
+struct SetupTotals {
+    seconds: i64,
+    snapshots: usize,
+    posture_sum: f32,
+    posture_samples: usize,
+}
+
+impl SetupTotals {
+    fn add(&mut self, other: &SetupTotals) {
+        self.seconds += other.seconds;
+        self.snapshots += other.snapshots;
+        self.posture_sum += other.posture_sum;
+        self.posture_samples += other.posture_samples;
+    }
+
+    fn avg_posture(&self) -> Option<f32> {
+        (self.posture_samples > 0).then(|| self.posture_sum / self.posture_samples as f32)
+    }
+}
+
+/// Split a chronological run of snapshots by display setup. Each snapshot counts the time until the
+/// next one, unless they are more than MAX_SAMPLE_GAP_SECS apart.
+pub fn docking_summary(snapshots: &[EyeCoreData]) -> DockingSummary {
+    let mut totals: BTreeMap<&'static str, SetupTotals> = BTreeMap::new();
+    for (i, data) in snapshots.iter().enumerate() {
+        let Some(setup) = data.system_events.as_ref().and_then(|e| e.dock.as_ref()).and_then(display_setup) else {
+            continue;
+        };
+        let entry = totals.entry(setup).or_default();
+        entry.seconds += snapshots
+            .get(i + 1)
+            .map(|next| (next.timestamp - data.timestamp).num_seconds())
+            .filter(|gap| (0..=MAX_SAMPLE_GAP_SECS).contains(gap))
+            .unwrap_or(0);
+        entry.snapshots += 1;
+        if let Some(camera) = data.camera_data.as_ref().filter(|c| c.enabled && c.face_detected) {
+            entry.posture_sum += camera.posture_score;
+            entry.posture_samples += 1;
+        }
+    }
+
+    let (mut docked, mut undocked) = (SetupTotals::default(), SetupTotals::default());
+    for (setup, setup_totals) in &totals {
+        if *setup == "laptop_only" {
+            undocked.add(setup_totals);
+        } else {
+            docked.add(setup_totals);
+        }
+    }
+    DockingSummary {
+        docked_minutes: docked.seconds / 60,
+        undocked_minutes: undocked.seconds / 60,
+        avg_posture_docked: docked.avg_posture(),
+        avg_posture_undocked: undocked.avg_posture(),
+        setups: totals
+            .iter()
+            .map(|(setup, setup_totals)| DisplaySetupSummary {
+                setup,
+                minutes: setup_totals.seconds / 60,
+                snapshots: setup_totals.snapshots,
+                avg_posture_score: setup_totals.avg_posture(),
+            })
+            .collect(),
+    }
+}
+
+/// Live version of the rule: raises one anomaly per session once it passes the threshold
+#[derive(Default)]
+pub struct EveningGlareMonitor {
+    session: GlareSession,
+    flagged: bool,
+}
+
+impl EveningGlareMonitor {
+    pub fn observe(&mut self, data: &EyeCoreData) -> Option<serde_json::Value> {
+        let previous_start = self.session.start;
+        self.session.observe(data);
+        if self.session.start != previous_start {
+            self.flagged = false;
+        }
+
+        if self.flagged || self.session.duration_minutes() < MIN_SESSION_MINUTES {
+            return None;
+        }
+        self.flagged = true;
+
+        let finding = self.session.current()?;
+        Some(json!({
+            "type": "evening_glare",
+            "value": finding.avg_brightness,
+            "threshold": HIGH_BRIGHTNESS_PERCENT,
+            "ambient_light_lux": finding.avg_ambient_lux,
+            "duration_minutes": finding.duration_minutes,
+            "timestamp": data.timestamp,
+        }))
+    }
+}
@@ -0,0 +1,305 @@
+//! C ABI for embedding the collection engine in another desktop application (for example an exam
+//! proctoring client) instead of running `eyecore_mvp` next to it. Built with `--features ffi` as
+//! `eyecore.dll` / `libeyecore.so`; the declarations are in `include/eyecore.h`.
+//!
+//! Only the collectors run: no API server, storage, uploads or logger. The host polls snapshots as
+//! JSON (the same shape as `/data/latest`) and decides what to keep. Privacy-sensitive modules
+//! follow the consent recorded in `<data_dir>/<device>/consent.json`, which the host changes with
+//! `eyecore_enable_module` and `eyecore_disable_module`.
+#![cfg(feature = "ffi")]
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use crate::consent::{self, ConsentManager};
+use crate::data_collector::{DataCollector, WARM_UP_INTERVAL};
+use crate::{config, meeting, models, schedule, storage};
+
+/// Bumped whenever a declaration in eyecore.h changes; hosts check it after loading the library
+pub const EYECORE_ABI_VERSION: u32 = 2;
+
+pub const EYECORE_OK: i32 = 0;
+pub const EYECORE_ERR_NULL: i32 = -1;
+pub const EYECORE_ERR_RUNNING: i32 = -2;
+pub const EYECORE_ERR_NOT_RUNNING: i32 = -3;
+pub const EYECORE_ERR_INVALID_ARGUMENT: i32 = -4;
+pub const EYECORE_ERR_PANIC: i32 = -5;
+pub const EYECORE_ERR_IO: i32 = -6;
+
+/// One embedded collector with its own runtime; opaque to C
+pub struct EyeCore {
+    runtime: tokio::runtime::Runtime,
+    collector: Arc<RwLock<DataCollector>>,
+    consent: ConsentManager,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl EyeCore {
+    fn new() -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("eyecore")
+            .enable_all()
+            .build()?;
+
+        // Consent lives where the service keeps it, so the host and the service agree on it
+        let config = config::load().map_err(std::io::Error::other)?.config;
+        let device_id = match &config.device_id {
+            Some(device_id) => device_id.clone(),
+            None => runtime.block_on(storage::load_or_create_device_id(&config.data_dir))?,
+        };
+        let consent = ConsentManager::load(&Path::new(&config.data_dir).join(device_id));
+
+        let mut collector = DataCollector::new();
+        consent.apply(&mut collector);
+        config.modules.apply(&mut collector);
+        let calendar_path = std::env::var("EYECORE_CALENDAR_ICS").ok().map(PathBuf::from);
+        collector.attach_meeting_detector(Arc::new(meeting::MeetingDetector::new(calendar_path)));
+
+        Ok(EyeCore {
+            runtime,
+            collector: Arc::new(RwLock::new(collector)),
+            consent,
+            task: None,
+        })
+    }
+
+    /// Record consent for `module` and switch it on or off
+    fn set_consent(&mut self, module: &str, granted: bool) -> i32 {
+        if !consent::is_module(module) {
+            return EYECORE_ERR_INVALID_ARGUMENT;
+        }
+        let mut collector = self.runtime.block_on(self.collector.write());
+        match self.consent.set(module, granted, "ffi", None, &mut collector) {
+            Ok(_) => EYECORE_OK,
+            Err(_) => EYECORE_ERR_IO,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.task.as_ref().is_some_and(|task| !task.is_finished())
+    }
+
+    fn start(&mut self, interval: Duration) -> i32 {
+        if self.is_running() {
+            return EYECORE_ERR_RUNNING;
+        }
+        let collector = Arc::clone(&self.collector);
+        self.task = Some(self.runtime.spawn(async move {
+            // Same phase offset and jitter settings (EYECORE_PHASE_OFFSET, EYECORE_JITTER_MS) as the binary
+            let mut schedule = schedule::ScheduleConfig::from_env().schedule("snapshot", interval);
+            loop {
+                schedule.tick().await;
+                collector.write().await.collect_all().await;
+            }
+        }));
+        EYECORE_OK
+    }
+
+    fn stop(&mut self) -> i32 {
+        let Some(task) = self.task.take() else {
+            return EYECORE_ERR_NOT_RUNNING;
+        };
+        task.abort();
+        // Wait for the abort, so no collection is running once this returns
+        let _ = self.runtime.block_on(task);
+        EYECORE_OK
+    }
+
+    /// One collection outside the schedule; the first one also runs a warm-up pass, as `collect --once` does
+    fn collect_once(&self) -> Option<models::EyeCoreData> {
+        self.runtime.block_on(async {
+            let primed = {
+                let mut collector = self.collector.write().await;
+                if collector.get_latest_data().is_some() {
+                    true
+                } else {
+                    collector.collect_all().await;
+                    false
+                }
+            };
+            if !primed {
+                tokio::time::sleep(WARM_UP_INTERVAL).await;
+            }
+            let mut collector = self.collector.write().await;
+            collector.collect_all().await;
+            collector.get_latest_data()
+        })
+    }
+
+    fn latest(&self) -> Option<models::EyeCoreData> {
+        self.runtime.block_on(async { self.collector.read().await.get_latest_data() })
+    }
+}
+
+/// `module` as a Rust string, None for NULL or invalid UTF-8
+///
+/// # Safety
+/// `module` must be NULL or a NUL-terminated string.
+unsafe fn module_name<'a>(module: *const c_char) -> Option<&'a str> {
+    if module.is_null() {
+        return None;
+    }
+    CStr::from_ptr(module).to_str().ok()
+}
+
+/// Run `f`, turning a panic into `fallback`; unwinding into C is undefined behaviour
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// Serialize a snapshot into a string the host frees with `eyecore_string_free`
+fn into_c_json(data: Option<models::EyeCoreData>) -> *mut c_char {
+    data.and_then(|data| serde_json::to_string(&data).ok())
+        .and_then(|json| CString::new(json).ok())
+        .map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+#[no_mangle]
+pub extern "C" fn eyecore_abi_version() -> u32 {
+    EYECORE_ABI_VERSION
+}
+
+/// Create a collector (not yet collecting) with the privacy-sensitive modules that have recorded
+/// consent. Returns NULL if its runtime cannot be started or the configuration is broken.
+#[no_mangle]
+pub extern "C" fn eyecore_create() -> *mut EyeCore {
+    guard(std::ptr::null_mut(), || match EyeCore::new() {
+        Ok(core) => Box::into_raw(Box::new(core)),
+        Err(_) => std::ptr::null_mut(),
+    })
+}
+
+/// Stop collecting and free the collector. NULL is ignored.
+///
+/// # Safety
+/// `core` must come from `eyecore_create` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn eyecore_destroy(core: *mut EyeCore) {
+    if core.is_null() {
+        return;
+    }
+    let mut core = Box::from_raw(core);
+    guard((), || {
+        core.stop();
+    });
+    drop(core);
+}
+
+/// Collect every `interval_ms` milliseconds on a background thread until `eyecore_stop`
+///
+/// # Safety
+/// `core` must be NULL or a live pointer from `eyecore_create`, not used concurrently from another thread.
+#[no_mangle]
+pub unsafe extern "C" fn eyecore_start(core: *mut EyeCore, interval_ms: u32) -> i32 {
+    let Some(core) = core.as_mut() else {
+        return EYECORE_ERR_NULL;
+    };
+    if interval_ms == 0 {
+        return EYECORE_ERR_INVALID_ARGUMENT;
+    }
+    guard(EYECORE_ERR_PANIC, || core.start(Duration::from_millis(interval_ms as u64)))
+}
+
+/// Stop the background collection; returns once no collection is running
+///
+/// # Safety
+/// As for `eyecore_start`.
+#[no_mangle]
+pub unsafe extern "C" fn eyecore_stop(core: *mut EyeCore) -> i32 {
+    let Some(core) = core.as_mut() else {
+        return EYECORE_ERR_NULL;
+    };
+    guard(EYECORE_ERR_PANIC, || core.stop())
+}
+
+/// Collect one snapshot now (blocking, about a second the first time) and return it as JSON,
+/// or NULL on failure. Free the result with `eyecore_string_free`.
+///
+/// # Safety
+/// As for `eyecore_start`.
+#[no_mangle]
+pub unsafe extern "C" fn eyecore_collect_once(core: *mut EyeCore) -> *mut c_char {
+    let Some(core) = core.as_ref() else {
+        return std::ptr::null_mut();
+    };
+    guard(std::ptr::null_mut(), || into_c_json(core.collect_once()))
+}
+
+/// Latest snapshot as JSON, or NULL before the first collection. Free it with `eyecore_string_free`.
+///
+/// # Safety
+/// As for `eyecore_start`.
+#[no_mangle]
+pub unsafe extern "C" fn eyecore_latest_json(core: *mut EyeCore) -> *mut c_char {
+    let Some(core) = core.as_ref() else {
+        return std::ptr::null_mut();
+    };
+    guard(std::ptr::null_mut(), || into_c_json(core.latest()))
+}
+
+/// Grant consent for a module ("voice", "camera", "camera_presence", "camera_cloud", "keystroke",
+/// "typed_text", "screen_text" or "files") and switch it on. Consent is recorded in the same `consent.json` as the
+/// service's `/consent`, with the source "ffi".
+///
+/// # Safety
+/// As for `eyecore_start`; `module` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn eyecore_enable_module(core: *mut EyeCore, module: *const c_char) -> i32 {
+    let Some(core) = core.as_mut() else {
+        return EYECORE_ERR_NULL;
+    };
+    let Some(module) = module_name(module) else {
+        return EYECORE_ERR_INVALID_ARGUMENT;
+    };
+    guard(EYECORE_ERR_PANIC, || core.set_consent(module, true))
+}
+
+/// Revoke consent for a module and switch it off
+///
+/// # Safety
+/// As for `eyecore_enable_module`.
+#[no_mangle]
+pub unsafe extern "C" fn eyecore_disable_module(core: *mut EyeCore, module: *const c_char) -> i32 {
+    let Some(core) = core.as_mut() else {
+        return EYECORE_ERR_NULL;
+    };
+    let Some(module) = module_name(module) else {
+        return EYECORE_ERR_INVALID_ARGUMENT;
+    };
+    guard(EYECORE_ERR_PANIC, || core.set_consent(module, false))
+}
+
+/// Consent per module and its history as JSON (the shape of `/consent`). Free it with
+/// `eyecore_string_free`.
+///
+/// # Safety
+/// As for `eyecore_start`.
+#[no_mangle]
+pub unsafe extern "C" fn eyecore_consent_json(core: *mut EyeCore) -> *mut c_char {
+    let Some(core) = core.as_ref() else {
+        return std::ptr::null_mut();
+    };
+    guard(std::ptr::null_mut(), || {
+        serde_json::to_string(&core.consent.status())
+            .ok()
+            .and_then(|json| CString::new(json).ok())
+            .map_or(std::ptr::null_mut(), CString::into_raw)
+    })
+}
+
+/// Free a string returned by this library. NULL is ignored.
+///
+/// # Safety
+/// `json` must come from `eyecore_collect_once`, `eyecore_latest_json` or `eyecore_consent_json`
+/// and be freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn eyecore_string_free(json: *mut c_char) {
+    if !json.is_null() {
+        drop(CString::from_raw(json));
+    }
+}
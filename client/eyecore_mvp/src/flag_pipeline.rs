@@ -0,0 +1,66 @@
+//! In-process flag detection (`flag_detection = "in_process"`): the snapshot loop publishes every
+//! snapshot on a broadcast channel as soon as it is collected, and the flag_detection pipeline runs
+//! on it here instead of in the standalone binary watching the data directory, so flags do not wait
+//! for files to be written and noticed. Rules, severity policy and outputs are the same, set by the
+//! same `FLAG_*` variables (thresholds from FLAG_DETECTOR_CONFIG, `detector.toml` in the working
+//! directory by default), and flags go to `<data_dir>/flags` as before. The standalone binary
+//! stays for setups that analyze snapshots on another machine; run only one of the two against a
+//! data directory, or every flag is raised twice.
+
+use flag_detection::models::{Baseline, EyeCoreData as DetectorSnapshot};
+use flag_detection::pipeline::Pipeline;
+use flag_detection::thresholds::SharedThresholds;
+use log::{error, info, warn};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::http::HttpClient;
+use crate::models::EyeCoreData;
+use crate::storage::DataStorage;
+
+/// Snapshots waiting for the detector; one further behind skips the oldest
+pub const SNAPSHOT_CHANNEL: usize = 64;
+
+/// Run the detector on the snapshots published on `snapshots` until the sender is dropped; also
+/// returns its thresholds, which follow edits to detector.toml, and the SIEM flags are forwarded to
+pub async fn start(
+    storage: &DataStorage,
+    mut snapshots: broadcast::Receiver<Arc<EyeCoreData>>,
+    http: &HttpClient,
+) -> Result<(JoinHandle<()>, SharedThresholds, Option<String>), String> {
+    let flags_dir = storage.get_data_dir().join("flags");
+    let pipeline = Pipeline::from_env(&flags_dir.to_string_lossy(), http.reqwest_client()).await?;
+    let baseline_path = storage.device_dir().join("baseline.json");
+    let thresholds = pipeline.thresholds();
+    let siem_recipient = pipeline.siem_recipient();
+    info!("🚩 Flag detection running in-process, flags in {:?}", flags_dir);
+
+    let task = tokio::spawn(async move {
+        loop {
+            let snapshot = match snapshots.recv().await {
+                Ok(snapshot) => snapshot,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("⚠️ Flag detection fell behind and skipped {} snapshots", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            // The detector has its own model of a snapshot, read from the same JSON as the files
+            let data = match serde_json::to_value(&*snapshot).and_then(serde_json::from_value::<DetectorSnapshot>) {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("Failed to hand snapshot {} to flag detection: {}", snapshot.timestamp, e);
+                    continue;
+                }
+            };
+            // Calibration may replace the baseline at any time, so it is read for every snapshot
+            let baseline = tokio::fs::read_to_string(&baseline_path)
+                .await
+                .ok()
+                .and_then(|content| serde_json::from_str::<Baseline>(&content).ok());
+            pipeline.analyze(&data, baseline.as_ref()).await;
+        }
+    });
+    Ok((task, thresholds, siem_recipient))
+}
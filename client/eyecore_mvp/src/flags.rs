@@ -0,0 +1,89 @@
+//! Querying the flag detector's flags over HTTP (`/flags`, `/flags/recent`, `/flags/{session_id}`).
+//! Flags are read the way incidents and samples read them, from `flags/`, its monthly archive and the
+//! SQLite store, so the detector does not need to run for them to be served. A `severity` filter is a
+//! minimum: `high` returns High and Critical flags.
+
+use crate::models::FlagRecord;
+
+/// Severities of the flag_detection service, lowest first
+pub const SEVERITIES: [&str; 5] = ["Informational", "Low", "Medium", "High", "Critical"];
+pub const DEFAULT_RECENT_HOURS: i64 = 24;
+pub const MAX_RECENT_HOURS: i64 = 24 * 7;
+pub const DEFAULT_RECENT_LIMIT: usize = 50;
+pub const MAX_RECENT_LIMIT: usize = 500;
+
+/// Position of `severity` in `SEVERITIES`, ignoring case
+fn severity_rank(severity: &str) -> Option<usize> {
+    SEVERITIES.iter().position(|known| known.eq_ignore_ascii_case(severity))
+}
+
+/// Which flags a query returns
+#[derive(Debug, Clone, Default)]
+pub struct FlagFilter {
+    min_severity: Option<usize>,
+    flag_type: Option<String>,
+    session_id: Option<String>,
+}
+
+impl FlagFilter {
+    pub fn new(severity: Option<&str>, flag_type: Option<&str>, session_id: Option<&str>) -> Result<Self, String> {
+        let min_severity = match severity {
+            Some(severity) => Some(severity_rank(severity).ok_or_else(|| {
+                format!("Unknown severity {:?} (expected one of {})", severity, SEVERITIES.join(", ").to_lowercase())
+            })?),
+            None => None,
+        };
+        Ok(FlagFilter {
+            min_severity,
+            flag_type: flag_type.map(str::to_string),
+            session_id: session_id.map(str::to_string),
+        })
+    }
+
+    pub fn matches(&self, flag: &FlagRecord) -> bool {
+        // Flags with a severity the client does not know are only left out by a severity filter
+        self.min_severity.is_none_or(|min| severity_rank(&flag.severity).is_some_and(|rank| rank >= min))
+            && self.flag_type.as_ref().is_none_or(|name| flag.flag_type.name.eq_ignore_ascii_case(name))
+            && self.session_id.as_ref().is_none_or(|id| &flag.session_id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FlagTypeTag;
+
+    fn flag(severity: &str, flag_type: &str, session_id: &str) -> FlagRecord {
+        FlagRecord {
+            id: format!("{}-{}", flag_type, severity),
+            timestamp: chrono::Utc::now(),
+            session_id: session_id.to_string(),
+            flag_type: FlagTypeTag { name: flag_type.to_string() },
+            severity: severity.to_string(),
+            title: String::new(),
+            description: String::new(),
+            data_source: String::new(),
+            metrics: serde_json::Value::Null,
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn severity_is_a_minimum() {
+        let filter = FlagFilter::new(Some("high"), None, None).unwrap();
+        assert!(filter.matches(&flag("High", "HealthConcern", "s")));
+        assert!(filter.matches(&flag("Critical", "SecurityConcern", "s")));
+        assert!(!filter.matches(&flag("Medium", "HealthConcern", "s")));
+        assert!(!filter.matches(&flag("Unheard", "HealthConcern", "s")));
+        assert!(FlagFilter::new(Some("urgent"), None, None).is_err());
+    }
+
+    #[test]
+    fn type_and_session_narrow_the_flags() {
+        let filter = FlagFilter::new(None, Some("securityconcern"), Some("s1")).unwrap();
+        assert!(filter.matches(&flag("Low", "SecurityConcern", "s1")));
+        assert!(!filter.matches(&flag("Low", "SecurityConcern", "s2")));
+        assert!(!filter.matches(&flag("Low", "HealthConcern", "s1")));
+        assert!(FlagFilter::default().matches(&flag("Unheard", "Other", "s3")));
+    }
+}
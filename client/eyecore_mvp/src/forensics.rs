@@ -0,0 +1,150 @@
+//! Forensic input buffer (`forensic_buffer_secs`): the raw keyboard and mouse events of the last
+//! seconds, kept in memory only, and written into an incident bundle when the flag detector raises a
+//! Critical SecurityConcern. Keys are reduced to their class (letter, digit, editing, ...), so a
+//! bundle shows the rhythm and the pointer path, never what was typed.
+//!
+//! Events are recorded only while the keystroke module has consent and is enabled; withdrawing it
+//! empties the buffer. The bundles themselves are written by `incidents`.
+
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+use rdev::{Button, Event, EventType, Key};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::models::RawInputEvent;
+
+/// Longest `forensic_buffer_secs`
+pub const MAX_BUFFER_SECS: u32 = 600;
+/// Events kept at most, whatever the window
+const MAX_EVENTS: usize = 50_000;
+/// Pointer moves closer together than this are not kept
+const MOVE_INTERVAL_MS: i64 = 50;
+
+#[derive(Default)]
+struct Buffer {
+    events: VecDeque<RawInputEvent>,
+    position: Option<(f64, f64)>,
+    last_move: Option<DateTime<Utc>>,
+}
+
+pub struct ForensicBuffer {
+    window_secs: u32,
+    active: AtomicBool,
+    buffer: Mutex<Buffer>,
+}
+
+impl ForensicBuffer {
+    fn new(window_secs: u32) -> Self {
+        ForensicBuffer {
+            window_secs,
+            active: AtomicBool::new(false),
+            buffer: Mutex::new(Buffer::default()),
+        }
+    }
+
+    pub fn window_secs(&self) -> u32 {
+        self.window_secs
+    }
+
+    /// Record only while active, i.e. while keystroke consent is granted; stopping empties the buffer
+    pub fn set_active(&self, active: bool) {
+        if !self.active.swap(active, Ordering::Relaxed) || active {
+            return;
+        }
+        if let Ok(mut buffer) = self.buffer.lock() {
+            *buffer = Buffer::default();
+        }
+    }
+
+    /// Events of the last `window_secs`, oldest first
+    pub fn events(&self) -> Vec<RawInputEvent> {
+        let cutoff = Utc::now() - Duration::seconds(self.window_secs as i64);
+        self.buffer
+            .lock()
+            .map(|buffer| buffer.events.iter().filter(|event| event.timestamp >= cutoff).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn record(&self, event: &Event) {
+        if !self.active.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(mut buffer) = self.buffer.lock() else {
+            return;
+        };
+        let timestamp = DateTime::<Utc>::from(event.time);
+        let (kind, key_class, button) = match event.event_type {
+            EventType::KeyPress(key) => ("key_down", Some(key_class(key)), None),
+            EventType::KeyRelease(key) => ("key_up", Some(key_class(key)), None),
+            EventType::ButtonPress(pressed) => ("button_down", None, Some(button_name(pressed))),
+            EventType::ButtonRelease(released) => ("button_up", None, Some(button_name(released))),
+            EventType::Wheel { .. } => ("wheel", None, None),
+            EventType::MouseMove { x, y } => {
+                buffer.position = Some((x, y));
+                if buffer.last_move.is_some_and(|last| timestamp - last < Duration::milliseconds(MOVE_INTERVAL_MS)) {
+                    return;
+                }
+                buffer.last_move = Some(timestamp);
+                ("move", None, None)
+            }
+        };
+        let position = buffer.position;
+        buffer.events.push_back(RawInputEvent {
+            timestamp,
+            kind: kind.to_string(),
+            key_class: key_class.map(str::to_string),
+            button: button.map(str::to_string),
+            position,
+        });
+
+        let cutoff = timestamp - Duration::seconds(self.window_secs as i64);
+        while buffer.events.front().is_some_and(|oldest| oldest.timestamp < cutoff) || buffer.events.len() > MAX_EVENTS {
+            buffer.events.pop_front();
+        }
+    }
+}
+
+/// Start buffering the last `window_secs` of input from the process-wide hook (inactive until
+/// keystroke consent is applied)
+pub fn start(window_secs: u32) -> Arc<ForensicBuffer> {
+    let buffer = Arc::new(ForensicBuffer::new(window_secs));
+    let recorder = Arc::clone(&buffer);
+    crate::input_hooks::global().subscribe(move |event| recorder.record(event));
+    info!("🧾 Forensic input buffer: last {}s, bundled for Critical SecurityConcern flags", window_secs);
+    buffer
+}
+
+/// Kind of key, without which one it was
+pub fn key_class(key: Key) -> &'static str {
+    match key {
+        Key::KeyA | Key::KeyB | Key::KeyC | Key::KeyD | Key::KeyE | Key::KeyF | Key::KeyG | Key::KeyH | Key::KeyI
+        | Key::KeyJ | Key::KeyK | Key::KeyL | Key::KeyM | Key::KeyN | Key::KeyO | Key::KeyP | Key::KeyQ | Key::KeyR
+        | Key::KeyS | Key::KeyT | Key::KeyU | Key::KeyV | Key::KeyW | Key::KeyX | Key::KeyY | Key::KeyZ => "letter",
+        Key::Num0 | Key::Num1 | Key::Num2 | Key::Num3 | Key::Num4 | Key::Num5 | Key::Num6 | Key::Num7 | Key::Num8
+        | Key::Num9 | Key::Kp0 | Key::Kp1 | Key::Kp2 | Key::Kp3 | Key::Kp4 | Key::Kp5 | Key::Kp6 | Key::Kp7
+        | Key::Kp8 | Key::Kp9 => "digit",
+        Key::Space | Key::Tab | Key::Return | Key::KpReturn => "whitespace",
+        Key::BackQuote | Key::Minus | Key::Equal | Key::LeftBracket | Key::RightBracket | Key::SemiColon | Key::Quote
+        | Key::BackSlash | Key::IntlBackslash | Key::Comma | Key::Dot | Key::Slash | Key::KpMinus | Key::KpPlus
+        | Key::KpMultiply | Key::KpDivide => "punctuation",
+        Key::Backspace | Key::Delete | Key::KpDelete | Key::Insert => "editing",
+        Key::ShiftLeft | Key::ShiftRight | Key::ControlLeft | Key::ControlRight | Key::Alt | Key::AltGr
+        | Key::MetaLeft | Key::MetaRight | Key::CapsLock | Key::NumLock | Key::Function => "modifier",
+        Key::UpArrow | Key::DownArrow | Key::LeftArrow | Key::RightArrow | Key::Home | Key::End | Key::PageUp
+        | Key::PageDown => "navigation",
+        Key::F1 | Key::F2 | Key::F3 | Key::F4 | Key::F5 | Key::F6 | Key::F7 | Key::F8 | Key::F9 | Key::F10
+        | Key::F11 | Key::F12 => "function",
+        Key::Escape | Key::PrintScreen | Key::ScrollLock | Key::Pause | Key::Unknown(_) => "other",
+    }
+}
+
+pub fn button_name(button: Button) -> &'static str {
+    match button {
+        Button::Left => "left",
+        Button::Right => "right",
+        Button::Middle => "middle",
+        Button::Unknown(_) => "other",
+    }
+}
@@ -0,0 +1,247 @@
+//! Gaze calibration (`/calibration/gaze`): a dashboard shows prompts across the screen, and while
+//! the user looks at each one the camera's raw gaze estimate is paired with the prompt position.
+//! A least-squares affine mapping from estimate to screen pixels is fitted from the pairs and kept
+//! in `<device>/gaze_calibration.json`. Mapped gaze points then give `camera_data.gaze_direction`
+//! and weigh into the attention zones next to the mouse.
+
+use chrono::Utc;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+
+use crate::models::{AttentionZone, GazeCalibration, GazeCalibrationStatus, GazeSample};
+
+/// Samples needed to fit a calibration
+pub const MIN_SAMPLES: usize = 6;
+/// Longest wait for a camera frame when recording a sample
+pub const SAMPLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// Prompts sit this far in from the screen edges, as a share of its size
+const PROMPT_MARGIN: f32 = 0.1;
+/// Attention zones split the screen into this many columns and rows
+const ZONE_GRID: usize = 4;
+/// Gaze this far off the screen, as a share of its size, is looking away
+const AWAY_MARGIN: f32 = 0.1;
+
+/// Screen assumed for attention zones until gaze is calibrated
+pub const DEFAULT_SCREEN: (f32, f32) = (1920.0, 1080.0);
+
+struct Session {
+    screen: (u32, u32),
+    samples: Vec<GazeSample>,
+}
+
+pub struct GazeCalibrator {
+    path: PathBuf,
+    session: Option<Session>,
+    calibration: Option<GazeCalibration>,
+}
+
+impl GazeCalibrator {
+    /// Calibration kept in `device_dir`
+    pub fn load(device_dir: &Path) -> Self {
+        let path = device_dir.join("gaze_calibration.json");
+        let calibration = std::fs::read_to_string(&path).ok().and_then(|content| {
+            serde_json::from_str(&content)
+                .map_err(|e| warn!("⚠️ Ignoring unreadable {:?}: {}", path, e))
+                .ok()
+        });
+        GazeCalibrator { path, session: None, calibration }
+    }
+
+    pub fn calibration(&self) -> Option<&GazeCalibration> {
+        self.calibration.as_ref()
+    }
+
+    /// Begin a session for a screen of `width` x `height` pixels, dropping one still running;
+    /// the current calibration stays in use until the new one is finished
+    pub fn start(&mut self, width: u32, height: u32) -> Result<GazeCalibrationStatus, String> {
+        if width == 0 || height == 0 {
+            return Err("screen_width and screen_height must be at least 1".to_string());
+        }
+        self.session = Some(Session { screen: (width, height), samples: Vec::new() });
+        info!("👀 Gaze calibration started for a {}x{} screen", width, height);
+        Ok(self.status())
+    }
+
+    /// Pair the prompt at `target` (screen pixels) with the camera's raw `estimate`
+    pub fn record(&mut self, target: [f32; 2], estimate: [f32; 2]) -> Result<GazeCalibrationStatus, String> {
+        let session = self.session.as_mut().ok_or("No gaze calibration is running")?;
+        let (width, height) = session.screen;
+        if !(0.0..=width as f32).contains(&target[0]) || !(0.0..=height as f32).contains(&target[1]) {
+            return Err(format!("The prompt must lie on the {}x{} screen", width, height));
+        }
+        session.samples.push(GazeSample { target, estimate });
+        Ok(self.status())
+    }
+
+    /// Fit and keep the calibration from the running session's samples
+    pub fn finish(&mut self) -> Result<GazeCalibration, String> {
+        let session = self.session.as_ref().ok_or("No gaze calibration is running")?;
+        if session.samples.len() < MIN_SAMPLES {
+            return Err(format!("{} samples recorded, at least {} are needed", session.samples.len(), MIN_SAMPLES));
+        }
+        let (x_coefficients, y_coefficients) = fit(&session.samples)
+            .ok_or("The samples do not span the screen; look at prompts in different rows and columns")?;
+        let mut calibration = GazeCalibration {
+            created_at: Utc::now(),
+            screen_width: session.screen.0,
+            screen_height: session.screen.1,
+            samples: session.samples.len(),
+            mean_error_px: 0.0,
+            x_coefficients,
+            y_coefficients,
+        };
+        let error: f32 = session
+            .samples
+            .iter()
+            .map(|sample| {
+                let (x, y) = calibration.to_screen(sample.estimate);
+                (x - sample.target[0]).hypot(y - sample.target[1])
+            })
+            .sum();
+        calibration.mean_error_px = error / session.samples.len() as f32;
+
+        let content = serde_json::to_string_pretty(&calibration).map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, content).map_err(|e| format!("Failed to save gaze calibration {:?}: {}", self.path, e))?;
+        info!(
+            "👀 Gaze calibrated from {} samples, mean error {:.0}px",
+            calibration.samples, calibration.mean_error_px
+        );
+        self.session = None;
+        self.calibration = Some(calibration.clone());
+        Ok(calibration)
+    }
+
+    pub fn status(&self) -> GazeCalibrationStatus {
+        let screen = self.session.as_ref().map(|session| session.screen);
+        GazeCalibrationStatus {
+            active: self.session.is_some(),
+            screen_width: screen.map(|(width, _)| width),
+            screen_height: screen.map(|(_, height)| height),
+            prompts: screen.map(|(width, height)| prompts(width, height)).unwrap_or_default(),
+            samples: self.session.as_ref().map(|session| session.samples.clone()).unwrap_or_default(),
+            calibration: self.calibration.clone(),
+        }
+    }
+}
+
+impl GazeCalibration {
+    /// Screen pixels for a raw gaze estimate; may lie off the screen
+    pub fn to_screen(&self, estimate: [f32; 2]) -> (f32, f32) {
+        let map = |c: &[f32; 3]| c[0] + c[1] * estimate[0] + c[2] * estimate[1];
+        (map(&self.x_coefficients), map(&self.y_coefficients))
+    }
+
+    pub fn screen(&self) -> (f32, f32) {
+        (self.screen_width as f32, self.screen_height as f32)
+    }
+
+    /// `camera_data.gaze_direction` for a point on (or off) the screen
+    pub fn direction(&self, (x, y): (f32, f32)) -> &'static str {
+        let (width, height) = self.screen();
+        let outside = |value: f32, size: f32| value < -AWAY_MARGIN * size || value > (1.0 + AWAY_MARGIN) * size;
+        if outside(x, width) || outside(y, height) {
+            "away"
+        } else if y > height * 2.0 / 3.0 {
+            "down"
+        } else if x < width / 3.0 {
+            "left"
+        } else if x > width * 2.0 / 3.0 {
+            "right"
+        } else {
+            "center"
+        }
+    }
+}
+
+/// Where to show the prompts: a 3x3 grid inside the screen margins, row by row
+fn prompts(width: u32, height: u32) -> Vec<[f32; 2]> {
+    let steps = [PROMPT_MARGIN, 0.5, 1.0 - PROMPT_MARGIN];
+    steps
+        .iter()
+        .flat_map(|row| steps.iter().map(move |column| [column * width as f32, row * height as f32]))
+        .collect()
+}
+
+/// Least-squares coefficients of target = c0 + c1 * u + c2 * v for each screen axis; None when
+/// the estimates lie on a line
+fn fit(samples: &[GazeSample]) -> Option<([f32; 3], [f32; 3])> {
+    // Normal equations: (AᵀA) c = Aᵀt with rows [1, u, v]
+    let mut normal = [[0.0f64; 3]; 3];
+    let mut rhs = [[0.0f64; 3]; 2];
+    for sample in samples {
+        let row = [1.0, sample.estimate[0] as f64, sample.estimate[1] as f64];
+        for i in 0..3 {
+            for j in 0..3 {
+                normal[i][j] += row[i] * row[j];
+            }
+            rhs[0][i] += row[i] * sample.target[0] as f64;
+            rhs[1][i] += row[i] * sample.target[1] as f64;
+        }
+    }
+    let det = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let determinant = det(normal);
+    if determinant.abs() < 1e-9 {
+        return None;
+    }
+    // Cramer's rule
+    let solve = |b: [f64; 3]| {
+        let mut coefficients = [0.0f32; 3];
+        for (column, coefficient) in coefficients.iter_mut().enumerate() {
+            let mut replaced = normal;
+            for row in 0..3 {
+                replaced[row][column] = b[row];
+            }
+            *coefficient = (det(replaced) / determinant) as f32;
+        }
+        coefficients
+    };
+    Some((solve(rhs[0]), solve(rhs[1])))
+}
+
+/// Attention over a grid of screen zones from recent pointer positions and, once calibrated, gaze
+/// points: each zone scores its share of the pointer positions, averaged with its share of the gaze
+/// points when there are any. Zones nobody looked at or pointed to are left out.
+pub fn attention_zones(pointer: &[(f32, f32)], gaze: &[(f32, f32)], screen: (f32, f32), window_secs: f32) -> Vec<AttentionZone> {
+    let (width, height) = screen;
+    let cell = |(x, y): (f32, f32)| {
+        let column = ((x / width * ZONE_GRID as f32).floor() as isize).clamp(0, ZONE_GRID as isize - 1) as usize;
+        let row = ((y / height * ZONE_GRID as f32).floor() as isize).clamp(0, ZONE_GRID as isize - 1) as usize;
+        row * ZONE_GRID + column
+    };
+    let shares = |points: &[(f32, f32)]| {
+        let mut counts = [0usize; ZONE_GRID * ZONE_GRID];
+        for point in points {
+            counts[cell(*point)] += 1;
+        }
+        counts.map(|count| if points.is_empty() { 0.0 } else { count as f32 / points.len() as f32 })
+    };
+    let pointer_shares = shares(pointer);
+    let gaze_shares = shares(gaze);
+
+    let (zone_width, zone_height) = (width / ZONE_GRID as f32, height / ZONE_GRID as f32);
+    let mut zones: Vec<AttentionZone> = (0..ZONE_GRID * ZONE_GRID)
+        .filter_map(|index| {
+            let score = match (pointer.is_empty(), gaze.is_empty()) {
+                (_, true) => pointer_shares[index],
+                (true, false) => gaze_shares[index],
+                (false, false) => (pointer_shares[index] + gaze_shares[index]) / 2.0,
+            };
+            (score > 0.0).then(|| AttentionZone {
+                region: vec![
+                    ((index % ZONE_GRID) as f32 * zone_width) as i32,
+                    ((index / ZONE_GRID) as f32 * zone_height) as i32,
+                    zone_width as i32,
+                    zone_height as i32,
+                ],
+                attention_score: score,
+                duration_seconds: score * window_secs,
+                gaze_score: (!gaze.is_empty()).then_some(gaze_shares[index]),
+            })
+        })
+        .collect();
+    zones.sort_by(|a, b| b.attention_score.total_cmp(&a.attention_score));
+    zones
+}
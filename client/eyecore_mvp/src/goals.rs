@@ -0,0 +1,300 @@
+//! Goals such as "4h deep work on eyecore per day", registered through `POST /goals` and kept in
+//! `goals.json` in the device directory. Progress comes from the stored snapshots: each counts the
+//! time until the next one (unless they are more than a minute apart) towards the goals whose
+//! metric and project it matches, with the current app classes and project names. `/goals/progress`
+//! shows the current day and week; the daily and weekly reports record whether goals were met.
+
+use chrono::{Datelike, Duration, NaiveDate, Timelike, Utc};
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+
+use crate::classification::AppClassification;
+use crate::models::{EyeCoreData, Goal, GoalAttainment, GoalMetric, GoalPeriod, GoalProgress, NewGoal, PresenceState, Productivity};
+use crate::projects::ProjectDetector;
+use crate::storage::DataStorage;
+
+/// Goals file in the device directory
+const GOALS_FILE: &str = "goals.json";
+const MAX_GOALS: usize = 50;
+const MAX_NAME_LENGTH: usize = 200;
+/// Snapshots further apart than this do not add goal time
+const MAX_SAMPLE_GAP_SECS: i64 = 60;
+/// Focus level from which productive time counts as deep work
+const DEEP_WORK_FOCUS_LEVEL: f32 = 0.6;
+/// Shorter stretches of focused work do not count as deep work
+const DEEP_WORK_MIN_SECS: i64 = 25 * 60;
+
+pub struct GoalBook {
+    path: PathBuf,
+    goals: Vec<Goal>,
+}
+
+impl GoalBook {
+    /// The goals kept in `device_dir`
+    pub fn load(device_dir: &Path) -> Self {
+        let path = device_dir.join(GOALS_FILE);
+        let goals = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("⚠️ Ignoring unreadable goals {:?}: {}", path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        GoalBook { path, goals }
+    }
+
+    pub fn goals(&self) -> &[Goal] {
+        &self.goals
+    }
+
+    pub fn add(&mut self, goal: NewGoal) -> Result<Goal, String> {
+        if self.goals.len() >= MAX_GOALS {
+            return Err(format!("At most {} goals can be registered", MAX_GOALS));
+        }
+        let (period, period_minutes) = match goal.period {
+            GoalPeriod::Daily => ("daily", 24 * 60),
+            GoalPeriod::Weekly => ("weekly", 7 * 24 * 60),
+        };
+        if goal.target_minutes == 0 || goal.target_minutes > period_minutes {
+            return Err(format!("target_minutes must be 1-{} for a {} goal", period_minutes, period));
+        }
+        let project = goal.project.map(|project| project.trim().to_string()).filter(|project| !project.is_empty());
+        let name = match goal.name.map(|name| name.trim().to_string()) {
+            Some(name) if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH => {
+                return Err(format!("name must be 1-{} characters", MAX_NAME_LENGTH));
+            }
+            Some(name) => name,
+            None => default_name(goal.metric, project.as_deref(), goal.target_minutes, goal.period),
+        };
+
+        let goal = Goal {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            metric: goal.metric,
+            project,
+            target_minutes: goal.target_minutes,
+            period: goal.period,
+            created_at: Utc::now(),
+        };
+        self.goals.push(goal.clone());
+        self.save()?;
+        info!("🎯 Goal added: {}", goal.name);
+        Ok(goal)
+    }
+
+    /// Remove a goal; false if there is none with `id`
+    pub fn remove(&mut self, id: &str) -> Result<bool, String> {
+        let Some(index) = self.goals.iter().position(|goal| goal.id == id) else {
+            return Ok(false);
+        };
+        let goal = self.goals.remove(index);
+        self.save()?;
+        info!("🎯 Goal removed: {}", goal.name);
+        Ok(true)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.goals).map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, content).map_err(|e| format!("Failed to save goals {:?}: {}", self.path, e))
+    }
+}
+
+/// Progress of every goal in the day or week containing `today`, from the stored snapshots
+pub async fn progress(storage: &DataStorage, goals: &[Goal], today: NaiveDate) -> std::io::Result<Vec<GoalProgress>> {
+    let week_start = week_start(today);
+    let scorer = Scorer::load(&storage.device_dir());
+    let mut week = vec![0; goals.len()];
+    let mut day = vec![0; goals.len()];
+    for date in week_start.iter_days().take_while(|date| *date <= today) {
+        day = scorer.minutes(&storage.load_snapshots(date).await?, goals);
+        week.iter_mut().zip(&day).for_each(|(total, minutes)| *total += minutes);
+    }
+
+    // Days of the week gone by, counting today by the hour
+    let now = Utc::now();
+    let elapsed_days = (today - week_start).num_days() as f32 + now.num_seconds_from_midnight() as f32 / 86_400.0;
+    Ok(goals
+        .iter()
+        .enumerate()
+        .map(|(i, goal)| {
+            let (period_start, period_end, minutes, projected_minutes) = match goal.period {
+                GoalPeriod::Daily => (today, today, day[i], None),
+                GoalPeriod::Weekly => {
+                    let projected = (elapsed_days >= 1.0).then(|| (week[i] as f32 / elapsed_days * 7.0).round() as u32);
+                    (week_start, week_start + Duration::days(6), week[i], projected)
+                }
+            };
+            GoalProgress {
+                goal: goal.clone(),
+                period_start,
+                period_end,
+                minutes,
+                percent: minutes as f32 / goal.target_minutes as f32 * 100.0,
+                achieved: minutes >= goal.target_minutes,
+                remaining_minutes: goal.target_minutes.saturating_sub(minutes),
+                projected_minutes,
+            }
+        })
+        .collect())
+}
+
+/// Whether the daily goals were met on the day of `snapshots`, for the daily report
+pub fn daily_attainment(snapshots: &[EyeCoreData], device_dir: &Path) -> Vec<GoalAttainment> {
+    let book = GoalBook::load(device_dir);
+    let goals: Vec<Goal> = book.goals().iter().filter(|goal| goal.period == GoalPeriod::Daily).cloned().collect();
+    let minutes = Scorer::load(device_dir).minutes(snapshots, &goals);
+    goals
+        .iter()
+        .zip(minutes)
+        .map(|(goal, minutes)| GoalAttainment {
+            id: goal.id.clone(),
+            name: goal.name.clone(),
+            period: goal.period,
+            target_minutes: goal.target_minutes,
+            minutes,
+            achieved: minutes >= goal.target_minutes,
+            days_achieved: None,
+        })
+        .collect()
+}
+
+/// Whether the goals were met in the week from `week_start`, for the weekly report: weekly goals
+/// over the week, daily goals day by day
+pub async fn weekly_attainment(storage: &DataStorage, week_start: NaiveDate) -> std::io::Result<Vec<GoalAttainment>> {
+    let goals = GoalBook::load(&storage.device_dir()).goals().to_vec();
+    let scorer = Scorer::load(&storage.device_dir());
+    let mut totals = vec![0; goals.len()];
+    let mut days_achieved = vec![0; goals.len()];
+    for date in week_start.iter_days().take(7) {
+        let day = scorer.minutes(&storage.load_snapshots(date).await?, &goals);
+        for (i, minutes) in day.into_iter().enumerate() {
+            totals[i] += minutes;
+            if minutes >= goals[i].target_minutes {
+                days_achieved[i] += 1;
+            }
+        }
+    }
+    Ok(goals
+        .iter()
+        .enumerate()
+        .map(|(i, goal)| {
+            let daily = goal.period == GoalPeriod::Daily;
+            GoalAttainment {
+                id: goal.id.clone(),
+                name: goal.name.clone(),
+                period: goal.period,
+                target_minutes: goal.target_minutes,
+                minutes: totals[i],
+                achieved: if daily { days_achieved[i] == 7 } else { totals[i] >= goal.target_minutes },
+                days_achieved: daily.then_some(days_achieved[i]),
+            }
+        })
+        .collect())
+}
+
+/// Monday of the week containing `date`
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Counts snapshots towards goals with the current classification and project names
+struct Scorer {
+    classification: AppClassification,
+    projects: ProjectDetector,
+}
+
+impl Scorer {
+    fn load(device_dir: &Path) -> Self {
+        Scorer { classification: AppClassification::load(device_dir), projects: ProjectDetector::load(device_dir) }
+    }
+
+    /// Minutes each goal gets from one day's snapshots
+    fn minutes(&self, snapshots: &[EyeCoreData], goals: &[Goal]) -> Vec<u32> {
+        // Per snapshot: counted seconds, current project, productive, focused
+        let samples: Vec<(i64, Option<String>, bool, bool)> = snapshots
+            .iter()
+            .enumerate()
+            .map(|(i, data)| {
+                let seconds = snapshots
+                    .get(i + 1)
+                    .map(|next| (next.timestamp - data.timestamp).num_seconds())
+                    .filter(|gap| (0..=MAX_SAMPLE_GAP_SECS).contains(gap))
+                    .unwrap_or(0);
+                let process = &data.process_data;
+                let away = data.presence.as_ref().is_some_and(|presence| presence.state == PresenceState::Away);
+                if process.active_process == "unknown" || away {
+                    return (0, None, false, false);
+                }
+                let project = process
+                    .project
+                    .as_deref()
+                    .and_then(|name| self.projects.resolve(name))
+                    .map(|project| project.name.clone());
+                let productive = self.classification.classify(&process.active_process, &process.active_window_title).productivity
+                    == Productivity::Productive;
+                let in_meeting = data.meeting.as_ref().is_some_and(|meeting| meeting.in_meeting);
+                let focused = productive && !in_meeting && data.focus_metrics.focus_level >= DEEP_WORK_FOCUS_LEVEL;
+                (seconds, project, productive, focused)
+            })
+            .collect();
+
+        // Deep work: runs of focused snapshots lasting long enough
+        let mut deep = vec![false; samples.len()];
+        let mut start = 0;
+        while start < samples.len() {
+            if !samples[start].3 {
+                start += 1;
+                continue;
+            }
+            let mut end = start;
+            while end + 1 < samples.len() && samples[end].0 > 0 && samples[end + 1].3 {
+                end += 1;
+            }
+            let run: i64 = samples[start..=end].iter().map(|sample| sample.0).sum();
+            if run >= DEEP_WORK_MIN_SECS {
+                deep[start..=end].iter_mut().for_each(|deep| *deep = true);
+            }
+            start = end + 1;
+        }
+
+        goals
+            .iter()
+            .map(|goal| {
+                let project = goal.project.as_deref().map(|name| {
+                    self.projects.resolve(name).map_or_else(|| name.to_string(), |project| project.name.clone())
+                });
+                let seconds: i64 = samples
+                    .iter()
+                    .zip(&deep)
+                    .filter(|((_, sample_project, productive, _), deep)| {
+                        let counts = match goal.metric {
+                            GoalMetric::DeepWork => **deep,
+                            GoalMetric::Productive => *productive,
+                            GoalMetric::Active => true,
+                        };
+                        counts && project.as_ref().is_none_or(|project| sample_project.as_ref() == Some(project))
+                    })
+                    .map(|((seconds, ..), _)| seconds)
+                    .sum();
+                (seconds / 60) as u32
+            })
+            .collect()
+    }
+}
+
+/// "240 min deep work on eyecore per day"
+fn default_name(metric: GoalMetric, project: Option<&str>, target_minutes: u32, period: GoalPeriod) -> String {
+    let metric = match metric {
+        GoalMetric::DeepWork => "deep work",
+        GoalMetric::Productive => "productive time",
+        GoalMetric::Active => "active time",
+    };
+    let period = match period {
+        GoalPeriod::Daily => "day",
+        GoalPeriod::Weekly => "week",
+    };
+    match project {
+        Some(project) => format!("{} min {} on {} per {}", target_minutes, metric, project, period),
+        None => format!("{} min {} per {}", target_minutes, metric, period),
+    }
+}
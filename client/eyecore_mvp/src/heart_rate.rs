@@ -0,0 +1,295 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tokio::sync::RwLock;
+
+use crate::models::{EyeCoreData, PhysiologicalData};
+
+/// Samples kept in memory (~2 hours at one sample per second)
+const MAX_SAMPLES: usize = 7200;
+
+/// Snapshots need at least this many paired readings before calibration is reported
+const MIN_CALIBRATION_SAMPLES: usize = 10;
+
+/// One heart-rate reading from a BLE strap or a Health Connect/HealthKit bridge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartRateSample {
+    pub timestamp: DateTime<Utc>,
+    pub bpm: f32,
+    #[serde(default)]
+    pub rr_intervals_ms: Vec<f32>, // beat-to-beat intervals, used for HRV
+    #[serde(default = "default_source")]
+    pub source: String,
+}
+
+fn default_source() -> String {
+    "bridge".to_string()
+}
+
+/// Buffers recent heart-rate samples for the collector
+pub struct HeartRateMonitor {
+    samples: RwLock<VecDeque<HeartRateSample>>,
+}
+
+impl HeartRateMonitor {
+    pub fn new() -> Self {
+        HeartRateMonitor {
+            samples: RwLock::new(VecDeque::with_capacity(MAX_SAMPLES)),
+        }
+    }
+
+    /// Add samples, ignoring physiologically impossible readings. Relays may push batches out
+    /// of order, so samples are inserted by timestamp and the oldest are dropped first.
+    pub async fn ingest(&self, samples: Vec<HeartRateSample>) -> usize {
+        let mut buffer = self.samples.write().await;
+        let mut accepted = 0;
+        for sample in samples {
+            if !(20.0..=250.0).contains(&sample.bpm) {
+                continue;
+            }
+            let at = buffer.partition_point(|s| s.timestamp <= sample.timestamp);
+            buffer.insert(at, sample);
+            accepted += 1;
+        }
+        while buffer.len() > MAX_SAMPLES {
+            buffer.pop_front();
+        }
+        accepted
+    }
+
+    /// Summarize the samples received in the last `window`
+    pub async fn summarize(&self, window: Duration) -> Option<PhysiologicalData> {
+        let since = Utc::now() - window;
+        let buffer = self.samples.read().await;
+        let mut recent: Vec<&HeartRateSample> = buffer.iter().filter(|s| s.timestamp >= since).collect();
+        // The latest reading and successive RR differences need time order
+        recent.sort_by_key(|s| s.timestamp);
+        let last = recent.last()?;
+
+        let heart_rate_bpm = recent.iter().map(|s| s.bpm).sum::<f32>() / recent.len() as f32;
+        let rr_intervals: Vec<f32> = recent.iter().flat_map(|s| s.rr_intervals_ms.iter().copied()).collect();
+
+        Some(PhysiologicalData {
+            timestamp: last.timestamp,
+            heart_rate_bpm,
+            hrv_rmssd_ms: rmssd(&rr_intervals),
+            sample_count: recent.len() as u32,
+            source: last.source.clone(),
+        })
+    }
+}
+
+/// Root mean square of successive RR differences, the standard short-term HRV measure
+fn rmssd(rr_intervals: &[f32]) -> Option<f32> {
+    if rr_intervals.len() < 2 {
+        return None;
+    }
+    let squared: f32 = rr_intervals.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+    Some((squared / (rr_intervals.len() - 1) as f32).sqrt())
+}
+
+/// How well behavioral stress indicators track physiological arousal
+#[derive(Debug, Clone, Serialize)]
+pub struct StressCalibration {
+    pub paired_samples: usize,
+    pub baseline_heart_rate_bpm: Option<f32>,
+    pub keystroke_vs_heart_rate: Option<f32>, // Pearson r
+    pub keystroke_vs_hrv: Option<f32>,        // expected negative: stress lowers HRV
+    pub mouse_erratic_vs_heart_rate: Option<f32>, // erratic = 1 - path smoothness
+    pub sufficient_data: bool,
+}
+
+/// Correlate keystroke/mouse stress indicators with heart rate and HRV across snapshots
+pub fn calibrate_stress(history: &[EyeCoreData]) -> StressCalibration {
+    let paired: Vec<(&EyeCoreData, &PhysiologicalData)> = history
+        .iter()
+        .filter_map(|d| d.physiological_data.as_ref().map(|p| (d, p)))
+        .collect();
+
+    let keystroke: Vec<(f32, f32)> = paired
+        .iter()
+        .filter_map(|(d, p)| d.keystroke_dynamics.as_ref().map(|k| (k.stress_indicator, p.heart_rate_bpm)))
+        .collect();
+    let keystroke_hrv: Vec<(f32, f32)> = paired
+        .iter()
+        .filter_map(|(d, p)| Some((d.keystroke_dynamics.as_ref()?.stress_indicator, p.hrv_rmssd_ms?)))
+        .collect();
+    let mouse: Vec<(f32, f32)> = paired
+        .iter()
+        .filter_map(|(d, p)| d.mouse_dynamics.as_ref().map(|m| (1.0 - m.path_smoothness, p.heart_rate_bpm)))
+        .collect();
+
+    // Resting baseline: lower quartile of observed heart rate
+    let mut rates: Vec<f32> = paired.iter().map(|(_, p)| p.heart_rate_bpm).collect();
+    rates.sort_by(|a, b| a.total_cmp(b));
+    let baseline_heart_rate_bpm = rates.get(rates.len() / 4).copied();
+
+    StressCalibration {
+        paired_samples: paired.len(),
+        baseline_heart_rate_bpm,
+        keystroke_vs_heart_rate: pearson(&keystroke),
+        keystroke_vs_hrv: pearson(&keystroke_hrv),
+        mouse_erratic_vs_heart_rate: pearson(&mouse),
+        sufficient_data: paired.len() >= MIN_CALIBRATION_SAMPLES,
+    }
+}
+
+fn pearson(pairs: &[(f32, f32)]) -> Option<f32> {
+    if pairs.len() < MIN_CALIBRATION_SAMPLES {
+        return None;
+    }
+    let n = pairs.len() as f32;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f32>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f32>() / n;
+
+    let covariance: f32 = pairs.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let var_x: f32 = pairs.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let var_y: f32 = pairs.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(covariance / (var_x.sqrt() * var_y.sqrt()))
+}
+
+/// BLE Heart Rate Profile collector (service 0x180D, measurement characteristic 0x2A37)
+#[cfg(feature = "heart-rate-ble")]
+pub mod ble {
+    use super::{HeartRateMonitor, HeartRateSample};
+    use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter};
+    use btleplug::platform::Manager;
+    use chrono::Utc;
+    use futures_util::StreamExt;
+    use log::{error, info, warn};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    const HEART_RATE_SERVICE: Uuid = Uuid::from_u128(0x0000180d_0000_1000_8000_00805f9b34fb);
+    const HEART_RATE_MEASUREMENT: Uuid = Uuid::from_u128(0x00002a37_0000_1000_8000_00805f9b34fb);
+
+    /// Connect to the first heart-rate strap found and stream readings into `monitor`
+    pub fn start(monitor: Arc<HeartRateMonitor>) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = run(&monitor).await {
+                    warn!("BLE heart-rate collector: {}. Retrying in 30s...", e);
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+            }
+        });
+    }
+
+    async fn run(monitor: &HeartRateMonitor) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let manager = Manager::new().await?;
+        let adapter = manager.adapters().await?.into_iter().next().ok_or("No Bluetooth adapter")?;
+
+        adapter.start_scan(ScanFilter { services: vec![HEART_RATE_SERVICE] }).await?;
+        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+
+        let peripheral = adapter.peripherals().await?.into_iter().next().ok_or("No heart-rate device found")?;
+        peripheral.connect().await?;
+        peripheral.discover_services().await?;
+
+        let characteristic = peripheral
+            .characteristics()
+            .into_iter()
+            .find(|c| c.uuid == HEART_RATE_MEASUREMENT)
+            .ok_or("Device has no heart-rate measurement characteristic")?;
+        peripheral.subscribe(&characteristic).await?;
+        info!("❤ Connected to BLE heart-rate device");
+
+        let mut notifications = peripheral.notifications().await?;
+        while let Some(notification) = notifications.next().await {
+            if notification.uuid != HEART_RATE_MEASUREMENT {
+                continue;
+            }
+            match parse_measurement(&notification.value) {
+                Some((bpm, rr_intervals_ms)) => {
+                    monitor
+                        .ingest(vec![HeartRateSample {
+                            timestamp: Utc::now(),
+                            bpm,
+                            rr_intervals_ms,
+                            source: "ble".to_string(),
+                        }])
+                        .await;
+                }
+                None => error!("Malformed heart-rate measurement"),
+            }
+        }
+
+        Err("Heart-rate device disconnected".into())
+    }
+
+    /// Decode a Heart Rate Measurement value (Bluetooth GATT spec)
+    fn parse_measurement(value: &[u8]) -> Option<(f32, Vec<f32>)> {
+        let flags = *value.first()?;
+        let mut offset = 1;
+
+        let bpm = if flags & 0x01 == 0 {
+            offset += 1;
+            *value.get(1)? as f32
+        } else {
+            offset += 2;
+            u16::from_le_bytes([*value.get(1)?, *value.get(2)?]) as f32
+        };
+
+        // Skip energy expended if present
+        if flags & 0x08 != 0 {
+            offset += 2;
+        }
+
+        // RR intervals are in 1/1024 s units
+        let mut rr_intervals_ms = Vec::new();
+        if flags & 0x10 != 0 {
+            while offset + 1 < value.len() {
+                let rr = u16::from_le_bytes([value[offset], value[offset + 1]]);
+                rr_intervals_ms.push(rr as f32 * 1000.0 / 1024.0);
+                offset += 2;
+            }
+        }
+
+        Some((bpm, rr_intervals_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(seconds_ago: i64, bpm: f32, rr_intervals_ms: Vec<f32>) -> HeartRateSample {
+        HeartRateSample {
+            timestamp: Utc::now() - Duration::seconds(seconds_ago),
+            bpm,
+            rr_intervals_ms,
+            source: format!("strap-{}", seconds_ago),
+        }
+    }
+
+    #[tokio::test]
+    async fn unsorted_samples_are_summarized_in_time_order() {
+        let monitor = HeartRateMonitor::new();
+        let accepted = monitor
+            .ingest(vec![
+                sample(10, 70.0, vec![800.0]),
+                sample(30, 60.0, vec![1000.0]),
+                sample(20, 65.0, vec![900.0]),
+                sample(5, 400.0, vec![]), // impossible, dropped
+            ])
+            .await;
+        assert_eq!(accepted, 3);
+
+        let summary = monitor.summarize(Duration::minutes(1)).await.unwrap();
+        assert_eq!(summary.sample_count, 3);
+        assert_eq!(summary.source, "strap-10");
+        assert!((summary.heart_rate_bpm - 65.0).abs() < 1e-4);
+        // 1000, 900, 800 ms: successive differences of 100 ms, not the 200/100 of arrival order
+        assert!((summary.hrv_rmssd_ms.unwrap() - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn rmssd_needs_two_intervals() {
+        assert_eq!(rmssd(&[800.0]), None);
+        assert!((rmssd(&[800.0, 810.0, 790.0]).unwrap() - (250.0f32).sqrt()).abs() < 1e-3);
+    }
+}
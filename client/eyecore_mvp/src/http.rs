@@ -0,0 +1,189 @@
+use log::warn;
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Header that makes a non-idempotent request (POST, PATCH) safe to send again
+pub const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+
+/// Outbound HTTP settings shared by all internet integrations
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    pub max_retries: u32,
+    pub proxy: Option<String>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            request_timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+            max_retries: DEFAULT_MAX_RETRIES,
+            proxy: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Read EYECORE_HTTP_CONNECT_TIMEOUT_SECS, EYECORE_HTTP_TIMEOUT_SECS, EYECORE_HTTP_RETRIES
+    /// and EYECORE_HTTPS_PROXY, keeping defaults for anything unset
+    pub fn from_env() -> Self {
+        let number = |name: &str| std::env::var(name).ok().and_then(|v| v.trim().parse::<u64>().ok());
+        let defaults = HttpConfig::default();
+
+        HttpConfig {
+            connect_timeout: number("EYECORE_HTTP_CONNECT_TIMEOUT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.connect_timeout),
+            request_timeout: number("EYECORE_HTTP_TIMEOUT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.request_timeout),
+            max_retries: number("EYECORE_HTTP_RETRIES").map(|n| n as u32).unwrap_or(defaults.max_retries),
+            proxy: std::env::var("EYECORE_HTTPS_PROXY").ok().filter(|p| !p.trim().is_empty()),
+        }
+    }
+}
+
+/// reqwest client with timeouts, proxy support and retries; cheap to clone
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    max_retries: u32,
+}
+
+impl HttpClient {
+    pub fn new(config: &HttpConfig) -> Result<Self, String> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
+            .user_agent(concat!("eyecore/", env!("CARGO_PKG_VERSION")));
+
+        if let Some(proxy) = &config.proxy {
+            let proxy = reqwest::Proxy::https(proxy).map_err(|e| format!("Invalid HTTPS proxy: {}", e))?;
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(HttpClient {
+            client: builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))?,
+            max_retries: config.max_retries,
+        })
+    }
+
+    /// Client from environment settings, falling back to defaults if they are invalid
+    pub fn from_env() -> Self {
+        HttpClient::new(&HttpConfig::from_env()).unwrap_or_else(|e| {
+            log::error!("{}. Using default HTTP settings", e);
+            HttpClient::new(&HttpConfig::default()).expect("default HTTP client")
+        })
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// The underlying client, for code that takes a plain reqwest client (the SIEM exporter)
+    pub fn reqwest_client(&self) -> reqwest::Client {
+        self.client.clone()
+    }
+
+    /// Send a request, retrying connection failures, timeouts, 429 and 5xx gateway errors
+    /// with jittered exponential backoff. Only idempotent methods and requests carrying an
+    /// `Idempotency-Key` are retried; a POST that timed out may still have been applied.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, String> {
+        let replay_safe = request
+            .try_clone()
+            .and_then(|probe| probe.build().ok())
+            .is_some_and(|probe| is_replay_safe(probe.method(), probe.headers()));
+        let max_retries = if replay_safe { self.max_retries } else { 0 };
+        let mut attempt = 0;
+        loop {
+            // Streaming bodies can't be replayed, so those requests get a single attempt
+            let retry = if attempt < max_retries { request.try_clone() } else { None };
+            let current = match retry {
+                Some(clone) => clone,
+                None => return request.send().await.map_err(|e| format!("Request error: {}", e)),
+            };
+
+            let delay = match current.send().await {
+                Ok(response) if is_retryable_status(response.status()) => {
+                    let delay = retry_after(&response).unwrap_or_else(|| backoff(attempt));
+                    warn!("{} returned {}, retrying in {:?}", response.url(), response.status(), delay);
+                    delay
+                }
+                Ok(response) => return Ok(response),
+                Err(e) if e.is_connect() || e.is_timeout() => {
+                    let delay = backoff(attempt);
+                    warn!("Request failed ({}), retrying in {:?}", e, delay);
+                    delay
+                }
+                Err(e) => return Err(format!("Request error: {}", e)),
+            };
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Whether sending the request twice has the same effect as sending it once
+fn is_replay_safe(method: &Method, headers: &HeaderMap) -> bool {
+    method.is_idempotent() || headers.contains_key(IDEMPOTENCY_KEY)
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Server-requested delay in seconds, capped so a bad header can't stall a loop for long
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_DELAY))
+}
+
+/// Exponential backoff with jitter: between half and all of base * 2^attempt
+fn backoff(attempt: u32) -> Duration {
+    let ceiling = RETRY_BASE_DELAY.saturating_mul(1 << attempt.min(16)).min(MAX_RETRY_DELAY);
+    ceiling.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_idempotent_requests_are_retried() {
+        let none = HeaderMap::new();
+        assert!(is_replay_safe(&Method::GET, &none));
+        assert!(is_replay_safe(&Method::PUT, &none));
+        assert!(is_replay_safe(&Method::DELETE, &none));
+        assert!(!is_replay_safe(&Method::POST, &none));
+        assert!(!is_replay_safe(&Method::PATCH, &none));
+
+        let mut keyed = HeaderMap::new();
+        keyed.insert(IDEMPOTENCY_KEY, "b7c1e2".parse().unwrap());
+        assert!(is_replay_safe(&Method::POST, &keyed));
+    }
+}
@@ -0,0 +1,162 @@
+//! Idle time: seconds since the last keyboard or mouse input, read from the OS (`GetLastInputInfo`
+//! on Windows, the XScreenSaver extension or `xprintidle` on X11, the HID event source on macOS),
+//! and the idle sessions made of it. Input idle for `IDLE_SESSION_SECS` opens a session starting at
+//! the last input; the next input closes it. Sessions are stored one JSON object per line in the
+//! `idle/` folder of the day they end.
+
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+
+use crate::models::{EyeCoreData, IdleSession, Provenance};
+
+/// Input idle this long starts an idle session (the point where presence turns `away`)
+pub const IDLE_SESSION_SECS: u32 = 300;
+
+/// Seconds since the last keyboard or mouse input in the session; None where it cannot be read
+pub fn read_idle_secs() -> Option<u32> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::Win32::System::SystemInformation::GetTickCount;
+        use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        unsafe {
+            if !GetLastInputInfo(&mut info).as_bool() {
+                return None;
+            }
+            // Both tick counts wrap after 49.7 days
+            Some(GetTickCount().wrapping_sub(info.dwTime) / 1000)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        screensaver::idle_millis().or_else(|| {
+            // Without the extension (or a display of our own); xprintidle reports milliseconds
+            let output = std::process::Command::new("xprintidle").output().ok()?;
+            String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+        })
+        .map(|millis: u64| (millis / 1000) as u32)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        #[link(name = "CoreGraphics", kind = "framework")]
+        extern "C" {
+            fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+        }
+        // kCGEventSourceStateCombinedSessionState, kCGAnyInputEventType
+        let seconds = unsafe { CGEventSourceSecondsSinceLastEventType(0, u32::MAX) };
+        (seconds.is_finite() && seconds >= 0.0).then_some(seconds as u32)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+/// Idle seconds of `data` when they were read from the OS or the input hooks, None when simulated
+pub fn measured_idle(data: &EyeCoreData) -> Option<u32> {
+    let measured = |field: &str| data.provenance.get(field) == Some(&Provenance::Measured);
+    (measured("input_metrics") || measured("input_metrics.idle_duration_seconds"))
+        .then_some(data.input_metrics.idle_duration_seconds)
+}
+
+/// Turns the idle time of successive snapshots into idle sessions
+pub struct IdleTracker {
+    threshold: Duration,
+    open: Option<OpenSession>,
+}
+
+struct OpenSession {
+    start: DateTime<Utc>,       // last input before the session
+    confirmed: DateTime<Utc>,   // latest snapshot that was still idle
+}
+
+impl IdleTracker {
+    pub fn new() -> Self {
+        IdleTracker {
+            threshold: Duration::seconds(IDLE_SESSION_SECS as i64),
+            open: None,
+        }
+    }
+
+    /// Record the idle time measured at `timestamp` (None when it was not measured), returning the
+    /// idle session that input has just ended, if any
+    pub fn observe(&mut self, timestamp: DateTime<Utc>, idle_seconds: Option<u32>) -> Option<IdleSession> {
+        let idle = Duration::seconds(idle_seconds? as i64);
+        let last_input = timestamp - idle;
+        // Input since the session started ends it; it is only known to have lasted to the last idle snapshot
+        let ended = self
+            .open
+            .take_if(|open| idle < self.threshold || last_input > open.start)
+            .map(|open| close(open.start, open.confirmed));
+        if idle >= self.threshold {
+            let open = self.open.get_or_insert(OpenSession { start: last_input, confirmed: timestamp });
+            open.confirmed = timestamp;
+        }
+        ended
+    }
+
+    /// The session still open, closed at the last idle snapshot (on shutdown)
+    pub fn finish(&mut self) -> Option<IdleSession> {
+        self.open.take().map(|open| close(open.start, open.confirmed))
+    }
+}
+
+fn close(start: DateTime<Utc>, end: DateTime<Utc>) -> IdleSession {
+    let session = IdleSession {
+        start,
+        end,
+        duration_seconds: (end - start).num_seconds().max(0) as u64,
+    };
+    info!("💤 Idle for {}s since {}", session.duration_seconds, start.format("%H:%M:%S"));
+    session
+}
+
+/// `XScreenSaverQueryInfo` on a display connection kept for the life of the process
+#[cfg(target_os = "linux")]
+mod screensaver {
+    use std::sync::{Mutex, OnceLock};
+    use x11::{xlib, xss};
+
+    struct Connection(*mut xlib::Display);
+
+    // The connection is only used under the mutex
+    unsafe impl Send for Connection {}
+
+    static CONNECTION: OnceLock<Option<Mutex<Connection>>> = OnceLock::new();
+
+    fn connect() -> Option<Mutex<Connection>> {
+        unsafe {
+            let display = xlib::XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+            let (mut event_base, mut error_base) = (0, 0);
+            if xss::XScreenSaverQueryExtension(display, &mut event_base, &mut error_base) == 0 {
+                xlib::XCloseDisplay(display);
+                return None;
+            }
+            Some(Mutex::new(Connection(display)))
+        }
+    }
+
+    pub fn idle_millis() -> Option<u64> {
+        let connection = CONNECTION.get_or_init(connect).as_ref()?.lock().ok()?;
+        unsafe {
+            let info = xss::XScreenSaverAllocInfo();
+            if info.is_null() {
+                return None;
+            }
+            let status = xss::XScreenSaverQueryInfo(connection.0, xlib::XDefaultRootWindow(connection.0), info);
+            let idle = (*info).idle as u64;
+            xlib::XFree(info.cast());
+            (status != 0).then_some(idle)
+        }
+    }
+}
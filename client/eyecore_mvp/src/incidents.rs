@@ -0,0 +1,76 @@
+//! Incident bundles: when the flag detector raises a Critical SecurityConcern, the events of the
+//! forensic input buffer (`forensic_buffer_secs`, see forensics) are written with the flag to
+//! `incidents/` of the flag's day. Bundles are always sealed, which is why the buffer needs
+//! `encryption` to be set, and are only served on the audited content endpoint
+//! `/content/incidents`.
+
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::forensics::ForensicBuffer;
+use crate::models::{FlagRecord, IncidentBundle};
+use crate::storage::DataStorage;
+
+/// Flags bundled, remembered so a flag is bundled once
+const MAX_REMEMBERED_FLAGS: usize = 1000;
+
+/// Whether `flag` gets an incident bundle
+pub fn is_incident(flag: &FlagRecord) -> bool {
+    flag.flag_type.name == "SecurityConcern" && flag.severity == "Critical"
+}
+
+/// Watches the flag detector's flags and bundles the buffer for each new incident
+pub struct IncidentWatcher {
+    buffer: Arc<ForensicBuffer>,
+    since: DateTime<Utc>,
+    bundled: HashSet<String>,
+}
+
+impl IncidentWatcher {
+    /// Flags raised before now are not bundled: their events have left the buffer
+    pub fn new(buffer: Arc<ForensicBuffer>) -> Self {
+        IncidentWatcher {
+            buffer,
+            since: Utc::now(),
+            bundled: HashSet::new(),
+        }
+    }
+
+    /// Bundle the buffer for incidents flagged since the previous poll
+    pub async fn poll(&mut self, storage: &DataStorage) {
+        let now = Utc::now();
+        // The detector writes a flag after the snapshot it is about, so look back over the window
+        let start = self.since - Duration::seconds(self.buffer.window_secs() as i64);
+        let flags = match storage.load_flags(start, now).await {
+            Ok(flags) => flags,
+            Err(e) => {
+                error!("Failed to read flags for incident bundles: {}", e);
+                return;
+            }
+        };
+        self.since = now;
+
+        for flag in flags.into_iter().filter(is_incident) {
+            if self.bundled.contains(&flag.id) {
+                continue;
+            }
+            if self.bundled.len() >= MAX_REMEMBERED_FLAGS {
+                self.bundled.clear();
+            }
+            self.bundled.insert(flag.id.clone());
+            let bundle = IncidentBundle {
+                id: uuid::Uuid::new_v4().to_string(),
+                captured_at: now,
+                window_secs: self.buffer.window_secs(),
+                events: self.buffer.events(),
+                flag,
+            };
+            match storage.save_incident_bundle(&bundle).await {
+                Ok(path) => info!("🧾 Incident bundle for {:?} with {} events: {:?}", bundle.flag.title, bundle.events.len(), path),
+                Err(e) => error!("Failed to save incident bundle for flag {}: {}", bundle.flag.id, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! The process-wide input hook (rdev: low-level hooks on Windows, XRecord on X11). rdev keeps one
+//! global callback, so every consumer of raw input (input counters, the bookmark hotkey) subscribes
+//! here instead of calling `rdev::listen` itself.
+
+use log::{info, warn};
+use rdev::{Event, EventType};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+type Subscriber = Box<dyn FnMut(&Event) + Send>;
+
+pub struct InputHooks {
+    started: Instant,
+    mouse_clicks: AtomicU32,
+    keyboard_events: AtomicU32,
+    last_input_ms: AtomicU64,      // since `started`
+    failed: AtomicBool,
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+/// Input counted since the previous `take`
+pub struct InputCounts {
+    pub mouse_clicks: u32,
+    pub keyboard_events: u32,
+    pub idle_seconds: u32,
+}
+
+static HOOKS: OnceLock<InputHooks> = OnceLock::new();
+
+/// The shared hook, installed on first use
+pub fn global() -> &'static InputHooks {
+    let mut installed = false;
+    let hooks = HOOKS.get_or_init(|| {
+        installed = true;
+        InputHooks {
+            started: Instant::now(),
+            mouse_clicks: AtomicU32::new(0),
+            keyboard_events: AtomicU32::new(0),
+            last_input_ms: AtomicU64::new(0),
+            failed: AtomicBool::new(false),
+            subscribers: Mutex::new(Vec::new()),
+        }
+    });
+    if installed {
+        // rdev blocks its thread for the lifetime of the hook
+        std::thread::spawn(|| {
+            let hooks = global();
+            let mut held = HashSet::new();
+            if let Err(e) = rdev::listen(move |event| hooks.record(&event, &mut held)) {
+                warn!("⚠️ Input hooks unavailable: {:?}", e);
+                hooks.failed.store(true, Ordering::Relaxed);
+            }
+        });
+        info!("✓ Input hooks installed");
+    }
+    hooks
+}
+
+impl InputHooks {
+    fn record(&self, event: &Event, held: &mut HashSet<rdev::Key>) {
+        match event.event_type {
+            // Holding a key auto-repeats its press; only the first one is a keystroke
+            EventType::KeyPress(key) if held.insert(key) => {
+                self.keyboard_events.fetch_add(1, Ordering::Relaxed);
+            }
+            EventType::KeyRelease(key) => {
+                held.remove(&key);
+            }
+            EventType::ButtonPress(_) => {
+                self.mouse_clicks.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+        self.last_input_ms.store(self.started.elapsed().as_millis() as u64, Ordering::Relaxed);
+
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.iter_mut().for_each(|subscriber| subscriber(event));
+        }
+    }
+
+    /// False once the OS refused the hook (no display server, missing permissions)
+    pub fn available(&self) -> bool {
+        !self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Clicks and key presses since the previous call, and seconds since the last input of any kind
+    pub fn take(&self) -> InputCounts {
+        InputCounts {
+            mouse_clicks: self.mouse_clicks.swap(0, Ordering::Relaxed),
+            keyboard_events: self.keyboard_events.swap(0, Ordering::Relaxed),
+            idle_seconds: self.idle_seconds(),
+        }
+    }
+
+    /// Seconds since the last input of any kind, without resetting the counters
+    pub fn idle_seconds(&self) -> u32 {
+        let since_start_ms = self.started.elapsed().as_millis() as u64;
+        (since_start_ms.saturating_sub(self.last_input_ms.load(Ordering::Relaxed)) / 1000) as u32
+    }
+
+    /// Call `subscriber` with every raw input event, on the hook thread; keep it short
+    pub fn subscribe(&self, subscriber: impl FnMut(&Event) + Send + 'static) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(Box::new(subscriber));
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Research input trace (`[input_trace]`): every keyboard and mouse event at full resolution, kept
+//! in compact binary files apart from the JSON snapshots. Keys are reduced to their class as in
+//! forensics and the pointer to movement deltas, so a trace holds timing and motion, never what was
+//! typed. Events are recorded only while the keystroke module has consent and is enabled, and at
+//! most `max_events_per_sec`; trace_files writes them out.
+//!
+//! File format (`.eytrace`): the 8 bytes `EYTRACE1`, the start time as a little-endian i64 of
+//! microseconds since the Unix epoch, then `TraceRecord`s in bincode's varint encoding, each timed
+//! in microseconds after the one before (the first after the start time).
+
+use log::info;
+use rdev::{Event, EventType};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::forensics::{button_name, key_class};
+use crate::models::InputTraceStatus;
+
+pub const FILE_MAGIC: &[u8; 8] = b"EYTRACE1";
+/// Key classes in the order `TraceEvent::KeyDown`/`KeyUp` number them
+pub const KEY_CLASSES: [&str; 9] =
+    ["letter", "digit", "whitespace", "punctuation", "editing", "modifier", "navigation", "function", "other"];
+/// Buttons in the order `TraceEvent::ButtonDown`/`ButtonUp` number them
+pub const BUTTONS: [&str; 4] = ["left", "right", "middle", "other"];
+/// Seconds of events held for the writer before further ones are dropped
+const PENDING_SECS: usize = 10;
+
+/// `[input_trace]` settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputTraceConfig {
+    pub enabled: bool,
+    pub max_file_mb: u64,          // size at which a new file is started
+    pub max_total_mb: u64,         // the oldest files are deleted beyond this
+    pub max_events_per_sec: u32,   // further events in the same second are dropped
+}
+
+impl Default for InputTraceConfig {
+    fn default() -> Self {
+        InputTraceConfig { enabled: false, max_file_mb: 16, max_total_mb: 256, max_events_per_sec: 1000 }
+    }
+}
+
+impl InputTraceConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_file_mb == 0 {
+            return Err("input_trace.max_file_mb must be at least 1".to_string());
+        }
+        if self.max_total_mb < self.max_file_mb {
+            return Err("input_trace.max_total_mb must be at least input_trace.max_file_mb".to_string());
+        }
+        if self.max_events_per_sec == 0 {
+            return Err("input_trace.max_events_per_sec must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// One event of a trace file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    pub delta_us: u64,
+    pub event: TraceEvent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TraceEvent {
+    KeyDown(u8),       // index into KEY_CLASSES
+    KeyUp(u8),
+    ButtonDown(u8),    // index into BUTTONS
+    ButtonUp(u8),
+    Move { dx: i32, dy: i32 },    // pixels since the previous move; the first of a file is the position
+    Wheel { dx: i32, dy: i32 },
+}
+
+#[derive(Default)]
+struct Pending {
+    events: Vec<(SystemTime, EventType)>,
+    second: u64,
+    in_second: u32,
+}
+
+#[derive(Default)]
+struct DiskUsage {
+    files: usize,
+    bytes: u64,
+    current: Option<PathBuf>,
+}
+
+pub struct InputTrace {
+    config: InputTraceConfig,
+    active: AtomicBool,
+    pending: Mutex<Pending>,
+    written: AtomicU64,
+    dropped: AtomicU64,
+    disk: Mutex<DiskUsage>,
+}
+
+impl InputTrace {
+    fn new(config: InputTraceConfig) -> Self {
+        InputTrace {
+            config,
+            active: AtomicBool::new(false),
+            pending: Mutex::new(Pending::default()),
+            written: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            disk: Mutex::new(DiskUsage::default()),
+        }
+    }
+
+    pub fn config(&self) -> &InputTraceConfig {
+        &self.config
+    }
+
+    /// Record only while active, i.e. while keystroke consent is granted; stopping drops what the
+    /// writer has not taken yet
+    pub fn set_active(&self, active: bool) {
+        if !self.active.swap(active, Ordering::Relaxed) || active {
+            return;
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.events.clear();
+        }
+    }
+
+    /// Events recorded since the previous call, oldest first
+    pub fn take(&self) -> Vec<(SystemTime, EventType)> {
+        self.pending.lock().map(|mut pending| std::mem::take(&mut pending.events)).unwrap_or_default()
+    }
+
+    /// Called by the writer once events are on disk
+    pub fn record_written(&self, events: usize, files: usize, bytes: u64, current: Option<PathBuf>) {
+        self.written.fetch_add(events as u64, Ordering::Relaxed);
+        if let Ok(mut disk) = self.disk.lock() {
+            *disk = DiskUsage { files, bytes, current };
+        }
+    }
+
+    pub fn status(&self) -> InputTraceStatus {
+        let disk = self.disk.lock();
+        let (files, bytes, current) = disk
+            .as_ref()
+            .map(|disk| (disk.files, disk.bytes, disk.current.as_ref().map(|path| path.display().to_string())))
+            .unwrap_or_default();
+        InputTraceStatus {
+            enabled: true,
+            recording: self.active.load(Ordering::Relaxed),
+            files,
+            total_bytes: bytes,
+            max_total_bytes: self.config.max_total_mb * 1024 * 1024,
+            current_file: current,
+            events_written: self.written.load(Ordering::Relaxed),
+            events_dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record(&self, event: &Event) {
+        if !self.active.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        let second = event.time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+        if pending.second != second {
+            pending.second = second;
+            pending.in_second = 0;
+        }
+        let limit = self.config.max_events_per_sec;
+        if pending.in_second >= limit || pending.events.len() >= limit as usize * PENDING_SECS {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        pending.in_second += 1;
+        pending.events.push((event.time, event.event_type));
+    }
+}
+
+/// The trace event for a raw input event; pointer moves are still absolute positions
+pub fn trace_event(event: EventType) -> TraceEvent {
+    let index = |names: &[&str], name: &str| names.iter().position(|candidate| *candidate == name).unwrap_or(names.len() - 1) as u8;
+    match event {
+        EventType::KeyPress(key) => TraceEvent::KeyDown(index(&KEY_CLASSES, key_class(key))),
+        EventType::KeyRelease(key) => TraceEvent::KeyUp(index(&KEY_CLASSES, key_class(key))),
+        EventType::ButtonPress(button) => TraceEvent::ButtonDown(index(&BUTTONS, button_name(button))),
+        EventType::ButtonRelease(button) => TraceEvent::ButtonUp(index(&BUTTONS, button_name(button))),
+        EventType::MouseMove { x, y } => TraceEvent::Move { dx: x.round() as i32, dy: y.round() as i32 },
+        EventType::Wheel { delta_x, delta_y } => TraceEvent::Wheel {
+            dx: delta_x.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+            dy: delta_y.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+        },
+    }
+}
+
+/// Start recording from the process-wide hook (inactive until keystroke consent is applied)
+pub fn start(config: InputTraceConfig) -> Arc<InputTrace> {
+    let trace = Arc::new(InputTrace::new(config));
+    let recorder = Arc::clone(&trace);
+    crate::input_hooks::global().subscribe(move |event| recorder.record(event));
+    info!(
+        "🧬 Input trace: up to {} events/s, {} MB files, {} MB in total",
+        trace.config.max_events_per_sec, trace.config.max_file_mb, trace.config.max_total_mb
+    );
+    trace
+}
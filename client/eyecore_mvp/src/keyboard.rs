@@ -0,0 +1,217 @@
+use crate::models::KeyboardLayout;
+
+#[cfg(target_os = "linux")]
+use std::sync::{Arc, Mutex};
+#[cfg(target_os = "linux")]
+use std::time::{Duration, Instant};
+
+// Layouts are switched by hotkey, so they go stale quickly; reading one starts a process on Linux
+#[cfg(target_os = "linux")]
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct CachedLayout {
+    layout: Option<KeyboardLayout>,
+    refreshed: Option<Instant>,
+    refreshing: bool,
+}
+
+/// Keyboard layout of the keystroke samples. On Windows it is a cheap per-thread lookup made
+/// every time; on Linux it is re-read in the background at most every ten seconds.
+#[derive(Clone, Default)]
+pub struct LayoutCache {
+    #[cfg(target_os = "linux")]
+    inner: Arc<Mutex<CachedLayout>>,
+}
+
+impl LayoutCache {
+    /// Keyboard layout and input language the user is typing with
+    pub fn layout(&self) -> Option<KeyboardLayout> {
+        #[cfg(target_os = "windows")]
+        {
+            read_foreground_layout()
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let mut cached = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            let stale = cached.refreshed.is_none_or(|refreshed| refreshed.elapsed() >= REFRESH_INTERVAL);
+            if stale && !cached.refreshing {
+                if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                    cached.refreshing = true;
+                    let inner = self.inner.clone();
+                    runtime.spawn(async move {
+                        let layout = read_session_layout().await;
+                        let mut cached = inner.lock().unwrap_or_else(|e| e.into_inner());
+                        cached.layout = layout;
+                        cached.refreshed = Some(Instant::now());
+                        cached.refreshing = false;
+                    });
+                }
+            }
+            cached.layout.clone()
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        {
+            None
+        }
+    }
+}
+
+/// Layout and input language of the foreground window
+#[cfg(target_os = "windows")]
+fn read_foreground_layout() -> Option<KeyboardLayout> {
+    use windows::Win32::Globalization::{LCIDToLocaleName, LOCALE_NAME_MAX_LENGTH};
+    use windows::Win32::UI::Input::KeyboardAndMouse::GetKeyboardLayout;
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    unsafe {
+        // Layouts are per thread, so ask for the one the user is typing into
+        let thread = GetWindowThreadProcessId(GetForegroundWindow(), None);
+        let hkl = GetKeyboardLayout(thread).0 as usize as u32;
+        if hkl == 0 {
+            return None;
+        }
+
+        // Low word is the input language, high word the physical layout
+        let mut name = [0u16; LOCALE_NAME_MAX_LENGTH as usize];
+        let len = LCIDToLocaleName(hkl & 0xFFFF, Some(&mut name), 0);
+        let language = (len > 1).then(|| String::from_utf16_lossy(&name[..len as usize - 1]));
+
+        Some(KeyboardLayout {
+            layout_id: format!("{:08X}", hkl),
+            language,
+        })
+    }
+}
+
+/// XKB layout of the session: setxkbmap on X11; on Wayland, where setxkbmap only sees
+/// XWayland's default, the GNOME or KDE input sources and then the system default
+#[cfg(target_os = "linux")]
+async fn read_session_layout() -> Option<KeyboardLayout> {
+    let layout_id = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut gnome = None;
+        for key in ["mru-sources", "sources"] {
+            let sources = command_output("gsettings", &["get", "org.gnome.desktop.input-sources", key]).await;
+            gnome = sources.and_then(|sources| parse_gsettings_sources(&sources));
+            if gnome.is_some() {
+                break;
+            }
+        }
+        match gnome {
+            Some(layout) => Some(layout),
+            None => read_kde_layout().await.or_else(default_layout),
+        }
+    } else {
+        command_output("setxkbmap", &["-query"])
+            .await
+            .and_then(|query| parse_setxkbmap(&query))
+    };
+    layout_id.map(|layout_id| KeyboardLayout { layout_id, language: None })
+}
+
+#[cfg(target_os = "linux")]
+async fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "linux")]
+async fn read_kde_layout() -> Option<String> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".config")))?
+        .join("kxkbrc");
+    parse_kxkbrc(&tokio::fs::read_to_string(config).await.ok()?)
+}
+
+/// Layout configured for the system (Debian's keyboard file), or by XKB_DEFAULT_LAYOUT
+#[cfg(target_os = "linux")]
+fn default_layout() -> Option<String> {
+    if let Ok(layout) = std::env::var("XKB_DEFAULT_LAYOUT") {
+        return xkb_id(&layout, std::env::var("XKB_DEFAULT_VARIANT").ok().as_deref());
+    }
+    parse_default_keyboard(&std::fs::read_to_string("/etc/default/keyboard").ok()?)
+}
+
+/// `de(nodeadkeys)` from the first of possibly several comma-separated layouts and variants
+fn xkb_id(layouts: &str, variants: Option<&str>) -> Option<String> {
+    let layout = layouts.split(',').next()?.trim();
+    if layout.is_empty() {
+        return None;
+    }
+    match variants.and_then(|v| v.split(',').next()).map(str::trim).filter(|v| !v.is_empty()) {
+        Some(variant) => Some(format!("{}({})", layout, variant)),
+        None => Some(layout.to_string()),
+    }
+}
+
+/// `setxkbmap -query` lists the configured layouts, the first being the default group
+pub fn parse_setxkbmap(query: &str) -> Option<String> {
+    let field = |key: &str| query.lines().find_map(|line| line.strip_prefix(key)).map(str::trim);
+    xkb_id(field("layout:")?, field("variant:"))
+}
+
+/// GNOME input sources, e.g. `[('xkb', 'de+nodeadkeys'), ('ibus', 'mozc-jp')]`; the first
+/// XKB source (of `mru-sources`, the current one first)
+pub fn parse_gsettings_sources(sources: &str) -> Option<String> {
+    sources
+        .split("('xkb', '")
+        .nth(1)?
+        .split('\'')
+        .next()
+        .and_then(|source| {
+            let mut parts = source.splitn(2, '+');
+            xkb_id(parts.next()?, parts.next())
+        })
+}
+
+/// KDE's `kxkbrc`: `LayoutList=us,de` and `VariantList=,nodeadkeys` under `[Layout]`
+pub fn parse_kxkbrc(config: &str) -> Option<String> {
+    let field = |key: &str| config.lines().find_map(|line| line.trim().strip_prefix(key));
+    xkb_id(field("LayoutList=")?, field("VariantList="))
+}
+
+/// `/etc/default/keyboard`: `XKBLAYOUT="de"` and `XKBVARIANT="nodeadkeys"`
+pub fn parse_default_keyboard(config: &str) -> Option<String> {
+    let field = |key: &str| {
+        config
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(key))
+            .map(|value| value.trim().trim_matches('"'))
+    };
+    xkb_id(field("XKBLAYOUT=")?, field("XKBVARIANT="))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setxkbmap_query() {
+        let query = "rules:      evdev\nmodel:      pc105\nlayout:     de,us\nvariant:    nodeadkeys,\n";
+        assert_eq!(parse_setxkbmap(query).as_deref(), Some("de(nodeadkeys)"));
+        assert_eq!(parse_setxkbmap("layout:     us\n").as_deref(), Some("us"));
+        assert_eq!(parse_setxkbmap("rules:      evdev\n"), None);
+    }
+
+    #[test]
+    fn wayland_sources() {
+        let gnome = "[('ibus', 'mozc-jp'), ('xkb', 'de+nodeadkeys'), ('xkb', 'us')]\n";
+        assert_eq!(parse_gsettings_sources(gnome).as_deref(), Some("de(nodeadkeys)"));
+        assert_eq!(parse_gsettings_sources("@a(ss) []\n"), None);
+
+        let kde = "[Layout]\nDisplayNames=,\nLayoutList=fr,us\nUse=true\nVariantList=azerty,\n";
+        assert_eq!(parse_kxkbrc(kde).as_deref(), Some("fr(azerty)"));
+
+        let debian = "XKBMODEL=\"pc105\"\nXKBLAYOUT=\"gb\"\nXKBVARIANT=\"\"\nXKBOPTIONS=\"\"\n";
+        assert_eq!(parse_default_keyboard(debian).as_deref(), Some("gb"));
+    }
+}
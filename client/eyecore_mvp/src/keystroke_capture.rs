@@ -0,0 +1,385 @@
+//! Real keystroke capture for `keystroke_dynamics`, replacing the simulated typing figures and sample
+//! text. It is off unless the binary is built with `--features keystroke-capture` and
+//! `keystroke_capture` is set to `"timing"` (when keys go down and come up, never which) or
+//! `"content"` (also the text typed); and it records only while keystroke consent is granted. Text
+//! needs its own `typed_text` consent as well: without it, content capture records timing only.
+//!
+//! Keys come from the process-wide hook in input_hooks (a low-level `SetWindowsHookEx` hook on
+//! Windows, a `CGEventTap` on macOS, XRecord on X11). On Linux the keyboards under `/dev/input` are
+//! read directly (evdev) when the user may open them, which also covers Wayland and the console.
+
+// Without the feature nothing feeds the capture
+#![cfg_attr(not(feature = "keystroke-capture"), allow(dead_code))]
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Presses kept between collections; older ones are dropped
+const MAX_PRESSES: usize = 10_000;
+/// Characters of typed text kept between collections
+const MAX_TEXT_CHARS: usize = 10_000;
+/// Gaps longer than this are pauses, not the interval between two keys
+const MAX_KEY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What `keystroke_capture` records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMode {
+    Off,
+    Timing,    // key down and up times only
+    Content,   // timing and the text typed
+}
+
+/// A key going down, as the hook reports it
+enum Typed {
+    Text(String),
+    Backspace,
+    Nothing,   // modifiers, navigation and the like
+}
+
+#[derive(Default)]
+struct CaptureState {
+    held: HashMap<u64, Instant>,
+    presses: Vec<Instant>,
+    holds_ms: Vec<f32>,
+    corrections: u32,
+    text: String,
+}
+
+/// Keystrokes since the previous `take`
+pub struct CapturedKeystrokes {
+    pub presses: Vec<Instant>,
+    pub holds_ms: Vec<f32>,
+    pub corrections: u32,          // backspaces
+    pub text: Option<String>,      // None when capturing timing only, or nothing was typed
+    pub elapsed: Duration,
+}
+
+pub struct KeystrokeCapture {
+    mode: CaptureMode,
+    active: AtomicBool,
+    text_allowed: AtomicBool,
+    state: Mutex<CaptureState>,
+    since: Mutex<Instant>,
+}
+
+impl KeystrokeCapture {
+    fn new(mode: CaptureMode) -> Self {
+        KeystrokeCapture {
+            mode,
+            active: AtomicBool::new(false),
+            text_allowed: AtomicBool::new(false),
+            state: Mutex::new(CaptureState::default()),
+            since: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn mode(&self) -> CaptureMode {
+        self.mode
+    }
+
+    /// Record only while active, i.e. while keystroke consent is granted; stopping discards what
+    /// was not collected yet
+    pub fn set_active(&self, active: bool) {
+        if !self.active.swap(active, Ordering::Relaxed) || active {
+            return;
+        }
+        if let Ok(mut state) = self.state.lock() {
+            *state = CaptureState::default();
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Record typed text (in content mode) only while typed text consent is granted; withdrawing
+    /// it discards the text not collected yet
+    pub fn set_text_allowed(&self, allowed: bool) {
+        if self.text_allowed.swap(allowed, Ordering::Relaxed) && !allowed {
+            if let Ok(mut state) = self.state.lock() {
+                state.text.clear();
+            }
+        }
+    }
+
+    /// Whether typed text is recorded: content mode and typed text consent
+    pub fn records_text(&self) -> bool {
+        self.mode == CaptureMode::Content && self.text_allowed.load(Ordering::Relaxed)
+    }
+
+    /// Keystrokes since the previous call
+    pub fn take(&self) -> CapturedKeystrokes {
+        let state = self.state.lock().map(|mut state| std::mem::take(&mut *state)).unwrap_or_default();
+        let elapsed = self
+            .since
+            .lock()
+            .map(|mut since| std::mem::replace(&mut *since, Instant::now()).elapsed())
+            .unwrap_or_default();
+        CapturedKeystrokes {
+            presses: state.presses,
+            holds_ms: state.holds_ms,
+            corrections: state.corrections,
+            text: (self.records_text() && !state.text.is_empty()).then_some(state.text),
+            elapsed,
+        }
+    }
+
+    fn press(&self, key: u64, typed: Typed) {
+        if !self.is_active() {
+            return;
+        }
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let now = Instant::now();
+        // Holding a key auto-repeats its press; only the first one is a keystroke
+        if state.held.insert(key, now).is_some() {
+            return;
+        }
+        if state.presses.len() < MAX_PRESSES {
+            state.presses.push(now);
+        }
+        match typed {
+            Typed::Backspace => {
+                state.corrections += 1;
+                if self.records_text() {
+                    state.text.pop();
+                }
+            }
+            Typed::Text(text) if self.records_text() && state.text.chars().count() < MAX_TEXT_CHARS => {
+                state.text.push_str(&text);
+            }
+            _ => {}
+        }
+    }
+
+    fn release(&self, key: u64) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if let Some(pressed) = state.held.remove(&key) {
+            if state.holds_ms.len() < MAX_PRESSES {
+                state.holds_ms.push(pressed.elapsed().as_secs_f32() * 1000.0);
+            }
+        }
+    }
+}
+
+impl CapturedKeystrokes {
+    /// Milliseconds between consecutive presses, leaving out pauses
+    pub fn intervals_ms(&self) -> Vec<f32> {
+        self.presses
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .filter(|gap| *gap <= MAX_KEY_INTERVAL)
+            .map(|gap| gap.as_secs_f32() * 1000.0)
+            .collect()
+    }
+}
+
+/// Start capturing in `mode` from the platform's keyboard hook. Capture stays inactive until
+/// `set_active`.
+#[cfg(feature = "keystroke-capture")]
+pub fn start(mode: CaptureMode) -> Result<std::sync::Arc<KeystrokeCapture>, String> {
+    if mode == CaptureMode::Off {
+        return Err("keystroke_capture is off".to_string());
+    }
+    let capture = std::sync::Arc::new(KeystrokeCapture::new(mode));
+    #[cfg(target_os = "linux")]
+    if evdev::start(std::sync::Arc::clone(&capture)) {
+        return Ok(capture);
+    }
+    hook::start(std::sync::Arc::clone(&capture));
+    Ok(capture)
+}
+
+#[cfg(not(feature = "keystroke-capture"))]
+pub fn start(_mode: CaptureMode) -> Result<std::sync::Arc<KeystrokeCapture>, String> {
+    Err("keystroke capture not compiled in (build with --features keystroke-capture)".to_string())
+}
+
+/// Keys from the shared rdev hook
+#[cfg(feature = "keystroke-capture")]
+mod hook {
+    use super::{KeystrokeCapture, Typed};
+    use crate::input_hooks;
+    use rdev::{EventType, Key};
+    use std::hash::{Hash, Hasher};
+    use std::sync::Arc;
+
+    fn key_id(key: &Key) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn start(capture: Arc<KeystrokeCapture>) {
+        log::info!("⌨️ Keystroke capture ({:?}) from the input hook", capture.mode);
+        input_hooks::global().subscribe(move |event| match event.event_type {
+            EventType::KeyPress(key) => {
+                let typed = match (key, &event.name) {
+                    (Key::Backspace, _) => Typed::Backspace,
+                    (Key::Return | Key::KpReturn, _) => Typed::Text("\n".to_string()),
+                    // Control characters come with Ctrl shortcuts and are not text
+                    (_, Some(name)) if !name.chars().any(char::is_control) => Typed::Text(name.clone()),
+                    _ => Typed::Nothing,
+                };
+                capture.press(key_id(&key), typed);
+            }
+            EventType::KeyRelease(key) => capture.release(key_id(&key)),
+            _ => {}
+        });
+    }
+}
+
+/// Keyboards read from `/dev/input/by-path/*-event-kbd`, one thread each
+#[cfg(all(feature = "keystroke-capture", target_os = "linux"))]
+mod evdev {
+    use super::{KeystrokeCapture, Typed};
+    use log::{info, warn};
+    use std::io::Read;
+    use std::sync::Arc;
+
+    // struct input_event: a timeval (two longs), then type, code and value
+    const TIME_LEN: usize = 2 * std::mem::size_of::<std::ffi::c_long>();
+    const EVENT_LEN: usize = TIME_LEN + 8;
+    const EV_KEY: u16 = 1;
+    const KEY_BACKSPACE: u16 = 14;
+    const KEY_ENTER: u16 = 28;
+    const KEY_LEFTSHIFT: u16 = 42;
+    const KEY_RIGHTSHIFT: u16 = 54;
+    const KEY_SPACE: u16 = 57;
+    const KEY_KPENTER: u16 = 96;
+
+    /// Characters for the keys of a US layout from KEY_1 (2) to KEY_SLASH (53), unshifted and shifted;
+    /// evdev reports physical keys, so other layouts come out as their US equivalents
+    const US_KEYS: [(char, char); 52] = [
+        ('1', '!'), ('2', '@'), ('3', '#'), ('4', '$'), ('5', '%'), ('6', '^'), ('7', '&'), ('8', '*'),
+        ('9', '('), ('0', ')'), ('-', '_'), ('=', '+'), ('\0', '\0'), ('\0', '\0'), ('q', 'Q'), ('w', 'W'),
+        ('e', 'E'), ('r', 'R'), ('t', 'T'), ('y', 'Y'), ('u', 'U'), ('i', 'I'), ('o', 'O'), ('p', 'P'),
+        ('[', '{'), (']', '}'), ('\0', '\0'), ('\0', '\0'), ('a', 'A'), ('s', 'S'), ('d', 'D'), ('f', 'F'),
+        ('g', 'G'), ('h', 'H'), ('j', 'J'), ('k', 'K'), ('l', 'L'), (';', ':'), ('\'', '"'), ('`', '~'),
+        ('\0', '\0'), ('\\', '|'), ('z', 'Z'), ('x', 'X'), ('c', 'C'), ('v', 'V'), ('b', 'B'), ('n', 'N'),
+        ('m', 'M'), (',', '<'), ('.', '>'), ('/', '?'),
+    ];
+
+    fn typed(code: u16, shift: bool) -> Typed {
+        match code {
+            KEY_BACKSPACE => Typed::Backspace,
+            KEY_ENTER | KEY_KPENTER => Typed::Text("\n".to_string()),
+            KEY_SPACE => Typed::Text(" ".to_string()),
+            2..=53 => match US_KEYS[code as usize - 2] {
+                ('\0', _) => Typed::Nothing,
+                (plain, shifted) => Typed::Text(if shift { shifted } else { plain }.to_string()),
+            },
+            _ => Typed::Nothing,
+        }
+    }
+
+    /// Read every keyboard that can be opened; false if there is none
+    pub fn start(capture: Arc<KeystrokeCapture>) -> bool {
+        let Ok(entries) = std::fs::read_dir("/dev/input/by-path") else {
+            return false;
+        };
+        let keyboards: Vec<_> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.to_str().is_some_and(|path| path.ends_with("-event-kbd")))
+            .collect();
+        let mut opened = 0;
+        for path in keyboards {
+            match std::fs::File::open(&path) {
+                Ok(device) => {
+                    let capture = Arc::clone(&capture);
+                    std::thread::spawn(move || read_keyboard(device, &capture));
+                    opened += 1;
+                }
+                Err(e) => warn!("⚠️ Cannot read keyboard {:?} ({}), add the user to the input group", path, e),
+            }
+        }
+        if opened > 0 {
+            info!("⌨️ Keystroke capture ({:?}) from {} evdev keyboard(s)", capture.mode, opened);
+        }
+        opened > 0
+    }
+
+    fn read_keyboard(mut device: std::fs::File, capture: &KeystrokeCapture) {
+        let mut event = [0u8; EVENT_LEN];
+        let mut shifts = 0u8;
+        while device.read_exact(&mut event).is_ok() {
+            let kind = u16::from_ne_bytes([event[TIME_LEN], event[TIME_LEN + 1]]);
+            let code = u16::from_ne_bytes([event[TIME_LEN + 2], event[TIME_LEN + 3]]);
+            let value = i32::from_ne_bytes(event[TIME_LEN + 4..].try_into().expect("four bytes"));
+            if kind != EV_KEY {
+                continue;
+            }
+            let shift = 1u8 << (code == KEY_RIGHTSHIFT) as u8;
+            // 1 is a press, 0 a release, 2 an auto-repeat
+            match value {
+                1 if code == KEY_LEFTSHIFT || code == KEY_RIGHTSHIFT => {
+                    shifts |= shift;
+                    capture.press(code as u64, Typed::Nothing);
+                }
+                0 if code == KEY_LEFTSHIFT || code == KEY_RIGHTSHIFT => {
+                    shifts &= !shift;
+                    capture.release(code as u64);
+                }
+                1 => capture.press(code as u64, typed(code, shifts != 0)),
+                0 => capture.release(code as u64),
+                _ => {}
+            }
+        }
+        warn!("⚠️ Keyboard disconnected, no longer captured");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_word(capture: &KeystrokeCapture) {
+        for (key, letter) in ["h", "i"].iter().enumerate() {
+            capture.press(key as u64, Typed::Text(letter.to_string()));
+            capture.release(key as u64);
+        }
+    }
+
+    #[test]
+    fn content_mode_needs_typed_text_consent() {
+        let capture = KeystrokeCapture::new(CaptureMode::Content);
+        capture.set_active(true);
+        type_word(&capture);
+        let timing_only = capture.take();
+        assert_eq!(timing_only.presses.len(), 2);
+        assert_eq!(timing_only.text, None);
+
+        capture.set_text_allowed(true);
+        type_word(&capture);
+        assert_eq!(capture.take().text.as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn withdrawing_typed_text_consent_discards_pending_text() {
+        let capture = KeystrokeCapture::new(CaptureMode::Content);
+        capture.set_active(true);
+        capture.set_text_allowed(true);
+        type_word(&capture);
+        capture.set_text_allowed(false);
+        let captured = capture.take();
+        assert_eq!(captured.presses.len(), 2);
+        assert_eq!(captured.text, None);
+    }
+
+    #[test]
+    fn timing_mode_never_records_text() {
+        let capture = KeystrokeCapture::new(CaptureMode::Timing);
+        capture.set_active(true);
+        capture.set_text_allowed(true);
+        type_word(&capture);
+        assert_eq!(capture.take().text, None);
+    }
+}
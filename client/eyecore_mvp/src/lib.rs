@@ -0,0 +1,89 @@
+//! EyeCore's collection engine, storage and local API. The `eyecore_mvp` binary runs them as a
+//! service; with `--features ffi` the same modules are also exported through the C ABI in `ffi`.
+
+// The monitors' `new()` probes the platform, which a `Default` impl would hide
+#![allow(clippy::new_without_default)]
+
+pub mod data_collector;
+pub mod models;
+pub mod api;
+pub mod utils;
+pub mod storage;
+pub mod voice;
+pub mod audio_cleaner;
+pub mod audio_pipeline;
+pub mod websocket_client;
+pub mod version;
+pub mod updater;
+pub mod timeline;
+pub mod reports;
+pub mod discovery;
+pub mod team;
+pub mod query;
+pub mod privacy;
+pub mod compliance;
+pub mod heart_rate;
+pub mod display;
+pub mod ergonomics;
+pub mod capture_log;
+pub mod meeting;
+pub mod http;
+pub mod access;
+pub mod cli;
+pub mod sampling;
+pub mod keyboard;
+pub mod bookmarks;
+pub mod schedule;
+pub mod bulk_read;
+pub mod burst;
+pub mod attribution;
+pub mod notifications;
+pub mod switches;
+pub mod presence;
+pub mod media;
+pub mod routing;
+pub mod wifi;
+pub mod docking;
+pub mod input_hooks;
+pub mod onboarding;
+pub mod consent;
+pub mod calibration;
+pub mod system_sampler;
+pub mod active_window;
+pub mod power;
+pub mod config;
+pub mod bundle;
+pub mod module_control;
+pub mod shutdown;
+pub mod maintenance;
+pub mod soak;
+pub mod classification;
+pub mod app_rules;
+pub mod redaction;
+pub mod comparison;
+pub mod keystroke_capture;
+pub mod download;
+pub mod idle;
+pub mod forensics;
+pub mod incidents;
+pub mod emotion;
+pub mod connections;
+pub mod gaze;
+pub mod process_resources;
+pub mod collectors;
+pub mod permissions;
+pub mod input_trace;
+pub mod trace_files;
+pub mod upload_queue;
+pub mod projects;
+pub mod goals;
+pub mod time_export;
+pub mod remote_commands;
+pub mod upload_batch;
+pub mod capabilities;
+pub mod flags;
+pub mod share;
+pub mod flag_pipeline;
+pub mod scheduler;
+#[cfg(feature = "ffi")]
+pub mod ffi;
@@ -1,23 +1,23 @@
-mod data_collector;
-mod models;
-mod api;
-mod utils;
-mod storage;
-mod voice;
-mod audio_cleaner;
-mod websocket_client;
+use eyecore::{
+    data_collector, models, api, storage, voice, audio_pipeline, websocket_client, version, updater,
+    timeline, reports, discovery, team, privacy, compliance, heart_rate, ergonomics, meeting, http,
+    access, cli, bookmarks, schedule, burst, onboarding, consent, calibration, power,
+    config, module_control, shutdown, maintenance, redaction, keystroke_capture, idle, forensics,
+    incidents, emotion, gaze, permissions, input_trace, trace_files, upload_queue, goals,
+    time_export, remote_commands, share, flag_pipeline, scheduler,
+};
 
 use axum::{
-    routing::get,
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
 use std::sync::Arc;
-use tokio::sync::{RwLock, mpsc};
+use tokio::sync::{broadcast, RwLock, mpsc};
 use log::info;
 use std::path::PathBuf;
 use models::EyeCoreData;
 use serde_json::json;
-use uuid::Uuid;
 
 /// Detect system anomalies from collected data
 fn detect_system_anomalies(data: &EyeCoreData) -> Vec<serde_json::Value> {
@@ -42,8 +42,9 @@ fn detect_system_anomalies(data: &EyeCoreData) -> Vec<serde_json::Value> {
         }));
     }
     
-    // Check focus metrics
-    if data.focus_metrics.focus_level < 0.3 {
+    // Check focus metrics (low input activity is expected while on a call)
+    let in_meeting = data.meeting.as_ref().is_some_and(|m| m.in_meeting);
+    if data.focus_metrics.focus_level < 0.3 && !in_meeting {
         anomalies.push(json!({
             "type": "low_focus",
             "value": data.focus_metrics.focus_level,
@@ -67,47 +68,420 @@ fn detect_system_anomalies(data: &EyeCoreData) -> Vec<serde_json::Value> {
     anomalies
 }
 
+/// True when every address `bind_addr` resolves to is a loopback address
+fn is_loopback(bind_addr: &str) -> bool {
+    use std::net::ToSocketAddrs;
+    bind_addr
+        .to_socket_addrs()
+        .map(|mut addrs| addrs.all(|addr| addr.ip().is_loopback()))
+        .unwrap_or(false)
+}
+
+/// Bind the API port. An instance started by an update waits up to 30 seconds for the previous
+/// one to release it instead of failing.
+async fn bind_listener(bind_addr: &str) -> std::io::Result<tokio::net::TcpListener> {
+    const RETRY: std::time::Duration = std::time::Duration::from_millis(250);
+    let mut attempts = if std::env::var_os(updater::RESTARTED_ENV).is_some() { 120 } else { 0 };
+    loop {
+        match tokio::net::TcpListener::bind(bind_addr).await {
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && attempts > 0 => {
+                attempts -= 1;
+                tokio::time::sleep(RETRY).await;
+            }
+            result => return result,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     env_logger::init();
     
+    // Freshly installed binaries are verified by the updater before going live
+    if std::env::args().any(|arg| arg == "--selftest") {
+        std::process::exit(updater::run_selftest().await);
+    }
+    
+    // Tooling commands: one-shot collection (`collect --once`), schema checks (`validate <path>`),
+    // the snapshot scan and soak benchmarks (`bench-scan <data-dir>`, `bench-soak`), configuration
+    // bundles (`config ...`) and maintenance mode (`maintenance on|off|status`)
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("collect") {
+        std::process::exit(cli::run_collect(&args[2..]).await);
+    }
+    if args.get(1).map(String::as_str) == Some("validate") {
+        std::process::exit(cli::run_validate(&args[2..]));
+    }
+    if args.get(1).map(String::as_str) == Some("bench-scan") {
+        std::process::exit(cli::run_bench_scan(&args[2..]).await);
+    }
+    if args.get(1).map(String::as_str) == Some("bench-soak") {
+        std::process::exit(cli::run_bench_soak(&args[2..]).await);
+    }
+    if args.get(1).map(String::as_str) == Some("config") {
+        std::process::exit(cli::run_config(&args[2..]).await);
+    }
+    if args.get(1).map(String::as_str) == Some("maintenance") {
+        std::process::exit(cli::run_maintenance(&args[2..]).await);
+    }
+    
+    // eyecore.toml (or EYECORE_CONFIG) with EYECORE_* overrides; a broken config file stops startup
+    let config = Arc::new(config::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }));
+    let data_dir = config.config.data_dir.as_str();
+    
+    // Configured device ID, or the one generated on first run
+    let device_id = match &config.config.device_id {
+        Some(device_id) => device_id.clone(),
+        None => storage::load_or_create_device_id(data_dir)
+            .await
+            .expect("Failed to load device ID"),
+    };
+    
+    // One-off move of data written before per-day sharding (`eyecore_mvp migrate-storage`)
+    if std::env::args().nth(1).as_deref() == Some("migrate-storage") {
+        let mut storage = storage::DataStorage::new(data_dir, &device_id);
+        // `migrate-storage sqlite` imports the JSON files into the SQLite backend instead
+        if std::env::args().nth(2).as_deref() == Some("sqlite") {
+            let imported = match storage.attach_sqlite() {
+                Ok(()) => storage.import_into_sqlite().await,
+                Err(e) => Err(e),
+            };
+            match imported {
+                Ok(imported) => {
+                    for (table, rows) in imported {
+                        println!("Imported {} {}", rows, table);
+                    }
+                    std::process::exit(0);
+                }
+                Err(e) => {
+                    eprintln!("SQLite import failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        match storage.migrate_legacy_layout().await {
+            Ok(moved) => {
+                println!("Migrated {} files into {:?}", moved, storage.device_dir());
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("Storage migration failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    
     info!("🔍 EyeCore MVP Starting...");
     
-    // Generate or load device ID
-    let device_id = Uuid::new_v4().to_string();
     info!("📱 Device ID: {}", device_id);
     
     // Initialize data storage
-    let storage = Arc::new(storage::DataStorage::new("./data"));
+    let mut storage = storage::DataStorage::new(data_dir, &device_id);
     storage.initialize().await.expect("Failed to initialize data storage");
+    share::remove_expired(&storage).await;
+    if config.config.storage_backend == config::StorageBackend::Sqlite {
+        storage.attach_sqlite().expect("Failed to open SQLite storage");
+    }
+    if config.config.compression == config::Compression::Zstd {
+        storage.enable_compression();
+    }
+    if config.config.snapshot_format == config::SnapshotFormat::Ndjson {
+        storage.enable_snapshot_log(config.config.snapshot_log_max_mb * 1024 * 1024);
+    }
+    // The keyring is loaded whenever it exists, so files sealed earlier stay readable with encryption off
+    let key_file = config.config.encryption_key_file.as_ref().map_or_else(
+        || storage.device_dir().join(storage::encryption::DEFAULT_KEY_FILE),
+        std::path::PathBuf::from,
+    );
+    let encrypt = config.config.encryption != config::Encryption::None;
+    if encrypt || key_file.exists() {
+        let keyring = storage::encryption::Keyring::load_or_create(&key_file).expect("Failed to load the encryption keyring");
+        if encrypt {
+            info!("🔐 Encrypting stored data with key {} from {:?}", keyring.active_key(), key_file);
+        }
+        storage.attach_keyring(keyring, encrypt);
+    }
+    if config.config.metrics_mirror {
+        storage.enable_metrics_mirror();
+        info!("📈 Mirroring snapshot metrics unencrypted to metrics/");
+    }
+    // Personal data in captured text is masked by the collector and again before it is written
+    let redactor = Arc::new(redaction::Redactor::new(&config.config.redaction).expect("redaction rules are validated with the config"));
+    storage.set_redactor(Arc::clone(&redactor));
+    let storage = Arc::new(storage);
     
     // Initialize data collector
-    let collector = Arc::new(RwLock::new(data_collector::DataCollector::new()));
+    let mut collector = data_collector::DataCollector::new();
+    collector.set_history_capacity(config.config.history_size);
+    collector.set_redactor(redactor);
+    collector.set_top_processes(config.config.top_processes);
+    for name in collector.collectors_mut().configure(&config.config.collectors) {
+        log::warn!("⚠️ Ignoring [collectors.{}]: no such collector", name);
+    }
+    if config.config.compare_collectors {
+        collector.set_compare_collectors(true);
+        info!("⚖️ Comparing measured collectors with their simulations, see /data/comparison");
+    }
+    let collector = Arc::new(RwLock::new(collector));
+    collector.write().await.attach_network_labels(&device_id, storage.device_dir().join("network_labels.json"));
+    collector.write().await.attach_app_classification(&storage.device_dir());
+    collector.write().await.attach_projects(&storage.device_dir());
     
-    // Shared latest data for WebSocket client
-    let latest_data: Arc<RwLock<Option<EyeCoreData>>> = Arc::new(RwLock::new(None));
+    // Wearable heart rate: "bridge" accepts pushed samples, "ble" also connects to a strap
+    let heart_rate = match std::env::var("EYECORE_HEART_RATE").as_deref() {
+        Ok("bridge") | Ok("ble") => {
+            let monitor = Arc::new(heart_rate::HeartRateMonitor::new());
+            collector.write().await.attach_heart_rate(Arc::clone(&monitor));
+            #[cfg(feature = "heart-rate-ble")]
+            if std::env::var("EYECORE_HEART_RATE").as_deref() == Ok("ble") {
+                heart_rate::ble::start(Arc::clone(&monitor));
+            }
+            #[cfg(not(feature = "heart-rate-ble"))]
+            if std::env::var("EYECORE_HEART_RATE").as_deref() == Ok("ble") {
+                log::error!("BLE heart-rate support not compiled in (build with --features heart-rate-ble)");
+            }
+            info!("❤ Heart-rate collection enabled");
+            Some(monitor)
+        }
+        _ => None,
+    };
+    
+    // Meeting detection; an exported calendar (.ics) adds scheduled meetings as evidence
+    let calendar_path = std::env::var("EYECORE_CALENDAR_ICS").ok().map(PathBuf::from);
+    let meeting_detector = Arc::new(meeting::MeetingDetector::new(calendar_path));
+    collector.write().await.attach_meeting_detector(Arc::clone(&meeting_detector));
+    
+    // Bookmark hotkey; opt-in so key combinations never set bookmarks unexpectedly
+    if std::env::var("EYECORE_BOOKMARK_HOTKEY").as_deref() == Ok("1") {
+        bookmarks::start_hotkey_listener(Arc::clone(&collector), Arc::clone(&storage));
+    }
+    
+    // Shared client for internet integrations (timeouts, retries, proxy)
+    let http_client = http::HttpClient::from_env();
+    
+    // Start update checker (if update URL configured)
+    let update_status = Arc::new(RwLock::new(version::UpdateStatus::new()));
+    if let Ok(update_url) = std::env::var("EYECORE_UPDATE_URL") {
+        version::UpdateChecker::new(update_url, http_client.clone()).start(Arc::clone(&update_status));
+        info!("✓ Update checker started");
+    }
+    
+    // Ctrl-C / SIGTERM stop the loops below at their next tick and flush before exiting
+    let shutdown = shutdown::Shutdown::listen();
+    
+    // Installing an update restarts through the same shutdown
+    let updater = match updater::Updater::from_env(http_client.clone(), shutdown.token()) {
+        Ok(updater) => updater.map(Arc::new),
+        Err(e) => {
+            log::error!("Updater disabled: {}", e);
+            None
+        }
+    };
+    
+    // Collectors start at random points in their interval (plus optional jitter) to spread load
+    let schedules = schedule::ScheduleConfig::from_env();
+    
+    // Every collected snapshot goes to the WebSocket client's upload queue on disk
+    let (upload_tx, upload_rx) = mpsc::channel::<EyeCoreData>(100);
+    
+    // With flag_detection = "in_process", snapshots also go straight to the flag detector
+    let (flag_tx, detector_thresholds, siem_recipient) = if config.config.flag_detection == config::FlagDetection::InProcess {
+        let (flag_tx, flag_rx) = broadcast::channel::<Arc<EyeCoreData>>(flag_pipeline::SNAPSHOT_CHANNEL);
+        match flag_pipeline::start(&storage, flag_rx, &http_client).await {
+            Ok((task, thresholds, siem_recipient)) => {
+                shutdown.track("flag detection", task);
+                (Some(flag_tx), Some(thresholds), siem_recipient)
+            }
+            Err(e) => {
+                log::error!("{}. In-process flag detection is off", e);
+                (None, None, None)
+            }
+        }
+    } else {
+        (None, None, None)
+    };
     
     // Create channel for audio cleaning pipeline
-    let (audio_tx, mut audio_rx) = mpsc::channel::<PathBuf>(100);
+    let (audio_tx, audio_rx) = mpsc::channel::<PathBuf>(100);
+    
+    // Commands pushed by the upload server, and the snapshots and recordings they ask for
+    let (command_tx, command_rx) = mpsc::channel::<remote_commands::CommandRequest>(remote_commands::COMMAND_QUEUE);
+    let (snapshot_request_tx, mut snapshot_requests) = mpsc::channel::<remote_commands::SnapshotRequest>(4);
+    let (recording_request_tx, recording_requests) = mpsc::channel::<remote_commands::RecordingRequest>(4);
     
     // Start WebSocket client
-    let ws_client = Arc::new(websocket_client::WebSocketClient::new(device_id.clone()));
-    let ws_data = Arc::clone(&latest_data);
-    ws_client.start(ws_data).await;
+    let upload_mode = privacy::UploadMode::from_env().unwrap_or_else(|e| {
+        log::error!("{}. Falling back to raw uploads", e);
+        privacy::UploadMode::Raw
+    });
+    let upload_queue = Arc::new(upload_queue::UploadQueue::open(Arc::clone(&storage), config.config.upload_queue_max_mb * 1024 * 1024).await);
+    let ws_client = Arc::new(websocket_client::WebSocketClient::new(
+        config.config.server_url.clone(),
+        device_id.clone(),
+        config.config.upload_tls.clone(),
+        upload_mode,
+        upload_queue,
+        config.config.upload_batch_size,
+        config.config.remote_commands.then_some(command_tx),
+    ));
+    let upload_server = ws_client.server_url().to_string();
+    shutdown.track("upload", Arc::clone(&ws_client).start(upload_rx, schedules.clone(), shutdown.token()).await);
     info!("🔌 WebSocket client started");
     
+    // Detect holes in the timeline left by crashes, reboots or sleep
+    let last_snapshot = storage.latest_snapshot_timestamp().await.ok().flatten();
+    let mut gap_detector = timeline::GapDetector::new(
+        last_snapshot,
+        std::time::Duration::from_secs(config.config.snapshot_interval_secs),
+    );
+    let mut idle_tracker = idle::IdleTracker::new();
+    let mut glare_monitor = ergonomics::EveningGlareMonitor::default();
+    
+    // First-run consent, device checks and calibration; collection waits for it
+    let onboarding = onboarding::Onboarding::load(storage.device_dir().join("onboarding.json"), last_snapshot.is_some());
+    // Privacy-sensitive modules only collect with recorded consent (`/consent`)
+    let mut consent = consent::ConsentManager::load(&storage.device_dir());
+    consent.migrate_onboarding(onboarding.consents());
+    consent.apply(&mut *collector.write().await);
+    let consent = Arc::new(RwLock::new(consent));
+    config.config.modules.apply(&mut *collector.write().await);
+    // Real keystrokes, recorded only while the keystroke module has consent and is enabled
+    if config.config.keystroke_capture != config::CaptureMode::Off {
+        match keystroke_capture::start(config.config.keystroke_capture) {
+            Ok(capture) => collector.write().await.attach_keystroke_capture(capture),
+            Err(e) => log::error!("Keystroke capture unavailable: {}", e),
+        }
+    }
+    // Raw input of the last seconds for incident bundles, under the same consent
+    let forensic_buffer = (config.config.forensic_buffer_secs > 0).then(|| forensics::start(config.config.forensic_buffer_secs));
+    if let Some(buffer) = &forensic_buffer {
+        collector.write().await.attach_forensic_buffer(Arc::clone(buffer));
+    }
+    // Full-resolution input in binary files for research (`[input_trace]`), under the same consent
+    let input_trace = config.config.input_trace.enabled.then(|| input_trace::start(config.config.input_trace.clone()));
+    if let Some(trace) = &input_trace {
+        collector.write().await.attach_input_trace(Arc::clone(trace));
+        shutdown.track("input trace", tokio::spawn(trace_files::run(Arc::clone(trace), Arc::clone(&storage), shutdown.token())));
+    }
+    // Facial emotions from a local model or a cloud service (`[emotion]`), only while the camera module is enabled
+    let capture_tracker = collector.read().await.capture_tracker();
+    match emotion::start(&config.config.emotion, capture_tracker, http_client.clone()) {
+        Ok(Some(detector)) => collector.write().await.attach_emotion_detector(detector),
+        Ok(None) => {}
+        Err(e) => log::error!("Emotion detection unavailable: {}", e),
+    }
+    let module_control = module_control::ModuleControl::load(&storage.device_dir());
+    module_control.apply(&mut *collector.write().await);
+    let module_control = Arc::new(RwLock::new(module_control));
+    // Server commands act on the collector like `/control` and `/collectors`, or ask the loops
+    // below for a snapshot or a recording
+    remote_commands::CommandDispatcher {
+        collector: Arc::clone(&collector),
+        module_control: Arc::clone(&module_control),
+        consent: Arc::clone(&consent),
+        snapshots: snapshot_request_tx,
+        recordings: recording_request_tx,
+    }
+    .start(command_rx);
+    // Maintenance mode (`/maintenance`, `eyecore_mvp maintenance`) pauses collection, nothing else
+    let maintenance = Arc::new(RwLock::new(maintenance::Maintenance::load(&storage.device_dir())));
+    // Storage-wide age, size and per-category limits (`[retention]`, `/storage/stats`)
+    let retention = Arc::new(RwLock::new(storage::retention::RetentionManager::new(config.config.retention.clone())));
+    // Time goals (`/goals`), measured against the stored snapshots
+    let goals = Arc::new(RwLock::new(goals::GoalBook::load(&storage.device_dir())));
+    // Time entries drafted for review before they go to a time-tracking tool (`/time_entries`)
+    let time_export = Arc::new(RwLock::new(time_export::TimeExporter::load(
+        config.config.time_export.clone(),
+        http_client.clone(),
+        &storage.device_dir(),
+    )));
+    time_export::start_daily_drafts(Arc::clone(&time_export), Arc::clone(&storage));
+    let onboarding = Arc::new(RwLock::new(onboarding));
+    let calibration = Arc::new(RwLock::new(calibration::Calibration::load(&storage.device_dir())));
+    // Camera gaze mapped to the screen (`/calibration/gaze`)
+    let gaze_calibration = gaze::GazeCalibrator::load(&storage.device_dir());
+    collector.write().await.set_gaze_calibration(gaze_calibration.calibration().cloned());
+    let gaze_calibration = Arc::new(RwLock::new(gaze_calibration));
+    
+    // Missing OS permissions make collectors come back empty rather than fail, so say so up front
+    let permissions = Arc::new(permissions::PermissionChecker::new());
+    let checker = Arc::clone(&permissions);
+    tokio::task::spawn_blocking(move || permissions::log_denied(&checker.check_all()));
+    
     // Start background collection tasks
     let collector_clone = Arc::clone(&collector);
     let storage_clone = Arc::clone(&storage);
-    let latest_data_clone = Arc::clone(&latest_data);
-    tokio::spawn(async move {
+    let onboarding_clone = Arc::clone(&onboarding);
+    let calibration_clone = Arc::clone(&calibration);
+    let maintenance_clone = Arc::clone(&maintenance);
+    let snapshot_interval = tokio::time::Duration::from_secs(config.config.snapshot_interval_secs);
+    let mut snapshot_schedule = schedules.schedule("snapshot", snapshot_interval);
+    // Anomalies switch to a faster rate for a while (EYECORE_BURST_*)
+    let mut burst = burst::BurstMode::from_env();
+    let stop = shutdown.token();
+    shutdown.track("snapshot collection", tokio::spawn(async move {
         loop {
-            collector_clone.write().await.collect_all().await;
+            // Longer on battery (EYECORE_BATTERY_SAVER_*); bursts still run at their own rate
+            let profile = collector_clone.read().await.collection_profile();
+            let interval = burst.interval(power::interval(profile, snapshot_interval));
+            snapshot_schedule.set_interval(interval);
+            gap_detector.set_interval(interval);
+            // A snapshot the server asked for (`collect_snapshot`) is taken right away
+            let mut requested = None;
+            tokio::select! {
+                _ = snapshot_schedule.tick() => {}
+                Some(reply) = snapshot_requests.recv() => requested = Some(reply),
+                _ = stop.cancelled() => break,
+            }
+            if !onboarding_clone.write().await.poll() {
+                if let Some(reply) = requested {
+                    let _ = reply.send(Err("Collection waits for onboarding".to_string()));
+                }
+                continue;
+            }
+            if maintenance_clone.write().await.active() {
+                gap_detector.pause();
+                if let Some(reply) = requested {
+                    let _ = reply.send(Err("Collection is paused for maintenance".to_string()));
+                }
+                continue;
+            }
+            {
+                let mut collector = collector_clone.write().await;
+                collector.set_burst(burst.sample());
+                collector.collect_all().await;
+            }
             
             // Save collected data to disk
             if let Some(data) = collector_clone.read().await.get_latest_data() {
-                // Update shared latest data for WebSocket
-                *latest_data_clone.write().await = Some(data.clone());
+                // Queue for upload; the channel closes once the upload client has stopped
+                let _ = upload_tx.send(data.clone()).await;
+                // No receiver only means in-process detection has stopped
+                if let Some(flag_tx) = &flag_tx {
+                    let _ = flag_tx.send(Arc::new(data.clone()));
+                }
+                
+                if let Some(gap) = gap_detector.observe(data.timestamp) {
+                    if let Err(e) = storage_clone.save_collection_gap(&gap).await {
+                        log::error!("Failed to save collection gap: {}", e);
+                    }
+                }
+                
+                if let Some(session) = idle_tracker.observe(data.timestamp, idle::measured_idle(&data)) {
+                    if let Err(e) = storage_clone.save_idle_session(&session).await {
+                        log::error!("Failed to save idle session: {}", e);
+                    }
+                }
+                
+                // Keep an audit trail of when the microphone/camera were open
+                for event in data.system_events.iter().flat_map(|events| &events.capture_events) {
+                    if let Err(e) = storage_clone.save_capture_event(event).await {
+                        log::error!("Failed to save capture event: {}", e);
+                    }
+                }
                 
                 if let Err(e) = storage_clone.save_data_snapshot(&data).await {
                     log::error!("Failed to save data snapshot: {}", e);
@@ -119,37 +493,117 @@ async fn main() {
                 }
                 
                 // Run anomaly detection on collected metrics
-                let anomalies = detect_system_anomalies(&data);
+                let mut anomalies = detect_system_anomalies(&data);
+                burst.trigger(&anomalies);
+                anomalies.extend(glare_monitor.observe(&data));
                 if !anomalies.is_empty() {
                     if let Err(e) = storage_clone.save_anomalies(&data.session_id, &anomalies).await {
                         log::error!("Failed to save anomalies: {}", e);
                     }
                 }
-            }
-            
-            // NEW: Collect and save enhanced screen and keyboard data every iteration
-            {
-                let enhanced_data = collector_clone.write().await.collect_enhanced_screen_keyboard_data();
-                if let Err(e) = storage_clone.save_enhanced_screen_keyboard_data(&enhanced_data).await {
-                    log::error!("Failed to save enhanced screen & keyboard data: {}", e);
+                
+                // A finished calibration session turns its snapshots into the baseline
+                calibration_clone.write().await.poll(&storage_clone).await;
+                
+                if let Some(reply) = requested.take() {
+                    let _ = reply.send(Ok(data.timestamp));
                 }
             }
-            
-            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            if let Some(reply) = requested {
+                let _ = reply.send(Err("No snapshot was collected".to_string()));
+            }
         }
-    });
+        
+        // An idle period running at shutdown is kept up to the last snapshot
+        if let Some(session) = idle_tracker.finish() {
+            if let Err(e) = storage_clone.save_idle_session(&session).await {
+                log::error!("Failed to save idle session: {}", e);
+            }
+        }
+    }));
+    
+    // Enhanced screen and keyboard data, on its own schedule so it does not land with the snapshot
+    let collector_clone = Arc::clone(&collector);
+    let storage_clone = Arc::clone(&storage);
+    let onboarding_clone = Arc::clone(&onboarding);
+    let maintenance_clone = Arc::clone(&maintenance);
+    let enhanced_interval = tokio::time::Duration::from_secs(5);
+    let mut enhanced_schedule = schedules.schedule("enhanced", enhanced_interval);
+    let stop = shutdown.token();
+    shutdown.track("screen & keyboard collection", tokio::spawn(async move {
+        loop {
+            let profile = collector_clone.read().await.collection_profile();
+            enhanced_schedule.set_interval(power::interval(profile, enhanced_interval));
+            tokio::select! {
+                _ = enhanced_schedule.tick() => {}
+                _ = stop.cancelled() => break,
+            }
+            if !onboarding_clone.write().await.poll() || maintenance_clone.write().await.active() {
+                continue;
+            }
+            let enhanced_data = collector_clone.write().await.collect_enhanced_screen_keyboard_data();
+            if let Err(e) = storage_clone.save_enhanced_screen_keyboard_data(&enhanced_data).await {
+                log::error!("Failed to save enhanced screen & keyboard data: {}", e);
+            }
+        }
+    }));
+    
+    // Modules sampled on their own interval (`/control/{module}`); snapshots take their latest readings
+    collector.write().await.start_module_scheduler();
+    for (module, declared) in data_collector::SCHEDULED_MODULES {
+        let collector_clone = Arc::clone(&collector);
+        let onboarding_clone = Arc::clone(&onboarding);
+        let maintenance_clone = Arc::clone(&maintenance);
+        let mut module_schedule = schedules.schedule(module, declared);
+        let stop = shutdown.token();
+        shutdown.track(module, tokio::spawn(async move {
+            loop {
+                let (interval, profile) = {
+                    let collector = collector_clone.read().await;
+                    (collector.module_interval(module).unwrap_or(declared), collector.collection_profile())
+                };
+                module_schedule.set_interval(power::interval(profile, interval));
+                tokio::select! {
+                    _ = module_schedule.tick() => {}
+                    _ = stop.cancelled() => break,
+                }
+                if !onboarding_clone.write().await.poll() || maintenance_clone.write().await.active() {
+                    continue;
+                }
+                collector_clone.write().await.sample_module(module);
+            }
+        }));
+    }
     
     // Start voice collection task (if API key available)
     if let Ok(api_key) = std::env::var("ELEVENLABS_API_KEY") {
         let storage_clone = Arc::clone(&storage);
         let audio_tx_clone = audio_tx.clone();
         let collector_clone = Arc::clone(&collector);
+        let capture_tracker = collector.read().await.capture_tracker();
+        let maintenance_clone = Arc::clone(&maintenance);
+        let mut voice_schedule = schedules.schedule("voice", tokio::time::Duration::from_secs(10));
+        let mut recording_requests = recording_requests;
+        let stop = shutdown.token();
         
-        tokio::spawn(async move {
-            let voice_collector = voice::VoiceCollector::new(api_key);
+        shutdown.track("voice collection", tokio::spawn(async move {
+            let voice_collector = voice::VoiceCollector::new(api_key, http_client);
             
             loop {
-                // Check if voice is enabled
+                // A recording the server asked for (`capture_audio`) starts right away
+                let mut requested = None;
+                tokio::select! {
+                    _ = voice_schedule.tick() => {}
+                    Some(request) = recording_requests.recv() => requested = Some(request),
+                    _ = stop.cancelled() => break,
+                }
+                if maintenance_clone.write().await.active() {
+                    if let Some(request) = requested {
+                        let _ = request.reply.send(Err("Collection is paused for maintenance".to_string()));
+                    }
+                    continue;
+                }
+                // Record when the latest snapshot collected voice data, so recordings follow its sampling interval
                 let voice_enabled = {
                     let collector_guard = collector_clone.read().await;
                     collector_guard.get_latest_data()
@@ -157,12 +611,19 @@ async fn main() {
                         .map(|v| v.enabled)
                         .unwrap_or(false)
                 };
+                let (duration_ms, mut reply) = match requested {
+                    Some(request) => (request.duration_ms, Some(request.reply)),
+                    None => (5000, None),
+                };
                 
                 if voice_enabled {
                     info!("📢 Collecting voice data...");
                     
-                    // Collect 5 seconds of audio
-                    let audio_result = voice_collector.collect_audio_chunk(5000).await;
+                    // Collect 5 seconds of audio, or as long as the server asked for
+                    let audio_result = {
+                        let _microphone_stream = capture_tracker.open("microphone", "voice_analysis");
+                        voice_collector.collect_audio_chunk(duration_ms).await
+                    };
                     match audio_result {
                         Ok(audio_bytes) if !audio_bytes.is_empty() => {
                             let session_id = uuid::Uuid::new_v4().to_string();
@@ -171,6 +632,10 @@ async fn main() {
                             match storage_clone.save_audio(&audio_bytes, &session_id).await {
                                 Ok(audio_path) => {
                                     info!("✓ Audio saved: {:?}", audio_path);
+                                    if let Some(reply) = reply.take() {
+                                        let file = audio_path.file_name().map(|n| n.to_string_lossy().into_owned());
+                                        let _ = reply.send(Ok(file.unwrap_or_default()));
+                                    }
                                     
                                     // Send to cleaning pipeline
                                     let _ = audio_tx_clone.send(audio_path.clone()).await;
@@ -182,18 +647,32 @@ async fn main() {
                                             info!("✓ Audio analysis complete: {} chars transcribed", analysis.transcription.len());
                                             
                                             // Save transcription and anomalies
-                                            let _ = storage_clone.save_transcription(
+                                            let transcription = storage_clone.save_transcription(
                                                 &session_id,
                                                 &analysis.transcription,
                                                 &analysis.anomalies,
                                             ).await;
+                                            let _ = storage_clone.update_audio_metadata(&audio_path, |metadata| {
+                                                match &transcription {
+                                                    Ok(path) => {
+                                                        metadata.transcription_status = models::TranscriptionStatus::Completed;
+                                                        metadata.transcription_file = path.file_name().map(|n| n.to_string_lossy().into_owned());
+                                                    }
+                                                    Err(e) => metadata.transcription_status = models::TranscriptionStatus::Failed { error: e.to_string() },
+                                                }
+                                            }).await;
                                             
                                             let _ = storage_clone.save_audio_anomalies(
                                                 &session_id,
                                                 &analysis.anomalies,
                                             ).await;
                                         }
-                                        Err(e) => log::error!("Voice analysis failed: {}", e),
+                                        Err(e) => {
+                                            log::error!("Voice analysis failed: {}", e);
+                                            let _ = storage_clone.update_audio_metadata(&audio_path, |metadata| {
+                                                metadata.transcription_status = models::TranscriptionStatus::Failed { error: e };
+                                            }).await;
+                                        }
                                     }
                                 }
                                 Err(e) => log::error!("Failed to save audio: {}", e),
@@ -203,67 +682,237 @@ async fn main() {
                         Err(e) => log::error!("Audio collection failed: {}", e),
                     }
                 }
-                
-                tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+                if let Some(reply) = reply {
+                    let error = if voice_enabled { "No audio was recorded" } else { "The voice module is disabled" };
+                    let _ = reply.send(Err(error.to_string()));
+                }
             }
-        });
+        }));
         
         info!("✓ Voice collection task started");
     } else {
         info!("⚠ ELEVENLABS_API_KEY not set - voice collection disabled");
+        // So `capture_audio` commands are refused rather than left waiting
+        drop(recording_requests);
     }
+    // The cleaning queue closes, and drains on shutdown, once the voice task drops its sender
+    drop(audio_tx);
+    
+    // Background jobs on cron-like schedules (`[scheduler]`, `/scheduler/jobs`)
+    let scheduler = scheduler::Scheduler::new(config.config.scheduler.clone());
     
-    // Start audio cleaning pipeline
+    // Drop module data that is past its retention period (`POST /control/{module}`), then apply
+    // the storage-wide limits
+    let module_control_clone = Arc::clone(&module_control);
+    let retention_clone = Arc::clone(&retention);
     let storage_clone = Arc::clone(&storage);
-    tokio::spawn(async move {
-        info!("🧹 Audio cleaning pipeline started");
-        
-        while let Some(audio_path) = audio_rx.recv().await {
-            info!("Cleaning audio file: {:?}", audio_path);
-            
-            let clean_result = audio_cleaner::AudioCleaner::clean_audio_segment(
-                &audio_path,
-                -40.0, // silence threshold in dB
-                500,   // minimum speech duration in ms
-            ).await;
-            
-            match clean_result {
-                Ok(cleaned) => {
-                    // Save cleaned audio
-                    let cleaned_filename = audio_path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .map(|n| n.replace(".wav", "_cleaned.wav"))
-                        .unwrap_or_else(|| "cleaned.wav".to_string());
-                    
-                    let cleaned_path = storage_clone
-                        .get_data_dir()
-                        .join("raw_audio")
-                        .join(cleaned_filename);
-                    
-                    let save_result = cleaned.save(&cleaned_path).await;
-                    if let Err(e) = save_result {
-                        log::error!("Failed to save cleaned audio: {}", e);
-                    } else {
-                        info!("✓ Audio cleaned: {:.1}ms -> {:.1}ms", 
-                            cleaned.original_duration_ms, 
-                            cleaned.cleaned_duration_ms
-                        );
-                    }
+    let retention_job = scheduler.start("retention", shutdown.token(), move || {
+        let (module_control, retention, storage) =
+            (Arc::clone(&module_control_clone), Arc::clone(&retention_clone), Arc::clone(&storage_clone));
+        async move {
+            module_control.write().await.expire(&storage).await;
+            retention.write().await.run(&storage).await;
+            Ok(())
+        }
+    });
+    if let Some(task) = retention_job.await {
+        shutdown.track("retention", task);
+    }
+    
+    // Merge the timeslot files of past days into one archive per day, nightly (`compact_after_days`)
+    if let Some(after_days) = config.config.compact_after_days {
+        let storage_clone = Arc::clone(&storage);
+        let stop = shutdown.token();
+        let compaction_job = scheduler.start("compaction", shutdown.token(), move || {
+            let (storage, stop) = (Arc::clone(&storage_clone), stop.clone());
+            async move {
+                storage::compaction::compact(&storage, after_days, &stop).await;
+                Ok(())
+            }
+        });
+        if let Some(task) = compaction_job.await {
+            shutdown.track("compaction", task);
+        }
+    }
+    
+    // Bundle the forensic buffer for each Critical SecurityConcern flag the detector raises
+    if let Some(buffer) = forensic_buffer {
+        let storage_clone = Arc::clone(&storage);
+        let mut incidents = incidents::IncidentWatcher::new(buffer);
+        let stop = shutdown.token();
+        shutdown.track("incident bundles", tokio::spawn(async move {
+            let mut poll = tokio::time::interval(tokio::time::Duration::from_secs(5));
+            loop {
+                tokio::select! {
+                    _ = poll.tick() => {}
+                    _ = stop.cancelled() => break,
                 }
-                Err(e) => log::error!("Audio cleaning failed: {}", e),
+                incidents.poll(&storage_clone).await;
+            }
+        }));
+    }
+    
+    // Generate daily reports (coverage etc.) after each day rolls over
+    let storage_clone = Arc::clone(&storage);
+    let reports_job = scheduler.start("reports", shutdown.token(), move || {
+        let storage = Arc::clone(&storage_clone);
+        async move { reports::write_due_reports(&storage).await }
+    });
+    if let Some(task) = reports_job.await {
+        shutdown.track("reports", task);
+    }
+    
+    // Start audio cleaning pipeline (DSP runs on blocking threads)
+    shutdown.track("audio cleaning", audio_pipeline::start_cleaning_pool(
+        audio_rx,
+        audio_pipeline::workers_from_env(),
+        Arc::clone(&storage),
+        Arc::clone(&meeting_detector),
+    ));
+    
+    // API listens on localhost unless a LAN address is configured
+    let bind_addr = config.config.bind_addr.clone();
+    let loopback_only = is_loopback(&bind_addr);
+    
+    // Metrics, content and admin API keys; a broken key list must not leave the API open
+    let access = Arc::new(access::AccessControl::from_env().unwrap_or_else(|e| {
+        log::error!("{}", e);
+        std::process::exit(1);
+    }));
+    if access.is_open() {
+        log::warn!("No API keys configured, metric and admin endpoints are unauthenticated");
+    } else if !access.admin_enabled() {
+        info!("Admin endpoints disabled (set EYECORE_ADMIN_KEYS to enable)");
+    }
+    if !access.content_enabled() {
+        info!("Content endpoints disabled (set EYECORE_CONTENT_KEYS to enable)");
+    }
+    
+    // Unauthenticated admin endpoints must never be reachable from the network
+    if !loopback_only && access.is_open() {
+        log::error!("Refusing to listen on {} without API keys (set EYECORE_ADMIN_KEYS or EYECORE_METRICS_KEYS)", bind_addr);
+        std::process::exit(1);
+    }
+    
+    // Advertise the API over mDNS (opt-in, for small offices without a server)
+    let mdns = std::env::var("EYECORE_MDNS").map(|v| v == "1" || v == "true").unwrap_or(false);
+    if mdns && loopback_only {
+        info!("mDNS advertising skipped: the API only listens on {}", bind_addr);
+    }
+    let discovery = if mdns && !loopback_only {
+        let port = bind_addr
+            .rsplit(':')
+            .next()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(3000);
+        match discovery::Discovery::start(&device_id, port) {
+            Ok(discovery) => Some(Arc::new(discovery)),
+            Err(e) => {
+                log::error!("mDNS discovery disabled: {}", e);
+                None
             }
         }
+    } else {
+        None
+    };
+    
+    // Team mode: share availability with discovered teammates holding the same secret; the API
+    // it exposes on the LAN must not be open
+    let team = match (std::env::var("EYECORE_TEAM_SECRET"), &discovery) {
+        (Ok(secret), _) if !secret.is_empty() && access.is_open() => {
+            log::error!("Team mode requires API keys (EYECORE_ADMIN_KEYS or EYECORE_METRICS_KEYS)");
+            None
+        }
+        (Ok(secret), Some(discovery)) if !secret.is_empty() => {
+            let team = Arc::new(team::TeamMode::new(secret, device_id.clone()));
+            Arc::clone(&team).start(Arc::clone(discovery));
+            Some(team)
+        }
+        (Ok(_), None) => {
+            log::error!("Team mode requires mDNS discovery (EYECORE_MDNS=1)");
+            None
+        }
+        _ => None,
+    };
+    
+    // Snapshot of where data goes, for the processing register
+    let processing = Arc::new(compliance::ProcessingContext {
+        device_id: device_id.clone(),
+        data_dir: config.config.data_dir.clone(),
+        history_size: collector.read().await.history_capacity(),
+        retention: config.config.retention.clone(),
+        upload_server,
+        upload_mode,
+        voice_transcription: std::env::var("ELEVENLABS_API_KEY").is_ok(),
+        lan_discovery: discovery.is_some(),
+        team_mode: team.is_some(),
+        update_check: std::env::var("EYECORE_UPDATE_URL").is_ok(),
+        emotion_service: (config.config.emotion.backend == emotion::EmotionBackendKind::Cloud)
+            .then(|| config.config.emotion.endpoint.clone().unwrap_or_default()),
+        time_export: config.config.time_export.recipient(),
+        siem: siem_recipient,
     });
     
-    // Build router with all endpoints
-    let app = Router::new()
-        // Health & core endpoints
-        .route("/health", get(api::handlers::health))
+    let state = api::AppState {
+        collector,
+        storage,
+        update_status,
+        updater,
+        discovery,
+        team,
+        processing,
+        heart_rate,
+        access,
+        onboarding,
+        consent,
+        calibration,
+        gaze_calibration,
+        config,
+        module_control,
+        permissions,
+        input_trace,
+        upload: ws_client,
+        maintenance,
+        retention,
+        goals,
+        time_export,
+        detector_thresholds,
+        scheduler,
+    };
+    
+    // Metric-grade endpoints: numbers, labels and scores, never typed or on-screen text
+    let metrics = Router::new()
         .route("/data/latest", get(api::handlers::get_latest_data))
-        .route("/data/history", get(api::handlers::get_history))
+        .route("/data/history", get(api::handlers::get_history).layer(tower_http::compression::CompressionLayer::new()))
         .route("/data/stats", get(api::handlers::get_stats))
+        .route("/data/coverage", get(api::handlers::get_coverage))
+        .route("/data/comparison", get(api::handlers::get_collector_comparison))
+        .route("/data/metrics", get(api::handlers::get_metrics_mirror))
+        .route("/data/idle", get(api::handlers::get_idle_sessions))
+        .route("/data/audio", get(api::handlers::get_audio_segments))
+        .route("/privacy/activity", get(api::handlers::get_privacy_activity))
+        .route("/onboarding/state", get(api::handlers::get_onboarding_state))
+        .route("/consent", get(api::handlers::get_consent))
+        .route("/data/samples", get(api::handlers::get_samples).layer(tower_http::compression::CompressionLayer::new()))
         .route("/status", get(api::handlers::get_status))
+        .route("/version", get(api::handlers::get_version))
+        .route("/compliance/register", get(api::handlers::get_compliance_register))
+        .route("/config", get(api::handlers::get_config))
+        .route("/storage/stats", get(api::handlers::get_storage_stats))
+        .route("/storage/encryption", get(api::handlers::get_encryption_status))
+        .route("/discovery/peers", get(api::handlers::get_discovery_peers))
+        .route("/team/status", get(api::handlers::get_team_status))
+        .route("/classification/apps", get(api::handlers::get_app_classification))
+        .route("/projects", get(api::handlers::get_projects))
+        .route("/goals", get(api::handlers::get_goals))
+        .route("/goals/progress", get(api::handlers::get_goal_progress))
+        .route("/time_entries", get(api::handlers::get_time_entries))
+        .route("/bookmarks", get(api::handlers::get_bookmarks))
+        .route("/notes", get(api::handlers::get_notes))
+        .route("/flags", get(api::handlers::get_flags))
+        .route("/flags/recent", get(api::handlers::get_recent_flags))
+        .route("/flags/:session_id", get(api::handlers::get_session_flags))
+        .route("/detector/config", get(api::handlers::get_detector_config))
         
         // Data endpoints for individual metrics
         .route("/data/voice", get(api::handlers::get_voice_data))
@@ -274,25 +923,92 @@ async fn main() {
         .route("/data/system-events", get(api::handlers::get_system_events))
         .route("/data/mouse", get(api::handlers::get_mouse_dynamics))
         .route("/data/network", get(api::handlers::get_network_metadata))
+        .route("/data/notifications", get(api::handlers::get_notifications))
+        .route("/data/switches", get(api::handlers::get_switches))
         
-        // Control endpoints for enabling/disabling modules
-        .route("/control/voice/enable", get(api::handlers::enable_voice))
-        .route("/control/voice/disable", get(api::handlers::disable_voice))
-        .route("/control/camera/enable", get(api::handlers::enable_camera))
-        .route("/control/camera/disable", get(api::handlers::disable_camera))
-        .route("/control/keystroke/enable", get(api::handlers::enable_keystroke))
-        .route("/control/keystroke/disable", get(api::handlers::disable_keystroke))
-        .route("/control/files/enable", get(api::handlers::enable_file_monitoring))
-        .route("/control/files/disable", get(api::handlers::disable_file_monitoring))
-        
-        .with_state(collector);
+        // Opt-in module settings: on/off, sampling interval, retention
+        .route("/maintenance", get(api::handlers::get_maintenance))
+        .route("/control", get(api::handlers::get_modules))
+        .route("/collectors", get(api::handlers::get_collectors))
+        .route("/permissions", get(api::handlers::get_permissions))
+        .route("/input_trace", get(api::handlers::get_input_trace))
+        .route("/upload/status", get(api::handlers::get_upload_status))
+        .route("/scheduler/jobs", get(api::handlers::get_scheduler_jobs))
+        .route_layer(middleware::from_fn_with_state(state.clone(), access::require_metrics));
+    
+    // Consent and onboarding answers come from the person at the machine, never over the network
+    let local = Router::new()
+        .route("/consent/:module/grant", post(api::handlers::grant_consent))
+        .route("/onboarding/answer", post(api::handlers::answer_onboarding))
+        .route_layer(middleware::from_fn(access::require_local));
+    
+    // Admin endpoints: change what is collected, record or edit the user's data and state, or send
+    // data off the device; metrics keys only read
+    let admin = Router::new()
+        .route("/ingest/heart-rate", post(api::handlers::ingest_heart_rate))
+        .route("/team/dnd", post(api::handlers::set_do_not_disturb))
+        .route("/network/label", post(api::handlers::label_network))
+        .route("/classification/apps", put(api::handlers::set_app_classification))
+        .route("/projects/:name/confirm", post(api::handlers::confirm_project))
+        .route("/projects/:name/reject", post(api::handlers::reject_project))
+        .route("/goals", post(api::handlers::create_goal))
+        .route("/goals/:id", delete(api::handlers::delete_goal))
+        .route("/time_entries/draft", post(api::handlers::draft_time_entries))
+        .route("/time_entries/:id/discard", post(api::handlers::discard_time_entry))
+        .route("/bookmark", post(api::handlers::create_bookmark))
+        .route("/notes", post(api::handlers::create_note))
+        .route("/consent/:module/revoke", post(api::handlers::revoke_consent))
+        .route("/control/:module", post(api::handlers::update_module))
+        .route("/collectors/:name", post(api::handlers::update_collector))
+        .route("/permissions/:name/request", post(api::handlers::request_permission))
+        .route("/maintenance", post(api::handlers::set_maintenance))
+        .route("/calibration/stress", get(api::handlers::get_stress_calibration))
+        .route("/calibration/status", get(api::handlers::get_calibration_status))
+        .route("/calibration/start", post(api::handlers::start_calibration))
+        .route("/calibration/gaze", get(api::handlers::get_gaze_calibration))
+        .route("/calibration/gaze/start", post(api::handlers::start_gaze_calibration))
+        .route("/calibration/gaze/point", post(api::handlers::record_gaze_point))
+        .route("/calibration/gaze/finish", post(api::handlers::finish_gaze_calibration))
+        .route("/storage/encryption/rotate", post(api::handlers::rotate_encryption_key))
+        .route("/update/apply", post(api::handlers::apply_update))
+        .route("/query/sql", post(api::handlers::query_sql))
+        .route("/time_entries/:id/submit", post(api::handlers::submit_time_entry))
+        .route("/share", post(api::handlers::create_share))
+        .route("/privacy/content-access", get(api::handlers::get_content_access_log))
+        .merge(local)
+        .route_layer(middleware::from_fn_with_state(state.clone(), access::require_admin));
+    
+    // Content-grade endpoints: separate keys, every access audited
+    let content = Router::new()
+        .route("/content/typed-text", get(api::handlers::get_typed_text))
+        .route("/content/screen-text", get(api::handlers::get_screen_text))
+        .route("/content/transcripts", get(api::handlers::get_transcripts))
+        .route("/content/incidents", get(api::handlers::get_incidents))
+        .route("/download/range", get(api::handlers::download_range))
+        .route_layer(middleware::from_fn_with_state(state.clone(), access::require_content));
+    
+    // Health checks and teammate requests (HMAC-signed) need no API key
+    let app = Router::new()
+        .route("/health", get(api::handlers::health))
+        .route("/team/peer-status", get(api::handlers::get_team_peer_status))
+        // Share bundles are encrypted; their unguessable ID and password are what protects them
+        .route("/share/:id", get(api::handlers::get_share))
+        .merge(metrics)
+        .merge(admin)
+        .merge(content)
+        .with_state(state.clone());
     
     // Start server
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
-        .await
-        .unwrap();
+    let listener = bind_listener(&bind_addr).await.unwrap();
     
-    info!("🚀 EyeCore API running on http://127.0.0.1:3000");
+    info!("🚀 EyeCore API running on http://{}", bind_addr);
     
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .with_graceful_shutdown(shutdown.token().cancelled_owned())
+        .await
+        .unwrap();
+    shutdown.finish(&state.collector, &state.storage).await;
+    if let Some(updater) = state.updater.as_ref().filter(|updater| updater.restart_pending()) {
+        updater.restart();
+    }
 }
@@ -0,0 +1,105 @@
+//! Maintenance mode: collection pauses while the API, stored-data readers, exports and retention keep
+//! running, so investigations and data migrations see a dataset that does not change underneath
+//! them. The state lives in `<device>/maintenance.json`, which lets `eyecore_mvp maintenance on`
+//! switch a running instance and keeps the mode across restarts.
+
+use chrono::Utc;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::models::MaintenanceStatus;
+
+pub const MAX_REASON_LENGTH: usize = 500;
+
+pub struct Maintenance {
+    path: PathBuf,
+    status: MaintenanceStatus,
+    modified: Option<SystemTime>,    // of the state file when last read
+}
+
+impl Maintenance {
+    /// State kept in `device_dir`
+    pub fn load(device_dir: &Path) -> Self {
+        let mut maintenance = Maintenance {
+            path: device_dir.join("maintenance.json"),
+            status: MaintenanceStatus::default(),
+            modified: None,
+        };
+        maintenance.refresh();
+        if maintenance.status.active {
+            warn!("🔧 Maintenance mode is on, collection stays paused until it is switched off");
+        }
+        maintenance
+    }
+
+    /// Re-read the state file when another process (the CLI) changed it
+    fn refresh(&mut self) {
+        let modified = std::fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok();
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+
+        let status = match std::fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("⚠️ Ignoring unreadable {:?}: {}", self.path, e);
+                MaintenanceStatus::default()
+            }),
+            Err(_) => MaintenanceStatus::default(),
+        };
+        if status.active != self.status.active {
+            log_change(&status);
+        }
+        self.status = status;
+    }
+
+    /// Whether collection is paused
+    pub fn active(&mut self) -> bool {
+        self.refresh();
+        self.status.active
+    }
+
+    pub fn status(&mut self) -> MaintenanceStatus {
+        self.refresh();
+        self.status.clone()
+    }
+
+    /// Switch maintenance mode on or off. Switching it on again only updates the reason.
+    pub fn set(&mut self, active: bool, reason: Option<String>, source: &str) -> Result<MaintenanceStatus, String> {
+        let reason = reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty());
+        if reason.as_ref().is_some_and(|reason| reason.chars().count() > MAX_REASON_LENGTH) {
+            return Err(format!("reason is limited to {} characters", MAX_REASON_LENGTH));
+        }
+        self.refresh();
+
+        let status = if active {
+            MaintenanceStatus {
+                active: true,
+                since: self.status.since.filter(|_| self.status.active).or_else(|| Some(Utc::now())),
+                reason: reason.or_else(|| self.status.reason.clone().filter(|_| self.status.active)),
+                source: Some(source.to_string()),
+            }
+        } else {
+            MaintenanceStatus::default()
+        };
+        let content = serde_json::to_string_pretty(&status).map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, content)
+            .map_err(|e| format!("Failed to save maintenance state {:?}: {}", self.path, e))?;
+        self.modified = std::fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok();
+
+        if status.active != self.status.active {
+            log_change(&status);
+        }
+        self.status = status;
+        Ok(self.status.clone())
+    }
+}
+
+fn log_change(status: &MaintenanceStatus) {
+    match (status.active, &status.reason) {
+        (true, Some(reason)) => info!("🔧 Maintenance mode on, collection paused: {}", reason),
+        (true, None) => info!("🔧 Maintenance mode on, collection paused"),
+        (false, _) => info!("✓ Maintenance mode off, collection resumed"),
+    }
+}
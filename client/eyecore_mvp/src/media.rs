@@ -0,0 +1,161 @@
+//! Media playback: which apps are playing audio (Windows audio sessions, PulseAudio/PipeWire streams
+//! on Linux) and whether it is music, a call or video. Presence, the focus level and the network
+//! traffic type use it, so music while coding is not read as a distraction or as someone watching.
+
+use crate::models::{MediaKind, MediaPlayback};
+
+/// Lowercase fragments of app names, checked in order (calls first: meeting apps also play music)
+const APP_KINDS: &[(MediaKind, &[&str])] = &[
+    (MediaKind::VideoCall, &["zoom", "teams", "skype", "webex", "slack", "discord", "gotomeeting", "signal", "whatsapp", "telegram"]),
+    (MediaKind::Music, &["spotify", "itunes", "music.ui", "foobar2000", "winamp", "aimp", "musicbee", "tidal", "deezer", "amazon music", "rhythmbox", "clementine", "strawberry", "audacious", "lollypop"]),
+    (MediaKind::Video, &["vlc", "mpc-hc", "mpc-be", "mpv", "potplayer", "video.ui", "netflix", "plex", "kodi", "totem", "celluloid"]),
+];
+
+/// Sites in a browser's window title, for audio playing in a browser
+const SITE_KINDS: &[(MediaKind, &[&str])] = &[
+    (MediaKind::VideoCall, &["google meet", "meet.google.com", "microsoft teams", "zoom meeting", "whereby", "jitsi"]),
+    (MediaKind::Music, &["youtube music", "spotify", "soundcloud", "deezer", "tidal", "bandcamp", "apple music"]),
+    (MediaKind::Video, &["youtube", "netflix", "twitch", "prime video", "disney+", "vimeo", "hulu"]),
+];
+
+const BROWSERS: &[&str] = &["chrome", "msedge", "firefox", "brave", "opera", "vivaldi", "safari", "chromium"];
+
+pub struct MediaMonitor {
+    #[cfg(target_os = "windows")]
+    system: sysinfo::System,
+}
+
+impl MediaMonitor {
+    pub fn new() -> Self {
+        MediaMonitor {
+            #[cfg(target_os = "windows")]
+            system: sysinfo::System::new(),
+        }
+    }
+
+    /// Apps playing audio now, classified; None where playback cannot be read. `window_title` is
+    /// the foreground window's title, used to tell what a browser is playing.
+    #[cfg(target_os = "windows")]
+    pub fn observe(&mut self, window_title: &str) -> Option<Vec<MediaPlayback>> {
+        use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED};
+
+        // Collections run on any runtime thread, so join COM for the duration of the call
+        let initialized = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.is_ok();
+        let pids = unsafe { active_audio_pids() }.ok();
+        if initialized {
+            unsafe { CoUninitialize() };
+        }
+
+        let mut apps: Vec<String> = pids?
+            .into_iter()
+            .filter_map(|pid| {
+                let pid = sysinfo::Pid::from_u32(pid);
+                self.system.refresh_process(pid);
+                self.system.process(pid).map(|process| process.name().to_string())
+            })
+            .collect();
+        apps.sort();
+        apps.dedup();
+        Some(
+            apps.into_iter()
+                .map(|app| MediaPlayback {
+                    kind: classify(&app, None, window_title),
+                    app,
+                })
+                .collect(),
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn observe(&mut self, window_title: &str) -> Option<Vec<MediaPlayback>> {
+        // PulseAudio/PipeWire streams that are not paused ("corked")
+        let output = std::process::Command::new("pactl").args(["list", "sink-inputs"]).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let listing = String::from_utf8_lossy(&output.stdout);
+        let mut playing: Vec<MediaPlayback> = listing
+            .split("Sink Input #")
+            .filter(|stream| stream.lines().any(|line| line.trim() == "Corked: no"))
+            .filter_map(|stream| {
+                let property = |key: &str| {
+                    stream.lines().find_map(|line| {
+                        let value = line.trim().strip_prefix(key)?.strip_prefix(" = ")?;
+                        Some(value.trim_matches('"').to_string())
+                    })
+                };
+                let app = property("application.name")?;
+                let role = property("media.role");
+                Some(MediaPlayback {
+                    kind: classify(&app, role.as_deref(), window_title),
+                    app,
+                })
+            })
+            .collect();
+        playing.sort_by(|a, b| a.app.cmp(&b.app));
+        playing.dedup_by(|a, b| a.app == b.app);
+        Some(playing)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    pub fn observe(&mut self, _window_title: &str) -> Option<Vec<MediaPlayback>> {
+        None
+    }
+}
+
+/// Kind of playback from the stream's declared role (PulseAudio `media.role`), the app, or for
+/// browsers the site in the foreground window title
+fn classify(app: &str, role: Option<&str>, window_title: &str) -> MediaKind {
+    match role {
+        Some("phone") => return MediaKind::VideoCall,
+        Some("music") => return MediaKind::Music,
+        Some("video") | Some("movie") => return MediaKind::Video,
+        _ => {}
+    }
+
+    let app = app.to_lowercase();
+    let app = app.strip_suffix(".exe").unwrap_or(&app);
+    let matching = |kinds: &[(MediaKind, &[&str])], text: &str| {
+        kinds
+            .iter()
+            .find(|(_, names)| names.iter().any(|name| text.contains(name)))
+            .map(|(kind, _)| *kind)
+    };
+    if let Some(kind) = matching(APP_KINDS, app) {
+        return kind;
+    }
+    if BROWSERS.iter().any(|browser| app.contains(browser)) {
+        return matching(SITE_KINDS, &window_title.to_lowercase()).unwrap_or(MediaKind::Unknown);
+    }
+    MediaKind::Unknown
+}
+
+/// Process IDs of audio sessions currently playing on the default output device
+#[cfg(target_os = "windows")]
+unsafe fn active_audio_pids() -> windows::core::Result<Vec<u32>> {
+    use windows::core::ComInterface;
+    use windows::Win32::Media::Audio::{
+        eMultimedia, eRender, AudioSessionStateActive, IAudioSessionControl2, IAudioSessionManager2, IMMDeviceEnumerator,
+        MMDeviceEnumerator,
+    };
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+    let device = enumerator.GetDefaultAudioEndpoint(eRender, eMultimedia)?;
+    let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None)?;
+    let sessions = manager.GetSessionEnumerator()?;
+
+    let mut pids = Vec::new();
+    for index in 0..sessions.GetCount()? {
+        let session = sessions.GetSession(index)?;
+        if session.GetState()? != AudioSessionStateActive {
+            continue;
+        }
+        // Process 0 is the system sounds session
+        match session.cast::<IAudioSessionControl2>().and_then(|session| session.GetProcessId()) {
+            Ok(pid) if pid != 0 => pids.push(pid),
+            _ => {}
+        }
+    }
+    Ok(pids)
+}
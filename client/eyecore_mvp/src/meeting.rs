@@ -0,0 +1,176 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use log::{info, warn};
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tokio::sync::RwLock;
+
+use crate::models::{EyeCoreData, MeetingStatus};
+
+/// Window titles of common video-call apps
+pub const MEETING_APPS: &[&str] = &["zoom", "microsoft teams", "google meet", "webex", "skype", "slack huddle"];
+
+// Evidence weights; a snapshot is "in a meeting" once they add up to MEETING_THRESHOLD,
+// so no single signal is enough on its own
+const CALENDAR_WEIGHT: f32 = 0.4;
+const MEETING_APP_WEIGHT: f32 = 0.35;
+const VIDEO_TRAFFIC_WEIGHT: f32 = 0.25;
+const SPEAKERS_WEIGHT: f32 = 0.35;
+const MEETING_THRESHOLD: f32 = 0.5;
+
+/// Speaker estimates older than this no longer describe the current conversation
+const SPEAKER_ESTIMATE_TTL_SECS: i64 = 120;
+
+/// Busy period from the calendar
+#[derive(Debug, Clone)]
+struct CalendarEvent {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct CalendarCache {
+    modified: Option<SystemTime>,
+    events: Vec<CalendarEvent>,
+}
+
+/// Combines calendar, foreground app, network and speaker-count evidence into a meeting flag
+pub struct MeetingDetector {
+    calendar_path: Option<PathBuf>,
+    calendar: RwLock<CalendarCache>,
+    speakers: RwLock<Option<(DateTime<Utc>, u32)>>,
+}
+
+impl MeetingDetector {
+    /// `calendar_path` points to an exported .ics file, re-read whenever it changes
+    pub fn new(calendar_path: Option<PathBuf>) -> Self {
+        MeetingDetector {
+            calendar_path,
+            calendar: RwLock::new(CalendarCache::default()),
+            speakers: RwLock::new(None),
+        }
+    }
+
+    /// Latest speaker-count estimate from a cleaned recording
+    pub async fn record_speakers(&self, recorded_at: DateTime<Utc>, speakers: u32) {
+        let mut latest = self.speakers.write().await;
+        if latest.is_none_or(|(at, _)| recorded_at >= at) {
+            *latest = Some((recorded_at, speakers));
+        }
+    }
+
+    pub async fn assess(&self, data: &EyeCoreData) -> MeetingStatus {
+        let mut signals = Vec::new();
+        let mut confidence = 0.0;
+
+        if self.calendar_busy(data.timestamp).await {
+            signals.push("calendar".to_string());
+            confidence += CALENDAR_WEIGHT;
+        }
+
+        let title = data.process_data.active_window_title.to_lowercase();
+        if MEETING_APPS.iter().any(|app| title.contains(app)) {
+            signals.push("meeting_app".to_string());
+            confidence += MEETING_APP_WEIGHT;
+        }
+
+        if data
+            .network_activity_metadata
+            .as_ref()
+            .is_some_and(|network| network.traffic_type == "video_conference")
+        {
+            signals.push("video_conference_traffic".to_string());
+            confidence += VIDEO_TRAFFIC_WEIGHT;
+        }
+
+        let estimated_speakers = (*self.speakers.read().await)
+            .filter(|(at, _)| (data.timestamp - *at).num_seconds() <= SPEAKER_ESTIMATE_TTL_SECS)
+            .map(|(_, speakers)| speakers);
+        if estimated_speakers.is_some_and(|speakers| speakers >= 2) {
+            signals.push("multiple_speakers".to_string());
+            confidence += SPEAKERS_WEIGHT;
+        }
+
+        let confidence: f32 = confidence.min(1.0);
+        MeetingStatus {
+            in_meeting: confidence >= MEETING_THRESHOLD,
+            confidence,
+            signals,
+            estimated_speakers,
+        }
+    }
+
+    async fn calendar_busy(&self, at: DateTime<Utc>) -> bool {
+        let Some(path) = &self.calendar_path else {
+            return false;
+        };
+
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified != self.calendar.read().await.modified {
+            let events = match std::fs::read_to_string(path) {
+                Ok(ics) => parse_ics(&ics),
+                Err(e) => {
+                    warn!("Failed to read calendar {:?}: {}", path, e);
+                    Vec::new()
+                }
+            };
+            info!("📅 Loaded {} calendar events from {:?}", events.len(), path);
+            *self.calendar.write().await = CalendarCache { modified, events };
+        }
+
+        self.calendar
+            .read()
+            .await
+            .events
+            .iter()
+            .any(|event| event.start <= at && at < event.end)
+    }
+}
+
+/// Start, end and busy flag of the VEVENT being parsed
+type PartialEvent = (Option<DateTime<Utc>>, Option<DateTime<Utc>>, bool);
+
+/// Busy events from an iCalendar file. All-day, cancelled and "free" events are skipped;
+/// recurring events only count their first occurrence.
+fn parse_ics(ics: &str) -> Vec<CalendarEvent> {
+    // Long lines are folded onto continuation lines starting with a space or tab
+    let unfolded = ics.replace("\r\n", "\n").replace("\n ", "").replace("\n\t", "");
+
+    let mut events = Vec::new();
+    let mut current: Option<PartialEvent> = None;
+    for line in unfolded.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (property, params) = name.split_once(';').unwrap_or((name, ""));
+
+        match (property, current.as_mut()) {
+            ("BEGIN", _) if value == "VEVENT" => current = Some((None, None, true)),
+            ("END", Some((start, end, busy))) if value == "VEVENT" => {
+                if let (Some(start), Some(end), true) = (*start, *end, *busy) {
+                    events.push(CalendarEvent { start, end });
+                }
+                current = None;
+            }
+            ("DTSTART", Some((start, _, busy))) => {
+                *start = parse_ics_time(value);
+                // Date-only values are all-day events, which are not meetings
+                *busy &= !params.contains("VALUE=DATE") && start.is_some();
+            }
+            ("DTEND", Some((_, end, _))) => *end = parse_ics_time(value),
+            ("STATUS", Some((_, _, busy))) if value == "CANCELLED" => *busy = false,
+            ("TRANSP", Some((_, _, busy))) if value == "TRANSPARENT" => *busy = false,
+            _ => {}
+        }
+    }
+    events
+}
+
+/// "20261016T100000Z" is UTC; times without the Z (floating or TZID) are taken as local time
+fn parse_ics_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&naive));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc))
+}
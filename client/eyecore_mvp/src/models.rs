@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemMetrics {
@@ -7,6 +8,25 @@ pub struct SystemMetrics {
     pub cpu_usage: f32,
     pub memory_usage: f32,
     pub disk_usage: f32,
+    #[serde(default)]
+    pub display_brightness: Option<f32>, // percent, where the display reports it
+    #[serde(default)]
+    pub ambient_light_lux: Option<f32>,  // only on devices with a light sensor
+    #[serde(default)]
+    pub per_core_cpu: Vec<f32>,          // percent per logical core, same order as the OS lists them
+    #[serde(default)]
+    pub disks: Vec<DiskUtilization>,
+}
+
+/// Space used on one mounted disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskUtilization {
+    pub name: String,              // device or volume label, e.g. "/dev/nvme0n1p2"
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub used_percent: f32,
+    pub removable: bool,           // not counted in `disk_usage`
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +35,23 @@ pub struct ProcessData {
     pub active_process: String,
     pub active_window_title: String,
     pub process_count: usize,
+    #[serde(default)]
+    pub productivity: Option<Productivity>, // class of the foreground app or site; None when unknown
+    #[serde(default)]
+    pub app: Option<String>,                // app identified from the executable and `app_rules`
+    #[serde(default)]
+    pub project: Option<String>,            // project of the foreground window, see `/projects`
+}
+
+/// One process's share of the machine since the previous snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_usage: f32,           // percent of all cores, 0-100
+    pub memory_bytes: u64,
+    pub disk_read_bytes: u64,     // bytes since previous sample
+    pub disk_written_bytes: u64,  // bytes since previous sample
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,9 +65,29 @@ pub struct InputMetrics {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkMetrics {
     pub timestamp: DateTime<Utc>,
+    pub bytes_sent: u64,        // bytes since previous sample
+    pub bytes_received: u64,    // bytes since previous sample
+    pub active_connections: usize,
+    #[serde(default)]
+    pub bytes_sent_per_sec: f64,
+    #[serde(default)]
+    pub bytes_received_per_sec: f64,
+    #[serde(default)]
+    pub tcp_connections: Option<usize>, // every state but listening; None where sockets cannot be listed
+    #[serde(default)]
+    pub udp_sockets: Option<usize>,
+    #[serde(default)]
+    pub interfaces: Vec<InterfaceTraffic>, // adapters that have carried traffic, by name
+}
+
+/// Traffic of one network adapter since the previous sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterfaceTraffic {
+    pub name: String,
     pub bytes_sent: u64,
     pub bytes_received: u64,
-    pub active_connections: usize,
+    pub bytes_sent_per_sec: f64,
+    pub bytes_received_per_sec: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,15 +95,24 @@ pub struct FocusMetrics {
     pub timestamp: DateTime<Utc>,
     pub focus_level: f32, // 0.0 to 1.0
     pub context_switches: u32,
-    pub productive_app_time: u32, // seconds
+    pub productive_app_time: u32, // seconds in productive apps over the last five minutes
+    #[serde(default)]
+    pub switching: Option<SwitchCadence>, // last five minutes, where switches are sampled
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EyeCoreData {
     pub session_id: String,
     pub timestamp: DateTime<Utc>,
+    // Per-session collection counter starting at 1; orders snapshots even if the clock jumps.
+    // 0 in snapshots written before it existed.
+    #[serde(default)]
+    pub sequence: u64,
     pub system_metrics: SystemMetrics,
     pub process_data: ProcessData,
+    // Busiest processes by CPU, then memory; empty when `top_processes = 0`
+    #[serde(default)]
+    pub top_processes: Vec<ProcessUsage>,
     pub input_metrics: InputMetrics,
     pub network_metrics: NetworkMetrics,
     pub focus_metrics: FocusMetrics,
@@ -59,6 +125,645 @@ pub struct EyeCoreData {
     pub system_events: Option<SystemEvents>,
     pub mouse_dynamics: Option<MouseDynamics>,
     pub network_activity_metadata: Option<NetworkActivityMetadata>,
+    // Heart rate / HRV from a wearable, when one is connected
+    #[serde(default)]
+    pub physiological_data: Option<PhysiologicalData>,
+    // Whether the user appears to be in a call, when meeting detection is enabled
+    #[serde(default)]
+    pub meeting: Option<MeetingStatus>,
+    // Set when the snapshot was taken at the raised rate that follows an anomaly
+    #[serde(default)]
+    pub burst: Option<BurstSample>,
+    // What pulled attention away since the previous snapshot, for attributing focus drops
+    #[serde(default)]
+    pub focus_context: Option<FocusContext>,
+    // Active, reading/watching or away, from input idleness, display power and media playback
+    #[serde(default)]
+    pub presence: Option<Presence>,
+    // Apps playing audio and what they play; None where playback cannot be read
+    #[serde(default)]
+    pub media: Option<Vec<MediaPlayback>>,
+    // Power source and the collection profile it selected
+    #[serde(default)]
+    pub power: Option<PowerState>,
+    // Where each field came from, keyed by "section" or "section.field"
+    #[serde(default)]
+    pub provenance: BTreeMap<String, Provenance>,
+    // Personal data masked in the captured text, matches per redaction rule
+    #[serde(default)]
+    pub redactions: BTreeMap<String, u32>,
+    // With `compare_collectors`: measured fields and what their simulation reported alongside
+    #[serde(default)]
+    pub comparisons: BTreeMap<String, CollectorComparison>,
+    // Readings of the pluggable collectors that were due, by collector name (see collectors)
+    #[serde(default)]
+    pub collectors: BTreeMap<String, serde_json::Value>,
+}
+
+/// The numbers of one snapshot in the metrics-only mirror (`metrics_mirror`), written unencrypted;
+/// it never holds text, window titles, audio or file references
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsOnlyEntry {
+    pub metrics_only: bool,           // always true; marks mirror lines
+    pub session_id: String,
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    pub cpu_usage: f32,
+    pub memory_usage: f32,
+    pub disk_usage: f32,
+    pub mouse_clicks: u32,
+    pub keyboard_events: u32,
+    pub idle_duration_seconds: u32,
+    pub bytes_sent_per_sec: f64,
+    pub bytes_received_per_sec: f64,
+    pub active_connections: usize,
+    pub focus_level: f32,
+    pub context_switches: u32,
+    pub productive_app_time: u32,
+}
+
+impl MetricsOnlyEntry {
+    pub fn from_snapshot(data: &EyeCoreData) -> Self {
+        MetricsOnlyEntry {
+            metrics_only: true,
+            session_id: data.session_id.clone(),
+            sequence: data.sequence,
+            timestamp: data.timestamp,
+            cpu_usage: data.system_metrics.cpu_usage,
+            memory_usage: data.system_metrics.memory_usage,
+            disk_usage: data.system_metrics.disk_usage,
+            mouse_clicks: data.input_metrics.mouse_clicks,
+            keyboard_events: data.input_metrics.keyboard_events,
+            idle_duration_seconds: data.input_metrics.idle_duration_seconds,
+            bytes_sent_per_sec: data.network_metrics.bytes_sent_per_sec,
+            bytes_received_per_sec: data.network_metrics.bytes_received_per_sec,
+            active_connections: data.network_metrics.active_connections,
+            focus_level: data.focus_metrics.focus_level,
+            context_switches: data.focus_metrics.context_switches,
+            productive_app_time: data.focus_metrics.productive_app_time,
+        }
+    }
+}
+
+/// Meeting detection result for one snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingStatus {
+    pub in_meeting: bool,
+    pub confidence: f32,                   // 0.0 to 1.0, sum of signal weights
+    pub signals: Vec<String>,              // "calendar", "meeting_app", "video_conference_traffic", "multiple_speakers"
+    pub estimated_speakers: Option<u32>,   // from the most recent recording, if recent
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceState {
+    Active,              // input within the last minute
+    PassiveConsuming,    // no input, but video or a call is playing or the user is likely reading
+    Away,                // display off, or no input and nothing but music playing for five minutes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Music,
+    VideoCall,           // meeting and messaging apps, or a stream with the "phone" role
+    Video,
+    Unknown,             // e.g. a browser on a site it cannot place
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaPlayback {
+    pub app: String,                     // executable name (Windows) or stream application name (Linux)
+    pub kind: MediaKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Presence {
+    pub state: PresenceState,
+    pub idle_seconds: u32,
+    pub idle_measured: bool,             // false where idleness comes from the simulated input metrics
+    pub display_on: Option<bool>,        // None where the display's power state cannot be read
+    pub media_apps: Option<Vec<String>>, // apps playing audio; None where playback cannot be read
+}
+
+/// Possible distraction sources seen since the previous snapshot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FocusContext {
+    pub app_switches: Vec<AppSwitch>,
+    pub notifications: Vec<NotificationArrival>,
+    pub site_changes: Vec<SiteChange>,
+    pub notifications_available: bool,    // false where the notification listener is missing, denied or disabled
+}
+
+/// The foreground application changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppSwitch {
+    pub timestamp: DateTime<Utc>,
+    pub from_app: Option<String>,         // executable names, e.g. "Code.exe" -> "Discord.exe"
+    pub to_app: String,
+    #[serde(default)]
+    pub from_category: Option<String>,    // browser, communication, development, documents, media, games, system or other
+    #[serde(default)]
+    pub to_category: Option<String>,
+}
+
+/// How often the foreground app changed over a recent window (`focus_metrics.switching`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchCadence {
+    pub window_secs: u64,
+    pub switches: u32,
+    pub switches_per_minute: f32,
+    pub median_dwell_secs: Option<f32>,   // time between consecutive switches
+    pub ping_pong: Vec<PingPong>,
+}
+
+/// Rapid back-and-forth between two apps (at least four switches, each within 20 s of the last)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PingPong {
+    pub first_app: String,
+    pub second_app: String,
+    pub first_category: Option<String>,
+    pub second_category: Option<String>,
+    pub switches: u32,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+}
+
+/// Recent foreground app switches (`/data/switches`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchReport {
+    pub available: bool,                  // false where the foreground window cannot be sampled
+    pub cadence: Option<SwitchCadence>,   // over the requested window
+    pub switches: Vec<AppSwitch>,         // the same window, newest first
+}
+
+/// A toast notification arrived; only the sending app is recorded, never the content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationArrival {
+    pub timestamp: DateTime<Utc>,
+    pub app: String,
+}
+
+/// Notification counts per sending app since startup (`/data/notifications`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationStats {
+    pub available: bool,                  // false where the notification listener is missing, denied or disabled
+    pub since: DateTime<Utc>,
+    pub total: u64,
+    pub last_hour: u64,
+    pub apps: Vec<AppNotificationFrequency>, // most frequent first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppNotificationFrequency {
+    pub app: String,
+    pub count: u64,
+    pub last_hour: u64,
+    pub per_hour: f64,                    // average since startup, over at least one hour
+    pub last_seen: DateTime<Utc>,
+}
+
+/// The site in the foreground browser changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteChange {
+    pub timestamp: DateTime<Utc>,
+    pub browser: String,
+    pub from_site: Option<String>,
+    pub to_site: String,                  // domain when the window title shows one, else the site name in it
+}
+
+/// Burst-mode annotation: collection runs faster for a while after an anomaly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurstSample {
+    pub triggers: Vec<String>,        // anomaly types that started or extended the burst
+    pub started_at: DateTime<Utc>,
+    pub interval_ms: u64,
+}
+
+/// Which collectors run and how often; power saving drops OCR and the camera and stretches intervals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionProfile {
+    Full,
+    PowerSaving,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerState {
+    pub profile: CollectionProfile,
+    pub on_battery: bool,
+    pub battery_percent: Option<f32>,   // None without a system battery
+}
+
+/// Origin of a collected value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provenance {
+    Measured,    // read from the OS
+    Simulated,   // generated for the MVP
+    WarmingUp,   // collector has not taken enough samples for a meaningful value yet
+    Withheld,    // left out by the user's privacy choices, e.g. the camera's presence-only mode
+}
+
+/// A measured field next to the value its simulation produced for the same snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CollectorComparison {
+    pub measured: f64,    // the value in the snapshot
+    pub simulated: f64,
+}
+
+/// How far the simulation of a field was from the measured values, as served by `/data/comparison`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectorDrift {
+    pub field: String,
+    pub samples: usize,
+    pub measured_mean: f64,
+    pub simulated_mean: f64,
+    pub mean_absolute_difference: f64,
+    pub relative_difference: Option<f64>,  // mean absolute difference over the measured mean; None when that is 0
+}
+
+/// Drift between measured and simulated collectors over a range of days
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub enabled: bool,                // whether `compare_collectors` is on now
+    pub days: Vec<String>,
+    pub snapshots_compared: usize,    // snapshots recorded with comparisons
+    pub fields: Vec<CollectorDrift>,
+}
+
+/// Explicit marker for a period where no snapshots were collected
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionGap {
+    #[serde(rename = "type")]
+    pub gap_type: String,          // always "collection_gap"
+    pub start: DateTime<Utc>,      // last snapshot before the gap
+    pub end: DateTime<Utc>,        // first snapshot after the gap
+    pub duration_seconds: u64,
+    pub reason: String,            // "not_running" (crash/reboot), "collection_stalled" (sleep, hang), "maintenance"
+}
+
+/// A period without keyboard or mouse input (see `idle`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdleSession {
+    pub start: DateTime<Utc>,      // last input before the session
+    pub end: DateTime<Utc>,        // last snapshot before input resumed
+    pub duration_seconds: u64,
+}
+
+/// Collection coverage for a single UTC day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyCoverage {
+    pub date: String,
+    pub snapshot_count: usize,
+    pub gap_count: usize,
+    pub uncovered_seconds: u64,
+    pub gaps: Vec<CollectionGap>,
+    #[serde(default)]
+    pub notes: Vec<SessionNote>,   // user notes overlapping the day
+}
+
+/// Context written by the user for a stretch of time, e.g. "compiling large project"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionNote {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub session_id: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,        // equal to `start` for a note about a moment
+    pub text: String,
+}
+
+/// Spread of one metric over a calibration session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricDistribution {
+    pub samples: usize,
+    pub mean: f32,
+    pub std_dev: f32,
+    pub p10: f32,
+    pub p50: f32,
+    pub p90: f32,
+}
+
+/// How far a baseline can be trusted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationQuality {
+    pub grade: String,             // "good", "fair" or "poor"
+    pub score: f32,                // 0.0 to 1.0
+    pub snapshots: usize,
+    pub coverage: f32,             // share of the session's snapshot slots that were collected
+    pub samples: BTreeMap<String, usize>, // per group ("typing", "mouse", "voice") with activity
+    pub issues: Vec<String>,
+}
+
+/// Personal reference ranges recorded by a calibration session (`<device>/baseline.json`).
+/// Metric keys are snapshot paths, e.g. "keystroke_dynamics.typing_speed_wpm".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub metrics: BTreeMap<String, MetricDistribution>,
+    pub quality: CalibrationQuality,
+}
+
+/// Running calibration session and the current baseline (`/calibration/status`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationStatus {
+    pub active: bool,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub baseline: Option<Baseline>,
+}
+
+/// Mapping from raw camera gaze estimates to screen pixels (`<device>/gaze_calibration.json`):
+/// x = x_coefficients[0] + x_coefficients[1] * u + x_coefficients[2] * v, likewise y
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GazeCalibration {
+    pub created_at: DateTime<Utc>,
+    pub screen_width: u32,
+    pub screen_height: u32,
+    pub samples: usize,
+    pub mean_error_px: f32,        // distance between the mapped samples and their prompts
+    pub x_coefficients: [f32; 3],
+    pub y_coefficients: [f32; 3],
+}
+
+/// One calibration sample: where the prompt was and what the camera estimated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GazeSample {
+    pub target: [f32; 2],          // screen pixels
+    pub estimate: [f32; 2],        // raw, in the emotion backend's units
+}
+
+/// Research input trace and its files (`/input_trace`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputTraceStatus {
+    pub enabled: bool,                 // `[input_trace] enabled`
+    pub recording: bool,               // the keystroke module is enabled
+    pub files: usize,
+    pub total_bytes: u64,
+    pub max_total_bytes: u64,
+    pub current_file: Option<String>,
+    pub events_written: u64,           // since startup
+    pub events_dropped: u64,           // over max_events_per_sec since startup
+}
+
+/// Whether the OS lets the collectors use something they need (`/permissions`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    Unavailable,    // no such device or display server
+    Unknown,        // the OS does not say without a recording (macOS microphone)
+    NotNeeded,
+}
+
+/// One OS permission (`/permissions`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionStatus {
+    pub permission: String,           // "camera", "microphone", "accessibility" or "input_monitoring"
+    pub state: PermissionState,
+    pub needed_by: Vec<String>,       // snapshot fields that go missing or simulated without it
+    pub detail: Option<String>,
+    pub remediation: Option<String>,  // how to grant it on this OS, unless granted
+    pub checked_at: DateTime<Utc>,
+}
+
+/// State of the upload socket (`/upload/status`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadConnection {
+    Connecting,
+    Connected,
+    Disconnected,     // waiting to reconnect, or shut down
+}
+
+/// WebSocket uploads to the server (`/upload/status`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadStatus {
+    pub server_url: String,
+    pub device_id: String,
+    pub mode: String,                      // "raw", "aggregate" or "team"
+    pub connection: UploadConnection,
+    pub connected_since: Option<DateTime<Utc>>,
+    pub last_sent: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,        // why the last connection ended
+    pub queued_packages: usize,            // snapshots spooled for sending (or aggregating)
+    pub queued_bytes: u64,
+    pub max_queued_bytes: u64,             // `upload_queue_max_mb`
+    pub packages_sent: u64,                // since startup
+    pub packages_dropped: u64,             // oldest snapshots dropped from a full queue since startup
+    pub failed_attempts: u32,              // connections in a row that failed; reconnects back off with them
+    pub next_attempt: Option<DateTime<Utc>>, // while disconnected
+    #[serde(default)]
+    pub capabilities: Option<NegotiatedCapabilities>, // None while disconnected or for servers without a manifest
+}
+
+/// What the client and the upload server agreed on when connecting, from their capability manifests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NegotiatedCapabilities {
+    pub server_version: Option<String>,
+    pub schema_version: u32,                    // snapshot schema version of the packages sent
+    pub data_categories: Option<Vec<String>>,   // snapshot sections the server takes; None: all
+    pub encodings: Vec<String>,                 // package encodings both support
+}
+
+/// Running gaze calibration session and the calibration in use (`/calibration/gaze`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GazeCalibrationStatus {
+    pub active: bool,
+    pub screen_width: Option<u32>,
+    pub screen_height: Option<u32>,
+    pub prompts: Vec<[f32; 2]>,    // where to show the prompts, in screen pixels
+    pub samples: Vec<GazeSample>,
+    pub calibration: Option<GazeCalibration>,
+}
+
+/// Current settings of one module (`/control`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleSettings {
+    pub module: String,                      // "voice", "camera", "keystroke", "files", "system" or "screen"
+    pub enabled: bool,
+    pub sampling_interval_secs: Option<u64>, // None: collected with every snapshot
+    pub retention_days: Option<u32>,         // None: stored data is kept
+}
+
+/// Body of `POST /control/{module}`; fields left out keep their value and 0 clears a setting
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModuleUpdate {
+    pub enabled: Option<bool>,
+    pub sampling_interval_secs: Option<u64>,
+    pub retention_days: Option<u32>,
+}
+
+/// One pluggable collector (`/collectors`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectorStatus {
+    pub name: String,
+    pub enabled: bool,
+    pub interval_secs: u64,
+    pub default_interval_secs: u64,          // the collector's own interval
+    pub last_collected: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,          // from the latest attempt; None once it succeeds
+}
+
+/// Body of `POST /collectors/{name}`; fields left out keep their value and an interval of 0
+/// restores the collector's own
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CollectorUpdate {
+    pub enabled: Option<bool>,
+    pub interval_secs: Option<u64>,
+}
+
+/// A background job of the scheduler (`/scheduler/jobs`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub name: String,
+    pub cron: String,
+    pub jitter_secs: u64,
+    pub enabled: bool,
+    pub running: bool,
+    pub next_run: Option<DateTime<Utc>>,     // without jitter; None while switched off
+    pub skipped: u64,                        // start times passed over because a run was still going
+    pub runs: Vec<JobRun>,                   // newest first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Consent for one privacy-sensitive module (`/consent`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleConsent {
+    pub module: String,                      // "voice", "camera", "camera_presence", "camera_cloud", "keystroke", "typed_text", "screen_text" or "files"
+    pub description: String,
+    pub granted: bool,                       // false until consent is given
+    pub updated_at: Option<DateTime<Utc>>,   // last grant or revocation
+    pub source: Option<String>,              // how: "onboarding", "api" or "bundle"
+}
+
+/// One grant or revocation, as kept in `<device>/consent.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsentEvent {
+    pub module: String,
+    pub granted: bool,
+    pub at: DateTime<Utc>,
+    pub source: String,
+    pub reason: Option<String>,
+}
+
+/// `GET /consent`: every module's consent and the history of changes, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsentStatus {
+    pub modules: Vec<ModuleConsent>,
+    pub history: Vec<ConsentEvent>,
+}
+
+/// Body of `POST /consent/{module}/grant` and `/revoke`; optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConsentChange {
+    pub reason: Option<String>,
+}
+
+/// Maintenance mode (`/maintenance`, `<device>/maintenance.json`): collection paused, everything else running
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub active: bool,
+    pub since: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    pub source: Option<String>,              // "api" or "cli"
+}
+
+/// Files and bytes of one storage category across all days (`/storage/stats`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CategoryUsage {
+    pub files: u64,
+    pub bytes: u64,
+    pub quota_bytes: Option<u64>,            // from [retention] quotas_mb
+}
+
+/// What one retention run removed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionRun {
+    pub at: DateTime<Utc>,
+    pub files_removed: u64,
+    pub rows_removed: u64,                   // SQLite rows, with storage_backend = "sqlite"
+    pub bytes_reclaimed: u64,
+    pub bytes_by_policy: BTreeMap<String, u64>, // "max_age", "quota" and "max_total"
+}
+
+/// Disk usage of this device's data and the retention policies applied to it (`/storage/stats`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageStats {
+    pub total_bytes: u64,
+    pub database_bytes: Option<u64>,         // eyecore.db, with storage_backend = "sqlite"
+    pub categories: BTreeMap<String, CategoryUsage>,
+    pub oldest_day: Option<NaiveDate>,
+    pub newest_day: Option<NaiveDate>,
+    pub max_age_days: Option<u32>,
+    pub max_total_bytes: Option<u64>,
+    pub last_run: Option<RetentionRun>,
+    pub reclaimed_bytes: u64,                // by all runs since startup
+}
+
+/// At-rest encryption of this device's data (`/storage/encryption`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionStatus {
+    pub enabled: bool,                       // new files are sealed
+    pub key_file: Option<String>,            // None when no keyring is configured
+    pub active_key: Option<String>,          // ID of the key new files are sealed with
+    pub keys: Vec<String>,                   // IDs of every key files can be read with
+}
+
+/// Outcome of `POST /storage/encryption/rotate`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotation {
+    pub active_key: String,
+    pub reencrypted: usize,
+    pub failed: usize,                       // still sealed with an old key, which is kept
+    pub queued: usize,                       // upload packages under an old key, which is kept until they are sent
+    pub retired_keys: Vec<String>,
+}
+
+/// Outcome of an onboarding device check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceTestResult {
+    pub passed: bool,
+    pub skipped: bool,
+    pub detail: String,            // device found, or why the check failed
+    pub tested_at: DateTime<Utc>,
+}
+
+/// The question onboarding is waiting on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnboardingStep {
+    pub id: String,                // e.g. "consent.voice_data", "device_test.microphone", "calibration"
+    pub kind: String,              // "consent", "device_test", "calibration", "calibrating" or "complete"
+    pub prompt: String,
+    pub answers: Vec<String>,      // accepted by `POST /onboarding/answer`
+}
+
+/// First-run onboarding progress (`/onboarding/state`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnboardingStatus {
+    pub complete: bool,
+    pub collection_allowed: bool,  // snapshots are taken from the start of calibration on
+    pub step: OnboardingStep,
+    pub steps_done: usize,
+    pub steps_total: usize,
+    pub consents: BTreeMap<String, bool>,
+    pub device_tests: BTreeMap<String, DeviceTestResult>,
+    pub calibration_started_at: Option<DateTime<Utc>>,
+    pub calibration_ends_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +784,8 @@ pub struct CollectionStatus {
     pub uptime_seconds: u64,
     pub data_points_collected: usize,
     pub last_collection: DateTime<Utc>,
+    pub update_available: bool,
+    pub latest_version: Option<String>,
 }
 
 // NEW: Voice Data Collection
@@ -105,6 +812,12 @@ pub struct CameraData {
     pub face_detected: bool,
     pub posture_score: f32,           // 0.0 (poor) to 1.0 (good)
     pub enabled: bool,                // privacy: user consent status
+    #[serde(default)]
+    pub emotion_backend: Option<String>, // "onnx" or "cloud" when emotions are inferred; None when simulated
+    #[serde(default)]
+    pub gaze_point: Option<[f32; 2]>,    // screen pixels, once gaze is calibrated (`/calibration/gaze`)
+    #[serde(default)]
+    pub presence_only: bool,             // only face_detected is reported, see `camera_presence` consent
 }
 
 // NEW: Keystroke Dynamics WITH CONTENT for AI Analysis
@@ -123,6 +836,15 @@ pub struct KeystrokeDynamics {
     pub typed_text: Option<String>,      // actual text typed for AI context
     pub buttons_clicked: Vec<ButtonClick>, // all button/UI clicks tracked
     pub enabled: bool,                   // privacy: user consent status
+    #[serde(default)]
+    pub keyboard_layout: Option<KeyboardLayout>, // layout in use, to compare typing stats per layout
+}
+
+// Active keyboard layout of the foreground window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardLayout {
+    pub layout_id: String,               // Windows HKL ("04090409", "F0020409") or XKB layout ("us", "de(nodeadkeys)")
+    pub language: Option<String>,        // input language as a BCP 47 tag ("en-US"), Windows only
 }
 
 // NEW: Screen Interaction Analysis WITH FULL SCREEN READING
@@ -162,13 +884,130 @@ pub struct FileMetadata {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemEvents {
     pub timestamp: DateTime<Utc>,
-    pub event_type: String,        // "lock", "unlock", "sleep", "wake", "peripheral_connect", "peripheral_disconnect"
-    pub event_subtype: String,     // "usb_device", "monitor", "keyboard", "mouse"
+    pub event_type: String,        // "lock", "unlock", "sleep", "wake", "peripheral_connect", "peripheral_disconnect", "docked", "undocked", "lid_open", "lid_closed"
+    pub event_subtype: String,     // "usb_device", "monitor", "keyboard", "mouse", "external_display", "lid"
     pub session_start: Option<DateTime<Utc>>,
     pub session_end: Option<DateTime<Utc>>,
     pub break_duration_seconds: u64,
     pub active_session_duration_seconds: u64,
     pub daily_rhythm_score: f32,   // consistency of work hours
+    // Microphone/camera streams closed since the previous snapshot
+    #[serde(default)]
+    pub capture_events: Vec<CaptureEvent>,
+    #[serde(default)]
+    pub dock: Option<DockState>,
+}
+
+/// Displays in use and lid position; "docked" means at least one external display is active
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockState {
+    pub docked: bool,
+    pub lid_open: Option<bool>,               // None on devices without a built-in panel
+    pub internal_display_active: bool,
+    pub external_displays: u32,
+}
+
+// Microphone/camera stream open period (privacy indicator audit)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureEvent {
+    pub device: String,            // "microphone", "camera"
+    pub purpose: String,           // what the stream was opened for
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+// One request to a content-grade endpoint (typed text, screen text, transcripts)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentAccessEvent {
+    pub timestamp: DateTime<Utc>,
+    pub requester: String,         // key name, or "unknown" when no valid key was sent
+    pub endpoint: String,
+    pub query: Option<String>,
+    pub status: u16,               // response status; 401/403 for rejected requests
+}
+
+// Flag in data/flags, in the flag_detection service's file format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlagRecord {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    pub flag_type: FlagTypeTag,
+    pub severity: String,
+    pub title: String,
+    pub description: String,
+    pub data_source: String,
+    pub metrics: serde_json::Value,
+    pub confidence: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlagTypeTag {
+    #[serde(rename = "type")]
+    pub name: String,                // e.g. "HealthConcern"
+}
+
+// One raw input event from the forensic ring buffer; keys are reduced to their class
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawInputEvent {
+    pub timestamp: DateTime<Utc>,
+    pub kind: String,                // "key_down", "key_up", "button_down", "button_up", "move" or "wheel"
+    #[serde(default)]
+    pub key_class: Option<String>,   // "letter", "digit", "whitespace", "editing", "modifier", ... for keys
+    #[serde(default)]
+    pub button: Option<String>,      // "left", "right", "middle" or "other" for buttons
+    #[serde(default)]
+    pub position: Option<(f64, f64)>, // pointer position in screen coordinates, where known
+}
+
+// Raw input around a Critical SecurityConcern flag (incidents/, always sealed)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentBundle {
+    pub id: String,
+    pub flag: FlagRecord,
+    pub captured_at: DateTime<Utc>,
+    pub window_secs: u32,            // how far back the events reach from `captured_at`
+    pub events: Vec<RawInputEvent>,  // oldest first
+}
+
+// "Something just happened" mark set by the user, with the snapshots around it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    pub note: Option<String>,
+    pub source: String,              // "hotkey" or "api"
+    pub snapshots: Vec<EyeCoreData>, // oldest first; filled in after the mark as collection continues
+}
+
+// Sidecar metadata stored next to each raw audio file (<name>.meta.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioSegmentMetadata {
+    pub file: String,
+    pub session_id: String,
+    pub recorded_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub sha256: String,
+    pub voice_segments_ms: Vec<(u64, u64)>,  // filled in once cleaning finishes
+    #[serde(default)]
+    pub estimated_speakers: Option<u32>,
+    pub cleaned_file: Option<String>,
+    pub cleaning_report: Option<serde_json::Value>,
+    pub transcription_status: TranscriptionStatus,
+    pub transcription_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptionStatus {
+    Pending,
+    Completed,
+    Failed { error: String },
 }
 
 // NEW: Mouse Movement Dynamics
@@ -186,6 +1025,16 @@ pub struct MouseDynamics {
     pub total_distance_px: u64,        // total pixels traveled
 }
 
+// Wearable heart-rate data summarized over the collection interval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhysiologicalData {
+    pub timestamp: DateTime<Utc>,      // newest sample in the interval
+    pub heart_rate_bpm: f32,           // mean over the interval
+    pub hrv_rmssd_ms: Option<f32>,     // needs beat-to-beat (RR) intervals
+    pub sample_count: u32,
+    pub source: String,                // "ble", "bridge"
+}
+
 // ENHANCED: Network Activity Metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkActivityMetadata {
@@ -199,6 +1048,227 @@ pub struct NetworkActivityMetadata {
     pub latency_avg_ms: f32,           // average ping
     pub packet_loss_rate: f32,         // 0.0 to 1.0
     pub connection_stability: f32,     // 0.0 (unstable) to 1.0 (stable)
+    #[serde(default)]
+    pub routing: Option<NetworkRouting>,
+    #[serde(default)]
+    pub wifi: Option<WifiNetwork>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkCategory {
+    Home,
+    Office,
+    Public,              // labeled public, or an unlabeled open network
+    Unknown,             // unlabeled
+}
+
+impl NetworkCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkCategory::Home => "home",
+            NetworkCategory::Office => "office",
+            NetworkCategory::Public => "public",
+            NetworkCategory::Unknown => "unknown",
+        }
+    }
+}
+
+/// How an app or site counts toward focus, from `classification`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Productivity {
+    Productive,
+    Neutral,
+    Distracting,
+}
+
+impl Productivity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Productivity::Productive => "productive",
+            Productivity::Neutral => "neutral",
+            Productivity::Distracting => "distracting",
+        }
+    }
+}
+
+/// App and site classes for `/classification/apps`; an override wins over the bundled default
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppClassifications {
+    pub defaults: BTreeMap<String, Productivity>,
+    pub overrides: BTreeMap<String, Productivity>,
+}
+
+/// Foreground time of a day by productivity class, for the daily report
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProductivitySummary {
+    pub productive_minutes: i64,
+    pub neutral_minutes: i64,
+    pub distracting_minutes: i64,
+    pub apps: Vec<AppTime>,                   // most time first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppTime {
+    pub app: String,                          // app name, or the site for browser tabs with a rule
+    pub productivity: Productivity,
+    pub minutes: i64,
+}
+
+/// A project detected from window titles and paths (`/projects`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    pub confirmed: bool,                      // confirmed by the user, or only detected so far
+    pub aliases: Vec<String>,                 // lowercase workspace, folder and repository names that count as it
+    pub roots: Vec<String>,                   // project directories found around paths in window titles
+    pub first_seen: DateTime<Utc>,
+}
+
+/// Detected projects and the names the user rejected (`/projects`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Projects {
+    pub projects: Vec<Project>,
+    pub rejected: Vec<String>,                // never detected as a project again
+}
+
+/// Foreground time and focus of a day on one project, for the daily report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTime {
+    pub project: String,
+    pub confirmed: bool,
+    pub minutes: i64,
+    pub avg_focus_level: f32,
+    pub context_switches: u32,
+}
+
+/// What a goal counts (`/goals`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalMetric {
+    DeepWork,      // productive, focused and outside meetings, in blocks of 25 minutes or more
+    Productive,    // in productive apps and sites
+    Active,        // any foreground time while present
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalPeriod {
+    Daily,
+    Weekly,        // Monday to Sunday
+}
+
+/// A goal such as "4h deep work on eyecore per day" (`/goals`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Goal {
+    pub id: String,
+    pub name: String,
+    pub metric: GoalMetric,
+    pub project: Option<String>,              // only time on this project counts; any project if None
+    pub target_minutes: u32,
+    pub period: GoalPeriod,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of `POST /goals`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewGoal {
+    #[serde(default)]
+    pub name: Option<String>,                 // generated from the other fields if missing
+    pub metric: GoalMetric,
+    #[serde(default)]
+    pub project: Option<String>,
+    pub target_minutes: u32,
+    pub period: GoalPeriod,
+}
+
+/// A goal's progress in the current day or week (`/goals/progress`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub minutes: u32,
+    pub percent: f32,                         // of the target, may pass 100
+    pub achieved: bool,
+    pub remaining_minutes: u32,
+    pub projected_minutes: Option<u32>,       // weekly goals: the week's total at the pace so far
+}
+
+/// Whether a goal was met, for the daily and weekly reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalAttainment {
+    pub id: String,
+    pub name: String,
+    pub period: GoalPeriod,
+    pub target_minutes: u32,
+    pub minutes: u32,                         // daily goals in the weekly report: the week's total
+    pub achieved: bool,                       // daily goals in the weekly report: met every day
+    pub days_achieved: Option<u32>,           // daily goals in the weekly report only
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeEntryStatus {
+    Pending,       // waiting for review
+    Submitted,
+    Discarded,
+}
+
+/// A block of work on one project, queued for a time-tracking tool (`/time_entries`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: String,
+    pub project: String,
+    pub description: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub minutes: u32,
+    pub status: TimeEntryStatus,
+    pub remote_id: Option<String>,            // the entry's ID in the time-tracking tool once submitted
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,           // why the last submission failed
+}
+
+/// What a share bundle holds (`POST /share`), as previewed before it is confirmed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareContents {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub fields: Vec<String>,                  // snapshot fields included, besides timestamp and session
+    pub snapshots: usize,
+    pub flags: Option<usize>,                 // None when flags were not asked for
+    pub capture_events: Option<usize>,        // None when logs were not asked for
+    pub collection_gaps: Option<usize>,
+}
+
+/// A built share bundle; the password is only ever returned here
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareBundle {
+    pub id: String,
+    pub url: String,                          // path on the local API serving the zip until it expires
+    pub password: String,
+    pub expires_at: DateTime<Utc>,
+    pub contents: ShareContents,
+}
+
+/// Connected Wi-Fi network; names are only stored as hashes salted per device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WifiNetwork {
+    pub ssid_hash: String,
+    pub bssid_hash: Option<String>,           // access point, to tell rooms or branches apart
+    pub category: NetworkCategory,
+    pub labeled: bool,                        // category chosen by the user rather than inferred
+}
+
+/// VPN and proxy in use; proxy URLs never carry credentials
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkRouting {
+    pub vpn_active: bool,
+    pub vpn_adapters: Vec<String>,            // interface names, e.g. "wg0", "NordLynx"
+    pub vpn_connected_mid_session: bool,      // a VPN came up since the previous snapshot, after the first
+    pub proxy: Option<String>,                // proxy server or PAC URL
 }
 
 // NEW: Window Content Capture for AI Analysis - MAXIMUM DATA
@@ -269,6 +1339,9 @@ pub struct EnhancedScreenKeystrokeData {
     pub enhanced_keystroke_data: EnhancedKeystrokeData,
     pub enhanced_screen_data: EnhancedScreenData,
     pub context_metadata: Option<ContextMetadata>,
+    // Personal data masked in the typed and screen text, matches per redaction rule
+    #[serde(default)]
+    pub redactions: BTreeMap<String, u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -361,7 +1434,9 @@ pub struct ContextMetadata {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttentionZone {
-    pub region: Vec<i32>,
+    pub region: Vec<i32>,                // x, y, width, height in screen pixels
     pub attention_score: f32,
     pub duration_seconds: f32,
+    #[serde(default)]
+    pub gaze_score: Option<f32>,         // share of calibrated gaze in the zone; None without gaze
 }
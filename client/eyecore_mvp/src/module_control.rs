@@ -0,0 +1,202 @@
+//! Settings of the modules behind `/control`: on/off, a sampling interval for modules that need
+//! not run with every snapshot, and how many days their stored data is kept. Intervals and
+//! retention persist in `<device>/modules.json`; on/off follows recorded consent (see consent) and
+//! the config at startup, and a module is only switched on here once consent is granted. System
+//! metrics and screen interactions are always on; only their sampling interval can be changed.
+
+use chrono::{NaiveDate, Utc};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::data_collector::DataCollector;
+use crate::models::{ModuleSettings, ModuleUpdate};
+use crate::storage::DataStorage;
+
+pub const MAX_SAMPLING_INTERVAL_SECS: u64 = 86_400;
+pub const MAX_RETENTION_DAYS: u32 = 3650;
+
+/// API name, snapshot field, and the storage categories that hold only this module's data
+const MODULES: [(&str, &str, &[&str]); 4] = [
+    ("voice", "voice_data", &["raw_audio", "transcriptions"]),
+    ("camera", "camera_data", &[]),
+    ("keystroke", "keystroke_dynamics", &["input_trace"]),
+    ("files", "file_metadata", &[]),
+];
+
+/// API name and snapshot field of the always-on modules with a sampling interval of their own
+const CORE_MODULES: [(&str, &str); 2] = [("system", "system_metrics"), ("screen", "screen_interactions")];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct StoredSettings {
+    sampling_interval_secs: Option<u64>,
+    retention_days: Option<u32>,
+    expired_through: Option<NaiveDate>,    // last day whose data was already removed
+}
+
+pub struct ModuleControl {
+    path: PathBuf,
+    settings: BTreeMap<String, StoredSettings>,
+}
+
+/// Whether `/control/{module}` names a module
+pub fn is_module(module: &str) -> bool {
+    field(module).is_some()
+}
+
+/// Snapshot field of the module `/control/{module}` names
+fn field(module: &str) -> Option<&'static str> {
+    MODULES
+        .iter()
+        .map(|(name, field, _)| (*name, *field))
+        .chain(CORE_MODULES)
+        .find(|(name, _)| *name == module)
+        .map(|(_, field)| field)
+}
+
+fn set_enabled(collector: &mut DataCollector, field: &str, enabled: bool) {
+    match (field, enabled) {
+        ("voice_data", true) => collector.enable_voice(),
+        ("voice_data", false) => collector.disable_voice(),
+        ("camera_data", true) => collector.enable_camera(),
+        ("camera_data", false) => collector.disable_camera(),
+        ("keystroke_dynamics", true) => collector.enable_keystroke(),
+        ("keystroke_dynamics", false) => collector.disable_keystroke(),
+        ("file_metadata", true) => collector.enable_file_monitoring(),
+        ("file_metadata", false) => collector.disable_file_monitoring(),
+        _ => {}
+    }
+}
+
+impl ModuleControl {
+    /// Settings kept in `device_dir`
+    pub fn load(device_dir: &Path) -> Self {
+        let path = device_dir.join("modules.json");
+        let settings = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("⚠️ Ignoring unreadable {:?}: {}", path, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        ModuleControl { path, settings }
+    }
+
+    /// Hand the saved sampling intervals to the collector
+    pub fn apply(&self, collector: &mut DataCollector) {
+        for (name, field) in MODULES.iter().map(|(name, field, _)| (*name, *field)).chain(CORE_MODULES) {
+            let interval = self.settings.get(name).and_then(|settings| settings.sampling_interval_secs);
+            collector.set_sampling_interval(field, interval.map(Duration::from_secs));
+        }
+    }
+
+    /// Settings of `name`; scheduled modules report the interval they are sampled at
+    fn module_settings(&self, name: &str, field: &str, collector: &DataCollector) -> ModuleSettings {
+        let stored = self.settings.get(name).cloned().unwrap_or_default();
+        let scheduled = collector.module_interval(field).map(|interval| interval.as_secs());
+        ModuleSettings {
+            module: name.to_string(),
+            enabled: collector.enabled_modules().get(field).copied().unwrap_or(false),
+            sampling_interval_secs: stored.sampling_interval_secs.or(scheduled),
+            retention_days: stored.retention_days,
+        }
+    }
+
+    pub fn list(&self, collector: &DataCollector) -> Vec<ModuleSettings> {
+        MODULES
+            .iter()
+            .map(|(name, field, _)| (*name, *field))
+            .chain(CORE_MODULES)
+            .map(|(name, field)| self.module_settings(name, field, collector))
+            .collect()
+    }
+
+    /// Apply a `POST /control/{module}` body; nothing changes unless every field is valid
+    pub fn update(&mut self, module: &str, update: ModuleUpdate, collector: &mut DataCollector) -> Result<ModuleSettings, String> {
+        let field = field(module).ok_or_else(|| format!("Unknown module {:?}", module))?;
+        if CORE_MODULES.iter().any(|(name, _)| *name == module) {
+            if update.enabled == Some(false) {
+                return Err(format!("The {} module is always on", module));
+            }
+            if update.retention_days.is_some_and(|days| days > 0) {
+                return Err(format!("{} data is kept with every snapshot; it has no retention period", module));
+            }
+        }
+        if update.sampling_interval_secs.is_some_and(|secs| secs > MAX_SAMPLING_INTERVAL_SECS) {
+            return Err(format!("sampling_interval_secs must be at most {}", MAX_SAMPLING_INTERVAL_SECS));
+        }
+        if update.retention_days.is_some_and(|days| days > MAX_RETENTION_DAYS) {
+            return Err(format!("retention_days must be at most {}", MAX_RETENTION_DAYS));
+        }
+
+        let stored = self.settings.entry(module.to_string()).or_default();
+        if let Some(secs) = update.sampling_interval_secs {
+            stored.sampling_interval_secs = (secs > 0).then_some(secs);
+            collector.set_sampling_interval(field, stored.sampling_interval_secs.map(Duration::from_secs));
+        }
+        if let Some(days) = update.retention_days {
+            stored.retention_days = (days > 0).then_some(days);
+        }
+        if let Some(enabled) = update.enabled {
+            set_enabled(collector, field, enabled);
+        }
+        self.save();
+
+        let settings = self.module_settings(module, field, collector);
+        info!(
+            "🎛️ {} module: enabled={}, sampling interval={:?}s, retention={:?} days",
+            module, settings.enabled, settings.sampling_interval_secs, settings.retention_days
+        );
+        Ok(settings)
+    }
+
+    /// Remove stored data that is past each module's retention period. Every day is only
+    /// processed once; a day that fails is retried on the next run.
+    pub async fn expire(&mut self, storage: &DataStorage) {
+        let dates = match storage.list_dates().await {
+            Ok(dates) => dates,
+            Err(e) => {
+                error!("Retention check failed: {}", e);
+                return;
+            }
+        };
+        let today = Utc::now().date_naive();
+        let mut changed = false;
+
+        for (name, field, categories) in MODULES {
+            let Some(stored) = self.settings.get_mut(name) else { continue };
+            let Some(days) = stored.retention_days else { continue };
+            let cutoff = today - chrono::Duration::days(days as i64);
+            for &date in dates.iter().filter(|date| **date <= cutoff) {
+                if stored.expired_through >= Some(date) {
+                    continue;
+                }
+                match storage.expire_module_data(field, categories, date).await {
+                    Ok(0) => {}
+                    Ok(files) => info!("🧹 Removed {} data of {} from {} files (retention {} days)", name, date, files, days),
+                    Err(e) => {
+                        error!("Failed to remove {} data of {}: {}", name, date, e);
+                        break;
+                    }
+                }
+                stored.expired_through = Some(date);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let result = serde_json::to_string_pretty(&self.settings)
+            .map_err(|e| e.to_string())
+            .and_then(|content| std::fs::write(&self.path, content).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            error!("Failed to save module settings {:?}: {}", self.path, e);
+        }
+    }
+}
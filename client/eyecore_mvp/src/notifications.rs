@@ -0,0 +1,164 @@
+//! Notification monitoring: which apps send toast notifications and how often, read through the
+//! Windows notification listener. Only the sending app and arrival time are read, never the
+//! content. Arrivals feed focus attribution (`focus_context.notifications`) and `/data/notifications`.
+
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+use std::collections::{HashMap, VecDeque};
+
+use crate::models::{AppNotificationFrequency, NotificationArrival, NotificationStats};
+
+pub struct NotificationMonitor {
+    #[cfg(target_os = "windows")]
+    listener: Option<windows::UI::Notifications::Management::UserNotificationListener>,
+    #[cfg(target_os = "windows")]
+    seen: std::collections::HashSet<u32>,
+    started_at: DateTime<Utc>,
+    latest: Vec<NotificationArrival>,             // found by the most recent poll
+    per_app: HashMap<String, (u64, DateTime<Utc>)>, // count and last arrival
+    recent: VecDeque<NotificationArrival>,        // the last hour
+}
+
+impl NotificationMonitor {
+    /// Access to the listener is requested once. EYECORE_NOTIFICATIONS=0 skips the listener (and
+    /// its access prompt) entirely; where access is denied, or on other platforms, nothing is reported.
+    pub fn new() -> Self {
+        let enabled = !matches!(std::env::var("EYECORE_NOTIFICATIONS").as_deref().map(str::trim), Ok("0" | "false" | "off"));
+        let monitor = NotificationMonitor {
+            #[cfg(target_os = "windows")]
+            listener: if enabled { Self::open_listener() } else { None },
+            #[cfg(target_os = "windows")]
+            seen: std::collections::HashSet::new(),
+            started_at: Utc::now(),
+            latest: Vec::new(),
+            per_app: HashMap::new(),
+            recent: VecDeque::new(),
+        };
+        if enabled && !monitor.available() {
+            info!("Notification listener unavailable; notification counts and attribution are disabled");
+        }
+        monitor
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn available(&self) -> bool {
+        self.listener.is_some()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn available(&self) -> bool {
+        false
+    }
+
+    /// Look for notifications that arrived since the previous poll; called once per collection
+    pub fn poll(&mut self) {
+        let now = Utc::now();
+        self.latest = self.read_new();
+        for arrival in &self.latest {
+            let entry = self.per_app.entry(arrival.app.clone()).or_insert((0, arrival.timestamp));
+            entry.0 += 1;
+            entry.1 = entry.1.max(arrival.timestamp);
+            self.recent.push_back(arrival.clone());
+        }
+        while self.recent.front().is_some_and(|arrival| now - arrival.timestamp > Duration::hours(1)) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Arrivals found by the most recent poll
+    pub fn since_last_poll(&self) -> &[NotificationArrival] {
+        &self.latest
+    }
+
+    /// Counts per app since startup, most frequent first
+    pub fn stats(&self) -> NotificationStats {
+        // Rates are averaged over at least an hour, so the first few notifications do not read as a flood
+        let hours = ((Utc::now() - self.started_at).num_seconds() as f64 / 3600.0).max(1.0);
+        let mut apps: Vec<AppNotificationFrequency> = self
+            .per_app
+            .iter()
+            .map(|(app, (count, last_seen))| AppNotificationFrequency {
+                app: app.clone(),
+                count: *count,
+                last_hour: self.recent.iter().filter(|arrival| &arrival.app == app).count() as u64,
+                per_hour: *count as f64 / hours,
+                last_seen: *last_seen,
+            })
+            .collect();
+        apps.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.app.cmp(&b.app)));
+
+        NotificationStats {
+            available: self.available(),
+            since: self.started_at,
+            total: apps.iter().map(|app| app.count).sum(),
+            last_hour: self.recent.len() as u64,
+            apps,
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn open_listener() -> Option<windows::UI::Notifications::Management::UserNotificationListener> {
+        use windows::UI::Notifications::Management::{UserNotificationListener, UserNotificationListenerAccessStatus};
+
+        UserNotificationListener::Current().ok().filter(|listener| {
+            listener
+                .RequestAccessAsync()
+                .and_then(|request| request.get())
+                .is_ok_and(|status| status == UserNotificationListenerAccessStatus::Allowed)
+        })
+    }
+
+    #[cfg(target_os = "windows")]
+    fn read_new(&mut self) -> Vec<NotificationArrival> {
+        use windows::UI::Notifications::NotificationKinds;
+
+        // 100 ns ticks between 1601-01-01 (Windows epoch) and 1970-01-01
+        const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+
+        let Some(listener) = &self.listener else {
+            return Vec::new();
+        };
+        let Ok(notifications) = listener.GetNotificationsAsync(NotificationKinds::Toast).and_then(|op| op.get()) else {
+            return Vec::new();
+        };
+
+        let mut arrivals = Vec::new();
+        let mut current = std::collections::HashSet::new();
+        for notification in notifications {
+            let Ok(id) = notification.Id() else { continue };
+            current.insert(id);
+            if self.seen.contains(&id) {
+                continue;
+            }
+            let timestamp = notification
+                .CreationTime()
+                .ok()
+                .and_then(|time| {
+                    let ticks = time.UniversalTime - UNIX_EPOCH_TICKS;
+                    DateTime::from_timestamp(ticks.div_euclid(10_000_000), (ticks.rem_euclid(10_000_000) * 100) as u32)
+                })
+                .unwrap_or_else(Utc::now);
+            // Notifications already in the action center at startup are not new
+            if timestamp < self.started_at {
+                continue;
+            }
+            // Only the app's display name; the toast's text and images are never read
+            let app = notification
+                .AppInfo()
+                .and_then(|info| info.DisplayInfo())
+                .and_then(|display| display.DisplayName())
+                .map(|name| name.to_string())
+                .unwrap_or_else(|_| "unknown".to_string());
+            arrivals.push(NotificationArrival { timestamp, app });
+        }
+        // Dismissed notifications drop out of the action center, so only remember current ones
+        self.seen = current;
+        arrivals.sort_by_key(|arrival| arrival.timestamp);
+        arrivals
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn read_new(&mut self) -> Vec<NotificationArrival> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,308 @@
+//! First-run onboarding: consent for each optional module, a check of the devices the consented
+//! modules need, and a baseline calibration period. Snapshots are only taken once calibration starts.
+//! Consent answers are recorded with the consent manager, which decides what collects.
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::calibration::{Calibration, DEFAULT_CALIBRATION_MINUTES};
+use crate::consent::{self, ConsentManager};
+use crate::data_collector::DataCollector;
+use crate::input_hooks;
+use crate::models::{DeviceTestResult, OnboardingStatus, OnboardingStep};
+
+/// The input check passes when a key press or click arrived this recently
+const INPUT_TEST_WINDOW_SECS: u32 = 30;
+
+/// Modules that only run with consent, and the question asked for each
+const CONSENT_MODULES: [(&str, &str); 6] = [
+    ("voice_data", "Record short microphone samples to measure speaking time and vocal stress?"),
+    ("camera_data", "Use the camera to estimate posture, gaze and facial expressions?"),
+    ("keystroke_dynamics", "Capture keystroke timing (when keys go down and up, not which keys) for typing rhythm?"),
+    ("typed_text", "Also capture the text you type and the buttons you click, for content analysis?"),
+    ("screen_text", "Capture the text visible on screen and in open windows?"),
+    ("file_metadata", "Record file types, sizes and access patterns (never file names or contents)?"),
+];
+
+/// Devices checked during onboarding, and the module that needs each (None: always checked)
+const DEVICE_TESTS: [(&str, Option<&str>, &str); 3] = [
+    ("microphone", Some("voice_data"), "Check that a microphone can record."),
+    ("camera", Some("camera_data"), "Check that a camera is available."),
+    ("input", None, "Press a key or click anywhere, then run the check to confirm input is counted."),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Consent(&'static str),
+    DeviceTest(&'static str),
+    Calibration,
+    Complete,
+}
+
+/// Answers so far, persisted so onboarding resumes where it stopped after a restart
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OnboardingRecord {
+    consents: BTreeMap<String, bool>,
+    device_tests: BTreeMap<String, DeviceTestResult>,
+    calibration_started_at: Option<DateTime<Utc>>,
+    calibration_ends_at: Option<DateTime<Utc>>,
+    calibration_skipped: bool,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+pub struct Onboarding {
+    path: PathBuf,
+    record: OnboardingRecord,
+}
+
+impl Onboarding {
+    /// Resume onboarding from `path`. Devices that already collected before onboarding existed, and
+    /// deployments that opt out with `EYECORE_ONBOARDING=0`, start out complete.
+    pub fn load(path: PathBuf, existing_install: bool) -> Self {
+        let record = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("⚠️ Restarting onboarding, unreadable state {:?}: {}", path, e);
+                OnboardingRecord::default()
+            }),
+            Err(_) => OnboardingRecord::default(),
+        };
+        let mut onboarding = Onboarding { path, record };
+
+        let opted_out = std::env::var("EYECORE_ONBOARDING").as_deref() == Ok("0");
+        if onboarding.record.completed_at.is_none() && (existing_install || opted_out) {
+            onboarding.record.completed_at = Some(Utc::now());
+            onboarding.save();
+            info!("✓ Onboarding skipped ({})", if opted_out { "EYECORE_ONBOARDING=0" } else { "existing data" });
+        } else if onboarding.record.completed_at.is_none() {
+            info!("👋 Onboarding pending, collection starts once it is answered via /onboarding/answer");
+        }
+        onboarding
+    }
+
+    /// Whether snapshots may be taken, i.e. calibration has started or onboarding is complete.
+    /// Completes onboarding once calibration has run its course.
+    pub fn poll(&mut self) -> bool {
+        if self.record.completed_at.is_none() && self.current() == Step::Complete {
+            self.record.completed_at = Some(Utc::now());
+            self.save();
+            info!("✓ Onboarding complete, full collection running");
+        }
+        self.collection_allowed()
+    }
+
+    /// Consent answers so far, keyed by module
+    pub fn consents(&self) -> &BTreeMap<String, bool> {
+        &self.record.consents
+    }
+
+    /// Take over consent answers given on another device (configuration bundles); the device checks
+    /// and calibration still run here
+    pub fn import_consents(&mut self, consents: &BTreeMap<String, bool>) -> Result<(), String> {
+        for (module, granted) in consents {
+            if CONSENT_MODULES.iter().any(|(name, _)| name == module) {
+                self.record.consents.insert(module.clone(), *granted);
+            }
+        }
+        self.write()
+    }
+
+    pub fn status(&self) -> OnboardingStatus {
+        let steps = self.steps();
+        let complete = self.record.completed_at.is_some();
+        OnboardingStatus {
+            complete,
+            collection_allowed: self.collection_allowed(),
+            step: self.describe(self.current()),
+            steps_done: if complete { steps.len() } else { steps.iter().filter(|step| self.done(**step)).count() },
+            steps_total: steps.len(),
+            consents: self.record.consents.clone(),
+            device_tests: self.record.device_tests.clone(),
+            calibration_started_at: self.record.calibration_started_at,
+            calibration_ends_at: self.record.calibration_ends_at,
+            completed_at: self.record.completed_at,
+        }
+    }
+
+    /// Answer the current step with one of its `answers`; consent answers are recorded in `consent`,
+    /// starting calibration starts a session in `calibration`
+    pub fn answer(
+        &mut self,
+        answer: &str,
+        collector: &mut DataCollector,
+        consent: &mut ConsentManager,
+        calibration: &mut Calibration,
+    ) -> Result<OnboardingStatus, String> {
+        let step = self.current();
+        let expected = self.describe(step);
+        if !expected.answers.iter().any(|accepted| accepted == answer) {
+            return Err(if expected.answers.is_empty() {
+                "Onboarding is already complete".to_string()
+            } else {
+                format!("Expected one of {} for \"{}\", got \"{}\"", expected.answers.join(", "), expected.id, answer)
+            });
+        }
+
+        match step {
+            Step::Consent(module) => {
+                let granted = answer == "yes";
+                if let Some(name) = consent::module_for_field(module) {
+                    consent.set(name, granted, "onboarding", None, collector)?;
+                }
+                self.record.consents.insert(module.to_string(), granted);
+                info!("✓ Consent for {}: {}", module, answer);
+            }
+            Step::DeviceTest(device) => {
+                let result = if answer == "skip" {
+                    DeviceTestResult { passed: false, skipped: true, detail: "Skipped".to_string(), tested_at: Utc::now() }
+                } else {
+                    run_device_test(device)
+                };
+                if result.passed {
+                    info!("✓ Device check {} passed: {}", device, result.detail);
+                } else if !result.skipped {
+                    warn!("⚠️ Device check {} failed: {}", device, result.detail);
+                }
+                self.record.device_tests.insert(device.to_string(), result);
+            }
+            Step::Calibration if answer == "start" => {
+                let session = calibration.start(DEFAULT_CALIBRATION_MINUTES)?;
+                self.record.calibration_started_at = session.started_at;
+                self.record.calibration_ends_at = session.ends_at;
+            }
+            Step::Calibration => self.record.calibration_skipped = true,
+            Step::Complete => {}
+        }
+        self.save();
+        self.poll();
+        Ok(self.status())
+    }
+
+    fn collection_allowed(&self) -> bool {
+        self.record.completed_at.is_some() || self.record.calibration_started_at.is_some()
+    }
+
+    /// Steps in order; typed text without keystrokes and device checks for declined modules drop out
+    fn steps(&self) -> Vec<Step> {
+        let mut steps: Vec<Step> = CONSENT_MODULES
+            .iter()
+            .filter(|(module, _)| *module != "typed_text" || self.record.consents.get("keystroke_dynamics") != Some(&false))
+            .map(|(module, _)| Step::Consent(module))
+            .collect();
+        for (device, module, _) in DEVICE_TESTS {
+            if module.is_none_or(|module| self.record.consents.get(module) != Some(&false)) {
+                steps.push(Step::DeviceTest(device));
+            }
+        }
+        steps.push(Step::Calibration);
+        steps
+    }
+
+    fn done(&self, step: Step) -> bool {
+        match step {
+            Step::Consent(module) => self.record.consents.contains_key(module),
+            // A failed check stays current so it can be re-run after fixing the device, or skipped
+            Step::DeviceTest(device) => self.record.device_tests.get(device).is_some_and(|test| test.passed || test.skipped),
+            Step::Calibration => {
+                self.record.calibration_skipped || self.record.calibration_ends_at.is_some_and(|end| Utc::now() >= end)
+            }
+            Step::Complete => true,
+        }
+    }
+
+    fn current(&self) -> Step {
+        if self.record.completed_at.is_some() {
+            return Step::Complete;
+        }
+        self.steps().into_iter().find(|step| !self.done(*step)).unwrap_or(Step::Complete)
+    }
+
+    fn describe(&self, step: Step) -> OnboardingStep {
+        let (id, kind, prompt, answers): (String, &str, String, &[&str]) = match step {
+            Step::Consent(module) => {
+                let prompt = CONSENT_MODULES.iter().find(|(name, _)| *name == module).map_or("", |(_, prompt)| prompt);
+                (format!("consent.{}", module), "consent", prompt.to_string(), &["yes", "no"])
+            }
+            Step::DeviceTest(device) => {
+                let prompt = DEVICE_TESTS.iter().find(|(name, _, _)| *name == device).map_or("", |(_, _, prompt)| prompt);
+                (format!("device_test.{}", device), "device_test", prompt.to_string(), &["run", "skip"])
+            }
+            Step::Calibration => match self.record.calibration_ends_at {
+                Some(end) => (
+                    "calibration".to_string(),
+                    "calibrating",
+                    format!("Calibrating: keep working as usual until {}.", end.to_rfc3339()),
+                    &["skip"],
+                ),
+                None => (
+                    "calibration".to_string(),
+                    "calibration",
+                    format!("Collect {} minutes of normal use as your personal baseline?", DEFAULT_CALIBRATION_MINUTES),
+                    &["start", "skip"],
+                ),
+            },
+            Step::Complete => ("complete".to_string(), "complete", "Onboarding complete.".to_string(), &[]),
+        };
+        OnboardingStep {
+            id,
+            kind: kind.to_string(),
+            prompt,
+            answers: answers.iter().map(|answer| answer.to_string()).collect(),
+        }
+    }
+
+    fn save(&self) {
+        if let Err(e) = self.write() {
+            log::error!("{}", e);
+        }
+    }
+
+    fn write(&self) -> Result<(), String> {
+        serde_json::to_string_pretty(&self.record)
+            .map_err(|e| e.to_string())
+            .and_then(|content| std::fs::write(&self.path, content).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to save onboarding state {:?}: {}", self.path, e))
+    }
+}
+
+fn run_device_test(device: &str) -> DeviceTestResult {
+    let outcome = match device {
+        "microphone" => test_microphone(),
+        "camera" => test_camera(),
+        _ => test_input(),
+    };
+    let (passed, detail) = match outcome {
+        Ok(found) => (true, found),
+        Err(problem) => (false, problem),
+    };
+    DeviceTestResult { passed, skipped: false, detail, tested_at: Utc::now() }
+}
+
+fn test_microphone() -> Result<String, String> {
+    use cpal::traits::{DeviceTrait, HostTrait};
+    let device = cpal::default_host().default_input_device().ok_or("No microphone found")?;
+    let config = device.default_input_config().map_err(|e| format!("Microphone cannot record: {}", e))?;
+    let name = device.name().unwrap_or_else(|_| "Default microphone".to_string());
+    Ok(format!("{} ({} Hz)", name, config.sample_rate().0))
+}
+
+fn test_camera() -> Result<String, String> {
+    let cameras = nokhwa::query(nokhwa::utils::ApiBackend::Auto).map_err(|e| format!("Cameras cannot be listed: {}", e))?;
+    cameras.first().map(|camera| camera.human_name()).ok_or_else(|| "No camera found".to_string())
+}
+
+fn test_input() -> Result<String, String> {
+    if cfg!(feature = "simulated-input") {
+        return Ok("Simulated input (simulated-input build)".to_string());
+    }
+    let hooks = input_hooks::global();
+    if !hooks.available() {
+        return Err("Input hooks unavailable (no display server or missing permissions)".to_string());
+    }
+    match hooks.idle_seconds() {
+        idle if idle <= INPUT_TEST_WINDOW_SECS => Ok("Input received".to_string()),
+        idle => Err(format!("No key press or click for {} s; press a key, then run the check again", idle)),
+    }
+}
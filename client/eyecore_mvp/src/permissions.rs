@@ -0,0 +1,360 @@
+//! OS permissions the collectors depend on (`/permissions`). Without them cameras, microphones,
+//! window titles and input hooks do not fail loudly: they return nothing, silence or simulated
+//! values. Each permission is checked without capturing anything where the OS allows it; a denied
+//! one comes with the exact steps to grant it, and `POST /permissions/{name}/request` shows the OS
+//! prompt (or opens the settings page) where there is one.
+
+use chrono::Utc;
+use log::{info, warn};
+use std::sync::{Arc, Mutex};
+
+use crate::active_window;
+use crate::capture_log::CaptureTracker;
+use crate::input_hooks;
+use crate::models::{PermissionState, PermissionStatus};
+
+/// Permission names, and the snapshot fields that depend on each
+const PERMISSIONS: [(&str, &[&str]); 4] = [
+    ("camera", &["camera_data"]),
+    ("microphone", &["voice_data"]),
+    ("accessibility", &["process_data.active_window_title", "focus_context"]),
+    ("input_monitoring", &["input_metrics", "keystroke_dynamics", "mouse_dynamics"]),
+];
+
+/// How long the microphone records to find out whether it is allowed
+const MICROPHONE_PROBE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Whether `/permissions/{name}` names a permission
+pub fn is_permission(name: &str) -> bool {
+    PERMISSIONS.iter().any(|(permission, _)| *permission == name)
+}
+
+#[derive(Default)]
+pub struct PermissionChecker {
+    // Outcome of the last microphone recording; macOS only tells by handing out silence
+    microphone: Mutex<Option<(PermissionState, Option<String>)>>,
+}
+
+impl PermissionChecker {
+    pub fn new() -> Self {
+        PermissionChecker::default()
+    }
+
+    /// Every permission; runs OS helper commands, so call it off the async runtime
+    pub fn check_all(&self) -> Vec<PermissionStatus> {
+        PERMISSIONS.iter().filter_map(|(name, _)| self.check(name)).collect()
+    }
+
+    pub fn check(&self, name: &str) -> Option<PermissionStatus> {
+        let (state, detail) = match name {
+            "camera" => check_camera(),
+            "microphone" => self.check_microphone(),
+            "accessibility" => check_accessibility(),
+            "input_monitoring" => check_input_monitoring(),
+            _ => return None,
+        };
+        Some(status(name, state, detail))
+    }
+
+    /// Show the OS prompt for `name` where there is one, or open its settings page while it is
+    /// not granted. The microphone is recorded briefly, which is what prompts for it. Returns the
+    /// status afterwards and whether anything was shown.
+    pub fn request(&self, name: &str, capture_tracker: &Arc<CaptureTracker>) -> Result<(PermissionStatus, bool), String> {
+        let before = self.check(name).ok_or_else(|| format!("Unknown permission {:?}", name))?;
+        let prompted = match name {
+            "microphone" => {
+                let outcome = {
+                    let _capture = capture_tracker.open("microphone", "permission_check");
+                    probe_microphone()
+                };
+                *self.microphone.lock().unwrap() = Some(outcome);
+                true
+            }
+            _ if before.state != PermissionState::Denied && before.state != PermissionState::Unknown => false,
+            "camera" => request_camera(),
+            _ => open_settings(name),
+        };
+        let after = self.check(name).unwrap_or(before);
+        // A refused microphone is not asked about again, so its settings page is all there is
+        if name == "microphone" && after.state == PermissionState::Denied {
+            open_settings(name);
+        }
+        info!("🔐 Permission {}: {:?} (prompted: {})", name, after.state, prompted);
+        Ok((after, prompted))
+    }
+
+    fn check_microphone(&self) -> (PermissionState, Option<String>) {
+        if let Some(probed) = self.microphone.lock().unwrap().clone() {
+            return probed;
+        }
+        use cpal::traits::{DeviceTrait, HostTrait};
+        let Some(device) = cpal::default_host().default_input_device() else {
+            return (PermissionState::Unavailable, Some("No microphone found".to_string()));
+        };
+        if let Err(e) = device.default_input_config() {
+            return (PermissionState::Unavailable, Some(format!("Microphone cannot record: {}", e)));
+        }
+        if cfg!(target_os = "windows") {
+            return match windows_consent("microphone") {
+                Some(true) => (PermissionState::Granted, None),
+                Some(false) => (PermissionState::Denied, None),
+                None => (PermissionState::Unknown, None),
+            };
+        }
+        if cfg!(target_os = "macos") {
+            return (
+                PermissionState::Unknown,
+                Some("macOS only tells by recording; POST /permissions/microphone/request records half a second".to_string()),
+            );
+        }
+        (PermissionState::Granted, None)
+    }
+}
+
+/// Warn about each denied permission at startup, with the steps to grant it
+pub fn log_denied(statuses: &[PermissionStatus]) {
+    for status in statuses.iter().filter(|status| status.state == PermissionState::Denied) {
+        warn!(
+            "⚠️ No {} permission, {} will be missing or simulated. {}",
+            status.permission,
+            status.needed_by.join(", "),
+            status.remediation.as_deref().unwrap_or_default()
+        );
+    }
+}
+
+fn status(name: &str, state: PermissionState, detail: Option<String>) -> PermissionStatus {
+    let needed_by = PERMISSIONS
+        .iter()
+        .find(|(permission, _)| *permission == name)
+        .map(|(_, fields)| fields.iter().map(|field| field.to_string()).collect())
+        .unwrap_or_default();
+    // A missing camera or microphone is not something a setting fixes
+    let fixable = match state {
+        PermissionState::Denied | PermissionState::Unknown => true,
+        PermissionState::Unavailable => !matches!(name, "camera" | "microphone"),
+        PermissionState::Granted | PermissionState::NotNeeded => false,
+    };
+    PermissionStatus {
+        permission: name.to_string(),
+        state,
+        needed_by,
+        detail,
+        remediation: fixable.then(|| remediation(name).to_string()),
+        checked_at: Utc::now(),
+    }
+}
+
+/// What to do to grant `name` on this OS
+fn remediation(name: &str) -> &'static str {
+    match name {
+        "camera" if cfg!(target_os = "macos") => {
+            "Open System Settings > Privacy & Security > Camera and switch on the app running eyecore_mvp (your terminal when started from one), then restart eyecore_mvp."
+        }
+        "camera" if cfg!(target_os = "windows") => {
+            "Open Settings > Privacy & security > Camera and switch on both \"Camera access\" and \"Let desktop apps access your camera\"."
+        }
+        "camera" => "Add your user to the video group (sudo usermod -aG video $USER), then log out and back in.",
+        "microphone" if cfg!(target_os = "macos") => {
+            "Open System Settings > Privacy & Security > Microphone and switch on the app running eyecore_mvp (your terminal when started from one), then restart eyecore_mvp."
+        }
+        "microphone" if cfg!(target_os = "windows") => {
+            "Open Settings > Privacy & security > Microphone and switch on both \"Microphone access\" and \"Let desktop apps access your microphone\"."
+        }
+        "microphone" => "Add your user to the audio group (sudo usermod -aG audio $USER), then log out and back in.",
+        "accessibility" if cfg!(target_os = "macos") => {
+            "Open System Settings > Privacy & Security > Accessibility and switch on the app running eyecore_mvp; under Automation, allow it to control System Events."
+        }
+        "accessibility" => "Run an X11 session, or a Wayland session under sway or Hyprland, whose focused window can be read.",
+        "input_monitoring" if cfg!(target_os = "macos") => {
+            "Open System Settings > Privacy & Security > Input Monitoring and Accessibility, switch on the app running eyecore_mvp in both, then restart eyecore_mvp."
+        }
+        "input_monitoring" if cfg!(target_os = "windows") => {
+            "Input hooks could not be installed; run eyecore_mvp in the signed-in user's desktop session, not as a service."
+        }
+        _ => "Input hooks need an X11 session (or XWayland) with the RECORD extension; restart eyecore_mvp from one.",
+    }
+}
+
+fn check_camera() -> (PermissionState, Option<String>) {
+    #[cfg(target_os = "linux")]
+    {
+        let mut devices: Vec<_> = std::fs::read_dir("/dev")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("video")))
+            .collect();
+        devices.sort();
+        let Some(device) = devices.first() else {
+            return (PermissionState::Unavailable, Some("No camera found".to_string()));
+        };
+        // Opening the device node does not start a capture
+        match std::fs::File::open(device) {
+            Ok(_) => (PermissionState::Granted, Some(device.display().to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                (PermissionState::Denied, Some(format!("{} is not readable", device.display())))
+            }
+            Err(e) => (PermissionState::Unavailable, Some(format!("{}: {}", device.display(), e))),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        match nokhwa::query(nokhwa::utils::ApiBackend::Auto) {
+            Ok(cameras) if cameras.is_empty() => (PermissionState::Unavailable, Some("No camera found".to_string())),
+            Err(e) => (PermissionState::Unavailable, Some(format!("Cameras cannot be listed: {}", e))),
+            Ok(cameras) => {
+                let name = cameras.first().map(|camera| camera.human_name());
+                let granted = if cfg!(target_os = "windows") { windows_consent("webcam") } else { Some(nokhwa::nokhwa_check()) };
+                match granted {
+                    Some(true) => (PermissionState::Granted, name),
+                    Some(false) => (PermissionState::Denied, name),
+                    None => (PermissionState::Unknown, name),
+                }
+            }
+        }
+    }
+}
+
+/// Ask macOS for camera access; elsewhere there is no prompt and the settings page is opened
+fn request_camera() -> bool {
+    if cfg!(target_os = "macos") {
+        nokhwa::nokhwa_initialize(|granted| info!("🔐 Camera access {}", if granted { "granted" } else { "refused" }));
+        true
+    } else {
+        open_settings("camera")
+    }
+}
+
+/// Record briefly and see whether anything but silence arrives; macOS hands out zeros while the
+/// microphone is not allowed, Windows refuses to open it
+fn probe_microphone() -> (PermissionState, Option<String>) {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let Some(device) = cpal::default_host().default_input_device() else {
+        return (PermissionState::Unavailable, Some("No microphone found".to_string()));
+    };
+    let config = match device.default_input_config() {
+        Ok(config) => config,
+        Err(e) => return (PermissionState::Unavailable, Some(format!("Microphone cannot record: {}", e))),
+    };
+    let heard = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&heard);
+    let stream = device.build_input_stream_raw(
+        &config.config(),
+        config.sample_format(),
+        move |data: &cpal::Data, _: &cpal::InputCallbackInfo| {
+            if data.bytes().iter().any(|byte| *byte != 0) {
+                flag.store(true, Ordering::Relaxed);
+            }
+        },
+        |e| warn!("⚠️ Microphone check: {}", e),
+        Some(MICROPHONE_PROBE),
+    );
+    let stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => return (PermissionState::Denied, Some(format!("Microphone cannot be opened: {}", e))),
+    };
+    if let Err(e) = stream.play() {
+        return (PermissionState::Denied, Some(format!("Microphone cannot be started: {}", e)));
+    }
+    std::thread::sleep(MICROPHONE_PROBE);
+    drop(stream);
+
+    match heard.load(Ordering::Relaxed) {
+        true => (PermissionState::Granted, None),
+        false if cfg!(target_os = "macos") => (PermissionState::Denied, Some("Only silence was recorded".to_string())),
+        false => (PermissionState::Granted, Some("Only silence was recorded; check that the microphone is not muted".to_string())),
+    }
+}
+
+fn check_accessibility() -> (PermissionState, Option<String>) {
+    if cfg!(target_os = "windows") {
+        return (PermissionState::NotNeeded, None);
+    }
+    if cfg!(target_os = "macos") {
+        const SCRIPT: &str =
+            r#"tell application "System Events" to get name of front window of (first application process whose frontmost is true)"#;
+        let output = match std::process::Command::new("osascript").args(["-e", SCRIPT]).output() {
+            Ok(output) => output,
+            Err(e) => return (PermissionState::Unknown, Some(format!("osascript failed: {}", e))),
+        };
+        let error = String::from_utf8_lossy(&output.stderr);
+        // -1719/-25211: no Accessibility access; -1743: not allowed to control System Events;
+        // -1728: no front window, which can only be told with access
+        return if output.status.success() || error.contains("-1728") {
+            (PermissionState::Granted, None)
+        } else if ["-1719", "-25211", "-1743"].iter().any(|code| error.contains(code)) {
+            (PermissionState::Denied, Some(error.trim().to_string()))
+        } else {
+            (PermissionState::Unknown, Some(error.trim().to_string()))
+        };
+    }
+    match active_window::backend() {
+        Some(backend) => (PermissionState::Granted, Some(format!("Active window from {}", backend))),
+        None => (PermissionState::Unavailable, Some("No supported window system".to_string())),
+    }
+}
+
+fn check_input_monitoring() -> (PermissionState, Option<String>) {
+    if cfg!(feature = "simulated-input") {
+        return (PermissionState::NotNeeded, Some("Simulated input (simulated-input build)".to_string()));
+    }
+    if input_hooks::global().available() {
+        (PermissionState::Granted, None)
+    } else if cfg!(target_os = "linux") {
+        (PermissionState::Unavailable, Some("Input hooks unavailable (no X11 display)".to_string()))
+    } else {
+        (PermissionState::Denied, Some("The OS refused the input hooks".to_string()))
+    }
+}
+
+/// Windows privacy switches under CapabilityAccessManager: false when the device-wide, the user's
+/// or the desktop-app switch denies `capability`; None when none of them could be read
+fn windows_consent(capability: &str) -> Option<bool> {
+    const CONSENT_STORE: &str = r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore";
+    let keys = [
+        format!(r"HKLM\{}\{}", CONSENT_STORE, capability),
+        format!(r"HKCU\{}\{}", CONSENT_STORE, capability),
+        format!(r"HKCU\{}\{}\NonPackaged", CONSENT_STORE, capability),
+    ];
+    let mut granted = None;
+    for key in keys {
+        let Ok(output) = std::process::Command::new("reg").args(["query", &key, "/v", "Value"]).output() else { continue };
+        let text = String::from_utf8_lossy(&output.stdout);
+        if text.contains("Deny") {
+            return Some(false);
+        }
+        if text.contains("Allow") {
+            granted = Some(true);
+        }
+    }
+    granted
+}
+
+/// Open the OS settings page for `name`; false where there is none
+fn open_settings(name: &str) -> bool {
+    let (program, args): (&str, Vec<&str>) = match name {
+        "camera" if cfg!(target_os = "windows") => ("cmd", vec!["/C", "start", "ms-settings:privacy-webcam"]),
+        "microphone" if cfg!(target_os = "windows") => ("cmd", vec!["/C", "start", "ms-settings:privacy-microphone"]),
+        "camera" if cfg!(target_os = "macos") => ("open", vec!["x-apple.systempreferences:com.apple.preference.security?Privacy_Camera"]),
+        "microphone" if cfg!(target_os = "macos") => ("open", vec!["x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone"]),
+        "accessibility" if cfg!(target_os = "macos") => {
+            ("open", vec!["x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility"])
+        }
+        "input_monitoring" if cfg!(target_os = "macos") => {
+            ("open", vec!["x-apple.systempreferences:com.apple.preference.security?Privacy_ListenEvent"])
+        }
+        _ => return false,
+    };
+    match std::process::Command::new(program).args(&args).status() {
+        Ok(status) => status.success(),
+        Err(e) => {
+            warn!("⚠️ Could not open the {} settings: {}", name, e);
+            false
+        }
+    }
+}
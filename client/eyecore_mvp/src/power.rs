@@ -0,0 +1,161 @@
+//! Battery-aware collection: on battery below a threshold the collector switches to the power-saving
+//! profile (no OCR, no camera, longer intervals) and returns to the full profile on AC power.
+
+use log::{info, warn};
+use std::time::{Duration, Instant};
+
+use crate::models::{CollectionProfile, PowerState};
+
+pub const DEFAULT_BATTERY_THRESHOLD: f32 = 30.0;
+/// Collection intervals are this many times longer while saving power
+const POWER_SAVING_INTERVAL_FACTOR: u32 = 3;
+/// The power source is read at most this often
+const POWER_REFRESH: Duration = Duration::from_secs(60);
+
+/// Power source at one reading
+struct PowerReading {
+    on_battery: bool,
+    battery_percent: Option<f32>,
+}
+
+pub struct PowerMonitor {
+    enabled: bool,
+    threshold: f32,
+    state: PowerState,
+    last_read: Option<Instant>,
+}
+
+impl PowerMonitor {
+    /// EYECORE_BATTERY_SAVER=0 keeps the full profile on battery; EYECORE_BATTERY_SAVER_PERCENT sets
+    /// the charge below which power saving starts
+    pub fn from_env() -> Self {
+        let enabled = !matches!(std::env::var("EYECORE_BATTERY_SAVER").as_deref().map(str::trim), Ok("0" | "false" | "off"));
+        let threshold = match std::env::var("EYECORE_BATTERY_SAVER_PERCENT") {
+            Ok(value) => match value.trim().parse::<f32>() {
+                Ok(percent) if (0.0..=100.0).contains(&percent) => percent,
+                _ => {
+                    warn!("Ignoring EYECORE_BATTERY_SAVER_PERCENT: {:?} is not a percentage", value);
+                    DEFAULT_BATTERY_THRESHOLD
+                }
+            },
+            Err(_) => DEFAULT_BATTERY_THRESHOLD,
+        };
+        PowerMonitor {
+            enabled,
+            threshold,
+            state: PowerState { profile: CollectionProfile::Full, on_battery: false, battery_percent: None },
+            last_read: None,
+        }
+    }
+
+    /// Current power state, re-reading the power source once it is due
+    pub fn observe(&mut self) -> PowerState {
+        if self.last_read.is_some_and(|at| at.elapsed() < POWER_REFRESH) {
+            return self.state.clone();
+        }
+        self.last_read = Some(Instant::now());
+
+        // Desktops and unreadable power sources count as AC power
+        let reading = read_power().unwrap_or(PowerReading { on_battery: false, battery_percent: None });
+        // Once saving, it stays that way until AC power is back, even if the reading climbs over the threshold
+        let saving = self.enabled
+            && reading.on_battery
+            && (self.state.profile == CollectionProfile::PowerSaving
+                || reading.battery_percent.is_some_and(|percent| percent < self.threshold));
+        let profile = if saving { CollectionProfile::PowerSaving } else { CollectionProfile::Full };
+        if profile != self.state.profile {
+            match profile {
+                CollectionProfile::PowerSaving => info!(
+                    "🔋 On battery at {:.0}%, switching to the power-saving profile",
+                    reading.battery_percent.unwrap_or_default()
+                ),
+                CollectionProfile::Full => info!("🔌 On AC power, restoring the full collection profile"),
+            }
+        }
+        self.state = PowerState { profile, on_battery: reading.on_battery, battery_percent: reading.battery_percent };
+        self.state.clone()
+    }
+
+    /// Profile from the latest reading
+    pub fn profile(&self) -> CollectionProfile {
+        self.state.profile
+    }
+}
+
+/// Interval of a collector whose full-profile interval is `normal`
+pub fn interval(profile: CollectionProfile, normal: Duration) -> Duration {
+    match profile {
+        CollectionProfile::Full => normal,
+        CollectionProfile::PowerSaving => normal * POWER_SAVING_INTERVAL_FACTOR,
+    }
+}
+
+/// None where the power source cannot be read
+fn read_power() -> Option<PowerReading> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+        const AC_OFFLINE: u8 = 0;
+        const NO_SYSTEM_BATTERY: u8 = 128;
+        const UNKNOWN_PERCENT: u8 = 255;
+
+        let mut status = SYSTEM_POWER_STATUS::default();
+        unsafe { GetSystemPowerStatus(&mut status) }.ok()?;
+        if status.BatteryFlag & NO_SYSTEM_BATTERY != 0 {
+            return Some(PowerReading { on_battery: false, battery_percent: None });
+        }
+        Some(PowerReading {
+            on_battery: status.ACLineStatus == AC_OFFLINE,
+            battery_percent: (status.BatteryLifePercent != UNKNOWN_PERCENT).then_some(status.BatteryLifePercent as f32),
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut capacities = Vec::new();
+        let mut discharging = false;
+        let mut mains_online = None;
+        for supply in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+            let path = supply.path();
+            let read = |name: &str| std::fs::read_to_string(path.join(name)).map(|v| v.trim().to_string()).ok();
+            match read("type").as_deref() {
+                // Batteries of wireless mice and headsets have scope "Device"
+                Some("Battery") if read("scope").as_deref() != Some("Device") => {
+                    if let Some(capacity) = read("capacity").and_then(|v| v.parse::<f32>().ok()) {
+                        capacities.push(capacity);
+                    }
+                    discharging |= read("status").as_deref() == Some("Discharging");
+                }
+                Some("Mains" | "USB") => {
+                    let online = read("online").as_deref() == Some("1");
+                    mains_online = Some(mains_online.unwrap_or(false) || online);
+                }
+                _ => {}
+            }
+        }
+        if capacities.is_empty() {
+            return Some(PowerReading { on_battery: false, battery_percent: None });
+        }
+        Some(PowerReading {
+            on_battery: mains_online.map_or(discharging, |online| !online),
+            battery_percent: Some(capacities.iter().sum::<f32>() / capacities.len() as f32),
+        })
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=...)\t85%; discharging; ..."
+        let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let battery_percent = text
+            .split(|c: char| c.is_whitespace() || c == ';')
+            .find_map(|word| word.strip_suffix('%')?.parse::<f32>().ok());
+        Some(PowerReading { on_battery: text.contains("'Battery Power'"), battery_percent })
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        None
+    }
+}
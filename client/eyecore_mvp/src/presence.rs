@@ -0,0 +1,138 @@
+//! Presence: whether the user is working (active), reading or watching without touching the input
+//! devices (passive_consuming) or gone (away). Combines input idleness with the display's power
+//! state and media playback (from `media`), so a long video is not mistaken for an empty desk.
+//! Music alone does not count as watching: it plays on after the user has left.
+
+use crate::idle::{read_idle_secs, IDLE_SESSION_SECS};
+use crate::models::{MediaKind, MediaPlayback, Presence, PresenceState};
+
+/// Input within this long means the user is at the keyboard
+const ACTIVE_IDLE_SECS: u32 = 60;
+/// Without input or video, a user with the display on is assumed to be reading for this long
+const READING_IDLE_SECS: u32 = IDLE_SESSION_SECS;
+
+pub struct PresenceMonitor;
+
+impl PresenceMonitor {
+    pub fn new() -> Self {
+        #[cfg(target_os = "windows")]
+        display_power::subscribe();
+        PresenceMonitor
+    }
+
+    /// Classify the user's presence now, given what is playing (None where playback cannot be read);
+    /// `reported_idle_secs` is used where input idleness cannot be read
+    pub fn observe(&self, reported_idle_secs: u32, media: Option<&[MediaPlayback]>) -> Presence {
+        let measured_idle = read_idle_secs();
+        let idle_seconds = measured_idle.unwrap_or(reported_idle_secs);
+        let display_on = read_display_on();
+        let watching = media.is_some_and(|media| media.iter().any(|playback| playback.kind != MediaKind::Music));
+        Presence {
+            state: classify(idle_seconds, display_on, watching),
+            idle_seconds,
+            idle_measured: measured_idle.is_some(),
+            display_on,
+            media_apps: media.map(|media| media.iter().map(|playback| playback.app.clone()).collect()),
+        }
+    }
+}
+
+fn classify(idle_seconds: u32, display_on: Option<bool>, watching: bool) -> PresenceState {
+    if idle_seconds < ACTIVE_IDLE_SECS {
+        PresenceState::Active
+    } else if display_on == Some(false) {
+        PresenceState::Away
+    } else if watching || idle_seconds < READING_IDLE_SECS {
+        PresenceState::PassiveConsuming
+    } else {
+        PresenceState::Away
+    }
+}
+
+/// Whether a display is powered on; None where its power state cannot be read
+fn read_display_on() -> Option<bool> {
+    #[cfg(target_os = "windows")]
+    {
+        display_power::is_on()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // DRM connectors report DPMS as "On", "Standby", "Suspend" or "Off"
+        let mut connected = std::fs::read_dir("/sys/class/drm")
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| std::fs::read_to_string(path.join("status")).is_ok_and(|status| status.trim() == "connected"))
+            .filter_map(|path| std::fs::read_to_string(path.join("dpms")).ok())
+            .peekable();
+        connected.peek()?;
+        Some(connected.any(|dpms| dpms.trim() == "On"))
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+/// Console display state, pushed by the power manager whenever it changes
+#[cfg(target_os = "windows")]
+mod display_power {
+    use std::ffi::c_void;
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::Once;
+    use windows::core::GUID;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Power::{
+        PowerSettingRegisterNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS, POWERBROADCAST_SETTING,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{DEVICE_NOTIFY_CALLBACK, PBT_POWERSETTINGCHANGE};
+
+    /// GUID_CONSOLE_DISPLAY_STATE: 0 off, 1 on, 2 dimmed
+    const CONSOLE_DISPLAY_STATE: GUID = GUID::from_u128(0x6fe69556_704a_47a0_8f24_c28d936fda47);
+    const UNKNOWN: u8 = u8::MAX;
+
+    static STATE: AtomicU8 = AtomicU8::new(UNKNOWN);
+    static SUBSCRIBE: Once = Once::new();
+
+    /// Register once per process; the current state is delivered right away
+    pub fn subscribe() {
+        SUBSCRIBE.call_once(|| {
+            // The power manager keeps the pointer for as long as the registration lasts (the process lifetime)
+            let parameters = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+                Callback: Some(on_change),
+                Context: std::ptr::null_mut(),
+            }));
+            let mut registration = std::ptr::null_mut();
+            let result = unsafe {
+                PowerSettingRegisterNotification(
+                    &CONSOLE_DISPLAY_STATE,
+                    DEVICE_NOTIFY_CALLBACK,
+                    HANDLE(parameters as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as isize),
+                    &mut registration,
+                )
+            };
+            if let Err(e) = result {
+                log::warn!("Display power state unavailable: {}", e);
+            }
+        });
+    }
+
+    pub fn is_on() -> Option<bool> {
+        match STATE.load(Ordering::Relaxed) {
+            UNKNOWN => None,
+            state => Some(state != 0),
+        }
+    }
+
+    unsafe extern "system" fn on_change(_context: *const c_void, kind: u32, setting: *const c_void) -> u32 {
+        if kind == PBT_POWERSETTINGCHANGE && !setting.is_null() {
+            let setting = &*(setting as *const POWERBROADCAST_SETTING);
+            if setting.PowerSetting == CONSOLE_DISPLAY_STATE && setting.DataLength >= 1 {
+                STATE.store(setting.Data[0], Ordering::Relaxed);
+            }
+        }
+        0
+    }
+}
@@ -0,0 +1,318 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::attribution;
+use crate::models::EyeCoreData;
+
+pub const DEFAULT_EPSILON: f64 = 1.0;
+pub const DEFAULT_K: usize = 5;
+pub const MAX_K: usize = 1000;
+
+/// Number of snapshots aggregated into one upload (~5 minutes at the 5s upload interval)
+pub const AGGREGATE_WINDOW_SAMPLES: usize = 60;
+
+/// What the WebSocket client uploads
+#[derive(Debug, Clone, Copy)]
+pub enum UploadMode {
+    /// Every snapshot as collected
+    Raw,
+    /// Only noised aggregates over a window of snapshots
+    Aggregate { epsilon: f64 },
+    /// Only aggregates over at least `k` snapshots, with the apps and domains used, for
+    /// organizational deployments; the server suppresses apps and domains fewer than `k` devices use
+    Team { k: usize },
+}
+
+impl UploadMode {
+    /// `EYECORE_UPLOAD_MODE=aggregate` with optional `EYECORE_DP_EPSILON`, or `team` with optional
+    /// `EYECORE_K_ANONYMITY`
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("EYECORE_UPLOAD_MODE").as_deref() {
+            Ok("aggregate") => {
+                let epsilon = match std::env::var("EYECORE_DP_EPSILON") {
+                    Ok(value) => value
+                        .parse::<f64>()
+                        .map_err(|_| format!("Invalid EYECORE_DP_EPSILON: {}", value))?,
+                    Err(_) => DEFAULT_EPSILON,
+                };
+                if !epsilon.is_finite() || epsilon <= 0.0 {
+                    return Err("EYECORE_DP_EPSILON must be a positive number".to_string());
+                }
+                Ok(UploadMode::Aggregate { epsilon })
+            }
+            Ok("team") => {
+                let k = match std::env::var("EYECORE_K_ANONYMITY") {
+                    Ok(value) => value
+                        .parse::<usize>()
+                        .map_err(|_| format!("Invalid EYECORE_K_ANONYMITY: {}", value))?,
+                    Err(_) => DEFAULT_K,
+                };
+                if !(2..=MAX_K).contains(&k) {
+                    return Err(format!("EYECORE_K_ANONYMITY must be 2-{}", MAX_K));
+                }
+                Ok(UploadMode::Team { k })
+            }
+            Ok("raw") | Err(_) => Ok(UploadMode::Raw),
+            Ok(other) => Err(format!("Unknown EYECORE_UPLOAD_MODE: {}", other)),
+        }
+    }
+
+    /// Snapshots aggregated into one upload; None for raw uploads
+    pub fn window(&self) -> Option<usize> {
+        match self {
+            UploadMode::Raw => None,
+            UploadMode::Aggregate { .. } => Some(AGGREGATE_WINDOW_SAMPLES),
+            UploadMode::Team { k } => Some(AGGREGATE_WINDOW_SAMPLES.max(*k)),
+        }
+    }
+}
+
+/// Privacy parameters recorded with every aggregate upload
+#[derive(Debug, Clone, Serialize)]
+pub struct PrivacyParameters {
+    pub mechanism: &'static str,
+    pub epsilon: f64,
+    pub epsilon_per_metric: f64,
+}
+
+/// Noised statistics over one upload window
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregateUpload {
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub sample_count: usize,
+    pub avg_cpu_usage: f64,
+    pub avg_memory_usage: f64,
+    pub avg_focus_level: f64,
+    pub avg_idle_seconds: f64,
+    pub total_mouse_clicks: f64,
+    pub total_keyboard_events: f64,
+    pub privacy: PrivacyParameters,
+}
+
+// Per-snapshot contribution bounds; values are clamped so sensitivity is known
+const CPU_BOUND: f64 = 100.0;
+const MEMORY_BOUND: f64 = 100.0;
+const FOCUS_BOUND: f64 = 1.0;
+const IDLE_BOUND: f64 = 300.0;
+const MOUSE_CLICKS_BOUND: f64 = 200.0;
+const KEYBOARD_EVENTS_BOUND: f64 = 1000.0;
+const METRIC_COUNT: f64 = 6.0;
+
+/// Aggregate a window of snapshots with the Laplace mechanism.
+/// The total `epsilon` is split evenly across the released metrics.
+pub fn aggregate_with_noise(window: &[EyeCoreData], epsilon: f64) -> Option<AggregateUpload> {
+    let first = window.first()?;
+    let last = window.last()?;
+    let n = window.len() as f64;
+    let epsilon_per_metric = epsilon / METRIC_COUNT;
+
+    let sum = |value: &dyn Fn(&EyeCoreData) -> f64, bound: f64| -> f64 {
+        window.iter().map(|d| value(d).clamp(0.0, bound)).sum()
+    };
+
+    // Changing one snapshot moves a mean by at most bound/n and a sum by at most bound
+    let noisy_mean = |value: &dyn Fn(&EyeCoreData) -> f64, bound: f64| -> f64 {
+        (sum(value, bound) / n + laplace(bound / n / epsilon_per_metric)).clamp(0.0, bound)
+    };
+    let noisy_sum = |value: &dyn Fn(&EyeCoreData) -> f64, bound: f64| -> f64 {
+        (sum(value, bound) + laplace(bound / epsilon_per_metric)).max(0.0)
+    };
+
+    Some(AggregateUpload {
+        window_start: first.timestamp,
+        window_end: last.timestamp,
+        sample_count: window.len(),
+        avg_cpu_usage: noisy_mean(&|d| d.system_metrics.cpu_usage as f64, CPU_BOUND),
+        avg_memory_usage: noisy_mean(&|d| d.system_metrics.memory_usage as f64, MEMORY_BOUND),
+        avg_focus_level: noisy_mean(&|d| d.focus_metrics.focus_level as f64, FOCUS_BOUND),
+        avg_idle_seconds: noisy_mean(&|d| d.input_metrics.idle_duration_seconds as f64, IDLE_BOUND),
+        total_mouse_clicks: noisy_sum(&|d| d.input_metrics.mouse_clicks as f64, MOUSE_CLICKS_BOUND),
+        total_keyboard_events: noisy_sum(&|d| d.input_metrics.keyboard_events as f64, KEYBOARD_EVENTS_BOUND),
+        privacy: PrivacyParameters {
+            mechanism: "laplace",
+            epsilon,
+            epsilon_per_metric,
+        },
+    })
+}
+
+/// What a team upload asks the server to enforce
+#[derive(Debug, Clone, Serialize)]
+pub struct KAnonymityHints {
+    pub mechanism: &'static str,
+    pub k: usize,
+    pub dimensions: [&'static str; 2],    // breakdowns to suppress where fewer than k devices report a value
+    pub suppressed_apps: usize,           // in fewer than k snapshots of the window, left out here
+    pub suppressed_domains: usize,
+}
+
+/// Snapshots of the window with one app or domain in the foreground
+#[derive(Debug, Clone, Serialize)]
+pub struct DimensionCount {
+    pub value: String,
+    pub samples: usize,
+}
+
+/// Statistics over one team upload window
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamUpload {
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub sample_count: usize,
+    pub avg_cpu_usage: f64,
+    pub avg_memory_usage: f64,
+    pub avg_focus_level: f64,
+    pub avg_idle_seconds: f64,
+    pub total_mouse_clicks: u64,
+    pub total_keyboard_events: u64,
+    pub apps: Vec<DimensionCount>,
+    pub domains: Vec<DimensionCount>,
+    pub privacy: KAnonymityHints,
+}
+
+/// Aggregate a window of at least `k` snapshots for team mode. Apps and domains in the foreground
+/// in fewer than `k` of them are left out; window titles never leave the device.
+pub fn aggregate_for_team(window: &[EyeCoreData], k: usize) -> Option<TeamUpload> {
+    if window.is_empty() || window.len() < k {
+        return None;
+    }
+    let first = window.first()?;
+    let last = window.last()?;
+    let n = window.len() as f64;
+    let mean = |value: &dyn Fn(&EyeCoreData) -> f64| -> f64 { window.iter().map(value).sum::<f64>() / n };
+    let (apps, suppressed_apps) = count_dimension(window.iter().filter_map(foreground_app), k);
+    let (domains, suppressed_domains) = count_dimension(window.iter().filter_map(foreground_domain), k);
+
+    Some(TeamUpload {
+        window_start: first.timestamp,
+        window_end: last.timestamp,
+        sample_count: window.len(),
+        avg_cpu_usage: mean(&|d| d.system_metrics.cpu_usage as f64),
+        avg_memory_usage: mean(&|d| d.system_metrics.memory_usage as f64),
+        avg_focus_level: mean(&|d| d.focus_metrics.focus_level as f64),
+        avg_idle_seconds: mean(&|d| d.input_metrics.idle_duration_seconds as f64),
+        total_mouse_clicks: window.iter().map(|d| d.input_metrics.mouse_clicks as u64).sum(),
+        total_keyboard_events: window.iter().map(|d| d.input_metrics.keyboard_events as u64).sum(),
+        apps,
+        domains,
+        privacy: KAnonymityHints {
+            mechanism: "k-anonymity",
+            k,
+            dimensions: ["app", "domain"],
+            suppressed_apps,
+            suppressed_domains,
+        },
+    })
+}
+
+/// App in the foreground of a snapshot, when known
+fn foreground_app(data: &EyeCoreData) -> Option<String> {
+    let process = &data.process_data;
+    let app = process.app.as_deref().unwrap_or(&process.active_process);
+    (!app.is_empty() && app != "unknown").then(|| app.to_string())
+}
+
+/// Site in the foreground browser of a snapshot
+fn foreground_domain(data: &EyeCoreData) -> Option<String> {
+    let process = &data.process_data;
+    if !attribution::is_browser(&process.active_process) {
+        return None;
+    }
+    attribution::site_from_title(&process.active_window_title)
+}
+
+/// Values in at least `k` snapshots, most frequent first, and how many others were left out
+fn count_dimension(values: impl Iterator<Item = String>, k: usize) -> (Vec<DimensionCount>, usize) {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for value in values {
+        *counts.entry(value).or_default() += 1;
+    }
+    let suppressed = counts.values().filter(|samples| **samples < k).count();
+    let mut kept: Vec<DimensionCount> = counts
+        .into_iter()
+        .filter(|(_, samples)| *samples >= k)
+        .map(|(value, samples)| DimensionCount { value, samples })
+        .collect();
+    kept.sort_by_key(|count| std::cmp::Reverse(count.samples));
+    (kept, suppressed)
+}
+
+/// Sample from Laplace(0, scale) by inverse transform
+fn laplace(scale: f64) -> f64 {
+    let u: f64 = rand::thread_rng().gen_range(-0.5..0.5);
+    -scale * u.signum() * (1.0 - 2.0 * u.abs()).max(f64::MIN_POSITIVE).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn snapshot(process: &str, title: &str) -> EyeCoreData {
+        let now = Utc::now();
+        serde_json::from_value(json!({
+            "session_id": "test",
+            "timestamp": now,
+            "system_metrics": { "timestamp": now, "cpu_usage": 40.0, "memory_usage": 60.0, "disk_usage": 50.0 },
+            "process_data": { "timestamp": now, "active_process": process, "active_window_title": title, "process_count": 100 },
+            "input_metrics": { "timestamp": now, "mouse_clicks": 3, "keyboard_events": 20, "idle_duration_seconds": 1 },
+            "network_metrics": { "timestamp": now, "bytes_sent": 0, "bytes_received": 0, "active_connections": 0 },
+            "focus_metrics": { "timestamp": now, "focus_level": 0.5, "context_switches": 0, "productive_app_time": 0 },
+        }))
+        .unwrap()
+    }
+
+    fn window(counts: &[(&str, &str, usize)]) -> Vec<EyeCoreData> {
+        counts
+            .iter()
+            .flat_map(|(process, title, count)| std::iter::repeat_with(|| snapshot(process, title)).take(*count))
+            .collect()
+    }
+
+    #[test]
+    fn team_aggregates_need_k_snapshots() {
+        assert!(aggregate_for_team(&window(&[("Code.exe", "main.rs", 4)]), 5).is_none());
+        assert!(aggregate_for_team(&[], 2).is_none());
+
+        let upload = aggregate_for_team(&window(&[("Code.exe", "main.rs", 5)]), 5).unwrap();
+        assert_eq!(upload.sample_count, 5);
+        assert_eq!(upload.total_keyboard_events, 100);
+        assert!((upload.avg_cpu_usage - 40.0).abs() < 1e-6);
+        assert_eq!(upload.privacy.k, 5);
+    }
+
+    #[test]
+    fn rare_apps_and_domains_are_suppressed() {
+        let snapshots = window(&[
+            ("Code.exe", "main.rs - eyecore - Visual Studio Code", 6),
+            ("chrome.exe", "Pull requests · github.com - Google Chrome", 3),
+            ("chrome.exe", "Hotel booking · travel.example.com - Google Chrome", 1),
+            ("unknown", "", 2),
+        ]);
+        let upload = aggregate_for_team(&snapshots, 3).unwrap();
+
+        let apps: Vec<(&str, usize)> = upload.apps.iter().map(|app| (app.value.as_str(), app.samples)).collect();
+        assert_eq!(apps, [("Code.exe", 6), ("chrome.exe", 4)]);
+        let domains: Vec<&str> = upload.domains.iter().map(|domain| domain.value.as_str()).collect();
+        assert_eq!(domains, ["github.com"]);
+        assert_eq!(upload.privacy.suppressed_apps, 0);
+        assert_eq!(upload.privacy.suppressed_domains, 1);
+    }
+
+    #[test]
+    fn team_uploads_carry_no_window_titles() {
+        let snapshots = window(&[("Code.exe", "salaries.xlsx - Excel", 5)]);
+        let upload = serde_json::to_string(&aggregate_for_team(&snapshots, 5).unwrap()).unwrap();
+        assert!(!upload.contains("salaries"));
+    }
+
+    #[test]
+    fn team_windows_hold_at_least_k_snapshots() {
+        assert_eq!(UploadMode::Team { k: 5 }.window(), Some(AGGREGATE_WINDOW_SAMPLES));
+        assert_eq!(UploadMode::Team { k: 500 }.window(), Some(500));
+        assert_eq!(UploadMode::Raw.window(), None);
+    }
+}
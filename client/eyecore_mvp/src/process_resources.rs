@@ -0,0 +1,63 @@
+//! Per-process resource use for `top_processes`: CPU, memory and disk I/O of the busiest processes,
+//! so a high CPU reading can be put down to one application. The sysinfo handle lives across
+//! cycles, since process CPU and disk I/O are deltas between two refreshes.
+
+use sysinfo::{ProcessRefreshKind, System};
+
+use crate::models::ProcessUsage;
+
+/// Processes reported per snapshot unless `top_processes` says otherwise
+pub const DEFAULT_TOP_N: usize = 5;
+/// Most processes a snapshot may list
+pub const MAX_TOP_N: usize = 50;
+
+pub struct ProcessResourceTracker {
+    system: System,
+    top_n: usize,
+    cores: f32,
+}
+
+impl ProcessResourceTracker {
+    /// Keep the `top_n` busiest processes per snapshot; 0 only counts them
+    pub fn new(top_n: usize) -> Self {
+        ProcessResourceTracker {
+            system: System::new(),
+            top_n,
+            cores: std::thread::available_parallelism().map_or(1, |cores| cores.get()) as f32,
+        }
+    }
+
+    pub fn set_top_n(&mut self, top_n: usize) {
+        self.top_n = top_n;
+    }
+
+    /// Number of running processes and the busiest of them, by CPU and then memory
+    pub fn sample(&mut self) -> (usize, Vec<ProcessUsage>) {
+        let kind = ProcessRefreshKind::new().with_cpu().with_memory();
+        let kind = if self.top_n > 0 { kind.with_disk_usage() } else { kind };
+        self.system.refresh_processes_specifics(kind);
+
+        let processes = self.system.processes();
+        if self.top_n == 0 {
+            return (processes.len(), Vec::new());
+        }
+        let mut usage: Vec<ProcessUsage> = processes
+            .values()
+            .map(|process| {
+                let disk = process.disk_usage();
+                ProcessUsage {
+                    pid: process.pid().as_u32(),
+                    name: process.name().to_string(),
+                    // sysinfo reports a share of one core, up to 100% per core
+                    cpu_usage: (process.cpu_usage() / self.cores).min(100.0),
+                    memory_bytes: process.memory(),
+                    disk_read_bytes: disk.read_bytes,
+                    disk_written_bytes: disk.written_bytes,
+                }
+            })
+            .collect();
+        usage.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage).then(b.memory_bytes.cmp(&a.memory_bytes)));
+        usage.truncate(self.top_n);
+        (processes.len(), usage)
+    }
+}
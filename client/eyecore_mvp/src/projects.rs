@@ -0,0 +1,333 @@
+//! Projects worked on, detected from the foreground window: IDE workspace names in window titles,
+//! paths in titles (clustered by the repository or package root around them) and GitHub or GitLab
+//! repositories open in a browser. A name not seen before becomes an unconfirmed project, which the
+//! user confirms (optionally renaming or merging it into another) or rejects through `/projects`.
+//! Snapshots carry the project in `process_data.project`, and the daily report breaks foreground
+//! time and focus down per project.
+
+use chrono::Utc;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::app_rules::executable_name;
+use crate::models::{EyeCoreData, Project, ProjectTime, Projects};
+
+/// Projects file in the device directory
+const PROJECTS_FILE: &str = "projects.json";
+/// Snapshots further apart than this do not add project time
+const MAX_SAMPLE_GAP_SECS: i64 = 60;
+const MAX_NAME_LENGTH: usize = 100;
+/// Files or directories that mark the root of a project
+const ROOT_MARKERS: &[&str] = &[
+    ".git", "Cargo.toml", "package.json", "pyproject.toml", "setup.py", "go.mod", "pom.xml", "build.gradle",
+    "CMakeLists.txt", ".project", ".vscode", ".idea",
+];
+/// Directories looked up from a path before giving up on finding its root
+const MAX_ROOT_DEPTH: usize = 8;
+const ROOT_CACHE_SIZE: usize = 1000;
+
+const VSCODE: &[&str] = &["code", "code - insiders", "visual studio code", "cursor", "codium", "vscodium"];
+const JETBRAINS: &[&str] = &[
+    "idea", "idea64", "pycharm", "pycharm64", "clion", "clion64", "rider64", "webstorm", "webstorm64", "goland",
+    "goland64", "phpstorm64", "rubymine64", "studio64", "android studio",
+];
+const BROWSERS: &[&str] = &[
+    "chrome", "google chrome", "chromium", "msedge", "microsoft edge", "firefox", "brave", "opera", "vivaldi", "safari",
+];
+
+/// A project name found in a window, with the directory it came from if any
+struct Candidate {
+    name: String,
+    root: Option<PathBuf>,
+}
+
+pub struct ProjectDetector {
+    path: Option<PathBuf>,
+    projects: Projects,
+    // Directory seen in a title -> its project root, so the file system is not walked per snapshot
+    roots: HashMap<PathBuf, Option<PathBuf>>,
+}
+
+impl ProjectDetector {
+    /// Detection without saving, for collectors that are not attached to a device directory
+    pub fn new() -> Self {
+        ProjectDetector { path: None, projects: Projects::default(), roots: HashMap::new() }
+    }
+
+    /// The projects kept in `device_dir`
+    pub fn load(device_dir: &Path) -> Self {
+        let path = device_dir.join(PROJECTS_FILE);
+        let projects = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("⚠️ Ignoring unreadable projects {:?}: {}", path, e);
+                Projects::default()
+            }),
+            Err(_) => Projects::default(),
+        };
+        ProjectDetector { path: Some(path), projects, roots: HashMap::new() }
+    }
+
+    pub fn projects(&self) -> Projects {
+        self.projects.clone()
+    }
+
+    /// Whether `name` is a project's name or alias
+    pub fn contains(&self, name: &str) -> bool {
+        self.resolve(name).is_some()
+    }
+
+    /// Project of the foreground window, adding it unconfirmed when it is new
+    pub fn observe(&mut self, process: &str, window_title: &str) -> Option<String> {
+        let candidate = self.candidate(process, window_title)?;
+        let alias = normalize(&candidate.name);
+        if alias.is_empty() || self.projects.rejected.contains(&alias) {
+            return None;
+        }
+        let root = candidate.root.map(|root| root.display().to_string());
+
+        let index = match self.index_of(&alias) {
+            Some(index) => index,
+            None => {
+                info!("🗂️ New project detected: {}", candidate.name);
+                self.projects.projects.push(Project {
+                    name: candidate.name,
+                    confirmed: false,
+                    aliases: vec![alias],
+                    roots: Vec::new(),
+                    first_seen: Utc::now(),
+                });
+                self.save_logged();
+                self.projects.projects.len() - 1
+            }
+        };
+        let roots = &mut self.projects.projects[index].roots;
+        if let Some(root) = root.filter(|root| !roots.contains(root)) {
+            roots.push(root);
+            self.save_logged();
+        }
+        Some(self.projects.projects[index].name.clone())
+    }
+
+    /// Confirm a project, renaming it to `name` if given; renaming to another project's name
+    /// merges the two
+    pub fn confirm(&mut self, project: &str, name: Option<String>) -> Result<Projects, String> {
+        let index = self.index_of(&normalize(project)).ok_or_else(|| format!("Unknown project {:?}", project))?;
+        let name = name.map(|name| name.trim().to_string());
+        if let Some(name) = &name {
+            if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+                return Err(format!("Project names must be 1 to {} characters, got {:?}", MAX_NAME_LENGTH, name));
+            }
+        }
+
+        let mut confirmed = self.projects.projects.remove(index);
+        confirmed.confirmed = true;
+        if let Some(name) = name {
+            // The old name keeps resolving, so earlier snapshots count towards the new one
+            confirmed.aliases.push(normalize(&confirmed.name));
+            confirmed.aliases.push(normalize(&name));
+            match self.index_of(&normalize(&name)) {
+                Some(other) => {
+                    let other = &mut self.projects.projects[other];
+                    other.confirmed = true;
+                    other.aliases.extend(confirmed.aliases);
+                    other.aliases.sort();
+                    other.aliases.dedup();
+                    other.roots.extend(confirmed.roots);
+                    other.roots.sort();
+                    other.roots.dedup();
+                    other.first_seen = other.first_seen.min(confirmed.first_seen);
+                    info!("🗂️ Project {} merged into {}", confirmed.name, other.name);
+                    return self.save().map(|_| self.projects());
+                }
+                None => {
+                    confirmed.name = name;
+                    confirmed.aliases.sort();
+                    confirmed.aliases.dedup();
+                }
+            }
+        }
+        info!("🗂️ Project {} confirmed", confirmed.name);
+        self.projects.projects.insert(index, confirmed);
+        self.save()?;
+        Ok(self.projects())
+    }
+
+    /// Forget a project and stop detecting its names
+    pub fn reject(&mut self, project: &str) -> Result<Projects, String> {
+        let index = self.index_of(&normalize(project)).ok_or_else(|| format!("Unknown project {:?}", project))?;
+        let rejected = self.projects.projects.remove(index);
+        self.projects.rejected.extend(rejected.aliases);
+        self.projects.rejected.sort();
+        self.projects.rejected.dedup();
+        info!("🗂️ Project {} rejected", rejected.name);
+        self.save()?;
+        Ok(self.projects())
+    }
+
+    /// Current project of a name stored in a snapshot: renames and merges apply to earlier days,
+    /// and rejected projects count for none
+    pub fn resolve(&self, name: &str) -> Option<&Project> {
+        self.index_of(&normalize(name)).map(|index| &self.projects.projects[index])
+    }
+
+    fn index_of(&self, alias: &str) -> Option<usize> {
+        self.projects
+            .projects
+            .iter()
+            .position(|project| normalize(&project.name) == alias || project.aliases.iter().any(|known| known == alias))
+    }
+
+    fn candidate(&mut self, process: &str, title: &str) -> Option<Candidate> {
+        let executable = executable_name(process);
+        if let Some(root) = paths_in_title(title).into_iter().find_map(|path| self.root_of(&path)) {
+            let name = root.file_name()?.to_string_lossy().to_string();
+            return Some(Candidate { name, root: Some(root) });
+        }
+        if BROWSERS.contains(&executable.as_str()) {
+            return repository_in_title(title).map(|name| Candidate { name, root: None });
+        }
+        workspace_in_title(&executable, title).map(|name| Candidate { name, root: None })
+    }
+
+    /// Nearest directory above `path` with a root marker, looked up once per directory
+    fn root_of(&mut self, path: &Path) -> Option<PathBuf> {
+        let dir = path.ancestors().find(|dir| dir.is_dir())?.to_path_buf();
+        if let Some(root) = self.roots.get(&dir) {
+            return root.clone();
+        }
+        let root = dir
+            .ancestors()
+            .take(MAX_ROOT_DEPTH)
+            // A marker in the home directory itself (dotfiles repository) is not a project
+            .filter(|candidate| Some(*candidate) != home_dir().as_deref())
+            .find(|candidate| ROOT_MARKERS.iter().any(|marker| candidate.join(marker).exists()))
+            .map(Path::to_path_buf);
+        if self.roots.len() >= ROOT_CACHE_SIZE {
+            self.roots.clear();
+        }
+        self.roots.insert(dir, root.clone());
+        root
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let content = serde_json::to_string_pretty(&self.projects).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| format!("Failed to save projects {:?}: {}", path, e))
+    }
+
+    fn save_logged(&self) {
+        if let Err(e) = self.save() {
+            warn!("⚠️ {}", e);
+        }
+    }
+}
+
+/// Foreground time, mean focus level and context switches of a day's snapshots per project, with
+/// the current names. Each snapshot counts the time until the next one, unless they are more than a
+/// minute apart.
+pub fn project_breakdown(snapshots: &[EyeCoreData], detector: &ProjectDetector) -> Vec<ProjectTime> {
+    // seconds, summed focus level, snapshots, context switches
+    let mut totals: HashMap<String, (i64, f32, u32, u32)> = HashMap::new();
+    for (i, data) in snapshots.iter().enumerate() {
+        let Some(project) = data.process_data.project.as_deref().and_then(|name| detector.resolve(name)) else {
+            continue;
+        };
+        let seconds = snapshots
+            .get(i + 1)
+            .map(|next| (next.timestamp - data.timestamp).num_seconds())
+            .filter(|gap| (0..=MAX_SAMPLE_GAP_SECS).contains(gap))
+            .unwrap_or(0);
+        let total = totals.entry(project.name.clone()).or_default();
+        total.0 += seconds;
+        total.1 += data.focus_metrics.focus_level;
+        total.2 += 1;
+        total.3 += data.focus_metrics.context_switches;
+    }
+
+    let mut breakdown: Vec<ProjectTime> = totals
+        .into_iter()
+        .map(|(name, (seconds, focus, count, switches))| ProjectTime {
+            confirmed: detector.resolve(&name).is_some_and(|project| project.confirmed),
+            project: name,
+            minutes: seconds / 60,
+            avg_focus_level: focus / count as f32,
+            context_switches: switches,
+        })
+        .filter(|project| project.minutes > 0)
+        .collect();
+    breakdown.sort_by(|a, b| b.minutes.cmp(&a.minutes).then_with(|| a.project.cmp(&b.project)));
+    breakdown
+}
+
+/// Workspace or project an IDE names in its window title
+fn workspace_in_title(executable: &str, title: &str) -> Option<String> {
+    let title = title.trim_start_matches(['●', '•', '*', ' ']);
+    let name = if VSCODE.contains(&executable) {
+        // "main.rs - eyecore (Workspace) - Visual Studio Code"; with two parts the first may be a
+        // folder or an editor ("Welcome"), so only titles with an open editor count
+        let parts: Vec<&str> = title.split(" - ").collect();
+        (parts.len() >= 3).then(|| parts[parts.len() - 2].trim_end_matches(" (Workspace)"))
+    } else if JETBRAINS.contains(&executable) {
+        // "eyecore_mvp [~/code/eyecore_mvp] – main.rs"
+        title.split(" – ").next().map(|name| name.split(" [").next().unwrap_or(name))
+    } else if executable == "devenv" {
+        // "EyeCore (Running) - Microsoft Visual Studio"
+        title.split(" - ").next().map(|name| name.split(" (").next().unwrap_or(name))
+    } else if executable == "sublime_text" {
+        // "main.rs (eyecore_mvp) - Sublime Text"
+        title.rsplit_once(" - ").and_then(|(page, _)| page.rsplit_once(" (")).map(|(_, name)| name.trim_end_matches(')'))
+    } else if executable == "xcode" {
+        // "EyeCore — ContentView.swift"
+        title.split(" — ").next()
+    } else {
+        None
+    };
+    name.map(str::trim).filter(|name| !name.is_empty() && name.chars().count() <= MAX_NAME_LENGTH).map(str::to_string)
+}
+
+/// Repository of a GitHub or GitLab page in a browser title, such as "GitHub - owner/repo:
+/// description" or "Fix parser · Pull Request #12 · owner/repo · GitHub"
+fn repository_in_title(title: &str) -> Option<String> {
+    let lowercase = title.to_lowercase();
+    if !lowercase.contains("github") && !lowercase.contains("gitlab") {
+        return None;
+    }
+    title
+        .split(|c: char| c.is_whitespace() || c == ':' || c == '·')
+        .filter_map(|word| word.split_once('/'))
+        .find(|(owner, repo)| {
+            let valid = |part: &str| {
+                !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+            };
+            valid(owner) && valid(repo)
+        })
+        .map(|(_, repo)| repo.to_string())
+}
+
+/// Absolute paths in a window title, e.g. "user@host: ~/code/eyecore" or "C:\code\eyecore\main.rs -
+/// Notepad++". Each runs to the next title separator; `root_of` trims what does not exist.
+fn paths_in_title(title: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let title = [" — ", " – ", " | ", ": "].iter().fold(title.to_string(), |title, sep| title.replace(sep, " - "));
+    for part in title.split(" - ") {
+        let part = part.trim().trim_matches(['[', ']', '(', ')', '"']);
+        let path = if let Some(rest) = part.strip_prefix("~/") {
+            home_dir().map(|home| home.join(rest))
+        } else if part.starts_with('/') || part.get(1..3) == Some(":\\") {
+            Some(PathBuf::from(part))
+        } else {
+            None
+        };
+        paths.extend(path);
+    }
+    paths
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).map(PathBuf::from)
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
@@ -0,0 +1,627 @@
+use chrono::{DateTime, Datelike, Duration, DurationRound, NaiveDate, SecondsFormat, TimeZone, Utc};
+use log::error;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::data_type::{DoubleType, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::format::KeyValue;
+use parquet::record::RowAccessor;
+use parquet::schema::parser::parse_message_type;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, ErrorCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::models::EyeCoreData;
+use crate::storage::DataStorage;
+
+/// Raw snapshots, one row per collection
+pub const SNAPSHOTS_TABLE: &str = "snapshots";
+/// Hourly rollups of the snapshots, one row per hour with data
+pub const HOURLY_TABLE: &str = "hourly";
+
+// Day directory category holding the rollup of each finished day, exported as Parquet
+pub const ROLLUP_CATEGORY: &str = "rollups";
+const ROLLUP_FILE: &str = "hourly_rollup.parquet";
+
+/// Columns of a rollup file, one row per `HourlyRollup`
+const ROLLUP_SCHEMA: &str = "
+message hourly_rollup {
+    REQUIRED INT64 hour (TIMESTAMP(MILLIS,true));
+    REQUIRED INT64 samples;
+    REQUIRED DOUBLE avg_cpu_usage;
+    REQUIRED DOUBLE max_cpu_usage;
+    REQUIRED DOUBLE avg_memory_usage;
+    REQUIRED DOUBLE avg_disk_usage;
+    REQUIRED INT64 mouse_clicks;
+    REQUIRED INT64 keyboard_events;
+    REQUIRED INT64 bytes_sent;
+    REQUIRED INT64 bytes_received;
+    REQUIRED DOUBLE avg_focus_level;
+    REQUIRED INT64 context_switches;
+    OPTIONAL DOUBLE avg_typing_speed_wpm;
+}";
+// File metadata key holding the day's snapshot count when it was rolled up
+const SNAPSHOTS_KEY: &str = "eyecore.snapshots";
+
+pub const DEFAULT_MAX_ROWS: usize = 1_000;
+pub const MAX_ROWS_LIMIT: usize = 10_000;
+pub const MAX_DAYS: u32 = 31;
+// Raw snapshots are parsed on every query, rollups only once per day
+pub const MAX_SNAPSHOT_DAYS: u32 = 7;
+pub const MAX_ROLLUP_DAYS: u32 = 366;
+pub const QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+// SQLite VM instructions between deadline checks
+const PROGRESS_INTERVAL: i32 = 1_000;
+
+/// Result of a SQL query, row-oriented for JSON clients
+#[derive(Debug, Serialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_count: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    Invalid(String),
+    TimedOut,
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::Invalid(e) => write!(f, "{}", e),
+            QueryError::TimedOut => write!(f, "Query timed out after {} seconds", QUERY_TIMEOUT.as_secs()),
+        }
+    }
+}
+
+/// One hour of snapshots folded into sums and averages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HourlyRollup {
+    pub hour: DateTime<Utc>,
+    pub samples: u64,
+    pub avg_cpu_usage: f64,
+    pub max_cpu_usage: f64,
+    pub avg_memory_usage: f64,
+    pub avg_disk_usage: f64,
+    pub mouse_clicks: u64,
+    pub keyboard_events: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub avg_focus_level: f64,
+    pub context_switches: u64,
+    pub avg_typing_speed_wpm: Option<f64>, // hours without keystroke dynamics have none
+}
+
+/// Rollup of one day; `snapshots` tells whether the day changed since
+#[derive(Debug, PartialEq)]
+struct DayRollup {
+    snapshots: usize,
+    hours: Vec<HourlyRollup>,
+}
+
+/// Tables a query reads, only the ones it names are loaded
+#[derive(Debug, Default)]
+pub struct QueryTables {
+    pub snapshots: Vec<EyeCoreData>,
+    pub hourly: Vec<HourlyRollup>,
+}
+
+/// Only single read-only statements are allowed
+pub fn validate_sql(sql: &str) -> Result<(), String> {
+    let trimmed = sql.trim().trim_end_matches(';').trim();
+    if trimmed.is_empty() {
+        return Err("Query is empty".to_string());
+    }
+    if trimmed.contains(';') {
+        return Err("Only a single statement is allowed".to_string());
+    }
+
+    let keyword = trimmed
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .to_ascii_uppercase();
+    if keyword != "SELECT" && keyword != "WITH" {
+        return Err("Only SELECT queries are allowed".to_string());
+    }
+
+    Ok(())
+}
+
+/// Whether `sql` mentions `table` as a whole word
+pub fn references(sql: &str, table: &str) -> bool {
+    sql.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .any(|word| word.eq_ignore_ascii_case(table))
+}
+
+/// Load the last `days` days of snapshots (ending at `end`) into a flat table
+pub async fn load_snapshot_table(storage: &DataStorage, end: NaiveDate, days: u32) -> std::io::Result<Vec<EyeCoreData>> {
+    let mut snapshots = Vec::new();
+    for offset in (0..days.clamp(1, MAX_DAYS) as i64).rev() {
+        snapshots.extend(storage.load_snapshots(end - Duration::days(offset)).await?);
+    }
+    // Sessions that span midnight were ordered per day
+    crate::storage::order_snapshots(&mut snapshots);
+    Ok(snapshots)
+}
+
+/// Hourly rollups of the last `days` days ending at `end`. Finished days are rolled up once and
+/// exported as Parquet, which other tools can read too; a day is rolled up again when its snapshot
+/// count changes (late writes, deletions).
+pub async fn load_hourly_table(storage: &DataStorage, end: NaiveDate, days: u32) -> std::io::Result<Vec<HourlyRollup>> {
+    let start = end - Duration::days(days.clamp(1, MAX_ROLLUP_DAYS) as i64 - 1);
+    let today = Utc::now().date_naive();
+    let mut hourly = Vec::new();
+    for date in storage.list_dates().await? {
+        if date < start || date > end {
+            continue;
+        }
+        let count = storage.list_snapshot_timestamps(Some(date)).await?.len();
+        let path = storage.day_dir(date, ROLLUP_CATEGORY).join(ROLLUP_FILE);
+        let exported = path.clone();
+        let cached = tokio::task::spawn_blocking(move || read_rollup(&exported).ok())
+            .await
+            .map_err(std::io::Error::other)?
+            .filter(|rollup| rollup.snapshots == count);
+        let rollup = match cached {
+            Some(rollup) => rollup,
+            None => {
+                let rollup = DayRollup {
+                    snapshots: count,
+                    hours: rollup_hours(&storage.load_snapshots(date).await?),
+                };
+                // Today is still being written
+                if date < today && count > 0 {
+                    let (rollup, saved) = tokio::task::spawn_blocking(move || {
+                        let saved = save_rollup(&path, &rollup);
+                        (rollup, saved)
+                    })
+                    .await
+                    .map_err(std::io::Error::other)?;
+                    if let Err(e) = saved {
+                        error!("Failed to export rollup for {}: {}", date, e);
+                    }
+                    rollup
+                } else {
+                    rollup
+                }
+            }
+        };
+        hourly.extend(rollup.hours);
+    }
+    Ok(hourly)
+}
+
+/// Write `rollup` as a Parquet file, replacing `path` atomically
+fn save_rollup(path: &Path, rollup: &DayRollup) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temporary = path.with_extension("parquet.tmp");
+    write_rollup(std::fs::File::create(&temporary)?, rollup).map_err(std::io::Error::other)?;
+    std::fs::rename(&temporary, path)
+}
+
+fn write_rollup<W: std::io::Write + Send>(out: W, rollup: &DayRollup) -> Result<W, ParquetError> {
+    enum Column {
+        Long(Vec<i64>),
+        Double(Vec<f64>),
+        OptionalDouble(Vec<Option<f64>>),
+    }
+    let hours = &rollup.hours;
+    let long = |value: fn(&HourlyRollup) -> i64| Column::Long(hours.iter().map(value).collect());
+    let double = |value: fn(&HourlyRollup) -> f64| Column::Double(hours.iter().map(value).collect());
+    // In schema order
+    let columns = [
+        long(|h| h.hour.timestamp_millis()),
+        long(|h| h.samples as i64),
+        double(|h| h.avg_cpu_usage),
+        double(|h| h.max_cpu_usage),
+        double(|h| h.avg_memory_usage),
+        double(|h| h.avg_disk_usage),
+        long(|h| h.mouse_clicks as i64),
+        long(|h| h.keyboard_events as i64),
+        long(|h| h.bytes_sent as i64),
+        long(|h| h.bytes_received as i64),
+        double(|h| h.avg_focus_level),
+        long(|h| h.context_switches as i64),
+        Column::OptionalDouble(hours.iter().map(|h| h.avg_typing_speed_wpm).collect()),
+    ];
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .set_key_value_metadata(Some(vec![KeyValue::new(SNAPSHOTS_KEY.to_string(), rollup.snapshots.to_string())]))
+        .build();
+    let schema = Arc::new(parse_message_type(ROLLUP_SCHEMA)?);
+    let mut writer = SerializedFileWriter::new(out, schema, Arc::new(properties))?;
+    let mut row_group = writer.next_row_group()?;
+    for column in columns {
+        let mut column_writer = row_group
+            .next_column()?
+            .ok_or_else(|| ParquetError::General("rollup schema has fewer columns than the rollup".to_string()))?;
+        match column {
+            Column::Long(values) => column_writer.typed::<Int64Type>().write_batch(&values, None, None)?,
+            Column::Double(values) => column_writer.typed::<DoubleType>().write_batch(&values, None, None)?,
+            Column::OptionalDouble(values) => {
+                let present: Vec<f64> = values.iter().flatten().copied().collect();
+                let levels: Vec<i16> = values.iter().map(|v| v.is_some() as i16).collect();
+                column_writer.typed::<DoubleType>().write_batch(&present, Some(&levels), None)?
+            }
+        };
+        column_writer.close()?;
+    }
+    row_group.close()?;
+    writer.into_inner()
+}
+
+fn read_rollup(path: &Path) -> Result<DayRollup, ParquetError> {
+    let reader = SerializedFileReader::new(std::fs::File::open(path)?)?;
+    let snapshots = reader
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .and_then(|entries| entries.iter().find(|entry| entry.key == SNAPSHOTS_KEY))
+        .and_then(|entry| entry.value.as_deref()?.parse().ok())
+        .ok_or_else(|| ParquetError::General("rollup has no snapshot count".to_string()))?;
+
+    let mut hours = Vec::new();
+    for row in reader.get_row_iter(None)? {
+        let row = row?;
+        let hour = DateTime::from_timestamp_millis(row.get_timestamp_millis(0)?)
+            .ok_or_else(|| ParquetError::General("rollup hour out of range".to_string()))?;
+        hours.push(HourlyRollup {
+            hour,
+            samples: row.get_long(1)? as u64,
+            avg_cpu_usage: row.get_double(2)?,
+            max_cpu_usage: row.get_double(3)?,
+            avg_memory_usage: row.get_double(4)?,
+            avg_disk_usage: row.get_double(5)?,
+            mouse_clicks: row.get_long(6)? as u64,
+            keyboard_events: row.get_long(7)? as u64,
+            bytes_sent: row.get_long(8)? as u64,
+            bytes_received: row.get_long(9)? as u64,
+            avg_focus_level: row.get_double(10)?,
+            context_switches: row.get_long(11)? as u64,
+            // Null when the hour had no keystroke dynamics
+            avg_typing_speed_wpm: row.get_double(12).ok(),
+        });
+    }
+    Ok(DayRollup { snapshots, hours })
+}
+
+/// Fold snapshots into one rollup per hour, oldest first
+pub fn rollup_hours(snapshots: &[EyeCoreData]) -> Vec<HourlyRollup> {
+    let mut hours: BTreeMap<DateTime<Utc>, Vec<&EyeCoreData>> = BTreeMap::new();
+    for data in snapshots {
+        hours.entry(truncate(data.timestamp, "hour").unwrap_or(data.timestamp)).or_default().push(data);
+    }
+    hours
+        .into_iter()
+        .map(|(hour, samples)| {
+            let n = samples.len() as f64;
+            let avg = |value: fn(&EyeCoreData) -> f64| samples.iter().map(|d| value(d)).sum::<f64>() / n;
+            let sum = |value: fn(&EyeCoreData) -> u64| samples.iter().map(|d| value(d)).sum::<u64>();
+            let typing: Vec<f64> = samples
+                .iter()
+                .filter_map(|d| d.keystroke_dynamics.as_ref().map(|k| k.typing_speed_wpm as f64))
+                .collect();
+            HourlyRollup {
+                hour,
+                samples: samples.len() as u64,
+                avg_cpu_usage: avg(|d| d.system_metrics.cpu_usage as f64),
+                max_cpu_usage: samples.iter().map(|d| d.system_metrics.cpu_usage as f64).fold(0.0, f64::max),
+                avg_memory_usage: avg(|d| d.system_metrics.memory_usage as f64),
+                avg_disk_usage: avg(|d| d.system_metrics.disk_usage as f64),
+                mouse_clicks: sum(|d| d.input_metrics.mouse_clicks as u64),
+                keyboard_events: sum(|d| d.input_metrics.keyboard_events as u64),
+                bytes_sent: sum(|d| d.network_metrics.bytes_sent),
+                bytes_received: sum(|d| d.network_metrics.bytes_received),
+                avg_focus_level: avg(|d| d.focus_metrics.focus_level as f64),
+                context_switches: sum(|d| d.focus_metrics.context_switches as u64),
+                avg_typing_speed_wpm: (!typing.is_empty()).then(|| typing.iter().sum::<f64>() / typing.len() as f64),
+            }
+        })
+        .collect()
+}
+
+/// Truncate `ts` to the start of its minute, hour, day, week (Monday), month or year
+pub fn truncate(ts: DateTime<Utc>, unit: &str) -> Option<DateTime<Utc>> {
+    let day = || Some(Utc.from_utc_datetime(&ts.date_naive().and_hms_opt(0, 0, 0)?));
+    match unit.to_ascii_lowercase().as_str() {
+        "minute" => ts.duration_trunc(Duration::minutes(1)).ok(),
+        "hour" => ts.duration_trunc(Duration::hours(1)).ok(),
+        "day" => day(),
+        "week" => day().map(|d: DateTime<Utc>| d - Duration::days(d.weekday().num_days_from_monday() as i64)),
+        "month" => day().and_then(|d: DateTime<Utc>| d.with_day(1)),
+        "year" => day().and_then(|d: DateTime<Utc>| d.with_day(1)?.with_month(1)),
+        _ => None,
+    }
+}
+
+// Timestamps are RFC 3339 text in UTC, so they sort and compare as strings
+fn timestamp_text(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn bucket_text(ts: DateTime<Utc>, unit: &str) -> Option<String> {
+    truncate(ts, unit).map(|ts| ts.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// `date_trunc(unit, ts)` as in Postgres, for timestamps stored as text
+fn register_date_trunc(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "date_trunc",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let unit = ctx.get::<String>(0)?;
+            let Some(ts) = ctx.get::<Option<String>>(1)? else {
+                return Ok(None);
+            };
+            let ts = DateTime::parse_from_rfc3339(&ts)
+                .map(|ts| ts.with_timezone(&Utc))
+                .or_else(|_| NaiveDate::parse_from_str(&ts, "%Y-%m-%d").map(|d| Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap_or_default())))
+                .map_err(|e| rusqlite::Error::UserFunctionError(format!("date_trunc: invalid timestamp {:?}: {}", ts, e).into()))?;
+            bucket_text(ts, &unit)
+                .map(Some)
+                .ok_or_else(|| rusqlite::Error::UserFunctionError(format!("date_trunc: unknown unit {:?}", unit).into()))
+        },
+    )
+}
+
+fn create_tables(conn: &Connection, tables: &QueryTables) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE snapshots (
+            ts TEXT, hour TEXT, day TEXT, session_id TEXT,
+            cpu_usage REAL, memory_usage REAL, disk_usage REAL,
+            active_process TEXT, process_count INTEGER,
+            mouse_clicks INTEGER, keyboard_events INTEGER, idle_duration_seconds INTEGER,
+            bytes_sent INTEGER, bytes_received INTEGER, bytes_sent_per_sec REAL, bytes_received_per_sec REAL,
+            tcp_connections INTEGER, udp_sockets INTEGER,
+            focus_level REAL, context_switches INTEGER, typing_speed_wpm REAL, keyboard_layout TEXT
+        );
+        CREATE TABLE hourly (
+            hour TEXT, day TEXT, samples INTEGER,
+            avg_cpu_usage REAL, max_cpu_usage REAL, avg_memory_usage REAL, avg_disk_usage REAL,
+            mouse_clicks INTEGER, keyboard_events INTEGER, bytes_sent INTEGER, bytes_received INTEGER,
+            avg_focus_level REAL, context_switches INTEGER, avg_typing_speed_wpm REAL
+        );",
+    )?;
+
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut insert = tx.prepare("INSERT INTO snapshots VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)")?;
+        for d in &tables.snapshots {
+            let keystrokes = d.keystroke_dynamics.as_ref();
+            insert.execute(params![
+                timestamp_text(d.timestamp),
+                bucket_text(d.timestamp, "hour"),
+                bucket_text(d.timestamp, "day"),
+                d.session_id,
+                d.system_metrics.cpu_usage,
+                d.system_metrics.memory_usage,
+                d.system_metrics.disk_usage,
+                d.process_data.active_process,
+                d.process_data.process_count as i64,
+                d.input_metrics.mouse_clicks,
+                d.input_metrics.keyboard_events,
+                d.input_metrics.idle_duration_seconds,
+                d.network_metrics.bytes_sent as i64,
+                d.network_metrics.bytes_received as i64,
+                d.network_metrics.bytes_sent_per_sec,
+                d.network_metrics.bytes_received_per_sec,
+                d.network_metrics.tcp_connections.map(|n| n as i64),
+                d.network_metrics.udp_sockets.map(|n| n as i64),
+                d.focus_metrics.focus_level,
+                d.focus_metrics.context_switches,
+                keystrokes.map(|k| k.typing_speed_wpm),
+                keystrokes.and_then(|k| k.keyboard_layout.as_ref()).map(|l| l.layout_id.as_str()),
+            ])?;
+        }
+
+        let mut insert = tx.prepare("INSERT INTO hourly VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)")?;
+        for h in &tables.hourly {
+            insert.execute(params![
+                bucket_text(h.hour, "hour"),
+                bucket_text(h.hour, "day"),
+                h.samples as i64,
+                h.avg_cpu_usage,
+                h.max_cpu_usage,
+                h.avg_memory_usage,
+                h.avg_disk_usage,
+                h.mouse_clicks as i64,
+                h.keyboard_events as i64,
+                h.bytes_sent as i64,
+                h.bytes_received as i64,
+                h.avg_focus_level,
+                h.context_switches as i64,
+                h.avg_typing_speed_wpm,
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+/// Run `sql` against in-memory copies of `tables`, returning at most `max_rows` rows.
+/// The query is interrupted inside SQLite once `timeout` has passed.
+pub fn execute(sql: &str, tables: &QueryTables, max_rows: usize, timeout: std::time::Duration) -> Result<QueryResult, QueryError> {
+    validate_sql(sql).map_err(QueryError::Invalid)?;
+    let deadline = Instant::now() + timeout;
+
+    let failed = |e: rusqlite::Error| match e.sqlite_error_code() {
+        Some(ErrorCode::OperationInterrupted) => QueryError::TimedOut,
+        _ => QueryError::Invalid(format!("Query error: {}", e)),
+    };
+    let conn = Connection::open_in_memory().map_err(failed)?;
+    conn.progress_handler(PROGRESS_INTERVAL, Some(move || Instant::now() >= deadline));
+    register_date_trunc(&conn).map_err(failed)?;
+    create_tables(&conn, tables).map_err(failed)?;
+
+    // From here on statements may only read the tables
+    conn.authorizer(Some(|context: AuthContext<'_>| match context.action {
+        AuthAction::Select | AuthAction::Read { .. } | AuthAction::Function { .. } | AuthAction::Recursive => Authorization::Allow,
+        _ => Authorization::Deny,
+    }));
+
+    let mut statement = conn.prepare(sql).map_err(failed)?;
+    if !statement.readonly() {
+        return Err(QueryError::Invalid("Only SELECT queries are allowed".to_string()));
+    }
+    let columns: Vec<String> = statement.column_names().iter().map(|name| name.to_string()).collect();
+
+    // Stop stepping one row past the budget, which tells the result was truncated
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    let mut cursor = statement.query([]).map_err(failed)?;
+    while let Some(row) = cursor.next().map_err(failed)? {
+        if rows.len() == max_rows {
+            truncated = true;
+            break;
+        }
+        rows.push(
+            (0..columns.len())
+                .map(|i| row.get_ref(i).map(value_to_json).unwrap_or(serde_json::Value::Null))
+                .collect(),
+        );
+    }
+
+    Ok(QueryResult {
+        columns,
+        row_count: rows.len(),
+        rows,
+        truncated,
+    })
+}
+
+fn value_to_json(value: ValueRef<'_>) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(v) => v.into(),
+        ValueRef::Real(v) => serde_json::Number::from_f64(v).map(serde_json::Value::Number).unwrap_or(serde_json::Value::Null),
+        ValueRef::Text(v) => String::from_utf8_lossy(v).into(),
+        ValueRef::Blob(v) => hex::encode(v).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn snapshot(ts: &str, cpu: f32) -> EyeCoreData {
+        serde_json::from_value(json!({
+            "session_id": "test",
+            "timestamp": ts,
+            "system_metrics": { "timestamp": ts, "cpu_usage": cpu, "memory_usage": 60.0, "disk_usage": 50.0 },
+            "process_data": { "timestamp": ts, "active_process": "Code.exe", "active_window_title": "", "process_count": 100 },
+            "input_metrics": { "timestamp": ts, "mouse_clicks": 3, "keyboard_events": 20, "idle_duration_seconds": 1 },
+            "network_metrics": { "timestamp": ts, "bytes_sent": 0, "bytes_received": 0, "active_connections": 0 },
+            "focus_metrics": { "timestamp": ts, "focus_level": 0.5, "context_switches": 0, "productive_app_time": 0 },
+        }))
+        .unwrap()
+    }
+
+    fn tables() -> QueryTables {
+        let snapshots = vec![
+            snapshot("2026-10-16T13:05:00Z", 10.0),
+            snapshot("2026-10-16T13:35:00Z", 30.0),
+            snapshot("2026-10-16T14:10:00Z", 50.0),
+        ];
+        QueryTables {
+            hourly: rollup_hours(&snapshots),
+            snapshots,
+        }
+    }
+
+    fn run(sql: &str, max_rows: usize) -> Result<QueryResult, QueryError> {
+        execute(sql, &tables(), max_rows, QUERY_TIMEOUT)
+    }
+
+    #[test]
+    fn date_trunc_groups_by_hour() {
+        let result = run("SELECT date_trunc('hour', ts), avg(cpu_usage) FROM snapshots GROUP BY 1 ORDER BY 1", 10).unwrap();
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![json!("2026-10-16T13:00:00Z"), json!(20.0)],
+                vec![json!("2026-10-16T14:00:00Z"), json!(50.0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn date_trunc_units() {
+        let ts: DateTime<Utc> = "2026-10-16T13:35:12Z".parse().unwrap(); // a Friday
+        let bucket = |unit| bucket_text(ts, unit);
+        assert_eq!(bucket("minute").as_deref(), Some("2026-10-16T13:35:00Z"));
+        assert_eq!(bucket("DAY").as_deref(), Some("2026-10-16T00:00:00Z"));
+        assert_eq!(bucket("week").as_deref(), Some("2026-10-12T00:00:00Z"));
+        assert_eq!(bucket("month").as_deref(), Some("2026-10-01T00:00:00Z"));
+        assert_eq!(bucket("year").as_deref(), Some("2026-01-01T00:00:00Z"));
+        assert!(matches!(run("SELECT date_trunc('fortnight', ts) FROM snapshots", 10), Err(QueryError::Invalid(_))));
+    }
+
+    #[test]
+    fn hourly_table_holds_rollups() {
+        let result = run("SELECT hour, samples, avg_cpu_usage, max_cpu_usage FROM hourly ORDER BY hour", 10).unwrap();
+        assert_eq!(result.rows[0], vec![json!("2026-10-16T13:00:00Z"), json!(2), json!(20.0), json!(30.0)]);
+        assert_eq!(result.rows[1][1], json!(1));
+    }
+
+    #[test]
+    fn rollups_round_trip_through_parquet() {
+        let mut hours = tables().hourly;
+        hours[0].avg_typing_speed_wpm = Some(62.5);
+        let rollup = DayRollup { snapshots: 3, hours };
+        let dir = std::env::temp_dir().join(format!("eyecore_rollup_{}", uuid::Uuid::new_v4()));
+        let path = dir.join(ROLLUP_FILE);
+
+        save_rollup(&path, &rollup).unwrap();
+        let read = read_rollup(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(read.unwrap(), rollup);
+    }
+
+    #[test]
+    fn rows_stop_at_the_budget() {
+        let sql = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT i FROM n";
+        let result = run(sql, 5).unwrap();
+        assert_eq!(result.row_count, 5);
+        assert!(result.truncated);
+    }
+
+    #[test]
+    fn long_queries_are_interrupted() {
+        let sql = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT count(*) FROM n";
+        let started = Instant::now();
+        let result = execute(sql, &tables(), 10, std::time::Duration::from_millis(100));
+        assert_eq!(result.unwrap_err(), QueryError::TimedOut);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn writes_and_file_access_are_rejected() {
+        for sql in [
+            "DELETE FROM snapshots",
+            "SELECT 1; DROP TABLE snapshots",
+            "WITH x AS (SELECT 1) DELETE FROM snapshots",
+            "SELECT load_extension('/tmp/evil')",
+        ] {
+            assert!(matches!(run(sql, 10), Err(QueryError::Invalid(_))), "{}", sql);
+        }
+        assert!(references("select * FROM Hourly", HOURLY_TABLE));
+        assert!(!references("select * from hourly_x", HOURLY_TABLE));
+    }
+}
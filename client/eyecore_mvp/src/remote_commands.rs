@@ -0,0 +1,164 @@
+//! Commands the upload server pushes over the WebSocket connection, such as
+//! `{"method": "Command", "data": {"id": "42", "command": "collect_snapshot"}}`. The WebSocket client
+//! hands them to the dispatcher on a channel; it carries them out one at a time against the collector
+//! and the collection loops, and each is answered on the same connection with
+//! `{"method": "CommandResult", "data": {"id": "42", "ok": true, "result": {...}}}` (or `"error"`).
+//! Modules are only switched on remotely once their consent is granted, as with `/control`.
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, RwLock};
+
+use crate::consent::{self, ConsentManager};
+use crate::data_collector::DataCollector;
+use crate::models::{CollectorUpdate, ModuleUpdate};
+use crate::module_control::{self, ModuleControl};
+
+/// Commands waiting for the dispatcher; more are refused as busy
+pub const COMMAND_QUEUE: usize = 16;
+const DEFAULT_RECORDING_SECS: u64 = 5;
+const MAX_RECORDING_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    /// Switch a module or change its sampling interval or retention, like `POST /control/{module}`
+    UpdateModule { module: String, update: ModuleUpdate },
+    /// Switch a pluggable collector or change its interval, like `POST /collectors/{name}`
+    UpdateCollector { collector: String, update: CollectorUpdate },
+    /// Collect, store and queue a snapshot now instead of at the next tick
+    CollectSnapshot,
+    /// Record the microphone now; needs the voice module
+    CaptureAudio { duration_secs: Option<u64> },
+}
+
+/// Body of a `Command` message
+#[derive(Debug, Clone, Deserialize)]
+struct CommandMessage {
+    id: String,
+    #[serde(flatten)]
+    command: RemoteCommand,
+}
+
+/// Body of the `CommandResult` message answering a command
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandResult {
+    pub id: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CommandResult {
+    pub fn new(id: String, outcome: Result<serde_json::Value, String>) -> Self {
+        match outcome {
+            Ok(result) => CommandResult { id, ok: true, result: Some(result), error: None },
+            Err(error) => CommandResult { id, ok: false, result: None, error: Some(error) },
+        }
+    }
+
+    /// The `CommandResult` message
+    pub fn to_message(&self) -> serde_json::Value {
+        json!({ "method": "CommandResult", "data": self })
+    }
+}
+
+/// A command on its way to the dispatcher, with where to send its result
+pub struct CommandRequest {
+    pub id: String,
+    pub command: RemoteCommand,
+    pub results: mpsc::Sender<CommandResult>,
+}
+
+/// A snapshot asked for by `collect_snapshot`; answered with its timestamp once stored
+pub type SnapshotRequest = oneshot::Sender<Result<DateTime<Utc>, String>>;
+
+/// A recording asked for by `capture_audio`; answered with the audio file once saved
+pub struct RecordingRequest {
+    pub duration_ms: u64,
+    pub reply: oneshot::Sender<Result<String, String>>,
+}
+
+/// The id and command of a `Command` message's data; the id is empty if there is none
+pub fn parse(data: &serde_json::Value) -> (String, Result<RemoteCommand, String>) {
+    match serde_json::from_value::<CommandMessage>(data.clone()) {
+        Ok(message) => (message.id, Ok(message.command)),
+        Err(e) => {
+            let id = data.get("id").and_then(|id| id.as_str()).unwrap_or_default().to_string();
+            (id, Err(format!("Invalid command: {}", e)))
+        }
+    }
+}
+
+pub struct CommandDispatcher {
+    pub collector: Arc<RwLock<DataCollector>>,
+    pub module_control: Arc<RwLock<ModuleControl>>,
+    pub consent: Arc<RwLock<ConsentManager>>,
+    pub snapshots: mpsc::Sender<SnapshotRequest>,
+    pub recordings: mpsc::Sender<RecordingRequest>,
+}
+
+impl CommandDispatcher {
+    /// Carry out commands until the WebSocket client drops its sender
+    pub fn start(self, mut requests: mpsc::Receiver<CommandRequest>) {
+        tokio::spawn(async move {
+            while let Some(request) = requests.recv().await {
+                info!("📡 Server command {}: {:?}", request.id, request.command);
+                let outcome = self.run(request.command).await;
+                if let Err(e) = &outcome {
+                    warn!("⚠️ Server command {} failed: {}", request.id, e);
+                }
+                // The connection may have ended meanwhile; the server learns nothing then
+                let _ = request.results.send(CommandResult::new(request.id, outcome)).await;
+            }
+        });
+    }
+
+    async fn run(&self, command: RemoteCommand) -> Result<serde_json::Value, String> {
+        match command {
+            RemoteCommand::UpdateModule { module, update } => {
+                if !module_control::is_module(&module) {
+                    return Err(format!("Unknown module {:?}", module));
+                }
+                let mut collector = self.collector.write().await;
+                if update.enabled == Some(true) && consent::is_module(&module) && !self.consent.read().await.is_granted(&module) {
+                    return Err(format!("No consent for {}", module));
+                }
+                let settings = self.module_control.write().await.update(&module, update, &mut collector)?;
+                Ok(json!(settings))
+            }
+            RemoteCommand::UpdateCollector { collector: name, update } => {
+                let mut collector = self.collector.write().await;
+                if !collector.collectors().contains(&name) {
+                    return Err(format!("Unknown collector {:?}", name));
+                }
+                let status = collector.collectors_mut().update(&name, update)?;
+                info!("🧩 Collector {}: enabled={}, interval={}s", status.name, status.enabled, status.interval_secs);
+                Ok(json!(status))
+            }
+            RemoteCommand::CollectSnapshot => {
+                let (reply, answer) = oneshot::channel();
+                self.snapshots.send(reply).await.map_err(|_| "Snapshot collection has stopped".to_string())?;
+                let timestamp = answer.await.map_err(|_| "Snapshot collection has stopped".to_string())??;
+                Ok(json!({ "timestamp": timestamp }))
+            }
+            RemoteCommand::CaptureAudio { duration_secs } => {
+                let duration_secs = duration_secs.unwrap_or(DEFAULT_RECORDING_SECS);
+                if !(1..=MAX_RECORDING_SECS).contains(&duration_secs) {
+                    return Err(format!("duration_secs must be 1-{}", MAX_RECORDING_SECS));
+                }
+                let unavailable = || "Audio capture is unavailable: voice collection is off (ELEVENLABS_API_KEY not set)".to_string();
+                let (reply, answer) = oneshot::channel();
+                let request = RecordingRequest { duration_ms: duration_secs * 1000, reply };
+                self.recordings.send(request).await.map_err(|_| unavailable())?;
+                let file = answer.await.map_err(|_| unavailable())??;
+                Ok(json!({ "file": file, "duration_secs": duration_secs }))
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use chrono::{Duration, NaiveDate, Utc};
+use log::info;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::capture_log::{self, DailyCaptureActivity};
+use crate::classification::{self, AppClassification};
+use crate::ergonomics;
+use crate::goals;
+use crate::models::DailyCoverage;
+use crate::projects::{self, ProjectDetector};
+use crate::storage::DataStorage;
+use crate::timeline;
+use crate::wifi;
+
+/// Compute collection coverage for each of the given days
+pub async fn coverage_for_dates(storage: &DataStorage, dates: &[NaiveDate]) -> std::io::Result<Vec<DailyCoverage>> {
+    let gaps = storage.load_collection_gaps().await?;
+
+    let mut coverage = Vec::with_capacity(dates.len());
+    for date in dates {
+        let snapshot_count = storage.list_snapshot_timestamps(Some(*date)).await?.len();
+        let mut notes = storage.load_notes(*date - Duration::days(1)).await?;
+        notes.extend(storage.load_notes(*date).await?);
+        coverage.push(timeline::daily_coverage(*date, snapshot_count, &gaps, &notes));
+    }
+    Ok(coverage)
+}
+
+/// Microphone/camera capture time for each of the given days
+pub async fn capture_activity_for_dates(storage: &DataStorage, dates: &[NaiveDate]) -> std::io::Result<Vec<DailyCaptureActivity>> {
+    let mut activity = Vec::with_capacity(dates.len());
+    for date in dates {
+        let events = storage.load_capture_events(*date).await?;
+        activity.push(capture_log::summarize_day(*date, &events));
+    }
+    Ok(activity)
+}
+
+/// Build the daily report for `date`
+pub async fn build_daily_report(storage: &DataStorage, date: NaiveDate) -> std::io::Result<serde_json::Value> {
+    let coverage = coverage_for_dates(storage, &[date]).await?.remove(0);
+    
+    let snapshots = storage.load_snapshots(date).await?;
+    let ergonomics = ergonomics::evening_glare_sessions(&snapshots);
+    let docking = ergonomics::docking_summary(&snapshots);
+    let capture_activity = capture_activity_for_dates(storage, &[date]).await?.remove(0);
+    let locations = wifi::location_breakdown(&snapshots);
+    let classification = AppClassification::load(&storage.device_dir());
+    let productivity = classification::productivity_breakdown(&snapshots, &classification);
+    let projects = projects::project_breakdown(&snapshots, &ProjectDetector::load(&storage.device_dir()));
+    let goals = goals::daily_attainment(&snapshots, &storage.device_dir());
+
+    Ok(json!({
+        "date": date.format("%Y-%m-%d").to_string(),
+        "generated_at": Utc::now().to_rfc3339(),
+        "coverage": coverage,
+        "wellbeing": {
+            "ergonomics": ergonomics,
+            "evening_glare_minutes": ergonomics.iter().map(|f| f.duration_minutes).sum::<i64>(),
+            "docking": docking,
+        },
+        "locations": locations,
+        "productivity": productivity,
+        "projects": projects,
+        "goals": goals,
+        "transparency": {
+            "capture_activity": capture_activity,
+        },
+    }))
+}
+
+/// Build the weekly report for the week (Monday to Sunday) from `week_start`
+pub async fn build_weekly_report(storage: &DataStorage, week_start: NaiveDate) -> std::io::Result<serde_json::Value> {
+    let classification = AppClassification::load(&storage.device_dir());
+    let detector = ProjectDetector::load(&storage.device_dir());
+    let mut days = Vec::new();
+    let mut project_minutes: BTreeMap<String, i64> = BTreeMap::new();
+    for date in week_start.iter_days().take(7) {
+        let snapshots = storage.load_snapshots(date).await?;
+        let productivity = classification::productivity_breakdown(&snapshots, &classification);
+        for project in projects::project_breakdown(&snapshots, &detector) {
+            *project_minutes.entry(project.project).or_default() += project.minutes;
+        }
+        days.push(json!({
+            "date": date.format("%Y-%m-%d").to_string(),
+            "productive_minutes": productivity.productive_minutes,
+            "neutral_minutes": productivity.neutral_minutes,
+            "distracting_minutes": productivity.distracting_minutes,
+        }));
+    }
+    let mut projects: Vec<_> = project_minutes.into_iter().collect();
+    projects.sort_by_key(|(_, minutes)| std::cmp::Reverse(*minutes));
+    let goals = goals::weekly_attainment(storage, week_start).await?;
+
+    Ok(json!({
+        "week_start": week_start.format("%Y-%m-%d").to_string(),
+        "week_end": (week_start + Duration::days(6)).format("%Y-%m-%d").to_string(),
+        "generated_at": Utc::now().to_rfc3339(),
+        "days": days,
+        "projects": projects.into_iter().map(|(project, minutes)| json!({ "project": project, "minutes": minutes })).collect::<Vec<_>>(),
+        "goals": goals,
+    }))
+}
+
+/// Write yesterday's daily report once the day has rolled over, and last week's weekly report
+/// once the week has (the scheduler's `reports` job)
+pub async fn write_due_reports(storage: &DataStorage) -> Result<(), String> {
+    let today = Utc::now().date_naive();
+    let mut errors = Vec::new();
+
+    let yesterday = today - Duration::days(1);
+    if !storage.has_daily_report(yesterday) {
+        match build_daily_report(storage, yesterday).await {
+            Ok(report) => match storage.save_daily_report(yesterday, &report).await {
+                Ok(_) => info!("📅 Daily report generated for {}", yesterday),
+                Err(e) => errors.push(format!("Failed to save daily report: {}", e)),
+            },
+            Err(e) => errors.push(format!("Failed to build daily report: {}", e)),
+        }
+    }
+
+    let last_week = goals::week_start(today) - Duration::days(7);
+    if !storage.has_weekly_report(last_week) {
+        match build_weekly_report(storage, last_week).await {
+            Ok(report) => match storage.save_weekly_report(last_week, &report).await {
+                Ok(_) => info!("📅 Weekly report generated for the week of {}", last_week),
+                Err(e) => errors.push(format!("Failed to save weekly report: {}", e)),
+            },
+            Err(e) => errors.push(format!("Failed to build weekly report: {}", e)),
+        }
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors.join("; ")) }
+}
@@ -0,0 +1,372 @@
+//! Stratified samples of stored snapshots, packaged for human labeling
+
+use chrono::Duration;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::access;
+use crate::models::{EyeCoreData, FlagRecord};
+
+pub const DEFAULT_SAMPLES: usize = 100;
+pub const MAX_SAMPLES: usize = 1_000;
+pub const MAX_CONTEXT_SECS: i64 = 120;
+
+/// Flags are raised moments after their snapshot is saved; a flag further out belongs to a newer one
+const FLAG_MATCH_WINDOW_SECS: i64 = 30;
+const UNFLAGGED: &str = "unflagged";
+
+/// How snapshots are grouped before sampling, so rare flags are not drowned out by quiet periods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stratify {
+    FlagType,
+    Severity,
+    None,
+}
+
+impl Stratify {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "flag_type" => Ok(Stratify::FlagType),
+            "severity" => Ok(Stratify::Severity),
+            "none" => Ok(Stratify::None),
+            other => Err(format!("Unknown stratify_by {:?} (expected flag_type, severity or none)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SampleFlag {
+    pub flag_type: String,
+    pub severity: String,
+    pub title: String,
+}
+
+/// Left empty for the labeler; scores are 0.0-1.0 like the collected metrics
+#[derive(Debug, Default, Serialize)]
+pub struct LabelFields {
+    pub focus_level: Option<f32>,
+    pub stress_level: Option<f32>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LabelingSample {
+    pub sample_id: String,
+    pub stratum: String,
+    pub snapshot: EyeCoreData,
+    pub flags: Vec<SampleFlag>,
+    pub context: Vec<EyeCoreData>, // same-session snapshots within `context_secs`, oldest first
+    pub labels: LabelFields,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StratumSummary {
+    pub available: usize,
+    pub sampled: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SampleSet {
+    pub stratify_by: Stratify,
+    pub seed: u64,
+    pub requested: usize,
+    pub count: usize,
+    pub strata: BTreeMap<String, StratumSummary>,
+    pub samples: Vec<LabelingSample>,
+}
+
+/// Draw up to `n` snapshots, taking from each stratum in turn so small strata are fully
+/// represented before large ones fill the rest. The same inputs and `seed` give the same sample.
+pub fn stratified_sample(
+    mut snapshots: Vec<EyeCoreData>,
+    flags: &[FlagRecord],
+    stratify: Stratify,
+    n: usize,
+    context_secs: i64,
+    seed: u64,
+) -> SampleSet {
+    snapshots.sort_by_key(|s| s.timestamp);
+    let attached = attach_flags(&snapshots, flags);
+
+    let mut strata: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, snapshot_flags) in attached.iter().enumerate() {
+        for key in strata_keys(snapshot_flags, stratify) {
+            strata.entry(key).or_default().push(index);
+        }
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut queues: Vec<(&String, std::vec::IntoIter<usize>)> = strata
+        .iter()
+        .map(|(key, members)| {
+            let mut members = members.clone();
+            members.shuffle(&mut rng);
+            (key, members.into_iter())
+        })
+        .collect();
+
+    // A snapshot with several flag types sits in several strata but is only sampled once
+    let mut taken = vec![false; snapshots.len()];
+    let mut picks: Vec<(usize, &String)> = Vec::new();
+    while picks.len() < n {
+        let mut progressed = false;
+        for (key, queue) in queues.iter_mut() {
+            if picks.len() >= n {
+                break;
+            }
+            if let Some(index) = queue.find(|&index| !taken[index]) {
+                taken[index] = true;
+                picks.push((index, *key));
+                progressed = true;
+            }
+        }
+        if !progressed {
+            break;
+        }
+    }
+
+    let mut summary: BTreeMap<String, StratumSummary> = strata
+        .iter()
+        .map(|(key, members)| (key.clone(), StratumSummary { available: members.len(), sampled: 0 }))
+        .collect();
+    let context_window = Duration::seconds(context_secs.clamp(0, MAX_CONTEXT_SECS));
+    let samples: Vec<LabelingSample> = picks
+        .into_iter()
+        .map(|(index, stratum)| {
+            if let Some(stratum) = summary.get_mut(stratum) {
+                stratum.sampled += 1;
+            }
+            let snapshot = &snapshots[index];
+            LabelingSample {
+                sample_id: format!("{}_{}", snapshot.session_id, snapshot.timestamp.format("%Y%m%dT%H%M%S%.3fZ")),
+                stratum: stratum.clone(),
+                snapshot: access::metrics_only(snapshot),
+                flags: attached[index]
+                    .iter()
+                    .map(|flag| SampleFlag {
+                        flag_type: flag.flag_type.name.clone(),
+                        severity: flag.severity.clone(),
+                        title: flag.title.clone(),
+                    })
+                    .collect(),
+                context: neighbours(&snapshots, index, context_window)
+                    .into_iter()
+                    .map(access::metrics_only)
+                    .collect(),
+                labels: LabelFields::default(),
+            }
+        })
+        .collect();
+
+    SampleSet {
+        stratify_by: stratify,
+        seed,
+        requested: n,
+        count: samples.len(),
+        strata: summary,
+        samples,
+    }
+}
+
+/// Flags per snapshot (`snapshots` sorted by time): each flag goes to the latest snapshot of its
+/// session taken no more than FLAG_MATCH_WINDOW_SECS before it
+fn attach_flags<'a>(snapshots: &[EyeCoreData], flags: &'a [FlagRecord]) -> Vec<Vec<&'a FlagRecord>> {
+    let mut attached = vec![Vec::new(); snapshots.len()];
+    let window = Duration::seconds(FLAG_MATCH_WINDOW_SECS);
+
+    for flag in flags {
+        let end = snapshots.partition_point(|s| s.timestamp <= flag.timestamp);
+        let matched = snapshots[..end]
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(_, s)| flag.timestamp - s.timestamp <= window)
+            .find(|(_, s)| s.session_id == flag.session_id);
+        if let Some((index, _)) = matched {
+            attached[index].push(flag);
+        }
+    }
+    attached
+}
+
+fn strata_keys(flags: &[&FlagRecord], stratify: Stratify) -> Vec<String> {
+    let mut keys: Vec<String> = match stratify {
+        Stratify::None => return vec!["all".to_string()],
+        Stratify::FlagType => flags.iter().map(|f| f.flag_type.name.clone()).collect(),
+        Stratify::Severity => flags.iter().map(|f| f.severity.clone()).collect(),
+    };
+    keys.sort();
+    keys.dedup();
+    if keys.is_empty() {
+        keys.push(UNFLAGGED.to_string());
+    }
+    keys
+}
+
+/// Same-session snapshots within `window` of `snapshots[index]`, oldest first
+fn neighbours(snapshots: &[EyeCoreData], index: usize, window: Duration) -> Vec<&EyeCoreData> {
+    if window.is_zero() {
+        return Vec::new();
+    }
+    let center = &snapshots[index];
+    let start = snapshots.partition_point(|s| s.timestamp < center.timestamp - window);
+    let end = snapshots.partition_point(|s| s.timestamp <= center.timestamp + window);
+
+    (start..end)
+        .filter(|&i| i != index && snapshots[i].session_id == center.session_id)
+        .map(|i| &snapshots[i])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FlagTypeTag;
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+
+    fn start() -> DateTime<Utc> {
+        "2026-10-16T09:00:00Z".parse().unwrap()
+    }
+
+    /// One snapshot every 10 seconds from 09:00
+    fn snapshot(session: &str, slot: i64) -> EyeCoreData {
+        let ts = start() + Duration::seconds(slot * 10);
+        serde_json::from_value(json!({
+            "session_id": session,
+            "timestamp": ts,
+            "system_metrics": { "timestamp": ts, "cpu_usage": 40.0, "memory_usage": 60.0, "disk_usage": 50.0 },
+            "process_data": { "timestamp": ts, "active_process": "Code.exe", "active_window_title": "main.rs", "process_count": 100 },
+            "input_metrics": { "timestamp": ts, "mouse_clicks": 3, "keyboard_events": 20, "idle_duration_seconds": 1 },
+            "network_metrics": { "timestamp": ts, "bytes_sent": 0, "bytes_received": 0, "active_connections": 0 },
+            "focus_metrics": { "timestamp": ts, "focus_level": 0.5, "context_switches": 0, "productive_app_time": 0 },
+        }))
+        .unwrap()
+    }
+
+    fn session(count: i64) -> Vec<EyeCoreData> {
+        (0..count).map(|slot| snapshot("s1", slot)).collect()
+    }
+
+    /// A flag raised `delay_secs` after the snapshot in `slot`
+    fn flag(session: &str, slot: i64, delay_secs: i64, flag_type: &str, severity: &str) -> FlagRecord {
+        FlagRecord {
+            id: format!("{}-{}-{}", session, slot, flag_type),
+            timestamp: start() + Duration::seconds(slot * 10 + delay_secs),
+            session_id: session.to_string(),
+            flag_type: FlagTypeTag { name: flag_type.to_string() },
+            severity: severity.to_string(),
+            title: flag_type.to_string(),
+            description: String::new(),
+            data_source: "test".to_string(),
+            metrics: json!({}),
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn rare_strata_are_fully_sampled_and_the_rest_fill_up() {
+        // 100 snapshots: 2 with a health flag, 8 with a performance flag, 90 unflagged
+        let mut flags: Vec<FlagRecord> = (0..2).map(|slot| flag("s1", slot, 1, "HealthConcern", "High")).collect();
+        flags.extend((10..18).map(|slot| flag("s1", slot, 1, "PerformanceIssue", "Medium")));
+
+        let set = stratified_sample(session(100), &flags, Stratify::FlagType, 20, 0, 7);
+        assert_eq!(set.count, 20);
+        let rate = |key: &str| (set.strata[key].sampled, set.strata[key].available);
+        assert_eq!(rate("HealthConcern"), (2, 2));
+        assert_eq!(rate("PerformanceIssue"), (8, 8));
+        assert_eq!(rate(UNFLAGGED), (10, 90));
+        assert!(set.samples.iter().filter(|s| s.stratum == "HealthConcern").all(|s| s.flags[0].severity == "High"));
+    }
+
+    #[test]
+    fn strata_take_turns_while_all_have_snapshots() {
+        let flags: Vec<FlagRecord> = (0..50).map(|slot| flag("s1", slot, 1, "ProductivityAlert", "Low")).collect();
+        let set = stratified_sample(session(100), &flags, Stratify::Severity, 10, 0, 1);
+        assert_eq!(set.strata["Low"].sampled, 5);
+        assert_eq!(set.strata[UNFLAGGED].sampled, 5);
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_sample() {
+        let ids = |seed| -> Vec<String> {
+            stratified_sample(session(200), &[], Stratify::None, 15, 0, seed)
+                .samples
+                .into_iter()
+                .map(|s| s.sample_id)
+                .collect()
+        };
+        assert_eq!(ids(42), ids(42));
+        assert_ne!(ids(42), ids(43));
+    }
+
+    #[test]
+    fn empty_inputs_and_oversized_requests() {
+        let set = stratified_sample(Vec::new(), &[], Stratify::FlagType, 10, 30, 1);
+        assert_eq!(set.count, 0);
+        assert!(set.strata.is_empty());
+
+        let set = stratified_sample(session(5), &[], Stratify::FlagType, 0, 30, 1);
+        assert_eq!(set.count, 0);
+        assert_eq!(set.strata[UNFLAGGED].available, 5);
+
+        let set = stratified_sample(session(5), &[], Stratify::None, 100, 0, 1);
+        assert_eq!((set.requested, set.count), (100, 5));
+    }
+
+    #[test]
+    fn snapshots_in_several_strata_are_sampled_once() {
+        let flags = vec![flag("s1", 0, 1, "HealthConcern", "High"), flag("s1", 0, 2, "BehaviorAnomaly", "Low")];
+        let set = stratified_sample(session(1), &flags, Stratify::FlagType, 10, 0, 1);
+        assert_eq!(set.count, 1);
+        assert_eq!(set.samples[0].flags.len(), 2);
+        let sampled: usize = set.strata.values().map(|s| s.sampled).sum();
+        assert_eq!(sampled, 1);
+    }
+
+    #[test]
+    fn flags_attach_to_the_latest_snapshot_of_their_session_in_the_window() {
+        let mut snapshots = session(3);
+        snapshots.push(snapshot("s2", 2));
+        snapshots.sort_by_key(|s| s.timestamp);
+        let flags = vec![
+            flag("s1", 0, 15, "HealthConcern", "High"), // between slots 1 and 2: slot 1
+            flag("s2", 2, 1, "SecurityConcern", "Low"),  // other session at the same time
+            flag("s1", 2, FLAG_MATCH_WINDOW_SECS + 1, "PerformanceIssue", "Low"), // too late for any snapshot
+        ];
+        let attached = attach_flags(&snapshots, &flags);
+        let titles: Vec<Vec<&str>> = attached.iter().map(|f| f.iter().map(|f| f.title.as_str()).collect()).collect();
+        let slot1 = snapshots.iter().position(|s| s.session_id == "s1" && s.timestamp == start() + Duration::seconds(10)).unwrap();
+        let s2 = snapshots.iter().position(|s| s.session_id == "s2").unwrap();
+        assert_eq!(titles[slot1], ["HealthConcern"]);
+        assert_eq!(titles[s2], ["SecurityConcern"]);
+        assert_eq!(titles.iter().map(Vec::len).sum::<usize>(), 2);
+    }
+
+    #[test]
+    fn context_is_same_session_within_the_clamped_window() {
+        let mut snapshots = session(40);
+        snapshots.push(snapshot("s2", 20));
+        snapshots.sort_by_key(|s| s.timestamp);
+        let center = snapshots.iter().position(|s| s.session_id == "s1" && s.timestamp == start() + Duration::seconds(200)).unwrap();
+
+        let context = neighbours(&snapshots, center, Duration::seconds(20));
+        let slots: Vec<i64> = context.iter().map(|s| (s.timestamp - start()).num_seconds() / 10).collect();
+        assert_eq!(slots, [18, 19, 21, 22]);
+        assert!(neighbours(&snapshots, center, Duration::zero()).is_empty());
+
+        // context_secs is capped at MAX_CONTEXT_SECS (12 slots either side)
+        let set = stratified_sample(session(40), &[], Stratify::None, 40, 10_000, 1);
+        assert!(set.samples.iter().all(|s| s.context.len() <= 2 * (MAX_CONTEXT_SECS as usize / 10)));
+        assert!(set.samples.iter().any(|s| s.context.len() == 24));
+    }
+
+    #[test]
+    fn stratify_names() {
+        assert_eq!(Stratify::parse("flag_type"), Ok(Stratify::FlagType));
+        assert_eq!(Stratify::parse("none"), Ok(Stratify::None));
+        assert!(Stratify::parse("flagtype").is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! Timing for periodic collectors: each schedule starts at a random phase within its interval and
+//! can add jitter to every run, so collectors do not all fire at the same instant
+
+use log::warn;
+use rand::Rng;
+use std::collections::HashMap;
+use tokio::time::{Duration, Instant};
+
+/// Names accepted in EYECORE_JITTER_MS
+pub const SCHEDULES: &[&str] = &[
+    "snapshot",
+    "enhanced",
+    "upload",
+    "voice",
+    "system_metrics",
+    "screen_interactions",
+    "camera_data",
+    "file_metadata",
+];
+
+/// Phase and jitter settings for all schedules
+#[derive(Debug, Clone)]
+pub struct ScheduleConfig {
+    pub random_phase: bool,
+    pub default_jitter: Duration,
+    pub jitter: HashMap<String, Duration>,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        ScheduleConfig {
+            random_phase: true,
+            default_jitter: Duration::ZERO,
+            jitter: HashMap::new(),
+        }
+    }
+}
+
+impl ScheduleConfig {
+    /// Read EYECORE_PHASE_OFFSET ("0" starts every schedule immediately) and EYECORE_JITTER_MS,
+    /// either one value for all schedules or a list like "snapshot:200,enhanced:800"
+    pub fn from_env() -> Self {
+        let mut config = ScheduleConfig::default();
+        if let Ok(value) = std::env::var("EYECORE_PHASE_OFFSET") {
+            config.random_phase = !matches!(value.trim(), "0" | "false" | "off");
+        }
+        if let Ok(value) = std::env::var("EYECORE_JITTER_MS") {
+            if let Err(e) = config.parse_jitter(&value) {
+                warn!("Ignoring EYECORE_JITTER_MS: {}", e);
+            }
+        }
+        config
+    }
+
+    fn parse_jitter(&mut self, value: &str) -> Result<(), String> {
+        let millis = |v: &str| {
+            v.trim()
+                .parse::<u64>()
+                .map(Duration::from_millis)
+                .map_err(|_| format!("{:?} is not a number of milliseconds", v.trim()))
+        };
+
+        if !value.contains(':') {
+            self.default_jitter = millis(value)?;
+            return Ok(());
+        }
+        for entry in value.split(',').filter(|e| !e.trim().is_empty()) {
+            let (name, ms) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("expected name:ms, got {:?}", entry.trim()))?;
+            let name = name.trim();
+            if !SCHEDULES.contains(&name) {
+                return Err(format!("unknown schedule {:?} (expected one of {})", name, SCHEDULES.join(", ")));
+            }
+            self.jitter.insert(name.to_string(), millis(ms)?);
+        }
+        Ok(())
+    }
+
+    pub fn schedule(&self, name: &str, interval: Duration) -> Schedule {
+        let phase = if self.random_phase && !interval.is_zero() {
+            rand::thread_rng().gen_range(Duration::ZERO..interval)
+        } else {
+            Duration::ZERO
+        };
+        let jitter = self.jitter.get(name).copied().unwrap_or(self.default_jitter);
+        Schedule {
+            interval,
+            jitter,
+            next: Instant::now() + phase,
+        }
+    }
+}
+
+/// Fixed-rate timer: run k is due at start + phase + k * interval, shifted by up to ±jitter.
+/// Slow runs do not push later ones back; missed runs are skipped rather than bunched up.
+#[derive(Debug)]
+pub struct Schedule {
+    interval: Duration,
+    jitter: Duration,
+    next: Instant,
+}
+
+impl Schedule {
+    /// Wait until the next run is due
+    pub async fn tick(&mut self) {
+        let now = Instant::now();
+        while self.next + self.interval <= now {
+            self.next += self.interval;
+        }
+        let due = self.next;
+        self.next += self.interval;
+
+        // More than half an interval would let consecutive runs swap order
+        let jitter = self.jitter.min(self.interval / 2);
+        let due = if jitter.is_zero() {
+            due
+        } else {
+            let offset = rand::thread_rng().gen_range(Duration::ZERO..=jitter * 2);
+            (due + offset).checked_sub(jitter).unwrap_or(due)
+        };
+        tokio::time::sleep_until(due).await;
+    }
+
+    /// Change the interval from the next run on. A shorter interval takes effect right away rather
+    /// than after the run already scheduled.
+    pub fn set_interval(&mut self, interval: Duration) {
+        if interval == self.interval {
+            return;
+        }
+        self.next = self.next.min(Instant::now() + interval);
+        self.interval = interval;
+    }
+}
@@ -0,0 +1,355 @@
+//! Background jobs on cron-like schedules (`[scheduler]`, `/scheduler/jobs`): storage retention,
+//! compaction and reports run at the times of a five-field cron expression in local time, which
+//! `[scheduler.jobs.<name>]` can change. Each run can be delayed by a random jitter so devices do
+//! not all start together. A run still going when the job is next due makes it skip that time
+//! instead of starting a second run alongside, and the last runs of each job are kept for status.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use log::{error, warn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::{JobRun, ScheduledJob};
+
+/// Jobs and their default schedules
+pub const JOBS: &[(&str, &str)] = &[
+    ("retention", "0 * * * *"),    // hourly
+    ("compaction", "0 3 * * *"),   // nightly, with compact_after_days
+    ("reports", "*/10 * * * *"),   // writes the daily and weekly reports once their period is over
+];
+pub const MAX_JITTER_SECS: u64 = 3600;
+/// Runs kept per job
+pub const HISTORY_LEN: usize = 20;
+/// How far ahead a schedule is searched for its next time; expressions with none are rejected
+const SEARCH_DAYS: i64 = 366 * 4;
+
+/// `[scheduler]` settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerConfig {
+    pub jitter_secs: u64,                  // default random delay of each run, up to this
+    pub jobs: BTreeMap<String, JobConfig>, // by job name, see JOBS
+}
+
+/// `[scheduler.jobs.<name>]` settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobConfig {
+    pub enabled: bool,
+    pub cron: Option<String>,              // the job's own schedule if unset
+    pub jitter_secs: Option<u64>,          // `scheduler.jitter_secs` if unset
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        JobConfig { enabled: true, cron: None, jitter_secs: None }
+    }
+}
+
+impl SchedulerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.jitter_secs > MAX_JITTER_SECS {
+            return Err(format!("scheduler.jitter_secs must be at most {}", MAX_JITTER_SECS));
+        }
+        for (name, job) in &self.jobs {
+            if !JOBS.iter().any(|(known, _)| known == name) {
+                let names: Vec<&str> = JOBS.iter().map(|(known, _)| *known).collect();
+                return Err(format!("Unknown scheduler job {:?} (expected one of {})", name, names.join(", ")));
+            }
+            if let Some(expr) = &job.cron {
+                Cron::parse(expr).map_err(|e| format!("scheduler.jobs.{}.cron: {}", name, e))?;
+            }
+            if job.jitter_secs.is_some_and(|secs| secs > MAX_JITTER_SECS) {
+                return Err(format!("scheduler.jobs.{}.jitter_secs must be at most {}", name, MAX_JITTER_SECS));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the job runs, its schedule and its jitter
+    fn job(&self, name: &str) -> (bool, String, u64) {
+        let job = self.jobs.get(name).cloned().unwrap_or_default();
+        let default = JOBS.iter().find(|(known, _)| *known == name).map_or("0 * * * *", |(_, cron)| cron);
+        (job.enabled, job.cron.unwrap_or_else(|| default.to_string()), job.jitter_secs.unwrap_or(self.jitter_secs))
+    }
+}
+
+/// A cron expression: minute, hour, day of month, month and day of week (0 or 7 is Sunday). Each
+/// field is `*`, a number, a range `a-b` or a list of these separated by commas, each optionally
+/// with a step (`*/15`, `8-18/2`). `@hourly`, `@daily`, `@weekly` and `@monthly` are shorthands.
+/// As in cron, when both the day of month and the day of week are restricted, either one matching
+/// is enough.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            expr => expr,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("expected 5 fields (minute hour day month weekday), got {:?}", expr));
+        };
+        let weekdays = field(weekday, 0, 7, "day of week")?;
+        let cron = Cron {
+            minutes: field(minute, 0, 59, "minute")?,
+            hours: field(hour, 0, 23, "hour")? as u32,
+            days: field(day, 1, 31, "day of month")? as u32,
+            months: field(month, 1, 12, "month")? as u16,
+            // 7 is another name for Sunday
+            weekdays: ((weekdays | (weekdays >> 7)) & 0x7f) as u8,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        };
+        let now = Local::now().naive_local();
+        if cron.next_naive(now).is_none() {
+            return Err(format!("{:?} never matches a date", expr));
+        }
+        Ok(cron)
+    }
+
+    /// First time after `after` that matches, to the minute
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut from = after.naive_local();
+        loop {
+            let next = self.next_naive(from)?;
+            // Times inside a daylight saving gap do not exist locally and are passed over
+            match Local.from_local_datetime(&next).earliest() {
+                Some(time) => return Some(time),
+                None => from = next,
+            }
+        }
+    }
+
+    fn next_naive(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = time + Duration::days(SEARCH_DAYS);
+        while time < limit {
+            let date = time.date();
+            if self.months & (1 << date.month()) == 0 {
+                let (year, month) = if date.month() == 12 { (date.year() + 1, 1) } else { (date.year(), date.month() + 1) };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_day(date) {
+                time = (date + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+/// Bit set of the values one cron field allows
+fn field(spec: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("invalid step {:?} in {}", step, name)),
+            },
+            None => (part, 1),
+        };
+        let number = |value: &str| match value.parse::<u32>() {
+            Ok(value) if (min..=max).contains(&value) => Ok(value),
+            _ => Err(format!("{} must be {}-{}, got {:?}", name, min, max, value)),
+        };
+        let (first, last) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((first, last)) => (number(first)?, number(last)?),
+                // `5/15` runs from 5 to the end, as in cron
+                None if step > 1 => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if first > last {
+            return Err(format!("{} range {:?} runs backwards", name, range));
+        }
+        for value in (first..=last).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+/// Starts jobs and keeps their status for `/scheduler/jobs`
+pub struct Scheduler {
+    config: SchedulerConfig,
+    jobs: RwLock<BTreeMap<&'static str, ScheduledJob>>,
+}
+
+impl Scheduler {
+    pub fn new(config: SchedulerConfig) -> Arc<Self> {
+        Arc::new(Scheduler { config, jobs: RwLock::new(BTreeMap::new()) })
+    }
+
+    /// Run `job` at the times of its schedule until `stop`. Returns the task, or None when
+    /// `[scheduler.jobs.<name>]` switches the job off.
+    pub async fn start<F, Fut>(self: &Arc<Self>, name: &'static str, stop: CancellationToken, job: F) -> Option<JoinHandle<()>>
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send,
+    {
+        let (enabled, expr, jitter_secs) = self.config.job(name);
+        // Validated with the rest of the config at startup
+        let cron = Cron::parse(&expr).ok()?;
+        self.jobs.write().await.insert(name, ScheduledJob {
+            name: name.to_string(),
+            cron: expr,
+            jitter_secs,
+            enabled,
+            running: false,
+            next_run: None,
+            skipped: 0,
+            runs: Vec::new(),
+        });
+        if !enabled {
+            return None;
+        }
+
+        let scheduler = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            while let Some(due) = cron.next_after(Local::now()) {
+                scheduler.update(name, |job| job.next_run = Some(due.with_timezone(&Utc))).await;
+                let jitter = std::time::Duration::from_secs(rand::thread_rng().gen_range(0..=jitter_secs));
+                let wait = (due - Local::now()).to_std().unwrap_or_default() + jitter;
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = stop.cancelled() => break,
+                }
+
+                scheduler.update(name, |job| job.running = true).await;
+                let started_at = Utc::now();
+                let clock = std::time::Instant::now();
+                let result = job().await;
+                let run = JobRun {
+                    started_at,
+                    duration_ms: clock.elapsed().as_millis() as u64,
+                    ok: result.is_ok(),
+                    error: result.err(),
+                };
+                if let Some(e) = &run.error {
+                    error!("Scheduled job {} failed: {}", name, e);
+                }
+
+                // Times that went by during the run are skipped, not made up for
+                let mut skipped = 0;
+                let mut missed = cron.next_after(due);
+                while let Some(time) = missed.filter(|time| *time <= Local::now()) {
+                    skipped += 1;
+                    missed = cron.next_after(time);
+                }
+                if skipped > 0 {
+                    warn!("⏭️ Scheduled job {} ran past {} of its start times, which were skipped", name, skipped);
+                }
+                scheduler
+                    .update(name, |job| {
+                        job.running = false;
+                        job.skipped += skipped;
+                        job.runs.insert(0, run);
+                        job.runs.truncate(HISTORY_LEN);
+                    })
+                    .await;
+            }
+            scheduler.update(name, |job| job.next_run = None).await;
+        }))
+    }
+
+    async fn update(&self, name: &str, change: impl FnOnce(&mut ScheduledJob)) {
+        if let Some(job) = self.jobs.write().await.get_mut(name) {
+            change(job);
+        }
+    }
+
+    /// Every job started, with its last runs newest first
+    pub async fn jobs(&self) -> Vec<ScheduledJob> {
+        self.jobs.read().await.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(expr: &str, after: &str) -> String {
+        Cron::parse(expr).unwrap().next_naive(at(after)).unwrap().format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    #[test]
+    fn next_run_follows_the_fields() {
+        assert_eq!(next("0 * * * *", "2026-03-02 10:00"), "2026-03-02 11:00");
+        assert_eq!(next("*/15 * * * *", "2026-03-02 10:07"), "2026-03-02 10:15");
+        assert_eq!(next("0 3 * * *", "2026-03-02 03:00"), "2026-03-03 03:00");
+        assert_eq!(next("30 8-18/2 * * 1-5", "2026-03-06 18:45"), "2026-03-09 08:30");
+        assert_eq!(next("0 0 1 */3 *", "2026-02-10 12:00"), "2026-04-01 00:00");
+        assert_eq!(next("@weekly", "2026-03-02 10:00"), "2026-03-08 00:00");
+        assert_eq!(next("0 0 * * 7", "2026-03-02 10:00"), "2026-03-08 00:00");
+    }
+
+    #[test]
+    fn day_of_month_or_weekday_when_both_are_set() {
+        // The 13th, or any Friday
+        assert_eq!(next("0 12 13 * 5", "2026-03-02 00:00"), "2026-03-06 12:00");
+        assert_eq!(next("0 12 13 * 5", "2026-03-06 13:00"), "2026-03-13 12:00");
+        assert_eq!(next("0 0 29 2 *", "2026-03-01 00:00"), "2028-02-29 00:00");
+    }
+
+    #[test]
+    fn bad_expressions_are_rejected() {
+        assert!(Cron::parse("0 * * *").is_err());
+        assert!(Cron::parse("60 * * * *").is_err());
+        assert!(Cron::parse("0 5-2 * * *").is_err());
+        assert!(Cron::parse("*/0 * * * *").is_err());
+        assert!(Cron::parse("0 0 31 2 *").is_err());
+        assert!(Cron::parse("@yearly").is_err());
+    }
+
+    #[test]
+    fn config_names_known_jobs_only() {
+        let mut config = SchedulerConfig::default();
+        config.jobs.insert("retention".to_string(), JobConfig { cron: Some("15 */2 * * *".to_string()), ..Default::default() });
+        assert!(config.validate().is_ok());
+        assert_eq!(config.job("retention"), (true, "15 */2 * * *".to_string(), 0));
+        assert_eq!(config.job("compaction").1, "0 3 * * *");
+
+        config.jobs.insert("backup".to_string(), JobConfig::default());
+        assert!(config.validate().is_err());
+    }
+}
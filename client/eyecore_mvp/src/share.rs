@@ -0,0 +1,302 @@
+//! Share bundles (`POST /share`): a short window of stored data packed for someone else, e.g. IT
+//! looking into a bug. The caller lists every snapshot field that goes in (dotted paths as in
+//! `/data/history`) and whether flags and logs (capture events and collection gaps) do; a request
+//! without `confirm: true` only previews what would be packed. Snapshots are metrics-only, as on the
+//! metrics endpoints, and session IDs become pseudonyms salted per bundle, so bundles cannot be
+//! linked to each other or to the device. The bundle is an AES-256 zip in `<device>/shares/` with a
+//! random password returned once; it is served at `/share/{id}` and removed when it expires.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use log::{info, warn};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::access;
+use crate::capabilities;
+use crate::models::{CaptureEvent, CollectionGap, FlagRecord, ShareBundle, ShareContents};
+use crate::storage::DataStorage;
+use crate::utils;
+
+/// Longest window one bundle may cover
+pub const MAX_WINDOW_MINUTES: i64 = 120;
+pub const DEFAULT_EXPIRY_HOURS: i64 = 24;
+pub const MAX_EXPIRY_HOURS: i64 = 7 * 24;
+const SHARE_DIR: &str = "shares";
+const PASSWORD_LENGTH: usize = 24;
+
+/// What the caller confirmed to go into a bundle
+pub struct ShareSelection {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub fields: Vec<String>,
+    pub flags: bool,
+    pub logs: bool,
+}
+
+impl ShareSelection {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.end <= self.start {
+            return Err("end must be after start".to_string());
+        }
+        if self.end - self.start > Duration::minutes(MAX_WINDOW_MINUTES) {
+            return Err(format!("A share covers at most {} minutes", MAX_WINDOW_MINUTES));
+        }
+        if self.fields.is_empty() {
+            return Err("fields must list the snapshot fields to share, e.g. [\"system_metrics.cpu_usage\", \"focus_metrics\"]".to_string());
+        }
+        for field in &self.fields {
+            let section = field.split('.').next().unwrap_or_default();
+            if !capabilities::DATA_CATEGORIES.contains(&section) || field.split('.').any(str::is_empty) {
+                return Err(format!("Unknown snapshot field {:?}", field));
+            }
+        }
+        Ok(())
+    }
+
+    /// Days the window touches
+    fn dates(&self) -> Vec<NaiveDate> {
+        self.start.date_naive().iter_days().take_while(|date| *date <= self.end.date_naive()).collect()
+    }
+}
+
+/// The anonymized data of a selection, to preview or pack
+pub struct ShareData {
+    pub contents: ShareContents,
+    snapshots: Vec<Value>,
+    flags: Vec<FlagRecord>,
+    capture_events: Vec<CaptureEvent>,
+    collection_gaps: Vec<CollectionGap>,
+}
+
+pub async fn collect(storage: &DataStorage, selection: &ShareSelection) -> std::io::Result<ShareData> {
+    let salt = hex::encode(rand::random::<[u8; 16]>());
+    let pseudonym = |session_id: &str| format!("session_{}", &utils::hash_data(format!("{}{}", salt, session_id).as_bytes())[..12]);
+    let in_window = |timestamp: DateTime<Utc>| timestamp >= selection.start && timestamp <= selection.end;
+    // "system_metrics.cpu_usage" -> "/system_metrics/cpu_usage"; timestamp is always kept
+    let pointers: Vec<String> = selection
+        .fields
+        .iter()
+        .map(|field| format!("/{}", field.replace('.', "/")))
+        .chain(std::iter::once("/timestamp".to_string()))
+        .collect();
+
+    let mut snapshots = Vec::new();
+    for date in selection.dates() {
+        for snapshot in storage.load_snapshots(date).await? {
+            if !in_window(snapshot.timestamp) {
+                continue;
+            }
+            let value = serde_json::to_value(access::metrics_only(&snapshot)).map_err(std::io::Error::other)?;
+            let mut projected = utils::project_json(&value, &pointers);
+            projected["session"] = json!(pseudonym(&snapshot.session_id));
+            snapshots.push(projected);
+        }
+    }
+
+    let mut flags = Vec::new();
+    if selection.flags {
+        flags = storage.load_flags(selection.start, selection.end).await?;
+        for flag in &mut flags {
+            flag.session_id = pseudonym(&flag.session_id);
+        }
+    }
+    let (mut capture_events, mut collection_gaps) = (Vec::new(), Vec::new());
+    if selection.logs {
+        for date in selection.dates() {
+            capture_events.extend(storage.load_capture_events(date).await?);
+        }
+        capture_events.retain(|event| event.started_at <= selection.end && event.ended_at >= selection.start);
+        collection_gaps = storage.load_collection_gaps().await?;
+        collection_gaps.retain(|gap| gap.start <= selection.end && gap.end >= selection.start);
+    }
+
+    Ok(ShareData {
+        contents: ShareContents {
+            start: selection.start,
+            end: selection.end,
+            fields: selection.fields.clone(),
+            snapshots: snapshots.len(),
+            flags: selection.flags.then_some(flags.len()),
+            capture_events: selection.logs.then_some(capture_events.len()),
+            collection_gaps: selection.logs.then_some(collection_gaps.len()),
+        },
+        snapshots,
+        flags,
+        capture_events,
+        collection_gaps,
+    })
+}
+
+/// Pack `data` into a password-protected zip that is removed after `expires_in`
+pub async fn create(storage: &DataStorage, data: ShareData, expires_in: Duration) -> std::io::Result<ShareBundle> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let password: String = rand::thread_rng().sample_iter(&Alphanumeric).take(PASSWORD_LENGTH).map(char::from).collect();
+    let expires_at = Utc::now() + expires_in;
+
+    let mut files = vec![
+        ("manifest.json", pretty(&json!({ "contents": data.contents, "expires_at": expires_at }))?),
+        ("snapshots.json", pretty(&data.snapshots)?),
+    ];
+    if data.contents.flags.is_some() {
+        files.push(("flags.json", pretty(&data.flags)?));
+    }
+    if data.contents.capture_events.is_some() {
+        files.push(("logs/capture_events.json", pretty(&data.capture_events)?));
+        files.push(("logs/collection_gaps.json", pretty(&data.collection_gaps)?));
+    }
+
+    let dir = storage.device_dir().join(SHARE_DIR);
+    let path = dir.join(format!("share_{}_{}.zip", id, expires_at.timestamp()));
+    let (written, key) = (path.clone(), password.clone());
+    tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&dir)?;
+        remove_expired_in(&dir);
+        write_zip(&written, &files, &key)
+    })
+    .await
+    .map_err(std::io::Error::other)??;
+    // Bundles outliving a restart are removed by the next sweep instead
+    tokio::spawn(async move {
+        tokio::time::sleep(expires_in.to_std().unwrap_or_default()).await;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => info!("🔗 Share bundle {:?} expired and was removed", path.file_name().unwrap_or_default()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("⚠️ Failed to remove expired share bundle {:?}: {}", path, e),
+        }
+    });
+
+    info!("🔗 Share bundle {} with {} snapshots, expires {}", id, data.contents.snapshots, expires_at);
+    Ok(ShareBundle { url: format!("/share/{}", id), id, password, expires_at, contents: data.contents })
+}
+
+/// The zip of bundle `id`, if it exists and has not expired
+pub async fn find(storage: &DataStorage, id: &str) -> std::io::Result<Option<PathBuf>> {
+    let dir = storage.device_dir().join(SHARE_DIR);
+    let prefix = format!("share_{}_", id);
+    tokio::task::spawn_blocking(move || {
+        remove_expired_in(&dir);
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return None;
+        };
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| {
+                path.extension().and_then(|s| s.to_str()) == Some("zip")
+                    && path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with(&prefix))
+            })
+    })
+    .await
+    .map_err(std::io::Error::other)
+}
+
+/// Remove bundles past their expiry, e.g. on startup
+pub async fn remove_expired(storage: &DataStorage) {
+    let dir = storage.device_dir().join(SHARE_DIR);
+    let _ = tokio::task::spawn_blocking(move || remove_expired_in(&dir)).await;
+}
+
+fn remove_expired_in(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let now = Utc::now().timestamp();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // share_<id>_<expiry>.zip, or .tmp while being written
+        let expiry = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.rsplit_once('_'))
+            .and_then(|(_, expiry)| expiry.parse::<i64>().ok());
+        if expiry.is_none_or(|expiry| expiry <= now) {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+fn pretty<T: Serialize>(value: &T) -> std::io::Result<Vec<u8>> {
+    serde_json::to_vec_pretty(value).map_err(std::io::Error::other)
+}
+
+/// Write the zip to a temporary file next to `path`, then move it into place
+fn write_zip(path: &Path, files: &[(&str, Vec<u8>)], password: &str) -> std::io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    let result = (|| {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&temporary)?);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .with_aes_encryption(zip::AesMode::Aes256, password);
+        for (name, contents) in files {
+            zip.start_file(*name, options).map_err(std::io::Error::other)?;
+            zip.write_all(contents)?;
+        }
+        zip.finish().map_err(std::io::Error::other)?;
+        std::fs::rename(&temporary, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temporary);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn selection(minutes: i64, fields: &[&str]) -> ShareSelection {
+        let start = Utc::now();
+        ShareSelection {
+            start,
+            end: start + Duration::minutes(minutes),
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+            flags: false,
+            logs: false,
+        }
+    }
+
+    #[test]
+    fn every_field_must_be_named() {
+        assert!(selection(30, &["system_metrics.cpu_usage", "focus_metrics"]).validate().is_ok());
+        assert!(selection(30, &[]).validate().is_err());
+        assert!(selection(30, &["session_id"]).validate().is_err());
+        assert!(selection(30, &["system_metrics."]).validate().is_err());
+        assert!(selection(MAX_WINDOW_MINUTES + 1, &["focus_metrics"]).validate().is_err());
+        assert!(selection(0, &["focus_metrics"]).validate().is_err());
+    }
+
+    #[test]
+    fn bundles_open_only_with_the_password() {
+        let dir = std::env::temp_dir().join(format!("eyecore_share_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("share_test_{}.zip", (Utc::now() + Duration::hours(1)).timestamp()));
+        write_zip(&path, &[("snapshots.json", b"[]".to_vec())], "secret").unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert!(archive.by_name_decrypt("snapshots.json", b"wrong").is_err());
+        let mut contents = String::new();
+        archive.by_name_decrypt("snapshots.json", b"secret").unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "[]");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn expired_bundles_are_swept() {
+        let dir = std::env::temp_dir().join(format!("eyecore_share_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let now = Utc::now().timestamp();
+        let expired = dir.join(format!("share_a_{}.zip", now - 1));
+        let current = dir.join(format!("share_b_{}.zip", now + 3600));
+        std::fs::write(&expired, b"").unwrap();
+        std::fs::write(&current, b"").unwrap();
+
+        remove_expired_in(&dir);
+        assert!(!expired.exists());
+        assert!(current.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+//! Graceful shutdown: on Ctrl-C or SIGTERM, or before restarting into an update, the collection loops
+//! stop at their next tick, the audio cleaning queue is worked off, the upload socket is closed with a
+//! close frame and snapshots still only held in memory are written out. A second signal exits right
+//! away.
+
+use log::{error, info, warn};
+use std::sync::Mutex;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time::{timeout_at, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::data_collector::DataCollector;
+use crate::storage::DataStorage;
+
+/// How long stopped tasks get to finish their current run
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct Shutdown {
+    token: CancellationToken,
+    tasks: Mutex<Vec<(&'static str, JoinHandle<()>)>>,
+}
+
+impl Shutdown {
+    /// Start listening for shutdown signals
+    pub fn listen() -> Self {
+        let token = CancellationToken::new();
+        let signalled = token.clone();
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            info!("🛑 Shutting down, finishing current work (signal again to exit immediately)");
+            signalled.cancel();
+            wait_for_signal().await;
+            warn!("⚠️ Second signal, exiting without flushing");
+            std::process::exit(130);
+        });
+        Shutdown { token, tasks: Mutex::new(Vec::new()) }
+    }
+
+    /// Cancelled once shutdown starts; loops check it between runs
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Wait for `task` before exiting
+    pub fn track(&self, name: &'static str, task: JoinHandle<()>) {
+        self.tasks.lock().unwrap().push((name, task));
+    }
+
+    /// Wait for the tracked tasks, then save the in-memory history snapshots that never reached
+    /// storage
+    pub async fn finish(&self, collector: &RwLock<DataCollector>, storage: &DataStorage) {
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        for (name, task) in tasks {
+            match timeout_at(deadline, task).await {
+                Ok(Ok(())) => info!("✓ {} stopped", name),
+                Ok(Err(e)) => error!("{} task failed during shutdown: {}", name, e),
+                Err(_) => warn!("⚠️ {} did not stop within {:?}, abandoning it", name, SHUTDOWN_TIMEOUT),
+            }
+        }
+
+        let history = collector.read().await.get_history(usize::MAX);
+        let mut flushed = 0;
+        for data in history {
+            if storage.has_data_snapshot(&data) {
+                continue;
+            }
+            match storage.save_data_snapshot(&data).await {
+                Ok(_) => flushed += 1,
+                Err(e) => error!("Failed to flush snapshot {}: {}", data.timestamp, e),
+            }
+        }
+        if flushed > 0 {
+            info!("💾 Flushed {} unsaved snapshots", flushed);
+        }
+        info!("👋 EyeCore stopped");
+    }
+}
+
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                error!("Cannot listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
@@ -0,0 +1,260 @@
+//! Soak benchmark (`eyecore_mvp bench-soak`): runs the collection and storage pipeline for hours at a
+//! multiple of the real snapshot rate and watches resident memory, open file descriptors, the
+//! collector's in-memory buffers and write throughput. The run fails when memory or descriptors keep
+//! climbing after the warm-up, or a buffer is still growing at the end, which is how an unbounded
+//! queue or cache shows up.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use sysinfo::System;
+
+use crate::data_collector::DataCollector;
+use crate::storage::DataStorage;
+
+/// Real-time interval of snapshots and screen/keyboard collection
+const REAL_INTERVAL: Duration = Duration::from_secs(5);
+/// Samples from the first quarter of the run are not compared; buffers and caches are still filling
+const WARM_UP_FRACTION: f64 = 0.25;
+/// Buffers still growing during the last third of the run are reported as unbounded
+const FINAL_FRACTION: f64 = 1.0 / 3.0;
+
+pub struct SoakOptions {
+    pub duration: Duration,
+    pub speedup: u32,
+    pub sample_every: Duration,
+    pub data_dir: PathBuf,
+    pub keep_data: bool,
+    pub max_memory_growth_mb: f64,
+    pub max_fd_growth: usize,
+    pub report: Option<PathBuf>,
+}
+
+/// One measurement during the run
+#[derive(Debug, Clone, Serialize)]
+struct SoakSample {
+    at: DateTime<Utc>,
+    elapsed_secs: f64,
+    cycles: u64,
+    rss_mb: Option<f64>,
+    open_fds: Option<usize>,
+    files_written: u64,
+    bytes_written: u64,
+    buffers: BTreeMap<&'static str, usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct SoakReport {
+    duration_secs: f64,
+    speedup: u32,
+    cycles: u64,
+    cycles_per_sec: f64,
+    target_cycles_per_sec: f64,
+    write_mb_per_sec: f64,
+    failures: Vec<String>,
+    samples: Vec<SoakSample>,
+}
+
+/// Files and bytes written by the pipeline so far
+#[derive(Default)]
+struct WriteCounter {
+    files: u64,
+    bytes: u64,
+}
+
+impl WriteCounter {
+    fn record(&mut self, written: std::io::Result<PathBuf>) -> Result<(), String> {
+        let path = written.map_err(|e| format!("Write failed: {}", e))?;
+        self.files += 1;
+        self.bytes += std::fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        Ok(())
+    }
+}
+
+/// Process memory and descriptors; None where the platform does not expose them
+struct ProcessProbe {
+    system: System,
+    pid: Option<sysinfo::Pid>,
+}
+
+impl ProcessProbe {
+    fn new() -> Self {
+        ProcessProbe { system: System::new(), pid: sysinfo::get_current_pid().ok() }
+    }
+
+    fn rss_mb(&mut self) -> Option<f64> {
+        let pid = self.pid?;
+        self.system.refresh_process(pid);
+        self.system.process(pid).map(|process| process.memory() as f64 / 1e6)
+    }
+
+    fn open_fds(&self) -> Option<usize> {
+        let dir = if cfg!(target_os = "linux") {
+            "/proc/self/fd"
+        } else if cfg!(target_os = "macos") {
+            "/dev/fd"
+        } else {
+            return None;
+        };
+        // The directory handle used for listing shows up in the listing itself
+        std::fs::read_dir(dir).ok().map(|entries| entries.count().saturating_sub(1))
+    }
+}
+
+/// Run the soak and print its verdict; returns false when it detected a leak
+pub async fn run(options: SoakOptions) -> Result<bool, String> {
+    let interval = REAL_INTERVAL / options.speedup;
+    let storage = DataStorage::new(&options.data_dir.to_string_lossy(), "soak");
+    storage.initialize().await.map_err(|e| format!("Failed to prepare {:?}: {}", options.data_dir, e))?;
+    if !cfg!(feature = "simulated-input") {
+        log::warn!("⚠️ Built without simulated-input, the soak reads real input hooks");
+    }
+    println!(
+        "Soak: {:?} at {}x ({:?} per cycle), writing to {}",
+        options.duration,
+        options.speedup,
+        interval,
+        options.data_dir.display()
+    );
+
+    let mut collector = DataCollector::new();
+    let mut probe = ProcessProbe::new();
+    let mut writes = WriteCounter::default();
+    let mut samples = Vec::new();
+    let mut cycles = 0u64;
+    let started = Instant::now();
+    let mut next_sample = started;
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    while started.elapsed() < options.duration {
+        ticker.tick().await;
+
+        // The same steps as the snapshot and screen/keyboard loops of the service
+        collector.collect_all().await;
+        if let Some(data) = collector.get_latest_data() {
+            for event in data.system_events.iter().flat_map(|events| &events.capture_events) {
+                writes.record(storage.save_capture_event(event).await)?;
+            }
+            writes.record(storage.save_data_snapshot(&data).await)?;
+            writes.record(storage.save_session_log(&data).await)?;
+        }
+        let enhanced = collector.collect_enhanced_screen_keyboard_data();
+        writes.record(storage.save_enhanced_screen_keyboard_data(&enhanced).await)?;
+        cycles += 1;
+
+        if Instant::now() >= next_sample {
+            next_sample += options.sample_every;
+            let sample = SoakSample {
+                at: Utc::now(),
+                elapsed_secs: started.elapsed().as_secs_f64(),
+                cycles,
+                rss_mb: probe.rss_mb(),
+                open_fds: probe.open_fds(),
+                files_written: writes.files,
+                bytes_written: writes.bytes,
+                buffers: collector.buffer_lengths(),
+            };
+            println!(
+                "{:>8.0}s {:>9} cycles  rss {:>8}  fds {:>5}  {:>9} files  {:>10.1} MB written",
+                sample.elapsed_secs,
+                sample.cycles,
+                sample.rss_mb.map_or("n/a".to_string(), |mb| format!("{:.1} MB", mb)),
+                sample.open_fds.map_or("n/a".to_string(), |fds| fds.to_string()),
+                sample.files_written,
+                sample.bytes_written as f64 / 1e6
+            );
+            samples.push(sample);
+        }
+    }
+
+    let elapsed = started.elapsed().as_secs_f64();
+    let failures = check(&samples, &options);
+    let report = SoakReport {
+        duration_secs: elapsed,
+        speedup: options.speedup,
+        cycles,
+        cycles_per_sec: cycles as f64 / elapsed,
+        target_cycles_per_sec: 1.0 / interval.as_secs_f64(),
+        write_mb_per_sec: writes.bytes as f64 / 1e6 / elapsed,
+        failures,
+        samples,
+    };
+
+    println!(
+        "{} cycles in {:.0}s: {:.1}/s (target {:.1}/s), {:.2} MB/s written",
+        report.cycles, report.duration_secs, report.cycles_per_sec, report.target_cycles_per_sec, report.write_mb_per_sec
+    );
+    if report.cycles_per_sec < report.target_cycles_per_sec * 0.9 {
+        println!("⚠ The pipeline could not keep up with {}x; growth checks cover fewer cycles than intended", options.speedup);
+    }
+    for failure in &report.failures {
+        println!("✗ {}", failure);
+    }
+    if report.failures.is_empty() {
+        println!("✓ No unbounded growth detected");
+    }
+
+    if let Some(path) = &options.report {
+        let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        println!("Report written to {}", path.display());
+    }
+    if !options.keep_data {
+        remove_data(&options.data_dir);
+    }
+    Ok(report.failures.is_empty())
+}
+
+/// Growth that outlasts the warm-up
+fn check(samples: &[SoakSample], options: &SoakOptions) -> Vec<String> {
+    let mut failures = Vec::new();
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return vec!["No samples were taken; run longer than the sample interval".to_string()];
+    };
+    let at_fraction = |fraction: f64| {
+        let cutoff = first.elapsed_secs + (last.elapsed_secs - first.elapsed_secs) * fraction;
+        samples.iter().find(|sample| sample.elapsed_secs >= cutoff).unwrap_or(last)
+    };
+    let warm = at_fraction(WARM_UP_FRACTION);
+    let final_start = at_fraction(1.0 - FINAL_FRACTION);
+    if std::ptr::eq(warm, last) {
+        return vec!["Too few samples after the warm-up; run longer or sample more often".to_string()];
+    }
+
+    if let (Some(warm_rss), Some(last_rss)) = (warm.rss_mb, last.rss_mb) {
+        let growth = last_rss - warm_rss;
+        if growth > options.max_memory_growth_mb {
+            failures.push(format!(
+                "Resident memory grew {:.1} MB after the warm-up ({:.1} -> {:.1} MB, limit {:.0} MB)",
+                growth, warm_rss, last_rss, options.max_memory_growth_mb
+            ));
+        }
+    }
+    if let (Some(warm_fds), Some(last_fds)) = (warm.open_fds, last.open_fds) {
+        if last_fds > warm_fds + options.max_fd_growth {
+            failures.push(format!(
+                "Open file descriptors grew from {} to {} after the warm-up (limit +{})",
+                warm_fds, last_fds, options.max_fd_growth
+            ));
+        }
+    }
+    for (buffer, &length) in &last.buffers {
+        let earlier = final_start.buffers.get(buffer).copied().unwrap_or(0);
+        if length > earlier && !std::ptr::eq(final_start, last) {
+            failures.push(format!(
+                "Buffer {} was still growing at the end ({} -> {} entries over the last third)",
+                buffer, earlier, length
+            ));
+        }
+    }
+    failures
+}
+
+fn remove_data(data_dir: &Path) {
+    if let Err(e) = std::fs::remove_dir_all(data_dir) {
+        log::warn!("Failed to remove soak data {:?}: {}", data_dir, e);
+    }
+}
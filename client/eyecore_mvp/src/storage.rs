@@ -1,59 +1,338 @@
 use crate::models::*;
 use std::path::{Path, PathBuf};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 use serde_json::{json, to_string_pretty};
-use log::info;
-use chrono::Utc;
+use log::{info, error, warn};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
 
+pub mod compaction;
+pub mod encryption;
+pub mod metrics_mirror;
+pub mod retention;
+pub mod snapshot_log;
+pub mod sqlite;
+
+use encryption::Keyring;
+use snapshot_log::SnapshotLog;
+use sqlite::{Conflict, SqliteStore};
+use crate::redaction::Redactor;
+
+// Prefix of timeslot filenames: "2025-10-26_13-35-36-104_495d9930.json"
+const SNAPSHOT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S-%3f";
+const SNAPSHOT_TIMESTAMP_LEN: usize = 23;
+
+// zstd level of compressed snapshots and session logs; higher levels cost far more CPU for little gain
+pub(crate) const ZSTD_LEVEL: i32 = 3;
+
+// Append-only log of content-grade API accesses, one JSON object per line
+const CONTENT_ACCESS_LOG: &str = "content_access.jsonl";
+
+// Append-only log of idle sessions, one JSON object per line
+const IDLE_SESSION_LOG: &str = "idle_sessions.jsonl";
+
+// Append-only log of microphone and camera capture periods, one JSON object per line
+const CAPTURE_EVENT_LOG: &str = "capture_events.jsonl";
+
+// Snapshots waiting for the upload server, directly under the device directory
+pub const UPLOAD_QUEUE_DIR: &str = "upload_queue";
+
+// Data categories stored inside each day directory
+const CATEGORIES: &[&str] = &[
+    "timeslots",
+    "raw_audio",
+    "transcriptions",
+    "anomalies",
+    "session_logs",
+    "hourly_snapshots",
+    "daily_reports",
+    "weekly_reports",
+    "screen-and-keyboard",  // NEW: Enhanced screen and keyboard data
+    "gaps",
+    "heart_rate",
+    "capture_events",
+    "audit",
+    "bookmarks",
+    "notes",
+    "metrics",
+    "idle",
+    "incidents",
+    "input_trace",
+    "rollups",
+];
+
+/// Stores data sharded per device and day: data/<device>/<YYYY>/<MM>/<DD>/<category>/<file>
 pub struct DataStorage {
     data_dir: PathBuf,
+    device_id: String,
+    // Serializes read-modify-write of audio sidecars (cleaning and transcription finish independently)
+    audio_metadata_lock: tokio::sync::Mutex<()>,
+    // Holds snapshots, anomalies, transcriptions and flags instead of their JSON files when attached
+    sqlite: Option<SqliteStore>,
+    // Write snapshots and session logs as zstd-compressed `.json.zst` files
+    compress: bool,
+    // Append snapshots to a daily NDJSON log instead of writing a file each
+    snapshot_log: Option<SnapshotLog>,
+    // Keys for sealed (`.enc`) files; new files are sealed only when `encrypt` is set
+    keyring: Option<std::sync::RwLock<Keyring>>,
+    encrypt: bool,
+    // One key rotation at a time
+    rotation_lock: tokio::sync::Mutex<()>,
+    // Compaction and module expiry both rewrite timeslot archives
+    archive_lock: tokio::sync::Mutex<()>,
+    // Masks personal data left in snapshots and screen and keyboard records before they are written
+    redactor: std::sync::Arc<Redactor>,
+    // Also append each snapshot's numbers to the unencrypted hourly metrics-only files
+    metrics_mirror: bool,
 }
 
 impl DataStorage {
-    pub fn new(data_dir: &str) -> Self {
+    pub fn new(data_dir: &str, device_id: &str) -> Self {
         DataStorage {
             data_dir: PathBuf::from(data_dir),
+            device_id: device_id.to_string(),
+            audio_metadata_lock: tokio::sync::Mutex::new(()),
+            sqlite: None,
+            compress: false,
+            snapshot_log: None,
+            keyring: None,
+            encrypt: false,
+            rotation_lock: tokio::sync::Mutex::new(()),
+            archive_lock: tokio::sync::Mutex::new(()),
+            redactor: std::sync::Arc::new(Redactor::default()),
+            metrics_mirror: false,
         }
     }
 
+    /// Redact with `redactor` (`[redaction]`) instead of every built-in rule
+    pub fn set_redactor(&mut self, redactor: std::sync::Arc<Redactor>) {
+        self.redactor = redactor;
+    }
+
+    /// Mirror the numbers of every snapshot saved from now on into `metrics/`, unencrypted
+    /// (`metrics_mirror = true`)
+    pub fn enable_metrics_mirror(&mut self) {
+        self.metrics_mirror = true;
+    }
+
+    /// Write snapshots and session logs zstd-compressed from now on (`compression = "zstd"`).
+    /// Readers handle both kinds of files, so existing data stays readable.
+    pub fn enable_compression(&mut self) {
+        self.compress = true;
+    }
+
+    /// Append snapshots to `timeslots/snapshots_<date>_<part>.ndjson` from now on
+    /// (`snapshot_format = "ndjson"`), starting a new part at `max_bytes`. Snapshot files already
+    /// written stay readable. The log is not compressed, whatever `compression` says.
+    pub fn enable_snapshot_log(&mut self, max_bytes: u64) {
+        self.snapshot_log = Some(SnapshotLog::new(max_bytes));
+    }
+
+    /// Read sealed files with `keyring`, and seal snapshots, session logs, screen and keyboard data,
+    /// transcriptions and audio with it from now on if `encrypt` is set (`encryption =
+    /// "xchacha20poly1305"`). Files written unencrypted stay readable.
+    pub fn attach_keyring(&mut self, keyring: Keyring, encrypt: bool) {
+        self.keyring = Some(std::sync::RwLock::new(keyring));
+        self.encrypt = encrypt;
+    }
+
+    /// `filename` and `contents` as stored: sealed, with the `.enc` suffix, when encryption is on
+    fn seal(&self, filename: String, contents: Vec<u8>) -> std::io::Result<(String, Vec<u8>)> {
+        match self.keyring.as_ref().filter(|_| self.encrypt) {
+            Some(keyring) => Ok((
+                format!("{}{}", filename, encryption::ENCRYPTED_SUFFIX),
+                keyring.read().unwrap().seal(&contents)?,
+            )),
+            None => Ok((filename, contents)),
+        }
+    }
+
+    /// Contents of a stored file, decrypted if it is sealed and decompressed if it is a `.json.zst` file
+    pub async fn read_stored(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        let bytes = fs::read(path).await?;
+        if !encryption::is_encrypted(path) {
+            return crate::bulk_read::decode(path, bytes);
+        }
+        let Some(keyring) = &self.keyring else {
+            return Err(std::io::Error::other(format!("{:?} is encrypted but no encryption keyring is configured", path)));
+        };
+        let plaintext = keyring.read().unwrap().open(&bytes)?;
+        crate::bulk_read::decode(&encryption::plain_path(path), plaintext)
+    }
+
+    /// `value` in the format `path` was stored in: compressed and sealed as its name says
+    fn encode_as(&self, path: &Path, value: &serde_json::Value) -> std::io::Result<Vec<u8>> {
+        let content = if crate::bulk_read::is_compressed(&encryption::plain_path(path)) {
+            zstd::encode_all(serde_json::to_vec(value)?.as_slice(), ZSTD_LEVEL)?
+        } else {
+            to_string_pretty(value)?.into_bytes()
+        };
+        if !encryption::is_encrypted(path) {
+            return Ok(content);
+        }
+        match &self.keyring {
+            Some(keyring) => keyring.read().unwrap().seal(&content),
+            None => Err(std::io::Error::other(format!("{:?} is encrypted but no encryption keyring is configured", path))),
+        }
+    }
+
+    /// File name suffix and contents of a snapshot or session log
+    fn encode(&self, value: &serde_json::Value) -> std::io::Result<(&'static str, Vec<u8>)> {
+        if self.compress {
+            let compressed = zstd::encode_all(serde_json::to_vec(value)?.as_slice(), ZSTD_LEVEL)?;
+            Ok((crate::bulk_read::COMPRESSED_SUFFIX, compressed))
+        } else {
+            Ok(("", to_string_pretty(value)?.into_bytes()))
+        }
+    }
+
+    /// Keep snapshots, anomalies, transcriptions and flags in `<device>/eyecore.db` from now on
+    /// (`storage_backend = "sqlite"`). JSON files already written are no longer read; import them
+    /// with `migrate-storage sqlite`.
+    pub fn attach_sqlite(&mut self) -> std::io::Result<()> {
+        let store = SqliteStore::open(&self.device_dir().join("eyecore.db"))?;
+        info!("✓ SQLite storage at: {:?}", store.path());
+        self.sqlite = Some(store);
+        Ok(())
+    }
+
     /// Initialize data directory structure
     pub async fn initialize(&self) -> std::io::Result<()> {
-        // Create main data directory
-        fs::create_dir_all(&self.data_dir).await?;
-        
-        // Create subdirectories for different data types
-        let subdirs = vec![
-            "timeslots",
-            "raw_audio",
-            "transcriptions",
-            "anomalies",
-            "session_logs",
-            "hourly_snapshots",
-            "daily_reports",
-            "screen-and-keyboard",  // NEW: Enhanced screen and keyboard data
-        ];
-
-        for subdir in subdirs {
-            fs::create_dir_all(self.data_dir.join(subdir)).await?;
-        }
-
-        info!("✓ Data storage initialized at: {:?}", self.data_dir);
+        // Day directories are created on demand as data arrives
+        fs::create_dir_all(self.device_dir()).await?;
+
+        info!("✓ Data storage initialized at: {:?}", self.device_dir());
         Ok(())
     }
 
-    /// Save complete EyeCore data snapshot with timeslot info
+    /// Root of this device's shard tree
+    pub fn device_dir(&self) -> PathBuf {
+        self.data_dir.join(&self.device_id)
+    }
+
+    /// Directory holding `category` files for a given day
+    pub fn day_dir(&self, date: NaiveDate, category: &str) -> PathBuf {
+        self.device_dir()
+            .join(format!("{:04}", date.year()))
+            .join(format!("{:02}", date.month()))
+            .join(format!("{:02}", date.day()))
+            .join(category)
+    }
+
+    /// Day directory for `timestamp`, created if missing
+    async fn prepare_dir(&self, timestamp: DateTime<Utc>, category: &str) -> std::io::Result<PathBuf> {
+        let dir = self.day_dir(timestamp.date_naive(), category);
+        fs::create_dir_all(&dir).await?;
+        Ok(dir)
+    }
+
+    /// All days that have stored data, oldest first
+    pub async fn list_dates(&self) -> std::io::Result<Vec<NaiveDate>> {
+        let mut dates = Vec::new();
+
+        for year_dir in numbered_subdirs(&self.device_dir()).await? {
+            for month_dir in numbered_subdirs(&year_dir).await? {
+                for day_dir in numbered_subdirs(&month_dir).await? {
+                    let parts: Vec<i32> = [&year_dir, &month_dir, &day_dir]
+                        .iter()
+                        .filter_map(|dir| dir.file_name()?.to_str()?.parse().ok())
+                        .collect();
+                    if let [year, month, day] = parts[..] {
+                        if let Some(date) = NaiveDate::from_ymd_opt(year, month as u32, day as u32) {
+                            dates.push(date);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(store) = &self.sqlite {
+            dates.extend(store.dates().await?);
+        }
+        dates.sort();
+        dates.dedup();
+        Ok(dates)
+    }
+
+    /// Files of one category, either for a single day or across all days (oldest first)
+    pub async fn list_category_files(&self, category: &str, date: Option<NaiveDate>) -> std::io::Result<Vec<PathBuf>> {
+        let dates = match date {
+            Some(date) => vec![date],
+            None => self.list_dates().await?,
+        };
+
+        let mut files = Vec::new();
+        for date in dates {
+            let dir = self.day_dir(date, category);
+            if !dir.exists() {
+                continue;
+            }
+            let mut entries = fs::read_dir(&dir).await?;
+            let mut day_files = Vec::new();
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_file() {
+                    day_files.push(entry.path());
+                }
+            }
+            day_files.sort();
+            files.extend(day_files);
+        }
+
+        Ok(files)
+    }
+
+    /// Whether `data` has already been written by `save_data_snapshot`
+    pub fn has_data_snapshot(&self, data: &EyeCoreData) -> bool {
+        if let Some(store) = &self.sqlite {
+            return store.contains_snapshot(&snapshot_filename(data));
+        }
+        if self.snapshot_log.as_ref().is_some_and(|log| log.contains(data)) {
+            return true;
+        }
+        let date = data.timestamp.date_naive();
+        let dir = self.day_dir(date, "timeslots");
+        let filename = snapshot_filename(data);
+        let path = dir.join(&filename);
+        let compressed = compressed_path(&path);
+        [encryption::encrypted_path(&path), encryption::encrypted_path(&compressed), path, compressed]
+            .iter()
+            .any(|path| path.exists())
+            || crate::bulk_read::SnapshotArchive::open(&compaction::archive_path(&dir, date))
+                .is_ok_and(|archive| archive.contains(&filename))
+    }
+
+    /// Save complete EyeCore data snapshot with timeslot info, and its numbers to the metrics-only
+    /// mirror when enabled
     pub async fn save_data_snapshot(&self, data: &EyeCoreData) -> std::io::Result<PathBuf> {
-        let timestamp = data.timestamp.format("%Y-%m-%d_%H-%M-%S-%3f");
-        
-        // Save to timeslots directory
-        let filename = format!("{}_{}.json", timestamp, &data.session_id[0..8]);
-        let filepath = self.data_dir.join("timeslots").join(&filename);
+        let path = self.write_data_snapshot(data).await?;
+        if self.metrics_mirror {
+            let mirrored = match self.prepare_dir(data.timestamp, metrics_mirror::CATEGORY).await {
+                Ok(dir) => metrics_mirror::append(&dir, data).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = mirrored {
+                error!("Failed to mirror snapshot metrics: {}", e);
+            }
+        }
+        Ok(path)
+    }
+
+    async fn write_data_snapshot(&self, data: &EyeCoreData) -> std::io::Result<PathBuf> {
+        let data = &*self.redactor.redacted_snapshot(data);
+        let filename = snapshot_filename(data);
+        if let Some(store) = &self.sqlite {
+            store.insert_snapshot(&filename, data, Conflict::Fail).await?;
+            info!("✓ Data snapshot saved: {} (SQLite)", filename);
+            return Ok(store.path().to_path_buf());
+        }
+        let dir = self.prepare_dir(data.timestamp, "timeslots").await?;
 
         // Add metadata about what data was collected
         let full_data = json!({
             "metadata": {
                 "session_id": &data.session_id,
                 "timestamp": data.timestamp.to_rfc3339(),
+                "sequence": data.sequence,
                 "data_types_available": {
                     "system_metrics": true,
                     "process_data": true,
@@ -69,26 +348,198 @@ impl DataStorage {
                     "mouse_dynamics": data.mouse_dynamics.is_some(),
                     "network_activity_metadata": data.network_activity_metadata.is_some(),
                 },
+                "redactions": &data.redactions,
                 "saved_at": Utc::now().to_rfc3339(),
             },
             "data": data,
         });
 
-        let json_str = to_string_pretty(&full_data)?;
-        fs::write(&filepath, json_str).await?;
+        if let Some(log) = &self.snapshot_log {
+            let path = log.append(&dir, data.timestamp.date_naive(), data, serde_json::to_vec(&full_data)?).await?;
+            info!("✓ Data snapshot appended: {}", filename);
+            return Ok(path);
+        }
+
+        // Never replace an existing snapshot, even if another process picked the same name
+        let (suffix, contents) = self.encode(&full_data)?;
+        let (filename, contents) = self.seal(format!("{}{}", filename, suffix), contents)?;
+        let filepath = dir.join(&filename);
+        let mut file = fs::OpenOptions::new().write(true).create_new(true).open(&filepath).await?;
+        file.write_all(&contents).await?;
+        file.flush().await?;
         
         info!("✓ Data snapshot saved: {}", filename);
         Ok(filepath)
     }
 
-    /// Save voice/audio data
+    /// Remove one opt-in module's data from `date`: its section of every stored snapshot is cleared
+    /// and the categories holding only its data (e.g. raw audio) are deleted. Returns the number of
+    /// files changed.
+    pub async fn expire_module_data(&self, field: &str, categories: &[&str], date: NaiveDate) -> std::io::Result<usize> {
+        let mut changed = 0;
+        // Compaction would move the files listed here into an archive
+        let _archives = self.archive_lock.lock().await;
+
+        for path in self.list_category_files("timeslots", Some(date)).await? {
+            if crate::bulk_read::is_snapshot_log(&path) {
+                changed += self.expire_in_snapshot_log(&path, field).await?;
+                continue;
+            }
+            if crate::bulk_read::is_snapshot_archive(&path) {
+                let field = field.to_string();
+                changed += tokio::task::spawn_blocking(move || compaction::expire_in_archive(&path, &field))
+                    .await
+                    .map_err(std::io::Error::other)??;
+                continue;
+            }
+            let content = self.read_stored(&path).await?;
+            let mut snapshot: serde_json::Value = match serde_json::from_slice(&content) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    error!("Skipping unreadable snapshot {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            if snapshot["data"][field].is_null() {
+                continue;
+            }
+            snapshot["data"][field] = serde_json::Value::Null;
+            snapshot["metadata"]["data_types_available"][field] = json!(false);
+            // Rewritten in the format it was stored in, and replaced rather than truncated, as bulk
+            // scans may have a past day's file mapped
+            let temporary = path.with_extension("tmp");
+            fs::write(&temporary, self.encode_as(&path, &snapshot)?).await?;
+            fs::rename(&temporary, &path).await?;
+            changed += 1;
+        }
+        if let Some(store) = &self.sqlite {
+            changed += store.expire_module_data(field, date, categories.contains(&"transcriptions")).await?;
+        }
+
+        for category in categories {
+            for path in self.list_category_files(category, Some(date)).await? {
+                fs::remove_file(&path).await?;
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Clear `field` in every line of a snapshot log, replacing the log atomically; returns 1 if
+    /// anything changed. Appending waits meanwhile. Lines that fail to parse are kept as they are.
+    async fn expire_in_snapshot_log(&self, path: &Path, field: &str) -> std::io::Result<usize> {
+        let _paused = match &self.snapshot_log {
+            Some(log) => Some(log.pause().await),
+            None => None,
+        };
+        let content = fs::read(path).await?;
+        let mut rewritten = Vec::with_capacity(content.len());
+        let mut changed = false;
+        for line in content.split_inclusive(|byte| *byte == b'\n') {
+            let mut snapshot: serde_json::Value = match serde_json::from_slice(line) {
+                Ok(snapshot) => snapshot,
+                Err(_) => {
+                    rewritten.extend_from_slice(line);
+                    continue;
+                }
+            };
+            if !snapshot["data"][field].is_null() {
+                snapshot["data"][field] = serde_json::Value::Null;
+                snapshot["metadata"]["data_types_available"][field] = json!(false);
+                changed = true;
+            }
+            rewritten.extend(serde_json::to_vec(&snapshot)?);
+            rewritten.push(b'\n');
+        }
+        if !changed {
+            return Ok(0);
+        }
+        let temporary = path.with_extension("ndjson.tmp");
+        fs::write(&temporary, rewritten).await?;
+        fs::rename(&temporary, path).await?;
+        Ok(1)
+    }
+
     pub async fn save_audio(&self, audio_bytes: &[u8], session_id: &str) -> std::io::Result<PathBuf> {
-        let timestamp = Utc::now().format("%Y-%m-%d_%H-%M-%S-%3f");
-        let filename = format!("{}_{}.wav", timestamp, &session_id[0..8]);
-        let filepath = self.data_dir.join("raw_audio").join(&filename);
+        let now = Utc::now();
+        let timestamp = now.format("%Y-%m-%d_%H-%M-%S-%3f");
+        let (filename, contents) = self.seal(format!("{}_{}.wav", timestamp, &session_id[0..8]), audio_bytes.to_vec())?;
+        let filepath = self.prepare_dir(now, "raw_audio").await?.join(&filename);
 
-        fs::write(&filepath, audio_bytes).await?;
+        fs::write(&filepath, contents).await?;
         info!("✓ Audio saved: {}", filename);
+        
+        // Describe the recording so consumers don't have to probe the WAV
+        let (spec, frames) = match hound::WavReader::new(std::io::Cursor::new(audio_bytes)) {
+            Ok(reader) => (reader.spec(), reader.duration()),
+            Err(e) => {
+                warn!("No metadata for {}: unreadable WAV header ({})", filename, e);
+                return Ok(filepath);
+            }
+        };
+        let metadata = AudioSegmentMetadata {
+            file: filename,
+            session_id: session_id.to_string(),
+            recorded_at: now,
+            duration_ms: frames as u64 * 1000 / spec.sample_rate.max(1) as u64,
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            bits_per_sample: spec.bits_per_sample,
+            sha256: crate::utils::hash_data(audio_bytes),
+            voice_segments_ms: Vec::new(),
+            estimated_speakers: None,
+            cleaned_file: None,
+            cleaning_report: None,
+            transcription_status: TranscriptionStatus::Pending,
+            transcription_file: None,
+        };
+        fs::write(audio_metadata_path(&filepath), to_string_pretty(&metadata)?).await?;
+        Ok(filepath)
+    }
+    
+    /// Apply `update` to the sidecar of a saved audio file
+    pub async fn update_audio_metadata(
+        &self,
+        audio_path: &Path,
+        update: impl FnOnce(&mut AudioSegmentMetadata),
+    ) -> std::io::Result<()> {
+        let _guard = self.audio_metadata_lock.lock().await;
+        let path = audio_metadata_path(audio_path);
+        let mut metadata: AudioSegmentMetadata = serde_json::from_str(&fs::read_to_string(&path).await?)?;
+        update(&mut metadata);
+        fs::write(&path, to_string_pretty(&metadata)?).await
+    }
+    
+    /// Sidecar metadata of the audio recorded on `date`, oldest first
+    pub async fn load_audio_metadata(&self, date: NaiveDate) -> std::io::Result<Vec<AudioSegmentMetadata>> {
+        let mut segments = Vec::new();
+        
+        for path in self.list_category_files("raw_audio", Some(date)).await? {
+            if !path.to_string_lossy().ends_with(".meta.json") {
+                continue;
+            }
+            let content = fs::read_to_string(&path).await?;
+            match serde_json::from_str::<AudioSegmentMetadata>(&content) {
+                Ok(metadata) => segments.push(metadata),
+                Err(e) => error!("Skipping unreadable audio metadata {:?}: {}", path, e),
+            }
+        }
+        
+        Ok(segments)
+    }
+
+    /// Store the cleaned version of a sealed recording next to it, sealed as well:
+    /// recording.wav.enc -> recording_cleaned.wav.enc
+    pub async fn save_cleaned_audio(&self, audio_path: &Path, cleaned: Vec<u8>) -> std::io::Result<PathBuf> {
+        let name = encryption::plain_path(audio_path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| n.replace(".wav", "_cleaned.wav"))
+            .unwrap_or_else(|| "cleaned.wav".to_string());
+        let (filename, contents) = self.seal(name, cleaned)?;
+        let filepath = audio_path.with_file_name(filename);
+        fs::write(&filepath, contents).await?;
         Ok(filepath)
     }
 
@@ -99,9 +550,15 @@ impl DataStorage {
         text: &str,
         anomalies: &serde_json::Value,
     ) -> std::io::Result<PathBuf> {
-        let timestamp = Utc::now().format("%Y-%m-%d_%H-%M-%S");
+        let now = Utc::now();
+        let timestamp = now.format("%Y-%m-%d_%H-%M-%S");
         let filename = format!("{}_{}.json", timestamp, &session_id[0..8]);
-        let filepath = self.data_dir.join("transcriptions").join(&filename);
+        if let Some(store) = &self.sqlite {
+            store.insert_transcription(&filename, now, session_id, text, anomalies, Conflict::Replace).await?;
+            info!("✓ Transcription saved: {} (SQLite)", filename);
+            return Ok(store.path().to_path_buf());
+        }
+        let dir = self.prepare_dir(now, "transcriptions").await?;
 
         let transcription_data = json!({
             "session_id": session_id,
@@ -110,8 +567,9 @@ impl DataStorage {
             "anomalies": anomalies,
         });
 
-        let json_str = to_string_pretty(&transcription_data)?;
-        fs::write(&filepath, json_str).await?;
+        let (filename, contents) = self.seal(filename, to_string_pretty(&transcription_data)?.into_bytes())?;
+        let filepath = dir.join(&filename);
+        fs::write(&filepath, contents).await?;
         info!("✓ Transcription saved: {}", filename);
         Ok(filepath)
     }
@@ -122,9 +580,15 @@ impl DataStorage {
         session_id: &str,
         anomalies: &serde_json::Value,
     ) -> std::io::Result<PathBuf> {
-        let timestamp = Utc::now().format("%Y-%m-%d_%H-%M-%S");
+        let now = Utc::now();
+        let timestamp = now.format("%Y-%m-%d_%H-%M-%S");
         let filename = format!("anomalies_{}_{}.json", timestamp, &session_id[0..8]);
-        let filepath = self.data_dir.join("anomalies").join(&filename);
+        if let Some(store) = &self.sqlite {
+            store.insert_anomalies(&filename, now, session_id, "audio", anomalies, Conflict::Replace).await?;
+            info!("✓ Anomalies saved: {} (SQLite)", filename);
+            return Ok(store.path().to_path_buf());
+        }
+        let filepath = self.prepare_dir(now, "anomalies").await?.join(&filename);
 
         let data = json!({
             "session_id": session_id,
@@ -141,9 +605,10 @@ impl DataStorage {
 
     /// Save hourly snapshot for quick historical access
     pub async fn save_hourly_snapshot(&self, data: &EyeCoreData) -> std::io::Result<PathBuf> {
+        let data = &*self.redactor.redacted_snapshot(data);
         let hour = data.timestamp.format("%Y-%m-%d_%H");
         let filename = format!("{}_snapshot.json", hour);
-        let filepath = self.data_dir.join("hourly_snapshots").join(&filename);
+        let filepath = self.prepare_dir(data.timestamp, "hourly_snapshots").await?.join(&filename);
 
         // Check if file exists, if so append; otherwise create
         let mut hourly_data: serde_json::Value = if filepath.exists() {
@@ -169,44 +634,29 @@ impl DataStorage {
 
     /// List all saved sessions
     pub async fn list_sessions(&self) -> std::io::Result<Vec<String>> {
-        let timeslots_dir = self.data_dir.join("timeslots");
-        let mut entries = fs::read_dir(&timeslots_dir).await?;
-        let mut sessions = Vec::new();
-
-        while let Some(entry) = entries.next_entry().await? {
-            if let Some(filename) = entry.file_name().to_str() {
-                if filename.ends_with(".json") {
-                    sessions.push(filename.to_string());
-                }
-            }
-        }
+        let sessions = self
+            .list_category_files("timeslots", None)
+            .await?
+            .iter()
+            .filter_map(|path| path.file_name()?.to_str().map(|n| n.to_string()))
+            .filter(|filename| crate::bulk_read::is_json_file(&encryption::plain_path(Path::new(filename))))
+            .collect();
 
         Ok(sessions)
     }
 
-    /// Get all data files from a specific date
+    /// Get all data files from a specific date (YYYY-MM-DD)
     pub async fn get_data_by_date(&self, date: &str) -> std::io::Result<Vec<PathBuf>> {
-        let timeslots_dir = self.data_dir.join("timeslots");
-        let mut entries = fs::read_dir(&timeslots_dir).await?;
-        let mut files = Vec::new();
-
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                if filename.contains(date) {
-                    files.push(path);
-                }
-            }
-        }
-
-        Ok(files)
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        self.list_category_files("timeslots", Some(date)).await
     }
     
     /// Save session log with metadata
     pub async fn save_session_log(&self, data: &EyeCoreData) -> std::io::Result<PathBuf> {
         let timestamp = data.timestamp.format("%Y-%m-%d_%H-%M-%S");
         let filename = format!("session_{}_{}.json", timestamp, &data.session_id[0..8]);
-        let filepath = self.data_dir.join("session_logs").join(&filename);
+        let dir = self.prepare_dir(data.timestamp, "session_logs").await?;
         
         let session_log = json!({
             "session_id": data.session_id,
@@ -220,8 +670,10 @@ impl DataStorage {
             "recording_started_at": Utc::now().to_rfc3339(),
         });
         
-        let json_str = to_string_pretty(&session_log)?;
-        fs::write(&filepath, json_str).await?;
+        let (suffix, contents) = self.encode(&session_log)?;
+        let (filename, contents) = self.seal(format!("{}{}", filename, suffix), contents)?;
+        let filepath = dir.join(&filename);
+        fs::write(&filepath, contents).await?;
         info!("✓ Session log saved: {}", filename);
         Ok(filepath)
     }
@@ -232,9 +684,15 @@ impl DataStorage {
         session_id: &str,
         anomalies: &[serde_json::Value],
     ) -> std::io::Result<PathBuf> {
-        let timestamp = Utc::now().format("%Y-%m-%d_%H-%M-%S-%3f");
+        let now = Utc::now();
+        let timestamp = now.format("%Y-%m-%d_%H-%M-%S-%3f");
         let filename = format!("anomalies_{}_{}.json", timestamp, &session_id[0..8]);
-        let filepath = self.data_dir.join("anomalies").join(&filename);
+        if let Some(store) = &self.sqlite {
+            store.insert_anomalies(&filename, now, session_id, "snapshot", &json!(anomalies), Conflict::Replace).await?;
+            info!("🚨 {} anomalies detected and saved (SQLite)", anomalies.len());
+            return Ok(store.path().to_path_buf());
+        }
+        let filepath = self.prepare_dir(now, "anomalies").await?.join(&filename);
         
         let anomaly_data = json!({
             "session_id": session_id,
@@ -254,9 +712,10 @@ impl DataStorage {
         &self,
         data: &crate::models::EnhancedScreenKeystrokeData,
     ) -> std::io::Result<PathBuf> {
+        let data = &*self.redactor.redacted_enhanced(data);
         let timestamp = data.timestamp.format("%Y-%m-%d_%H-%M-%S-%3f");
         let filename = format!("screen-kbd_{}_{}.json", timestamp, &data.session_id[0..8]);
-        let filepath = self.data_dir.join("screen-and-keyboard").join(&filename);
+        let dir = self.prepare_dir(data.timestamp, "screen-and-keyboard").await?;
         
         // Create full data structure matching the schema
         let full_data = json!({
@@ -296,12 +755,919 @@ impl DataStorage {
                 "accessibility_tree": &data.enhanced_screen_data.accessibility_tree,
             },
             "context_metadata": &data.context_metadata,
+            "redactions": &data.redactions,
             "saved_at": Utc::now().to_rfc3339(),
         });
         
-        let json_str = to_string_pretty(&full_data)?;
-        fs::write(&filepath, json_str).await?;
+        let (filename, contents) = self.seal(filename, to_string_pretty(&full_data)?.into_bytes())?;
+        let filepath = dir.join(&filename);
+        fs::write(&filepath, contents).await?;
         info!("✓ Enhanced screen & keyboard data saved: {}", filename);
         Ok(filepath)
     }
+    
+    /// Save a batch of wearable heart-rate samples next to the day's snapshots
+    pub async fn save_heart_rate_samples(&self, samples: &[crate::heart_rate::HeartRateSample]) -> std::io::Result<Option<PathBuf>> {
+        let Some(first) = samples.first() else {
+            return Ok(None);
+        };
+        let filename = format!("hr_{}.json", first.timestamp.format(SNAPSHOT_TIMESTAMP_FORMAT));
+        let filepath = self.prepare_dir(first.timestamp, "heart_rate").await?.join(&filename);
+        
+        let json_str = to_string_pretty(samples)?;
+        fs::write(&filepath, json_str).await?;
+        Ok(Some(filepath))
+    }
+    
+    /// Append a microphone/camera capture period to the log of the day it started
+    pub async fn save_capture_event(&self, event: &CaptureEvent) -> std::io::Result<PathBuf> {
+        let filepath = self.prepare_dir(event.started_at, "capture_events").await?.join(CAPTURE_EVENT_LOG);
+        
+        let mut line = serde_json::to_string(event)?;
+        line.push('\n');
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(&filepath).await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(filepath)
+    }
+    
+    /// Capture periods that started on `date`, oldest first; days recorded before the daily log
+    /// have a `capture_<time>_<device>.json` file per event
+    pub async fn load_capture_events(&self, date: NaiveDate) -> std::io::Result<Vec<CaptureEvent>> {
+        let mut events = Vec::new();
+        
+        for path in self.list_category_files("capture_events", Some(date)).await? {
+            let content = fs::read_to_string(&path).await?;
+            let is_log = path.file_name().is_some_and(|name| name == CAPTURE_EVENT_LOG);
+            let entries: Vec<&str> = if is_log { content.lines().filter(|line| !line.trim().is_empty()).collect() } else { vec![&content] };
+            for entry in entries {
+                match serde_json::from_str::<CaptureEvent>(entry) {
+                    Ok(event) => events.push(event),
+                    Err(e) => error!("Skipping unreadable capture event in {:?}: {}", path, e),
+                }
+            }
+        }
+        
+        events.sort_by_key(|event| event.started_at);
+        Ok(events)
+    }
+    
+    /// Append a content-grade API access to the day's audit log
+    pub async fn save_content_access(&self, event: &ContentAccessEvent) -> std::io::Result<PathBuf> {
+        let filepath = self.prepare_dir(event.timestamp, "audit").await?.join(CONTENT_ACCESS_LOG);
+        
+        let mut line = serde_json::to_string(event)?;
+        line.push('\n');
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(&filepath).await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(filepath)
+    }
+    
+    /// Content-grade accesses logged on `date`, oldest first
+    pub async fn load_content_access(&self, date: NaiveDate) -> std::io::Result<Vec<ContentAccessEvent>> {
+        let path = self.day_dir(date, "audit").join(CONTENT_ACCESS_LOG);
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str::<ContentAccessEvent>(line) {
+                Ok(event) => Some(event),
+                Err(e) => {
+                    error!("Skipping unreadable audit entry in {:?}: {}", path, e);
+                    None
+                }
+            })
+            .collect())
+    }
+    
+    /// Store an incident bundle in the day of its flag, sealed whatever `encryption` says; fails
+    /// without a keyring
+    pub async fn save_incident_bundle(&self, bundle: &IncidentBundle) -> std::io::Result<PathBuf> {
+        let Some(keyring) = &self.keyring else {
+            return Err(std::io::Error::other("incident bundles are only stored encrypted, and no encryption keyring is configured"));
+        };
+        let filename = format!(
+            "incident_{}_{}.json{}",
+            bundle.flag.timestamp.format(SNAPSHOT_TIMESTAMP_FORMAT),
+            bundle.id,
+            encryption::ENCRYPTED_SUFFIX
+        );
+        let filepath = self.prepare_dir(bundle.flag.timestamp, "incidents").await?.join(&filename);
+        
+        let sealed = keyring.read().unwrap().seal(&serde_json::to_vec(bundle)?)?;
+        fs::write(&filepath, sealed).await?;
+        Ok(filepath)
+    }
+    
+    /// Incident bundles of flags raised on `date`, oldest first
+    pub async fn load_incident_bundles(&self, date: NaiveDate) -> std::io::Result<Vec<IncidentBundle>> {
+        let mut bundles = Vec::new();
+        
+        for path in self.list_category_files("incidents", Some(date)).await? {
+            let content = self.read_stored(&path).await?;
+            match serde_json::from_slice::<IncidentBundle>(&content) {
+                Ok(bundle) => bundles.push(bundle),
+                Err(e) => error!("Skipping unreadable incident bundle {:?}: {}", path, e),
+            }
+        }
+        
+        bundles.sort_by_key(|bundle| bundle.flag.timestamp);
+        Ok(bundles)
+    }
+    
+    /// Append an idle session to the log of the day it ended
+    pub async fn save_idle_session(&self, session: &IdleSession) -> std::io::Result<PathBuf> {
+        let filepath = self.prepare_dir(session.end, "idle").await?.join(IDLE_SESSION_LOG);
+        
+        let mut line = serde_json::to_string(session)?;
+        line.push('\n');
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(&filepath).await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(filepath)
+    }
+    
+    /// Idle sessions that ended on `date`, oldest first
+    pub async fn load_idle_sessions(&self, date: NaiveDate) -> std::io::Result<Vec<IdleSession>> {
+        let path = self.day_dir(date, "idle").join(IDLE_SESSION_LOG);
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str::<IdleSession>(line) {
+                Ok(session) => Some(session),
+                Err(e) => {
+                    error!("Skipping unreadable idle session in {:?}: {}", path, e);
+                    None
+                }
+            })
+            .collect())
+    }
+    
+    /// Transcriptions saved on `date`, oldest first
+    pub async fn load_transcriptions(&self, date: NaiveDate) -> std::io::Result<Vec<serde_json::Value>> {
+        if let Some(store) = &self.sqlite {
+            return store.load_transcriptions(date).await;
+        }
+        let mut transcriptions = Vec::new();
+        
+        for path in self.list_category_files("transcriptions", Some(date)).await? {
+            let content = self.read_stored(&path).await?;
+            match serde_json::from_slice::<serde_json::Value>(&content) {
+                Ok(transcription) => transcriptions.push(transcription),
+                Err(e) => error!("Skipping unreadable transcription {:?}: {}", path, e),
+            }
+        }
+        
+        Ok(transcriptions)
+    }
+    
+    /// Flags raised by the flag_detection service between `start` and `end`, oldest first, archived
+    /// ones included. The service always writes files; with SQLite attached they are merged with the
+    /// stored flags.
+    pub async fn load_flags(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> std::io::Result<Vec<FlagRecord>> {
+        let mut flags = self.load_flag_files(start, end).await?;
+        if let Some(store) = &self.sqlite {
+            let stored = store.load_flags(start, end).await?;
+            let ids: std::collections::HashSet<String> = stored.iter().map(|flag| flag.id.clone()).collect();
+            flags.retain(|flag| !ids.contains(&flag.id));
+            flags.extend(stored);
+        }
+        flags.sort_by_key(|flag| flag.timestamp);
+        Ok(flags)
+    }
+
+    async fn load_flag_files(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> std::io::Result<Vec<FlagRecord>> {
+        let flags_dir = self.data_dir.join("flags");
+        let mut entries = match fs::read_dir(&flags_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        
+        let mut flags = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            match fs::read_to_string(&path).await {
+                Ok(content) => match serde_json::from_str::<FlagRecord>(&content) {
+                    Ok(flag) if flag.timestamp >= start && flag.timestamp <= end => flags.push(flag),
+                    Ok(_) => {}
+                    Err(e) => error!("Failed to parse flag file {:?}: {}", path, e),
+                },
+                Err(e) => error!("Failed to read flag file {:?}: {}", path, e),
+            }
+        }
+        
+        flags.extend(self.load_archived_flags(start, end, &flags).await?);
+        Ok(flags)
+    }
+    
+    /// Flags the flag_detection service moved to `flags/archive/flags_<YYYY-MM>.ndjson` once past its
+    /// retention, from the months overlapping `start`..`end`; flags also in `live` are skipped
+    async fn load_archived_flags(&self, start: DateTime<Utc>, end: DateTime<Utc>, live: &[FlagRecord]) -> std::io::Result<Vec<FlagRecord>> {
+        let archive_dir = self.data_dir.join("flags").join("archive");
+        let mut entries = match fs::read_dir(&archive_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let (first, last) = (start.format("%Y-%m").to_string(), end.format("%Y-%m").to_string());
+        let ids: std::collections::HashSet<&str> = live.iter().map(|flag| flag.id.as_str()).collect();
+        
+        let mut flags = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(month) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix("flags_"))
+                .and_then(|n| n.strip_suffix(".ndjson"))
+            else {
+                continue;
+            };
+            if month < first.as_str() || month > last.as_str() {
+                continue;
+            }
+            let content = match fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(e) => {
+                    error!("Failed to read flag archive {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                match serde_json::from_str::<FlagRecord>(line) {
+                    Ok(flag) if flag.timestamp >= start && flag.timestamp <= end && !ids.contains(flag.id.as_str()) => flags.push(flag),
+                    Ok(_) => {}
+                    Err(e) => error!("Skipping unreadable flag in {:?}: {}", path, e),
+                }
+            }
+        }
+        Ok(flags)
+    }
+    
+    /// Write a flag raised outside the flag detection service (e.g. a bookmark) next to its flags
+    pub async fn save_flag(&self, flag: &FlagRecord) -> std::io::Result<PathBuf> {
+        if let Some(store) = &self.sqlite {
+            store.insert_flag(flag, Conflict::Replace).await?;
+            info!("🚩 Flag saved: {} (SQLite)", flag.title);
+            return Ok(store.path().to_path_buf());
+        }
+        let flags_dir = self.data_dir.join("flags");
+        fs::create_dir_all(&flags_dir).await?;
+        let filename = format!("flag_{}_{}.json", flag.timestamp.format(SNAPSHOT_TIMESTAMP_FORMAT), &flag.id[0..8]);
+        let filepath = flags_dir.join(&filename);
+        
+        fs::write(&filepath, to_string_pretty(flag)?).await?;
+        info!("🚩 Flag saved: {} - {}", flag.title, filename);
+        Ok(filepath)
+    }
+    
+    /// Save (or overwrite, as its snapshots fill in) a user bookmark
+    pub async fn save_bookmark(&self, bookmark: &Bookmark) -> std::io::Result<PathBuf> {
+        let filename = format!("bookmark_{}_{}.json", bookmark.timestamp.format(SNAPSHOT_TIMESTAMP_FORMAT), &bookmark.id[0..8]);
+        let filepath = self.prepare_dir(bookmark.timestamp, "bookmarks").await?.join(&filename);
+        
+        fs::write(&filepath, to_string_pretty(bookmark)?).await?;
+        Ok(filepath)
+    }
+    
+    /// Bookmarks set on `date`, oldest first
+    pub async fn load_bookmarks(&self, date: NaiveDate) -> std::io::Result<Vec<Bookmark>> {
+        let mut bookmarks = Vec::new();
+        
+        for path in self.list_category_files("bookmarks", Some(date)).await? {
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let content = fs::read_to_string(&path).await?;
+            match serde_json::from_str::<Bookmark>(&content) {
+                Ok(bookmark) => bookmarks.push(bookmark),
+                Err(e) => error!("Skipping unreadable bookmark {:?}: {}", path, e),
+            }
+        }
+        
+        bookmarks.sort_by_key(|bookmark| bookmark.timestamp);
+        Ok(bookmarks)
+    }
+    
+    /// Entries of the metrics-only mirror for `date`, oldest first
+    pub async fn load_metrics_mirror(&self, date: NaiveDate) -> std::io::Result<Vec<MetricsOnlyEntry>> {
+        let mut entries = Vec::new();
+        for path in self.list_category_files(metrics_mirror::CATEGORY, Some(date)).await? {
+            if metrics_mirror::is_mirror_file(&path) {
+                entries.extend(metrics_mirror::read(&path).await?);
+            }
+        }
+        entries.sort_by_key(|entry| entry.timestamp);
+        Ok(entries)
+    }
+    
+    /// Save a user note under the day it starts on
+    pub async fn save_note(&self, note: &SessionNote) -> std::io::Result<PathBuf> {
+        let filename = format!("note_{}_{}.json", note.start.format(SNAPSHOT_TIMESTAMP_FORMAT), &note.id[0..8]);
+        let filepath = self.prepare_dir(note.start, "notes").await?.join(&filename);
+        
+        fs::write(&filepath, to_string_pretty(note)?).await?;
+        info!("📝 Note saved: {}", filename);
+        Ok(filepath)
+    }
+    
+    /// Notes starting on `date`, oldest first
+    pub async fn load_notes(&self, date: NaiveDate) -> std::io::Result<Vec<SessionNote>> {
+        let mut notes = Vec::new();
+        
+        for path in self.list_category_files("notes", Some(date)).await? {
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let content = fs::read_to_string(&path).await?;
+            match serde_json::from_str::<SessionNote>(&content) {
+                Ok(note) => notes.push(note),
+                Err(e) => error!("Skipping unreadable note {:?}: {}", path, e),
+            }
+        }
+        
+        notes.sort_by_key(|note| note.start);
+        Ok(notes)
+    }
+    
+    /// Save an explicit collection gap marker into the timeline
+    pub async fn save_collection_gap(&self, gap: &CollectionGap) -> std::io::Result<PathBuf> {
+        let filename = format!(
+            "gap_{}_{}.json",
+            gap.start.format(SNAPSHOT_TIMESTAMP_FORMAT),
+            gap.end.format(SNAPSHOT_TIMESTAMP_FORMAT)
+        );
+        let filepath = self.prepare_dir(gap.end, "gaps").await?.join(&filename);
+        
+        let json_str = to_string_pretty(gap)?;
+        fs::write(&filepath, json_str).await?;
+        info!("⏸ Collection gap recorded: {}s ({})", gap.duration_seconds, gap.reason);
+        Ok(filepath)
+    }
+    
+    /// Spool a snapshot for upload as `upload_queue/<name>.json`, redacted, compressed and sealed
+    /// like snapshots; returns the path and its size on disk
+    pub async fn save_upload_package(&self, name: &str, data: &EyeCoreData) -> std::io::Result<(PathBuf, u64)> {
+        let dir = self.device_dir().join(UPLOAD_QUEUE_DIR);
+        fs::create_dir_all(&dir).await?;
+        let data = &*self.redactor.redacted_snapshot(data);
+        let (suffix, contents) = self.encode(&serde_json::to_value(data)?)?;
+        let (filename, contents) = self.seal(format!("{}.json{}", name, suffix), contents)?;
+        
+        // Renamed into place once complete, so a crash cannot leave half a package behind
+        let partial = dir.join(format!("{}.tmp", name));
+        fs::write(&partial, &contents).await?;
+        let path = dir.join(filename);
+        fs::rename(&partial, &path).await?;
+        Ok((path, contents.len() as u64))
+    }
+    
+    /// Load all recorded collection gaps, oldest first
+    pub async fn load_collection_gaps(&self) -> std::io::Result<Vec<CollectionGap>> {
+        let mut gaps = Vec::new();
+        
+        for path in self.list_category_files("gaps", None).await? {
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            match fs::read_to_string(&path).await {
+                Ok(content) => match serde_json::from_str::<CollectionGap>(&content) {
+                    Ok(gap) => gaps.push(gap),
+                    Err(e) => error!("Failed to parse gap file {:?}: {}", path, e),
+                },
+                Err(e) => error!("Failed to read gap file {:?}: {}", path, e),
+            }
+        }
+        
+        gaps.sort_by_key(|gap| gap.start);
+        Ok(gaps)
+    }
+    
+    /// Timestamps of stored snapshots (all days, or one day), parsed from their filenames
+    pub async fn list_snapshot_timestamps(&self, date: Option<NaiveDate>) -> std::io::Result<Vec<DateTime<Utc>>> {
+        if let Some(store) = &self.sqlite {
+            return store.snapshot_timestamps(date).await;
+        }
+        let paths = self.list_category_files("timeslots", date).await?;
+        tokio::task::spawn_blocking(move || {
+            let mut timestamps = Vec::with_capacity(paths.len());
+            for path in &paths {
+                if crate::bulk_read::is_snapshot_archive(path) {
+                    match crate::bulk_read::SnapshotArchive::open(path) {
+                        Ok(archive) => timestamps.extend(archive.entries().iter().map(|entry| entry.timestamp)),
+                        Err(e) => error!("Skipping unreadable snapshot archive {:?}: {}", path, e),
+                    }
+                    continue;
+                }
+                if !crate::bulk_read::is_snapshot_log(path) {
+                    timestamps.extend(path.file_name().and_then(|n| n.to_str()).and_then(snapshot_timestamp_from_filename));
+                    continue;
+                }
+                match crate::bulk_read::snapshot_log_timestamps(path) {
+                    Ok(logged) => timestamps.extend(logged),
+                    Err(e) => error!("Skipping unreadable snapshot log {:?}: {}", path, e),
+                }
+            }
+            timestamps.sort();
+            timestamps
+        })
+        .await
+        .map_err(std::io::Error::other)
+    }
+    
+    /// Load the snapshots stored for one day in `order_snapshots` order, skipping files that fail to parse
+    pub async fn load_snapshots(&self, date: NaiveDate) -> std::io::Result<Vec<EyeCoreData>> {
+        if let Some(store) = &self.sqlite {
+            let mut snapshots = store.load_snapshots(date).await?;
+            order_snapshots(&mut snapshots);
+            return Ok(snapshots);
+        }
+        let (sealed, paths): (Vec<PathBuf>, Vec<PathBuf>) = self
+            .list_category_files("timeslots", Some(date))
+            .await?
+            .into_iter()
+            .partition(|path| encryption::is_encrypted(path));
+        let mut snapshots = tokio::task::spawn_blocking(move || {
+            let mut snapshots = Vec::with_capacity(paths.len());
+            crate::bulk_read::scan_snapshots(&paths, |data| snapshots.push(data));
+            snapshots
+        })
+        .await
+        .map_err(std::io::Error::other)?;
+        for path in sealed {
+            let data = self.read_stored(&path).await.map_err(|e| e.to_string());
+            match data.and_then(|content| crate::bulk_read::parse_snapshot(&content)) {
+                Ok(data) => snapshots.push(data),
+                Err(e) => error!("Skipping unreadable snapshot {:?}: {}", path, e),
+            }
+        }
+        order_snapshots(&mut snapshots);
+        Ok(snapshots)
+    }
+    
+    /// Timestamp of the newest stored snapshot, looking only at the most recent day with data
+    pub async fn latest_snapshot_timestamp(&self) -> std::io::Result<Option<DateTime<Utc>>> {
+        for date in self.list_dates().await?.into_iter().rev() {
+            if let Some(latest) = self.list_snapshot_timestamps(Some(date)).await?.last() {
+                return Ok(Some(*latest));
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Save a generated daily report
+    pub async fn save_daily_report(&self, date: NaiveDate, report: &serde_json::Value) -> std::io::Result<PathBuf> {
+        let filename = format!("{}_report.json", date.format("%Y-%m-%d"));
+        let dir = self.day_dir(date, "daily_reports");
+        fs::create_dir_all(&dir).await?;
+        let filepath = dir.join(&filename);
+        
+        let json_str = to_string_pretty(report)?;
+        fs::write(&filepath, json_str).await?;
+        info!("📅 Daily report saved: {}", filename);
+        Ok(filepath)
+    }
+    
+    /// Check whether a daily report already exists for `date`
+    pub fn has_daily_report(&self, date: NaiveDate) -> bool {
+        self.day_dir(date, "daily_reports")
+            .join(format!("{}_report.json", date.format("%Y-%m-%d")))
+            .exists()
+    }
+    
+    /// Save a generated weekly report in the directory of the week's Monday
+    pub async fn save_weekly_report(&self, week_start: NaiveDate, report: &serde_json::Value) -> std::io::Result<PathBuf> {
+        let filename = format!("{}_weekly_report.json", week_start.format("%Y-%m-%d"));
+        let dir = self.day_dir(week_start, "weekly_reports");
+        fs::create_dir_all(&dir).await?;
+        let filepath = dir.join(&filename);
+        
+        let json_str = to_string_pretty(report)?;
+        fs::write(&filepath, json_str).await?;
+        info!("📅 Weekly report saved: {}", filename);
+        Ok(filepath)
+    }
+    
+    /// Check whether a weekly report already exists for the week from `week_start`
+    pub fn has_weekly_report(&self, week_start: NaiveDate) -> bool {
+        self.day_dir(week_start, "weekly_reports")
+            .join(format!("{}_weekly_report.json", week_start.format("%Y-%m-%d")))
+            .exists()
+    }
+    
+    /// Move files from the legacy flat layout (data/<category>/<file>) into the
+    /// per-device, per-day layout, keeping filenames unchanged
+    pub async fn migrate_legacy_layout(&self) -> std::io::Result<usize> {
+        let mut moved = 0;
+        
+        for category in CATEGORIES {
+            let legacy_dir = self.data_dir.join(category);
+            if !legacy_dir.is_dir() {
+                continue;
+            }
+            
+            let mut entries = fs::read_dir(&legacy_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                if !entry.file_type().await?.is_file() {
+                    continue;
+                }
+                let filename = entry.file_name();
+                let name = filename.to_string_lossy();
+                
+                // Date comes from the filename, falling back to the file's modification time
+                let date = match date_from_filename(&name) {
+                    Some(date) => date,
+                    None => {
+                        let modified: DateTime<Utc> = entry.metadata().await?.modified()?.into();
+                        modified.date_naive()
+                    }
+                };
+                
+                let target_dir = self.day_dir(date, category);
+                fs::create_dir_all(&target_dir).await?;
+                let target = target_dir.join(&filename);
+                if target.exists() {
+                    warn!("Skipping {:?}: already exists at {:?}", entry.path(), target);
+                    continue;
+                }
+                fs::rename(entry.path(), &target).await?;
+                moved += 1;
+            }
+            
+            // Only removes the legacy directory once it is empty
+            if fs::remove_dir(&legacy_dir).await.is_ok() {
+                info!("✓ Migrated legacy {} directory", category);
+            }
+        }
+        
+        Ok(moved)
+    }
+    
+    /// Keyring in use, for `GET /storage/encryption`
+    pub fn encryption_status(&self) -> EncryptionStatus {
+        let keyring = self.keyring.as_ref().map(|keyring| keyring.read().unwrap());
+        EncryptionStatus {
+            enabled: self.encrypt,
+            key_file: keyring.as_ref().map(|keyring| keyring.path().display().to_string()),
+            active_key: keyring.as_ref().map(|keyring| keyring.active_key()),
+            keys: keyring.map(|keyring| keyring.key_ids()).unwrap_or_default(),
+        }
+    }
+
+    /// Switch to a new key and re-seal every sealed file with it (`POST /storage/encryption/rotate`).
+    /// Files are replaced atomically, and new files use the new key right away. A file written or
+    /// deleted while it was being re-sealed is left as it is now. Spooled upload packages are not
+    /// re-sealed, as they are sent and deleted soon. The old keys are dropped from the keyring only
+    /// once no file or queued package needs them, so a failed file stays readable.
+    pub async fn rotate_encryption_key(&self) -> std::io::Result<KeyRotation> {
+        let Some(keyring) = &self.keyring else {
+            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Encryption is not configured"));
+        };
+        let _rotating = self.rotation_lock.lock().await;
+        let active_key = keyring.write().unwrap().rotate()?;
+        info!("🔐 Rotated to encryption key {}", active_key);
+
+        let device_dir = self.device_dir();
+        let paths = tokio::task::spawn_blocking(move || sealed_files(&device_dir))
+            .await
+            .map_err(std::io::Error::other)?;
+        let (mut reencrypted, mut failed) = (0, 0);
+        for path in paths {
+            let resealed = async {
+                let before = fs::metadata(&path).await?;
+                let sealed = fs::read(&path).await?;
+                let resealed = {
+                    let keyring = keyring.read().unwrap();
+                    if keyring.is_current(&sealed) {
+                        return Ok(false);
+                    }
+                    keyring.seal(&keyring.open(&sealed)?)?
+                };
+                let temporary = path.with_extension("enc.tmp");
+                write_owner_only(&temporary, &resealed).await?;
+
+                // A file rewritten (with the new key) or deleted meanwhile must not be brought back
+                let unchanged = fs::metadata(&path).await.is_ok_and(|after| {
+                    after.len() == before.len() && after.modified().ok() == before.modified().ok()
+                });
+                if !unchanged {
+                    fs::remove_file(&temporary).await?;
+                    return Ok(false);
+                }
+                fs::rename(&temporary, &path).await?;
+                Ok::<_, std::io::Error>(true)
+            };
+            match resealed.await {
+                Ok(changed) => reencrypted += changed as usize,
+                Err(e) => {
+                    error!("Failed to re-encrypt {:?}: {}", path, e);
+                    failed += 1;
+                }
+            }
+        }
+
+        let queued = self.queued_under_old_keys().await;
+        let retired_keys = if failed == 0 && queued == 0 { keyring.write().unwrap().retire_previous()? } else { Vec::new() };
+        info!(
+            "🔐 Re-encrypted {} files, {} failed, {} queued uploads under old keys, retired {} keys",
+            reencrypted, failed, queued, retired_keys.len()
+        );
+        Ok(KeyRotation { active_key, reencrypted, failed, queued, retired_keys })
+    }
+
+    /// Spooled upload packages still sealed with a key other than the active one
+    async fn queued_under_old_keys(&self) -> usize {
+        let Some(keyring) = &self.keyring else { return 0 };
+        let Ok(mut entries) = fs::read_dir(self.device_dir().join(UPLOAD_QUEUE_DIR)).await else { return 0 };
+        let mut queued = 0;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if !encryption::is_encrypted(&entry.path()) {
+                continue;
+            }
+            // A package sent and deleted meanwhile no longer needs its key
+            if let Ok(sealed) = fs::read(entry.path()).await {
+                queued += !keyring.read().unwrap().is_current(&sealed) as usize;
+            }
+        }
+        queued
+    }
+
+    /// Copy the snapshot, anomaly, transcription and flag files into the attached SQLite database
+    /// (`eyecore_mvp migrate-storage sqlite`). Rows already in the database are kept, so the import
+    /// can be re-run; the files are left in place. Returns the rows imported per table.
+    pub async fn import_into_sqlite(&self) -> std::io::Result<Vec<(&'static str, usize)>> {
+        let Some(store) = &self.sqlite else {
+            return Err(std::io::Error::other("SQLite storage is not attached"));
+        };
+        let mut imported = vec![("snapshots", 0), ("anomalies", 0), ("transcriptions", 0), ("flags", 0)];
+        
+        for path in self.list_category_files("timeslots", None).await? {
+            if crate::bulk_read::is_snapshot_log(&path) {
+                let mut snapshots = Vec::new();
+                let read = crate::bulk_read::read_snapshot_log(&path, |data| snapshots.push(data));
+                if let Err(e) = read {
+                    error!("Skipping unreadable snapshot log {:?}: {}", path, e);
+                }
+                for data in snapshots {
+                    imported[0].1 += store.insert_snapshot(&snapshot_filename(&data), &data, Conflict::Ignore).await? as usize;
+                }
+                continue;
+            }
+            if crate::bulk_read::is_snapshot_archive(&path) {
+                let archive = match crate::bulk_read::SnapshotArchive::open(&path) {
+                    Ok(archive) => archive,
+                    Err(e) => {
+                        error!("Skipping unreadable snapshot archive {:?}: {}", path, e);
+                        continue;
+                    }
+                };
+                for entry in archive.entries() {
+                    match archive.record(entry).and_then(|record| crate::bulk_read::parse_snapshot(&record)) {
+                        Ok(data) => imported[0].1 += store.insert_snapshot(&entry.name, &data, Conflict::Ignore).await? as usize,
+                        Err(e) => error!("Skipping unreadable record {} of {:?}: {}", entry.name, path, e),
+                    }
+                }
+                continue;
+            }
+            // Keyed by the plain file name, as the snapshots saved to the database are
+            let Some(name) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .filter(|_| crate::bulk_read::is_json_file(&path))
+                .map(|n| n.trim_end_matches(crate::bulk_read::COMPRESSED_SUFFIX))
+            else {
+                continue;
+            };
+            match crate::bulk_read::read_snapshot(&path) {
+                Ok(data) => imported[0].1 += store.insert_snapshot(name, &data, Conflict::Ignore).await? as usize,
+                Err(e) => error!("Skipping unreadable snapshot {:?}: {}", path, e),
+            }
+        }
+        
+        for path in self.list_category_files("anomalies", None).await? {
+            let Some((name, record, timestamp, session_id)) = read_record(&path).await else { continue };
+            // Audio analysis files list the anomaly types they checked for
+            let source = if record.get("types").is_some() { "audio" } else { "snapshot" };
+            let inserted = store
+                .insert_anomalies(&name, timestamp, &session_id, source, &record["anomalies"], Conflict::Ignore)
+                .await?;
+            imported[1].1 += inserted as usize;
+        }
+        
+        for path in self.list_category_files("transcriptions", None).await? {
+            let Some((name, record, timestamp, session_id)) = read_record(&path).await else { continue };
+            let text = record["text"].as_str().unwrap_or_default();
+            let inserted = store
+                .insert_transcription(&name, timestamp, &session_id, text, &record["anomalies"], Conflict::Ignore)
+                .await?;
+            imported[2].1 += inserted as usize;
+        }
+        
+        for flag in self.load_flag_files(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC).await? {
+            imported[3].1 += store.insert_flag(&flag, Conflict::Ignore).await? as usize;
+        }
+        
+        Ok(imported)
+    }
+}
+
+/// Name, content, timestamp and session of an anomaly or transcription file; None (logged) when unreadable
+async fn read_record(path: &Path) -> Option<(String, serde_json::Value, DateTime<Utc>, String)> {
+    let name = path.file_name()?.to_str().filter(|n| n.ends_with(".json"))?.to_string();
+    let content = fs::read_to_string(path).await.map_err(|e| e.to_string());
+    let record = match content.and_then(|content| serde_json::from_str::<serde_json::Value>(&content).map_err(|e| e.to_string())) {
+        Ok(record) => record,
+        Err(e) => {
+            error!("Skipping unreadable {:?}: {}", path, e);
+            return None;
+        }
+    };
+    let timestamp = record["timestamp"].as_str().and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+    let Some(timestamp) = timestamp else {
+        error!("Skipping {:?}: no timestamp", path);
+        return None;
+    };
+    let session_id = record["session_id"].as_str().unwrap_or_default().to_string();
+    Some((name, record, timestamp.with_timezone(&Utc), session_id))
+}
+
+/// Write `contents` to a new file readable by the owner only, like the keyring; a file left by an
+/// interrupted write is replaced
+async fn write_owner_only(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let _ = fs::remove_file(path).await;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    file.write_all(contents).await?;
+    file.sync_all().await
+}
+
+/// Sealed files anywhere under `dir`, except spooled upload packages
+fn sealed_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut files = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.file_name().is_some_and(|name| name == UPLOAD_QUEUE_DIR) {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(sealed_files(&path));
+        } else if encryption::is_encrypted(&path) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Subdirectories whose names are purely numeric (years, months, days), sorted
+async fn numbered_subdirs(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut subdirs = Vec::new();
+    if !dir.exists() {
+        return Ok(subdirs);
+    }
+
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        let is_numbered = name.to_str().map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())).unwrap_or(false);
+        if is_numbered && entry.file_type().await?.is_dir() {
+            subdirs.push(entry.path());
+        }
+    }
+
+    subdirs.sort();
+    Ok(subdirs)
+}
+
+/// Find the first YYYY-MM-DD date embedded in a filename
+fn date_from_filename(filename: &str) -> Option<NaiveDate> {
+    (0..filename.len().saturating_sub(9))
+        .filter_map(|start| filename.get(start..start + 10))
+        .find_map(|candidate| NaiveDate::parse_from_str(candidate, "%Y-%m-%d").ok())
+}
+
+/// Timeslot file name of a snapshot; the sequence keeps same-millisecond snapshots apart
+fn snapshot_filename(data: &EyeCoreData) -> String {
+    format!(
+        "{}_{}_{:08}.json",
+        data.timestamp.format(SNAPSHOT_TIMESTAMP_FORMAT),
+        &data.session_id[0..8],
+        data.sequence
+    )
+}
+
+/// `<name>.json.zst` next to `<name>.json`
+fn compressed_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(crate::bulk_read::COMPRESSED_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Sidecar path for an audio file: recording.wav (or recording.wav.enc) -> recording.meta.json
+pub fn audio_metadata_path(audio_path: &Path) -> PathBuf {
+    encryption::plain_path(audio_path).with_extension("meta.json")
+}
+
+/// Load the persistent device ID, generating one on first run
+pub async fn load_or_create_device_id(data_dir: &str) -> std::io::Result<String> {
+    let path = Path::new(data_dir).join("device_id");
+    if let Ok(existing) = fs::read_to_string(&path).await {
+        let existing = existing.trim();
+        if !existing.is_empty() {
+            return Ok(existing.to_string());
+        }
+    }
+
+    let device_id = uuid::Uuid::new_v4().to_string();
+    fs::create_dir_all(data_dir).await?;
+    fs::write(&path, &device_id).await?;
+    Ok(device_id)
+}
+
+/// Reading order for snapshots: sessions by their first snapshot, then each session by sequence, so
+/// clock changes and same-millisecond writes cannot reorder a session. Snapshots without a sequence
+/// (written before it existed) fall back to their timestamp.
+pub fn order_snapshots(snapshots: &mut [EyeCoreData]) {
+    let mut session_start: std::collections::HashMap<String, DateTime<Utc>> = std::collections::HashMap::new();
+    for data in snapshots.iter() {
+        session_start
+            .entry(data.session_id.clone())
+            .and_modify(|start| *start = (*start).min(data.timestamp))
+            .or_insert(data.timestamp);
+    }
+    snapshots.sort_by(|a, b| {
+        (session_start[&a.session_id], &a.session_id, a.sequence, a.timestamp)
+            .cmp(&(session_start[&b.session_id], &b.session_id, b.sequence, b.timestamp))
+    });
+}
+
+/// Parse the capture time encoded at the start of a timeslot filename
+pub fn snapshot_timestamp_from_filename(filename: &str) -> Option<DateTime<Utc>> {
+    let prefix = filename.get(..SNAPSHOT_TIMESTAMP_LEN)?;
+    NaiveDateTime::parse_from_str(prefix, SNAPSHOT_TIMESTAMP_FORMAT)
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn rotation_leaves_queued_uploads_and_keeps_their_key() {
+        let dir = TempDir(std::env::temp_dir().join(format!("eyecore_rotation_{}", uuid::Uuid::new_v4())));
+        let keyring = Keyring::load_or_create(&dir.0.join(encryption::DEFAULT_KEY_FILE)).unwrap();
+        let (flag, package) = (keyring.seal(b"flag").unwrap(), keyring.seal(b"package").unwrap());
+        let mut storage = DataStorage::new(&dir.0.to_string_lossy(), "device");
+        storage.attach_keyring(keyring, true);
+
+        let flag_path = storage.device_dir().join("2026/01/01/flags/flag.json.enc");
+        let package_path = storage.device_dir().join(UPLOAD_QUEUE_DIR).join("package.json.enc");
+        for (path, contents) in [(&flag_path, &flag), (&package_path, &package)] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let rotation = storage.rotate_encryption_key().await.unwrap();
+        assert_eq!((rotation.reencrypted, rotation.failed, rotation.queued), (1, 0, 1));
+        assert!(rotation.retired_keys.is_empty());
+        assert_eq!(storage.encryption_status().keys.len(), 2);
+        assert_eq!(std::fs::read(&package_path).unwrap(), package);
+        assert_ne!(std::fs::read(&flag_path).unwrap(), flag);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&flag_path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        // Once the package is sent, the next rotation retires both old keys
+        std::fs::remove_file(&package_path).unwrap();
+        let rotation = storage.rotate_encryption_key().await.unwrap();
+        assert_eq!((rotation.reencrypted, rotation.queued, rotation.retired_keys.len()), (1, 0, 2));
+    }
 }
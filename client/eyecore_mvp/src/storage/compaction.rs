@@ -0,0 +1,244 @@
+//! Compaction of past days (`compact_after_days`): nightly (the scheduler's `compaction` job), the timeslot files of each day at
+//! least that old are merged into a single `timeslots/snapshots_<date>.archive` and removed, so a
+//! day is one file instead of thousands. Records are the timeslot objects as compact JSON
+//! (zstd-compressed with `compression = "zstd"`), looked up through the index at the end of the
+//! archive; see bulk_read for the layout. Files that arrive for a day already compacted are merged
+//! into its archive on the next run. Sealed (`.enc`) files and snapshot logs are left as they are.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use log::{error, info, warn};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+use super::{snapshot_timestamp_from_filename, DataStorage, ZSTD_LEVEL};
+use crate::bulk_read::{self, ArchiveEntry, ArchiveIndex, SnapshotArchive};
+
+/// `timeslots/snapshots_<date>.archive`
+pub fn archive_path(timeslots_dir: &Path, date: NaiveDate) -> PathBuf {
+    timeslots_dir.join(format!("snapshots_{}{}", date.format("%Y-%m-%d"), bulk_read::SNAPSHOT_ARCHIVE_SUFFIX))
+}
+
+/// Compact every day at least `after_days` old that still has timeslot files, stopping between
+/// days on shutdown
+pub async fn compact(storage: &DataStorage, after_days: u32, stop: &CancellationToken) {
+    let cutoff = Utc::now().date_naive() - chrono::Duration::days(after_days as i64);
+    let dates = match storage.list_dates().await {
+        Ok(dates) => dates,
+        Err(e) => {
+            error!("Compaction failed to list stored days: {}", e);
+            return;
+        }
+    };
+    let (mut days, mut files) = (0, 0);
+    for date in dates.into_iter().filter(|date| *date <= cutoff) {
+        if stop.is_cancelled() {
+            break;
+        }
+        match compact_day(storage, date).await {
+            Ok(0) => {}
+            Ok(merged) => {
+                days += 1;
+                files += merged;
+            }
+            Err(e) => error!("Failed to compact {}: {}", date, e),
+        }
+    }
+    if days > 0 {
+        info!("🗜️ Compacted {} timeslot files from {} days", files, days);
+    }
+}
+
+/// Merge the timeslot files of `date` into its archive; returns the number of files merged
+pub async fn compact_day(storage: &DataStorage, date: NaiveDate) -> std::io::Result<usize> {
+    let _compacting = storage.archive_lock.lock().await;
+    let files: Vec<PathBuf> = storage
+        .list_category_files("timeslots", Some(date))
+        .await?
+        .into_iter()
+        .filter(|path| bulk_read::is_json_file(path))
+        .collect();
+    if files.is_empty() {
+        return Ok(0);
+    }
+    let archive = archive_path(&storage.day_dir(date, "timeslots"), date);
+    let compress = storage.compress;
+    tokio::task::spawn_blocking(move || merge_into_archive(&archive, &files, compress))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+enum Record<'a> {
+    Archived(&'a ArchiveEntry),
+    File { name: String, timestamp: DateTime<Utc>, path: PathBuf },
+}
+
+impl Record<'_> {
+    fn name(&self) -> &str {
+        match self {
+            Record::Archived(entry) => &entry.name,
+            Record::File { name, .. } => name,
+        }
+    }
+}
+
+/// Rewrite `archive` with its records plus those of `files`, then remove the files. Files that
+/// cannot be read or parsed are logged and left in place.
+fn merge_into_archive(archive: &Path, files: &[PathBuf], compress: bool) -> std::io::Result<usize> {
+    let existing = if archive.exists() {
+        Some(SnapshotArchive::open(archive).map_err(|e| std::io::Error::other(format!("{:?}: {}", archive, e)))?)
+    } else {
+        None
+    };
+    let mut records: Vec<Record> = existing.iter().flat_map(|archive| archive.entries()).map(Record::Archived).collect();
+    let mut merged = Vec::new();
+    for path in files {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .trim_end_matches(bulk_read::COMPRESSED_SUFFIX)
+            .to_string();
+        let Some(timestamp) = snapshot_timestamp_from_filename(&name) else {
+            warn!("Not compacting {:?}: no capture time in its name", path);
+            continue;
+        };
+        // Left behind by a run that stopped before removing the files it had archived
+        if existing.as_ref().is_some_and(|archive| archive.contains(&name)) {
+            merged.push(path.clone());
+            continue;
+        }
+        records.push(Record::File { name, timestamp, path: path.clone() });
+    }
+    records.sort_by(|a, b| a.name().cmp(b.name()));
+
+    let mut writer = ArchiveWriter::create(archive, compress)?;
+    let mut added = false;
+    for record in records {
+        match record {
+            Record::Archived(entry) => {
+                let archived = existing.as_ref().expect("archived records come from an archive");
+                let content = archived
+                    .record(entry)
+                    .map_err(|e| std::io::Error::other(format!("{:?}: {}", archive, e)))?;
+                writer.push(entry.name.clone(), entry.timestamp, &content)?;
+            }
+            Record::File { name, timestamp, path } => {
+                let timeslot = std::fs::read(&path)
+                    .and_then(|bytes| bulk_read::decode(&path, bytes))
+                    .map_err(|e| e.to_string())
+                    .and_then(|content| serde_json::from_slice::<serde_json::Value>(&content).map_err(|e| e.to_string()));
+                match timeslot {
+                    Ok(timeslot) => {
+                        writer.push(name, timestamp, &serde_json::to_vec(&timeslot)?)?;
+                        merged.push(path);
+                        added = true;
+                    }
+                    Err(e) => error!("Not compacting unreadable snapshot {:?}: {}", path, e),
+                }
+            }
+        }
+    }
+
+    if added {
+        let temporary = writer.finish()?;
+        // A mapped file cannot be replaced on Windows
+        drop(existing);
+        std::fs::rename(&temporary, archive)?;
+    } else {
+        writer.discard();
+    }
+    for path in &merged {
+        std::fs::remove_file(path)?;
+    }
+    Ok(merged.len())
+}
+
+/// Clear `field` in every record of an archive, replacing it; returns 1 if anything changed.
+/// Records that fail to parse are kept as they are.
+pub(super) fn expire_in_archive(archive: &Path, field: &str) -> std::io::Result<usize> {
+    let existing = SnapshotArchive::open(archive).map_err(|e| std::io::Error::other(format!("{:?}: {}", archive, e)))?;
+    let mut writer = ArchiveWriter::create(archive, existing.is_compressed())?;
+    let mut changed = false;
+    for entry in existing.entries() {
+        let record = existing.record(entry).map_err(std::io::Error::other)?;
+        let mut snapshot: serde_json::Value = match serde_json::from_slice(&record) {
+            Ok(snapshot) => snapshot,
+            Err(_) => {
+                writer.push(entry.name.clone(), entry.timestamp, &record)?;
+                continue;
+            }
+        };
+        if !snapshot["data"][field].is_null() {
+            snapshot["data"][field] = serde_json::Value::Null;
+            snapshot["metadata"]["data_types_available"][field] = serde_json::json!(false);
+            changed = true;
+        }
+        writer.push(entry.name.clone(), entry.timestamp, &serde_json::to_vec(&snapshot)?)?;
+    }
+    if !changed {
+        writer.discard();
+        return Ok(0);
+    }
+    let temporary = writer.finish()?;
+    drop(existing);
+    std::fs::rename(&temporary, archive)?;
+    Ok(1)
+}
+
+/// Writes an archive to a temporary file next to it, one record at a time
+struct ArchiveWriter {
+    temporary: PathBuf,
+    out: BufWriter<File>,
+    compress: bool,
+    offset: u64,
+    index: ArchiveIndex,
+}
+
+impl ArchiveWriter {
+    fn create(archive: &Path, compress: bool) -> std::io::Result<Self> {
+        let temporary = archive.with_extension("archive.tmp");
+        let mut out = BufWriter::new(File::create(&temporary)?);
+        out.write_all(bulk_read::ARCHIVE_MAGIC)?;
+        out.write_all(&[bulk_read::ARCHIVE_VERSION, if compress { bulk_read::ARCHIVE_ZSTD } else { 0 }])?;
+        Ok(ArchiveWriter {
+            temporary,
+            out,
+            compress,
+            offset: bulk_read::ARCHIVE_HEADER_LEN as u64,
+            index: ArchiveIndex::default(),
+        })
+    }
+
+    /// Append a timeslot object; records must come in name order
+    fn push(&mut self, name: String, timestamp: DateTime<Utc>, timeslot: &[u8]) -> std::io::Result<()> {
+        let record = if self.compress {
+            Cow::Owned(zstd::encode_all(timeslot, ZSTD_LEVEL)?)
+        } else {
+            Cow::Borrowed(timeslot)
+        };
+        let length = u32::try_from(record.len())
+            .map_err(|_| std::io::Error::other(format!("{} is too large to archive", name)))?;
+        self.out.write_all(&length.to_le_bytes())?;
+        self.out.write_all(&record)?;
+        self.index.records.push(ArchiveEntry { name, timestamp, offset: self.offset + 4, length: length as u64 });
+        self.offset += 4 + length as u64;
+        Ok(())
+    }
+
+    /// Write the index and footer and flush to disk; returns the file to move into place
+    fn finish(mut self) -> std::io::Result<PathBuf> {
+        serde_json::to_writer(&mut self.out, &self.index)?;
+        self.out.write_all(&self.offset.to_le_bytes())?;
+        self.out.write_all(bulk_read::ARCHIVE_MAGIC)?;
+        self.out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        Ok(self.temporary)
+    }
+
+    fn discard(self) {
+        drop(self.out);
+        let _ = std::fs::remove_file(&self.temporary);
+    }
+}
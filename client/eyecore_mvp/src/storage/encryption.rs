@@ -0,0 +1,313 @@
+//! At-rest encryption of stored behavioral data (`encryption = "xchacha20poly1305"`). Files are
+//! sealed with XChaCha20-Poly1305 under the keyring's active key and get an `.enc` suffix
+//! (`<name>.json.enc`, `<name>.json.zst.enc`, `<name>.wav.enc`).
+//!
+//! A sealed file is `EYEENC`, a format version byte, the 4-byte key ID, a 24-byte random nonce and
+//! the ciphertext; the header is authenticated with it. The keyring is a JSON file holding the active
+//! key and the previous ones, base64-encoded:
+//!
+//! ```json
+//! { "active": "<base64 32-byte key>", "previous": ["<base64 32-byte key>"] }
+//! ```
+//!
+//! A key ID is the start of the SHA-256 of the key, so a keyring can be written by hand.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use log::info;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Appended to the name of sealed files
+pub const ENCRYPTED_SUFFIX: &str = ".enc";
+/// Keyring in the device directory unless `encryption_key_file` names another
+pub const DEFAULT_KEY_FILE: &str = "encryption_keys.json";
+
+const MAGIC: &[u8] = b"EYEENC";
+const FORMAT_VERSION: u8 = 1;
+const KEY_ID_LEN: usize = 4;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + KEY_ID_LEN;
+const KEY_LEN: usize = 32;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyFile {
+    active: String,
+    #[serde(default)]
+    previous: Vec<String>,
+}
+
+struct DataKey {
+    id: [u8; KEY_ID_LEN],
+    encoded: String,
+    cipher: XChaCha20Poly1305,
+}
+
+impl DataKey {
+    fn generate() -> Self {
+        Self::decode(&BASE64.encode(XChaCha20Poly1305::generate_key(&mut OsRng))).expect("a generated key is valid")
+    }
+
+    fn decode(encoded: &str) -> Result<Self, String> {
+        let bytes = BASE64.decode(encoded.trim()).map_err(|e| format!("key is not base64: {}", e))?;
+        if bytes.len() != KEY_LEN {
+            return Err(format!("keys must be {} bytes, got {}", KEY_LEN, bytes.len()));
+        }
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&Sha256::digest(&bytes)[..KEY_ID_LEN]);
+        let key: [u8; KEY_LEN] = bytes.try_into().expect("the length is checked above");
+        Ok(DataKey { id, encoded: encoded.trim().to_string(), cipher: XChaCha20Poly1305::new(&Key::from(key)) })
+    }
+}
+
+/// The keys sealed files are read with; the first one seals new files
+pub struct Keyring {
+    path: PathBuf,
+    keys: Vec<DataKey>,
+}
+
+impl Keyring {
+    /// Keyring at `path`, created with a new random key if the file does not exist
+    pub fn load_or_create(path: &Path) -> std::io::Result<Self> {
+        if path.exists() {
+            return Self::load(path);
+        }
+        let keyring = Keyring { path: path.to_path_buf(), keys: vec![DataKey::generate()] };
+        keyring.save()?;
+        info!("🔐 Created encryption key {} in {:?}", keyring.active_key(), path);
+        Ok(keyring)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let invalid = |e: String| Error::new(ErrorKind::InvalidData, format!("Invalid encryption keyring {:?}: {}", path, e));
+        let content = std::fs::read_to_string(path)?;
+        let file: KeyFile = serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+        let keys = std::iter::once(&file.active)
+            .chain(&file.previous)
+            .map(|encoded| DataKey::decode(encoded))
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(invalid)?;
+        Ok(Keyring { path: path.to_path_buf(), keys })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// ID of the key new files are sealed with
+    pub fn active_key(&self) -> String {
+        hex::encode(self.keys[0].id)
+    }
+
+    pub fn key_ids(&self) -> Vec<String> {
+        self.keys.iter().map(|key| hex::encode(key.id)).collect()
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> std::io::Result<Vec<u8>> {
+        let key = &self.keys[0];
+        let mut sealed = Vec::with_capacity(HEADER_LEN + NONCE_LEN + plaintext.len() + 16);
+        sealed.extend_from_slice(MAGIC);
+        sealed.push(FORMAT_VERSION);
+        sealed.extend_from_slice(&key.id);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = key
+            .cipher
+            .encrypt(&nonce, Payload { msg: plaintext, aad: &sealed })
+            .map_err(|_| Error::other("Encryption failed"))?;
+        sealed.extend_from_slice(&nonce);
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> std::io::Result<Vec<u8>> {
+        let id = sealed_key_id(sealed)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Not an encrypted EyeCore file"))?;
+        let key = self.keys.iter().find(|key| key.id == id).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, format!("Encrypted with key {}, which is not in the keyring", hex::encode(id)))
+        })?;
+        let (header, rest) = sealed.split_at(HEADER_LEN);
+        if rest.len() < NONCE_LEN {
+            return Err(Error::new(ErrorKind::InvalidData, "Encrypted file is truncated"));
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("the nonce is NONCE_LEN bytes");
+        key.cipher
+            .decrypt(&XNonce::from(nonce), Payload { msg: ciphertext, aad: header })
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "Decryption failed: the file is damaged or was altered"))
+    }
+
+    /// Whether `sealed` was sealed with the active key
+    pub fn is_current(&self, sealed: &[u8]) -> bool {
+        sealed_key_id(sealed) == Some(self.keys[0].id)
+    }
+
+    /// Seal new files with a new random key from now on; the old keys stay for reading. Returns the
+    /// new key's ID.
+    pub fn rotate(&mut self) -> std::io::Result<String> {
+        self.keys.insert(0, DataKey::generate());
+        if let Err(e) = self.save() {
+            self.keys.remove(0);
+            return Err(e);
+        }
+        Ok(self.active_key())
+    }
+
+    /// Drop every key but the active one, once nothing is sealed with them; returns their IDs
+    pub fn retire_previous(&mut self) -> std::io::Result<Vec<String>> {
+        let retired: Vec<DataKey> = self.keys.drain(1..).collect();
+        if let Err(e) = self.save() {
+            self.keys.extend(retired);
+            return Err(e);
+        }
+        Ok(retired.iter().map(|key| hex::encode(key.id)).collect())
+    }
+
+    /// Replace the keyring file atomically, readable by the owner only
+    fn save(&self) -> std::io::Result<()> {
+        let file = KeyFile {
+            active: self.keys[0].encoded.clone(),
+            previous: self.keys[1..].iter().map(|key| key.encoded.clone()).collect(),
+        };
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temporary = self.path.with_extension("json.tmp");
+        // Created owner-only, so the keys are never readable by others, not even briefly; a file
+        // left by an interrupted save could have other permissions and is replaced
+        let _ = std::fs::remove_file(&temporary);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut writer = options.open(&temporary)?;
+        writer.write_all(&serde_json::to_vec_pretty(&file)?)?;
+        writer.sync_all()?;
+        drop(writer);
+        std::fs::rename(&temporary, &self.path)
+    }
+}
+
+fn sealed_key_id(sealed: &[u8]) -> Option<[u8; KEY_ID_LEN]> {
+    if sealed.len() < HEADER_LEN || !sealed.starts_with(MAGIC) || sealed[MAGIC.len()] != FORMAT_VERSION {
+        return None;
+    }
+    sealed[MAGIC.len() + 1..HEADER_LEN].try_into().ok()
+}
+
+pub fn is_encrypted(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(ENCRYPTED_SUFFIX))
+}
+
+/// `<name>.enc` next to `<name>`
+pub fn encrypted_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(ENCRYPTED_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// The path a sealed file would have unencrypted: `<name>.json.enc` -> `<name>.json`
+pub fn plain_path(path: &Path) -> PathBuf {
+    match path.to_str().and_then(|path| path.strip_suffix(ENCRYPTED_SUFFIX)) {
+        Some(plain) => PathBuf::from(plain),
+        None => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            TempDir(std::env::temp_dir().join(format!("eyecore_keyring_{}", uuid::Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn sealed_data_round_trips_and_is_authenticated() {
+        let dir = TempDir::new();
+        let keyring = Keyring::load_or_create(&dir.0.join(DEFAULT_KEY_FILE)).unwrap();
+        let sealed = keyring.seal(b"typed text").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert!(keyring.is_current(&sealed));
+        assert_eq!(keyring.open(&sealed).unwrap(), b"typed text");
+
+        let mut altered = sealed.clone();
+        *altered.last_mut().unwrap() ^= 1;
+        assert!(keyring.open(&altered).is_err());
+        assert!(keyring.open(&sealed[..HEADER_LEN + 4]).is_err());
+        assert!(keyring.open(b"{\"plain\": true}").is_err());
+    }
+
+    #[test]
+    fn rotation_keeps_old_files_readable_until_the_keys_are_retired() {
+        let dir = TempDir::new();
+        let path = dir.0.join(DEFAULT_KEY_FILE);
+        let mut keyring = Keyring::load_or_create(&path).unwrap();
+        let first = keyring.active_key();
+        let old = keyring.seal(b"before").unwrap();
+
+        let second = keyring.rotate().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(keyring.key_ids(), vec![second.clone(), first.clone()]);
+        assert!(!keyring.is_current(&old));
+        assert_eq!(keyring.open(&old).unwrap(), b"before");
+        let new = keyring.seal(b"after").unwrap();
+        assert!(keyring.is_current(&new));
+
+        // The file holds both keys, so a restart reads both kinds of files
+        let reloaded = Keyring::load(&path).unwrap();
+        assert_eq!(reloaded.key_ids(), vec![second.clone(), first.clone()]);
+        assert_eq!(reloaded.open(&old).unwrap(), b"before");
+
+        assert_eq!(keyring.retire_previous().unwrap(), vec![first]);
+        assert!(keyring.open(&old).is_err());
+        assert_eq!(Keyring::load(&path).unwrap().key_ids(), vec![second]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_keyring_file_is_readable_by_the_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TempDir::new();
+        let path = dir.0.join(DEFAULT_KEY_FILE);
+        std::fs::create_dir_all(&dir.0).unwrap();
+        // A world-readable file left by an interrupted save
+        std::fs::write(path.with_extension("json.tmp"), "stale").unwrap();
+        std::fs::set_permissions(path.with_extension("json.tmp"), std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let mut keyring = Keyring::load_or_create(&path).unwrap();
+        keyring.rotate().unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn keyrings_with_bad_keys_are_refused() {
+        let dir = TempDir::new();
+        std::fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join(DEFAULT_KEY_FILE);
+        for content in [r#"{"active": "not base64!"}"#, r#"{"active": "c2hvcnQ="}"#, r#"{"active": ""#] {
+            std::fs::write(&path, content).unwrap();
+            assert_eq!(Keyring::load(&path).err().map(|e| e.kind()), Some(ErrorKind::InvalidData), "{}", content);
+        }
+    }
+}
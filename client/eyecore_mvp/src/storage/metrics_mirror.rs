@@ -0,0 +1,45 @@
+//! Metrics-only mirror (`metrics_mirror = true`): the numbers of every snapshot (CPU, memory, disk,
+//! input counts, network rates, focus) appended as one line of
+//! `metrics/metrics_only_<YYYY-MM-DD>_<HH>.ndjson`, one file per UTC hour. The mirror is never
+//! encrypted, so dashboards can chart a device without the keyring even when `encryption` seals
+//! everything else. It carries no text, window titles, audio or file references, and no data of the
+//! opt-in modules; every line says `"metrics_only": true`.
+
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::models::{EyeCoreData, MetricsOnlyEntry};
+
+pub const CATEGORY: &str = "metrics";
+const PREFIX: &str = "metrics_only_";
+const SUFFIX: &str = ".ndjson";
+
+/// `metrics/metrics_only_<date>_<hour>.ndjson` in `dir`
+pub fn mirror_path(dir: &Path, timestamp: DateTime<Utc>) -> PathBuf {
+    dir.join(format!("{}{}{}", PREFIX, timestamp.format("%Y-%m-%d_%H"), SUFFIX))
+}
+
+pub fn is_mirror_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(PREFIX) && name.ends_with(SUFFIX))
+}
+
+/// Append the numbers of `data` to the mirror file of its hour in `dir`
+pub async fn append(dir: &Path, data: &EyeCoreData) -> std::io::Result<PathBuf> {
+    let path = mirror_path(dir, data.timestamp);
+    let mut line = serde_json::to_vec(&MetricsOnlyEntry::from_snapshot(data))?;
+    line.push(b'\n');
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&path).await?;
+    file.write_all(&line).await?;
+    file.flush().await?;
+    Ok(path)
+}
+
+/// The entries of a mirror file; a line cut short by a crash is skipped
+pub async fn read(path: &Path) -> std::io::Result<Vec<MetricsOnlyEntry>> {
+    let content = fs::read_to_string(path).await?;
+    Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
@@ -0,0 +1,362 @@
+//! Retention manager: keeps `./data` from growing forever. Three policies from `[retention]` run
+//! every hour, in this order: days older than `max_age_days` are removed, categories over their
+//! `quotas_mb` lose their oldest files, and while everything together exceeds `max_total_mb` the
+//! oldest whole day goes. The current day is never removed by the size limit.
+
+use chrono::{NaiveDate, Utc};
+use log::{error, info, warn};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use super::{DataStorage, CATEGORIES};
+use crate::config::RetentionConfig;
+use crate::models::{CategoryUsage, RetentionRun, StorageStats};
+
+/// The content access log is the record of who read typed and on-screen text; it is kept until
+/// removed by hand
+const KEPT_CATEGORIES: &[&str] = &["audit"];
+
+/// Whether `[retention]` may prune `category`
+pub fn is_prunable(category: &str) -> bool {
+    CATEGORIES.contains(&category) && !KEPT_CATEGORIES.contains(&category)
+}
+
+pub fn prunable_categories() -> Vec<&'static str> {
+    CATEGORIES.iter().copied().filter(|category| is_prunable(category)).collect()
+}
+
+/// A stored file, or a recording together with its `.meta.json` sidecar
+struct StoredUnit {
+    date: NaiveDate,
+    category: &'static str,
+    paths: Vec<PathBuf>,
+    bytes: u64,
+}
+
+pub struct RetentionManager {
+    policy: RetentionConfig,
+    last_run: Option<RetentionRun>,
+    reclaimed_bytes: u64,
+}
+
+impl RetentionManager {
+    pub fn new(policy: RetentionConfig) -> Self {
+        RetentionManager { policy, last_run: None, reclaimed_bytes: 0 }
+    }
+
+    fn is_active(&self) -> bool {
+        self.policy.max_age_days.is_some() || self.policy.max_total_mb.is_some() || !self.policy.quotas_mb.is_empty()
+    }
+
+    /// Apply the policies once
+    pub async fn run(&mut self, storage: &DataStorage) {
+        if !self.is_active() {
+            return;
+        }
+        let mut units = match scan(storage).await {
+            Ok(units) => units,
+            Err(e) => {
+                error!("Retention scan failed: {}", e);
+                return;
+            }
+        };
+        units.retain(|unit| is_prunable(unit.category));
+        let today = Utc::now().date_naive();
+        let mut run = RetentionRun {
+            at: Utc::now(),
+            files_removed: 0,
+            rows_removed: 0,
+            bytes_reclaimed: 0,
+            bytes_by_policy: BTreeMap::new(),
+        };
+
+        if let Some(days) = self.policy.max_age_days {
+            let cutoff = today - chrono::Duration::days(days as i64);
+            let expired = extract(&mut units, |unit| unit.date <= cutoff);
+            remove_units(storage, expired, "max_age", &mut run).await;
+            prune_database(storage, cutoff, "max_age", &mut run).await;
+        }
+
+        for (category, mb) in &self.policy.quotas_mb {
+            let quota = mb * 1024 * 1024;
+            let mut used: u64 = units.iter().filter(|unit| unit.category == category).map(|unit| unit.bytes).sum();
+            let over = extract(&mut units, |unit| {
+                if unit.category != category || used <= quota {
+                    return false;
+                }
+                used -= unit.bytes;
+                true
+            });
+            remove_units(storage, over, "quota", &mut run).await;
+        }
+
+        if let Some(mb) = self.policy.max_total_mb {
+            let limit = mb * 1024 * 1024;
+            let database_bytes = |storage: &DataStorage| storage.sqlite.as_ref().map_or(0, |store| store.size());
+            let mut days: BTreeSet<NaiveDate> = units.iter().map(|unit| unit.date).collect();
+            if let Some(store) = &storage.sqlite {
+                days.extend(store.dates().await.unwrap_or_default());
+            }
+            for day in days.into_iter().filter(|day| *day < today) {
+                let total = units.iter().map(|unit| unit.bytes).sum::<u64>() + database_bytes(storage);
+                if total <= limit {
+                    break;
+                }
+                let oldest = extract(&mut units, |unit| unit.date == day);
+                remove_units(storage, oldest, "max_total", &mut run).await;
+                prune_database(storage, day, "max_total", &mut run).await;
+            }
+            let total = units.iter().map(|unit| unit.bytes).sum::<u64>() + database_bytes(storage);
+            if total > limit {
+                warn!("⚠️ Stored data is {} MB, over max_total_mb = {}, with only today left to prune", total / 1024 / 1024, mb);
+            }
+        }
+
+        if run.files_removed > 0 || run.rows_removed > 0 {
+            info!(
+                "🧹 Retention removed {} files and {} rows, reclaiming {:.1} MB",
+                run.files_removed,
+                run.rows_removed,
+                run.bytes_reclaimed as f64 / 1024.0 / 1024.0
+            );
+        }
+        self.reclaimed_bytes += run.bytes_reclaimed;
+        self.last_run = Some(run);
+    }
+
+    /// Current usage per category, with the policies and what the runs reclaimed
+    pub async fn stats(&self, storage: &DataStorage) -> std::io::Result<StorageStats> {
+        let units = scan(storage).await?;
+        let mut categories: BTreeMap<String, CategoryUsage> = CATEGORIES
+            .iter()
+            .map(|category| {
+                let quota_bytes = self.policy.quotas_mb.get(*category).map(|mb| mb * 1024 * 1024);
+                (category.to_string(), CategoryUsage { quota_bytes, ..CategoryUsage::default() })
+            })
+            .collect();
+        for unit in &units {
+            let usage = categories.entry(unit.category.to_string()).or_default();
+            usage.files += unit.paths.len() as u64;
+            usage.bytes += unit.bytes;
+        }
+        let database_bytes = storage.sqlite.as_ref().map(|store| store.size());
+        let dates = storage.list_dates().await?;
+
+        Ok(StorageStats {
+            total_bytes: units.iter().map(|unit| unit.bytes).sum::<u64>() + database_bytes.unwrap_or(0),
+            database_bytes,
+            categories,
+            oldest_day: dates.first().copied(),
+            newest_day: dates.last().copied(),
+            max_age_days: self.policy.max_age_days,
+            max_total_bytes: self.policy.max_total_mb.map(|mb| mb * 1024 * 1024),
+            last_run: self.last_run.clone(),
+            reclaimed_bytes: self.reclaimed_bytes,
+        })
+    }
+}
+
+/// Move the units matching `remove` out of `units`
+fn extract(units: &mut Vec<StoredUnit>, mut remove: impl FnMut(&StoredUnit) -> bool) -> Vec<StoredUnit> {
+    let (removed, kept) = std::mem::take(units).into_iter().partition(|unit| remove(unit));
+    *units = kept;
+    removed
+}
+
+/// Every stored file in the day directories, oldest day first and by name within a category
+async fn scan(storage: &DataStorage) -> std::io::Result<Vec<StoredUnit>> {
+    let mut units = Vec::new();
+    for date in storage.list_dates().await? {
+        for category in CATEGORIES {
+            let mut grouped: BTreeMap<String, StoredUnit> = BTreeMap::new();
+            for path in storage.list_category_files(category, Some(date)).await? {
+                let bytes = fs::metadata(&path).await.map(|metadata| metadata.len()).unwrap_or(0);
+                // recording.wav and recording.meta.json go together
+                let stem = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.split('.').next()).unwrap_or_default();
+                let unit = grouped.entry(stem.to_string()).or_insert_with(|| StoredUnit {
+                    date,
+                    category,
+                    paths: Vec::new(),
+                    bytes: 0,
+                });
+                unit.paths.push(path);
+                unit.bytes += bytes;
+            }
+            units.extend(grouped.into_values());
+        }
+    }
+    Ok(units)
+}
+
+async fn remove_units(storage: &DataStorage, units: Vec<StoredUnit>, policy: &str, run: &mut RetentionRun) {
+    let mut dirs = BTreeSet::new();
+    for unit in units {
+        for path in &unit.paths {
+            match fs::remove_file(path).await {
+                Ok(()) => run.files_removed += 1,
+                Err(e) => {
+                    error!("Retention failed to remove {:?}: {}", path, e);
+                    continue;
+                }
+            }
+        }
+        run.bytes_reclaimed += unit.bytes;
+        *run.bytes_by_policy.entry(policy.to_string()).or_default() += unit.bytes;
+        dirs.insert(storage.day_dir(unit.date, unit.category));
+    }
+    for dir in dirs {
+        remove_empty_dirs(&dir, &storage.device_dir()).await;
+    }
+}
+
+/// SQLite rows from `date` and earlier, when that backend is in use
+async fn prune_database(storage: &DataStorage, date: NaiveDate, policy: &str, run: &mut RetentionRun) {
+    let Some(store) = &storage.sqlite else { return };
+    let before = store.size();
+    match store.prune_through(date).await {
+        Ok(0) => {}
+        Ok(rows) => {
+            let reclaimed = before.saturating_sub(store.size());
+            run.rows_removed += rows as u64;
+            run.bytes_reclaimed += reclaimed;
+            *run.bytes_by_policy.entry(policy.to_string()).or_default() += reclaimed;
+        }
+        Err(e) => error!("Retention failed to prune {:?} through {}: {}", store.path(), date, e),
+    }
+}
+
+/// Remove `dir` and its parents up to (not including) `root` while they are empty
+async fn remove_empty_dirs(dir: &Path, root: &Path) {
+    let mut dir = dir.to_path_buf();
+    while dir != root && dir.starts_with(root) {
+        if fs::remove_dir(&dir).await.is_err() {
+            break;
+        }
+        if !dir.pop() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const KIB: usize = 1024;
+
+    struct Fixture {
+        dir: PathBuf,
+        storage: DataStorage,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("eyecore_retention_{}", uuid::Uuid::new_v4()));
+            let storage = DataStorage::new(&dir.to_string_lossy(), "device");
+            Fixture { dir, storage }
+        }
+
+        fn write(&self, days_ago: i64, category: &str, name: &str, bytes: usize) -> PathBuf {
+            let dir = self.storage.day_dir(Utc::now().date_naive() - Duration::days(days_ago), category);
+            std::fs::create_dir_all(&dir).unwrap();
+            let path = dir.join(name);
+            std::fs::write(&path, vec![0u8; bytes]).unwrap();
+            path
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn policy(max_age_days: Option<u32>, max_total_mb: Option<u64>, quotas_mb: &[(&str, u64)]) -> RetentionManager {
+        RetentionManager::new(RetentionConfig {
+            max_age_days,
+            max_total_mb,
+            quotas_mb: quotas_mb.iter().map(|(category, mb)| (category.to_string(), *mb)).collect(),
+        })
+    }
+
+    #[tokio::test]
+    async fn nothing_is_removed_without_a_limit() {
+        let fixture = Fixture::new();
+        let old = fixture.write(400, "timeslots", "a.json", KIB);
+        let mut retention = policy(None, None, &[]);
+        retention.run(&fixture.storage).await;
+        assert!(old.exists());
+        assert!(retention.last_run.is_none());
+    }
+
+    #[tokio::test]
+    async fn max_age_removes_the_cutoff_day_and_keeps_the_day_after() {
+        let fixture = Fixture::new();
+        let expired = fixture.write(3, "timeslots", "a.json", KIB);
+        let cutoff = fixture.write(2, "timeslots", "b.json", KIB);
+        let kept = fixture.write(1, "timeslots", "c.json", KIB);
+        let audit = fixture.write(3, "audit", "access.json", KIB);
+
+        let mut retention = policy(Some(2), None, &[]);
+        retention.run(&fixture.storage).await;
+
+        assert!(!expired.exists() && !cutoff.exists());
+        assert!(kept.exists());
+        assert!(audit.exists(), "the content access log is never pruned");
+        assert!(!expired.parent().unwrap().exists(), "emptied day directories are removed");
+        let run = retention.last_run.as_ref().unwrap();
+        assert_eq!(run.files_removed, 2);
+        assert_eq!(run.bytes_by_policy.get("max_age"), Some(&(2 * KIB as u64)));
+    }
+
+    #[tokio::test]
+    async fn quotas_remove_the_oldest_files_until_the_category_fits() {
+        let fixture = Fixture::new();
+        let oldest = fixture.write(2, "raw_audio", "rec1.wav", 512 * KIB);
+        let sidecar = fixture.write(2, "raw_audio", "rec1.meta.json", KIB);
+        let middle = fixture.write(1, "raw_audio", "rec2.wav", 512 * KIB);
+        let newest = fixture.write(0, "raw_audio", "rec3.wav", 511 * KIB);
+        let other = fixture.write(2, "timeslots", "a.json", 2048 * KIB);
+
+        let mut retention = policy(None, None, &[("raw_audio", 1)]);
+        retention.run(&fixture.storage).await;
+
+        // A recording goes with its sidecar; what is left (1023 KiB) fits the 1 MB quota
+        assert!(!oldest.exists() && !sidecar.exists());
+        assert!(middle.exists() && newest.exists());
+        assert!(other.exists(), "quotas apply to their own category only");
+        assert_eq!(retention.last_run.as_ref().unwrap().files_removed, 2);
+    }
+
+    #[tokio::test]
+    async fn max_total_removes_whole_days_but_never_today() {
+        let fixture = Fixture::new();
+        let old = fixture.write(2, "timeslots", "a.json", 256 * KIB);
+        let old_audio = fixture.write(2, "raw_audio", "rec.wav", 256 * KIB);
+        let recent = fixture.write(1, "timeslots", "b.json", 256 * KIB);
+        let today = fixture.write(0, "timeslots", "c.json", 2048 * KIB);
+
+        let mut retention = policy(None, Some(1), &[]);
+        retention.run(&fixture.storage).await;
+
+        assert!(!old.exists() && !old_audio.exists() && !recent.exists());
+        assert!(today.exists(), "today stays even over the limit");
+        assert_eq!(retention.last_run.as_ref().unwrap().bytes_by_policy.get("max_total"), Some(&(768 * KIB as u64)));
+    }
+
+    #[tokio::test]
+    async fn max_total_stops_once_the_data_fits() {
+        let fixture = Fixture::new();
+        let old = fixture.write(2, "timeslots", "a.json", 512 * KIB);
+        let recent = fixture.write(1, "timeslots", "b.json", 512 * KIB);
+        let today = fixture.write(0, "timeslots", "c.json", 256 * KIB);
+
+        let mut retention = policy(None, Some(1), &[]);
+        retention.run(&fixture.storage).await;
+
+        assert!(!old.exists());
+        assert!(recent.exists() && today.exists());
+    }
+}
@@ -0,0 +1,132 @@
+//! Append-only snapshot log (`snapshot_format = "ndjson"`). Instead of one timeslot file per
+//! snapshot, each snapshot becomes one compact line of `timeslots/snapshots_<date>_<part>.ndjson`,
+//! in the same shape as a timeslot file. A new part starts when the day changes or the current part
+//! reaches `snapshot_log_max_mb`; after a restart, appending continues in the day's last part.
+
+use chrono::NaiveDate;
+use log::info;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::bulk_read::SNAPSHOT_LOG_SUFFIX;
+use crate::models::EyeCoreData;
+
+const LOG_PREFIX: &str = "snapshots_";
+
+/// The part being appended to
+pub struct OpenLog {
+    date: NaiveDate,
+    part: u32,
+    path: PathBuf,
+    file: fs::File,
+    size: u64,
+}
+
+pub struct SnapshotLog {
+    max_bytes: u64,
+    current: Mutex<Option<OpenLog>>,
+    // Session and sequence of the newest snapshot appended; snapshots are appended in order
+    last_written: std::sync::Mutex<Option<(String, u64)>>,
+}
+
+impl SnapshotLog {
+    pub fn new(max_bytes: u64) -> Self {
+        SnapshotLog { max_bytes, current: Mutex::new(None), last_written: std::sync::Mutex::new(None) }
+    }
+
+    /// Append `line` (without its newline) to the log for `date` in `dir`, rotating first if needed
+    pub async fn append(&self, dir: &Path, date: NaiveDate, data: &EyeCoreData, mut line: Vec<u8>) -> std::io::Result<PathBuf> {
+        let mut current = self.current.lock().await;
+        let needed = line.len() as u64 + 1;
+        let rotate = match current.as_ref() {
+            Some(log) => log.date != date || (log.size > 0 && log.size + needed > self.max_bytes),
+            None => true,
+        };
+        if rotate {
+            let part = match current.as_ref().filter(|log| log.date == date) {
+                Some(log) => log.part + 1,
+                None => match last_part(dir, date).await? {
+                    Some((part, size)) if size + needed > self.max_bytes => part + 1,
+                    Some((part, _)) => part,
+                    None => 0,
+                },
+            };
+            let log = open(dir, date, part).await?;
+            info!("📜 Appending snapshots to {:?}", log.path);
+            *current = Some(log);
+        }
+
+        let log = current.as_mut().expect("the log is opened above");
+        line.push(b'\n');
+        // One write per line, so a reader tailing the log never sees half of one unless the disk fills
+        log.file.write_all(&line).await?;
+        log.file.flush().await?;
+        log.size += line.len() as u64;
+        *self.last_written.lock().unwrap() = Some((data.session_id.clone(), data.sequence));
+        Ok(log.path.clone())
+    }
+
+    /// Whether `data` was appended by this process
+    pub fn contains(&self, data: &EyeCoreData) -> bool {
+        self.last_written
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|(session, sequence)| *session == data.session_id && data.sequence <= *sequence)
+    }
+
+    /// Stop appending until the guard is dropped, closing the open part, so a log can be rewritten
+    /// in place; appending resumes in the day's last part
+    pub async fn pause(&self) -> MutexGuard<'_, Option<OpenLog>> {
+        let mut current = self.current.lock().await;
+        *current = None;
+        current
+    }
+}
+
+/// `snapshots_2025-10-26_000.ndjson`; parts sort in the order they were written
+fn log_name(date: NaiveDate, part: u32) -> String {
+    format!("{}{}_{:03}{}", LOG_PREFIX, date.format("%Y-%m-%d"), part, SNAPSHOT_LOG_SUFFIX)
+}
+
+/// Highest part already written for `date`, with its size
+async fn last_part(dir: &Path, date: NaiveDate) -> std::io::Result<Option<(u32, u64)>> {
+    let prefix = format!("{}{}_", LOG_PREFIX, date.format("%Y-%m-%d"));
+    let mut last = None;
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        let part = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(&prefix))
+            .and_then(|rest| rest.strip_suffix(SNAPSHOT_LOG_SUFFIX))
+            .and_then(|part| part.parse::<u32>().ok());
+        if let Some(part) = part.filter(|part| last.is_none_or(|(last, _)| *part > last)) {
+            last = Some((part, entry.metadata().await?.len()));
+        }
+    }
+    Ok(last)
+}
+
+async fn open(dir: &Path, date: NaiveDate, part: u32) -> std::io::Result<OpenLog> {
+    let path = dir.join(log_name(date, part));
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&path).await?;
+    let mut size = file.metadata().await?.len();
+    // A line cut short by a crash is left as it is; the next one starts on a line of its own
+    if size > 0 && !ends_with_newline(&path)? {
+        file.write_all(b"\n").await?;
+        size += 1;
+    }
+    Ok(OpenLog { date, part, path, file, size })
+}
+
+fn ends_with_newline(path: &Path) -> std::io::Result<bool> {
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0u8; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
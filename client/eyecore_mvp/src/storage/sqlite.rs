@@ -0,0 +1,349 @@
+//! SQLite storage engine (`storage_backend = "sqlite"`): snapshots, anomalies, transcriptions and
+//! flags go into indexed tables of `<device>/eyecore.db` instead of one pretty-printed JSON file
+//! each. Everything else (audio, session logs, reports, ...) stays in the day directories.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use log::error;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::models::{EyeCoreData, FlagRecord};
+
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS snapshots (
+    name TEXT PRIMARY KEY,              -- timeslot file name, e.g. 2025-10-26_13-35-36-104_495d9930_00000001.json
+    timestamp INTEGER NOT NULL,         -- milliseconds since the epoch
+    date TEXT NOT NULL,                 -- YYYY-MM-DD (UTC)
+    session_id TEXT NOT NULL,
+    sequence INTEGER NOT NULL,
+    data TEXT NOT NULL                  -- EyeCoreData as JSON
+);
+CREATE INDEX IF NOT EXISTS snapshots_date ON snapshots (date, timestamp);
+CREATE INDEX IF NOT EXISTS snapshots_session ON snapshots (session_id, sequence);
+
+CREATE TABLE IF NOT EXISTS anomalies (
+    name TEXT PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    source TEXT NOT NULL,               -- snapshot or audio
+    anomalies TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS anomalies_date ON anomalies (date, timestamp);
+
+CREATE TABLE IF NOT EXISTS transcriptions (
+    name TEXT PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    text TEXT NOT NULL,
+    anomalies TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS transcriptions_date ON transcriptions (date, timestamp);
+
+CREATE TABLE IF NOT EXISTS flags (
+    id TEXT PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    data TEXT NOT NULL                  -- FlagRecord as JSON
+);
+CREATE INDEX IF NOT EXISTS flags_timestamp ON flags (timestamp);
+";
+
+/// What to do when a row with the same key exists
+#[derive(Clone, Copy)]
+pub enum Conflict {
+    Fail,       // snapshots are never replaced, as with create_new for files
+    Replace,    // later saves win, as when a file is rewritten
+    Ignore,     // imports keep what is already in the database
+}
+
+impl Conflict {
+    fn insert(self) -> &'static str {
+        match self {
+            Conflict::Fail => "INSERT",
+            Conflict::Replace => "INSERT OR REPLACE",
+            Conflict::Ignore => "INSERT OR IGNORE",
+        }
+    }
+}
+
+pub struct SqliteStore {
+    path: PathBuf,
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    /// Open (or create) the database and bring its schema up to date
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let conn = Connection::open(path).map_err(to_io)?;
+        conn.pragma_update(None, "journal_mode", "WAL").map_err(to_io)?;
+        conn.pragma_update(None, "synchronous", "NORMAL").map_err(to_io)?;
+        conn.busy_timeout(std::time::Duration::from_secs(5)).map_err(to_io)?;
+
+        let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0)).map_err(to_io)?;
+        if version > SCHEMA_VERSION {
+            return Err(std::io::Error::other(format!(
+                "{:?} has schema version {}, this build supports up to {}",
+                path, version, SCHEMA_VERSION
+            )));
+        }
+        conn.execute_batch(SCHEMA).map_err(to_io)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION).map_err(to_io)?;
+
+        Ok(SqliteStore { path: path.to_path_buf(), conn: Arc::new(Mutex::new(conn)) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Run `f` on the connection off the async runtime
+    async fn call<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    ) -> std::io::Result<T> {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || f(&conn.lock().unwrap()))
+            .await
+            .map_err(std::io::Error::other)?
+            .map_err(to_io)
+    }
+
+    /// Store a snapshot under its timeslot file name; false when `Conflict::Ignore` skipped it
+    pub async fn insert_snapshot(&self, name: &str, data: &EyeCoreData, conflict: Conflict) -> std::io::Result<bool> {
+        let json = serde_json::to_string(data)?;
+        let (name, session_id) = (name.to_string(), data.session_id.clone());
+        let (timestamp, date, sequence) = (data.timestamp.timestamp_millis(), day(data.timestamp), data.sequence as i64);
+        let sql = format!(
+            "{} INTO snapshots (name, timestamp, date, session_id, sequence, data) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            conflict.insert()
+        );
+        self.call(move |conn| conn.execute(&sql, params![name, timestamp, date, session_id, sequence, json]))
+            .await
+            .map(|inserted| inserted > 0)
+    }
+
+    /// Whether a snapshot with this timeslot file name is stored
+    pub fn contains_snapshot(&self, name: &str) -> bool {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT 1 FROM snapshots WHERE name = ?1", [name], |_| Ok(()))
+            .optional()
+            .map(|row| row.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Days that have snapshots, anomalies or transcriptions, oldest first
+    pub async fn dates(&self) -> std::io::Result<Vec<NaiveDate>> {
+        let days: Vec<String> = self
+            .call(|conn| {
+                let mut statement = conn.prepare(
+                    "SELECT date FROM snapshots UNION SELECT date FROM anomalies UNION SELECT date FROM transcriptions ORDER BY date",
+                )?;
+                let days = statement.query_map([], |row| row.get(0))?.collect();
+                days
+            })
+            .await?;
+        Ok(days.iter().filter_map(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()).collect())
+    }
+
+    /// Snapshot timestamps of one day, or of all days, oldest first
+    pub async fn snapshot_timestamps(&self, date: Option<NaiveDate>) -> std::io::Result<Vec<DateTime<Utc>>> {
+        let date = date.map(|date| date.to_string());
+        let millis: Vec<i64> = self
+            .call(move |conn| {
+                let mut statement =
+                    conn.prepare("SELECT timestamp FROM snapshots WHERE ?1 IS NULL OR date = ?1 ORDER BY timestamp")?;
+                let millis = statement.query_map([date], |row| row.get(0))?.collect();
+                millis
+            })
+            .await?;
+        Ok(millis.into_iter().filter_map(DateTime::from_timestamp_millis).collect())
+    }
+
+    /// Snapshots of one day, skipping rows that fail to parse; the caller orders them
+    pub async fn load_snapshots(&self, date: NaiveDate) -> std::io::Result<Vec<EyeCoreData>> {
+        let rows: Vec<(String, String)> = self
+            .call(move |conn| {
+                let mut statement = conn.prepare("SELECT name, data FROM snapshots WHERE date = ?1")?;
+                let rows = statement.query_map([date.to_string()], |row| Ok((row.get(0)?, row.get(1)?)))?.collect();
+                rows
+            })
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(name, data)| match serde_json::from_str(&data) {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    error!("Skipping unreadable snapshot {} in {:?}: {}", name, self.path, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    pub async fn insert_anomalies(
+        &self,
+        name: &str,
+        timestamp: DateTime<Utc>,
+        session_id: &str,
+        source: &'static str,
+        anomalies: &serde_json::Value,
+        conflict: Conflict,
+    ) -> std::io::Result<bool> {
+        let (name, session_id, anomalies) = (name.to_string(), session_id.to_string(), anomalies.to_string());
+        let (millis, date) = (timestamp.timestamp_millis(), day(timestamp));
+        let sql = format!(
+            "{} INTO anomalies (name, timestamp, date, session_id, source, anomalies) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            conflict.insert()
+        );
+        self.call(move |conn| conn.execute(&sql, params![name, millis, date, session_id, source, anomalies]))
+            .await
+            .map(|inserted| inserted > 0)
+    }
+
+    pub async fn insert_transcription(
+        &self,
+        name: &str,
+        timestamp: DateTime<Utc>,
+        session_id: &str,
+        text: &str,
+        anomalies: &serde_json::Value,
+        conflict: Conflict,
+    ) -> std::io::Result<bool> {
+        let (name, session_id, text, anomalies) =
+            (name.to_string(), session_id.to_string(), text.to_string(), anomalies.to_string());
+        let (millis, date) = (timestamp.timestamp_millis(), day(timestamp));
+        let sql = format!(
+            "{} INTO transcriptions (name, timestamp, date, session_id, text, anomalies) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            conflict.insert()
+        );
+        self.call(move |conn| conn.execute(&sql, params![name, millis, date, session_id, text, anomalies]))
+            .await
+            .map(|inserted| inserted > 0)
+    }
+
+    /// Transcriptions of one day, oldest first, in the shape of the transcription files
+    pub async fn load_transcriptions(&self, date: NaiveDate) -> std::io::Result<Vec<serde_json::Value>> {
+        let rows: Vec<(i64, String, String, String)> = self
+            .call(move |conn| {
+                let mut statement = conn.prepare(
+                    "SELECT timestamp, session_id, text, anomalies FROM transcriptions WHERE date = ?1 ORDER BY timestamp",
+                )?;
+                let rows = statement
+                    .query_map([date.to_string()], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+                    .collect();
+                rows
+            })
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(millis, session_id, text, anomalies)| {
+                serde_json::json!({
+                    "session_id": session_id,
+                    "timestamp": DateTime::from_timestamp_millis(millis).map(|t| t.to_rfc3339()),
+                    "text": text,
+                    "anomalies": serde_json::from_str::<serde_json::Value>(&anomalies).unwrap_or_default(),
+                })
+            })
+            .collect())
+    }
+
+    pub async fn insert_flag(&self, flag: &FlagRecord, conflict: Conflict) -> std::io::Result<bool> {
+        let json = serde_json::to_string(flag)?;
+        let (id, millis) = (flag.id.clone(), flag.timestamp.timestamp_millis());
+        let sql = format!("{} INTO flags (id, timestamp, data) VALUES (?1, ?2, ?3)", conflict.insert());
+        self.call(move |conn| conn.execute(&sql, params![id, millis, json]))
+            .await
+            .map(|inserted| inserted > 0)
+    }
+
+    /// Flags between `start` and `end`, oldest first
+    pub async fn load_flags(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> std::io::Result<Vec<FlagRecord>> {
+        let (start, end) = (start.timestamp_millis(), end.timestamp_millis());
+        let rows: Vec<(String, String)> = self
+            .call(move |conn| {
+                let mut statement =
+                    conn.prepare("SELECT id, data FROM flags WHERE timestamp BETWEEN ?1 AND ?2 ORDER BY timestamp")?;
+                let rows = statement.query_map([start, end], |row| Ok((row.get(0)?, row.get(1)?)))?.collect();
+                rows
+            })
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, data)| match serde_json::from_str(&data) {
+                Ok(flag) => Some(flag),
+                Err(e) => {
+                    error!("Skipping unreadable flag {} in {:?}: {}", id, self.path, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Clear one module's section (`field`) from the snapshots of `date`, and its transcriptions when
+    /// they belong to it. Returns the number of rows changed.
+    pub async fn expire_module_data(&self, field: &str, date: NaiveDate, transcriptions: bool) -> std::io::Result<usize> {
+        let path = format!("$.{}", field);
+        self.call(move |conn| {
+            let date = date.to_string();
+            let mut changed = conn.execute(
+                "UPDATE snapshots SET data = json_set(data, ?1, json('null'))
+                 WHERE date = ?2 AND json_type(data, ?1) IS NOT NULL AND json_type(data, ?1) != 'null'",
+                params![path, date],
+            )?;
+            if transcriptions {
+                changed += conn.execute("DELETE FROM transcriptions WHERE date = ?1", [&date])?;
+            }
+            Ok(changed)
+        })
+        .await
+    }
+
+    /// Remove every row from `date` and earlier, then give the space back to the file system.
+    /// Returns the number of rows removed.
+    pub async fn prune_through(&self, date: NaiveDate) -> std::io::Result<usize> {
+        let end = date.succ_opt().unwrap_or(date).and_hms_opt(0, 0, 0).map_or(i64::MIN, |t| t.and_utc().timestamp_millis());
+        self.call(move |conn| {
+            let date = date.to_string();
+            let mut removed = 0;
+            for table in ["snapshots", "anomalies", "transcriptions"] {
+                removed += conn.execute(&format!("DELETE FROM {} WHERE date <= ?1", table), [&date])?;
+            }
+            removed += conn.execute("DELETE FROM flags WHERE timestamp < ?1", [end])?;
+            if removed > 0 {
+                conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")?;
+            }
+            Ok(removed)
+        })
+        .await
+    }
+
+    /// Bytes on disk, including the write-ahead log
+    pub fn size(&self) -> u64 {
+        let wal = self.path.with_extension("db-wal");
+        [self.path.as_path(), wal.as_path()]
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+}
+
+fn day(timestamp: DateTime<Utc>) -> String {
+    timestamp.date_naive().to_string()
+}
+
+fn to_io(e: rusqlite::Error) -> std::io::Error {
+    match e {
+        rusqlite::Error::SqliteFailure(error, _) if error.code == rusqlite::ErrorCode::ConstraintViolation => {
+            std::io::Error::new(std::io::ErrorKind::AlreadyExists, e)
+        }
+        e => std::io::Error::other(e),
+    }
+}
@@ -0,0 +1,211 @@
+//! Window-switch stream: each change of the foreground app (Alt-Tab, clicks, the taskbar) with the
+//! apps' categories, sampled every 250 ms on a background thread (Windows). Summarized as switching
+//! cadence and "ping-pong" runs (rapid A↔B alternation) for the focus model and `/data/switches`.
+
+use chrono::{Duration, Utc};
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
+use std::sync::Mutex;
+
+use crate::models::{AppSwitch, PingPong, SwitchCadence};
+
+/// Switches kept for `/data/switches` and cadence windows
+const RETENTION: Duration = Duration::hours(1);
+/// Window the focus model's cadence covers
+pub const CADENCE_WINDOW: Duration = Duration::minutes(5);
+/// A ping-pong run is at least this many alternating switches, none more than PING_PONG_MAX_DWELL apart
+const PING_PONG_MIN_SWITCHES: usize = 4;
+const PING_PONG_MAX_DWELL: Duration = Duration::seconds(20);
+
+/// Executable names (lowercase, without ".exe") by category
+#[cfg(target_os = "windows")]
+const CATEGORIES: &[(&str, &[&str])] = &[
+    ("browser", &["chrome", "msedge", "firefox", "brave", "opera", "vivaldi", "safari"]),
+    ("communication", &["slack", "teams", "ms-teams", "discord", "zoom", "outlook", "thunderbird", "telegram", "whatsapp", "signal", "skype"]),
+    ("development", &["code", "devenv", "idea64", "pycharm64", "rider64", "clion64", "webstorm64", "sublime_text", "notepad++", "windowsterminal", "cmd", "powershell", "pwsh"]),
+    ("documents", &["winword", "excel", "powerpnt", "onenote", "acrobat", "acrord32", "notion", "obsidian", "notepad"]),
+    ("media", &["spotify", "vlc", "wmplayer", "music.ui", "video.ui"]),
+    ("games", &["steam", "epicgameslauncher", "minecraft", "javaw"]),
+    ("system", &["explorer", "taskmgr", "systemsettings", "applicationframehost"]),
+];
+
+/// Category of an app by executable name; "other" when unknown
+#[cfg(target_os = "windows")]
+pub fn app_category(app: &str) -> &'static str {
+    let name = app.to_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name);
+    CATEGORIES
+        .iter()
+        .find(|(_, apps)| apps.contains(&name))
+        .map_or("other", |(category, _)| category)
+}
+
+pub struct SwitchMonitor {
+    switches: Option<Mutex<Receiver<AppSwitch>>>, // from the sampling thread
+    latest: Vec<AppSwitch>,          // found by the most recent poll
+    history: VecDeque<AppSwitch>,    // the last hour, oldest first
+}
+
+impl SwitchMonitor {
+    /// Starts the sampling thread where the foreground window can be read (Windows)
+    pub fn new() -> Self {
+        SwitchMonitor {
+            switches: Self::start_sampler().map(Mutex::new),
+            latest: Vec::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn available(&self) -> bool {
+        self.switches.is_some()
+    }
+
+    /// Take the switches since the previous poll; called once per collection
+    pub fn poll(&mut self) {
+        self.latest = match self.switches.as_mut().map(Mutex::get_mut) {
+            Some(Ok(switches)) => switches.try_iter().collect(),
+            _ => Vec::new(),
+        };
+        self.history.extend(self.latest.iter().cloned());
+        let cutoff = Utc::now() - RETENTION;
+        while self.history.front().is_some_and(|switch| switch.timestamp < cutoff) {
+            self.history.pop_front();
+        }
+    }
+
+    /// Switches found by the most recent poll
+    pub fn since_last_poll(&self) -> &[AppSwitch] {
+        &self.latest
+    }
+
+    /// Switches of the last `window` (at most an hour), oldest first
+    pub fn recent(&self, window: Duration) -> Vec<AppSwitch> {
+        let cutoff = Utc::now() - window;
+        self.history.iter().filter(|switch| switch.timestamp >= cutoff).cloned().collect()
+    }
+
+    /// Cadence over the last `window`; None where switches are not sampled
+    pub fn cadence(&self, window: Duration) -> Option<SwitchCadence> {
+        self.available().then(|| cadence(&self.recent(window), window))
+    }
+
+    #[cfg(target_os = "windows")]
+    fn start_sampler() -> Option<Receiver<AppSwitch>> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::Builder::new()
+            .name("window-switches".to_string())
+            .spawn(move || sample_foreground(sender))
+            .map_err(|e| log::warn!("Window switch sampling unavailable: {}", e))
+            .ok()?;
+        Some(receiver)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn start_sampler() -> Option<Receiver<AppSwitch>> {
+        None
+    }
+}
+
+/// Poll the foreground window until the monitor is dropped. An app counts once it has stayed in
+/// front for two samples, so passing through the Alt-Tab switcher or a flashing dialog is not a switch.
+#[cfg(target_os = "windows")]
+fn sample_foreground(sender: std::sync::mpsc::Sender<AppSwitch>) {
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+    let mut system = sysinfo::System::new();
+    let mut current: Option<String> = None;
+    let mut candidate: Option<(String, chrono::DateTime<Utc>)> = None;
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let mut pid = 0u32;
+        unsafe {
+            GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut pid));
+        }
+        if pid == 0 {
+            continue;
+        }
+        let pid = sysinfo::Pid::from_u32(pid);
+        system.refresh_process(pid);
+        let Some(app) = system.process(pid).map(|process| process.name().to_string()) else {
+            continue;
+        };
+
+        if current.as_deref() == Some(app.as_str()) {
+            candidate = None;
+            continue;
+        }
+        match &candidate {
+            Some((pending, since)) if *pending == app => {
+                // The first app of a session has nothing to switch from
+                if current.is_some() {
+                    let switch = AppSwitch {
+                        timestamp: *since,
+                        from_category: current.as_deref().map(|from| app_category(from).to_string()),
+                        to_category: Some(app_category(&app).to_string()),
+                        from_app: current.take(),
+                        to_app: app.clone(),
+                    };
+                    if sender.send(switch).is_err() {
+                        return;
+                    }
+                }
+                current = Some(app);
+                candidate = None;
+            }
+            _ => candidate = Some((app, Utc::now())),
+        }
+    }
+}
+
+/// Cadence and ping-pong runs of `switches` (oldest first) over `window`
+fn cadence(switches: &[AppSwitch], window: Duration) -> SwitchCadence {
+    let mut dwells: Vec<f32> = switches
+        .windows(2)
+        .map(|pair| (pair[1].timestamp - pair[0].timestamp).num_milliseconds() as f32 / 1000.0)
+        .collect();
+    dwells.sort_by(|a, b| a.total_cmp(b));
+    let median_dwell_secs = (!dwells.is_empty()).then(|| dwells[dwells.len() / 2]);
+
+    let minutes = (window.num_seconds() as f32 / 60.0).max(1.0 / 60.0);
+    SwitchCadence {
+        window_secs: window.num_seconds().max(0) as u64,
+        switches: switches.len() as u32,
+        switches_per_minute: switches.len() as f32 / minutes,
+        median_dwell_secs,
+        ping_pong: ping_pong_runs(switches),
+    }
+}
+
+/// Runs of switches going back and forth between the same two apps without settling in either
+fn ping_pong_runs(switches: &[AppSwitch]) -> Vec<PingPong> {
+    let reverses = |previous: &AppSwitch, next: &AppSwitch| {
+        previous.from_app.as_deref() == Some(next.to_app.as_str())
+            && next.from_app.as_deref() == Some(previous.to_app.as_str())
+            && next.timestamp - previous.timestamp <= PING_PONG_MAX_DWELL
+    };
+
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for end in 1..=switches.len() {
+        if end < switches.len() && reverses(&switches[end - 1], &switches[end]) {
+            continue;
+        }
+        let run = &switches[start..end];
+        if run.len() >= PING_PONG_MIN_SWITCHES {
+            let (first, last) = (&run[0], &run[run.len() - 1]);
+            runs.push(PingPong {
+                first_app: first.from_app.clone().unwrap_or_default(),
+                second_app: first.to_app.clone(),
+                first_category: first.from_category.clone(),
+                second_category: first.to_category.clone(),
+                switches: run.len() as u32,
+                started_at: first.timestamp,
+                ended_at: last.timestamp,
+            });
+        }
+        start = end;
+    }
+    runs
+}
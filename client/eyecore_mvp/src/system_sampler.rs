@@ -0,0 +1,94 @@
+//! Host resource readings for `system_metrics`: CPU overall and per core, memory, and space used per
+//! disk. The sysinfo handles live across cycles, since CPU usage is the delta between two refreshes.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use sysinfo::{Disks, System};
+
+use crate::models::DiskUtilization;
+
+/// Mounted disks are re-listed this often to pick up drives plugged in or removed
+const DISK_LIST_REFRESH: Duration = Duration::from_secs(300);
+/// Read-only images (snap packages, live media) are always full and say nothing about free space
+const IGNORED_FILE_SYSTEMS: [&str; 2] = ["squashfs", "iso9660"];
+
+pub struct SystemSample {
+    pub cpu_usage: f32,
+    pub per_core_cpu: Vec<f32>,
+    pub memory_usage: f32,
+    pub disk_usage: f32,           // space used across fixed disks
+    pub disks: Vec<DiskUtilization>,
+}
+
+pub struct SystemSampler {
+    system: System,
+    disks: Disks,
+    last_disk_list: Instant,
+}
+
+impl SystemSampler {
+    pub fn new() -> Self {
+        SystemSampler {
+            system: System::new(),
+            disks: Disks::new_with_refreshed_list(),
+            last_disk_list: Instant::now(),
+        }
+    }
+
+    pub fn sample(&mut self) -> SystemSample {
+        self.system.refresh_cpu();
+        self.system.refresh_memory();
+        if self.last_disk_list.elapsed() >= DISK_LIST_REFRESH {
+            self.disks.refresh_list();
+            self.last_disk_list = Instant::now();
+        } else {
+            self.disks.refresh();
+        }
+
+        let total_memory = self.system.total_memory();
+        let memory_usage = if total_memory > 0 {
+            self.system.used_memory() as f32 / total_memory as f32 * 100.0
+        } else {
+            0.0
+        };
+
+        let disks = self.disk_utilization();
+        let (used, total) = disks
+            .iter()
+            .filter(|disk| !disk.removable)
+            .fold((0u64, 0u64), |(used, total), disk| (used + disk.used_bytes, total + disk.total_bytes));
+
+        SystemSample {
+            cpu_usage: self.system.global_cpu_info().cpu_usage(),
+            per_core_cpu: self.system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+            memory_usage,
+            disk_usage: if total > 0 { used as f32 / total as f32 * 100.0 } else { 0.0 },
+            disks,
+        }
+    }
+
+    fn disk_utilization(&self) -> Vec<DiskUtilization> {
+        // A device mounted several times (bind mounts, btrfs subvolumes) is counted once
+        let mut seen = HashSet::new();
+        self.disks
+            .iter()
+            .filter(|disk| disk.total_space() > 0)
+            .filter(|disk| !IGNORED_FILE_SYSTEMS.contains(&disk.file_system().to_string_lossy().as_ref()))
+            .filter(|disk| {
+                let name = disk.name().to_string_lossy();
+                name.is_empty() || seen.insert(name.to_string())
+            })
+            .map(|disk| {
+                let used_bytes = disk.total_space().saturating_sub(disk.available_space());
+                DiskUtilization {
+                    name: disk.name().to_string_lossy().to_string(),
+                    mount_point: disk.mount_point().to_string_lossy().to_string(),
+                    total_bytes: disk.total_space(),
+                    used_bytes,
+                    used_percent: used_bytes as f32 / disk.total_space() as f32 * 100.0,
+                    removable: disk.is_removable(),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+
+use crate::discovery::{Discovery, Peer};
+use crate::meeting::MEETING_APPS;
+use crate::models::EyeCoreData;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const AUTH_HEADER: &str = "x-eyecore-team-auth";
+pub const DEVICE_HEADER: &str = "x-eyecore-device-id";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+const MAX_CLOCK_SKEW_SECS: i64 = 120;
+
+/// The only information a device shares with its teammates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamStatus {
+    pub device_id: String,
+    pub device_name: String,
+    pub focus_state: String, // "focused", "available", "idle", "unknown"
+    pub in_meeting: bool,
+    pub do_not_disturb: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A teammate as seen from this device
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamMember {
+    pub status: Option<TeamStatus>,
+    pub reachable: bool,
+    pub last_error: Option<String>,
+    pub last_contact: Option<DateTime<Utc>>,
+}
+
+/// Opt-in team awareness: exchanges high-level availability with mDNS peers
+/// sharing the same team secret
+pub struct TeamMode {
+    secret: Vec<u8>,
+    device_id: String,
+    device_name: String,
+    do_not_disturb: RwLock<bool>,
+    members: Arc<RwLock<HashMap<String, TeamMember>>>,
+}
+
+impl TeamMode {
+    pub fn new(secret: String, device_id: String) -> Self {
+        TeamMode {
+            secret: secret.into_bytes(),
+            device_id,
+            device_name: sysinfo::System::host_name().unwrap_or_else(|| "eyecore".to_string()),
+            do_not_disturb: RwLock::new(false),
+            members: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub async fn set_do_not_disturb(&self, enabled: bool) {
+        *self.do_not_disturb.write().await = enabled;
+    }
+
+    /// Summarize the latest snapshot into shareable status
+    pub async fn local_status(&self, latest: Option<&EyeCoreData>) -> TeamStatus {
+        let (focus_state, in_meeting) = match latest {
+            Some(data) => {
+                let in_meeting = match &data.meeting {
+                    Some(meeting) => meeting.in_meeting,
+                    None => {
+                        let title = data.process_data.active_window_title.to_lowercase();
+                        MEETING_APPS.iter().any(|app| title.contains(app))
+                    }
+                };
+                // Listening on a call is not idle
+                let focus_state = if data.input_metrics.idle_duration_seconds > 300 && !in_meeting {
+                    "idle"
+                } else if data.focus_metrics.focus_level >= 0.7 {
+                    "focused"
+                } else {
+                    "available"
+                };
+                (focus_state, in_meeting)
+            }
+            None => ("unknown", false),
+        };
+
+        TeamStatus {
+            device_id: self.device_id.clone(),
+            device_name: self.device_name.clone(),
+            focus_state: focus_state.to_string(),
+            in_meeting,
+            do_not_disturb: *self.do_not_disturb.read().await,
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub async fn members(&self) -> Vec<TeamMember> {
+        let mut members: Vec<TeamMember> = self.members.read().await.values().cloned().collect();
+        members.sort_by(|a, b| {
+            let name = |m: &TeamMember| m.status.as_ref().map(|s| s.device_name.clone());
+            name(a).cmp(&name(b))
+        });
+        members
+    }
+
+    /// Auth header value for a message: "<unix timestamp>:<hex hmac>"
+    pub fn sign(&self, device_id: &str, body: &[u8]) -> String {
+        let timestamp = Utc::now().timestamp();
+        format!("{}:{}", timestamp, self.mac_hex(device_id, timestamp, body))
+    }
+
+    /// Check an auth header produced by `sign` with the shared secret
+    pub fn verify(&self, device_id: &str, body: &[u8], header: &str) -> Result<(), String> {
+        let (timestamp, mac_hex) = header.split_once(':').ok_or("Malformed auth header")?;
+        let timestamp: i64 = timestamp.parse().map_err(|_| "Malformed auth timestamp")?;
+        if (Utc::now().timestamp() - timestamp).abs() > MAX_CLOCK_SKEW_SECS {
+            return Err("Auth timestamp outside allowed window".to_string());
+        }
+
+        let expected = hex::decode(mac_hex).map_err(|_| "Malformed auth signature")?;
+        self.mac(device_id, timestamp, body)
+            .verify_slice(&expected).map_err(|_| "Invalid team signature".to_string())
+    }
+
+    fn mac(&self, device_id: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(device_id.as_bytes());
+        mac.update(b"|");
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b"|");
+        mac.update(body);
+        mac
+    }
+
+    fn mac_hex(&self, device_id: &str, timestamp: i64, body: &[u8]) -> String {
+        hex::encode(self.mac(device_id, timestamp, body).finalize().into_bytes())
+    }
+
+    /// Poll discovered peers for their team status in a background task
+    pub fn start(self: Arc<Self>, discovery: Arc<Discovery>) {
+        tokio::spawn(async move {
+            let client = reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(5))
+                .build()
+                .unwrap_or_default();
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                // Forget teammates that stopped advertising
+                let peers = discovery.peers().await;
+                self.members
+                    .write()
+                    .await
+                    .retain(|name, _| peers.iter().any(|peer| &peer.name == name));
+
+                for peer in peers {
+                    let result = self.fetch_peer_status(&client, &peer).await;
+                    let mut members = self.members.write().await;
+                    let member = members.entry(peer.name.clone()).or_insert(TeamMember {
+                        status: None,
+                        reachable: false,
+                        last_error: None,
+                        last_contact: None,
+                    });
+
+                    match result {
+                        Ok(status) => {
+                            member.status = Some(status);
+                            member.reachable = true;
+                            member.last_error = None;
+                            member.last_contact = Some(Utc::now());
+                        }
+                        Err(e) => {
+                            if member.reachable {
+                                warn!("Teammate {} unreachable: {}", peer.name, e);
+                            }
+                            member.reachable = false;
+                            member.last_error = Some(e);
+                        }
+                    }
+                }
+            }
+        });
+
+        info!("✓ Team mode started");
+    }
+
+    async fn fetch_peer_status(&self, client: &reqwest::Client, peer: &Peer) -> Result<TeamStatus, String> {
+        let address = peer
+            .addresses
+            .iter()
+            .find(|addr| !addr.contains(':'))
+            .or_else(|| peer.addresses.first())
+            .ok_or("Peer has no address")?;
+        let host = if address.contains(':') { format!("[{}]", address) } else { address.clone() };
+        let url = format!("http://{}:{}/team/peer-status", host, peer.port);
+
+        let response = client
+            .get(&url)
+            .header(DEVICE_HEADER, &self.device_id)
+            .header(AUTH_HEADER, self.sign(&self.device_id, b""))
+            .send()
+            .await
+            .map_err(|e| format!("Request error: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Peer returned {}", response.status()));
+        }
+
+        // The peer signs its response body with the same secret
+        let auth = response
+            .headers()
+            .get(AUTH_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+            .ok_or("Peer response is not signed")?;
+        let body = response.bytes().await.map_err(|e| format!("Read error: {}", e))?;
+
+        let status: TeamStatus = serde_json::from_slice(&body).map_err(|e| format!("JSON parse error: {}", e))?;
+        self.verify(&status.device_id, &body, &auth)?;
+        Ok(status)
+    }
+}
@@ -0,0 +1,426 @@
+//! Time entries for time-tracking tools (Toggl Track, Clockify or Jira Tempo), made from the blocks
+//! of work on projects mapped in `[time_export.projects]`. Entries are drafted per day into a review
+//! queue kept in `time_entries.json` in the device directory; nothing leaves the device until an
+//! entry is submitted through `/time_entries/{id}/submit`. The API token comes from
+//! EYECORE_TIME_EXPORT_TOKEN.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::classification::AppClassification;
+use crate::http::HttpClient;
+use crate::models::{EyeCoreData, PresenceState, Productivity, TimeEntry, TimeEntryStatus};
+use crate::projects::ProjectDetector;
+use crate::storage::DataStorage;
+
+/// Review queue in the device directory
+const TIME_ENTRIES_FILE: &str = "time_entries.json";
+/// Snapshots further apart than this do not add to a block
+const MAX_SAMPLE_GAP_SECS: i64 = 60;
+/// Entries older than this are dropped from the queue
+pub const KEEP_DAYS: i64 = 90;
+pub const MAX_DESCRIPTION_LENGTH: usize = 500;
+const DRAFT_CHECK_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_secs(600);
+
+const TOGGL_API: &str = "https://api.track.toggl.com/api/v9";
+const CLOCKIFY_API: &str = "https://api.clockify.me/api/v1";
+const TEMPO_API: &str = "https://api.tempo.io/4";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeTrackingProvider {
+    Toggl,
+    Clockify,
+    Tempo,         // Jira worklogs through Tempo
+}
+
+/// `[time_export]` settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeExportConfig {
+    pub provider: Option<TimeTrackingProvider>,    // off if unset
+    pub workspace_id: Option<String>,              // toggl and clockify
+    pub author_account_id: Option<String>,         // tempo: the Jira account the worklogs are for
+    pub min_block_minutes: u32,                    // shorter work on a project makes no entry
+    pub merge_gap_minutes: u32,                    // breaks up to this long do not split an entry
+    pub daily: bool,                               // draft yesterday's entries every day
+    pub projects: BTreeMap<String, TimeEntryMapping>, // detected project name -> where its time goes
+}
+
+impl Default for TimeExportConfig {
+    fn default() -> Self {
+        TimeExportConfig {
+            provider: None,
+            workspace_id: None,
+            author_account_id: None,
+            min_block_minutes: 15,
+            merge_gap_minutes: 5,
+            daily: true,
+            projects: BTreeMap::new(),
+        }
+    }
+}
+
+/// `[time_export.projects.<name>]`: the tool's project (which carries the client) and task, or the
+/// Jira issue for Tempo
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeEntryMapping {
+    pub project_id: Option<String>,    // toggl and clockify
+    pub task_id: Option<String>,       // toggl and clockify
+    pub issue_id: Option<String>,      // tempo: numeric Jira issue ID
+    pub billable: bool,                // toggl and clockify
+}
+
+impl TimeExportConfig {
+    /// The tool entries are submitted to, for the processing register; None when export is off
+    pub fn recipient(&self) -> Option<String> {
+        let (tool, api) = match self.provider? {
+            TimeTrackingProvider::Toggl => ("Toggl Track", TOGGL_API),
+            TimeTrackingProvider::Clockify => ("Clockify", CLOCKIFY_API),
+            TimeTrackingProvider::Tempo => ("Jira Tempo", TEMPO_API),
+        };
+        Some(format!("{} ({})", tool, api))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_block_minutes == 0 {
+            return Err("time_export.min_block_minutes must be at least 1".to_string());
+        }
+        if self.merge_gap_minutes > self.min_block_minutes {
+            return Err("time_export.merge_gap_minutes must be at most time_export.min_block_minutes".to_string());
+        }
+        let Some(provider) = self.provider else { return Ok(()) };
+        match provider {
+            TimeTrackingProvider::Toggl | TimeTrackingProvider::Clockify if self.workspace_id.is_none() => {
+                return Err("time_export.workspace_id is required for toggl and clockify".to_string());
+            }
+            TimeTrackingProvider::Tempo if self.author_account_id.is_none() => {
+                return Err("time_export.author_account_id is required for tempo".to_string());
+            }
+            _ => {}
+        }
+        let numeric = |value: &Option<String>| value.as_deref().is_none_or(|value| value.parse::<i64>().is_ok());
+        if provider == TimeTrackingProvider::Toggl && !numeric(&self.workspace_id) {
+            return Err("time_export.workspace_id must be a number for toggl".to_string());
+        }
+        for (name, mapping) in &self.projects {
+            match provider {
+                TimeTrackingProvider::Toggl if !numeric(&mapping.project_id) || !numeric(&mapping.task_id) => {
+                    return Err(format!("time_export.projects.{}: toggl project_id and task_id must be numbers", name));
+                }
+                TimeTrackingProvider::Tempo if mapping.issue_id.as_deref().is_none_or(|id| id.parse::<i64>().is_err()) => {
+                    return Err(format!("time_export.projects.{}: tempo needs a numeric issue_id", name));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Queue {
+    entries: Vec<TimeEntry>,
+    drafted_through: Option<NaiveDate>,    // the last day drafted automatically
+}
+
+pub struct TimeExporter {
+    config: TimeExportConfig,
+    token: Option<String>,
+    http: HttpClient,
+    device_dir: PathBuf,
+    queue: Queue,
+}
+
+impl TimeExporter {
+    /// The review queue kept in `device_dir`
+    pub fn load(config: TimeExportConfig, http: HttpClient, device_dir: &Path) -> Self {
+        let path = device_dir.join(TIME_ENTRIES_FILE);
+        let queue = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("⚠️ Ignoring unreadable time entries {:?}: {}", path, e);
+                Queue::default()
+            }),
+            Err(_) => Queue::default(),
+        };
+        let token = std::env::var("EYECORE_TIME_EXPORT_TOKEN").ok().filter(|token| !token.trim().is_empty());
+        TimeExporter { config, token, http, device_dir: device_dir.to_path_buf(), queue }
+    }
+
+    pub fn provider(&self) -> Option<TimeTrackingProvider> {
+        self.config.provider
+    }
+
+    /// Queued entries, oldest first, optionally only those with `status`
+    pub fn entries(&self, status: Option<TimeEntryStatus>) -> Vec<TimeEntry> {
+        self.queue.entries.iter().filter(|entry| status.is_none_or(|status| entry.status == status)).cloned().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&TimeEntry> {
+        self.queue.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Draft the entries of `date` for review, replacing its pending ones. Blocks overlapping an
+    /// entry that was already submitted or discarded are left out.
+    pub async fn draft(&mut self, storage: &DataStorage, date: NaiveDate) -> Result<Vec<TimeEntry>, String> {
+        let snapshots = storage.load_snapshots(date).await.map_err(|e| format!("Failed to load snapshots: {}", e))?;
+        let detector = ProjectDetector::load(&storage.device_dir());
+        let classification = AppClassification::load(&storage.device_dir());
+        let mapped: Vec<String> = self.mappings(&detector).into_iter().map(|(name, _)| name).collect();
+        let blocks = work_blocks(&snapshots, &detector, &classification, &mapped, &self.config);
+
+        let on_date = |entry: &TimeEntry| entry.start.date_naive() == date;
+        self.queue.entries.retain(|entry| !(entry.status == TimeEntryStatus::Pending && on_date(entry)));
+        let mut drafted = Vec::new();
+        for (project, start, end) in blocks {
+            let reviewed = self.queue.entries.iter().any(|entry| entry.project == project && entry.start < end && start < entry.end);
+            if reviewed {
+                continue;
+            }
+            drafted.push(TimeEntry {
+                id: uuid::Uuid::new_v4().to_string(),
+                description: project.clone(),
+                project,
+                start,
+                end,
+                minutes: (end - start).num_minutes() as u32,
+                status: TimeEntryStatus::Pending,
+                remote_id: None,
+                submitted_at: None,
+                last_error: None,
+            });
+        }
+        self.queue.entries.extend(drafted.iter().cloned());
+        self.queue.entries.sort_by_key(|entry| entry.start);
+        let cutoff = Utc::now() - Duration::days(KEEP_DAYS);
+        self.queue.entries.retain(|entry| entry.end >= cutoff);
+        self.save()?;
+        info!("⏱️ {} time entries drafted for {}", drafted.len(), date);
+        Ok(drafted)
+    }
+
+    /// Why `entry` cannot be submitted yet, if it cannot
+    pub fn check_submittable(&self, entry: &TimeEntry) -> Result<(), String> {
+        if self.config.provider.is_none() {
+            return Err("No time_export.provider configured".to_string());
+        }
+        if self.token.is_none() {
+            return Err("EYECORE_TIME_EXPORT_TOKEN is not set".to_string());
+        }
+        if entry.status != TimeEntryStatus::Pending {
+            return Err("Only pending time entries can be submitted".to_string());
+        }
+        if self.mapping(&entry.project).is_none() {
+            return Err(format!("Project {:?} is not mapped in time_export.projects", entry.project));
+        }
+        Ok(())
+    }
+
+    /// Send a pending entry to the time-tracking tool, with `description` replacing the drafted one
+    pub async fn submit(&mut self, id: &str, description: Option<String>) -> Result<TimeEntry, String> {
+        let index = self.queue.entries.iter().position(|entry| entry.id == id).ok_or("Unknown time entry")?;
+        self.check_submittable(&self.queue.entries[index])?;
+        let (Some(provider), Some(token), Some(mapping)) =
+            (self.config.provider, self.token.clone(), self.mapping(&self.queue.entries[index].project))
+        else {
+            return Err("Time entry cannot be submitted".to_string());
+        };
+        if let Some(description) = description {
+            self.queue.entries[index].description = description;
+        }
+        let entry = self.queue.entries[index].clone();
+
+        let result = self.send(provider, &token, &entry, &mapping).await;
+        let entry = &mut self.queue.entries[index];
+        match &result {
+            Ok(remote_id) => {
+                entry.status = TimeEntryStatus::Submitted;
+                entry.remote_id = Some(remote_id.clone());
+                entry.submitted_at = Some(Utc::now());
+                entry.last_error = None;
+                info!("⏱️ Time entry for {} submitted ({} min)", entry.project, entry.minutes);
+            }
+            Err(e) => entry.last_error = Some(e.clone()),
+        }
+        let entry = entry.clone();
+        self.save()?;
+        result.map(|_| entry)
+    }
+
+    /// Drop a pending entry without submitting it; it is not drafted again
+    pub fn discard(&mut self, id: &str) -> Result<TimeEntry, String> {
+        let entry = self.queue.entries.iter_mut().find(|entry| entry.id == id).ok_or("Unknown time entry")?;
+        if entry.status != TimeEntryStatus::Pending {
+            return Err("Only pending time entries can be discarded".to_string());
+        }
+        entry.status = TimeEntryStatus::Discarded;
+        let entry = entry.clone();
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// The mapped projects by their current names, so configured aliases and old names still apply
+    fn mappings(&self, detector: &ProjectDetector) -> Vec<(String, TimeEntryMapping)> {
+        self.config
+            .projects
+            .iter()
+            .map(|(name, mapping)| {
+                let name = detector.resolve(name).map_or_else(|| name.clone(), |project| project.name.clone());
+                (name, mapping.clone())
+            })
+            .collect()
+    }
+
+    fn mapping(&self, project: &str) -> Option<TimeEntryMapping> {
+        let detector = ProjectDetector::load(&self.device_dir);
+        self.mappings(&detector).into_iter().find(|(name, _)| name.eq_ignore_ascii_case(project)).map(|(_, mapping)| mapping)
+    }
+
+    /// Post the entry, returning its ID in the tool
+    async fn send(&self, provider: TimeTrackingProvider, token: &str, entry: &TimeEntry, mapping: &TimeEntryMapping) -> Result<String, String> {
+        let workspace = self.config.workspace_id.clone().unwrap_or_default();
+        let number = |value: &Option<String>| value.as_deref().and_then(|value| value.parse::<i64>().ok());
+        let (request, id_field) = match provider {
+            TimeTrackingProvider::Toggl => {
+                let body = json!({
+                    "created_with": "eyecore",
+                    "description": entry.description,
+                    "start": entry.start.to_rfc3339(),
+                    "stop": entry.end.to_rfc3339(),
+                    "duration": (entry.end - entry.start).num_seconds(),
+                    "workspace_id": number(&self.config.workspace_id),
+                    "project_id": number(&mapping.project_id),
+                    "task_id": number(&mapping.task_id),
+                    "billable": mapping.billable,
+                });
+                let url = format!("{}/workspaces/{}/time_entries", TOGGL_API, workspace);
+                (self.http.post(&url).basic_auth(token, Some("api_token")).json(&body), "id")
+            }
+            TimeTrackingProvider::Clockify => {
+                let body = json!({
+                    "description": entry.description,
+                    "start": entry.start.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    "end": entry.end.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    "projectId": mapping.project_id,
+                    "taskId": mapping.task_id,
+                    "billable": mapping.billable,
+                });
+                let url = format!("{}/workspaces/{}/time-entries", CLOCKIFY_API, workspace);
+                (self.http.post(&url).header("X-Api-Key", token).json(&body), "id")
+            }
+            TimeTrackingProvider::Tempo => {
+                // Tempo wants the local date and time the work started
+                let start = entry.start.with_timezone(&chrono::Local);
+                let body = json!({
+                    "issueId": number(&mapping.issue_id),
+                    "authorAccountId": self.config.author_account_id,
+                    "description": entry.description,
+                    "startDate": start.format("%Y-%m-%d").to_string(),
+                    "startTime": start.format("%H:%M:%S").to_string(),
+                    "timeSpentSeconds": (entry.end - entry.start).num_seconds(),
+                });
+                (self.http.post(&format!("{}/worklogs", TEMPO_API)).bearer_auth(token).json(&body), "tempoWorklogId")
+            }
+        };
+
+        let response = self.http.send(request).await?;
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(format!("{:?} answered {}: {}", provider, status, body));
+        }
+        match &body[id_field] {
+            serde_json::Value::String(id) => Ok(id.clone()),
+            serde_json::Value::Number(id) => Ok(id.to_string()),
+            _ => Err(format!("{:?} answered without an entry ID: {}", provider, body)),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = self.device_dir.join(TIME_ENTRIES_FILE);
+        let content = serde_json::to_string_pretty(&self.queue).map_err(|e| e.to_string())?;
+        std::fs::write(&path, content).map_err(|e| format!("Failed to save time entries {:?}: {}", path, e))
+    }
+}
+
+/// Draft yesterday's entries once the day has rolled over, if `[time_export] daily` is on
+pub fn start_daily_drafts(exporter: Arc<RwLock<TimeExporter>>, storage: Arc<DataStorage>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(DRAFT_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let yesterday = Utc::now().date_naive() - Duration::days(1);
+            let mut exporter = exporter.write().await;
+            if !exporter.config.daily || exporter.config.projects.is_empty() || exporter.queue.drafted_through >= Some(yesterday) {
+                continue;
+            }
+            exporter.queue.drafted_through = Some(yesterday);
+            if let Err(e) = exporter.draft(&storage, yesterday).await {
+                error!("Failed to draft time entries: {}", e);
+            }
+        }
+    });
+}
+
+/// Blocks of work on the `mapped` projects in a day's snapshots, as (project, start, end). Time in
+/// distracting apps, away or in an unknown window does not count. Fragments shorter than the merge
+/// gap are dropped, fragments of the same project with at most that gap between them are joined,
+/// and blocks shorter than the minimum are dropped.
+fn work_blocks(
+    snapshots: &[EyeCoreData],
+    detector: &ProjectDetector,
+    classification: &AppClassification,
+    mapped: &[String],
+    config: &TimeExportConfig,
+) -> Vec<(String, DateTime<Utc>, DateTime<Utc>)> {
+    let merge_gap = Duration::minutes(config.merge_gap_minutes as i64);
+    let min_block = Duration::minutes(config.min_block_minutes as i64);
+
+    let mut fragments: Vec<(String, DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for (i, data) in snapshots.iter().enumerate() {
+        let Some(seconds) = snapshots
+            .get(i + 1)
+            .map(|next| (next.timestamp - data.timestamp).num_seconds())
+            .filter(|gap| (1..=MAX_SAMPLE_GAP_SECS).contains(gap))
+        else {
+            continue;
+        };
+        let process = &data.process_data;
+        let away = data.presence.as_ref().is_some_and(|presence| presence.state == PresenceState::Away);
+        if process.active_process == "unknown" || away {
+            continue;
+        }
+        if classification.classify(&process.active_process, &process.active_window_title).productivity == Productivity::Distracting {
+            continue;
+        }
+        let Some(project) = process.project.as_deref().and_then(|name| detector.resolve(name)).map(|project| project.name.clone()) else {
+            continue;
+        };
+        if !mapped.iter().any(|name| name.eq_ignore_ascii_case(&project)) {
+            continue;
+        }
+        let end = data.timestamp + Duration::seconds(seconds);
+        match fragments.last_mut() {
+            Some((last, _, last_end)) if *last == project && *last_end == data.timestamp => *last_end = end,
+            _ => fragments.push((project, data.timestamp, end)),
+        }
+    }
+
+    let mut blocks: Vec<(String, DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for (project, start, end) in fragments.into_iter().filter(|(_, start, end)| *end - *start >= merge_gap) {
+        match blocks.last_mut() {
+            Some((last, _, last_end)) if *last == project && start - *last_end <= merge_gap => *last_end = end,
+            _ => blocks.push((project, start, end)),
+        }
+    }
+    blocks.retain(|(_, start, end)| *end - *start >= min_block);
+    blocks
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use log::warn;
+
+use crate::models::{CollectionGap, DailyCoverage, SessionNote};
+
+/// Snapshots further apart than this mean collection was not running, however short the interval
+pub const GAP_THRESHOLD_SECS: i64 = 30;
+
+/// Snapshots this many intervals apart (plus the time a collection takes) are a gap, so a skipped
+/// tick or a slow collection is not one
+const GAP_INTERVALS: i32 = 3;
+const COLLECTION_SLACK_SECS: i64 = 10;
+
+/// Notes are stored under their start day, so a day's notes are found by also reading the day
+/// before; this keeps a note from reaching any further
+pub const MAX_NOTE_SPAN_HOURS: i64 = 24;
+
+/// Detects holes in the snapshot timeline (crashes, reboots, sleep)
+pub struct GapDetector {
+    last_snapshot: Option<DateTime<Utc>>,
+    first_observation: bool,
+    paused: bool,
+    interval: Duration,
+    /// Longest interval in effect since the last snapshot, which the next one may still be due at
+    longest_interval: Duration,
+}
+
+impl GapDetector {
+    /// `last_snapshot` is the newest snapshot already on disk, if any; `interval` is the snapshot
+    /// interval in effect
+    pub fn new(last_snapshot: Option<DateTime<Utc>>, interval: std::time::Duration) -> Self {
+        let interval = Duration::from_std(interval).unwrap_or(Duration::MAX);
+        GapDetector {
+            last_snapshot,
+            first_observation: true,
+            paused: false,
+            interval,
+            longest_interval: interval,
+        }
+    }
+
+    /// The snapshot interval changed (battery saving, bursts); the next snapshot may still be due
+    /// at the old one
+    pub fn set_interval(&mut self, interval: std::time::Duration) {
+        self.interval = Duration::from_std(interval).unwrap_or(Duration::MAX);
+        self.longest_interval = self.longest_interval.max(self.interval);
+    }
+
+    /// Longest time between snapshots that is not a gap
+    fn threshold(&self) -> Duration {
+        let expected = self
+            .longest_interval
+            .checked_mul(GAP_INTERVALS)
+            .and_then(|t| t.checked_add(&Duration::seconds(COLLECTION_SLACK_SECS)))
+            .unwrap_or(Duration::MAX);
+        expected.max(Duration::seconds(GAP_THRESHOLD_SECS))
+    }
+
+    /// Collection was deliberately skipped (maintenance mode); the next gap is attributed to it
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Record a new snapshot timestamp, returning the gap before it if there was one
+    pub fn observe(&mut self, timestamp: DateTime<Utc>) -> Option<CollectionGap> {
+        let previous = self.last_snapshot.replace(timestamp);
+        let first_observation = std::mem::replace(&mut self.first_observation, false);
+        let paused = std::mem::replace(&mut self.paused, false);
+        let threshold = self.threshold();
+        self.longest_interval = self.interval;
+
+        let previous = previous?;
+        let elapsed = timestamp - previous;
+        if elapsed <= threshold {
+            return None;
+        }
+
+        // A gap before the first snapshot of this process means EyeCore wasn't running
+        let reason = if first_observation {
+            "not_running"
+        } else if paused {
+            "maintenance"
+        } else {
+            "collection_stalled"
+        };
+        warn!("⏸ Collection gap of {}s detected ({})", elapsed.num_seconds(), reason);
+
+        Some(CollectionGap {
+            gap_type: "collection_gap".to_string(),
+            start: previous,
+            end: timestamp,
+            duration_seconds: elapsed.num_seconds() as u64,
+            reason: reason.to_string(),
+        })
+    }
+}
+
+/// Summarize how much of `date` (UTC) was not covered by collection, with the user's notes for it
+pub fn daily_coverage(date: NaiveDate, snapshot_count: usize, gaps: &[CollectionGap], notes: &[SessionNote]) -> DailyCoverage {
+    let day_start = date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let day_end = day_start + Duration::days(1);
+
+    let day_gaps: Vec<CollectionGap> = gaps
+        .iter()
+        .filter(|gap| gap.start < day_end && gap.end > day_start)
+        .cloned()
+        .collect();
+
+    // Gaps spanning midnight only count towards the part inside this day
+    let uncovered_seconds = day_gaps
+        .iter()
+        .map(|gap| (gap.end.min(day_end) - gap.start.max(day_start)).num_seconds().max(0) as u64)
+        .sum();
+
+    let mut day_notes: Vec<SessionNote> = notes
+        .iter()
+        .filter(|note| note.start < day_end && note.end >= day_start)
+        .cloned()
+        .collect();
+    day_notes.sort_by_key(|note| note.start);
+
+    DailyCoverage {
+        date: date.format("%Y-%m-%d").to_string(),
+        snapshot_count,
+        gap_count: day_gaps.len(),
+        uncovered_seconds,
+        gaps: day_gaps,
+        notes: day_notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn short_intervals_use_the_minimum_threshold() {
+        let mut detector = GapDetector::new(Some(at(0)), std::time::Duration::from_secs(5));
+        assert!(detector.observe(at(25)).is_none());
+        let gap = detector.observe(at(70)).expect("45s without a snapshot is a gap");
+        assert_eq!(gap.reason, "collection_stalled");
+        assert_eq!(gap.duration_seconds, 45);
+    }
+
+    #[test]
+    fn long_intervals_are_not_gaps() {
+        let mut detector = GapDetector::new(Some(at(0)), std::time::Duration::from_secs(60));
+        assert_eq!(detector.observe(at(60)).map(|gap| gap.reason), None);
+        assert!(detector.observe(at(180)).is_none());
+        let gap = detector.observe(at(400)).expect("more than three intervals is a gap");
+        assert_eq!(gap.reason, "collection_stalled");
+    }
+
+    #[test]
+    fn interval_changes_move_the_threshold() {
+        // Battery saving triples a 20s interval
+        let mut detector = GapDetector::new(Some(at(0)), std::time::Duration::from_secs(20));
+        detector.set_interval(std::time::Duration::from_secs(60));
+        assert!(detector.observe(at(60)).is_none());
+        assert!(detector.observe(at(180)).is_none());
+
+        // Back on mains, the snapshot already due at the long interval is not a gap, later ones are
+        detector.set_interval(std::time::Duration::from_secs(20));
+        assert!(detector.observe(at(240)).is_none());
+        assert!(detector.observe(at(260)).is_none());
+        assert!(detector.observe(at(340)).is_some());
+    }
+}
@@ -0,0 +1,157 @@
+//! Writes the research input trace (see input_trace) once a second to
+//! `<day>/input_trace/<HHMMSSmmm>.eytrace`. A new file starts at `max_file_mb`, at midnight and at
+//! startup, and the oldest files are deleted once all of them pass `max_total_mb`.
+
+use bincode::Options;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::{error, info};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
+
+use crate::input_trace::{trace_event, InputTrace, TraceEvent, TraceRecord, FILE_MAGIC};
+use crate::storage::DataStorage;
+
+const CATEGORY: &str = "input_trace";
+
+struct OpenFile {
+    path: PathBuf,
+    date: NaiveDate,
+    bytes: u64,
+    last_event: SystemTime,
+    position: (i32, i32),
+}
+
+struct TraceWriter {
+    trace: Arc<InputTrace>,
+    storage: Arc<DataStorage>,
+    max_file_bytes: u64,
+    max_total_bytes: u64,
+    // Closed and open files with their sizes, oldest first
+    files: VecDeque<(PathBuf, u64)>,
+    current: Option<OpenFile>,
+}
+
+/// Write the trace until shutdown, then write out what is still pending
+pub async fn run(trace: Arc<InputTrace>, storage: Arc<DataStorage>, stop: CancellationToken) {
+    let mut files = VecDeque::new();
+    for path in storage.list_category_files(CATEGORY, None).await.unwrap_or_default() {
+        let bytes = tokio::fs::metadata(&path).await.map_or(0, |metadata| metadata.len());
+        files.push_back((path, bytes));
+    }
+    let config = trace.config();
+    let mut writer = TraceWriter {
+        max_file_bytes: config.max_file_mb * 1024 * 1024,
+        max_total_bytes: config.max_total_mb * 1024 * 1024,
+        trace,
+        storage,
+        files,
+        current: None,
+    };
+    writer.prune().await;
+
+    let mut flush = tokio::time::interval(tokio::time::Duration::from_secs(1));
+    loop {
+        let stopping = tokio::select! {
+            _ = flush.tick() => false,
+            _ = stop.cancelled() => true,
+        };
+        if let Err(e) = writer.write(writer.trace.take()).await {
+            error!("Failed to write input trace: {}", e);
+        }
+        if stopping {
+            break;
+        }
+    }
+}
+
+impl TraceWriter {
+    async fn write(&mut self, events: Vec<(SystemTime, rdev::EventType)>) -> std::io::Result<()> {
+        let count = events.len();
+        let options = bincode::DefaultOptions::new();
+        let mut chunk = Vec::new();
+        for (at, event) in events {
+            let date = DateTime::<Utc>::from(at).date_naive();
+            let rotate = self
+                .current
+                .as_ref()
+                .is_none_or(|file| file.date != date || file.bytes + chunk.len() as u64 >= self.max_file_bytes);
+            if rotate {
+                self.append(&std::mem::take(&mut chunk)).await?;
+                self.start_file(at).await?;
+            }
+            let Some(file) = self.current.as_mut() else { continue };
+
+            let mut event = trace_event(event);
+            if let TraceEvent::Move { dx: x, dy: y } = event {
+                event = TraceEvent::Move { dx: x - file.position.0, dy: y - file.position.1 };
+                file.position = (x, y);
+            }
+            let delta_us = at.duration_since(file.last_event).map_or(0, |delta| delta.as_micros() as u64);
+            file.last_event = at;
+            options.serialize_into(&mut chunk, &TraceRecord { delta_us, event }).map_err(std::io::Error::other)?;
+        }
+        self.append(&chunk).await?;
+        self.prune().await;
+
+        let total = self.files.iter().map(|(_, bytes)| bytes).sum();
+        let current = self.current.as_ref().map(|file| file.path.clone());
+        self.trace.record_written(count, self.files.len(), total, current);
+        Ok(())
+    }
+
+    /// Begin a file whose events are timed from `at`
+    async fn start_file(&mut self, at: SystemTime) -> std::io::Result<()> {
+        let time = DateTime::<Utc>::from(at);
+        let dir = self.storage.day_dir(time.date_naive(), CATEGORY);
+        tokio::fs::create_dir_all(&dir).await?;
+        let path = dir.join(format!("{}.eytrace", time.format("%H%M%S%3f")));
+
+        let mut header = FILE_MAGIC.to_vec();
+        let start_us = at.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_micros() as i64);
+        header.extend_from_slice(&start_us.to_le_bytes());
+        tokio::fs::write(&path, &header).await?;
+
+        info!("🧬 Input trace file {:?}", path);
+        self.files.push_back((path.clone(), header.len() as u64));
+        self.current = Some(OpenFile {
+            path,
+            date: time.date_naive(),
+            bytes: header.len() as u64,
+            last_event: at,
+            position: (0, 0),
+        });
+        Ok(())
+    }
+
+    async fn append(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        let Some(file) = self.current.as_mut().filter(|_| !chunk.is_empty()) else {
+            return Ok(());
+        };
+        let mut handle = tokio::fs::OpenOptions::new().append(true).open(&file.path).await?;
+        handle.write_all(chunk).await?;
+        file.bytes += chunk.len() as u64;
+        if let Some((_, bytes)) = self.files.back_mut().filter(|(path, _)| *path == file.path) {
+            *bytes = file.bytes;
+        }
+        Ok(())
+    }
+
+    /// Delete the oldest files while all of them pass `max_total_mb`, never the one being written
+    async fn prune(&mut self) {
+        let mut total: u64 = self.files.iter().map(|(_, bytes)| bytes).sum();
+        while total > self.max_total_bytes && self.files.len() > 1 {
+            let Some((path, bytes)) = self.files.pop_front() else { break };
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => info!("🧹 Removed input trace file {:?} (over {} bytes in total)", path, self.max_total_bytes),
+                // Already removed by retention
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => error!("Failed to remove input trace file {:?}: {}", path, e),
+            }
+            total -= bytes;
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use base64::{Engine as _, engine::general_purpose};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use log::{info, error, warn};
+use sha2::{Sha256, Digest};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio_util::sync::CancellationToken;
+
+use crate::http::HttpClient;
+use crate::version::ReleaseManifest;
+
+const DOWNLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+/// A selftest that has not finished by then counts as failed, and the update is rolled back
+const SELFTEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Set for an instance started by `Updater::restart`, so it waits for the old one to release the port
+pub const RESTARTED_ENV: &str = "EYECORE_RESTARTED";
+
+/// Downloads, verifies and installs new EyeCore binaries
+pub struct Updater {
+    client: HttpClient,
+    public_key: VerifyingKey,
+    exe_path: PathBuf,
+    shutdown: CancellationToken,
+    restart_pending: AtomicBool,
+}
+
+impl Updater {
+    /// Create an updater from EYECORE_UPDATE_PUBLIC_KEY (base64 ed25519 key).
+    /// Returns None when no key is configured, so unsigned binaries are never installed.
+    /// `shutdown` is cancelled to restart into an installed update.
+    pub fn from_env(client: HttpClient, shutdown: CancellationToken) -> Result<Option<Self>, String> {
+        let encoded_key = match std::env::var("EYECORE_UPDATE_PUBLIC_KEY") {
+            Ok(key) => key,
+            Err(_) => return Ok(None),
+        };
+
+        let key_bytes: [u8; 32] = general_purpose::STANDARD
+            .decode(encoded_key.trim())
+            .map_err(|e| format!("Invalid public key encoding: {}", e))?
+            .try_into()
+            .map_err(|_| "Public key must be 32 bytes".to_string())?;
+        let public_key = VerifyingKey::from_bytes(&key_bytes)
+            .map_err(|e| format!("Invalid public key: {}", e))?;
+
+        let exe_path = std::env::current_exe()
+            .map_err(|e| format!("Failed to locate current executable: {}", e))?;
+
+        Ok(Some(Updater {
+            client,
+            public_key,
+            exe_path,
+            shutdown,
+            restart_pending: AtomicBool::new(false),
+        }))
+    }
+
+    /// Download the release, verify it, swap it in and selftest it.
+    /// The previous binary is restored if verification or the selftest fails.
+    pub async fn apply(&self, manifest: &ReleaseManifest) -> Result<(), String> {
+        let download_url = manifest.download_url.as_ref()
+            .ok_or("Release manifest has no download_url")?;
+        let expected = verify_manifest(&self.public_key, manifest)?;
+        info!("✓ Release signature verified");
+
+        info!("⬇ Downloading EyeCore {} from {}", manifest.version, download_url);
+        let binary = self.download(download_url).await?;
+
+        let actual = format!("{:x}", Sha256::digest(&binary));
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!("Checksum mismatch: expected {}, got {}", expected, actual));
+        }
+
+        let staged_path = self.sibling_path("new");
+        let backup_path = self.sibling_path("old");
+        write_executable(&staged_path, &binary)?;
+
+        // Swap atomically: current -> .old, staged -> current
+        std::fs::rename(&self.exe_path, &backup_path)
+            .map_err(|e| format!("Failed to back up current binary: {}", e))?;
+        if let Err(e) = std::fs::rename(&staged_path, &self.exe_path) {
+            let _ = std::fs::rename(&backup_path, &self.exe_path);
+            return Err(format!("Failed to install new binary: {}", e));
+        }
+
+        match self.run_selftest().await {
+            Ok(()) => {
+                info!("✓ EyeCore {} installed, previous binary kept at {:?}", manifest.version, backup_path);
+                Ok(())
+            }
+            Err(e) => {
+                error!("New binary failed selftest: {}. Rolling back...", e);
+                self.rollback(&backup_path)?;
+                Err(format!("Selftest failed, rolled back: {}", e))
+            }
+        }
+    }
+
+    /// Shut down like on SIGTERM, flushing what is still in memory; `restart` then runs once
+    /// shutdown has finished
+    pub fn request_restart(&self) {
+        info!("🔄 Update installed, shutting down to restart");
+        self.restart_pending.store(true, Ordering::SeqCst);
+        self.shutdown.cancel();
+    }
+
+    pub fn restart_pending(&self) -> bool {
+        self.restart_pending.load(Ordering::SeqCst)
+    }
+
+    /// Replace the stopped process with a fresh instance of the installed binary, which waits
+    /// for this one to release the API port
+    pub fn restart(&self) -> ! {
+        info!("🔄 Restarting EyeCore...");
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        match Command::new(&self.exe_path).args(&args).env(RESTARTED_ENV, "1").spawn() {
+            Ok(_) => std::process::exit(0),
+            Err(e) => {
+                error!("Failed to restart EyeCore: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>, String> {
+        // Binaries take longer than the default request timeout on slow links
+        let response = self.client.send(self.client.get(url).timeout(DOWNLOAD_TIMEOUT)).await?;
+
+        if !response.status().is_success() {
+            return Err(format!("Download failed with status {}", response.status()));
+        }
+
+        response.bytes().await
+            .map(|b| b.to_vec())
+            .map_err(|e| format!("Download error: {}", e))
+    }
+
+    async fn run_selftest(&self) -> Result<(), String> {
+        let mut child = tokio::process::Command::new(&self.exe_path)
+            .arg("--selftest")
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to launch selftest: {}", e))?;
+        let status = match tokio::time::timeout(SELFTEST_TIMEOUT, child.wait()).await {
+            Ok(status) => status.map_err(|e| format!("Failed to wait for selftest: {}", e))?,
+            Err(_) => {
+                let _ = child.kill().await;
+                return Err(format!("selftest did not finish within {}s", SELFTEST_TIMEOUT.as_secs()));
+            }
+        };
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("selftest exited with {}", status))
+        }
+    }
+
+    fn rollback(&self, backup_path: &Path) -> Result<(), String> {
+        let failed_path = self.sibling_path("failed");
+        let _ = std::fs::rename(&self.exe_path, &failed_path);
+        std::fs::rename(backup_path, &self.exe_path)
+            .map_err(|e| format!("Rollback failed: {}", e))?;
+        warn!("↩ Restored previous binary, failed build kept at {:?}", failed_path);
+        Ok(())
+    }
+
+    fn sibling_path(&self, suffix: &str) -> PathBuf {
+        let mut name = self.exe_path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}", suffix));
+        self.exe_path.with_file_name(name)
+    }
+}
+
+/// Check the manifest's signature and that it offers a newer version; returns the binary's
+/// expected SHA-256. The signature binds the version to the checksum, so an old signed release
+/// cannot be served as an update.
+fn verify_manifest<'a>(public_key: &VerifyingKey, manifest: &'a ReleaseManifest) -> Result<&'a str, String> {
+    let sha256 = manifest.sha256.as_deref()
+        .ok_or("Release manifest has no sha256")?;
+    let encoded_signature = manifest.signature.as_ref()
+        .ok_or("Release manifest has no signature")?;
+
+    let signature_bytes = general_purpose::STANDARD
+        .decode(encoded_signature.trim())
+        .map_err(|e| format!("Invalid signature encoding: {}", e))?;
+    let signature = Signature::from_slice(&signature_bytes)
+        .map_err(|e| format!("Invalid signature: {}", e))?;
+    public_key
+        .verify(&signed_message(&manifest.version, sha256), &signature)
+        .map_err(|_| "Signature verification failed".to_string())?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    if !crate::version::is_newer_version(&manifest.version, current) {
+        return Err(format!("Refusing to install {}: not newer than {}", manifest.version, current));
+    }
+    Ok(sha256)
+}
+
+/// What the release key signs: the version, a newline and the lowercase hex SHA-256 of the binary
+fn signed_message(version: &str, sha256: &str) -> Vec<u8> {
+    format!("{}\n{}", version, sha256.to_ascii_lowercase()).into_bytes()
+}
+
+fn write_executable(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    std::fs::write(path, bytes).map_err(|e| format!("Failed to stage binary: {}", e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to mark binary executable: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Minimal startup check run by a freshly installed binary (`eyecore_mvp --selftest`). It is a
+/// dry probe: nothing is collected and no camera, microphone or input hook is opened.
+pub async fn run_selftest() -> i32 {
+    let build = crate::version::BuildInfo::current();
+    info!("🧪 Selftest for {} {} ({})", build.name, build.version, build.git_hash);
+
+    let selftest_dir = std::env::temp_dir().join("eyecore_selftest");
+    let storage = crate::storage::DataStorage::new(&selftest_dir.to_string_lossy(), "selftest");
+    if let Err(e) = storage.initialize().await {
+        error!("Selftest failed: storage initialization: {}", e);
+        return 1;
+    }
+
+    if let Err(e) = crate::config::load() {
+        error!("Selftest failed: configuration: {}", e);
+        return 1;
+    }
+
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    if system.total_memory() == 0 {
+        error!("Selftest failed: system metrics unavailable");
+        return 1;
+    }
+
+    let _ = std::fs::remove_dir_all(&selftest_dir);
+    info!("✓ Selftest passed");
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const SHA256: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn manifest(version: &str, signed_version: &str) -> ReleaseManifest {
+        let signature = signing_key().sign(&signed_message(signed_version, SHA256));
+        ReleaseManifest {
+            version: version.to_string(),
+            download_url: Some("https://updates.example.edu/eyecore".to_string()),
+            notes: None,
+            sha256: Some(SHA256.to_string()),
+            signature: Some(general_purpose::STANDARD.encode(signature.to_bytes())),
+        }
+    }
+
+    #[test]
+    fn accepts_a_signed_newer_release() {
+        let manifest = manifest("99.0.0", "99.0.0");
+        assert_eq!(verify_manifest(&signing_key().verifying_key(), &manifest), Ok(SHA256));
+    }
+
+    #[test]
+    fn signature_covers_the_version() {
+        let manifest = manifest("99.0.0", "98.0.0");
+        let error = verify_manifest(&signing_key().verifying_key(), &manifest).unwrap_err();
+        assert_eq!(error, "Signature verification failed");
+    }
+
+    #[test]
+    fn older_and_same_versions_are_refused() {
+        for version in ["0.0.1", env!("CARGO_PKG_VERSION")] {
+            let manifest = manifest(version, version);
+            let error = verify_manifest(&signing_key().verifying_key(), &manifest).unwrap_err();
+            assert!(error.starts_with("Refusing to install"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn checksum_is_required() {
+        let mut manifest = manifest("99.0.0", "99.0.0");
+        manifest.sha256 = None;
+        assert!(verify_manifest(&signing_key().verifying_key(), &manifest).is_err());
+    }
+}
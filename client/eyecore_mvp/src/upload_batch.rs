@@ -0,0 +1,123 @@
+//! `PackageBatch` messages: with `upload_batch_size` above 1, the WebSocket client waits for that
+//! many queued snapshots and sends them in one message. The first snapshot of a batch is sent whole
+//! and each of the others as a JSON merge patch (RFC 7396) against the one before, so fields that
+//! did not change are left out; the list is zstd-compressed and base64-encoded into `payload`.
+
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Map, Value};
+
+use crate::storage::ZSTD_LEVEL;
+
+pub const ENCODING: &str = "json-merge-patch+zstd+base64";
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// The `data` of a `PackageBatch` message for `snapshots`, oldest first, and the payload's size
+/// before and after compression
+pub fn encode(snapshots: &[Value]) -> Result<(Value, usize, usize), String> {
+    let mut entries = Vec::with_capacity(snapshots.len());
+    let mut previous: Option<&Value> = None;
+    for snapshot in snapshots {
+        entries.push(match previous {
+            Some(previous) => merge_patch(previous, snapshot),
+            None => snapshot.clone(),
+        });
+        previous = Some(snapshot);
+    }
+    let plain = serde_json::to_vec(&entries).map_err(|e| e.to_string())?;
+    let compressed = zstd::encode_all(plain.as_slice(), ZSTD_LEVEL).map_err(|e| format!("Failed to compress batch: {}", e))?;
+    let data = json!({
+        "encoding": ENCODING,
+        "count": snapshots.len(),
+        "payload": general_purpose::STANDARD.encode(&compressed),
+    });
+    Ok((data, plain.len(), compressed.len()))
+}
+
+/// The merge patch turning `from` into `to`: changed fields, with null for removed ones
+fn merge_patch(from: &Value, to: &Value) -> Value {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            let mut patch = Map::new();
+            for (key, value) in to {
+                match from.get(key) {
+                    Some(old) if old == value => {}
+                    // A null cannot be patched in; leaving the field out reads the same
+                    None if value.is_null() => {}
+                    Some(old) if old.is_object() && value.is_object() => {
+                        patch.insert(key.clone(), merge_patch(old, value));
+                    }
+                    _ => {
+                        patch.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            for key in from.keys() {
+                if !to.contains_key(key) {
+                    patch.insert(key.clone(), Value::Null);
+                }
+            }
+            Value::Object(patch)
+        }
+        _ => to.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 7396 as the server applies it
+    fn apply(target: &Value, patch: &Value) -> Value {
+        let Value::Object(patch) = patch else { return patch.clone() };
+        let mut target = target.as_object().cloned().unwrap_or_default();
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                let patched = apply(target.get(key).unwrap_or(&Value::Null), value);
+                target.insert(key.clone(), patched);
+            }
+        }
+        Value::Object(target)
+    }
+
+    fn decode(data: &Value) -> Vec<Value> {
+        let compressed = general_purpose::STANDARD.decode(data["payload"].as_str().unwrap()).unwrap();
+        let entries: Vec<Value> = serde_json::from_slice(&zstd::decode_all(compressed.as_slice()).unwrap()).unwrap();
+        let mut snapshots: Vec<Value> = Vec::new();
+        for entry in entries {
+            let snapshot = match snapshots.last() {
+                Some(previous) => apply(previous, &entry),
+                None => entry,
+            };
+            snapshots.push(snapshot);
+        }
+        snapshots
+    }
+
+    #[test]
+    fn patches_hold_only_changed_fields() {
+        let from = json!({"cpu": 10.0, "process": {"name": "code", "count": 100}, "tags": [1, 2], "voice": null});
+        let to = json!({"cpu": 10.0, "process": {"name": "code", "count": 101}, "tags": [1, 2, 3], "voice": null});
+        assert_eq!(merge_patch(&from, &to), json!({"process": {"count": 101}, "tags": [1, 2, 3]}));
+        assert_eq!(merge_patch(&to, &to), json!({}));
+    }
+
+    #[test]
+    fn batches_decode_to_the_snapshots() {
+        let snapshots = vec![
+            json!({"cpu": 10.0, "process": {"name": "code"}, "camera": {"faces": 1}}),
+            json!({"cpu": 12.5, "process": {"name": "code"}, "camera": null}),
+            json!({"cpu": 12.5, "process": {"name": "slack"}, "camera": {"faces": 2}}),
+        ];
+        let (data, plain, compressed) = encode(&snapshots).unwrap();
+        assert_eq!(data["count"], 3);
+        assert!(plain > 0 && compressed > 0);
+
+        let decoded = decode(&data);
+        assert_eq!(decoded[0], snapshots[0]);
+        // A field that became null is removed, which reads the same
+        assert_eq!(decoded[1], json!({"cpu": 12.5, "process": {"name": "code"}}));
+        assert_eq!(decoded[2], snapshots[2]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Durable outbound queue of the WebSocket client. Every snapshot is spooled to
+//! `<device>/upload_queue/<sequence>.json` (redacted, compressed and sealed like snapshots) and only
+//! removed once it has been sent, so snapshots collected while the server is unreachable, or
+//! before a restart, are replayed in order after reconnecting. Beyond `upload_queue_max_mb` the
+//! oldest are dropped.
+
+use log::{error, info, warn};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+use crate::models::EyeCoreData;
+use crate::storage::{DataStorage, UPLOAD_QUEUE_DIR};
+
+struct Spool {
+    next: u64,
+    // Spooled files with their sizes, oldest first
+    files: VecDeque<(PathBuf, u64)>,
+    bytes: u64,
+    dropped: u64,
+}
+
+pub struct UploadQueue {
+    storage: Arc<DataStorage>,
+    max_bytes: u64,
+    spool: Mutex<Spool>,
+}
+
+impl UploadQueue {
+    /// Open the spool, picking up what a previous run left unsent
+    pub async fn open(storage: Arc<DataStorage>, max_bytes: u64) -> Self {
+        let dir = storage.device_dir().join(UPLOAD_QUEUE_DIR);
+        let mut found = Vec::new();
+        if let Ok(mut entries) = tokio::fs::read_dir(&dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                // Left by a crash while spooling
+                if path.extension().is_some_and(|extension| extension == "tmp") {
+                    let _ = tokio::fs::remove_file(&path).await;
+                    continue;
+                }
+                let Some(sequence) = sequence_of(&path) else { continue };
+                let bytes = entry.metadata().await.map_or(0, |metadata| metadata.len());
+                found.push((sequence, path, bytes));
+            }
+        }
+        found.sort_by_key(|(sequence, _, _)| *sequence);
+
+        let spool = Spool {
+            next: found.last().map_or(0, |(sequence, _, _)| sequence + 1),
+            bytes: found.iter().map(|(_, _, bytes)| bytes).sum(),
+            files: found.into_iter().map(|(_, path, bytes)| (path, bytes)).collect(),
+            dropped: 0,
+        };
+        if !spool.files.is_empty() {
+            info!("📦 {} snapshots from an earlier run are queued for upload", spool.files.len());
+        }
+        let queue = UploadQueue { storage, max_bytes, spool: Mutex::new(spool) };
+        queue.trim().await;
+        queue
+    }
+
+    /// Spool snapshots from the collection loop until it closes the channel. The channel is
+    /// bounded, so the collection loop waits while spooling falls behind.
+    pub async fn fill(self: Arc<Self>, mut snapshots: mpsc::Receiver<EyeCoreData>) {
+        while let Some(data) = snapshots.recv().await {
+            if let Err(e) = self.push(&data).await {
+                error!("Failed to queue snapshot for upload: {}", e);
+            }
+        }
+    }
+
+    /// Spool a snapshot behind the others
+    pub async fn push(&self, data: &EyeCoreData) -> std::io::Result<()> {
+        let sequence = {
+            let mut spool = self.spool.lock().unwrap();
+            spool.next += 1;
+            spool.next - 1
+        };
+        let (path, bytes) = self.storage.save_upload_package(&format!("{:020}", sequence), data).await?;
+        {
+            let mut spool = self.spool.lock().unwrap();
+            spool.files.push_back((path, bytes));
+            spool.bytes += bytes;
+        }
+        self.trim().await;
+        Ok(())
+    }
+
+    /// Up to `limit` of the oldest snapshots with their files. Unreadable ones are removed.
+    pub async fn oldest(&self, limit: usize) -> Vec<(PathBuf, EyeCoreData)> {
+        let paths: Vec<PathBuf> = {
+            let spool = self.spool.lock().unwrap();
+            spool.files.iter().take(limit).map(|(path, _)| path.clone()).collect()
+        };
+        let mut snapshots = Vec::with_capacity(paths.len());
+        for path in paths {
+            let read = self.storage.read_stored(&path).await.and_then(|bytes| Ok(serde_json::from_slice(&bytes)?));
+            match read {
+                Ok(data) => snapshots.push((path, data)),
+                // Dropped from a full queue meanwhile
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    error!("Discarding unreadable upload package {:?}: {}", path, e);
+                    self.remove(&[path]).await;
+                }
+            }
+        }
+        snapshots
+    }
+
+    /// Remove snapshots that were sent
+    pub async fn remove(&self, paths: &[PathBuf]) {
+        {
+            let mut spool = self.spool.lock().unwrap();
+            let mut removed = 0;
+            spool.files.retain(|(path, bytes)| {
+                let keep = !paths.contains(path);
+                if !keep {
+                    removed += bytes;
+                }
+                keep
+            });
+            spool.bytes -= removed;
+        }
+        for path in paths {
+            remove_file(path).await;
+        }
+    }
+
+    /// Snapshots queued, their size on disk and how many were dropped since startup
+    pub fn usage(&self) -> (usize, u64, u64) {
+        let spool = self.spool.lock().unwrap();
+        (spool.files.len(), spool.bytes, spool.dropped)
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Drop the oldest snapshots while the queue is over its limit
+    async fn trim(&self) {
+        let dropped: Vec<PathBuf> = {
+            let mut spool = self.spool.lock().unwrap();
+            let mut dropped = Vec::new();
+            while spool.bytes > self.max_bytes {
+                let Some((path, bytes)) = spool.files.pop_front() else { break };
+                spool.bytes -= bytes;
+                dropped.push(path);
+            }
+            if !dropped.is_empty() && spool.dropped == 0 {
+                warn!("⚠️ Upload queue over {} bytes, dropping the oldest snapshots", self.max_bytes);
+            }
+            spool.dropped += dropped.len() as u64;
+            dropped
+        };
+        for path in &dropped {
+            remove_file(path).await;
+        }
+    }
+}
+
+async fn remove_file(path: &Path) {
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => error!("Failed to remove upload package {:?}: {}", path, e),
+    }
+}
+
+/// Queue position from a spooled file name such as `00000000000000000042.json.zst.enc`
+fn sequence_of(path: &Path) -> Option<u64> {
+    let name = path.file_name()?.to_str()?;
+    name.split('.').next()?.parse().ok()
+}
@@ -13,3 +13,32 @@ pub fn anonymize_text(text: &str) -> String {
     let hash = hash_data(text.as_bytes());
     format!("anon_{}", &hash[..8])
 }
+
+/// Copy only the values at `pointers` into a new document with the same nesting
+pub fn project_json(value: &serde_json::Value, pointers: &[String]) -> serde_json::Value {
+    let mut projected = serde_json::json!({});
+    for pointer in pointers {
+        let Some(selected) = value.pointer(pointer) else {
+            continue;
+        };
+        let segments: Vec<String> = pointer
+            .split('/')
+            .skip(1)
+            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        insert_at(&mut projected, &segments, selected.clone());
+    }
+    projected
+}
+
+fn insert_at(target: &mut serde_json::Value, segments: &[String], selected: serde_json::Value) {
+    // Pointers into arrays or scalars are kept whole by their parent
+    let (Some((first, rest)), Some(object)) = (segments.split_first(), target.as_object_mut()) else {
+        return;
+    };
+    if rest.is_empty() {
+        object.insert(first.clone(), selected);
+    } else {
+        insert_at(object.entry(first.clone()).or_insert_with(|| serde_json::json!({})), rest, selected);
+    }
+}
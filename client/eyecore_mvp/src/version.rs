@@ -0,0 +1,152 @@
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+
+use crate::http::HttpClient;
+
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Build information embedded at compile time by build.rs
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_hash: &'static str,
+    pub build_date: &'static str,
+    pub features: Vec<&'static str>,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        BuildInfo {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_hash: env!("EYECORE_GIT_HASH"),
+            build_date: env!("EYECORE_BUILD_DATE"),
+            features: env!("EYECORE_FEATURES")
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// Release manifest published at the update URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseManifest {
+    pub version: String,
+    pub download_url: Option<String>,
+    pub notes: Option<String>,
+    pub sha256: Option<String>,
+    pub signature: Option<String>, // base64 ed25519 signature of "<version>\n<sha256>"
+}
+
+/// Result of the most recent update check
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateStatus {
+    pub current_version: String,
+    pub latest_version: Option<String>,
+    pub update_available: bool,
+    pub download_url: Option<String>,
+    pub release_notes: Option<String>,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    #[serde(skip)]
+    pub latest_manifest: Option<ReleaseManifest>,
+}
+
+impl UpdateStatus {
+    pub fn new() -> Self {
+        UpdateStatus {
+            current_version: env!("CARGO_PKG_VERSION").to_string(),
+            latest_version: None,
+            update_available: false,
+            download_url: None,
+            release_notes: None,
+            last_checked: None,
+            last_error: None,
+            latest_manifest: None,
+        }
+    }
+}
+
+/// Periodically polls a release manifest and records whether a newer version exists
+pub struct UpdateChecker {
+    update_url: String,
+    client: HttpClient,
+}
+
+impl UpdateChecker {
+    pub fn new(update_url: String, client: HttpClient) -> Self {
+        UpdateChecker {
+            update_url,
+            client,
+        }
+    }
+
+    /// Start checking for updates in a background task
+    pub fn start(self, status: Arc<RwLock<UpdateStatus>>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(UPDATE_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+
+                let result = self.fetch_manifest().await;
+                let mut status = status.write().await;
+                status.last_checked = Some(Utc::now());
+
+                match result {
+                    Ok(manifest) => {
+                        status.update_available = is_newer_version(&manifest.version, &status.current_version);
+                        if status.update_available {
+                            info!("⬆ Update available: {} -> {}", status.current_version, manifest.version);
+                        }
+                        status.latest_version = Some(manifest.version.clone());
+                        status.download_url = manifest.download_url.clone();
+                        status.release_notes = manifest.notes.clone();
+                        status.latest_manifest = Some(manifest);
+                        status.last_error = None;
+                    }
+                    Err(e) => {
+                        warn!("Update check failed: {}", e);
+                        status.last_error = Some(e);
+                    }
+                }
+            }
+        });
+    }
+
+    async fn fetch_manifest(&self) -> Result<ReleaseManifest, String> {
+        let response = self.client.send(self.client.get(&self.update_url)).await?;
+
+        if !response.status().is_success() {
+            return Err(format!("Update server returned {}", response.status()));
+        }
+
+        response
+            .json::<ReleaseManifest>()
+            .await
+            .map_err(|e| format!("JSON parse error: {}", e))
+    }
+}
+
+/// Compare dotted version strings numerically ("0.10.0" > "0.9.1")
+pub fn is_newer_version(candidate: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Vec<u64> {
+        let mut parts: Vec<u64> = version
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or("")
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+        parts.resize(parts.len().max(3), 0);
+        parts
+    }
+
+    parse(candidate) > parse(current)
+}
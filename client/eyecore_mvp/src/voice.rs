@@ -5,16 +5,18 @@ use tokio::sync::Mutex;
 use cpal::traits::{HostTrait, DeviceTrait, StreamTrait};
 use base64::{Engine as _, engine::general_purpose};
 
+use crate::http::HttpClient;
+
 pub struct VoiceCollector {
     elevenlabs_api_key: String,
-    client: reqwest::Client,
+    client: HttpClient,
 }
 
 impl VoiceCollector {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(api_key: String, client: HttpClient) -> Self {
         VoiceCollector {
             elevenlabs_api_key: api_key,
-            client: reqwest::Client::new(),
+            client,
         }
     }
 
@@ -58,7 +60,7 @@ impl VoiceCollector {
         
         // data subchunk
         wav_data.extend_from_slice(b"data");
-        wav_data.extend_from_slice(&(samples.len() * 2).to_le_bytes());
+        wav_data.extend_from_slice(&((samples.len() * 2) as u32).to_le_bytes());
         
         // PCM samples
         for sample in samples {
@@ -99,17 +101,15 @@ impl VoiceCollector {
     async fn transcribe_audio(&self, base64_audio: &str) -> Result<String, String> {
         let url = "https://api.elevenlabs.io/v1/speech-to-text";
 
-        let response = self
+        let request = self
             .client
             .post(url)
             .header("Authorization", format!("Bearer {}", self.elevenlabs_api_key))
             .json(&json!({
                 "audio": base64_audio,
                 "language": "en",
-            }))
-            .send()
-            .await
-            .map_err(|e| format!("Request error: {}", e))?;
+            }));
+        let response = self.client.send(request).await?;
 
         if !response.status().is_success() {
             error!("ElevenLabs API error: {}", response.status());
@@ -131,17 +131,15 @@ impl VoiceCollector {
     async fn detect_anomalies(&self, base64_audio: &str) -> Result<serde_json::Value, String> {
         let url = "https://api.elevenlabs.io/v1/audio-analysis";
 
-        let response = self
+        let request = self
             .client
             .post(url)
             .header("Authorization", format!("Bearer {}", self.elevenlabs_api_key))
             .json(&json!({
                 "audio": base64_audio,
                 "analysis_type": "anomaly_detection",
-            }))
-            .send()
-            .await
-            .map_err(|e| format!("Request error: {}", e))?;
+            }));
+        let response = self.client.send(request).await?;
 
         if !response.status().is_success() {
             return Ok(json!({
@@ -1,108 +1,380 @@
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::{
+    connect_async_tls_with_config,
+    tungstenite::protocol::{frame::coding::CloseCode, CloseFrame},
+    tungstenite::Message,
+    Connector,
+};
 use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use chrono::{DateTime, Utc};
 use log::{info, error, warn};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::env;
-use tokio::sync::RwLock;
-use tokio::time::{sleep, Duration};
+use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
 
-use crate::models::EyeCoreData;
+use crate::capabilities;
+use crate::models::{EyeCoreData, NegotiatedCapabilities, UploadConnection, UploadStatus};
+use crate::privacy::{self, UploadMode};
+use crate::remote_commands::{self, CommandRequest, CommandResult};
+use crate::upload_batch;
+use crate::schedule::ScheduleConfig;
+use crate::upload_queue::UploadQueue;
 
-const SERVER_URL: &str = "ws://localhost:8765";
-const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Reconnect delays double with each failed attempt up to the longest, each randomized to between
+/// half and all of it
+const RECONNECT_DELAY_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(300);
+/// How long to wait for the reply to an `Authenticate` message
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Re-authenticate this long before the token expires
+const TOKEN_REFRESH_MARGIN: chrono::Duration = chrono::Duration::seconds(60);
+/// How long to wait for the server's reply to our close frame
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Queued snapshots sent per upload tick, so a backlog is replayed without flooding the server
+const REPLAY_BATCH: usize = 100;
+
+/// `[upload_tls]` settings for a wss:// `server_url`; the system's root certificates are always trusted
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UploadTlsConfig {
+    pub ca_file: Option<String>,       // PEM certificates to trust as well, e.g. a private CA or a self-signed server
+    pub accept_invalid_certs: bool,    // skip certificate and host name checks; for testing only
+}
+
+impl UploadTlsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ca_file.as_deref() == Some("") {
+            return Err("upload_tls.ca_file must not be empty".to_string());
+        }
+        self.connector().map(|_| ())
+    }
+
+    /// The TLS connector for these settings; None for the defaults
+    fn connector(&self) -> Result<Option<Connector>, String> {
+        if self.ca_file.is_none() && !self.accept_invalid_certs {
+            return Ok(None);
+        }
+        let mut builder = native_tls::TlsConnector::builder();
+        if let Some(path) = &self.ca_file {
+            for certificate in read_certificates(path)? {
+                builder.add_root_certificate(certificate);
+            }
+        }
+        if self.accept_invalid_certs {
+            builder.danger_accept_invalid_certs(true).danger_accept_invalid_hostnames(true);
+        }
+        let connector = builder.build().map_err(|e| format!("Invalid upload_tls settings: {}", e))?;
+        Ok(Some(Connector::NativeTls(connector)))
+    }
+}
+
+/// What `/upload/status` reports besides the queue
+struct Progress {
+    connection: UploadConnection,
+    connected_since: Option<chrono::DateTime<Utc>>,
+    last_sent: Option<chrono::DateTime<Utc>>,
+    last_error: Option<String>,
+    sent: u64,
+    failed_attempts: u32,
+    next_attempt: Option<chrono::DateTime<Utc>>,
+    capabilities: Option<NegotiatedCapabilities>,
+}
+
+/// Token from the server's reply to `Authenticate`
+struct AccessToken {
+    token: String,
+    expires_at: Option<DateTime<Utc>>,    // None if the server sets no expiry
+}
+
+impl AccessToken {
+    /// Whether to re-authenticate: the token expires soon, or the server rejected it
+    fn due(&self) -> bool {
+        self.expires_at.is_some_and(|at| at - TOKEN_REFRESH_MARGIN <= Utc::now())
+    }
+}
 
 pub struct WebSocketClient {
     server_url: String,
     device_id: String,
-    access_token: Arc<RwLock<Option<String>>>,
+    tls: UploadTlsConfig,
+    access_token: Arc<RwLock<Option<AccessToken>>>,
+    upload_mode: UploadMode,
+    // JSON pointers the server wants in Package messages (None = everything)
+    projection: Arc<RwLock<Option<Vec<String>>>>,
+    // Snapshots from the collection loop not sent yet, on disk
+    queue: Arc<UploadQueue>,
+    // Snapshots per PackageBatch message; 1 sends each as a Package
+    batch_size: usize,
+    // Server commands go to the dispatcher (None = refused, `remote_commands = false`)
+    commands: Option<mpsc::Sender<CommandRequest>>,
+    progress: Mutex<Progress>,
 }
 
 impl WebSocketClient {
-    pub fn new(device_id: String) -> Self {
+    pub fn new(
+        server_url: String,
+        device_id: String,
+        tls: UploadTlsConfig,
+        upload_mode: UploadMode,
+        queue: Arc<UploadQueue>,
+        batch_size: usize,
+        commands: Option<mpsc::Sender<CommandRequest>>,
+    ) -> Self {
         Self {
-            server_url: SERVER_URL.to_string(),
+            server_url,
             device_id,
+            tls,
             access_token: Arc::new(RwLock::new(None)),
+            upload_mode,
+            projection: Arc::new(RwLock::new(None)),
+            queue,
+            batch_size,
+            commands,
+            progress: Mutex::new(Progress {
+                connection: UploadConnection::Disconnected,
+                connected_since: None,
+                last_sent: None,
+                last_error: None,
+                sent: 0,
+                failed_attempts: 0,
+                next_attempt: None,
+                capabilities: None,
+            }),
+        }
+    }
+
+    pub fn server_url(&self) -> &str {
+        &self.server_url
+    }
+
+    pub fn status(&self) -> UploadStatus {
+        let (queued_packages, queued_bytes, dropped) = self.queue.usage();
+        let progress = self.progress.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        UploadStatus {
+            server_url: self.server_url.clone(),
+            device_id: self.device_id.clone(),
+            mode: match self.upload_mode {
+                UploadMode::Raw => "raw",
+                UploadMode::Aggregate { .. } => "aggregate",
+                UploadMode::Team { .. } => "team",
+            }
+            .to_string(),
+            connection: progress.connection,
+            connected_since: progress.connected_since,
+            last_sent: progress.last_sent,
+            last_error: progress.last_error.clone(),
+            queued_packages,
+            queued_bytes,
+            max_queued_bytes: self.queue.max_bytes(),
+            packages_sent: progress.sent,
+            packages_dropped: dropped,
+            failed_attempts: progress.failed_attempts,
+            next_attempt: progress.next_attempt,
+            capabilities: progress.capabilities.clone(),
         }
     }
 
-    /// Start the WebSocket client in a background task
+    /// Start the WebSocket client in a background task, uploading the snapshots sent on
+    /// `snapshots`; it closes the connection and ends once `shutdown` is cancelled
     pub async fn start(
         self: Arc<Self>,
-        data_receiver: Arc<RwLock<Option<EyeCoreData>>>,
-    ) {
+        snapshots: mpsc::Receiver<EyeCoreData>,
+        schedules: ScheduleConfig,
+        shutdown: CancellationToken,
+    ) -> JoinHandle<()> {
         tokio::spawn(async move {
+            let feeder = tokio::spawn(Arc::clone(&self.queue).fill(snapshots));
             loop {
-                match self.connect_and_run(Arc::clone(&data_receiver)).await {
+                self.set_connection(UploadConnection::Connecting);
+                let result = self.connect_and_run(&schedules, &shutdown).await;
+                let error = result.as_ref().err().map(|e| e.to_string());
+                let delay = {
+                    let mut progress = self.progress.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                    // Back off while connecting fails, start over once a connection was up
+                    if progress.connected_since.is_some() {
+                        progress.failed_attempts = 0;
+                    } else {
+                        progress.failed_attempts = progress.failed_attempts.saturating_add(1);
+                    }
+                    let delay = reconnect_delay(progress.failed_attempts);
+                    progress.next_attempt = chrono::Duration::from_std(delay).ok().map(|delay| Utc::now() + delay);
+                    progress.connection = UploadConnection::Disconnected;
+                    progress.connected_since = None;
+                    progress.capabilities = None;
+                    if error.is_some() {
+                        progress.last_error = error;
+                    }
+                    delay
+                };
+                if shutdown.is_cancelled() {
+                    break;
+                }
+                match result {
                     Ok(_) => {
-                        info!("WebSocket connection closed normally");
+                        info!("WebSocket connection closed normally. Reconnecting in {:.1?}...", delay);
                     }
                     Err(e) => {
-                        error!("WebSocket error: {}. Reconnecting in {:?}...", e, RECONNECT_DELAY);
+                        error!("WebSocket error: {}. Reconnecting in {:.1?}...", e, delay);
                     }
                 }
-                sleep(RECONNECT_DELAY).await;
+                tokio::select! {
+                    _ = sleep(delay) => {}
+                    _ = shutdown.cancelled() => break,
+                }
             }
-        });
+            // Spool what is still in the channel before exiting
+            let _ = tokio::time::timeout(CLOSE_TIMEOUT, feeder).await;
+        })
+    }
+
+    fn set_connection(&self, connection: UploadConnection) {
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.connection = connection;
+            progress.connected_since = (connection == UploadConnection::Connected).then(Utc::now);
+            progress.next_attempt = None;
+        }
+    }
+
+    fn record_sent(&self, packages: u64) {
+        if packages == 0 {
+            return;
+        }
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.sent += packages;
+            progress.last_sent = Some(Utc::now());
+        }
     }
 
     async fn connect_and_run(
         &self,
-        data_receiver: Arc<RwLock<Option<EyeCoreData>>>,
+        schedules: &ScheduleConfig,
+        shutdown: &CancellationToken,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("🔌 Connecting to server at {}...", self.server_url);
         
-        let (ws_stream, _) = connect_async(&self.server_url).await?;
+        let connector = self.tls.connector()?;
+        let (ws_stream, _) = tokio::select! {
+            connected = connect_async_tls_with_config(&self.server_url, None, false, connector) => connected?,
+            _ = shutdown.cancelled() => return Ok(()),
+        };
         info!("✅ Connected to server!");
+        self.set_connection(UploadConnection::Connected);
 
         let (mut write, mut read) = ws_stream.split();
 
         // Send authentication request
-        let access_code = env::var("ACCESS_CODE").unwrap_or_else(|_| "W9RFCDJG36".to_string());
-
-        let auth_payload = json!({
-            "method": "Authenticate",
-            "data": {
-                "access_code": access_code
-            }
-        });
-
-        if let Err(e) = write.send(Message::Text(auth_payload.to_string())).await {
-            error!("Failed to send authentication payload: {}", e);
-            return Err(Box::new(e));
-        }
+        write.send(self.authenticate_message()).await?;
         info!("✅ Authentication request sent");
 
         // Wait for authentication response and extract token
-        if let Some(Ok(Message::Text(text))) = read.next().await {
-            if let Ok(response) = serde_json::from_str::<serde_json::Value>(&text) {
-                info!("📥 Server response: {}", response);
-                
-                if response.get("status") == Some(&json!("success")) {
-                    if let Some(token) = response.get("data").and_then(|d| d.get("token")).and_then(|t| t.as_str()) {
-                        let mut token_lock = self.access_token.write().await;
-                        *token_lock = Some(token.to_string());
-                        info!("✅ Token received and stored: {}", token);
-                    } else {
-                        error!("Authentication response missing token.");
-                    }
-                } else {
-                    error!("Authentication failed: {}", response);
+        let reply = tokio::time::timeout(AUTH_TIMEOUT, async {
+            while let Some(message) = read.next().await {
+                match message? {
+                    Message::Text(text) => return Ok(Some(text)),
+                    Message::Close(_) => break,
+                    _ => {}
                 }
             }
+            Ok::<_, tokio_tungstenite::tungstenite::Error>(None)
+        })
+        .await
+        .map_err(|_| "No reply to the authentication request")??
+        .ok_or("Connection closed before authentication")?;
+        let response: serde_json::Value = serde_json::from_str(&reply)?;
+        info!("📥 Server response: {}", response);
+        match auth_reply(&response) {
+            Some(Ok(token)) => {
+                log_token(&token);
+                *self.access_token.write().await = Some(token);
+            }
+            Some(Err(e)) => return Err(e.into()),
+            None => return Err(format!("Unexpected authentication reply: {}", response).into()),
+        }
+
+        // Send only what the server says it understands
+        let negotiated = capabilities::negotiate(response["data"].get("capabilities"))?;
+        match &negotiated {
+            Some(negotiated) => info!(
+                "🤝 Negotiated schema v{}, encodings {:?}, {} data categories",
+                negotiated.schema_version,
+                negotiated.encodings,
+                negotiated.data_categories.as_ref().map_or_else(|| "all".to_string(), |categories| categories.len().to_string()),
+            ),
+            None => info!("🤝 Server sent no capability manifest, sending plain packages"),
+        }
+        let batches = negotiated.as_ref().is_some_and(|negotiated| negotiated.encodings.iter().any(|encoding| encoding == upload_batch::ENCODING));
+        let batch_size = if batches { self.batch_size } else { 1 };
+        if batch_size < self.batch_size {
+            warn!("⚠️ The server cannot decode PackageBatch messages, sending snapshots one by one");
+        }
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.capabilities = negotiated.clone();
         }
 
-        // Clone the access_token Arc for the read task
         let access_token_clone = Arc::clone(&self.access_token);
+        let projection_clone = Arc::clone(&self.projection);
+        let commands = self.commands.clone();
+        // Results of the server's commands, written back on this connection
+        let (results_tx, mut results) = mpsc::channel::<CommandResult>(remote_commands::COMMAND_QUEUE);
 
-        // Handle incoming messages in background
-        let read_handle = tokio::spawn(async move {
+        // Handle incoming messages in background; an error ends the connection
+        let mut read_handle = tokio::spawn(async move {
             while let Some(msg) = read.next().await {
                 match msg {
                     Ok(Message::Text(text)) => {
                         if let Ok(response) = serde_json::from_str::<serde_json::Value>(&text) {
                             info!("📥 Server response: {}", response);
+                            
+                            // Server-side data minimization: {"method": "SetProjection", "data": {"fields": ["/timestamp", ...]}}
+                            if response.get("method") == Some(&json!("SetProjection")) {
+                                let fields = parse_projection(&response["data"]["fields"]);
+                                match &fields {
+                                    Some(fields) => info!("✂ Uplink projection set to {} fields", fields.len()),
+                                    None => info!("✂ Uplink projection cleared"),
+                                }
+                                *projection_clone.write().await = fields;
+                            }
+
+                            // Remote control: {"method": "Command", "data": {"id": "...", "command": "collect_snapshot"}}
+                            if response.get("method") == Some(&json!("Command")) {
+                                let (id, command) = remote_commands::parse(&response["data"]);
+                                let refused = match (command, &commands) {
+                                    (Err(e), _) => Some(e),
+                                    (Ok(_), None) => Some("Remote commands are disabled on this device".to_string()),
+                                    (Ok(command), Some(commands)) => {
+                                        let request = CommandRequest { id: id.clone(), command, results: results_tx.clone() };
+                                        match commands.try_send(request) {
+                                            Ok(()) => None,
+                                            Err(mpsc::error::TrySendError::Full(_)) => Some("Too many commands pending".to_string()),
+                                            Err(mpsc::error::TrySendError::Closed(_)) => Some("Remote commands are unavailable".to_string()),
+                                        }
+                                    }
+                                };
+                                if let Some(error) = refused {
+                                    warn!("⚠️ Refused server command {:?}: {}", id, error);
+                                    let _ = results_tx.send(CommandResult::new(id, Err(error))).await;
+                                }
+                            }
+
+                            // Reply to a re-authentication
+                            match auth_reply(&response) {
+                                Some(Ok(token)) => {
+                                    log_token(&token);
+                                    *access_token_clone.write().await = Some(token);
+                                }
+                                Some(Err(e)) => return Err(e),
+                                None => {}
+                            }
+                            // An expired token: re-authenticate on the next tick
+                            if token_rejected(&response) {
+                                if let Some(token) = access_token_clone.write().await.as_mut() {
+                                    token.expires_at = Some(Utc::now());
+                                }
+                            }
                         }
                     }
                     Ok(Message::Close(_)) => {
@@ -111,28 +383,171 @@ impl WebSocketClient {
                     }
                     Err(e) => {
                         error!("Error reading message: {}", e);
-                        break;
+                        return Err(e.to_string());
                     }
                     _ => {}
                 }
             }
+            Ok(())
         });
 
         // Send data periodically
-        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        let mut schedule = schedules.schedule("upload", Duration::from_secs(5));
+        let mut reauth_since: Option<Instant> = None;
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = schedule.tick() => {}
+                Some(result) = results.recv() => {
+                    write.send(Message::Text(result.to_message().to_string())).await?;
+                    continue;
+                }
+                _ = shutdown.cancelled() => {
+                    let close = CloseFrame { code: CloseCode::Normal, reason: "client shutting down".into() };
+                    match write.send(Message::Close(Some(close))).await {
+                        // Give the server a moment to answer with its own close frame
+                        Ok(_) => {
+                            let _ = tokio::time::timeout(CLOSE_TIMEOUT, &mut read_handle).await;
+                            info!("👋 Closed the server connection");
+                        }
+                        Err(e) => warn!("Failed to send close frame: {}", e),
+                    }
+                    return Ok(());
+                }
+            }
 
-            // Get the latest collected data
-            let data_guard = data_receiver.read().await;
-            if let Some(data) = data_guard.as_ref() {
-                // Convert data to JSON value so we can add token field
-                let mut data_json = serde_json::to_value(data).unwrap();
+            // Re-authenticate on the open connection before the token expires. Nothing is sent
+            // meanwhile, so no package carries a token the server no longer accepts.
+            let due = self.access_token.read().await.as_ref().is_some_and(AccessToken::due);
+            match (reauth_since, due) {
+                (None, true) => {
+                    info!("🔑 Access token expiring, re-authenticating");
+                    write.send(self.authenticate_message()).await?;
+                    reauth_since = Some(Instant::now());
+                }
+                (Some(since), true) if since.elapsed() > AUTH_TIMEOUT => {
+                    return Err("No reply to the re-authentication request".into());
+                }
+                (Some(_), false) => reauth_since = None,
+                _ => {}
+            }
+            if reauth_since.is_some() {
+                if read_handle.is_finished() {
+                    break;
+                }
+                continue;
+            }
+
+            // Aggregate and team modes: only statistics over a full window leave the device
+            if let Some(window_samples) = self.upload_mode.window() {
+                let window = self.queue.oldest(window_samples).await;
+                if window.len() >= window_samples {
+                    let (paths, snapshots): (Vec<_>, Vec<_>) = window.into_iter().unzip();
+                    let aggregate = match self.upload_mode {
+                        UploadMode::Aggregate { epsilon } => privacy::aggregate_with_noise(&snapshots, epsilon)
+                            .map(|aggregate| ("AggregatePackage", serde_json::to_value(&aggregate).unwrap())),
+                        UploadMode::Team { k } => privacy::aggregate_for_team(&snapshots, k)
+                            .map(|aggregate| ("TeamPackage", serde_json::to_value(&aggregate).unwrap())),
+                        UploadMode::Raw => None,
+                    };
+                    if let Some((method, mut data_json)) = aggregate {
+                        data_json["device_id"] = json!(self.device_id);
+                        if let Some(negotiated) = &negotiated {
+                            data_json["schema_version"] = json!(negotiated.schema_version);
+                        }
+                        if let Some(token) = self.access_token.read().await.as_ref() {
+                            data_json["token"] = json!(token.token);
+                        }
+
+                        let package = json!({
+                            "method": method,
+                            "data": data_json,
+                        });
+
+                        // The window stays queued for the next connection if this fails
+                        if let Err(e) = write.send(Message::Text(package.to_string())).await {
+                            error!("Failed to send data: {}", e);
+                            return Err(Box::new(e));
+                        }
+                        self.record_sent(snapshots.len() as u64);
+                        info!("📤 Sent {} over {} snapshots", method, snapshots.len());
+                    }
+                    self.queue.remove(&paths).await;
+                }
+
+                if read_handle.is_finished() {
+                    break;
+                }
+                continue;
+            }
+
+            // Batched uploads: once `upload_batch_size` snapshots are queued, they go in one
+            // delta-encoded, compressed PackageBatch message
+            if batch_size > 1 {
+                let queued = self.queue.oldest(REPLAY_BATCH / batch_size * batch_size).await;
+                let mut sent = Vec::new();
+                for batch in queued.chunks_exact(batch_size) {
+                    let mut snapshots = Vec::with_capacity(batch.len());
+                    for (_, data) in batch {
+                        let mut data_json = serde_json::to_value(data).unwrap();
+                        if let Some(fields) = self.projection.read().await.as_ref() {
+                            data_json = crate::utils::project_json(&data_json, fields);
+                        }
+                        if let Some(negotiated) = &negotiated {
+                            capabilities::restrict(&mut data_json, negotiated);
+                        }
+                        snapshots.push(data_json);
+                    }
+                    let (mut data_json, plain_bytes, compressed_bytes) = upload_batch::encode(&snapshots)?;
+                    data_json["device_id"] = json!(self.device_id);
+                    if let Some(negotiated) = &negotiated {
+                        data_json["schema_version"] = json!(negotiated.schema_version);
+                    }
+                    if let Some(token) = self.access_token.read().await.as_ref() {
+                        data_json["token"] = json!(token.token);
+                    }
+
+                    let package = json!({
+                        "method": "PackageBatch",
+                        "data": data_json,
+                    });
+
+                    if let Err(e) = write.send(Message::Text(package.to_string())).await {
+                        error!("Failed to send data: {}", e);
+                        // Unsent batches stay queued for the next connection
+                        self.record_sent(sent.len() as u64);
+                        self.queue.remove(&sent).await;
+                        return Err(Box::new(e));
+                    }
+                    sent.extend(batch.iter().map(|(path, _)| path.clone()));
+                    info!("📤 Sent a batch of {} snapshots ({} bytes, {} compressed)", batch.len(), plain_bytes, compressed_bytes);
+                }
+                self.record_sent(sent.len() as u64);
+                self.queue.remove(&sent).await;
+
+                if read_handle.is_finished() {
+                    break;
+                }
+                continue;
+            }
+
+            // Send queued snapshots oldest first, a batch per tick until a backlog is worked off
+            let mut sent = Vec::new();
+            for (path, data) in self.queue.oldest(REPLAY_BATCH).await {
+                // Convert data to JSON value so we can add device and token fields
+                let mut data_json = serde_json::to_value(&data).unwrap();
+                if let Some(fields) = self.projection.read().await.as_ref() {
+                    data_json = crate::utils::project_json(&data_json, fields);
+                }
+                if let Some(negotiated) = &negotiated {
+                    capabilities::restrict(&mut data_json, negotiated);
+                    data_json["schema_version"] = json!(negotiated.schema_version);
+                }
+                data_json["device_id"] = json!(self.device_id);
                 
                 // Add token to the data object if we have one
                 let token_guard = self.access_token.read().await;
-                if let Some(ref token) = *token_guard {
-                    data_json["token"] = json!(token);
+                if let Some(token) = token_guard.as_ref() {
+                    data_json["token"] = json!(token.token);
                 }
                 drop(token_guard); // Release lock
 
@@ -142,17 +557,19 @@ impl WebSocketClient {
                     "data": data_json,
                 });
 
-                match write.send(Message::Text(package.to_string())).await {
-                    Ok(_) => {
-                        info!("📤 Sent data package to server");
-                    }
-                    Err(e) => {
-                        error!("Failed to send data: {}", e);
-                        break;
-                    }
+                if let Err(e) = write.send(Message::Text(package.to_string())).await {
+                    error!("Failed to send data: {}", e);
+                    // Unsent snapshots stay queued for the next connection
+                    self.record_sent(sent.len() as u64);
+                    self.queue.remove(&sent).await;
+                    return Err(Box::new(e));
                 }
-            } else {
-                warn!("No data available to send yet");
+                sent.push(path);
+            }
+            self.record_sent(sent.len() as u64);
+            self.queue.remove(&sent).await;
+            if !sent.is_empty() {
+                info!("📤 Sent {} data package(s) to server", sent.len());
             }
 
             // Check if read task has finished (connection closed)
@@ -161,6 +578,89 @@ impl WebSocketClient {
             }
         }
 
+        read_handle.await??;
         Ok(())
     }
+
+    fn authenticate_message(&self) -> Message {
+        let access_code = env::var("ACCESS_CODE").unwrap_or_else(|_| "W9RFCDJG36".to_string());
+        let auth_payload = json!({
+            "method": "Authenticate",
+            "data": {
+                "access_code": access_code,
+                "device_id": self.device_id,
+                "capabilities": capabilities::manifest(),
+            }
+        });
+        Message::Text(auth_payload.to_string())
+    }
+}
+
+/// The token from a successful `Authenticate` reply, which may set `expires_in` (seconds) or
+/// `expires_at` (RFC 3339), or the error of a failed one; None for other messages
+fn auth_reply(response: &serde_json::Value) -> Option<Result<AccessToken, String>> {
+    if response.get("status") == Some(&json!("success")) {
+        let data = &response["data"];
+        let token = data.get("token")?.as_str()?.to_string();
+        let expires_at = match (data.get("expires_in").and_then(|v| v.as_i64()), data.get("expires_at").and_then(|v| v.as_str())) {
+            (Some(secs), _) => Some(Utc::now() + chrono::Duration::seconds(secs)),
+            (None, Some(at)) => DateTime::parse_from_rfc3339(at).ok().map(|at| at.with_timezone(&Utc)),
+            (None, None) => None,
+        };
+        return Some(Ok(AccessToken { token, expires_at }));
+    }
+    let message = response.get("message").and_then(|m| m.as_str())?;
+    message
+        .to_lowercase()
+        .contains("authentication")
+        .then(|| Err(format!("Authentication failed: {}", message)))
+}
+
+/// Whether the server refused a package's token (`{"status": "error", "message": "Invalid token"}`)
+fn token_rejected(response: &serde_json::Value) -> bool {
+    response.get("status") == Some(&json!("error"))
+        && response.get("message").and_then(|m| m.as_str()).is_some_and(|m| m.to_lowercase().contains("token"))
+}
+
+fn log_token(token: &AccessToken) {
+    match token.expires_at {
+        Some(at) => info!("✅ Token received, valid until {}", at.to_rfc3339()),
+        None => info!("✅ Token received"),
+    }
+}
+
+/// Delay before the next connection attempt after `failures` failed ones in a row (0 after a
+/// connection that was up)
+fn reconnect_delay(failures: u32) -> Duration {
+    let ceiling = RECONNECT_DELAY_MIN.saturating_mul(1 << failures.min(16)).min(RECONNECT_DELAY_MAX);
+    // Jitter keeps clients that lost the server together from reconnecting in step
+    ceiling.mul_f64(0.5 + rand::random::<f64>() / 2.0)
+}
+
+/// Every certificate in a PEM file
+fn read_certificates(path: &str) -> Result<Vec<native_tls::Certificate>, String> {
+    let pem = std::fs::read_to_string(path).map_err(|e| format!("Failed to read upload_tls.ca_file {:?}: {}", path, e))?;
+    const END: &str = "-----END CERTIFICATE-----";
+    let certificates = pem
+        .split_inclusive(END)
+        .filter(|block| block.contains(END))
+        .map(|block| native_tls::Certificate::from_pem(block.as_bytes()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid certificate in upload_tls.ca_file {:?}: {}", path, e))?;
+    if certificates.is_empty() {
+        return Err(format!("No certificates in upload_tls.ca_file {:?}", path));
+    }
+    Ok(certificates)
+}
+
+/// Valid JSON pointers from a projection request; an empty or missing list means "everything"
+fn parse_projection(fields: &serde_json::Value) -> Option<Vec<String>> {
+    let fields: Vec<String> = fields
+        .as_array()?
+        .iter()
+        .filter_map(|f| f.as_str())
+        .filter(|f| f.starts_with('/'))
+        .map(|f| f.to_string())
+        .collect();
+    (!fields.is_empty()).then_some(fields)
 }
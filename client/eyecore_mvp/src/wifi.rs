@@ -0,0 +1,221 @@
+//! Wi-Fi network identity for `network_activity_metadata.wifi`: the connected SSID and BSSID, stored
+//! only as salted hashes, and a location category the user can assign to the network.
+
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::models::{EyeCoreData, NetworkCategory, WifiNetwork};
+use crate::utils::hash_data;
+
+/// Reading the connection spawns netsh/nmcli, and networks rarely change, so it is cached this long
+const WIFI_REFRESH: Duration = Duration::from_secs(30);
+/// Snapshots further apart than this do not add time to a location
+const MAX_SAMPLE_GAP_SECS: i64 = 60;
+
+/// The connected network as read from the OS; the names never leave this module
+struct WifiConnection {
+    ssid: String,
+    bssid: Option<String>,
+    open: bool,                   // no authentication, as on most public hotspots
+}
+
+pub struct WifiMonitor {
+    salt: String,
+    labels_path: Option<PathBuf>,
+    labels: HashMap<String, NetworkCategory>, // SSID hash -> category chosen by the user
+    connection: Option<WifiConnection>,
+    last_read: Option<Instant>,
+}
+
+impl WifiMonitor {
+    /// Unlabeled monitor with an unsalted hash, for one-off collection
+    pub fn new() -> Self {
+        WifiMonitor {
+            salt: String::new(),
+            labels_path: None,
+            labels: HashMap::new(),
+            connection: None,
+            last_read: None,
+        }
+    }
+
+    /// Hash with this device's salt and keep labels in `labels_path`, so hashes cannot be matched
+    /// across devices by dictionary and labels survive restarts
+    pub fn with_labels(salt: &str, labels_path: PathBuf) -> Self {
+        let labels = match std::fs::read_to_string(&labels_path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("⚠️ Ignoring unreadable network labels {:?}: {}", labels_path, e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        WifiMonitor {
+            salt: salt.to_string(),
+            labels_path: Some(labels_path),
+            labels,
+            connection: None,
+            last_read: None,
+        }
+    }
+
+    /// The connected Wi-Fi network; None on wired connections or where it cannot be read
+    pub fn observe(&mut self) -> Option<WifiNetwork> {
+        if self.last_read.is_none_or(|last| last.elapsed() >= WIFI_REFRESH) {
+            self.connection = read_connection();
+            self.last_read = Some(Instant::now());
+        }
+        self.current()
+    }
+
+    /// Assign `category` to the connected network; `Unknown` removes the label
+    pub fn label_current(&mut self, category: NetworkCategory) -> Result<WifiNetwork, String> {
+        self.connection = read_connection();
+        self.last_read = Some(Instant::now());
+        let ssid_hash = match &self.connection {
+            Some(connection) => self.hash(&connection.ssid),
+            None => return Err("Not connected to a Wi-Fi network".to_string()),
+        };
+
+        if category == NetworkCategory::Unknown {
+            self.labels.remove(&ssid_hash);
+        } else {
+            self.labels.insert(ssid_hash, category);
+        }
+        if let Some(path) = &self.labels_path {
+            let sorted: BTreeMap<_, _> = self.labels.iter().collect();
+            let content = serde_json::to_string_pretty(&sorted).map_err(|e| e.to_string())?;
+            std::fs::write(path, content).map_err(|e| format!("Failed to save network labels {:?}: {}", path, e))?;
+        }
+        info!("📶 Current Wi-Fi network labeled {:?}", category);
+        self.current().ok_or_else(|| "Not connected to a Wi-Fi network".to_string())
+    }
+
+    fn current(&self) -> Option<WifiNetwork> {
+        let connection = self.connection.as_ref()?;
+        let ssid_hash = self.hash(&connection.ssid);
+        let label = self.labels.get(&ssid_hash).copied();
+        Some(WifiNetwork {
+            category: label.unwrap_or(if connection.open { NetworkCategory::Public } else { NetworkCategory::Unknown }),
+            labeled: label.is_some(),
+            bssid_hash: connection.bssid.as_deref().map(|bssid| self.hash(&bssid.to_lowercase())),
+            ssid_hash,
+        })
+    }
+
+    fn hash(&self, value: &str) -> String {
+        hash_data(format!("{}:{}", self.salt, value).as_bytes())[..16].to_string()
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_connection() -> Option<WifiConnection> {
+    let output = std::process::Command::new("netsh").args(["wlan", "show", "interfaces"]).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // "    SSID                   : eduroam"; the BSSID value contains colons itself
+    let field = |name: &str| {
+        text.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == name).then(|| value.trim().to_string())
+        })
+    };
+    if field("State").as_deref() != Some("connected") {
+        return None;
+    }
+    Some(WifiConnection {
+        ssid: field("SSID").filter(|ssid| !ssid.is_empty())?,
+        bssid: field("BSSID"),
+        open: field("Authentication").as_deref() == Some("Open"),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_connection() -> Option<WifiConnection> {
+    let output = std::process::Command::new("nmcli")
+        .args(["-t", "-f", "ACTIVE,SSID,BSSID,SECURITY", "dev", "wifi"])
+        .output()
+        .ok()?;
+    // Terse output escapes colons inside values: "yes:eduroam:AA\:BB\:CC\:DD\:EE\:FF:WPA2 802.1X"
+    String::from_utf8_lossy(&output.stdout).lines().find_map(|line| {
+        let fields = split_terse(line);
+        if fields.len() < 4 || fields[0] != "yes" || fields[1].is_empty() {
+            return None;
+        }
+        Some(WifiConnection {
+            ssid: fields[1].clone(),
+            bssid: Some(fields[2].clone()).filter(|bssid| !bssid.is_empty()),
+            open: fields[3].is_empty() || fields[3] == "--",
+        })
+    })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn read_connection() -> Option<WifiConnection> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Time and averages for one location category in a day's snapshots
+#[derive(Debug, Clone, Serialize)]
+pub struct LocationSummary {
+    pub category: String,          // a NetworkCategory, or "not_on_wifi"
+    pub snapshots: usize,
+    pub minutes: i64,
+    pub avg_focus_level: f32,
+    pub avg_cpu_usage: f32,
+    pub context_switches: u32,
+}
+
+/// Split a day's snapshots by the Wi-Fi location they were taken at, most time first. Each snapshot
+/// counts the time until the next one, unless they are more than a minute apart.
+pub fn location_breakdown(snapshots: &[EyeCoreData]) -> Vec<LocationSummary> {
+    let mut totals: BTreeMap<String, (usize, i64, f32, f32, u32)> = BTreeMap::new();
+    for (i, data) in snapshots.iter().enumerate() {
+        let category = data
+            .network_activity_metadata
+            .as_ref()
+            .and_then(|network| network.wifi.as_ref())
+            .map_or("not_on_wifi".to_string(), |wifi| wifi.category.as_str().to_string());
+        let seconds = snapshots
+            .get(i + 1)
+            .map(|next| (next.timestamp - data.timestamp).num_seconds())
+            .filter(|gap| (0..=MAX_SAMPLE_GAP_SECS).contains(gap))
+            .unwrap_or(0);
+
+        let entry = totals.entry(category).or_default();
+        entry.0 += 1;
+        entry.1 += seconds;
+        entry.2 += data.focus_metrics.focus_level;
+        entry.3 += data.system_metrics.cpu_usage;
+        entry.4 += data.focus_metrics.context_switches;
+    }
+
+    let mut summaries: Vec<LocationSummary> = totals
+        .into_iter()
+        .map(|(category, (snapshots, seconds, focus, cpu, switches))| LocationSummary {
+            category,
+            snapshots,
+            minutes: seconds / 60,
+            avg_focus_level: focus / snapshots as f32,
+            avg_cpu_usage: cpu / snapshots as f32,
+            context_switches: switches,
+        })
+        .collect();
+    summaries.sort_by(|a, b| b.minutes.cmp(&a.minutes).then(b.snapshots.cmp(&a.snapshots)));
+    summaries
+}
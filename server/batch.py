@@ -0,0 +1,43 @@
+"""
+Decoding of `PackageBatch` messages from devices with `upload_batch_size` above 1.
+
+`payload` is a base64-encoded, zstd-compressed JSON list: the first snapshot whole, then each of
+the others as a JSON merge patch (RFC 7396) against the one before.
+"""
+from __future__ import annotations
+
+import base64
+import json
+from typing import Any, Dict, List
+
+import zstandard
+
+ENCODING = "json-merge-patch+zstd+base64"
+
+
+def merge_patch(target: Any, patch: Any) -> Any:
+    """Apply a JSON merge patch; null removes a field."""
+    if not isinstance(patch, dict):
+        return patch
+    result = dict(target) if isinstance(target, dict) else {}
+    for key, value in patch.items():
+        if value is None:
+            result.pop(key, None)
+        else:
+            result[key] = merge_patch(result.get(key), value)
+    return result
+
+
+def decode_batch(data: Dict[str, Any]) -> List[Dict[str, Any]]:
+    """The snapshots of a batch, oldest first, each with the batch's device_id and token."""
+    if data.get("encoding") != ENCODING:
+        raise ValueError(f"Unknown batch encoding: {data.get('encoding')}")
+    compressed = base64.b64decode(data["payload"])
+    entries = json.loads(zstandard.ZstdDecompressor().decompressobj().decompress(compressed))
+    snapshots: List[Dict[str, Any]] = []
+    for entry in entries:
+        snapshots.append(merge_patch(snapshots[-1], entry) if snapshots else entry)
+    for snapshot in snapshots:
+        snapshot["device_id"] = data.get("device_id")
+        snapshot["token"] = data.get("token")
+    return snapshots
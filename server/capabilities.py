@@ -0,0 +1,33 @@
+"""
+Capability manifests exchanged in the `Authenticate` handshake.
+
+The server answers a client's manifest with its own. Each side then uses what both support, so
+clients and servers of different versions keep working together. A client without a manifest
+sends plain `Package` messages.
+"""
+from __future__ import annotations
+
+from typing import Any, Dict, Optional
+
+from batch import ENCODING as BATCH_ENCODING
+
+SERVER_VERSION = "1.1.0"
+
+# No `data_categories`: every snapshot section is taken
+SERVER_CAPABILITIES: Dict[str, Any] = {
+    "server_version": SERVER_VERSION,
+    "schema_versions": [1],
+    "encodings": ["json", BATCH_ENCODING],
+}
+
+
+def negotiate(client: Optional[Dict[str, Any]]) -> Optional[Dict[str, Any]]:
+    """What a client with the manifest `client` will send; None for a client without one."""
+    if not isinstance(client, dict):
+        return None
+    common = set(client.get("schema_versions", [])) & set(SERVER_CAPABILITIES["schema_versions"])
+    return {
+        "client_version": client.get("client_version"),
+        "schema_version": max(common) if common else None,
+        "encodings": [e for e in SERVER_CAPABILITIES["encodings"] if e in client.get("encodings", ["json"])],
+    }
@@ -5,6 +5,12 @@ from websockets.asyncio.server import serve
 from db_init import db_connect
 from device import Devices
 from analyze import analyze
+from batch import decode_batch
+from capabilities import SERVER_CAPABILITIES, negotiate
+from team import TeamAggregates
+
+# Team-mode statistics from every connection
+team_aggregates = TeamAggregates()
     
 
 async def handler(ws):
@@ -63,7 +69,8 @@ async def handler(ws):
                         print("Authenticating with access code:", access_code)
                         token = device_manager.authenticate(ws, access_code)
                         if token:
-                            response = {"status": "success", "data": {"token": token}}
+                            device_manager.devices[token]["capabilities"] = negotiate(data.get("capabilities"))
+                            response = {"status": "success", "data": {"token": token, "capabilities": SERVER_CAPABILITIES}}
                         else:
                             response = {"status": "error", "message": "Authentication failed"}
 
@@ -78,6 +85,33 @@ async def handler(ws):
                         else:
                             response = {"status": "error", "message": "Invalid token"}
 
+                    case "PackageBatch":
+                        token = data.get("token")
+                        if token in device_manager.devices:
+                            snapshots = decode_batch(data)
+                            for snapshot in snapshots:
+                                analyzed = await analyze(snapshot)
+                                device_manager.create_report_from_analysis(token, analyzed, snapshot)
+                            response = {"status": "success", "data": {"count": len(snapshots)}}
+                        else:
+                            response = {"status": "error", "message": "Invalid token"}
+
+                    case "TeamPackage":
+                        token = data.get("token")
+                        if token in device_manager.devices:
+                            device_id = device_manager.devices[token]["device_id"]
+                            error = team_aggregates.add(device_id, data)
+                            if error:
+                                response = {"status": "error", "message": error}
+                            else:
+                                response = {"status": "success"}
+                        else:
+                            response = {"status": "error", "message": "Invalid token"}
+
+                    case "GetTeamAggregates":
+                        since_s = data.get("since_s") or 24 * 3600
+                        response = {"status": "success", "data": team_aggregates.report(since_s)}
+
                     case _:
                         response = {"status": "error", "message": "Unknown method"}
 
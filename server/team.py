@@ -0,0 +1,71 @@
+"""
+Fleet-wide statistics from devices uploading in team mode (`TeamPackage`).
+
+Each package covers a window of at least k snapshots of one device, with the apps and domains
+it used. Reports only cover windows from at least k devices, and only list an app or domain once
+k different devices reported it. k is the largest one the devices asked for in their
+`privacy` hints.
+"""
+from __future__ import annotations
+
+import time
+from typing import Any, Dict, List, Optional, Tuple
+
+# Packages older than this are forgotten
+MAX_AGE_S = 7 * 24 * 3600
+DIMENSIONS = {"app": "apps", "domain": "domains"}
+METRICS = ("avg_cpu_usage", "avg_memory_usage", "avg_focus_level", "avg_idle_seconds")
+TOTALS = ("total_mouse_clicks", "total_keyboard_events")
+
+
+class TeamAggregates:
+    def __init__(self):
+        self.packages: List[Tuple[float, str, Dict[str, Any]]] = []  # (received, device_id, package)
+
+    def add(self, device_id: str, package: Dict[str, Any]) -> Optional[str]:
+        """Keep a package; returns why it was refused, if it was."""
+        privacy = package.get("privacy") or {}
+        k = privacy.get("k")
+        if privacy.get("mechanism") != "k-anonymity" or not isinstance(k, int) or k < 2:
+            return "TeamPackage needs k-anonymity privacy hints"
+        if (package.get("sample_count") or 0) < k:
+            return "TeamPackage covers fewer than k snapshots"
+        now = time.time()
+        self.packages = [p for p in self.packages if now - p[0] < MAX_AGE_S]
+        self.packages.append((now, device_id, package))
+        return None
+
+    def report(self, since_s: float = 24 * 3600) -> Dict[str, Any]:
+        """Statistics over the packages of the last `since_s` seconds, suppressed below k devices."""
+        now = time.time()
+        recent = [(device_id, p) for received, device_id, p in self.packages if now - received < since_s]
+        devices = {device_id for device_id, _ in recent}
+        k = max((p["privacy"]["k"] for _, p in recent), default=2)
+        if len(devices) < k:
+            return {"k": k, "suppressed": True, "reason": f"fewer than {k} devices reported"}
+
+        samples = sum(p["sample_count"] for _, p in recent)
+        report: Dict[str, Any] = {"k": k, "suppressed": False, "devices": len(devices), "samples": samples}
+        for metric in METRICS:
+            report[metric] = sum(p.get(metric, 0) * p["sample_count"] for _, p in recent) / samples
+        for total in TOTALS:
+            report[total] = sum(p.get(total, 0) for _, p in recent)
+
+        for dimension, field in DIMENSIONS.items():
+            counts: Dict[str, Dict[str, Any]] = {}
+            for device_id, p in recent:
+                # Only dimensions the device marked for suppression are reported at all
+                if dimension not in p["privacy"].get("dimensions", []):
+                    continue
+                for entry in p.get(field, []):
+                    count = counts.setdefault(entry["value"], {"samples": 0, "devices": set()})
+                    count["samples"] += entry["samples"]
+                    count["devices"].add(device_id)
+            kept = [
+                {"value": value, "samples": c["samples"], "devices": len(c["devices"])}
+                for value, c in counts.items()
+                if len(c["devices"]) >= k
+            ]
+            report[field] = sorted(kept, key=lambda entry: entry["samples"], reverse=True)
+            report[f"suppressed_{field}"] = len(counts) - len(kept)
+        return report